@@ -0,0 +1,153 @@
+#!/usr/bin/env python3
+"""Compares the speed of `shopsite-aa2json` with the reference Perl and Python converters in `bench/reference`, on generated `.aa` files of several sizes.
+
+Each converter is run on identical input, and its output is checked against `shopsite-aa2json`'s before any timings are recorded, so that a converter can't win by doing less. The results are written as both Markdown (for reading) and JSON (for tracking over time).
+
+Usage: bench/compare.py [--sizes 1,10] [--runs 5] [--out-dir target/bench]
+
+Sizes are in megabytes. The corpus is generated from a fixed seed, so it's the same every time. Requires Python 3.7+, Perl 5 (with the core `Encode` and `JSON::PP` modules), and Cargo.
+"""
+
+import argparse
+import json
+import os
+import platform
+import random
+import statistics
+import subprocess
+import sys
+import time
+
+ROOT = os.path.dirname(os.path.dirname(os.path.abspath(__file__)))
+REFERENCE = os.path.join(ROOT, "bench", "reference")
+
+# Windows-1252 bytes that are defined in every decoder involved. (A handful of bytes in 0x80–0x9F are undefined, and each decoder treats those differently.)
+TEXT_BYTES = bytes(range(0x20, 0x7F)).replace(b"|", b"") + bytes(range(0xA0, 0x100))
+
+
+def generate_corpus(path, size_mb, seed=1):
+	"""Writes a `.aa` file of about `size_mb` megabytes, shaped roughly like ShopSite's product database dumps: comments, lots of keys with short values, some sequences, some long text, and some keys with no value."""
+	rng = random.Random(seed)
+	target = size_mb * 1024 * 1024
+	written = 0
+
+	def text(min_len, max_len):
+		return bytes(rng.choice(TEXT_BYTES) for _ in range(rng.randint(min_len, max_len))).strip()
+
+	with open(path, "wb") as f:
+		f.write(b"# Generated by bench/compare.py\r\n")
+		index = 0
+
+		while written < target:
+			lines = [
+				b"Product%dName: %s" % (index, text(5, 40)),
+				b"Product%dSKU: SKU-%06d" % (index, index),
+				b"Product%dPrice: %d.%02d" % (index, rng.randint(0, 9999), rng.randint(0, 99)),
+				b"Product%dOptions: %s" % (index, b"|".join(text(1, 12) for _ in range(rng.randint(0, 6)))),
+				b"Product%dDescription: %s" % (index, text(0, 400)),
+				b"Product%dTaxable" % index,
+			]
+
+			if index % 50 == 0:
+				lines.insert(0, b"# Product %d" % index)
+
+			chunk = b"\r\n".join(lines) + b"\r\n"
+			f.write(chunk)
+			written += len(chunk)
+			index += 1
+
+
+def converters(aa2json):
+	"""The converters to compare, as (name, command prefix) pairs. The input file name is appended to each command."""
+	return [
+		("shopsite-aa2json", [aa2json]),
+		("Perl reference", ["perl", os.path.join(REFERENCE, "aa2json.pl")]),
+		("Python reference", [sys.executable, os.path.join(REFERENCE, "aa2json.py")]),
+	]
+
+
+def run(command, corpus):
+	"""Runs a converter once. Returns the elapsed wall-clock time and the parsed output."""
+	start = time.perf_counter()
+	result = subprocess.run(command + [corpus], stdout=subprocess.PIPE, check=True)
+	elapsed = time.perf_counter() - start
+	return elapsed, json.loads(result.stdout.decode("utf-8"))
+
+
+def main():
+	parser = argparse.ArgumentParser(description=__doc__.splitlines()[0])
+	parser.add_argument("--sizes", default="1,10", help="comma-separated corpus sizes, in megabytes")
+	parser.add_argument("--runs", type=int, default=5, help="how many times to run each converter on each corpus")
+	parser.add_argument("--out-dir", default=os.path.join(ROOT, "target", "bench"), help="where to put the corpus and the reports")
+	args = parser.parse_args()
+
+	sizes = [int(size) for size in args.sizes.split(",")]
+	os.makedirs(args.out_dir, exist_ok=True)
+
+	subprocess.run(["cargo", "build", "--release", "-p", "shopsite-aa2json"], cwd=ROOT, check=True)
+	aa2json = os.path.join(ROOT, "target", "release", "shopsite-aa2json" + (".exe" if os.name == "nt" else ""))
+
+	results = []
+
+	for size in sizes:
+		corpus = os.path.join(args.out_dir, "corpus-%dmb.aa" % size)
+		generate_corpus(corpus, size)
+		corpus_bytes = os.path.getsize(corpus)
+		expected = None
+
+		for name, command in converters(aa2json):
+			times = []
+
+			for _ in range(args.runs):
+				elapsed, output = run(command, corpus)
+				times.append(elapsed)
+
+				if expected is None:
+					expected = output
+				elif output != expected:
+					sys.exit("%s disagrees with shopsite-aa2json on %s" % (name, corpus))
+
+			median = statistics.median(times)
+			results.append({
+				"converter": name,
+				"corpus_mb": size,
+				"corpus_bytes": corpus_bytes,
+				"runs": args.runs,
+				"min_s": min(times),
+				"median_s": median,
+				"mean_s": statistics.mean(times),
+				"mb_per_s": corpus_bytes / (1024 * 1024) / median,
+			})
+			print("%-18s %5d MB  median %8.3f s" % (name, size, median), file=sys.stderr)
+
+	report = {
+		"platform": platform.platform(),
+		"python": platform.python_version(),
+		"perl": subprocess.run(["perl", "-e", "print $^V"], stdout=subprocess.PIPE, check=True).stdout.decode(),
+		"results": results,
+	}
+
+	with open(os.path.join(args.out_dir, "report.json"), "w") as f:
+		json.dump(report, f, indent="\t")
+
+	with open(os.path.join(args.out_dir, "report.md"), "w") as f:
+		f.write("# `.aa` to JSON converter comparison\n\n")
+		f.write("Platform: %s, Python %s, Perl %s. Median of %d runs.\n\n" % (report["platform"], report["python"], report["perl"], args.runs))
+		f.write("| Converter | Corpus | Median | MB/s | Relative to shopsite-aa2json |\n")
+		f.write("|---|---:|---:|---:|---:|\n")
+
+		for result in results:
+			baseline = next(r for r in results if r["corpus_mb"] == result["corpus_mb"] and r["converter"] == "shopsite-aa2json")
+			f.write("| %s | %d MB | %.3f s | %.1f | %.1f× |\n" % (
+				result["converter"],
+				result["corpus_mb"],
+				result["median_s"],
+				result["mb_per_s"],
+				result["median_s"] / baseline["median_s"],
+			))
+
+	print("Wrote %s and %s" % (os.path.join(args.out_dir, "report.md"), os.path.join(args.out_dir, "report.json")), file=sys.stderr)
+
+
+if __name__ == "__main__":
+	main()
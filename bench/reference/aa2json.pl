@@ -0,0 +1,29 @@
+#!/usr/bin/perl
+# Reference `.aa` to JSON converter, in the style of the Perl scripts commonly used to pull data out of ShopSite. Used by `bench/compare.py` as a baseline for `shopsite-aa2json`. Only core modules are used, so it runs on any stock Perl.
+#
+# Usage: aa2json.pl FILE > OUTPUT.json
+
+use strict;
+use warnings;
+use Encode qw(decode);
+use JSON::PP;
+
+my %data;
+
+while (my $line = <>) {
+	$line =~ s/\r?\n\z//;
+
+	# Skip blank lines and comments.
+	next if $line =~ /\A\s*(?:#|\z)/;
+
+	if ($line =~ /\A([^:]*):(.*)\z/s) {
+		my ($key, $value) = ($1, $2);
+		$value =~ s/\A //;
+		$data{decode('cp1252', $key)} = decode('cp1252', $value);
+	}
+	else {
+		$data{decode('cp1252', $line)} = undef;
+	}
+}
+
+print JSON::PP->new->utf8->canonical->encode(\%data);
@@ -0,0 +1,34 @@
+#!/usr/bin/env python3
+"""Reference `.aa` to JSON converter, as a typical Python script would do it. Used by `bench/compare.py` as a baseline for `shopsite-aa2json`. Only the standard library is used.
+
+Usage: aa2json.py FILE > OUTPUT.json
+"""
+
+import json
+import sys
+
+
+def main():
+	data = {}
+	sys.stdout.reconfigure(encoding="utf-8")
+
+	with open(sys.argv[1], encoding="cp1252", errors="replace", newline="") as f:
+		for line in f:
+			line = line.rstrip("\r\n")
+
+			# Skip blank lines and comments.
+			stripped = line.lstrip()
+			if not stripped or stripped.startswith("#"):
+				continue
+
+			key, sep, value = line.partition(":")
+			if sep:
+				data[key] = value[1:] if value.startswith(" ") else value
+			else:
+				data[key] = None
+
+	json.dump(data, sys.stdout, ensure_ascii=False, separators=(",", ":"))
+
+
+if __name__ == "__main__":
+	main()
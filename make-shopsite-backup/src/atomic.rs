@@ -0,0 +1,74 @@
+//! Replacing files so that nothing ever sees them half-written.
+
+use std::{
+	fs,
+	io::{self, Write},
+	path::Path
+};
+use tempfile::Builder;
+
+/// Writes `contents` to the file at `path`, replacing it if it exists.
+/// 
+/// The contents are written to a uniquely named temporary file next to the file first, flushed to disk, then renamed into place, so that a crash or a full disk partway through leaves the old file alone, and anything reading the file sees either all of the old contents or all of the new. If the file already exists, its permissions are kept. If `path` is a symbolic link, the file it points to is replaced, and the link is left as it is.
+pub fn write(path: &Path, contents: &[u8]) -> io::Result<()> {
+	// Resolve symbolic links first, so that the link isn't replaced by a regular file. If the file doesn't exist yet, there's no link to resolve.
+	let path = fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf());
+	let dir = match path.parent() {
+		Some(dir) if !dir.as_os_str().is_empty() => dir,
+		_ => Path::new(".")
+	};
+
+	let existing = fs::metadata(&path).ok();
+	let mut builder = Builder::new();
+
+	// Temporary files are only readable by their owner, but a new file should get the usual permissions, as if it were created directly.
+	#[cfg(unix)]
+	if existing.is_none() {
+		use std::os::unix::fs::PermissionsExt;
+		builder.permissions(fs::Permissions::from_mode(0o666));
+	}
+
+	// The temporary file is deleted when it's dropped, if it hasn't been renamed into place yet.
+	let mut temp = builder.tempfile_in(dir)?;
+	temp.write_all(contents)?;
+
+	if let Some(existing) = existing {
+		temp.as_file().set_permissions(existing.permissions())?;
+	}
+
+	temp.as_file().sync_all()?;
+	temp.persist(&path).map_err(|error| error.error)?;
+	Ok(())
+}
+
+#[test]
+fn test_write() {
+	let path = std::env::temp_dir().join(format!("make-shopsite-backup-test-atomic-{}.json", std::process::id()));
+
+	// A file that happens to have the name of a temporary file is left alone.
+	let tmp = path.with_extension("json.tmp");
+	fs::write(&tmp, b"unrelated").unwrap();
+
+	write(&path, b"one").unwrap();
+	assert_eq!(fs::read(&path).unwrap(), b"one");
+	assert_eq!(fs::read(&tmp).unwrap(), b"unrelated");
+	fs::remove_file(&tmp).unwrap();
+
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+
+		fs::set_permissions(&path, fs::Permissions::from_mode(0o640)).unwrap();
+		write(&path, b"two").unwrap();
+		assert_eq!(fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+
+		let link = path.with_extension("link.json");
+		std::os::unix::fs::symlink(&path, &link).unwrap();
+		write(&link, b"three").unwrap();
+		assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+		assert_eq!(fs::read(&path).unwrap(), b"three");
+		fs::remove_file(&link).unwrap();
+	}
+
+	fs::remove_file(&path).unwrap();
+}
@@ -1,7 +1,18 @@
+use crate::http::{self, Client};
 use serde::Deserialize;
+use shopsite_aa::de as aa;
 use std::{
-	path::PathBuf
+	env,
+	fs,
+	path::{Path, PathBuf}
 };
+use toml::{value::Table, Value};
+
+/// Prefix of environment variables that override configuration settings. The rest of the variable's name is the path to the setting, with `__` between each level, like `SHOPSITE_BACKUP__BACKUP__DIR` for the `dir` setting in the `[backup]` table.
+const ENV_PREFIX: &str = "SHOPSITE_BACKUP__";
+
+/// Settings that are arrays or tables, rather than strings, as paths like `shopsite.bo_curl_options`. Environment variables overriding these are parsed as TOML.
+const STRUCTURED_SETTINGS: &[&str] = &["shopsite.bo_curl_options"];
 
 #[derive(Deserialize)]
 pub struct Config {
@@ -17,5 +28,129 @@ pub struct BackupConfig {
 #[derive(Deserialize)]
 pub struct ShopsiteConfig {
 	config_file: PathBuf,
-	bo_curl_options: Vec<String>
+	bo_curl_options: Vec<String>,
+
+	/// The URL of the store's back office, like `https://www.example.com/cgi-bin/ss/start.cgi`. Only needed for contacting ShopSite.
+	#[serde(default)]
+	bo_url: Option<String>
+}
+
+impl Config {
+	/// Loads the configuration from a TOML file, if given, then applies overrides from environment variables (see `ENV_PREFIX`). Every setting can be overridden this way, so a configuration file isn't needed at all if the environment has every setting, as is usual in containers.
+	pub fn load(path: Option<&Path>) -> Result<Config, String> {
+		let mut table = match path {
+			Some(path) => {
+				let text = fs::read_to_string(path).map_err(|error| format!("Error reading configuration file {}: {}", path.display(), error))?;
+				toml::from_str(&text).map_err(|error| format!("Error in configuration file {}: {}", path.display(), error))?
+			},
+			None => Table::new()
+		};
+
+		apply_env_overrides(&mut table, env::vars_os().filter_map(|(name, value)| Some((name.into_string().ok()?, value.into_string().ok()?))))?;
+
+		Value::Table(table).try_into().map_err(|error| format!("Error in configuration: {}", error))
+	}
+
+	/// Checks that the configuration makes sense, as far as can be told without contacting ShopSite: the backup directory exists, and the ShopSite configuration file can be read and parsed. The file is normally in Windows-1252, but someone may have edited it with a UTF-8 text editor, so its encoding is guessed rather than assumed.
+	pub fn check(&self) -> Result<(), String> {
+		if !self.backup.dir.is_dir() {
+			return Err(format!("Backup directory {} does not exist or is not a directory", self.backup.dir.display()))
+		}
+
+		let path = &self.shopsite.config_file;
+		let bytes = fs::read(path).map_err(|error| format!("Error reading ShopSite configuration file {}: {}", path.display(), error))?;
+
+		let mut de = aa::DeserializerBuilder::new()
+			.encoding(aa::detect_encoding(&bytes).encoding)
+			.build(&bytes[..], Some(path.as_path().into()));
+
+		aa::Map::deserialize(&mut de).map_err(|error| format!("Error reading ShopSite configuration file: {}", error))?;
+
+		Ok(())
+	}
+
+	/// A `Client` that contacts the store's back office, with the `bo_curl_options` setting.
+	pub fn bo_client(&self) -> http::Curl {
+		http::Curl::new(self.shopsite.bo_curl_options.clone())
+	}
+
+	/// Checks that the store's back office answers at `bo_url`, using `client` to contact it, as a backup would. It has to answer successfully; being asked to log in, for example, means `bo_curl_options` doesn't have the right credentials.
+	pub fn check_shopsite(&self, client: &mut impl Client) -> Result<(), String> {
+		let url = self.shopsite.bo_url.as_deref().ok_or("The shopsite.bo_url setting is needed to contact ShopSite")?;
+		let response = client.send(&http::Request::get(url))?;
+
+		match response.status {
+			_ if response.is_success() => Ok(()),
+			401 | 403 => Err(format!("ShopSite back office at {} refused access (HTTP status {}). Check the credentials in shopsite.bo_curl_options.", url, response.status)),
+			status => Err(format!("ShopSite back office at {} answered with HTTP status {}", url, status))
+		}
+	}
+}
+
+/// Applies overrides from environment variables to a configuration table. `vars` is the environment's variables; those without `ENV_PREFIX` are ignored.
+/// 
+/// Names are matched case-insensitively, by lower-casing them, since environment variables are usually upper case and settings are lower case. Values of the settings in `STRUCTURED_SETTINGS` are parsed as TOML arrays or inline tables, like `["-k", "--silent"]` for a list. Everything else is taken as a string as is, so that a PIN like `1234` or a password like `{secret}` isn't mistaken for a number or a table.
+fn apply_env_overrides(table: &mut Table, vars: impl IntoIterator<Item = (String, String)>) -> Result<(), String> {
+	for (name, value) in vars {
+		let path = match name.strip_prefix(ENV_PREFIX) {
+			Some(path) => path.to_lowercase(),
+			None => continue
+		};
+
+		let mut keys: Vec<&str> = path.split("__").collect();
+		let last_key = keys.pop().unwrap_or_default();
+
+		if last_key.is_empty() || keys.iter().any(|key| key.is_empty()) {
+			return Err(format!("Error in environment variable {}: expected a setting name like {}BACKUP__DIR", name, ENV_PREFIX))
+		}
+
+		let mut subtable = &mut *table;
+
+		for key in keys {
+			let entry = subtable.entry(key.to_string()).or_insert_with(|| Value::Table(Table::new()));
+
+			subtable = match entry {
+				Value::Table(subtable) => subtable,
+				_ => return Err(format!("Error in environment variable {}: setting {} is not a table", name, key))
+			};
+		}
+
+		let value = {
+			if STRUCTURED_SETTINGS.contains(&&*path.replace("__", ".")) {
+				// Parse the value by wrapping it in a dummy table, since the `toml` crate only parses whole documents.
+				let mut parsed: Table = toml::from_str(&format!("value = {}", value)).map_err(|error| format!("Error in environment variable {}: {}", name, error))?;
+				parsed.remove("value").expect("the dummy table should contain the value")
+			}
+			else {
+				Value::String(value)
+			}
+		};
+
+		subtable.insert(last_key.to_string(), value);
+	}
+
+	Ok(())
+}
+
+#[test]
+fn test_env_overrides() {
+	let mut table: Table = toml::from_str("[backup]\ndir = \"/from/file\"\n\n[shopsite]\nconfig_file = \"/from/file.aa\"\n").unwrap();
+
+	apply_env_overrides(&mut table, vec![
+		("SHOPSITE_BACKUP__BACKUP__DIR".to_string(), "/from/env".to_string()),
+		("SHOPSITE_BACKUP__SHOPSITE__BO_CURL_OPTIONS".to_string(), "[\"-k\", \"--silent\"]".to_string()),
+		("SHOPSITE_BACKUP__STORE__PIN".to_string(), "1234".to_string()),
+		("SHOPSITE_BACKUP__STORE__PASSWORD".to_string(), "{xK9[".to_string()),
+		("UNRELATED".to_string(), "x".to_string())
+	]).unwrap();
+
+	let config: Config = Value::Table(table.clone()).try_into().unwrap();
+	assert_eq!(config.backup.dir, Path::new("/from/env"));
+	assert_eq!(config.shopsite.config_file, Path::new("/from/file.aa"));
+	assert_eq!(config.shopsite.bo_curl_options, ["-k", "--silent"]);
+	assert_eq!(table["store"]["pin"].as_str(), Some("1234"));
+	assert_eq!(table["store"]["password"].as_str(), Some("{xK9["));
+
+	assert!(apply_env_overrides(&mut table, vec![("SHOPSITE_BACKUP__BACKUP__DIR__X".to_string(), "y".to_string())]).is_err());
+	assert!(apply_env_overrides(&mut table, vec![("SHOPSITE_BACKUP__SHOPSITE__BO_CURL_OPTIONS".to_string(), "-k".to_string())]).is_err());
 }
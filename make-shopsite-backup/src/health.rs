@@ -0,0 +1,38 @@
+//! The status file written by `--healthcheck-file`, for container schedulers and monitoring to check on.
+
+use crate::atomic;
+use serde::Serialize;
+use std::{
+	io,
+	path::Path,
+	time::{SystemTime, UNIX_EPOCH}
+};
+
+/// The outcome of a run, as written to the status file in JSON.
+#[derive(Serialize)]
+pub struct Status {
+	/// `success` or `failure`.
+	pub status: &'static str,
+
+	/// The exit code that the process is about to exit with. See the `EXIT_*` constants.
+	pub exit_code: i32,
+
+	/// When the run started and finished, in seconds since the Unix epoch.
+	pub started: u64,
+	pub finished: u64,
+
+	/// What went wrong, if anything.
+	pub error: Option<String>
+}
+
+/// The current time, in seconds since the Unix epoch.
+pub fn now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map(|time| time.as_secs()).unwrap_or(0)
+}
+
+/// Writes a status file. It's replaced atomically, so that anything reading it never sees a half-written file.
+pub fn write(path: &Path, status: &Status) -> io::Result<()> {
+	let mut json = serde_json::to_vec_pretty(status)?;
+	json.push(b'\n');
+	atomic::write(path, &json)
+}
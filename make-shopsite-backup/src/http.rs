@@ -0,0 +1,238 @@
+//! Talking to ShopSite's back office over HTTP, and recording and replaying those conversations.
+//! 
+//! Requests are made by running `curl`, with the `bo_curl_options` setting, so that anything `curl` can be told to do, like logging in or trusting a certificate, can be configured without this tool knowing about it.
+//! 
+//! A `Recorder` keeps each request and its response, and saves them to a file, called a cassette. A `Replayer` answers requests from a cassette instead of contacting ShopSite, so that `--check-config` can be tried against a changed configuration without a ShopSite server to talk to.
+
+use crate::atomic;
+use serde::{Deserialize, Serialize};
+use std::{
+	fs,
+	path::Path,
+	process::Command
+};
+
+/// The program that requests are made with.
+const CURL: &str = "curl";
+
+/// An HTTP request.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Request {
+	/// Like `GET`.
+	pub method: String,
+	pub url: String
+}
+
+impl Request {
+	/// A `GET` request for a URL.
+	pub fn get(url: &str) -> Request {
+		Request {
+			method: "GET".to_string(),
+			url: url.to_string()
+		}
+	}
+}
+
+/// An HTTP response.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Response {
+	/// Like 200.
+	pub status: u16,
+
+	/// The body, with anything that isn't valid UTF-8 replaced.
+	pub body: String
+}
+
+impl Response {
+	/// Whether the status is a successful one, in the 200s.
+	pub fn is_success(&self) -> bool {
+		(200..300).contains(&self.status)
+	}
+}
+
+/// Something that answers HTTP requests, either by making them or by looking them up in a cassette.
+pub trait Client {
+	fn send(&mut self, request: &Request) -> Result<Response, String>;
+}
+
+/// Makes requests by running `curl`.
+pub struct Curl {
+	/// Options to give `curl` on every request. See the `bo_curl_options` setting.
+	options: Vec<String>
+}
+
+impl Curl {
+	pub fn new(options: Vec<String>) -> Curl {
+		Curl { options }
+	}
+}
+
+impl Client for Curl {
+	fn send(&mut self, request: &Request) -> Result<Response, String> {
+		let output = Command::new(CURL)
+			.args(["--silent", "--show-error", "--write-out", "\n%{http_code}", "--request", &request.method])
+			.args(&self.options)
+			.arg("--url")
+			.arg(&request.url)
+			.output()
+			.map_err(|error| format!("Error running {}: {}", CURL, error))?;
+
+		if !output.status.success() {
+			return Err(format!("Error contacting ShopSite at {}: {}", request.url, String::from_utf8_lossy(&output.stderr).trim()))
+		}
+
+		parse_curl_output(&output.stdout).ok_or_else(|| format!("Error contacting ShopSite at {}: {} didn't say what the HTTP status was", request.url, CURL))
+	}
+}
+
+/// Parses what `curl` prints for a request made by `Curl::send`: the response body, then a line break and the HTTP status, from `--write-out`.
+fn parse_curl_output(output: &[u8]) -> Option<Response> {
+	let split = output.iter().rposition(|&byte| byte == b'\n')?;
+	let status = std::str::from_utf8(&output[split + 1..]).ok()?.parse().ok()?;
+
+	Some(Response {
+		status,
+		body: String::from_utf8_lossy(&output[..split]).into_owned()
+	})
+}
+
+/// A request, and the response it got.
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct Interaction {
+	pub request: Request,
+	pub response: Response
+}
+
+/// A recording of requests and their responses, as saved by `Recorder` and read by `Replayer`. The file is JSON, with an `interactions` array, in the order the requests were made.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Cassette {
+	pub interactions: Vec<Interaction>
+}
+
+impl Cassette {
+	/// Reads a cassette file.
+	pub fn load(path: &Path) -> Result<Cassette, String> {
+		let json = fs::read(path).map_err(|error| format!("Error reading cassette {}: {}", path.display(), error))?;
+		serde_json::from_slice(&json).map_err(|error| format!("Error in cassette {}: {}", path.display(), error))
+	}
+
+	/// Writes a cassette file, replacing it if it exists. It's replaced atomically, so that a failure partway through leaves the old cassette alone.
+	pub fn save(&self, path: &Path) -> Result<(), String> {
+		let error_writing = |error: &dyn std::fmt::Display| format!("Error writing cassette {}: {}", path.display(), error);
+
+		let mut json = serde_json::to_vec_pretty(self).map_err(|error| error_writing(&error))?;
+		json.push(b'\n');
+		atomic::write(path, &json).map_err(|error| error_writing(&error))
+	}
+}
+
+/// Passes requests on to another `Client`, and keeps each one and its response.
+/// 
+/// Only the requests and responses are kept, not the client's options, so credentials in `bo_curl_options` don't end up in the cassette. Anything secret in the URLs or the responses does, though.
+pub struct Recorder<C: Client> {
+	client: C,
+	cassette: Cassette,
+
+	/// Whether a request couldn't be sent, or got no response, and so is missing from the cassette.
+	failed: bool
+}
+
+impl<C: Client> Recorder<C> {
+	pub fn new(client: C) -> Self {
+		Recorder {
+			client,
+			cassette: Cassette::default(),
+			failed: false
+		}
+	}
+
+	/// Whether every request was answered and recorded, and there was at least one. A response that says something is wrong, like a refusal to log in, still counts as an answer. If this is `false`, the cassette is missing something, and shouldn't replace a complete one.
+	pub fn is_complete(&self) -> bool {
+		!self.failed && !self.cassette.interactions.is_empty()
+	}
+
+	/// The requests and responses recorded so far.
+	pub fn into_cassette(self) -> Cassette {
+		self.cassette
+	}
+}
+
+impl<C: Client> Client for Recorder<C> {
+	fn send(&mut self, request: &Request) -> Result<Response, String> {
+		let response = self.client.send(request).inspect_err(|_| self.failed = true)?;
+
+		self.cassette.interactions.push(Interaction {
+			request: request.clone(),
+			response: response.clone()
+		});
+
+		Ok(response)
+	}
+}
+
+/// Answers requests with the responses recorded in a cassette, without contacting anything.
+/// 
+/// Each request gets the response of the first recorded interaction with the same method and URL that hasn't been used yet. A request that doesn't have one is an error, since the configuration must have changed in a way that the recording can't answer for.
+pub struct Replayer {
+	interactions: Vec<Option<Interaction>>
+}
+
+impl Replayer {
+	pub fn new(cassette: Cassette) -> Replayer {
+		Replayer {
+			interactions: cassette.interactions.into_iter().map(Some).collect()
+		}
+	}
+}
+
+impl Client for Replayer {
+	fn send(&mut self, request: &Request) -> Result<Response, String> {
+		self.interactions.iter_mut()
+			.find(|interaction| matches!(interaction, Some(interaction) if interaction.request == *request))
+			.and_then(Option::take)
+			.map(|interaction| interaction.response)
+			.ok_or_else(|| format!("No recorded response for {} {}. Record again with --record.", request.method, request.url))
+	}
+}
+
+#[test]
+fn test_parse_curl_output() {
+	assert_eq!(parse_curl_output(b"<html>\nOK\n</html>\n200"), Some(Response { status: 200, body: "<html>\nOK\n</html>".to_string() }));
+	assert_eq!(parse_curl_output(b"\n401"), Some(Response { status: 401, body: String::new() }));
+	assert_eq!(parse_curl_output(b"caf\xe9\n200").unwrap().body, "caf\u{fffd}");
+	assert_eq!(parse_curl_output(b"200"), None);
+	assert_eq!(parse_curl_output(b"body\nnope"), None);
+}
+
+#[test]
+fn test_record_replay() {
+	/// A client that answers every request with its URL.
+	struct Echo;
+
+	impl Client for Echo {
+		fn send(&mut self, request: &Request) -> Result<Response, String> {
+			Ok(Response { status: 200, body: request.url.clone() })
+		}
+	}
+
+	let mut recorder = Recorder::new(Echo);
+	recorder.send(&Request::get("https://example.com/a")).unwrap();
+	recorder.send(&Request::get("https://example.com/b")).unwrap();
+	recorder.send(&Request::get("https://example.com/a")).unwrap();
+
+	let cassette = recorder.into_cassette();
+	assert_eq!(cassette.interactions.len(), 3);
+
+	let path = std::env::temp_dir().join(format!("make-shopsite-backup-test-cassette-{}.json", std::process::id()));
+	cassette.save(&path).unwrap();
+	let cassette = Cassette::load(&path);
+	fs::remove_file(&path).unwrap();
+
+	// Requests are answered in any order, but each recorded response only once.
+	let mut replayer = Replayer::new(cassette.unwrap());
+	assert_eq!(replayer.send(&Request::get("https://example.com/b")).unwrap().body, "https://example.com/b");
+	assert_eq!(replayer.send(&Request::get("https://example.com/a")).unwrap().body, "https://example.com/a");
+	assert_eq!(replayer.send(&Request::get("https://example.com/a")).unwrap().body, "https://example.com/a");
+	assert!(replayer.send(&Request::get("https://example.com/a")).is_err());
+	assert!(replayer.send(&Request::get("https://example.com/c")).is_err());
+}
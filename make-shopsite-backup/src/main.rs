@@ -1,22 +1,131 @@
 use std::{
-	borrow::Cow,
 	env,
 	path::PathBuf,
 	process::exit
 };
 use structopt::StructOpt;
 
+mod atomic;
 mod config;
+mod health;
+mod http;
 
-const BIN_NAME: &str = env!("CARGO_PKG_NAME");
-const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), '/', env!("CARGO_PKG_VERSION"));
+/// Exit code for a successful backup.
+const EXIT_SUCCESS: i32 = 0;
+
+/// Exit code for a backup that failed, but might succeed if tried again, such as because ShopSite couldn't be reached.
+const EXIT_FAILURE: i32 = 1;
+
+/// Exit code for a problem that trying again won't fix, like a bad command line or configuration. Schedulers should not retry when they see this; Kubernetes can be told so with a `podFailurePolicy` rule.
+const EXIT_CONFIG: i32 = 2;
 
 fn main() {
 	#[derive(StructOpt)]
-	#[structopt(rename_all = "kebab-case")]
+	#[structopt(
+		rename_all = "kebab-case",
+		after_help = "Exit status is 0 if the backup succeeded, 1 if it failed but might succeed if tried again, or 2 if the command line or configuration is wrong and trying again won't help."
+	)]
 	struct Opts {
-		config_path: PathBuf
+		/// Configuration file. Settings can also be given in environment variables, like `SHOPSITE_BACKUP__BACKUP__DIR` for the `dir` setting in the `[backup]` table, which take precedence over the file. If every setting is given that way, the file can be left out.
+		config_path: Option<PathBuf>,
+
+		/// Make one backup, then exit. This is what a scheduler like a Kubernetes CronJob or systemd timer wants. There is no long-running mode yet, so this is required unless `--check-config` is given.
+		#[structopt(long)]
+		oneshot: bool,
+
+		/// Check the configuration and exit, instead of making a backup. Exit status is 0 if the configuration looks right, or 2 if not. ShopSite isn't contacted, unless `--record` is given.
+		#[structopt(long)]
+		check_config: bool,
+
+		/// With `--check-config`, also contact ShopSite's back office at the `bo_url` setting, as a backup would, and save the requests and responses in this file, for `--replay`. The file doesn't have `bo_curl_options` in it, but it does have the URLs and responses, so keep it as private as they are.
+		#[structopt(long, value_name = "PATH", requires = "check-config", conflicts_with = "replay")]
+		record: Option<PathBuf>,
+
+		/// With `--check-config`, also check the configuration against the responses saved by `--record` in this file, instead of contacting ShopSite. This way, configuration changes can be tried without ShopSite. Requests that weren't recorded are errors.
+		#[structopt(long, value_name = "PATH", requires = "check-config")]
+		replay: Option<PathBuf>,
+
+		/// After the backup, write its outcome to this file, in JSON, for health checks and monitoring to look at. The file has `status` (`success` or `failure`), `exit_code`, `started` and `finished` (in seconds since the Unix epoch), and `error` (a message, or `null`). It is replaced atomically, so it's never seen half-written.
+		#[structopt(long, value_name = "PATH")]
+		healthcheck_file: Option<PathBuf>
+	}
+
+	let opts = match Opts::from_iter_safe(env::args_os()) {
+		Ok(opts) => opts,
+		Err(error) if error.use_stderr() => {
+			eprintln!("{}", error.message);
+			exit(EXIT_CONFIG)
+		},
+		// `--help` and `--version` also come through as errors, but aren't.
+		Err(error) => error.exit()
+	};
+
+	if opts.check_config {
+		let check = |config: config::Config| {
+			config.check()?;
+
+			if let Some(path) = &opts.record {
+				let mut recorder = http::Recorder::new(config.bo_client());
+				let result = config.check_shopsite(&mut recorder);
+
+				// Save what was recorded even if the check failed because of what ShopSite answered, so that the failure can be looked into offline, too. If ShopSite couldn't be asked at all, though, there's nothing worth replacing an earlier recording with.
+				if recorder.is_complete() {
+					recorder.into_cassette().save(path)?;
+				}
+
+				result
+			}
+			else if let Some(path) = &opts.replay {
+				config.check_shopsite(&mut http::Replayer::new(http::Cassette::load(path)?))
+			}
+			else {
+				Ok(())
+			}
+		};
+
+		match config::Config::load(opts.config_path.as_deref()).and_then(check) {
+			Ok(()) => {
+				println!("Configuration is OK.");
+				exit(EXIT_SUCCESS)
+			},
+			Err(error) => {
+				eprintln!("{}", error);
+				exit(EXIT_CONFIG)
+			}
+		}
+	}
+
+	if !opts.oneshot {
+		eprintln!("There is no long-running mode yet. Give --oneshot to make one backup and exit.");
+		exit(EXIT_CONFIG);
+	}
+
+	let started = health::now();
+
+	// Making a backup isn't supported yet, and trying again won't change that, so this is reported as a configuration problem rather than a failure worth retrying.
+	let (exit_code, error) = match config::Config::load(opts.config_path.as_deref()) {
+		Err(error) => (EXIT_CONFIG, Some(error)),
+		Ok(_config) => (EXIT_CONFIG, Some("Making backups is not supported by this version yet. Only --check-config is.".to_string()))
+	};
+
+	if let Some(ref error) = error {
+		eprintln!("{}", error);
+	}
+
+	if let Some(ref path) = opts.healthcheck_file {
+		let status = health::Status {
+			status: if exit_code == EXIT_SUCCESS { "success" } else { "failure" },
+			exit_code,
+			started,
+			finished: health::now(),
+			error
+		};
+
+		if let Err(error) = health::write(path, &status) {
+			eprintln!("Error writing health check file {}: {}", path.display(), error);
+			exit(EXIT_FAILURE);
+		}
 	}
 
-	let config_path = Opts::from_args().config_path;
+	exit(exit_code);
 }
@@ -0,0 +1,183 @@
+// These tests run the program, mostly with `--check-config`, recording a conversation with a stand-in for ShopSite's back office, then replaying it without one.
+
+use std::{
+	env,
+	fs,
+	io::{BufRead, BufReader, Write},
+	net::TcpListener,
+	path::{Path, PathBuf},
+	process::{Command, Output},
+	thread
+};
+
+/// A scratch directory for one test, with a backup directory and a ShopSite configuration file in it, and room for a cassette.
+struct Scratch(PathBuf);
+
+impl Scratch {
+	fn new(name: &str) -> Scratch {
+		let dir = env::temp_dir().join(format!("make-shopsite-backup-test-{}-{}", name, std::process::id()));
+		fs::create_dir_all(dir.join("backups")).unwrap();
+		fs::write(dir.join("store.aa"), b"StoreName: Caf\xe9 Widgets\n").unwrap();
+		Scratch(dir)
+	}
+
+	fn path(&self, name: &str) -> PathBuf {
+		self.0.join(name)
+	}
+
+	/// Runs the program with `--check-config` and the given arguments, configured entirely by environment variables, with the back office at `bo_url`.
+	fn check_config(&self, bo_url: &str, args: &[&Path]) -> Output {
+		Command::new(env!("CARGO_BIN_EXE_make-shopsite-backup"))
+			.arg("--check-config")
+			.args(args)
+			.env("SHOPSITE_BACKUP__BACKUP__DIR", self.path("backups"))
+			.env("SHOPSITE_BACKUP__SHOPSITE__CONFIG_FILE", self.path("store.aa"))
+			.env("SHOPSITE_BACKUP__SHOPSITE__BO_CURL_OPTIONS", "[\"--max-time\", \"10\"]")
+			.env("SHOPSITE_BACKUP__SHOPSITE__BO_URL", bo_url)
+			.output()
+			.unwrap()
+	}
+}
+
+impl Drop for Scratch {
+	fn drop(&mut self) {
+		let _ = fs::remove_dir_all(&self.0);
+	}
+}
+
+/// Serves one HTTP request on a local port, answering with the given status and body, in the background. Returns the URL to request.
+fn serve_once(status: &'static str, body: &'static str) -> String {
+	let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+	let url = format!("http://{}/cgi-bin/ss/start.cgi", listener.local_addr().unwrap());
+
+	thread::spawn(move || {
+		let (stream, _) = listener.accept().unwrap();
+		let mut reader = BufReader::new(&stream);
+		let mut line = String::new();
+
+		// Read the request head, up to the blank line after the headers.
+		while reader.read_line(&mut line).unwrap() > 2 {
+			line.clear();
+		}
+
+		write!(&stream, "HTTP/1.1 {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}", status, body.len(), body).unwrap();
+	});
+
+	url
+}
+
+fn stderr(output: &Output) -> String {
+	String::from_utf8_lossy(&output.stderr).into_owned()
+}
+
+#[test]
+fn test_record_replay() {
+	if Command::new("curl").arg("--version").output().is_err() {
+		eprintln!("skipping test_record_replay, because curl isn't installed");
+		return
+	}
+
+	let scratch = Scratch::new("record-replay");
+	let cassette = scratch.path("cassette.json");
+
+	// Record a real conversation.
+	let url = serve_once("200 OK", "<html>Back office</html>");
+	let output = scratch.check_config(&url, &[Path::new("--record"), &cassette]);
+	assert!(output.status.success(), "{}", stderr(&output));
+
+	let recorded: serde_json::Value = serde_json::from_slice(&fs::read(&cassette).unwrap()).unwrap();
+	assert_eq!(recorded["interactions"][0]["request"]["url"], url.as_str());
+	assert_eq!(recorded["interactions"][0]["response"]["status"], 200);
+	assert_eq!(recorded["interactions"][0]["response"]["body"], "<html>Back office</html>");
+	assert!(!fs::read_to_string(&cassette).unwrap().contains("max-time"), "the cassette shouldn't have bo_curl_options in it");
+
+	// Replay it, with nothing listening anymore.
+	let output = scratch.check_config(&url, &[Path::new("--replay"), &cassette]);
+	assert!(output.status.success(), "{}", stderr(&output));
+
+	// A configuration change that the recording can't answer for fails the check.
+	let output = scratch.check_config("https://elsewhere.example.com/cgi-bin/ss/start.cgi", &[Path::new("--replay"), &cassette]);
+	assert_eq!(output.status.code(), Some(2));
+	assert!(stderr(&output).contains("No recorded response for GET https://elsewhere.example.com/"), "{}", stderr(&output));
+
+	// A refusal is recorded too, and fails the check, both times.
+	let url = serve_once("401 Unauthorized", "Log in");
+	let output = scratch.check_config(&url, &[Path::new("--record"), &cassette]);
+	assert_eq!(output.status.code(), Some(2));
+	assert!(stderr(&output).contains("credentials"), "{}", stderr(&output));
+
+	let output = scratch.check_config(&url, &[Path::new("--replay"), &cassette]);
+	assert_eq!(output.status.code(), Some(2));
+	assert!(stderr(&output).contains("HTTP status 401"), "{}", stderr(&output));
+
+	// If ShopSite can't be reached at all, the earlier recording is kept, rather than replaced with an empty one.
+	let output = scratch.check_config(&url, &[Path::new("--record"), &cassette]);
+	assert_eq!(output.status.code(), Some(2));
+	assert!(!stderr(&output).contains("credentials"), "{}", stderr(&output));
+
+	let output = scratch.check_config(&url, &[Path::new("--replay"), &cassette]);
+	assert_eq!(output.status.code(), Some(2));
+	assert!(stderr(&output).contains("HTTP status 401"), "{}", stderr(&output));
+	assert!(!scratch.path("cassette.json.tmp").exists());
+}
+
+#[test]
+fn test_replay_without_shopsite() {
+	// A cassette can also be written by hand, to stand in for a ShopSite that has never been contacted.
+	let scratch = Scratch::new("replay");
+	let cassette = scratch.path("cassette.json");
+	let url = "https://www.example.com/cgi-bin/ss/start.cgi";
+
+	fs::write(&cassette, r#"{
+		"interactions": [
+			{
+				"request": { "method": "GET", "url": "https://www.example.com/cgi-bin/ss/start.cgi" },
+				"response": { "status": 200, "body": "" }
+			}
+		]
+	}"#).unwrap();
+
+	let output = scratch.check_config(url, &[Path::new("--replay"), &cassette]);
+	assert!(output.status.success(), "{}", stderr(&output));
+	assert_eq!(String::from_utf8_lossy(&output.stdout), "Configuration is OK.\n");
+
+	// The rest of the configuration is still checked.
+	fs::remove_dir(scratch.path("backups")).unwrap();
+	let output = scratch.check_config(url, &[Path::new("--replay"), &cassette]);
+	assert_eq!(output.status.code(), Some(2));
+	assert!(stderr(&output).contains("Backup directory"), "{}", stderr(&output));
+
+	fs::create_dir(scratch.path("backups")).unwrap();
+	let output = scratch.check_config(url, &[Path::new("--replay"), &scratch.path("missing.json")]);
+	assert_eq!(output.status.code(), Some(2));
+	assert!(stderr(&output).contains("Error reading cassette"), "{}", stderr(&output));
+}
+
+#[test]
+fn test_backup_not_supported() {
+	// Making backups isn't supported yet. That should be reported as a problem that trying again won't fix, not as a failure for a scheduler to retry.
+
+	let scratch = Scratch::new("backup");
+	let health = scratch.path("health.json");
+
+	let run = |args: &[&Path]| Command::new(env!("CARGO_BIN_EXE_make-shopsite-backup"))
+		.args(args)
+		.env("SHOPSITE_BACKUP__BACKUP__DIR", scratch.path("backups"))
+		.env("SHOPSITE_BACKUP__SHOPSITE__CONFIG_FILE", scratch.path("store.aa"))
+		.env("SHOPSITE_BACKUP__SHOPSITE__BO_CURL_OPTIONS", "[]")
+		.env("SHOPSITE_BACKUP__SHOPSITE__BO_URL", "http://127.0.0.1:9/")
+		.output()
+		.unwrap();
+
+	let output = run(&[]);
+	assert_eq!(output.status.code(), Some(2));
+	assert!(stderr(&output).contains("--oneshot"), "{}", stderr(&output));
+
+	let output = run(&[Path::new("--oneshot"), Path::new("--healthcheck-file"), &health]);
+	assert_eq!(output.status.code(), Some(2));
+	assert!(stderr(&output).contains("not supported"), "{}", stderr(&output));
+
+	let status: serde_json::Value = serde_json::from_slice(&fs::read(&health).unwrap()).unwrap();
+	assert_eq!(status["status"], "failure");
+	assert_eq!(status["exit_code"], 2);
+}
@@ -0,0 +1,47 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use serde::de::{Deserialize, IgnoredAny};
+use shopsite_aa::de as aa;
+use shopsite_aa_bench::{corpus, FewFields};
+use std::collections::HashMap;
+
+/// Corpus sizes to benchmark, in bytes.
+const SIZES: &[usize] = &[64 * 1024, 1024 * 1024];
+
+fn parse(c: &mut Criterion) {
+	let mut group = c.benchmark_group("parse");
+
+	for &size in SIZES {
+		let input = corpus(size);
+		group.throughput(Throughput::Bytes(input.len() as u64));
+
+		// Just the parser: every key and value is read, then thrown away.
+		group.bench_with_input(BenchmarkId::new("ignored", size), &input, |b, input| {
+			b.iter(|| aa::from_bytes::<IgnoredAny>(input, None).unwrap())
+		});
+
+		// Reading from an `io::Read` instead of a slice, which goes through the deserializer's own buffer.
+		group.bench_with_input(BenchmarkId::new("reader", size), &input, |b, input| {
+			b.iter(|| aa::from_reader::<IgnoredAny, _>(&input[..], None).unwrap())
+		});
+
+		// Decoding every value into an owned `String`.
+		group.bench_with_input(BenchmarkId::new("strings", size), &input, |b, input| {
+			b.iter(|| aa::from_bytes::<HashMap<String, Option<String>>>(input, None).unwrap())
+		});
+
+		// A struct with only a few of the keys as fields, so the rest are skipped without being decoded.
+		group.bench_with_input(BenchmarkId::new("few_fields", size), &input, |b, input| {
+			b.iter(|| FewFields::from_bytes(input).unwrap())
+		});
+
+		// The loosely-typed `Map`, which is what `shopsite-aa2json` and most ad hoc scripts end up using.
+		group.bench_with_input(BenchmarkId::new("map", size), &input, |b, input| {
+			b.iter(|| aa::Map::deserialize(&mut aa::Deserializer::new(&input[..], None)).unwrap())
+		});
+	}
+
+	group.finish();
+}
+
+criterion_group!(benches, parse);
+criterion_main!(benches);
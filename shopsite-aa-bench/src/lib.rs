@@ -0,0 +1,108 @@
+//! Benchmarks and allocation-count tests for the `shopsite-aa` parser.
+//! 
+//! The benchmarks are in `benches/parse.rs`, and use [Criterion](https://docs.rs/criterion). Run them with `cargo bench -p shopsite-aa-bench`. The allocation-count tests are in `tests/test_alloc.rs`, and run with the rest of the tests.
+//! 
+//! Both use the corpus generated by `corpus`, which is shaped like the one that `bench/compare.py` generates, so that the numbers are comparable.
+
+use serde::{de::IgnoredAny, Deserialize};
+use shopsite_aa::de as aa;
+
+/// A small, fast pseudo-random number generator (xorshift64*), so that the corpus is the same every time without depending on a particular version of the `rand` crate.
+struct Rng(u64);
+
+impl Rng {
+	fn next(&mut self) -> u64 {
+		self.0 ^= self.0 >> 12;
+		self.0 ^= self.0 << 25;
+		self.0 ^= self.0 >> 27;
+		self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+	}
+
+	/// A number from `min` to `max`, inclusive.
+	fn range(&mut self, min: usize, max: usize) -> usize {
+		min + (self.next() % (max - min + 1) as u64) as usize
+	}
+}
+
+/// Bytes that text in the corpus is made of: printable ASCII except `|`, and the printable upper half of Windows-1252.
+fn text_bytes() -> Vec<u8> {
+	(0x20..0x7f).filter(|&b| b != b'|').chain(0xa0..=0xff).collect()
+}
+
+/// Generates a `.aa` file of about `size` bytes, shaped roughly like ShopSite's product database dumps: comments, lots of keys with short values, some sequences, some long text, and some keys with no value. Lines end with CRLF, as ShopSite writes them.
+/// 
+/// Every product has six keys, all named after the product's index, so no key appears twice. The output only depends on `size`.
+pub fn corpus(size: usize) -> Vec<u8> {
+	let mut rng = Rng(0x5EED_5EED_5EED_5EED);
+	let text_bytes = text_bytes();
+
+	let text = |rng: &mut Rng, min_len: usize, max_len: usize| -> Vec<u8> {
+		let len = rng.range(min_len, max_len);
+		let text: Vec<u8> = (0..len).map(|_| text_bytes[rng.range(0, text_bytes.len() - 1)]).collect();
+		let start = text.iter().position(|&b| b != b' ').unwrap_or(text.len());
+		let end = text.iter().rposition(|&b| b != b' ').map_or(start, |end| end + 1);
+		text[start..end].to_vec()
+	};
+
+	let mut output = b"# Generated by shopsite-aa-bench\r\n".to_vec();
+	let mut index = 0;
+
+	while output.len() < size {
+		if index % 50 == 0 {
+			output.extend_from_slice(format!("# Product {}\r\n", index).as_bytes());
+		}
+
+		let name = text(&mut rng, 5, 40);
+		let price = format!("{}.{:02}", rng.range(0, 9999), rng.range(0, 99));
+		let options: Vec<Vec<u8>> = (0..rng.range(0, 6)).map(|_| text(&mut rng, 1, 12)).collect();
+		let description = text(&mut rng, 0, 400);
+
+		let mut line = |key: &str, value: Option<&[u8]>| {
+			output.extend_from_slice(format!("Product{}{}", index, key).as_bytes());
+
+			if let Some(value) = value {
+				output.extend_from_slice(b": ");
+				output.extend_from_slice(value);
+			}
+
+			output.extend_from_slice(b"\r\n");
+		};
+
+		line("Name", Some(&name));
+		line("SKU", Some(format!("SKU-{:06}", index).as_bytes()));
+		line("Price", Some(price.as_bytes()));
+		line("Options", Some(&options.join(&b'|')));
+		line("Description", Some(&description));
+		line("Taxable", None);
+
+		index += 1;
+	}
+
+	output
+}
+
+/// A struct with a few of the keys in a corpus generated by `corpus` as fields, one of which is ignored, for measuring how quickly the rest of the keys are skipped.
+#[derive(Debug, Deserialize)]
+#[allow(dead_code)]
+pub struct FewFields {
+	#[serde(rename = "Product0Name")]
+	name: String,
+
+	#[serde(rename = "Product0Price")]
+	price: String,
+
+	#[serde(rename = "Product0Description")]
+	description: IgnoredAny
+}
+
+impl FewFields {
+	/// Deserializes a corpus with `DeserializerBuilder::skip_unknown_keys` turned on, so that the keys that aren't fields are skipped.
+	pub fn from_bytes(input: &[u8]) -> aa::Result<FewFields> {
+		FewFields::deserialize(&mut aa::DeserializerBuilder::new().skip_unknown_keys(true).build(input, None))
+	}
+}
+
+/// Counts the keys in a corpus generated by `corpus`.
+pub fn count_keys(corpus: &[u8]) -> usize {
+	corpus.split(|&b| b == b'\n').filter(|line| !line.is_empty() && !line.starts_with(b"#")).count()
+}
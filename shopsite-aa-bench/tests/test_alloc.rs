@@ -0,0 +1,138 @@
+use serde::de::{Deserialize, IgnoredAny};
+use shopsite_aa::de as aa;
+use shopsite_aa_bench::{corpus, count_keys, FewFields};
+use std::{
+	alloc::{GlobalAlloc, Layout, System},
+	cell::Cell,
+	collections::HashMap
+};
+
+/// The system allocator, but counting allocations made by the current thread while `count_allocations` is running, and keeping track of the largest one. Tests run on separate threads, so each only counts its own.
+struct CountingAllocator;
+
+thread_local! {
+	static ALLOCATIONS: Cell<Option<usize>> = const { Cell::new(None) };
+	static LARGEST: Cell<usize> = const { Cell::new(0) };
+}
+
+/// Counts an allocation or reallocation of `size` bytes, if `count_allocations` is running.
+fn note_allocation(size: usize) {
+	// `try_with` fails while the thread is being torn down, when there's nothing to count anyway.
+	let counting = ALLOCATIONS.try_with(|count| {
+		count.set(count.get().map(|count| count + 1));
+		count.get().is_some()
+	});
+
+	if counting == Ok(true) {
+		let _ = LARGEST.try_with(|largest| largest.set(largest.get().max(size)));
+	}
+}
+
+unsafe impl GlobalAlloc for CountingAllocator {
+	unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+		note_allocation(layout.size());
+		System.alloc(layout)
+	}
+
+	unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+		System.dealloc(ptr, layout)
+	}
+
+	unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+		note_allocation(new_size);
+		System.realloc(ptr, layout, new_size)
+	}
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Runs `f`, and returns how many times it allocated or reallocated memory.
+fn count_allocations<T>(f: impl FnOnce() -> T) -> usize {
+	ALLOCATIONS.with(|count| count.set(Some(0)));
+	let result = f();
+	let count = ALLOCATIONS.with(|count| count.take()).unwrap();
+	drop(result);
+	count
+}
+
+/// Runs `f`, and returns the size of the largest block of memory that it allocated or reallocated.
+fn largest_allocation<T>(f: impl FnOnce() -> T) -> usize {
+	LARGEST.with(|largest| largest.set(0));
+	count_allocations(f);
+	LARGEST.with(|largest| largest.get())
+}
+
+/// Size of the corpus to parse. Large enough that per-key allocations dwarf the deserializer's fixed overhead.
+const CORPUS_SIZE: usize = 1024 * 1024;
+
+// These tests guard against the parser starting to allocate more than it does now. The limits are a little above what it actually does, so that they don't fail over small changes in the standard library. If the parser gets better, lower them.
+
+#[test]
+fn test_alloc_ignored() {
+	// Parsing without keeping anything currently allocates once for each key, since keys are always decoded into a new `String`. Ideally, this would be a fixed amount, no matter how big the input is.
+
+	let input = corpus(CORPUS_SIZE);
+	let keys = count_keys(&input);
+	let allocations = count_allocations(|| aa::from_bytes::<IgnoredAny>(&input, None).unwrap());
+	assert!(allocations <= keys + 16, "parsing {} keys into IgnoredAny made {} allocations", keys, allocations);
+}
+
+#[test]
+fn test_alloc_reader() {
+	// Reading through an `io::Read` should only add the deserializer's buffer.
+
+	let input = corpus(CORPUS_SIZE);
+	let keys = count_keys(&input);
+	let allocations = count_allocations(|| aa::from_reader::<IgnoredAny, _>(&input[..], None).unwrap());
+	assert!(allocations <= keys + 16, "parsing {} keys into IgnoredAny from a reader made {} allocations", keys, allocations);
+}
+
+#[test]
+fn test_alloc_strings() {
+	// Decoding into owned strings currently allocates about four times for each key, counting the map's growth.
+
+	let input = corpus(CORPUS_SIZE);
+	let keys = count_keys(&input);
+	let allocations = count_allocations(|| aa::from_bytes::<HashMap<String, Option<String>>>(&input, None).unwrap());
+	assert!(allocations <= keys * 4 + keys / 10, "parsing {} keys into strings made {} allocations", keys, allocations);
+}
+
+#[test]
+fn test_alloc_few_fields() {
+	// Keys that aren't fields of the struct aren't decoded, so only the fields' values are allocated, no matter how big the input is.
+
+	let input = corpus(CORPUS_SIZE);
+	let allocations = count_allocations(|| FewFields::from_bytes(&input).unwrap());
+	assert!(allocations <= 32, "parsing a struct with few fields made {} allocations", allocations);
+}
+
+#[test]
+fn test_alloc_reset() {
+	// Resetting a deserializer for another input keeps its buffers, so it doesn't allocate at all.
+
+	let input = corpus(64 * 1024);
+	let mut de = aa::Deserializer::new(&input[..], None);
+	IgnoredAny::deserialize(&mut de).unwrap();
+
+	let allocations = count_allocations(|| { de.reset(&input[..], None); });
+	assert_eq!(allocations, 0, "resetting a deserializer made {} allocations", allocations);
+	IgnoredAny::deserialize(&mut de).unwrap();
+}
+
+#[test]
+fn test_alloc_limits() {
+	// Lines and values that are over the limit are rejected before they're copied, so a huge line in a slice, which is all in the buffer at once, doesn't get allocated.
+
+	let input = [&b"name: "[..], &vec![b'x'; 4 * 1024 * 1024][..], b"\n"].concat();
+
+	for (max_line_length, max_value_size) in [(1024, usize::MAX), (usize::MAX, 1024)] {
+		let mut builder = aa::DeserializerBuilder::new();
+		builder.max_line_length(max_line_length).max_value_size(max_value_size);
+
+		let mut result = None;
+		let largest = largest_allocation(|| result = Some(HashMap::<String, String>::deserialize(&mut builder.build(&input[..], None))));
+		assert!(matches!(result, Some(Err(aa::Error::LimitExceeded { .. }))), "expected a limit to be exceeded, but got {:?}", result);
+		assert!(largest <= 64 * 1024, "parsing a line over the limit allocated {} bytes at once", largest);
+	}
+}
@@ -0,0 +1,177 @@
+//! Python bindings for the `shopsite-aa` library, as a module named `shopsite_aa`, so that scripts written in Python can read and write `.aa` files without a parser of their own. Its `loads` and `dumps` functions work like the ones in Python's `json` module:
+//! 
+//! ```python
+//! import shopsite_aa
+//! 
+//! with open("product.aa", "rb") as f:
+//!     product = shopsite_aa.loads(f.read())
+//! 
+//! product["Price"] = "9.99"
+//! 
+//! with open("product.aa", "w", encoding="cp1252", newline="\n") as f:
+//!     f.write(shopsite_aa.dumps(product))
+//! ```
+//! 
+//! Build and install it with [`maturin`](https://www.maturin.rs/): `pip install ./shopsite-aa-py`, or `maturin develop` from this directory in a virtual environment.
+
+use pyo3::{
+	exceptions::{PyTypeError, PyValueError},
+	prelude::*,
+	types::{PyBool, PyBytes, PyDict, PyFloat, PyInt, PyList, PyString, PyTuple}
+};
+use serde::{Deserialize, Serialize};
+use shopsite_aa::{
+	de::{self, DeserializerBuilder, InputEncoding, LineEnding},
+	ser::SerializerBuilder,
+	value::{Entries, Value}
+};
+
+/// Parses a .aa file into a dict, with the keys in the order they appear in the file.
+/// 
+/// `data` is either the bytes of the file, which are decoded from Windows-1252 (what ShopSite uses), or a str that has already been decoded.
+/// 
+/// Values are always str, since .aa files don't say what type anything is, except that a key with no value at all (no `:`) has the value None. Sequences are not split up; use `value.split("|")` for values known to be sequences. If a key appears more than once, the last value wins.
+/// 
+/// Raises ValueError, describing the problem and the line it's on, if the file can't be parsed.
+#[pyfunction]
+fn loads<'py>(py: Python<'py>, data: &Bound<'py, PyAny>) -> PyResult<Bound<'py, PyDict>> {
+	let result = if let Ok(bytes) = data.downcast::<PyBytes>() {
+		parse(bytes.as_bytes(), InputEncoding::Windows1252)
+	}
+	else if let Ok(text) = data.downcast::<PyString>() {
+		parse(text.to_str()?.as_bytes(), InputEncoding::Utf8)
+	}
+	else {
+		return Err(PyTypeError::new_err(format!("expected bytes or str, not {}", data.get_type().name()?)))
+	};
+
+	let Entries(entries) = result.map_err(|error| PyValueError::new_err(error.to_string()))?;
+	let dict = PyDict::new(py);
+
+	for (key, value) in entries {
+		dict.set_item(key, value_to_py(py, value)?)?;
+	}
+
+	Ok(dict)
+}
+
+/// Writes a dict as a .aa file, with the keys in the dict's order, and returns it as a str. ShopSite expects files in Windows-1252, so encode it with `.encode("cp1252")` before writing it.
+/// 
+/// Keys must be str. Values may be str, int, float, or bool, which are written as they are (booleans as `true` and `false`); None, which is written as a key with no value; or a list or tuple of those, whose elements are written separated by `|` (or by `,`, for a list in a list).
+/// 
+/// Raises TypeError if a key or value is of some other type, and ValueError if it can't be written in a .aa file without being read back as something else, such as a key with a `:` in it or a value with a line break in it.
+#[pyfunction]
+fn dumps(obj: &Bound<'_, PyDict>) -> PyResult<String> {
+	let mut entries = Vec::with_capacity(obj.len());
+
+	for (key, value) in obj.iter() {
+		let key = match key.downcast::<PyString>() {
+			Ok(key) => key.to_str()?.to_string(),
+			Err(_) => return Err(PyTypeError::new_err(format!("keys must be str, not {}", key.get_type().name()?)))
+		};
+
+		entries.push((key, value_from_py(&value)?));
+	}
+
+	let mut serializer = SerializerBuilder::new()
+		.encoding(InputEncoding::Utf8)
+		.line_ending(LineEnding::Lf)
+		.build(Vec::new());
+
+	Entries(entries).serialize(&mut serializer).map_err(|error| PyValueError::new_err(error.to_string()))?;
+	Ok(String::from_utf8(serializer.into_inner()).expect("the serializer should have written UTF-8"))
+}
+
+fn parse(bytes: &[u8], encoding: InputEncoding) -> de::Result<Entries> {
+	Entries::deserialize(&mut DeserializerBuilder::new().encoding(encoding).build(bytes, None))
+}
+
+fn value_to_py(py: Python<'_>, value: Value) -> PyResult<Bound<'_, PyAny>> {
+	Ok(match value {
+		Value::Unit => py.None().into_bound(py),
+		Value::String(s) => PyString::new(py, &s).into_any(),
+		Value::Seq(elements) => PyList::new(py, elements.into_iter().map(|element| value_to_py(py, element)).collect::<PyResult<Vec<_>>>()?)?.into_any(),
+		Value::Map(map) => {
+			let dict = PyDict::new(py);
+
+			for (key, value) in map {
+				dict.set_item(key, value_to_py(py, value)?)?;
+			}
+
+			dict.into_any()
+		}
+	})
+}
+
+fn value_from_py(obj: &Bound<'_, PyAny>) -> PyResult<Value> {
+	// `bool` is a subclass of `int`, so it has to be checked for first.
+	if obj.is_none() {
+		Ok(Value::Unit)
+	}
+	else if let Ok(s) = obj.downcast::<PyString>() {
+		Ok(Value::from(s.to_str()?))
+	}
+	else if obj.is_instance_of::<PyBool>() {
+		Ok(Value::from(if obj.is_truthy()? { "true" } else { "false" }))
+	}
+	else if obj.is_instance_of::<PyInt>() || obj.is_instance_of::<PyFloat>() {
+		Ok(Value::from(obj.str()?.to_str()?))
+	}
+	else if obj.is_instance_of::<PyList>() || obj.is_instance_of::<PyTuple>() {
+		obj.try_iter()?.map(|element| value_from_py(&element?)).collect::<PyResult<Vec<_>>>().map(Value::Seq)
+	}
+	else {
+		Err(PyTypeError::new_err(format!("{} can't be written in a .aa file", obj.get_type().name()?)))
+	}
+}
+
+/// Reads and writes ShopSite .aa files.
+#[pymodule]
+#[pyo3(name = "shopsite_aa")]
+fn shopsite_aa_py(m: &Bound<'_, PyModule>) -> PyResult<()> {
+	m.add_function(wrap_pyfunction!(loads, m)?)?;
+	m.add_function(wrap_pyfunction!(dumps, m)?)?;
+	Ok(())
+}
+
+#[test]
+fn test_loads_dumps() {
+	pyo3::prepare_freethreaded_python();
+
+	Python::with_gil(|py| {
+		let module = PyModule::new(py, "shopsite_aa").unwrap();
+		shopsite_aa_py(&module).unwrap();
+
+		let globals = PyDict::new(py);
+		globals.set_item("aa", module).unwrap();
+
+		let run = |code: &str| py.run(&std::ffi::CString::new(code).unwrap(), Some(&globals), None);
+
+		run(r##"
+d = aa.loads(b"# comment\nName: Caf\xe9\nTaxable\nTags: a|b\nName: again\nPrice: 10\n")
+assert d == {"Name": "again", "Taxable": None, "Tags": "a|b", "Price": "10"}, d
+assert list(d) == ["Name", "Taxable", "Tags", "Price"], list(d)
+assert aa.loads("Name: Café\n") == {"Name": "Café"}
+
+assert aa.dumps({"Name": "Café", "Taxable": None, "Price": 10.5, "Sale": False, "Tags": ["a", 2, None]}) == "Name: Café\nTaxable\nPrice: 10.5\nSale: false\nTags: a|2|\n"
+
+assert aa.dumps({"Sizes": [["S", "M"], "L"]}) == "Sizes: S,M|L\n"
+
+text = "Name: Widget\nTaxable\nTags: a|b\n"
+assert aa.dumps(aa.loads(text)) == text
+"##).unwrap();
+
+		// Bad input raises the usual exceptions.
+		for (code, exception) in &[
+			("aa.loads(1)", "TypeError"),
+			("aa.dumps({1: 'a'})", "TypeError"),
+			("aa.dumps({'a': object()})", "TypeError"),
+			("aa.dumps({'a': 'x\\ny'})", "ValueError"),
+			("aa.dumps({'a:b': 'x'})", "ValueError"),
+			("aa.dumps({'a': [[['x']]]})", "ValueError")
+		] {
+			let error = run(code).expect_err(code);
+			assert_eq!(error.get_type(py).name().unwrap().to_string(), *exception, "{}", code);
+		}
+	});
+}
@@ -0,0 +1,70 @@
+//! Reads a `.aa` file whose keys aren't all known ahead of time into a `Map`, changes it, and then reads the result into a struct. This is handy for fixing up files before handing them to code that expects them to be just so.
+//! 
+//! Run with `cargo run --example edit_map`.
+
+use serde::Deserialize;
+use shopsite_aa::de as aa;
+
+/// The keys that the rest of the program cares about. Everything else is kept in `other`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Page {
+	name: String,
+	title: String,
+	#[serde(flatten)]
+	other: aa::Map
+}
+
+const SAMPLE: &[u8] = b"\
+Name: about
+Title: About Us
+bgcolor: #FFFFFF
+ShowCart
+OldKey: no longer used
+";
+
+/// Makes some changes to a page, and describes the result.
+fn edit(input: &[u8]) -> aa::Result<String> {
+	let mut map: aa::Map = aa::from_bytes(input, None)?;
+	let mut text = String::new();
+
+	// Change a value, add a key, and remove one, as with any other map.
+	map.insert("Title".to_string(), aa::Value::from("About Our Store"));
+	map.insert("Keywords".to_string(), aa::Value::from(vec![aa::Value::from("about"), aa::Value::from("store")]));
+	map.retain(|key, _| key != "OldKey");
+
+	// `Value` writes itself as it would appear in a `.aa` file. A key whose value is `Unit` has no value at all.
+	for (key, value) in &map {
+		if value.is_unit() {
+			text += &format!("{}\n", key);
+		}
+		else {
+			text += &format!("{}: {}\n", key, value);
+		}
+	}
+
+	// Then turn the edited map into a struct.
+	let page: Page = aa::from_value(aa::Value::Map(map))?;
+	text += &format!("{} is titled {:?}, and has {} other keys\n", page.name, page.title, page.other.len());
+
+	Ok(text)
+}
+
+fn main() -> aa::Result<()> {
+	print!("{}", edit(SAMPLE)?);
+	Ok(())
+}
+
+#[test]
+fn test_edit_map() {
+	let expected_keys = {
+		if cfg!(feature = "indexmap") {
+			"Name: about\nTitle: About Our Store\nbgcolor: #FFFFFF\nShowCart\nKeywords: about|store\n"
+		}
+		else {
+			"Keywords: about|store\nName: about\nShowCart\nTitle: About Our Store\nbgcolor: #FFFFFF\n"
+		}
+	};
+
+	assert_eq!(edit(SAMPLE).unwrap(), format!("{}about is titled \"About Our Store\", and has 3 other keys\n", expected_keys));
+}
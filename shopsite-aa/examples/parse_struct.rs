@@ -0,0 +1,83 @@
+//! Deserializes a `.aa` file into a struct, the way most programs will want to read one.
+//! 
+//! Run with `cargo run --example parse_struct [FILE]`. Without a file, it reads a small sample product.
+
+use serde::Deserialize;
+use shopsite_aa::de as aa;
+use std::{
+	env,
+	fs::File,
+	path::Path,
+	process::exit
+};
+
+/// Some of the fields of a ShopSite product. Fields that ShopSite doesn't always write are `Option`s, and `#[serde(default)]` fills in the rest, so a file with fewer keys still parses.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Product {
+	name: String,
+	price: f64,
+	#[serde(default)]
+	taxable: bool,
+	#[serde(default)]
+	sale_price: Option<f64>,
+	#[serde(default)]
+	product_field1: Vec<String>
+}
+
+const SAMPLE: &[u8] = b"\
+# A product, as ShopSite would export it.
+Name: Widget
+Price: 19.99
+Taxable: checked
+SalePrice:
+ProductField1: red|green|blue
+";
+
+/// A deserializer builder for ShopSite products. ShopSite spells booleans like `checked`, which takes the lenient syntax.
+fn builder() -> aa::DeserializerBuilder {
+	let mut builder = aa::DeserializerBuilder::new();
+	builder.bool_syntax(aa::BoolSyntax::Lenient);
+	builder
+}
+
+/// Describes a product, in a line about its price and a line about its colors.
+fn describe(product: &Product) -> String {
+	format!(
+		"{} costs ${:.2}{}\nColors: {}\n",
+		product.name,
+		product.sale_price.unwrap_or(product.price),
+		if product.taxable { ", plus tax" } else { "" },
+		product.product_field1.join(", ")
+	)
+}
+
+fn main() {
+	let builder = builder();
+
+	let result = match env::args_os().nth(1) {
+		Some(path) => match File::open(&path) {
+			Ok(file) => Product::deserialize(&mut builder.build_from_read(file, Some(Path::new(&path).into()))),
+			Err(error) => {
+				eprintln!("Error opening {}: {}", path.to_string_lossy(), error);
+				exit(1)
+			}
+		},
+		None => Product::deserialize(&mut builder.build(SAMPLE, None))
+	};
+
+	match result {
+		Ok(product) => print!("{}", describe(&product)),
+		Err(error) => {
+			// Errors say which file, line, and column they happened at, and which key was being read.
+			eprintln!("{}", error);
+			exit(1)
+		}
+	}
+}
+
+#[test]
+fn test_parse_struct() {
+	let product = Product::deserialize(&mut builder().build(SAMPLE, None)).unwrap();
+	assert_eq!(describe(&product), "Widget costs $19.99, plus tax\nColors: red, green, blue\n");
+}
@@ -0,0 +1,87 @@
+//! Reads a file of many records, like one of ShopSite's product database dumps, one record at a time, so that the whole file never has to fit in memory.
+//! 
+//! Run with `cargo run --example stream_records [FILE]`. Without a file, it reads a few sample products.
+
+use serde::Deserialize;
+use shopsite_aa::de as aa;
+use std::{
+	env,
+	error::Error,
+	fs::File,
+	io::{self, BufRead, BufReader, Write},
+	path::Path,
+	process::exit
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct Product {
+	#[serde(rename = "SKU")]
+	sku: String,
+	name: String,
+	#[serde(default)]
+	quantity_on_hand: u32
+}
+
+const SAMPLE: &[u8] = b"\
+SKU: W-1
+Name: Widget
+QuantityOnHand: 12
+
+SKU: G-2
+Name: Gadget
+QuantityOnHand: 0
+
+SKU: D-3
+Name: Doohickey
+QuantityOnHand: 5
+";
+
+/// Lists the products that are out of stock, and counts everything that isn't, writing the report to `out`. Each record is dropped as soon as it's been looked at.
+fn report(de: &mut aa::Deserializer<impl BufRead>, out: &mut impl Write) -> Result<(), Box<dyn Error>> {
+	let mut in_stock = 0u64;
+
+	for product in de.records::<Product>() {
+		let product = product?;
+
+		if product.quantity_on_hand == 0 {
+			writeln!(out, "Out of stock: {} ({})", product.name, product.sku)?;
+		}
+		else {
+			in_stock += u64::from(product.quantity_on_hand);
+		}
+	}
+
+	writeln!(out, "Units in stock: {}", in_stock)?;
+	Ok(())
+}
+
+fn main() {
+	let mut builder = aa::DeserializerBuilder::new();
+	builder.record_boundary(aa::RecordBoundary::BlankLine);
+	let out = &mut io::stdout().lock();
+
+	let result = match env::args_os().nth(1) {
+		Some(path) => match File::open(&path) {
+			Ok(file) => report(&mut builder.build(BufReader::new(file), Some(Path::new(&path).into())), out),
+			Err(error) => {
+				eprintln!("Error opening {}: {}", path.to_string_lossy(), error);
+				exit(1)
+			}
+		},
+		None => report(&mut builder.build(SAMPLE, None), out)
+	};
+
+	// Errors say which file, line, and column they happened at, and which key was being read.
+	if let Err(error) = result {
+		eprintln!("{}", error);
+		exit(1)
+	}
+}
+
+#[test]
+fn test_stream_records() {
+	let mut out = Vec::new();
+	report(&mut aa::DeserializerBuilder::new().record_boundary(aa::RecordBoundary::BlankLine).build(SAMPLE, None), &mut out).unwrap();
+	assert_eq!(String::from_utf8(out).unwrap(), "Out of stock: Gadget (G-2)\nUnits in stock: 17\n");
+}
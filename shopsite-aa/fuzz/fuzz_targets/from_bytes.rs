@@ -0,0 +1,61 @@
+//! Feeds arbitrary bytes to the deserializer, which should return an error for any of them it doesn't like, but never panic.
+//! 
+//! Run with `cargo fuzz run from_bytes`, from the `shopsite-aa` directory.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use serde::Deserialize;
+use shopsite_aa::de as aa;
+
+fuzz_target!(|data: &[u8]| {
+	// The simplest way in, with every default.
+	let _ = aa::from_bytes::<aa::Value>(data, None);
+
+	// Everything optional turned on, to reach the code for escapes, sections, records, and limits. The first byte picks the options, so that the fuzzer can find its way to each of them.
+	let (options, input) = match data.split_first() {
+		Some((options, input)) => (*options, input),
+		None => return
+	};
+
+	let mut builder = aa::DeserializerBuilder::new();
+
+	builder
+	.seq_escape(match options & 0b11 {
+		0 => aa::SeqEscape::None,
+		1 => aa::SeqEscape::Backslash,
+		_ => aa::SeqEscape::NumericEntity
+	})
+	.section_syntax(match (options >> 2) & 0b11 {
+		0 => aa::SectionSyntax::None,
+		1 => aa::SectionSyntax::Brackets,
+		2 => aa::SectionSyntax::BeginEnd,
+		_ => aa::SectionSyntax::Any
+	})
+	.record_boundary(match (options >> 4) & 0b11 {
+		0 => aa::RecordBoundary::None,
+		1 => aa::RecordBoundary::BlankLine,
+		_ => aa::RecordBoundary::Key("a".to_string())
+	})
+	.trailing_empty_element(match (options >> 6) & 0b11 {
+		0 => aa::TrailingEmptyElement::Keep,
+		1 => aa::TrailingEmptyElement::DropAtEof,
+		_ => aa::TrailingEmptyElement::Drop
+	})
+	.sub_seq_delimiter(b'^')
+	.max_line_length(256)
+	.max_value_size(64)
+	.max_seq_elements(16)
+	.max_keys(64);
+
+	// A tiny read buffer, so that lines and escapes get split across reads.
+	let mut de = builder.build(std::io::BufReader::with_capacity(3, input), None);
+
+	for record in de.records::<aa::Value>().take(64) {
+		let _ = record;
+	}
+
+	let _ = de.end();
+
+	let _ = aa::Map::deserialize(&mut builder.build(input, None));
+});
@@ -0,0 +1,218 @@
+//! Deserialization of ShopSite's timestamps, using the [`chrono`](https://docs.rs/chrono) library.
+//! 
+//! ShopSite writes timestamps in the American style, like `04/15/2020 13:45:00`. `chrono`'s own `Deserialize` implementations don't understand that, so this module provides functions for use with Serde's `with` attribute instead:
+//! 
+//! ```
+//! use chrono::{DateTime, NaiveDate, Utc};
+//! use serde::Deserialize;
+//! 
+//! #[derive(Deserialize)]
+//! struct Order {
+//!     #[serde(with = "shopsite_aa::datetime")]
+//!     placed: DateTime<Utc>,
+//! 
+//!     #[serde(with = "shopsite_aa::datetime::option")]
+//!     shipped: Option<DateTime<Utc>>,
+//! 
+//!     #[serde(with = "shopsite_aa::datetime::date")]
+//!     ship_by: NaiveDate
+//! }
+//! ```
+//! 
+//! ShopSite's timestamps have no time zone. They are in whatever time zone the store's server uses, which this library has no way of knowing, so they are taken to be UTC. If that's wrong for your store, deserialize into `NaiveDateTime` using the `naive` module and apply the correct time zone yourself.
+//! 
+//! Several variations are accepted, in addition to `MM/DD/YYYY HH:MM:SS`:
+//! 
+//! * Seconds may be omitted, as in `MM/DD/YYYY HH:MM`.
+//! * The time may be omitted entirely, as in `MM/DD/YYYY`, in which case it is taken to be midnight.
+//! * ISO 8601-ish `YYYY-MM-DD HH:MM:SS` and `YYYY-MM-DD` are also accepted.
+//! * Months, days, and hours need not have a leading zero.
+
+use chrono::{DateTime, NaiveDate, NaiveDateTime, NaiveTime, Utc};
+use serde::{
+	de::{self, Deserializer, Visitor},
+	ser::Serializer
+};
+use std::{
+	fmt::{self, Formatter},
+	marker::PhantomData
+};
+
+/// Format in which timestamps are serialized.
+pub const FORMAT: &str = "%m/%d/%Y %H:%M:%S";
+
+/// Format in which dates are serialized.
+pub const DATE_FORMAT: &str = "%m/%d/%Y";
+
+/// Formats with both a date and time, in the order they are tried.
+const DATE_TIME_FORMATS: &[&str] = &[
+	FORMAT,
+	"%m/%d/%Y %H:%M",
+	"%Y-%m-%d %H:%M:%S",
+	"%Y-%m-%dT%H:%M:%S"
+];
+
+/// Formats with only a date, in the order they are tried.
+const DATE_FORMATS: &[&str] = &[
+	DATE_FORMAT,
+	"%Y-%m-%d"
+];
+
+/// Parses a timestamp in any of the formats described in the module documentation.
+pub fn parse(s: &str) -> Option<NaiveDateTime> {
+	let s = s.trim();
+
+	DATE_TIME_FORMATS.iter()
+	.find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+	.or_else(|| parse_date(s).map(|date| date.and_time(NaiveTime::MIN)))
+}
+
+/// Parses a date without a time, in any of the formats described in the module documentation.
+pub fn parse_date(s: &str) -> Option<NaiveDate> {
+	let s = s.trim();
+
+	DATE_FORMATS.iter()
+	.find_map(|format| NaiveDate::parse_from_str(s, format).ok())
+}
+
+/// Visitor that parses a string using some parsing function. If `OPTIONAL`, then empty strings and missing values are `None`.
+struct ParseVisitor<T, const OPTIONAL: bool> {
+	parse: fn(&str) -> Option<T>,
+	expecting: &'static str,
+	_t: PhantomData<T>
+}
+
+impl<'de, T, const OPTIONAL: bool> Visitor<'de> for ParseVisitor<T, OPTIONAL> {
+	type Value = Option<T>;
+
+	fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+		f.write_str(self.expecting)
+	}
+
+	fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+		if OPTIONAL && v.trim().is_empty() {
+			Ok(None)
+		}
+		else {
+			(self.parse)(v)
+			.map(Some)
+			.ok_or_else(|| E::invalid_value(de::Unexpected::Str(v), &self))
+		}
+	}
+
+	fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+		if OPTIONAL {
+			Ok(None)
+		}
+		else {
+			Err(E::invalid_type(de::Unexpected::Option, &self))
+		}
+	}
+
+	fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+		if OPTIONAL {
+			Ok(None)
+		}
+		else {
+			Err(E::invalid_type(de::Unexpected::Unit, &self))
+		}
+	}
+
+	fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+		deserializer.deserialize_str(self)
+	}
+}
+
+fn deserialize_with<'de, D: Deserializer<'de>, T, const OPTIONAL: bool>(deserializer: D, parse: fn(&str) -> Option<T>, expecting: &'static str) -> Result<Option<T>, D::Error> {
+	let visitor = ParseVisitor::<T, OPTIONAL> { parse, expecting, _t: PhantomData };
+
+	if OPTIONAL {
+		deserializer.deserialize_option(visitor)
+	}
+	else {
+		deserializer.deserialize_str(visitor)
+	}
+}
+
+fn parse_utc(s: &str) -> Option<DateTime<Utc>> {
+	parse(s).map(|dt| dt.and_utc())
+}
+
+const EXPECTING_TIMESTAMP: &str = "a timestamp like `MM/DD/YYYY HH:MM:SS`";
+const EXPECTING_DATE: &str = "a date like `MM/DD/YYYY`";
+
+/// Deserializes a timestamp as a `DateTime<Utc>`.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<DateTime<Utc>, D::Error> {
+	deserialize_with::<_, _, false>(deserializer, parse_utc, EXPECTING_TIMESTAMP).map(Option::unwrap)
+}
+
+/// Serializes a `DateTime<Utc>` as a timestamp, in the same format ShopSite uses.
+pub fn serialize<S: Serializer>(dt: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+	serializer.collect_str(&dt.format(FORMAT))
+}
+
+/// Like the parent module, but for `Option<DateTime<Utc>>`. Empty values are `None`.
+pub mod option {
+	use super::*;
+
+	/// Deserializes an optional timestamp as an `Option<DateTime<Utc>>`.
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<DateTime<Utc>>, D::Error> {
+		deserialize_with::<_, _, true>(deserializer, parse_utc, EXPECTING_TIMESTAMP)
+	}
+
+	/// Serializes an `Option<DateTime<Utc>>` as a timestamp, or as nothing if it is `None`.
+	pub fn serialize<S: Serializer>(dt: &Option<DateTime<Utc>>, serializer: S) -> Result<S::Ok, S::Error> {
+		match dt {
+			Some(dt) => super::serialize(dt, serializer),
+			None => serializer.serialize_none()
+		}
+	}
+}
+
+/// Like the parent module, but for timestamps without a time zone, as `NaiveDateTime`.
+pub mod naive {
+	use super::*;
+
+	/// Deserializes a timestamp as a `NaiveDateTime`.
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDateTime, D::Error> {
+		deserialize_with::<_, _, false>(deserializer, parse, EXPECTING_TIMESTAMP).map(Option::unwrap)
+	}
+
+	/// Serializes a `NaiveDateTime` as a timestamp, in the same format ShopSite uses.
+	pub fn serialize<S: Serializer>(dt: &NaiveDateTime, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(&dt.format(FORMAT))
+	}
+}
+
+/// Like the parent module, but for dates without a time, as `NaiveDate`.
+pub mod date {
+	use super::*;
+
+	/// Deserializes a date as a `NaiveDate`.
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<NaiveDate, D::Error> {
+		deserialize_with::<_, _, false>(deserializer, parse_date, EXPECTING_DATE).map(Option::unwrap)
+	}
+
+	/// Serializes a `NaiveDate` as a date, in the same format ShopSite uses.
+	pub fn serialize<S: Serializer>(date: &NaiveDate, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_str(&date.format(DATE_FORMAT))
+	}
+
+	/// Like the parent module, but for `Option<NaiveDate>`. Empty values are `None`.
+	pub mod option {
+		use super::*;
+
+		/// Deserializes an optional date as an `Option<NaiveDate>`.
+		pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<NaiveDate>, D::Error> {
+			deserialize_with::<_, _, true>(deserializer, parse_date, EXPECTING_DATE)
+		}
+
+		/// Serializes an `Option<NaiveDate>` as a date, or as nothing if it is `None`.
+		pub fn serialize<S: Serializer>(date: &Option<NaiveDate>, serializer: S) -> Result<S::Ok, S::Error> {
+			match date {
+				Some(date) => super::serialize(date, serializer),
+				None => serializer.serialize_none()
+			}
+		}
+	}
+}
@@ -7,17 +7,27 @@
 //! * Skip over lines containing only whitespace characters
 //! * Allow comments to begin after any number of whitespace characters
 //! * Understand `:` delimiters that are not followed by a space character
+//! * Skip a UTF-8 byte order mark at the beginning of the file, unless told to reject it (see `Bom`)
 //! 
 //! ShopSite itself may or may not be so forgiving. This parser is not designed to be used as a validator.
 //! 
 //! In other words, just because this parser doesn't reject or misunderstand a `.aa` file doesn't mean ShopSite won't reject or misunderstand it!
+//! 
+//! # Keys in Order
+//! 
+//! The top level of a file is a map, but maps keep only one value for each key, and most don't keep the keys in order either. To get every entry just as it appears in the file, deserialize the top level as a sequence of key-value pairs instead, like `Vec<(String, Value)>`. If the file is divided into sections, each pair is a section name and the map of that section's keys. For files too big to hold in memory all at once, see `Deserializer::entries`.
+//! 
+//! # Untrusted Input
+//! 
+//! The deserializer never panics, no matter what bytes it's given. Malformed input results in an `Error`, never a crash. A panic on any input is a bug. This is checked by the fuzz target in `fuzz/`, which can be run with [`cargo-fuzz`](https://github.com/rust-fuzz/cargo-fuzz), and, less thoroughly, by `test_arbitrary_input` in the tests. To guard against input that's merely huge, rather than malformed, see the limits in `DeserializerBuilder`.
 
 use serde::de::Deserialize;
 use std::{
 	fs::File,
-	io::{self, BufRead, BufReader},
+	io::{BufRead, BufReader, Read},
+	mem,
 	path::Path,
-	rc::Rc
+	sync::Arc
 };
 
 mod position;
@@ -30,17 +40,75 @@ mod parser_io;
 use parser_io::*;
 
 mod deser_toplevel;
+use deser_toplevel::*;
+
+mod entries;
+pub use entries::*;
+
+mod extract;
+pub use extract::*;
+
+mod raw_value;
+pub use raw_value::*;
+
+mod os_string;
+
+mod checkpoint;
+use checkpoint::*;
+
+mod input_encoding;
+pub use input_encoding::*;
+
+mod line_ending;
+pub use line_ending::*;
+
+mod detect;
+pub use detect::*;
+
+#[cfg(feature = "compression")]
+mod compression;
+#[cfg(feature = "compression")]
+pub use compression::*;
+
+#[cfg(feature = "parallel")]
+mod parallel;
+#[cfg(feature = "parallel")]
+pub use parallel::*;
+
+mod punctuation;
+pub use punctuation::*;
+
+mod records;
+pub use records::*;
+
+mod sections;
+
+mod known_fields;
+use known_fields::*;
 
 mod deser_value;
 use deser_value::*;
 
-pub struct Deserializer<R: BufRead> {
+mod builder;
+pub use builder::*;
+
+mod preset;
+pub use preset::*;
+
+pub use crate::{
+	diff::{diff, diff_split, Change},
+	document::{patch_file, Document},
+	merge::{merge, Conflict, Merged},
+	value::{from_value, to_value, Map, Value}
+};
+
+pub struct Deserializer<R> {
 	/// Source of input bytes.
 	reader: R,
 
 	/// Buffer of bytes read from the input source for the current line.
 	/// 
-	/// Parsing occurs at the byte level, since this format is always Windows-1252 and it's faster and simpler to parse byte-by-byte without dealing with UTF-8's variable-width characters.
+	/// Parsing occurs at the byte level, since the delimiters and escapes are all ASCII, and it's faster and simpler to parse byte-by-byte without dealing with variable-width characters. Bytes are only decoded, according to the `InputEncoding`, once a key or value has been found.
 	buf_b: Vec<u8>,
 
 	/// Buffer of decoded text from the input source.
@@ -54,48 +122,276 @@ pub struct Deserializer<R: BufRead> {
 	/// The last byte that was read.
 	last_byte: u8,
 
-	/// The next byte that will be read.
+	/// Initially `false`. Set to true upon reaching end-of-file.
+	reached_eof: bool,
+
+	/// The delimiter that ended the last sequence element read, or `None` if it was ended by a line ending or end-of-file.
+	last_delim: Option<u8>,
+
+	/// Line, column, and byte column where the last call to `fill_buf` stopped reading. That is, the position of the delimiter or line ending that it found, or the end of the file.
+	fill_end: (u32, u32, u32),
+
+	/// Set by `fill_buf` when it skips a blank line. Used to find the boundaries between records.
+	saw_blank_line: bool,
+
+	/// If a key has been read but not yet handed to a visitor, this is `Some`, and the key is in `buf_s`. The `bool` is `true` if the key has no value.
 	/// 
-	/// This is set to `Some` when `peek_byte` is called. When `read_byte` is called, it will first return this byte before reading any more from the reader.
-	peeked_byte: Option<u8>,
+	/// This happens when the key turns out to be the first key of the next record, in which case it's left here for the next record to start with.
+	pending_key: Option<bool>,
 
-	/// Initially `false`. Set to true upon reaching end-of-file.
-	reached_eof: bool
+	/// The line that the last key handed to a visitor was on, or 0 if there hasn't been one yet. Used to say where a missing field was noticed missing; see `Error::MissingField`.
+	last_key_line: u32,
+
+	/// How many keys have been read so far. Used to enforce `DeserializerBuilder::max_keys`.
+	keys_read: usize,
+
+	/// How many bytes have been consumed from `reader` so far. See `Deserializer::bytes_read`.
+	bytes_read: u64,
+
+	/// Which line endings have been read so far. See `Deserializer::line_ending`.
+	line_endings_seen: LineEndingsSeen,
+
+	/// The delimiter between elements of sequences in the value of the current key. Usually `|`, but see `DeserializerBuilder::seq_delimiter_override`.
+	seq_delimiter: u8,
+
+	/// Bytes that were read while there was a mark, and then rewound over, or that were taken out of `reader`'s buffer early, to see more of them at once than it had (see `buffer_more`). These are read before anything more is read from `reader`. See `Deserializer::rewind`.
+	replay: Vec<u8>,
+
+	/// How many bytes of `replay` have been read again so far.
+	replay_pos: usize,
+
+	/// Where `Deserializer::mark` was called, and what has been read since, if it has been.
+	mark: Option<Box<Mark>>,
+
+	/// The fields of the struct most recently deserialized with unknown keys skipped, kept for the next one, which is usually the next record of the same type.
+	known_fields: Option<Arc<KnownFields>>,
+
+	/// Options controlling how the input is interpreted. See `DeserializerBuilder`.
+	config: Config
 }
 
 impl<R: BufRead> Deserializer<R> {
-	pub fn new(reader: R, file: Option<Rc<Path>>) -> Deserializer<R> {
+	/// Creates a new `Deserializer` with the default options. To change the options, use `DeserializerBuilder` instead.
+	/// 
+	/// The reader must be buffered. If it isn't, use `Deserializer::from_read` instead.
+	pub fn new(reader: R, file: Option<Arc<Path>>) -> Deserializer<R> {
+		Deserializer::with_config(reader, file, Config::default())
+	}
+
+	/// Creates a new `Deserializer` with the default options, whose buffers start out with room for `capacity` bytes, instead of the usual 4 KiB. Lines longer than that still work; the buffers grow to fit. Make the buffers big enough for the longest lines in your files, such as long product descriptions, to avoid growing them.
+	/// 
+	/// To use other options, see `DeserializerBuilder::buffer_capacity`.
+	pub fn with_capacity(reader: R, file: Option<Arc<Path>>, capacity: usize) -> Deserializer<R> {
+		Deserializer::with_config(reader, file, Config {
+			buffer_capacity: capacity,
+			..Config::default()
+		})
+	}
+
+	fn with_config(reader: R, file: Option<Arc<Path>>, config: Config) -> Deserializer<R> {
+		let capacity = config.buffer_capacity;
+		Deserializer::from_parts(reader, file, config, Vec::with_capacity(capacity), String::with_capacity(capacity))
+	}
+}
+
+impl<R> Deserializer<R> {
+	/// Creates a `Deserializer` that's ready to start parsing, with the given buffers, which must be empty.
+	fn from_parts(reader: R, file: Option<Arc<Path>>, config: Config, buf_b: Vec<u8>, buf_s: String) -> Deserializer<R> {
 		Deserializer {
 			reader,
 			pos: Position {
-				file: file.into(),
+				file,
 				line: 1,
-				column: 1
+				column: 1,
+				byte_column: 1,
+				key: None
 			},
-			buf_b: Vec::with_capacity(4096),
-			buf_s: String::with_capacity(4096),
+			buf_b,
+			buf_s,
 			last_byte: 0,
-			peeked_byte: None,
-			reached_eof: false
+			reached_eof: false,
+			last_delim: None,
+			fill_end: (1, 1, 1),
+			saw_blank_line: false,
+			pending_key: None,
+			last_key_line: 0,
+			keys_read: 0,
+			bytes_read: 0,
+			line_endings_seen: LineEndingsSeen::default(),
+			seq_delimiter: b'|',
+			replay: Vec::new(),
+			replay_pos: 0,
+			mark: None,
+			known_fields: None,
+			config
+		}
+	}
+
+	/// Starts parsing a new input, with the same options, as if this were a brand new `Deserializer`. Returns the old reader.
+	/// 
+	/// The buffers are kept, along with whatever room they've grown to have, so a long-running service that parses many files can use one `Deserializer` for all of them instead of allocating new buffers for each.
+	pub fn reset(&mut self, reader: R, file: Option<Arc<Path>>) -> R {
+		let mut buf_b = mem::take(&mut self.buf_b);
+		buf_b.clear();
+
+		let mut buf_s = mem::take(&mut self.buf_s);
+		buf_s.clear();
+
+		let config = mem::take(&mut self.config);
+		mem::replace(self, Deserializer::from_parts(reader, file, config, buf_b, buf_s)).reader
+	}
+}
+
+impl<R: BufRead> Deserializer<R> {
+	/// Checks that there is nothing left in the input but blank lines and comments. Call this after deserializing, to detect garbage after the data that was asked for.
+	/// 
+	/// Deserializing a map reads all the way to the end of the file anyway, so this only ever finds anything when the input is divided into records (see `DeserializerBuilder::record_boundary`), in which case it finds the next record, if any.
+	/// 
+	/// # Errors
+	/// 
+	/// Fails with `Error::UnexpectedText` if there is another key in the input. The position is that of the line the key is on.
+	pub fn end(&mut self) -> Result<()> {
+		if self.pending_key.is_some() || self.read_key_undecoded()? {
+			Err(Error::UnexpectedText {
+				pos: Position {
+					file: self.pos.file.clone(),
+					line: self.fill_end.0,
+					column: 1,
+					byte_column: 1,
+					key: None
+				}
+			})
+		}
+		else {
+			Ok(())
+		}
+	}
+}
+
+impl<R> Deserializer<R> {
+	/// Unwraps this `Deserializer`, returning the underlying reader.
+	/// 
+	/// The reader is left just after the last byte that was parsed, so it can be used to read whatever comes after the data. Note that the parser reads a key ahead when looking for the end of a record, so if the input is divided into records, the first key of the next record has already been consumed. Use `end` first to make sure there isn't one.
+	/// 
+	/// If `rewind` was used, and some of the input that was rewound over hasn't been read again yet, that input is lost.
+	pub fn into_inner(self) -> R {
+		self.reader
+	}
+
+	/// Returns the number of bytes of input that have been parsed so far. This counts from wherever the reader was when the `Deserializer` was created, so it's usually the offset into the file.
+	/// 
+	/// Along with the file's size, this can be used to show the progress of a long-running conversion, like that of a big store's product database. It's cheap to call, so calling it after each record is fine.
+	pub fn bytes_read(&self) -> u64 {
+		self.bytes_read
+	}
+
+	/// Returns which line endings the input uses, or `None` if the parser hasn't come to any yet. Once the whole input has been parsed, this is what the whole input uses; before then, it's only what has been parsed so far.
+	/// 
+	/// The parser treats all line endings the same, but ShopSite writes `\r\n`, and a program that edits a file written on one system and then writes it back out from another can use this to keep from changing every line of it. See `LineEnding::as_str`.
+	pub fn line_ending(&self) -> Option<LineEnding> {
+		self.line_endings_seen.line_ending()
+	}
+
+	/// Returns the position in the input where the parser is currently looking. This is the line and column of the next byte to be parsed.
+	pub fn position(&self) -> &Position {
+		&self.pos
+	}
+
+	/// Gives an `Error::MissingField` a position, if it doesn't already have one. Serde reports missing fields without any, after the visitor has run out of keys, so this is done on the way out of `visit_map`. The position is that of the last key read; if the error already has one, it came from a map nested in this one, and is more precise.
+	fn locate_missing_field(&self, error: Error) -> Error {
+		match error {
+			Error::MissingField { field, pos: None } => Error::MissingField {
+				field,
+				pos: Some(Position {
+					file: self.pos.file.clone(),
+					line: if self.last_key_line != 0 { self.last_key_line } else { self.pos.line },
+					column: 1,
+					byte_column: 1,
+					key: None
+				})
+			},
+			error => error
+		}
+	}
+
+	/// Returns an `Error::LimitExceeded` if `count` exceeds `max`.
+	fn check_limit(&self, limit: Limit, max: Option<usize>, count: usize) -> Result<()> {
+		match max {
+			Some(max) if count > max => Err(Error::LimitExceeded {
+				limit,
+				max,
+				pos: self.pos.clone()
+			}),
+			_ => Ok(())
+		}
+	}
+}
+
+/// Reports a failed result as a `tracing` event, if the `trace` feature is enabled. Either way, the result is returned unchanged.
+fn trace_result<T>(result: Result<T>) -> Result<T> {
+	#[cfg(feature = "trace")]
+	{
+		if let Err(error) = &result {
+			tracing::debug!(%error, "deserialization failed");
 		}
 	}
+
+	result
+}
+
+impl<R: Read> Deserializer<BufReader<R>> {
+	/// Creates a new `Deserializer` with the default options, reading from an unbuffered reader, such as a network stream or a decompressor. The input is buffered internally.
+	pub fn from_read(reader: R, file: Option<Arc<Path>>) -> Deserializer<BufReader<R>> {
+		Deserializer::new(BufReader::new(reader), file)
+	}
+}
+
+/// Deserializes a value from a reader. The input is buffered internally, so the reader doesn't need to be, but if it already is, it's a bit faster to use `T::deserialize(&mut Deserializer::new(reader, path))`.
+/// 
+/// With the `compression` feature, input compressed with gzip or Zstandard is decompressed first. See `decompress`.
+pub fn from_reader<'de, T: Deserialize<'de>, R: Read>(reader: R, path: Option<Arc<Path>>) -> Result<T> {
+	#[cfg(feature = "compression")]
+	return match decompress(BufReader::new(reader)) {
+		Ok(reader) => from_buf_reader(reader, path),
+		Err(error) => Err(Error::Io { error, file: path, pos: None })
+	};
+
+	#[cfg(not(feature = "compression"))]
+	from_buf_reader(BufReader::new(reader), path)
 }
 
-pub fn from_reader<'de, T: Deserialize<'de>, R: BufRead>(reader: R, path: Option<Rc<Path>>) -> Result<T> {
+/// Deserializes a value from a reader that is already buffered.
+fn from_buf_reader<'de, T: Deserialize<'de>, R: BufRead>(reader: R, path: Option<Arc<Path>>) -> Result<T> {
 	let mut deserializer = Deserializer::new(reader, path);
 	let result = T::deserialize(&mut deserializer)?;
 	Ok(result)
 }
 
-pub fn from_bytes<'de, T: Deserialize<'de>>(bytes: &[u8], file: Option<Rc<Path>>) -> Result<T> {
-	from_reader(io::Cursor::new(bytes), file)
+pub fn from_bytes<'de, T: Deserialize<'de>>(bytes: &[u8], file: Option<Arc<Path>>) -> Result<T> {
+	from_buf_reader(bytes, file)
 }
 
-pub fn from_file<'de, T: Deserialize<'de>>(file: Rc<Path>) -> Result<T> {
-	let file = file.into();
-
+/// Deserializes a value from a file. With the `compression` feature, a compressed file, like a `.aa.gz` backup, is decompressed as it's read, as with `from_reader`.
+pub fn from_file<'de, T: Deserialize<'de>>(file: Arc<Path>) -> Result<T> {
 	match File::open(&file) {
-		Ok(fh) => from_reader(BufReader::new(fh), Some(file)),
-		Err(error) => Err(Error::Io { error, file: Some(file) })
+		Ok(fh) => from_reader(fh, Some(file)),
+		Err(error) => Err(Error::Io { error, file: Some(file), pos: None })
+	}
+}
+
+/// Like `from_file`, but maps the file into memory and parses it from there, instead of reading it a piece at a time. For large files, like a big store's product database, this saves a lot of copying and system calls.
+/// 
+/// The parsed values are still copied out of the file, so the type being deserialized can't borrow from it. (Values have to be decoded from the input encoding anyway, so there'd be little to borrow.)
+/// 
+/// # Safety
+/// 
+/// The file must not be modified or truncated, by this or any other process, until this function returns. If it is, the behavior is undefined. ShopSite writes its files in place, so don't use this on the files of a running store. See `memmap2::Mmap` for details.
+#[cfg(feature = "mmap")]
+pub unsafe fn from_mmap<T: serde::de::DeserializeOwned>(file: Arc<Path>) -> Result<T> {
+	let map = File::open(&file).and_then(|fh| memmap2::Mmap::map(&fh));
+
+	match map {
+		Ok(map) => from_buf_reader(&map[..], Some(file)),
+		Err(error) => Err(Error::Io { error, file: Some(file), pos: None })
 	}
 }
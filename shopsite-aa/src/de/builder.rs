@@ -0,0 +1,606 @@
+use std::{
+	borrow::Cow,
+	io::{BufRead, BufReader, Read},
+	path::Path,
+	sync::Arc,
+	str::{FromStr, ParseBoolError}
+};
+use super::{Deserializer, InputEncoding, Map, Preset, Punctuation};
+
+/// How boolean values are spelled.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum BoolSyntax {
+	/// Only `true` and `false` are accepted, exactly as with `FromStr for bool`. This is the default.
+	#[default]
+	Strict,
+
+	/// In addition to `true` and `false`, accept the spellings that ShopSite itself uses: `yes`/`no`, `on`/`off`, `checked`/`unchecked`, and `1`/`0`. Letter case is ignored.
+	Lenient
+}
+
+impl BoolSyntax {
+	/// Parses a boolean value according to this syntax.
+	pub fn parse(self, s: &str) -> std::result::Result<bool, ParseBoolError> {
+		if self == BoolSyntax::Lenient {
+			const TRUES: &[&str] = &["true", "yes", "on", "checked", "1"];
+			const FALSES: &[&str] = &["false", "no", "off", "unchecked", "0"];
+
+			if TRUES.iter().any(|t| t.eq_ignore_ascii_case(s)) {
+				return Ok(true)
+			}
+			else if FALSES.iter().any(|f| f.eq_ignore_ascii_case(s)) {
+				return Ok(false)
+			}
+		}
+
+		// Either we're in strict mode, or none of the lenient spellings matched. Either way, let the standard library have a go at it. In the latter case, this is just to get a `ParseBoolError`, which can't be constructed any other way.
+		bool::from_str(s)
+	}
+}
+
+/// How numeric values are spelled.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum NumberSyntax {
+	/// Numbers must be written exactly as `FromStr` for the target numeric type expects. This is the default.
+	#[default]
+	Strict,
+
+	/// Before parsing, strip surrounding whitespace, a leading `$` sign, and `,` thousands separators. This lets price fields like `$1,234.50` be deserialized as numbers. Formatting anywhere else, like `1,50` or `$1$2`, is left in place, so the number fails to parse.
+	Lenient
+}
+
+impl NumberSyntax {
+	/// Prepares a numeric value for parsing according to this syntax.
+	/// 
+	/// In strict mode, this returns `s` unchanged. In lenient mode, this returns `s` with currency formatting removed, allocating a new string only if there is formatting to remove. If the formatting isn't well-formed, `s` is returned with only the surrounding whitespace removed.
+	pub fn clean(self, s: &str) -> Cow<'_, str> {
+		if self == NumberSyntax::Lenient {
+			let s = s.trim();
+
+			if s.contains(['$', ',']) {
+				clean_currency(s).map_or(Cow::Borrowed(s), Cow::Owned)
+			}
+			else {
+				Cow::Borrowed(s)
+			}
+		}
+		else {
+			Cow::Borrowed(s)
+		}
+	}
+}
+
+/// Removes a `$` sign (after the sign of the number, if any) and `,` thousands separators from `s`, or returns `None` if they're anywhere else.
+fn clean_currency(s: &str) -> Option<String> {
+	let (sign, rest) = match s.strip_prefix(['+', '-']) {
+		Some(rest) => (&s[..1], rest),
+		None => ("", s)
+	};
+
+	// Whitespace between the `$` and the digits is surrounding whitespace too, for our purposes.
+	let rest = rest.strip_prefix('$').map_or(rest, str::trim_start);
+
+	let (whole, fraction) = match rest.find('.') {
+		Some(point) => rest.split_at(point),
+		None => (rest, "")
+	};
+
+	if rest.contains('$') || fraction.contains(',') {
+		return None
+	}
+
+	let mut groups = whole.split(',');
+	let first = groups.next().unwrap_or_default();
+	let is_digits = |group: &str| group.bytes().all(|byte| byte.is_ascii_digit());
+
+	if whole.contains(',') && !(matches!(first.len(), 1..=3) && is_digits(first)) {
+		return None
+	}
+
+	if !groups.all(|group| group.len() == 3 && is_digits(group)) {
+		return None
+	}
+
+	let mut cleaned = String::with_capacity(s.len());
+	cleaned.push_str(sign);
+	cleaned.extend(whole.split(','));
+	cleaned.push_str(fraction);
+	Some(cleaned)
+}
+
+#[test]
+fn test_number_syntax_clean() {
+	let cases: &[(&str, &str)] = &[
+		(" $1,234.50 ", "1234.50"),
+		("$ 999.99", "999.99"),
+		("-$5", "-5"),
+		("1,000", "1000"),
+		("12,345,678", "12345678"),
+		("1,50", "1,50"),
+		("1,2,3", "1,2,3"),
+		("$1$2", "$1$2"),
+		("5$", "5$"),
+		("1,000.000,1", "1,000.000,1"),
+		(",100", ",100"),
+		("1234,567", "1234,567")
+	];
+
+	for (input, expected) in cases {
+		assert_eq!(NumberSyntax::Lenient.clean(input), *expected, "{:?}", input);
+	}
+
+	assert!(NumberSyntax::Lenient.clean("1,50").parse::<f64>().is_err());
+	assert!(NumberSyntax::Lenient.clean("1,2,3").parse::<f64>().is_err());
+	assert_eq!(NumberSyntax::Strict.clean(" $1 "), " $1 ");
+}
+
+/// How a `|` character that is part of a sequence element, rather than a separator between elements, is written.
+/// 
+/// Escapes are only recognized in values that are being deserialized as sequences (or tuples, etc). Other values are always taken literally, since that's how ShopSite writes them. In particular, Windows paths with backslashes in them are not mangled.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SeqEscape {
+	/// There is no way to write a `|` in a sequence element. Every `|` separates elements. This is the default.
+	#[default]
+	None,
+
+	/// A backslash before a `|` makes it part of the element, and a doubled backslash stands for a single backslash. Backslashes before any other character are taken literally.
+	Backslash,
+
+	/// The HTML character references `&#124;` and `&#x7C;` (or `&#x7c;`) stand for a `|` in an element.
+	NumericEntity
+}
+
+impl SeqEscape {
+	/// The byte that escapes the next byte, if this kind of escaping has such a thing.
+	pub(super) fn escape_byte(self) -> Option<u8> {
+		match self {
+			SeqEscape::Backslash => Some(b'\\'),
+			_ => None
+		}
+	}
+}
+
+/// How values are handed over when their type isn't known ahead of time, such as when deserializing into a `Value` or transcoding to another format with `serde_transcode`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum UntypedValue {
+	/// Values are decoded from the input encoding (Windows-1252 by default) and handed over as text. This is the default.
+	#[default]
+	Text,
+
+	/// Values are handed over as the bytes they consist of in the file, without decoding. This is for when the recipient needs to reproduce the original bytes exactly, whatever they are.
+	Bytes
+}
+
+/// How values are handed over when deserializing into an `OsString`, or into a `PathBuf` with the `path` module.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum OsStringValue {
+	/// Values are decoded from the input encoding (Windows-1252 by default), the same as they would be for a `String`. This is the default.
+	#[default]
+	Text,
+
+	/// On Unix, values are handed over as the bytes they consist of in the file, without decoding, so that a file path that isn't valid text comes out exactly as it went in. On Windows, strings are UTF-16, so this has no effect; values are decoded as with `Text`.
+	Bytes
+}
+
+/// What a key with no value (a line with no `:` on it, like `showcart`) means.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum KeyOnlyLine {
+	/// The value is `()`. It comes out as `None` for an `Option` and `Value::Unit` for a `Value`, and doesn't fit most other types. This is the default.
+	#[default]
+	UnitValue,
+
+	/// The value is empty text, the same as for `showcart:`.
+	EmptyString,
+
+	/// The value is the text `true`, so that the key's presence can be deserialized as a `bool`, like a command-line flag. (Its absence is not `false`, though, so give the field `#[serde(default)]`.)
+	True,
+
+	/// Fail with `Error::MissingValue`.
+	Error,
+
+	/// Skip the key, as if it weren't there.
+	Skip
+}
+
+/// What separates one record from the next, in files that contain more than one record, like ShopSite's dumps of its product and page databases. See `Deserializer::records`.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum RecordBoundary {
+	/// The whole file is one record. This is the default.
+	#[default]
+	None,
+
+	/// Records are separated by one or more blank lines (or lines containing only whitespace). Comment lines don't count.
+	BlankLine,
+
+	/// Each record begins with the given key. That is, whenever this key appears, a new record starts, unless the current record has nothing in it yet.
+	Key(String)
+}
+
+/// What to do with the empty element after a `|` at the very end of a sequence, as in `a|b|`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum TrailingEmptyElement {
+	/// The empty element is part of the sequence, so `a|b|` is `["a", "b", ""]`. This is the default.
+	#[default]
+	Keep,
+
+	/// The empty element is dropped if the sequence is at the very end of the file, with no line ending after it. This tends to happen with files written by hand (or by a program that is sloppy about line endings), but not with ShopSite's own exports.
+	DropAtEof,
+
+	/// The empty element is always dropped, so `a|b|` is `["a", "b"]`.
+	Drop
+}
+
+/// How the beginnings and ends of sections are marked, in files that are divided into sections.
+/// 
+/// When sections are recognized, the file is deserialized as a map of section names to maps of the keys in each section. Keys before the first section are in a section whose name is empty.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum SectionSyntax {
+	/// The file is not divided into sections. This is the default.
+	#[default]
+	None,
+
+	/// Sections begin with a line like `[SectionName]`, and end where the next one begins.
+	Brackets,
+
+	/// Sections begin with a line like `BEGIN_SectionName` and end with a line like `END_SectionName`, as in some of ShopSite's files. Keys between the end of one section and the beginning of the next are in the section whose name is empty, but there should only be one of those per file, so this is best avoided.
+	BeginEnd,
+
+	/// Both of the above are recognized.
+	Any
+}
+
+/// What to do with a UTF-8 byte order mark at the beginning of the input. Some Windows tools put one at the beginning of every file they save as UTF-8, even `.aa` files.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Bom {
+	/// Skip it, whatever the input's encoding is. This is the default. If the input is read as Windows-1252, the rest of it is probably UTF-8, so consider `InputEncoding::Utf8` or `detect_encoding`.
+	#[default]
+	Skip,
+
+	/// Fail with `Error::UnexpectedBom`, since ShopSite doesn't write byte order marks, so a file that has one has been through something other than ShopSite.
+	Reject
+}
+
+/// What to do with HTML character references, like `&amp;` and `&#8220;`, in decoded values. ShopSite stores some text, like product names, the way it's written in the store's HTML, entities and all.
+#[cfg(feature = "html-entities")]
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum HtmlEntities {
+	/// Leave them as they are. This is the default.
+	#[default]
+	Keep,
+
+	/// Decode them, as `helpers::decode_html_entities` does, so that `&amp;` becomes `&`.
+	Decode
+}
+
+#[cfg(feature = "html-entities")]
+impl HtmlEntities {
+	/// Decodes the character references in `s`, in place, if this says to. Text without an `&` in it is left alone without being copied.
+	pub fn decode(self, s: &mut String) {
+		if self == HtmlEntities::Decode {
+			if let Cow::Owned(decoded) = crate::helpers::decode_html_entities(s) {
+				*s = decoded;
+			}
+		}
+	}
+}
+
+/// Options that control how a `Deserializer` interprets its input.
+#[derive(Clone, Debug)]
+pub(super) struct Config {
+	pub(super) encoding: InputEncoding,
+	pub(super) bom: Bom,
+	pub(super) punctuation: Punctuation,
+	#[cfg(feature = "html-entities")]
+	pub(super) html_entities: HtmlEntities,
+	pub(super) bool_syntax: BoolSyntax,
+	pub(super) number_syntax: NumberSyntax,
+	pub(super) seq_escape: SeqEscape,
+	pub(super) sub_seq_delimiter: u8,
+	pub(super) seq_delimiter_overrides: Vec<(String, u8)>,
+	pub(super) untyped_value: UntypedValue,
+	pub(super) os_string_value: OsStringValue,
+	pub(super) key_only_line: KeyOnlyLine,
+	pub(super) record_boundary: RecordBoundary,
+	pub(super) trailing_empty_element: TrailingEmptyElement,
+	pub(super) section_syntax: SectionSyntax,
+	pub(super) skip_unknown_keys: bool,
+	pub(super) tab_width: u32,
+	pub(super) max_line_length: Option<usize>,
+	pub(super) max_value_size: Option<usize>,
+	pub(super) max_seq_elements: Option<usize>,
+	pub(super) max_keys: Option<usize>,
+	pub(super) defaults: Option<Arc<Map>>,
+	pub(super) buffer_capacity: usize
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Config {
+			encoding: Default::default(),
+			bom: Default::default(),
+			punctuation: Default::default(),
+			#[cfg(feature = "html-entities")]
+			html_entities: Default::default(),
+			bool_syntax: Default::default(),
+			number_syntax: Default::default(),
+			seq_escape: Default::default(),
+			sub_seq_delimiter: b',',
+			seq_delimiter_overrides: Vec::new(),
+			untyped_value: Default::default(),
+			os_string_value: Default::default(),
+			key_only_line: Default::default(),
+			record_boundary: Default::default(),
+			trailing_empty_element: Default::default(),
+			section_syntax: Default::default(),
+			skip_unknown_keys: false,
+			tab_width: 8,
+			max_line_length: None,
+			max_value_size: None,
+			max_seq_elements: None,
+			max_keys: None,
+			defaults: None,
+			buffer_capacity: 4096
+		}
+	}
+}
+
+impl Config {
+	/// Finds the sequence delimiter to use for the values of the given key.
+	pub(super) fn seq_delimiter_for(&self, key: &str) -> u8 {
+		self.seq_delimiter_overrides.iter()
+		.find(|(pattern, _)| glob_matches(pattern.as_bytes(), key.as_bytes()))
+		.map_or(b'|', |(_, delimiter)| *delimiter)
+	}
+}
+
+/// Checks whether `text` matches the glob `pattern`, in which `*` matches any number of bytes (including none) and `?` matches any one byte. Non-ASCII characters are more than one byte in UTF-8, so `?` won't match those, but nobody puts those in their `.aa` keys anyway.
+pub(crate) fn glob_matches(pattern: &[u8], text: &[u8]) -> bool {
+	match pattern.split_first() {
+		None => text.is_empty(),
+		Some((b'*', rest)) => (0..=text.len()).any(|skip| glob_matches(rest, &text[skip..])),
+		Some((b'?', rest)) => !text.is_empty() && glob_matches(rest, &text[1..]),
+		Some((byte, rest)) => text.first() == Some(byte) && glob_matches(rest, &text[1..])
+	}
+}
+
+#[test]
+fn test_glob_matches() {
+	assert!(glob_matches(b"ProductField*", b"ProductField12"));
+	assert!(glob_matches(b"*Field?", b"ProductField1"));
+	assert!(!glob_matches(b"*Field?", b"ProductField12"));
+	assert!(glob_matches(b"*", b""));
+	assert!(!glob_matches(b"Name", b"name"));
+}
+
+/// Builds a `Deserializer` with non-default options.
+/// 
+/// `Deserializer::new` and the `from_*` functions use the default options, which are fairly strict about how values are spelled. Use this if your `.aa` files need a more forgiving touch.
+#[derive(Clone, Debug, Default)]
+pub struct DeserializerBuilder {
+	config: Config
+}
+
+impl DeserializerBuilder {
+	/// Creates a new builder with the default options.
+	pub fn new() -> DeserializerBuilder {
+		Default::default()
+	}
+
+	/// Sets all of the options that make up a `Preset`, for a common kind of `.aa` file. Options set afterward override the preset's, so call this first.
+	pub fn preset(&mut self, preset: Preset) -> &mut Self {
+		preset.apply(self);
+		self
+	}
+
+	/// Sets the character encoding of the input. The default is `InputEncoding::Windows1252`, which is what ShopSite uses. If the encoding isn't known ahead of time, `detect_encoding` can guess it.
+	pub fn encoding(&mut self, encoding: InputEncoding) -> &mut Self {
+		self.config.encoding = encoding;
+		self
+	}
+
+	/// Sets what to do with a UTF-8 byte order mark at the beginning of the input. The default is `Bom::Skip`.
+	pub fn bom(&mut self, bom: Bom) -> &mut Self {
+		self.config.bom = bom;
+		self
+	}
+
+	/// Sets what to do with typographic punctuation, like curly quotes, in decoded keys and values. The default is `Punctuation::Keep`.
+	/// 
+	/// This only applies to text. Values handed over as bytes, as with `UntypedValue::Bytes` or `RawValue`, are left as they are in the file.
+	pub fn punctuation(&mut self, punctuation: Punctuation) -> &mut Self {
+		self.config.punctuation = punctuation;
+		self
+	}
+
+	/// Sets what to do with HTML character references, like `&amp;`, in decoded values. The default is `HtmlEntities::Keep`.
+	/// 
+	/// Keys are never decoded, and neither are values handed over as bytes, as with `UntypedValue::Bytes` or `RawValue`, so a `RawValue` field still has the value as it's written in the file.
+	#[cfg(feature = "html-entities")]
+	pub fn html_entities(&mut self, html_entities: HtmlEntities) -> &mut Self {
+		self.config.html_entities = html_entities;
+		self
+	}
+
+	/// Sets which spellings of boolean values are accepted. The default is `BoolSyntax::Strict`.
+	pub fn bool_syntax(&mut self, bool_syntax: BoolSyntax) -> &mut Self {
+		self.config.bool_syntax = bool_syntax;
+		self
+	}
+
+	/// Sets which spellings of numeric values are accepted. The default is `NumberSyntax::Strict`.
+	pub fn number_syntax(&mut self, number_syntax: NumberSyntax) -> &mut Self {
+		self.config.number_syntax = number_syntax;
+		self
+	}
+
+	/// Sets how a `|` that is part of a sequence element is written. The default is `SeqEscape::None`.
+	pub fn seq_escape(&mut self, seq_escape: SeqEscape) -> &mut Self {
+		self.config.seq_escape = seq_escape;
+		self
+	}
+
+	/// Sets the character that separates the elements of a sequence inside a sequence, like the choices in each of a product's ordering options. The default is `,`.
+	/// 
+	/// This must be an ASCII character other than `|`, a line ending, or the escape character, if any. Otherwise, the results are undefined (but safe).
+	pub fn sub_seq_delimiter(&mut self, delimiter: u8) -> &mut Self {
+		self.config.sub_seq_delimiter = delimiter;
+		self
+	}
+
+	/// Makes sequences in the values of keys matching `key_glob` use `delimiter` to separate their elements, instead of `|`. In `key_glob`, `*` matches any number of characters and `?` matches any one character. Key matching is case sensitive.
+	/// 
+	/// This can be called more than once, to override the delimiter for several keys. If a key matches more than one glob, the one given first wins.
+	/// 
+	/// The same caveats apply as for `sub_seq_delimiter`. In addition, if a key's delimiter is the same as the `sub_seq_delimiter`, then nested sequences in that key's value won't work.
+	pub fn seq_delimiter_override(&mut self, key_glob: impl Into<String>, delimiter: u8) -> &mut Self {
+		self.config.seq_delimiter_overrides.push((key_glob.into(), delimiter));
+		self
+	}
+
+	/// Sets how values are handed over when their type isn't known ahead of time. The default is `UntypedValue::Text`.
+	/// 
+	/// This only affects values. Keys are always text.
+	pub fn untyped_value(&mut self, untyped_value: UntypedValue) -> &mut Self {
+		self.config.untyped_value = untyped_value;
+		self
+	}
+
+	/// Sets how values are handed over when deserializing into an `OsString` or `PathBuf`. The default is `OsStringValue::Text`.
+	pub fn os_string_value(&mut self, os_string_value: OsStringValue) -> &mut Self {
+		self.config.os_string_value = os_string_value;
+		self
+	}
+
+	/// Sets what a key with no value means. The default is `KeyOnlyLine::UnitValue`.
+	/// 
+	/// This doesn't apply to section markers, which are keys with no value too, when sections are recognized.
+	pub fn key_only_line(&mut self, key_only_line: KeyOnlyLine) -> &mut Self {
+		self.config.key_only_line = key_only_line;
+		self
+	}
+
+	/// Sets what separates one record from the next. The default is `RecordBoundary::None`, meaning the whole file is one record.
+	pub fn record_boundary(&mut self, record_boundary: RecordBoundary) -> &mut Self {
+		self.config.record_boundary = record_boundary;
+		self
+	}
+
+	/// Sets what to do with the empty element after a `|` at the end of a sequence. The default is `TrailingEmptyElement::Keep`.
+	/// 
+	/// This also applies to nested sequences, such as `a,b,|c`.
+	pub fn trailing_empty_element(&mut self, trailing_empty_element: TrailingEmptyElement) -> &mut Self {
+		self.config.trailing_empty_element = trailing_empty_element;
+		self
+	}
+
+	/// Sets how sections are marked, if the file is divided into sections. The default is `SectionSyntax::None`.
+	pub fn section_syntax(&mut self, section_syntax: SectionSyntax) -> &mut Self {
+		self.config.section_syntax = section_syntax;
+		self
+	}
+
+	/// Sets whether keys that aren't fields of the struct being deserialized are skipped without being decoded or shown to the struct's `Deserialize` implementation. This saves a good deal of work on files that have many more keys than the struct has fields, as ShopSite's files tend to. The default is `false`.
+	/// 
+	/// This only applies to a struct that the whole file (or record) is deserialized into, and only when the file isn't divided into sections. It doesn't apply to structs with `#[serde(flatten)]` fields, since those need to see every key.
+	/// 
+	/// Only turn this on if the struct doesn't use `#[serde(deny_unknown_fields)]`, and doesn't have a hand-written `Deserialize` implementation that wants to see keys other than the field names it declares. Skipped keys are never seen, so they won't be rejected. Serde doesn't say whether a struct denies unknown fields, so this can't be decided automatically.
+	pub fn skip_unknown_keys(&mut self, skip_unknown_keys: bool) -> &mut Self {
+		self.config.skip_unknown_keys = skip_unknown_keys;
+		self
+	}
+
+	/// Sets how many columns apart tab stops are, for the column numbers in `Position`s. A tab advances the column to the next tab stop, as in a text editor. The default is 8; set this to match the editor that people will be looking at the file in, like 4 for VS Code.
+	/// 
+	/// A width of 0 makes tabs take up no room at all, like other control characters. This doesn't affect `Position::byte_column`.
+	pub fn tab_width(&mut self, width: u32) -> &mut Self {
+		self.config.tab_width = width;
+		self
+	}
+
+	/// Limits how many bytes long a line can be, not counting the line ending. Longer lines cause an `Error::LimitExceeded`. By default, there is no limit.
+	/// 
+	/// This and the other limits are for parsing files from untrusted sources, like uploads, so that a single gigantic line can't exhaust memory. ShopSite's own files have long lines in product descriptions, so don't set this too low.
+	pub fn max_line_length(&mut self, max: usize) -> &mut Self {
+		self.config.max_line_length = Some(max);
+		self
+	}
+
+	/// Limits how many bytes long a key, a value, or one element of a sequence can be. Longer ones cause an `Error::LimitExceeded`. By default, there is no limit.
+	pub fn max_value_size(&mut self, max: usize) -> &mut Self {
+		self.config.max_value_size = Some(max);
+		self
+	}
+
+	/// Limits how many elements a sequence can have. Sequences with more cause an `Error::LimitExceeded`. By default, there is no limit.
+	pub fn max_seq_elements(&mut self, max: usize) -> &mut Self {
+		self.config.max_seq_elements = Some(max);
+		self
+	}
+
+	/// Limits how many keys the input can have in total, across all records, including keys that are skipped. Any more cause an `Error::LimitExceeded`. By default, there is no limit.
+	pub fn max_keys(&mut self, max: usize) -> &mut Self {
+		self.config.max_keys = Some(max);
+		self
+	}
+
+	/// Sets default values for keys that are missing from the input. After the last key of the input (or of each record), the defaults of any keys that didn't appear in it are handed over too, as if they'd been at the end. This way, a struct can have non-`Option` fields for keys that a partial export leaves out, while keys that aren't in the defaults are still required. By default, there are no defaults.
+	/// 
+	/// The defaults can come from anything that a `Map` can be deserialized from, such as a profile file in `.aa` or TOML format:
+	/// 
+	/// ```
+	/// use serde::Deserialize;
+	/// use shopsite_aa::de as aa;
+	/// 
+	/// #[derive(Deserialize)]
+	/// struct Product {
+	///     #[serde(rename = "Name")]
+	///     name: String,
+	///     #[serde(rename = "Taxable")]
+	///     taxable: bool
+	/// }
+	/// 
+	/// let defaults: aa::Map = aa::from_bytes(b"Taxable: true\n", None).unwrap();
+	/// 
+	/// let product = Product::deserialize(&mut aa::DeserializerBuilder::new()
+	///     .defaults(defaults)
+	///     .build(&b"Name: Widget\n"[..], None)
+	/// ).unwrap();
+	/// 
+	/// assert!(product.taxable);
+	/// ```
+	/// 
+	/// Default values are parsed as by `from_value`, which uses the default options, not the ones set on this builder. Errors in them have no position, since they aren't in the input. Defaults don't apply to the keys in sections.
+	pub fn defaults(&mut self, defaults: Map) -> &mut Self {
+		self.config.defaults = Some(Arc::new(defaults));
+		self
+	}
+
+	/// Sets how many bytes the deserializer's buffers have room for to start with. The default is 4096. See `Deserializer::with_capacity`.
+	pub fn buffer_capacity(&mut self, capacity: usize) -> &mut Self {
+		self.config.buffer_capacity = capacity;
+		self
+	}
+
+	/// Creates a `Deserializer` with the options set on this builder.
+	/// 
+	/// The reader must be buffered. If it isn't, use `build_from_read` instead.
+	pub fn build<R: BufRead>(&self, reader: R, file: Option<Arc<Path>>) -> Deserializer<R> {
+		Deserializer::with_config(reader, file, self.config.clone())
+	}
+
+	/// Creates a `Deserializer` with the options set on this builder, reading from an unbuffered reader. The input is buffered internally.
+	pub fn build_from_read<R: Read>(&self, reader: R, file: Option<Arc<Path>>) -> Deserializer<BufReader<R>> {
+		self.build(BufReader::new(reader), file)
+	}
+}
@@ -0,0 +1,150 @@
+use std::{
+	io::BufRead,
+	mem
+};
+use super::{Deserializer, LineEndingsSeen, Position};
+
+/// The state of a `Deserializer` at the point where `Deserializer::mark` was called, and the input it has read since then. Everything that `rewind` needs to put things back the way they were.
+pub(super) struct Mark {
+	pos: Position,
+	last_byte: u8,
+	reached_eof: bool,
+	last_delim: Option<u8>,
+	fill_end: (u32, u32, u32),
+	saw_blank_line: bool,
+	pending_key: Option<bool>,
+	buf_b: Vec<u8>,
+	buf_s: String,
+	last_key_line: u32,
+	keys_read: usize,
+	bytes_read: u64,
+	line_endings_seen: LineEndingsSeen,
+	seq_delimiter: u8,
+
+	/// The bytes consumed from the input since the mark was set (or last rewound to).
+	consumed: Vec<u8>
+}
+
+impl<R> Deserializer<R> {
+	/// Remembers the current position in the input, so that parsing can go back to it later with `rewind`. Any earlier mark is forgotten.
+	/// 
+	/// This is for speculative parsing: trying to deserialize a record as one type, and if that doesn't work out, going back and trying another. While a mark is set, every byte read is kept in memory, so that it can be read again, so call `unmark` once the attempt has worked out.
+	/// 
+	/// ```
+	/// use serde::Deserialize;
+	/// use shopsite_aa::de as aa;
+	/// 
+	/// #[derive(Deserialize)]
+	/// struct NewLayout { price: u32 }
+	/// 
+	/// #[derive(Deserialize)]
+	/// struct OldLayout { price: String }
+	/// 
+	/// let mut de = aa::Deserializer::new(&b"price: $5\n"[..], None);
+	/// de.mark();
+	/// 
+	/// let price = match NewLayout::deserialize(&mut de) {
+	///     Ok(record) => record.price.to_string(),
+	///     Err(_) => {
+	///         de.rewind();
+	///         OldLayout::deserialize(&mut de).unwrap().price
+	///     }
+	/// };
+	/// 
+	/// de.unmark();
+	/// assert_eq!(price, "$5");
+	/// ```
+	pub fn mark(&mut self) {
+		self.mark = Some(Box::new(Mark {
+			pos: self.pos.clone(),
+			last_byte: self.last_byte,
+			reached_eof: self.reached_eof,
+			last_delim: self.last_delim,
+			fill_end: self.fill_end,
+			saw_blank_line: self.saw_blank_line,
+			pending_key: self.pending_key,
+			buf_b: self.buf_b.clone(),
+			buf_s: self.buf_s.clone(),
+			last_key_line: self.last_key_line,
+			keys_read: self.keys_read,
+			bytes_read: self.bytes_read,
+			line_endings_seen: self.line_endings_seen,
+			seq_delimiter: self.seq_delimiter,
+			consumed: Vec::new()
+		}));
+	}
+
+	/// Goes back to where `mark` was called, as if nothing had been parsed since. The input read since then will be read again. Returns `false`, and does nothing, if there is no mark.
+	/// 
+	/// The mark stays set, so this can be done any number of times, to try any number of ways of parsing the same input.
+	pub fn rewind(&mut self) -> bool {
+		let mark = match &mut self.mark {
+			Some(mark) => mark,
+			None => return false
+		};
+
+		// The bytes consumed since the mark go back in front of whatever was still waiting to be read again from an earlier rewind.
+		let mut replay = mem::take(&mut mark.consumed);
+		replay.extend_from_slice(&self.replay[self.replay_pos..]);
+		self.replay = replay;
+		self.replay_pos = 0;
+
+		self.pos = mark.pos.clone();
+		self.last_byte = mark.last_byte;
+		self.reached_eof = mark.reached_eof;
+		self.last_delim = mark.last_delim;
+		self.fill_end = mark.fill_end;
+		self.saw_blank_line = mark.saw_blank_line;
+		self.pending_key = mark.pending_key;
+		self.buf_b.clone_from(&mark.buf_b);
+		self.buf_s.clone_from(&mark.buf_s);
+		self.last_key_line = mark.last_key_line;
+		self.keys_read = mark.keys_read;
+		self.bytes_read = mark.bytes_read;
+		self.line_endings_seen = mark.line_endings_seen;
+		self.seq_delimiter = mark.seq_delimiter;
+
+		true
+	}
+
+	/// Forgets the mark set by `mark`, if any, and stops keeping the input in memory. Input that was rewound over but hasn't been read again yet is still read again.
+	pub fn unmark(&mut self) {
+		self.mark = None;
+	}
+
+	/// Whether there is a mark to `rewind` to.
+	pub fn is_marked(&self) -> bool {
+		self.mark.is_some()
+	}
+}
+
+impl<R: BufRead> Deserializer<R> {
+	/// Consumes `amount` bytes of input, which must already be in the buffer returned by `reader_buf`. If there's a mark, the bytes are kept for `rewind`.
+	pub(super) fn consume_input(&mut self, amount: usize) {
+		if self.replay_pos < self.replay.len() {
+			// `reader_buf` returns only bytes that are being read again while there are any, so that's where these came from.
+			if let Some(mark) = &mut self.mark {
+				mark.consumed.extend_from_slice(&self.replay[self.replay_pos..][..amount]);
+			}
+
+			self.replay_pos += amount;
+
+			if self.replay_pos == self.replay.len() {
+				self.replay.clear();
+				self.replay_pos = 0;
+			}
+		}
+		else {
+			if let Some(mark) = &mut self.mark {
+				// The reader's buffer is already filled, so this doesn't read anything; it just gets at the bytes about to be consumed.
+				if let Ok(buf) = self.reader.fill_buf() {
+					mark.consumed.extend_from_slice(&buf[..amount.min(buf.len())]);
+				}
+			}
+
+			self.reader.consume(amount);
+		}
+
+		self.bytes_read += amount as u64;
+	}
+}
@@ -0,0 +1,239 @@
+use flate2::bufread::MultiGzDecoder;
+use ruzstd::decoding::{
+	errors::{FrameDecoderError, ReadFrameHeaderError},
+	BlockDecodingStrategy,
+	FrameDecoder
+};
+use std::io::{self, BufRead, BufReader, Chain, Cursor, Read};
+
+/// How many bytes at the beginning of the input `Compression::detect` needs to see.
+const MAGIC_LEN: usize = 4;
+
+/// Which kind of compression some input has. See `decompress`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, derive_more::Display)]
+#[non_exhaustive]
+pub enum Compression {
+	/// None at all.
+	#[display(fmt = "none")]
+	None,
+
+	/// gzip, as in a `.aa.gz` file.
+	#[display(fmt = "gzip")]
+	Gzip,
+
+	/// Zstandard, as in a `.aa.zst` file.
+	#[display(fmt = "zstd")]
+	Zstd
+}
+
+impl Compression {
+	/// Tells which kind of compression some input has, from its first few bytes. Compressed input starts with a magic number, which no `.aa` file starts with, so this doesn't go by file names.
+	pub fn detect(start: &[u8]) -> Compression {
+		if start.starts_with(b"\x1f\x8b") {
+			Compression::Gzip
+		}
+		else if start.starts_with(b"\x28\xb5\x2f\xfd") || is_skippable_frame(start) {
+			Compression::Zstd
+		}
+		else {
+			Compression::None
+		}
+	}
+}
+
+/// Whether some input starts with a Zstandard skippable frame, like the one `pzstd` writes before the rest.
+fn is_skippable_frame(start: &[u8]) -> bool {
+	matches!(start, [0x50..=0x5f, 0x2a, 0x4d, 0x18, ..])
+}
+
+/// A reader that decompresses its input, if it's compressed, or passes it along as it is, if not. Returned by `decompress`.
+pub struct Decompress<R: BufRead>(DecompressInner<Peeked<R>>);
+
+/// The input, after `decompress` has looked at the beginning of it. Bytes it had to take out of the reader's buffer to see enough of them are put back in front.
+type Peeked<R> = Chain<Cursor<Vec<u8>>, R>;
+
+enum DecompressInner<R: BufRead> {
+	None(R),
+	Gzip(BufReader<MultiGzDecoder<R>>),
+	Zstd(BufReader<ZstdFrames<R>>)
+}
+
+/// Decodes every frame of Zstandard input, one after another, skipping skippable frames, as the `zstd` command does.
+struct ZstdFrames<R: BufRead> {
+	source: R,
+
+	// The decoder's state is big, so it goes on the heap, to keep the uncompressed case small.
+	decoder: Box<FrameDecoder>,
+
+	/// Whether the last frame has been decoded and read.
+	done: bool
+}
+
+impl<R: BufRead> ZstdFrames<R> {
+	/// Starts decoding the first frame. Fails if its header is invalid.
+	fn new(source: R) -> io::Result<Self> {
+		let mut frames = ZstdFrames {
+			source,
+			decoder: Box::new(FrameDecoder::new()),
+			done: false
+		};
+
+		frames.done = !frames.next_frame()?;
+		Ok(frames)
+	}
+
+	/// Starts decoding the next frame, skipping any skippable frames before it. Returns `false` if there are no more frames. Fails if there's something other than a frame after the last one.
+	fn next_frame(&mut self) -> io::Result<bool> {
+		loop {
+			if fill_buf(&mut self.source)?.is_empty() {
+				return Ok(false)
+			}
+
+			match self.decoder.reset(&mut self.source) {
+				Ok(()) => return Ok(true),
+				Err(FrameDecoderError::ReadFrameHeaderError(ReadFrameHeaderError::SkipFrame { length, .. })) => {
+					let skipped = io::copy(&mut (&mut self.source).take(length.into()), &mut io::sink())?;
+
+					if skipped < length.into() {
+						return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "Zstandard skippable frame is truncated"))
+					}
+				}
+				Err(error) => return Err(io::Error::new(io::ErrorKind::InvalidData, error))
+			}
+		}
+	}
+}
+
+impl<R: BufRead> Read for ZstdFrames<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		if buf.is_empty() {
+			return Ok(0)
+		}
+
+		while !self.done {
+			// Decode until there's enough to fill `buf`, or the frame ends.
+			while self.decoder.can_collect() < buf.len() && !self.decoder.is_finished() {
+				let wanted = buf.len() - self.decoder.can_collect();
+
+				self.decoder.decode_blocks(&mut self.source, BlockDecodingStrategy::UptoBytes(wanted))
+					.map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))?;
+			}
+
+			let read = self.decoder.read(buf)?;
+			if read != 0 {
+				return Ok(read)
+			}
+
+			// This frame is all read, so go on to the next one, if there is one.
+			self.done = !self.next_frame()?;
+		}
+
+		Ok(0)
+	}
+}
+
+impl<R: BufRead> Decompress<R> {
+	/// Which kind of compression the input has.
+	pub fn compression(&self) -> Compression {
+		match &self.0 {
+			DecompressInner::None(_) => Compression::None,
+			DecompressInner::Gzip(_) => Compression::Gzip,
+			DecompressInner::Zstd(_) => Compression::Zstd
+		}
+	}
+}
+
+impl<R: BufRead> Read for Decompress<R> {
+	fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+		match &mut self.0 {
+			DecompressInner::None(reader) => reader.read(buf),
+			DecompressInner::Gzip(reader) => reader.read(buf),
+			DecompressInner::Zstd(reader) => reader.read(buf)
+		}
+	}
+}
+
+impl<R: BufRead> BufRead for Decompress<R> {
+	fn fill_buf(&mut self) -> io::Result<&[u8]> {
+		match &mut self.0 {
+			DecompressInner::None(reader) => reader.fill_buf(),
+			DecompressInner::Gzip(reader) => reader.fill_buf(),
+			DecompressInner::Zstd(reader) => reader.fill_buf()
+		}
+	}
+
+	fn consume(&mut self, amount: usize) {
+		match &mut self.0 {
+			DecompressInner::None(reader) => reader.consume(amount),
+			DecompressInner::Gzip(reader) => reader.consume(amount),
+			DecompressInner::Zstd(reader) => reader.consume(amount)
+		}
+	}
+}
+
+/// Looks at the beginning of the input to see whether it's compressed with gzip or Zstandard, and if so, decompresses it. Input that isn't compressed is passed along as it is. `from_reader` and `from_file` do this already; this is for building a `Deserializer` with non-default options:
+/// 
+/// ```
+/// use shopsite_aa::de as aa;
+/// use std::io::Write;
+/// 
+/// let mut compressed = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+/// compressed.write_all(b"Name: Widget\n").unwrap();
+/// let compressed = compressed.finish().unwrap();
+/// 
+/// let input = aa::decompress(&compressed[..]).unwrap();
+/// assert_eq!(input.compression(), aa::Compression::Gzip);
+/// 
+/// let map: aa::Map = serde::Deserialize::deserialize(&mut aa::DeserializerBuilder::new()
+///     .bom(aa::Bom::Reject)
+///     .build(input, None)
+/// ).unwrap();
+/// assert_eq!(map.get("Name"), Some(&aa::Value::from("Widget")));
+/// ```
+/// 
+/// A gzip file with several members, as from concatenating `.gz` files, is decompressed as a whole. So is a Zstandard file with several frames, as from concatenating `.zst` files or from `pzstd`.
+/// 
+/// Fails if reading the beginning of the input fails, or if the input looks like Zstandard but its first frame header is invalid. Errors in the rest of the compressed data, including anything after the last Zstandard frame that isn't another frame, turn up later, as errors reading from the returned reader.
+pub fn decompress<R: BufRead>(mut reader: R) -> io::Result<Decompress<R>> {
+	// Readers don't have to buffer more than one byte at a time, so bytes may need to be taken out of the reader's buffer, and put back in front of it afterward, to see enough of them. This isn't needed if the reader has enough bytes buffered already, as it usually does.
+	let mut peeked = Vec::new();
+
+	loop {
+		let buf = fill_buf(&mut reader)?;
+
+		if buf.is_empty() || (peeked.is_empty() && buf.len() >= MAGIC_LEN) {
+			break
+		}
+
+		let amount = buf.len().min(MAGIC_LEN - peeked.len());
+		peeked.extend_from_slice(&buf[..amount]);
+		reader.consume(amount);
+
+		if peeked.len() == MAGIC_LEN {
+			break
+		}
+	}
+
+	let compression = Compression::detect(if peeked.is_empty() { fill_buf(&mut reader)? } else { &peeked });
+	let reader = Cursor::new(peeked).chain(reader);
+
+	Ok(Decompress(match compression {
+		Compression::None => DecompressInner::None(reader),
+		Compression::Gzip => DecompressInner::Gzip(BufReader::new(MultiGzDecoder::new(reader))),
+		Compression::Zstd => DecompressInner::Zstd(BufReader::new(ZstdFrames::new(reader)?))
+	}))
+}
+
+/// Calls `fill_buf`, retrying if it's interrupted.
+fn fill_buf<R: BufRead>(reader: &mut R) -> io::Result<&[u8]> {
+	loop {
+		match reader.fill_buf() {
+			// This can't return `buf` directly, because of a limitation of the borrow checker, so it calls `fill_buf` again, which just returns what's already buffered.
+			Ok(_) => break,
+			Err(ref error) if error.kind() == io::ErrorKind::Interrupted => continue,
+			Err(error) => return Err(error)
+		}
+	}
+
+	reader.fill_buf()
+}
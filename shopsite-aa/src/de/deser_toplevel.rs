@@ -4,13 +4,29 @@ use serde::de::{
 	IntoDeserializer,
 	Visitor
 };
-use std::io::BufRead;
+use std::{
+	collections::HashSet,
+	io::BufRead,
+	sync::Arc,
+	vec
+};
 use super::{
+	sections::{AaSectionsMapAccess, SectionMarker},
 	AaValueDeserializer,
 	Deserializer,
+	EntrySeqAccess,
 	Error,
 	FillBufResult,
-	Result
+	KeyOnlyLine,
+	KnownFields,
+	Limit,
+	Map,
+	Position,
+	RecordBoundary,
+	Result,
+	SectionSyntax,
+	Value,
+	trace_result
 };
 
 impl<'de, R: BufRead> serde::Deserializer<'de> for &mut Deserializer<R> {
@@ -20,33 +36,200 @@ impl<'de, R: BufRead> serde::Deserializer<'de> for &mut Deserializer<R> {
 
 	fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
 	where V: Visitor<'de> {
-		visitor.visit_map(AaTopMapAccess {
-			de: self,
-			no_value: false
-		})
+		#[cfg(feature = "trace")]
+		let _span = self.record_span().entered();
+
+		let result = {
+			if self.config.section_syntax == SectionSyntax::None {
+				visitor.visit_map(AaTopMapAccess::new(self))
+			}
+			else {
+				visitor.visit_map(AaSectionsMapAccess::new(self))
+			}
+		};
+
+		trace_result(result.map_err(|error| self.locate_missing_field(error)))
+	}
+
+	fn deserialize_map<V>(self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		// Only a map being asked for by name is given a size hint, since counting the keys is a pass over the input of its own, and other visitors, like those of structs, would ignore it anyway.
+		if self.config.section_syntax != SectionSyntax::None || self.config.record_boundary != RecordBoundary::None {
+			return self.deserialize_any(visitor)
+		}
+
+		#[cfg(feature = "trace")]
+		let _span = self.record_span().entered();
+
+		let result = visitor.visit_map(AaTopMapAccess {
+			size_hint: self.peek_key_count(),
+			..AaTopMapAccess::new(self)
+		});
+
+		trace_result(result.map_err(|error| self.locate_missing_field(error)))
+	}
+
+	fn deserialize_struct<V>(self, _name: &'static str, fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		// If the file is divided into sections, then the struct's fields are section names, not keys, so there's nothing to skip at this level.
+		if self.config.section_syntax != SectionSyntax::None || !self.config.skip_unknown_keys {
+			return self.deserialize_any(visitor)
+		}
+
+		// Encode the field names the same way the keys are encoded, so that keys can be checked against them without decoding the keys.
+		let known_fields = KnownFields::cached(&mut self.known_fields, fields, self.config.encoding);
+
+		#[cfg(feature = "trace")]
+		let _span = self.record_span().entered();
+
+		let result = visitor.visit_map(AaTopMapAccess {
+			known_fields: Some(known_fields),
+			..AaTopMapAccess::new(self)
+		});
+
+		trace_result(result.map_err(|error| self.locate_missing_field(error)))
+	}
+
+	fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		// The top level is a map, but it can be had as a sequence of key-value pairs instead, for those who want every key in order, repeats and all.
+		#[cfg(feature = "trace")]
+		let _span = self.record_span().entered();
+
+		let result = {
+			if self.config.section_syntax == SectionSyntax::None {
+				visitor.visit_seq(EntrySeqAccess::new(AaTopMapAccess::new(self)))
+			}
+			else {
+				visitor.visit_seq(EntrySeqAccess::new(AaSectionsMapAccess::new(self)))
+			}
+		};
+
+		trace_result(result.map_err(|error| self.locate_missing_field(error)))
 	}
 
 	serde::forward_to_deserialize_any! {
 		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
-		bytes byte_buf option unit unit_struct newtype_struct seq tuple
-		tuple_struct map struct enum identifier ignored_any
+		bytes byte_buf option unit unit_struct newtype_struct tuple
+		tuple_struct enum identifier ignored_any
 	}
 }
 
-struct AaTopMapAccess<'a, R: BufRead> {
+pub(super) struct AaTopMapAccess<'a, R> {
 	de: &'a mut Deserializer<R>,
-	no_value: bool
+	no_value: bool,
+
+	/// How many keys have been handed to the visitor so far. A record boundary only counts as one if the record has at least one key in it.
+	keys_read: usize,
+
+	/// Whether this is the map of keys in one section, as opposed to the whole file. If so, the map ends at the next section marker.
+	in_section: bool,
+
+	/// The undecoded names of the fields of the struct being deserialized, if known. Other keys are skipped without being decoded or handed to the visitor.
+	known_fields: Option<Arc<KnownFields>>,
+
+	/// The key most recently handed to the visitor. Kept so that errors in its value can say which key it was, since reading the value overwrites `de.buf_s`.
+	key: String,
+
+	/// Values for keys that the input doesn't have. See `DeserializerBuilder::defaults`. Sections don't get any.
+	defaults: Option<Arc<Map>>,
+
+	/// The keys that have been read from the input, if there are defaults, so that only the defaults of the other keys are used. With the `trace` feature, these are always kept, so that repeated keys can be reported.
+	seen: HashSet<String>,
+
+	/// The defaults that are yet to be handed to the visitor. This is `None` until the input runs out.
+	remaining_defaults: Option<vec::IntoIter<(String, Value)>>,
+
+	/// The value of the key most recently handed to the visitor, if the key came from the defaults.
+	default_value: Option<Value>,
+
+	/// How many keys the map probably has, if known, so that the visitor can make room for them all at once. This is only known when the map is the whole input, not a record or a section, and was asked for with `deserialize_map`. See `Deserializer::peek_key_count`.
+	size_hint: Option<usize>
 }
 
-impl<'de, 'a, R: BufRead> MapAccess<'de> for AaTopMapAccess<'a, R> {
-	type Error = Error;
+impl<'a, R: BufRead> AaTopMapAccess<'a, R> {
+	pub(super) fn new(de: &'a mut Deserializer<R>) -> AaTopMapAccess<'a, R> {
+		AaTopMapAccess {
+			no_value: false,
+			keys_read: 0,
+			in_section: false,
+			known_fields: None,
+			key: String::new(),
+			defaults: de.config.defaults.clone(),
+			seen: HashSet::new(),
+			remaining_defaults: None,
+			default_value: None,
+			size_hint: None,
+			de
+		}
+	}
+
+	pub(super) fn in_section(de: &'a mut Deserializer<R>) -> AaTopMapAccess<'a, R> {
+		AaTopMapAccess {
+			in_section: true,
+			defaults: None,
+			..AaTopMapAccess::new(de)
+		}
+	}
+}
+
+impl<R: BufRead> Deserializer<R> {
+	/// Reads the next key into `self.buf_s`, and sets `self.pending_key` accordingly. Returns `false` if there are no more keys in the file.
+	pub(super) fn read_key(&mut self) -> Result<bool> {
+		if !self.read_key_undecoded()? {
+			return Ok(false)
+		}
+
+		// Keys are always strings, so decode it.
+		self.decode_buf_all();
+		Ok(true)
+	}
+
+	/// Like `read_key`, but skips over keys (and their values) that aren't in `known_fields`, without decoding them. Keys that may begin a new record are never skipped, since they're needed to find the end of the record.
+	fn read_known_key(&mut self, known_fields: &KnownFields) -> Result<bool> {
+		// Reading each key forgets whether there were blank lines before it, so remember whether there were any before the skipped keys.
+		let mut saw_blank_line = false;
+
+		loop {
+			if !self.read_key_undecoded()? {
+				self.pending_key = None;
+				return Ok(false)
+			}
+
+			saw_blank_line |= self.saw_blank_line;
+
+			let may_start_record = match &self.config.record_boundary {
+				RecordBoundary::None => false,
+				RecordBoundary::BlankLine => saw_blank_line,
+				// The boundary key hasn't been encoded, so only an ASCII one can be compared with the undecoded key. To be safe, don't skip anything if it's not ASCII.
+				RecordBoundary::Key(key) => !key.is_ascii() || self.buf_b == key.as_bytes()
+			};
+
+			if may_start_record || known_fields.contains(&self.buf_b) {
+				self.saw_blank_line = saw_blank_line;
+				self.decode_buf_all();
+				return Ok(true)
+			}
+
+			// Not interested. The rest of the line, if any, is skipped when reading the next key.
+			#[cfg(feature = "trace")]
+			tracing::trace!(key = %String::from_utf8_lossy(&self.buf_b), line = self.pos.line, "skipped key");
+
+			self.pending_key = None;
+		}
+	}
+
+	/// Does the work of `read_key`, except for decoding the key. The key is left in `self.buf_b`, and `self.buf_s` is not changed.
+	pub(super) fn read_key_undecoded(&mut self) -> Result<bool> {
+		self.skip_bom()?;
 
-	fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
-	where K: DeserializeSeed<'de> {
 		// Keys always occur at the beginning of a line, so if we're currently in the middle of a line, skip to the next line.
-		if self.de.pos.column != 1 {
+		if self.pos.byte_column != 1 {
 			loop {
-				if let Some(byte) = self.de.read_byte()? {
+				// Skip as much of the line as is already buffered, then look at what comes after it.
+				self.read_run(b"\r\n", false)?;
+
+				if let Some(byte) = self.read_byte()? {
 					if byte == b'\r' || byte == b'\n' {
 						// End of line.
 						break
@@ -54,55 +237,222 @@ impl<'de, 'a, R: BufRead> MapAccess<'de> for AaTopMapAccess<'a, R> {
 				}
 				else {
 					// End of file.
-					return Ok(None)
+					return Ok(false)
 				}
 			}
 		}
 
+		// Keep track of whether there are blank lines before the key, in case they separate records.
+		self.saw_blank_line = false;
+
 		// Read the key, look for the delimiter, and prepare to submit the key to the `Visitor`.
-		match self.de.fill_buf(&[b':'])? {
+		let no_value = match self.fill_buf(b":", None)? {
 			FillBufResult::FoundDelim(_) => {
 				// We've read in a key, and found the delimiter.
-				self.no_value = false;
-				
 				// Before we proceed, we need to strip the space that (usually?) comes after the delimiter.
-				match self.de.peek_byte()? {
+				match self.peek_byte()? {
 					Some(b' ') => {
 						// Found it. Now we need to consume it from the input so that it's not considered part of the value.
 						// This can't fail and we don't need to see the byte again, so just throw away the result.
-						let _ = self.de.read_byte();
+						let _ = self.read_byte();
 					},
 					_ => {
 						// Found some other byte. Leave it; we'll consider it part of the value.
 					}
 				}
+
+				false
 			},
-			FillBufResult::FoundEof if self.de.buf_b.is_empty() => {
+			FillBufResult::FoundEof if self.buf_b.is_empty() => {
 				// We've reached the end of the file and read nothing.
-				return Ok(None)
+				return Ok(false)
 			},
 			_ => {
 				// We've read a key with no value. We need to make note of this so that `next_value_seed` submits `()` instead of trying to read an actual value.
-				self.no_value = true;
+				true
 			}
+		};
+
+		self.keys_read += 1;
+		self.check_limit(Limit::Keys, self.config.max_keys, self.keys_read)?;
+
+		self.pending_key = Some(no_value);
+		Ok(true)
+	}
+
+	/// Creates the `tracing` span for deserializing one record, which is to say, the whole file if it only has one.
+	#[cfg(feature = "trace")]
+	fn record_span(&self) -> tracing::Span {
+		tracing::debug_span!(
+			"record",
+			file = %super::arc_path_to_str(&self.pos.file),
+			line = self.pos.line
+		)
+	}
+
+	/// Checks whether the pending key begins a new record.
+	pub(super) fn pending_key_starts_record(&self) -> bool {
+		match &self.config.record_boundary {
+			RecordBoundary::None => false,
+			RecordBoundary::BlankLine => self.saw_blank_line,
+			RecordBoundary::Key(key) => self.buf_s == *key
 		}
+	}
+}
 
-		// Keys are always strings, so decode it.
-		self.de.decode_buf_all();
+impl<'a, R: BufRead> AaTopMapAccess<'a, R> {
+	/// Reads the next key of this map from the input into `de.buf_s`, and gets ready to read its value. Returns `false` if there are no more keys in this map, in the input.
+	fn read_next_key(&mut self) -> Result<bool> {
+		loop {
+			// Read the next key, unless it's already been read.
+			if self.de.pending_key.is_none() {
+				let found = match &self.known_fields {
+					Some(known_fields) => self.de.read_known_key(known_fields)?,
+					None => self.de.read_key()?
+				};
+
+				if !found {
+					return Ok(false)
+				}
+			}
+
+			// If the key begins a new record, then this record is over. Leave the key pending, for the next record.
+			if self.keys_read != 0 && self.de.pending_key_starts_record() {
+				return Ok(false)
+			}
+
+			// If this is a section and the key is actually a section marker, then this section is over. If it's the beginning of the next section, leave it pending, for the next section. If it's the end of this section, consume it.
+			if self.in_section {
+				match self.de.pending_section_marker() {
+					Some(SectionMarker::Begin(_)) => return Ok(false),
+					Some(SectionMarker::End) => {
+						self.de.pending_key = None;
+						return Ok(false)
+					},
+					None => {}
+				}
+			}
+
+			// What to do with a key that has no value depends on `DeserializerBuilder::key_only_line`.
+			if self.de.pending_key == Some(true) {
+				match self.de.config.key_only_line {
+					KeyOnlyLine::Skip => {
+						#[cfg(feature = "trace")]
+						tracing::trace!(key = %self.de.buf_s, line = self.de.fill_end.0, "skipped key with no value");
+
+						self.de.pending_key = None;
+						continue
+					},
+					KeyOnlyLine::Error => return Err(Error::MissingValue {
+						pos: Position {
+							file: self.de.pos.file.clone(),
+							line: self.de.fill_end.0,
+							column: 1,
+							byte_column: 1,
+							key: Some(self.de.buf_s.clone())
+						}
+					}),
+					_ => {}
+				}
+			}
+
+			break
+		}
+
+		self.no_value = self.de.pending_key.take().unwrap_or_default();
+		self.keys_read += 1;
+		self.de.last_key_line = self.de.fill_end.0;
+
+		#[cfg(feature = "trace")]
+		tracing::trace!(key = %self.de.buf_s, line = self.de.pos.line, no_value = self.no_value, "key");
+
+		self.key.clear();
+		self.key.push_str(&self.de.buf_s);
 
-		// All ready. Submit the key to the `Visitor`.
-		seed.deserialize((&self.de.buf_s[..]).into_deserializer()).map(Some)
+		// Figure out how sequences in this key's value are delimited.
+		self.de.seq_delimiter = self.de.config.seq_delimiter_for(&self.de.buf_s);
+
+		Ok(true)
 	}
+}
 
-	fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
-	where V: DeserializeSeed<'de> {
-		if self.no_value {
-			// If we're at a key with no value, then say so.
-			seed.deserialize(().into_deserializer())
+impl<'de, 'a, R: BufRead> MapAccess<'de> for AaTopMapAccess<'a, R> {
+	type Error = Error;
+
+	fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+	where K: DeserializeSeed<'de> {
+		if self.remaining_defaults.is_none() {
+			if self.read_next_key()? {
+				// The keys seen so far are needed to use the defaults of the rest, and to notice repeated keys when tracing.
+				if self.defaults.is_some() || cfg!(feature = "trace") {
+					#[allow(unused_variables)]
+					let is_new = self.seen.insert(self.key.clone());
+
+					#[cfg(feature = "trace")]
+					if !is_new {
+						tracing::debug!(key = %self.key, line = self.de.last_key_line, "duplicate key");
+					}
+				}
+
+				// All ready. Submit the key to the `Visitor`.
+				return seed.deserialize((&self.de.buf_s[..]).into_deserializer()).map(Some)
+			}
+
+			// The input has run out. Now for the defaults of the keys it didn't have, if any.
+			let seen = &self.seen;
+
+			self.remaining_defaults = Some(
+				self.defaults.iter()
+				.flat_map(|defaults| defaults.iter())
+				.filter(|(key, _)| !seen.contains(*key))
+				.map(|(key, value)| (key.clone(), value.clone()))
+				.collect::<Vec<_>>()
+				.into_iter()
+			);
 		}
-		else {
-			// If there is a value, then pass a deserializer along to read it from.
-			seed.deserialize(AaValueDeserializer::new(self.de))
+
+		match self.remaining_defaults.as_mut().and_then(Iterator::next) {
+			Some((key, value)) => {
+				#[cfg(feature = "trace")]
+				tracing::trace!(key = %key, "default");
+
+				self.default_value = Some(value);
+				let result = seed.deserialize(key.as_str().into_deserializer()).map(Some);
+				self.key = key;
+				result
+			},
+			None => Ok(None)
 		}
 	}
+
+	fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+	where V: DeserializeSeed<'de> {
+		let result = {
+			if let Some(value) = self.default_value.take() {
+				// This key came from the defaults, not the input, so its value does too.
+				seed.deserialize(value)
+			}
+			else if self.no_value {
+				// If we're at a key with no value, then say so, in the way that `DeserializerBuilder::key_only_line` says to. (The other ways don't get this far.)
+				match self.de.config.key_only_line {
+					KeyOnlyLine::EmptyString => seed.deserialize(Value::String(String::new())),
+					KeyOnlyLine::True => seed.deserialize(Value::from("true")),
+					_ => seed.deserialize(().into_deserializer())
+				}
+			}
+			else {
+				// If there is a value, then pass a deserializer along to read it from.
+				#[cfg(feature = "trace")]
+				let _span = tracing::trace_span!("key", key = %self.key, line = self.de.last_key_line).entered();
+
+				seed.deserialize(AaValueDeserializer::new(self.de))
+			}
+		};
+
+		result.map_err(|error| error.in_key(&self.key))
+	}
+
+	fn size_hint(&self) -> Option<usize> {
+		self.size_hint.map(|len| len.saturating_sub(self.keys_read))
+	}
 }
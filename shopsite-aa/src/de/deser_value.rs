@@ -9,10 +9,17 @@ use std::{
 	str::FromStr
 };
 use super::{
+	raw_value::{self, RAW_VALUE_TOKEN},
 	Deserializer,
+	Position,
 	Error,
 	FillBufResult,
-	Result
+	Limit,
+	os_string::{OsStringAccess, OS_STRING_ENUM, OS_STRING_VARIANTS},
+	Result,
+	SeqEscape,
+	TrailingEmptyElement,
+	UntypedValue
 };
 
 macro_rules! deserialize_with_other {
@@ -30,20 +37,20 @@ macro_rules! deserialize_with_from_str {
 			self.fill_buf_auto()?;
 			self.de.decode_buf_all();
 			visitor.$visit_name (
-				FromStr::from_str(&self.de.buf_s[..])
-				.map_err(|error| Error::$error_kind { error: error, pos: start_pos })?
+				FromStr::from_str(&self.de.config.number_syntax.clean(&self.de.buf_s[..]))
+				.map_err(|error| Error::$error_kind { error, pos: start_pos })?
 			)
 		}
 	}
 }
 
-pub(super) struct AaValueDeserializer<'a, R: BufRead> {
+pub(super) struct AaValueDeserializer<'a, R> {
 	de: &'a mut Deserializer<R>,
 
-	/// `true` iff the value being deserialized is inside of a sequence.
+	/// How many sequences the value being deserialized is inside of.
 	/// 
-	/// Elements in a sequence are delimited by `|` characters, so if this is `true`, then reading will only proceed up to the next such delimiter, rather than reading all the way to the end of the line.
-	inside_seq: bool
+	/// Elements in a sequence are delimited by `|` characters (unless overridden with `DeserializerBuilder::seq_delimiter_override`), so if this is 1, then reading will only proceed up to the next such delimiter, rather than reading all the way to the end of the line. Elements of a sequence inside a sequence (like the option lists of a product's ordering options) are further delimited by `,` characters (or whatever `DeserializerBuilder::sub_seq_delimiter` says), so if this is 2 or more, reading stops at either delimiter.
+	seq_depth: u8
 }
 
 impl<'a, R: BufRead> AaValueDeserializer<'a, R> {
@@ -51,18 +58,62 @@ impl<'a, R: BufRead> AaValueDeserializer<'a, R> {
 	pub(super) fn new(de: &'a mut Deserializer<R>) -> AaValueDeserializer<'a, R> {
 		AaValueDeserializer {
 			de,
-			inside_seq: false
+			seq_depth: 0
 		}
 	}
 }
 
 impl<'a, R: BufRead> AaValueDeserializer<'a, R> {
-	/// Same effect as `self.de.fill_buf`, but with the delimiters automatically filled in with `self.read_until`.
+	/// Same effect as `self.de.fill_buf`, but with the delimiters and escape byte automatically filled in based on `self.seq_depth`. Escapes in sequence elements are also decoded.
+	/// 
+	/// The delimiter that was found, if any, is stored in `self.de.last_delim`, so that the `SeqAccess` for a nested sequence can tell whether the nested sequence has ended.
 	fn fill_buf_auto(&mut self) -> Result<FillBufResult> {
-		self.de.fill_buf(match self.inside_seq {
-			true => &[b'|'],
-			false => &[]
-		})
+		let result = if self.seq_depth > 0 {
+			let seq_escape = self.de.config.seq_escape;
+			let delimiters = [self.de.seq_delimiter, self.de.config.sub_seq_delimiter];
+
+			let result = self.de.fill_buf(match self.seq_depth {
+				1 => &delimiters[..1],
+				_ => &delimiters[..]
+			}, seq_escape.escape_byte())?;
+
+			if seq_escape == SeqEscape::NumericEntity {
+				self.de.unescape_pipe_entities();
+			}
+
+			result
+		}
+		else {
+			self.de.fill_buf(&[], None)?
+		};
+
+		self.de.last_delim = match result {
+			FillBufResult::FoundDelim(delim) => Some(delim),
+			_ => None
+		};
+
+		Ok(result)
+	}
+
+	/// Decodes `self.de.buf_b` into `self.de.buf_s`, like `Deserializer::decode_buf_all`, but decodes HTML character references in it, if `DeserializerBuilder::html_entities` says to, before normalizing its punctuation, so that punctuation written as a reference is normalized too. This is for values that are handed over as text.
+	fn decode_text(&mut self) {
+		self.de.decode_buf_all_unnormalized();
+
+		#[cfg(feature = "html-entities")]
+		self.de.config.html_entities.decode(&mut self.de.buf_s);
+
+		self.de.config.punctuation.normalize(&mut self.de.buf_s);
+	}
+
+	/// Like `decode_text`, but decodes into a new `String`.
+	fn decode_text_owned(&mut self) -> String {
+		let mut s = self.de.decode_buf_all_owned_unnormalized();
+
+		#[cfg(feature = "html-entities")]
+		self.de.config.html_entities.decode(&mut s);
+
+		self.de.config.punctuation.normalize(&mut s);
+		s
 	}
 }
 
@@ -77,10 +128,19 @@ impl<'de, 'a, R: BufRead> serde::Deserializer<'de> for AaValueDeserializer<'a, R
 		visitor.visit_bytes(&self.de.buf_b[..])
 	}
 
+	fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		// Every value in this format could be text, so that's what we hand over, unless asked not to.
+		match self.de.config.untyped_value {
+			UntypedValue::Text => self.deserialize_str(visitor),
+			UntypedValue::Bytes => self.deserialize_bytes(visitor)
+		}
+	}
+
 	fn deserialize_str<V>(mut self, visitor: V) -> Result<V::Value>
 	where V: Visitor<'de> {
 		self.fill_buf_auto()?;
-		self.de.decode_buf_all();
+		self.decode_text();
 		visitor.visit_str(&self.de.buf_s[..])
 	}
 
@@ -89,13 +149,13 @@ impl<'de, 'a, R: BufRead> serde::Deserializer<'de> for AaValueDeserializer<'a, R
 		self.fill_buf_auto()?;
 
 		// The recipient wants the text decoded, but wants to own the decoded `String`. Can do!
-		visitor.visit_string(self.de.decode_buf_all_owned())
+		visitor.visit_string(self.decode_text_owned())
 	}
 
 	fn deserialize_char<V>(mut self, visitor: V) -> Result<V::Value>
 	where V: Visitor<'de> {
 		self.fill_buf_auto()?;
-		self.de.decode_buf_all();
+		self.decode_text();
 		let mut chars = self.de.buf_s.chars();
 
 		match (chars.next(), chars.next()) {
@@ -129,8 +189,25 @@ impl<'de, 'a, R: BufRead> serde::Deserializer<'de> for AaValueDeserializer<'a, R
 		}
 	}
 
-	fn deserialize_newtype_struct<V>(self, _: &'static str, visitor: V) -> Result<V::Value>
+	fn deserialize_newtype_struct<V>(mut self, name: &'static str, visitor: V) -> Result<V::Value>
 	where V: Visitor<'de> {
+		if name == RAW_VALUE_TOKEN {
+			// Someone wants a `RawValue`. Read the value, note where it was, and send it along in the secret format that only `RawValue` understands.
+			let start = self.de.pos.clone();
+			self.fill_buf_auto()?;
+			let (end_line, end_column, end_byte_column) = self.de.fill_end;
+
+			let end = Position {
+				file: start.file.clone(),
+				line: end_line,
+				column: end_column,
+				byte_column: end_byte_column,
+				key: None
+			};
+
+			return visitor.visit_byte_buf(raw_value::encode(&self.de.buf_b, self.de.config.encoding, &start, &end))
+		}
+
 		// Yeah, sure, buddy, we got your fancy “newtype struct” in this here dead-simple key-value format. Uh huh. Whatever you say, boss.
 		visitor.visit_newtype_struct(self)
 	}
@@ -152,38 +229,69 @@ impl<'de, 'a, R: BufRead> serde::Deserializer<'de> for AaValueDeserializer<'a, R
 		visitor.visit_unit()
 	}
 
-	fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+	fn deserialize_option<V>(mut self, visitor: V) -> Result<V::Value>
 	where V: Visitor<'de> {
-		// In this case, we'll consider an empty value to mean `None` and a non-empty value to mean `Some`.
-		match self.de.peek_byte()? {
-			None | Some(b'\r') | Some(b'\n') => {
-				// The next byte is a line ending or end-of-file. That's a `None` for our purposes.
-				visitor.visit_none()
-			},
-			Some(_) => {
-				// The next byte is something else. That's a `Some`.
-				visitor.visit_some(self)
+		// In this case, we'll consider an empty value to mean `None` and a non-empty value to mean `Some`. In a sequence, an element is also empty if it's immediately followed by the delimiter that ends it.
+		let is_empty = match self.de.peek_byte()? {
+			None | Some(b'\r') | Some(b'\n') => true,
+			Some(byte) => match self.seq_depth {
+				0 => false,
+				1 => byte == self.de.seq_delimiter,
+				_ => byte == self.de.seq_delimiter || byte == self.de.config.sub_seq_delimiter
+			}
+		};
+
+		if !is_empty {
+			// The next byte is something else. That's a `Some`.
+			visitor.visit_some(self)
+		}
+		else {
+			// That's a `None` for our purposes. In a sequence, the empty element still has to be read, along with whatever ended it, so that the sequence moves on to the next element.
+			if self.seq_depth > 0 {
+				self.fill_buf_auto()?;
 			}
+
+			visitor.visit_none()
 		}
 	}
 
 	fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
 	where V: Visitor<'de> {
+		let depth = self.seq_depth.saturating_add(1);
+
 		visitor.visit_seq(AaValueSeqAccess {
+			size_hint: self.de.peek_seq_len(depth)?,
 			de: self.de,
 			is_first_element: true,
-			is_nested_seq: self.inside_seq
+			elements_read: 0,
+			depth
 		})
 	}
 
-	fn deserialize_enum<V>(mut self, _: &'static str, _: &'static [&'static str], visitor: V) -> Result<V::Value>
+	fn deserialize_enum<V>(mut self, name: &'static str, variants: &'static [&'static str], visitor: V) -> Result<V::Value>
 	where V: Visitor<'de> {
 		self.fill_buf_auto()?;
+
+		if name == OS_STRING_ENUM && variants == OS_STRING_VARIANTS {
+			// Someone wants an `OsString`, which is an enum as far as Serde is concerned. Hand over the value in the form it wants for this platform.
+			return visitor.visit_enum(OsStringAccess::new(&self.de.buf_b, self.de.config.encoding, self.de.config.os_string_value))
+		}
+
 		self.de.decode_buf_all();
 		visitor.visit_enum((&self.de.buf_s[..]).into_deserializer())
 	}
 
-	deserialize_with_from_str!(deserialize_bool, visit_bool, InvalidBool);
+	fn deserialize_bool<V>(mut self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		let start_pos = self.de.pos.clone();
+		self.fill_buf_auto()?;
+		self.de.decode_buf_all();
+		visitor.visit_bool(
+			self.de.config.bool_syntax.parse(&self.de.buf_s[..])
+			.map_err(|error| Error::InvalidBool { error, pos: start_pos })?
+		)
+	}
+
 	deserialize_with_from_str!(deserialize_i8, visit_i8, InvalidInt);
 	deserialize_with_from_str!(deserialize_i16, visit_i16, InvalidInt);
 	deserialize_with_from_str!(deserialize_i32, visit_i32, InvalidInt);
@@ -197,7 +305,6 @@ impl<'de, 'a, R: BufRead> serde::Deserializer<'de> for AaValueDeserializer<'a, R
 	deserialize_with_from_str!(deserialize_f32, visit_f32, InvalidFloat);
 	deserialize_with_from_str!(deserialize_f64, visit_f64, InvalidFloat);
 	deserialize_with_other!(deserialize_byte_buf, deserialize_bytes);
-	deserialize_with_other!(deserialize_any, deserialize_str);
 
 	serde::forward_to_deserialize_any! {
 		map struct identifier
@@ -206,15 +313,36 @@ impl<'de, 'a, R: BufRead> serde::Deserializer<'de> for AaValueDeserializer<'a, R
 
 /// Accessor for a sequence of values.
 /// 
-/// In the ShopSite `.aa` format, items in a sequence are separated by a `|` (pipe) character.
-struct AaValueSeqAccess<'a, R: BufRead> {
+/// In the ShopSite `.aa` format, items in a sequence are separated by a `|` (pipe) character. Items in a sequence that is itself an item of a sequence are separated by a `,` (comma) character, so `a,b|c` is `[["a", "b"], ["c"]]`.
+struct AaValueSeqAccess<'a, R> {
 	de: &'a mut Deserializer<R>,
 
 	/// Initially `true`. Set to `false` just before `next_element_seed` returns.
 	is_first_element: bool,
 
-	/// `true` if this is a nested sequence. Nested sequences have only one element.
-	is_nested_seq: bool
+	/// How many elements have been read so far. Used to enforce `DeserializerBuilder::max_seq_elements`.
+	elements_read: usize,
+
+	/// 1 for a top-level sequence, 2 for a sequence inside a sequence, and so on.
+	/// 
+	/// There are only two delimiters, so sequences nested any deeper than 2 have only one element.
+	depth: u8,
+
+	/// How many elements the sequence probably has, counting from the first, if known, so that the visitor can make room for them all at once. See `Deserializer::peek_seq_len`.
+	size_hint: Option<usize>
+}
+
+impl<'a, R: BufRead> AaValueSeqAccess<'a, R> {
+	/// Checks whether the next element is an empty one at the end of the sequence that should be dropped, according to `DeserializerBuilder::trailing_empty_element`.
+	fn is_dropped_trailing_element(&mut self) -> Result<bool> {
+		let next_byte = self.de.peek_byte()?;
+
+		Ok(match self.de.config.trailing_empty_element {
+			TrailingEmptyElement::Keep => false,
+			TrailingEmptyElement::DropAtEof => next_byte.is_none(),
+			TrailingEmptyElement::Drop => matches!(next_byte, None | Some(b'\r') | Some(b'\n'))
+		})
+	}
 }
 
 impl<'de, 'a, R: BufRead> SeqAccess<'de> for AaValueSeqAccess<'a, R> {
@@ -222,24 +350,43 @@ impl<'de, 'a, R: BufRead> SeqAccess<'de> for AaValueSeqAccess<'a, R> {
 
 	fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
 	where T: DeserializeSeed<'de> {
+		let sub_seq_delimiter = self.de.config.sub_seq_delimiter;
+
 		if
-			// Nested sequences have only one element.
-			(self.is_nested_seq && !self.is_first_element) ||
+			// A nested sequence ends when its previous element was ended by something other than the nested-sequence delimiter. Sequences nested even deeper have only one element.
+			(self.depth >= 2 && !self.is_first_element && (self.depth > 2 || self.de.last_delim != Some(sub_seq_delimiter))) ||
 			// We've reached the end of the sequence.
-			self.de.pos.column == 1 || self.de.reached_eof ||
+			self.de.pos.byte_column == 1 || self.de.reached_eof ||
 			// This is an empty sequence. That is, this is the first element, and the next call to `read_byte` will yield either end-of-file or a line ending.
-			(self.is_first_element && self.de.peek_byte()?.filter(|b| *b != b'\r' && *b != b'\n').is_none())
+			(self.is_first_element && self.de.peek_byte()?.filter(|b| *b != b'\r' && *b != b'\n').is_none()) ||
+			// This is an empty element after a trailing delimiter, and we've been asked to drop those.
+			(!self.is_first_element && self.is_dropped_trailing_element()?)
 		{
+			if self.is_first_element && self.depth >= 2 && !self.de.reached_eof && self.de.pos.byte_column != 1 {
+				// This is an empty nested sequence at the end of its parent, as in `a,b|`. It still has to be read, along with the line ending after it, or else the parent never ends.
+				AaValueDeserializer {
+					de: self.de,
+					seq_depth: self.depth
+				}.fill_buf_auto()?;
+			}
+
 			Ok(None)
 		}
 		else {
-			// There's another element in the sequence, so let's pass it along.
+			// There's another element in the sequence, so let's pass it along. That is, unless there are too many.
+			self.elements_read += 1;
+			self.de.check_limit(Limit::SeqElements, self.de.config.max_seq_elements, self.elements_read)?;
+
 			let ret = seed.deserialize(AaValueDeserializer {
 				de: self.de,
-				inside_seq: true
+				seq_depth: self.depth
 			}).map(Some);
 			self.is_first_element = false;
 			ret
 		}
 	}
+
+	fn size_hint(&self) -> Option<usize> {
+		self.size_hint.map(|len| len.saturating_sub(self.elements_read))
+	}
 }
@@ -0,0 +1,120 @@
+use super::detect_encoding;
+
+/// How much some input looks like a `.aa` file, from 0 (not at all) to 1 (every line is just what a `.aa` file would have). Returned by `detect`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+pub struct Confidence(pub f32);
+
+impl Confidence {
+	/// Whether the input is more likely a `.aa` file than not, which is to say, whether the confidence is at least 0.5.
+	pub fn is_likely(self) -> bool {
+		self.0 >= 0.5
+	}
+}
+
+/// Guesses whether some input is a `.aa` file, from a sample of it, such as the first few kilobytes. This is for importers that are pointed at a directory full of who knows what, and need to pick out the `.aa` files without parsing every file all the way through.
+/// 
+/// Each line of the sample is judged on its own. Lines like `key: value`, comments, and section markers like `[Section]` count in favor; so do lines with only a key on them, though less so, since any one-word line looks like that. Anything else, like a line of CSV or a line that starts with `{` or `<`, counts against. Blank lines don't count either way. The result is the share of lines in favor, scaled down if the bytes don't look like text in any encoding that `.aa` files come in (see `detect_encoding`).
+/// 
+/// Input with a NUL byte in it is never a `.aa` file, and neither is input that doesn't have a single `key: value` line. A line cut off at the end of the sample isn't judged, so the sample can be cut anywhere.
+/// 
+/// This is a heuristic, and a cheap one. Some other formats, like simple YAML files, look just like `.aa` files, and are detected as such.
+pub fn detect(sample: &[u8]) -> Confidence {
+	if sample.is_empty() || sample.contains(&0) {
+		return Confidence(0.0)
+	}
+
+	let sample = sample.strip_prefix(b"\xef\xbb\xbf").unwrap_or(sample);
+
+	let mut lines: Vec<&[u8]> = sample.split(|byte| *byte == b'\n' || *byte == b'\r').collect();
+
+	// The last line may have been cut off. If there's more than one, don't judge it.
+	if lines.len() > 1 && !sample.ends_with(b"\n") && !sample.ends_with(b"\r") {
+		lines.pop();
+	}
+
+	let mut key_value_lines = 0usize;
+	let mut score = 0.0f32;
+	let mut judged = 0usize;
+
+	for line in lines {
+		let trimmed = line.trim_ascii();
+
+		if trimmed.is_empty() {
+			continue
+		}
+
+		judged += 1;
+
+		if trimmed.starts_with(b"#") {
+			score += 1.0;
+			continue
+		}
+
+		if trimmed.len() > 2 && trimmed.starts_with(b"[") && trimmed.ends_with(b"]") && !trimmed.contains(&b' ') {
+			// A section marker. See `SectionSyntax::Brackets`.
+			score += 1.0;
+			continue
+		}
+
+		if matches!(line[0], b'{' | b'}' | b'[' | b'<' | b'"' | b'-') {
+			// JSON, XML, quoted CSV, YAML lists, and so on.
+			continue
+		}
+
+		match line.iter().position(|byte| *byte == b':') {
+			Some(colon) if is_plausible_key(&line[..colon]) => {
+				key_value_lines += 1;
+				score += 1.0;
+			},
+			Some(_) => {},
+			None if is_plausible_key(line) && !line.contains(&b' ') && !line.contains(&b',') => {
+				// A key with no value, or just a word.
+				score += 0.5;
+			},
+			None => {}
+		}
+	}
+
+	if key_value_lines == 0 {
+		return Confidence(0.0)
+	}
+
+	let encoding = detect_encoding(sample);
+	Confidence(score / judged as f32 * (0.5 + 0.5 * encoding.confidence))
+}
+
+/// Whether some bytes could be a key: not empty, not too long, not starting with whitespace, and without control characters or characters that `.aa` keys never have.
+fn is_plausible_key(key: &[u8]) -> bool {
+	!key.is_empty() &&
+	key.len() <= 100 &&
+	!key[0].is_ascii_whitespace() &&
+	!key.iter().any(|byte| (byte.is_ascii_control() && *byte != b'\t') || matches!(byte, b'=' | b'{' | b'}' | b'<' | b'>' | b';'))
+}
+
+#[test]
+fn test_detect() {
+	fn detect_score(sample: &[u8]) -> f32 {
+		detect(sample).0
+	}
+
+	assert_eq!(detect_score(b""), 0.0);
+	assert_eq!(detect_score(b"Name: Widget\r\nPrice: 5.00\r\n"), 1.0);
+	assert_eq!(detect_score(b"# Exported\nName: Widget\n\nTaxable\n"), 2.5 / 3.0);
+	assert_eq!(detect_score(b"\xef\xbb\xbfName: Widget\n"), 1.0);
+
+	// A line cut off at the end isn't judged.
+	assert_eq!(detect_score(b"Name: Widget\nDescription is a long"), 1.0);
+
+	// Other formats.
+	assert_eq!(detect_score(b"{\"Name\": \"Widget\"}\n"), 0.0);
+	assert_eq!(detect_score(b"<?xml version=\"1.0\"?>\n<a>b</a>\n"), 0.0);
+	assert_eq!(detect_score(b"Name,Price\nWidget,5.00\n"), 0.0);
+	assert_eq!(detect_score(b"[section]\nname = value\n"), 0.0);
+	assert_eq!(detect_score(b"[Store]\nName: Widget\n"), 1.0);
+	assert_eq!(detect_score(b"Name: Widget\0\0\0"), 0.0);
+	assert!(!detect(b"Name,Price\nWidget,5.00\nTime: 12:00\n").is_likely());
+
+	// Bytes that are implausible in Windows-1252 and not UTF-8 count against it.
+	assert!(detect_score(b"Name: \x81\x8d\x8f\x90\n") < detect_score(b"Name: Caf\xe9\n"));
+	assert!(detect(b"Name: Caf\xe9\n").is_likely());
+}
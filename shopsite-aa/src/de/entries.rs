@@ -0,0 +1,154 @@
+use serde::de::{
+	DeserializeSeed,
+	IgnoredAny,
+	IntoDeserializer,
+	MapAccess,
+	SeqAccess,
+	Visitor
+};
+use std::io::BufRead;
+use super::{
+	AaTopMapAccess,
+	Deserializer,
+	Error,
+	Result,
+	Value,
+	trace_result
+};
+
+/// Iterator over the entries of a `.aa` file, one key-value pair at a time. Returned by `Deserializer::entries`.
+/// 
+/// After an error, the iterator ends. Errors in `.aa` files are almost always I/O errors, and there's no telling where in the file the reader is after one of those.
+pub struct Entries<'a, R> {
+	access: AaTopMapAccess<'a, R>,
+	done: bool
+}
+
+impl<R: BufRead> Deserializer<R> {
+	/// Reads the file one entry at a time, instead of all at once.
+	/// 
+	/// Only one entry is held in memory at a time, so this is suitable for enormous files, like dumps of a large store's product database.
+	/// 
+	/// Each value is read as if it were being deserialized into a `Value`, so it'll be either `Value::String` or, for keys with no value, `Value::Unit`.
+	pub fn entries(&mut self) -> Entries<'_, R> {
+		Entries {
+			access: AaTopMapAccess::new(self),
+			done: false
+		}
+	}
+}
+
+impl<'a, R: BufRead> Entries<'a, R> {
+	fn next_entry(&mut self) -> Result<Option<(String, Value)>> {
+		trace_result(self.access.next_entry())
+	}
+}
+
+impl<'a, R: BufRead> Iterator for Entries<'a, R> {
+	type Item = Result<(String, Value)>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None
+		}
+
+		let result = self.next_entry().transpose();
+
+		// Stop after the last entry or the first error.
+		if !matches!(result, Some(Ok(_))) {
+			self.done = true;
+		}
+
+		result
+	}
+}
+
+impl<'a, R: BufRead> std::iter::FusedIterator for Entries<'a, R> {}
+
+/// Hands over the entries of a map as a sequence of key-value pairs, for deserializing the top level of a file as a sequence, such as a `Vec<(String, Value)>`. Unlike a map, this keeps repeated keys and the order of the keys, whatever type the entries are collected into.
+pub(super) struct EntrySeqAccess<A> {
+	map: A
+}
+
+impl<A> EntrySeqAccess<A> {
+	pub(super) fn new(map: A) -> EntrySeqAccess<A> {
+		EntrySeqAccess { map }
+	}
+}
+
+impl<'de, A: MapAccess<'de, Error = Error>> SeqAccess<'de> for EntrySeqAccess<A> {
+	type Error = Error;
+
+	fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+	where T: DeserializeSeed<'de> {
+		match self.map.next_key::<String>()? {
+			Some(key) => seed.deserialize(EntryDeserializer { map: &mut self.map, key }).map(Some),
+			None => Ok(None)
+		}
+	}
+}
+
+/// Deserializer for one entry of an `EntrySeqAccess`. The entry is a sequence of two elements: the key, then the value.
+struct EntryDeserializer<'a, A> {
+	map: &'a mut A,
+	key: String
+}
+
+impl<'de, 'a, A: MapAccess<'de, Error = Error>> serde::Deserializer<'de> for EntryDeserializer<'a, A> {
+	type Error = Error;
+
+	fn is_human_readable(&self) -> bool { true }
+
+	fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		let mut access = EntryAccess {
+			map: self.map,
+			key: Some(self.key),
+			value_read: false
+		};
+
+		let result = visitor.visit_seq(&mut access)?;
+
+		// If the visitor didn't want the value, skip it anyway, or else the map would take it for the next key.
+		if !access.value_read {
+			access.map.next_value::<IgnoredAny>()?;
+		}
+
+		Ok(result)
+	}
+
+	serde::forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf option unit unit_struct newtype_struct seq tuple
+		tuple_struct map struct enum identifier ignored_any
+	}
+}
+
+/// The two elements of an entry, for `EntryDeserializer`.
+struct EntryAccess<'a, A> {
+	map: &'a mut A,
+	key: Option<String>,
+	value_read: bool
+}
+
+impl<'de, 'a, A: MapAccess<'de, Error = Error>> SeqAccess<'de> for &mut EntryAccess<'a, A> {
+	type Error = Error;
+
+	fn next_element_seed<T>(&mut self, seed: T) -> Result<Option<T::Value>>
+	where T: DeserializeSeed<'de> {
+		if let Some(key) = self.key.take() {
+			seed.deserialize(key.into_deserializer()).map(Some)
+		}
+		else if !self.value_read {
+			self.value_read = true;
+			self.map.next_value_seed(seed).map(Some)
+		}
+		else {
+			Ok(None)
+		}
+	}
+
+	fn size_hint(&self) -> Option<usize> {
+		Some(self.key.is_some() as usize + !self.value_read as usize)
+	}
+}
@@ -1,15 +1,16 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 use std::{
 	borrow::Cow,
 	io,
 	num::{ParseFloatError, ParseIntError},
-	rc::Rc,
 	path::Path,
-	str::ParseBoolError
+	str::ParseBoolError,
+	sync::Arc
 };
 use super::Position;
 
-/// Takes an `Option<Rc<Path>>` (like in the `Position` type) and turns it into a `str`.
-pub(super) fn rc_path_to_str(file: &Option<Rc<Path>>) -> Cow<str> {
+/// Takes an `Option<Arc<Path>>` (like in the `Position` type) and turns it into a `str`.
+pub(super) fn arc_path_to_str(file: &Option<Arc<Path>>) -> Cow<'_, str> {
 	if let Some(ref file) = file {
 		file.as_os_str().to_string_lossy()
 	}
@@ -18,16 +19,37 @@ pub(super) fn rc_path_to_str(file: &Option<Rc<Path>>) -> Cow<str> {
 	}
 }
 
+/// Describes where an I/O error happened, for `Error::Io`'s error message.
+fn io_error_location(file: &Option<Arc<Path>>, pos: &Option<Position>) -> String {
+	match pos {
+		Some(pos) => pos.to_string(),
+		None => arc_path_to_str(file).into_owned()
+	}
+}
+
+/// Describes where a field was found to be missing, for `Error::MissingField`'s error message.
+fn missing_field_location(pos: &Option<Position>) -> String {
+	match pos {
+		Some(pos) => format!("{}: ", pos),
+		None => String::new()
+	}
+}
+
 /// An error that occurred during reading, parsing, or deserialization.
 #[derive(Debug, derive_more::Display, derive_more::Error)]
 #[non_exhaustive]
 pub enum Error {
 	Other(#[error(ignore)] Cow<'static, str>),
 
-	#[display(fmt = "{}: I/O error: {}", "rc_path_to_str(file)", error)]
+	#[display(fmt = "{}: I/O error: {}", "io_error_location(file, pos)", error)]
 	Io {
 		error: io::Error,
-		file: Option<Rc<Path>>
+
+		/// The file that was being read.
+		file: Option<Arc<Path>>,
+
+		/// How far parsing had got when the error occurred, or `None` if it hadn't started yet, as when the file couldn't be opened. If a download was cut off partway, this is about where it was cut off.
+		pos: Option<Position>
 	},
 
 	#[display(fmt = "{}: {}", pos, error)]
@@ -51,6 +73,242 @@ pub enum Error {
 	#[display(fmt = "{}: unexpected text before end of file", pos)]
 	UnexpectedText {
 		pos: Position
+	},
+
+	/// A key has no value, and `DeserializerBuilder::key_only_line` says that's an error. The position is the line the key is on, and names the key.
+	#[display(fmt = "{}: key has no value", pos)]
+	MissingValue {
+		pos: Position
+	},
+
+	/// The input starts with a UTF-8 byte order mark, and `DeserializerBuilder::bom` says to reject it.
+	#[display(fmt = "{}: unexpected byte order mark", pos)]
+	UnexpectedBom {
+		pos: Position
+	},
+
+	/// One of the limits set with `DeserializerBuilder` was exceeded. The position is where the parser was when it noticed, which is at or a little past the point where the limit was exceeded.
+	#[display(fmt = "{}: {} exceeds the limit of {}", pos, limit, max)]
+	LimitExceeded {
+		limit: Limit,
+		max: usize,
+		pos: Position
+	},
+
+	/// A field that the type being deserialized requires was not in the input. The position is the line of the last key that was read before the parser gave up looking for it, which is usually the last line of the record or section that should have had it. It's `None` only if the error didn't come from this deserializer's input, as with `from_value`.
+	#[display(fmt = "{}missing field `{}`", "missing_field_location(pos)", field)]
+	MissingField {
+		field: &'static str,
+		pos: Option<Position>
+	}
+}
+
+/// Which limit was exceeded, in an `Error::LimitExceeded`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, derive_more::Display)]
+#[non_exhaustive]
+pub enum Limit {
+	/// The number of bytes in a line. See `DeserializerBuilder::max_line_length`.
+	#[display(fmt = "line length")]
+	LineLength,
+
+	/// The number of bytes in a key, value, or sequence element. See `DeserializerBuilder::max_value_size`.
+	#[display(fmt = "value size")]
+	ValueSize,
+
+	/// The number of elements in a sequence. See `DeserializerBuilder::max_seq_elements`.
+	#[display(fmt = "number of sequence elements")]
+	SeqElements,
+
+	/// The number of keys in the input. See `DeserializerBuilder::max_keys`.
+	#[display(fmt = "number of keys")]
+	Keys
+}
+
+/// What kind of error an `Error` is, without the details. See `Error::kind`.
+/// 
+/// Each kind has a code, like `AA0003`, that stays the same from one version of this library to the next, so that programs can pick out particular kinds of errors, such as to ignore them, by something that won't change when the error messages are reworded.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum ErrorKind {
+	/// `Error::Other`, such as a value of the wrong type, or an unknown enum variant.
+	Other,
+
+	/// `Error::Io`.
+	Io,
+
+	/// `Error::InvalidBool`.
+	InvalidBool,
+
+	/// `Error::InvalidInt`.
+	InvalidInt,
+
+	/// `Error::InvalidFloat`.
+	InvalidFloat,
+
+	/// `Error::UnexpectedText`.
+	UnexpectedText,
+
+	/// `Error::LimitExceeded`.
+	LimitExceeded,
+
+	/// `Error::MissingField`.
+	MissingField,
+
+	/// `Error::UnexpectedBom`.
+	UnexpectedBom,
+
+	/// `Error::MissingValue`.
+	MissingValue
+}
+
+impl ErrorKind {
+	/// Every kind of error, in order of their codes.
+	pub const ALL: &'static [ErrorKind] = &[
+		ErrorKind::Other,
+		ErrorKind::Io,
+		ErrorKind::InvalidBool,
+		ErrorKind::InvalidInt,
+		ErrorKind::InvalidFloat,
+		ErrorKind::UnexpectedText,
+		ErrorKind::LimitExceeded,
+		ErrorKind::MissingField,
+		ErrorKind::UnexpectedBom,
+		ErrorKind::MissingValue
+	];
+
+	/// The stable code for this kind of error, like `AA0003`. This is also what `Display` writes.
+	pub fn code(self) -> &'static str {
+		match self {
+			ErrorKind::Other => "AA0000",
+			ErrorKind::Io => "AA0001",
+			ErrorKind::InvalidBool => "AA0002",
+			ErrorKind::InvalidInt => "AA0003",
+			ErrorKind::InvalidFloat => "AA0004",
+			ErrorKind::UnexpectedText => "AA0005",
+			ErrorKind::LimitExceeded => "AA0006",
+			ErrorKind::MissingField => "AA0007",
+			ErrorKind::UnexpectedBom => "AA0008",
+			ErrorKind::MissingValue => "AA0009"
+		}
+	}
+}
+
+impl std::fmt::Display for ErrorKind {
+	fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+		f.write_str(self.code())
+	}
+}
+
+impl std::str::FromStr for ErrorKind {
+	type Err = String;
+
+	/// Parses an error code, like `AA0003`. Letter case is ignored.
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		ErrorKind::ALL.iter()
+		.find(|kind| kind.code().eq_ignore_ascii_case(s))
+		.copied()
+		.ok_or_else(|| format!("unrecognized error code “{}”; expected one like AA0003", s))
+	}
+}
+
+impl Error {
+	/// What kind of error this is. See `ErrorKind`.
+	pub fn kind(&self) -> ErrorKind {
+		match self {
+			Error::Other(_) => ErrorKind::Other,
+			Error::Io { .. } => ErrorKind::Io,
+			Error::InvalidBool { .. } => ErrorKind::InvalidBool,
+			Error::InvalidFloat { .. } => ErrorKind::InvalidFloat,
+			Error::InvalidInt { .. } => ErrorKind::InvalidInt,
+			Error::UnexpectedText { .. } => ErrorKind::UnexpectedText,
+			Error::UnexpectedBom { .. } => ErrorKind::UnexpectedBom,
+			Error::MissingValue { .. } => ErrorKind::MissingValue,
+			Error::LimitExceeded { .. } => ErrorKind::LimitExceeded,
+			Error::MissingField { .. } => ErrorKind::MissingField
+		}
+	}
+
+	/// The stable code for this kind of error, like `AA0003`. Short for `self.kind().code()`.
+	pub fn code(&self) -> &'static str {
+		self.kind().code()
+	}
+
+	/// Makes an `Error::Io` for an error that occurred while parsing, at the given position.
+	pub(super) fn io(error: io::Error, pos: &Position) -> Error {
+		Error::Io {
+			error,
+			file: pos.file.clone(),
+			pos: Some(pos.clone())
+		}
+	}
+
+	/// Gets the position in the input where the error occurred, if known.
+	pub fn position(&self) -> Option<&Position> {
+		match self {
+			Error::InvalidBool { pos, .. } |
+			Error::InvalidFloat { pos, .. } |
+			Error::InvalidInt { pos, .. } |
+			Error::UnexpectedText { pos } |
+			Error::UnexpectedBom { pos } |
+			Error::MissingValue { pos } |
+			Error::LimitExceeded { pos, .. } => Some(pos),
+			Error::Io { pos, .. } |
+			Error::MissingField { pos, .. } => pos.as_ref(),
+			Error::Other(_) => None
+		}
+	}
+
+	/// Notes that this error occurred in the value of the given key, so that the error message says which key it was. If the error already names a key, that key is taken to be inside this one, as with a key in a section.
+	/// 
+	/// Errors that have a `Position` get the key there. Other errors, like those from a `Deserialize` implementation, get it at the beginning of the message. I/O errors have nothing to do with any particular key, so they are left alone, as are missing fields that haven't been given a position yet.
+	pub(super) fn in_key(mut self, key: &str) -> Error {
+		match self {
+			Error::InvalidBool { ref mut pos, .. } |
+			Error::InvalidFloat { ref mut pos, .. } |
+			Error::InvalidInt { ref mut pos, .. } |
+			Error::UnexpectedText { ref mut pos } |
+			Error::UnexpectedBom { ref mut pos } |
+			Error::MissingValue { ref mut pos } |
+			Error::LimitExceeded { ref mut pos, .. } |
+			Error::MissingField { pos: Some(ref mut pos), .. } => {
+				pos.key = Some(match pos.key.take() {
+					Some(inner) => format!("{}.{}", key, inner),
+					None => key.to_string()
+				});
+				self
+			},
+			Error::Other(msg) => Error::Other(format!("{}: {}", key, msg).into()),
+			Error::Io { .. } |
+			Error::MissingField { pos: None, .. } => self
+		}
+	}
+}
+
+impl Serialize for Error {
+	/// Serializes the error as a report for machines to read, such as a JSON response from a service that accepts uploaded `.aa` files. It's a struct with these fields:
+	/// 
+	/// * `code`: The error's stable code, like `AA0003`. See `ErrorKind`.
+	/// * `message`: The error message, as `Display` writes it, position and all.
+	/// * `file`: The file that the error is in, if known, as in `Position`.
+	/// * `line`, `column`, `byte_column`, `key`: Where in the file the error is, as in `Position`, or `None` if that isn't known.
+	/// 
+	/// The fields are always there, so that every error has the same shape, even if some of them are `None`.
+	fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+		let pos = self.position();
+		let file = match self {
+			Error::Io { file, .. } => file.as_ref(),
+			_ => pos.and_then(|pos| pos.file.as_ref())
+		};
+
+		let mut s = serializer.serialize_struct("Error", 7)?;
+		s.serialize_field("code", self.code())?;
+		s.serialize_field("message", &self.to_string())?;
+		s.serialize_field("file", &file.map(|file| file.to_string_lossy()))?;
+		s.serialize_field("line", &pos.map(|pos| pos.line))?;
+		s.serialize_field("column", &pos.map(|pos| pos.column))?;
+		s.serialize_field("byte_column", &pos.map(|pos| pos.byte_column))?;
+		s.serialize_field("key", &pos.and_then(|pos| pos.key.as_ref()))?;
+		s.end()
 	}
 }
 
@@ -58,6 +316,16 @@ impl serde::de::Error for Error {
 	fn custom<T: std::fmt::Display>(msg: T) -> Self {
 		Error::Other(msg.to_string().into())
 	}
+
+	fn missing_field(field: &'static str) -> Self {
+		Error::MissingField { field, pos: None }
+	}
+}
+
+impl serde::ser::Error for Error {
+	fn custom<T: std::fmt::Display>(msg: T) -> Self {
+		Error::Other(msg.to_string().into())
+	}
 }
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -0,0 +1,68 @@
+use std::{
+	collections::HashMap,
+	io::{BufRead, BufReader, Read}
+};
+use super::{
+	Deserializer,
+	Result,
+	trace_result
+};
+
+impl<R: BufRead> Deserializer<R> {
+	/// Reads the values of just the given keys, stopping as soon as all of them have been found, rather than parsing the rest of the input. This is for tools that need a setting or two out of a big file, like the store's name and email address out of its configuration.
+	/// 
+	/// Keys are compared before they're decoded, and the values of other keys are skipped without being decoded, so this is about as quick as reading the input can be. Values are decoded as text, with the options this `Deserializer` was built with. A key with no value has an empty one.
+	/// 
+	/// If a key appears more than once, the first value wins, since the input isn't read any further than it has to be. (Deserializing into a map, the last would win.) Keys that aren't in the input aren't in the returned map. Records and sections make no difference; the input is searched from wherever the parser is now, to the end if need be.
+	pub fn extract(&mut self, keys: &[&str]) -> Result<HashMap<String, String>> {
+		// The keys that haven't been found yet, encoded like the keys in the input. A key that can't be encoded can't be in the input.
+		let mut wanted: Vec<(&str, Vec<u8>)> = keys.iter()
+		.filter_map(|key| self.config.encoding.encode(key).map(|encoded| (*key, encoded.into_owned())))
+		.collect();
+
+		let mut found = HashMap::with_capacity(wanted.len());
+
+		while !wanted.is_empty() {
+			if self.pending_key.is_none() && !trace_result(self.read_key_undecoded())? {
+				break
+			}
+
+			let no_value = self.pending_key.take().unwrap_or_default();
+
+			// Not interested in this one? The rest of the line is skipped when reading the next key.
+			let index = match wanted.iter().position(|(_, encoded)| *encoded == self.buf_b) {
+				Some(index) => index,
+				None => continue
+			};
+
+			let (key, _) = wanted.swap_remove(index);
+
+			let value = {
+				if no_value {
+					String::new()
+				}
+				else {
+					trace_result(self.fill_buf(&[], None))?;
+					self.decode_buf_all_owned()
+				}
+			};
+
+			found.insert(key.to_string(), value);
+		}
+
+		Ok(found)
+	}
+}
+
+/// Reads the values of just the given keys from a `.aa` file, with the default options, stopping as soon as all of them have been found. See `Deserializer::extract`.
+/// 
+/// ```
+/// use shopsite_aa::de as aa;
+/// 
+/// let config = b"StoreName: Widgets Inc.\nEmail: sales@example.com\nProductCount: 5000\n";
+/// let settings = aa::extract(&config[..], &["StoreName", "Email"]).unwrap();
+/// assert_eq!(settings["Email"], "sales@example.com");
+/// ```
+pub fn extract<R: Read>(reader: R, keys: &[&str]) -> Result<HashMap<String, String>> {
+	Deserializer::new(BufReader::new(reader), None).extract(keys)
+}
@@ -0,0 +1,152 @@
+use encoding::{all::WINDOWS_1252, DecoderTrap, EncoderTrap, Encoding};
+use std::{
+	borrow::Cow,
+	str::FromStr
+};
+
+/// Character encodings that `.aa` files can be read in.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq, derive_more::Display)]
+#[non_exhaustive]
+pub enum InputEncoding {
+	/// The encoding that ShopSite uses for everything. This is the default.
+	#[default]
+	#[display(fmt = "windows-1252")]
+	Windows1252,
+
+	/// UTF-8, as in `.aa` files written by other programs, or converted by someone. Invalid UTF-8 is decoded as `U+FFFD`.
+	#[display(fmt = "utf-8")]
+	Utf8
+}
+
+impl FromStr for InputEncoding {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"windows-1252" => Ok(InputEncoding::Windows1252),
+			"utf-8" => Ok(InputEncoding::Utf8),
+			_ => Err(format!("unrecognized encoding “{}”; expected windows-1252 or utf-8", s))
+		}
+	}
+}
+
+impl InputEncoding {
+	/// Decodes `bytes`, appending the text to `s`. Neither encoding can fail to decode; anything invalid is replaced with `U+FFFD`.
+	pub(crate) fn decode_to(self, bytes: &[u8], s: &mut String) {
+		match self {
+			// The infallibility of Windows-1252 decoding is verified by a unit test, below.
+			InputEncoding::Windows1252 => WINDOWS_1252.decode_to(bytes, DecoderTrap::Replace, s).unwrap(),
+			InputEncoding::Utf8 => s.push_str(&String::from_utf8_lossy(bytes))
+		}
+	}
+
+	/// Decodes `bytes` into a new `String`.
+	pub(crate) fn decode(self, bytes: &[u8]) -> String {
+		match self {
+			InputEncoding::Windows1252 => WINDOWS_1252.decode(bytes, DecoderTrap::Replace).unwrap(),
+			InputEncoding::Utf8 => String::from_utf8_lossy(bytes).into_owned()
+		}
+	}
+
+	/// Encodes `s`, or returns `None` if it has characters that this encoding can't represent.
+	pub(crate) fn encode(self, s: &str) -> Option<Cow<'_, [u8]>> {
+		match self {
+			InputEncoding::Windows1252 => WINDOWS_1252.encode(s, EncoderTrap::Strict).ok().map(Cow::Owned),
+			InputEncoding::Utf8 => Some(Cow::Borrowed(s.as_bytes()))
+		}
+	}
+}
+
+/// The result of `detect_encoding`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DetectedEncoding {
+	/// The encoding that the input is most likely in.
+	pub encoding: InputEncoding,
+
+	/// How sure `detect_encoding` is about it, from 0 (a wild guess) to 1 (certain). Input that's entirely ASCII reads the same in every supported encoding, so its confidence is 1.
+	pub confidence: f32
+}
+
+/// Guesses which encoding some input is in, from a sample of it, such as the first few kilobytes of a file.
+/// 
+/// Input with a UTF-8 byte order mark is UTF-8. Otherwise, input that is valid UTF-8 and has non-ASCII characters in it is probably UTF-8, since Windows-1252 text almost never happens to be valid UTF-8; the more such characters there are, the more certain the guess. Input that isn't valid UTF-8 is probably Windows-1252, with confidence according to how many of its non-ASCII bytes are letters or punctuation, as opposed to control characters and bytes that Windows-1252 doesn't define.
+/// 
+/// A UTF-8 character cut off at the end of the sample doesn't count against UTF-8, so the sample can be cut anywhere.
+pub fn detect_encoding(sample: &[u8]) -> DetectedEncoding {
+	if sample.starts_with(b"\xef\xbb\xbf") {
+		return DetectedEncoding { encoding: InputEncoding::Utf8, confidence: 1.0 }
+	}
+
+	let valid_utf8 = match std::str::from_utf8(sample) {
+		Ok(text) => Some(text),
+		// An incomplete character at the end is fine. Any other error means this isn't UTF-8.
+		Err(error) if error.error_len().is_none() => Some(std::str::from_utf8(&sample[..error.valid_up_to()]).unwrap_or_default()),
+		Err(_) => None
+	};
+
+	if let Some(text) = valid_utf8 {
+		let non_ascii_chars = text.chars().filter(|c| !c.is_ascii()).count();
+
+		return {
+			if non_ascii_chars == 0 {
+				DetectedEncoding { encoding: InputEncoding::Windows1252, confidence: 1.0 }
+			}
+			else {
+				// Each multi-byte character is more evidence. One is a fair bet; a handful is near certainty.
+				DetectedEncoding { encoding: InputEncoding::Utf8, confidence: 1.0 - 0.5f32.powi(non_ascii_chars.min(24) as i32 + 1) }
+			}
+		}
+	}
+
+	// Not UTF-8, so it's Windows-1252, or something we can't read anyway. Judge how much it looks like text.
+	let (plausible, total) = sample.iter()
+		.filter(|byte| !byte.is_ascii() || (byte.is_ascii_control() && !matches!(byte, b'\t' | b'\r' | b'\n')))
+		.fold((0usize, 0usize), |(plausible, total), &byte| {
+			let is_plausible = byte >= 0x80 && !matches!(byte, 0x81 | 0x8d | 0x8f | 0x90 | 0x9d);
+			(plausible + is_plausible as usize, total + 1)
+		});
+
+	DetectedEncoding {
+		encoding: InputEncoding::Windows1252,
+		confidence: plausible as f32 / total.max(1) as f32
+	}
+}
+
+#[test]
+fn test_detect_encoding() {
+	fn detect(sample: &[u8]) -> (InputEncoding, f32) {
+		let detected = detect_encoding(sample);
+		(detected.encoding, detected.confidence)
+	}
+
+	assert_eq!(detect(b""), (InputEncoding::Windows1252, 1.0));
+	assert_eq!(detect(b"Name: Widget\r\n"), (InputEncoding::Windows1252, 1.0));
+	assert_eq!(detect(b"\xef\xbb\xbfName: Widget\n"), (InputEncoding::Utf8, 1.0));
+	assert_eq!(detect("Name: Café\n".as_bytes()), (InputEncoding::Utf8, 0.75));
+	assert!(detect("Name: Café Crème Brûlée\n".as_bytes()).1 > 0.95);
+	assert_eq!(detect(b"Name: Caf\xe9\n"), (InputEncoding::Windows1252, 1.0));
+	assert_eq!(detect(b"Name: \x81\x00\xe9\x90"), (InputEncoding::Windows1252, 0.25));
+
+	// A character cut off at the end of the sample is still UTF-8.
+	assert_eq!(detect(&"Name: é".as_bytes()[..7]), (InputEncoding::Windows1252, 1.0));
+	assert_eq!(detect(&"é Name: é".as_bytes()[..10]).0, InputEncoding::Utf8);
+}
+
+#[test]
+fn test_decoding_windows_1252_cannot_fail() {
+	// We assume above that decoding Windows-1252 can never fail. This verifies that that's actually true by throwing every single Windows-1252 code point at the decoder.
+
+	// Assemble the byte array.
+	let mut bytes = [0u8; 256];
+	for i in 0u8..=255u8 {
+		bytes[i as usize] = i;
+	}
+	
+	// Check that we assembled the byte array correctly.
+	assert_eq!(bytes[0], 0u8);
+	assert_eq!(bytes[127], 127u8);
+	assert_eq!(bytes[255], 255u8);
+
+	// Now, throw it at the decoder and make sure it doesn't fail. The decoder's output doesn't actually matter here, just that it succeeds.
+	WINDOWS_1252.decode(&bytes[..], DecoderTrap::Replace).expect("Decoding Windows-1252 should never fail!");
+}
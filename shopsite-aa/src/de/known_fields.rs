@@ -0,0 +1,65 @@
+use std::sync::Arc;
+use super::InputEncoding;
+
+/// The names of the fields of a struct, encoded the same way the keys in the input are, so that keys can be checked against them without decoding the keys. Used to skip keys that aren't fields; see `DeserializerBuilder::skip_unknown_keys`.
+/// 
+/// The names are kept sorted, and looked up by binary search. Structs have few enough fields that this is quicker than hashing each key, which would mean looking at every byte of it, when most keys differ from most field names in the first few bytes.
+#[derive(Debug)]
+pub(super) struct KnownFields {
+	/// The `fields` that this was made from, to tell whether it can be used again for the next struct. These come from `#[derive(Deserialize)]`, so the same struct always passes the same slice.
+	fields: &'static [&'static str],
+
+	/// The encoding that the names were encoded in.
+	encoding: InputEncoding,
+
+	/// The encoded names, sorted.
+	names: Vec<Vec<u8>>
+}
+
+impl KnownFields {
+	/// Encodes and sorts the given field names. A field name that can't be encoded can't appear in the input, so it's left out.
+	pub(super) fn new(fields: &'static [&'static str], encoding: InputEncoding) -> KnownFields {
+		let mut names: Vec<Vec<u8>> = fields.iter()
+		.filter_map(|field| encoding.encode(field))
+		.map(|field| field.into_owned())
+		.collect();
+
+		names.sort_unstable();
+		names.dedup();
+
+		KnownFields { fields, encoding, names }
+	}
+
+	/// Gets the `KnownFields` for the given field names, reusing `cached` if it was made from the same ones, and otherwise replacing it with a new one. When a file has many records of the same type, this saves encoding the field names all over again for each one.
+	pub(super) fn cached(cached: &mut Option<Arc<KnownFields>>, fields: &'static [&'static str], encoding: InputEncoding) -> Arc<KnownFields> {
+		match cached {
+			Some(known) if std::ptr::eq(known.fields, fields) && known.encoding == encoding => known.clone(),
+			_ => cached.insert(Arc::new(KnownFields::new(fields, encoding))).clone()
+		}
+	}
+
+	/// Whether `key`, as it appears in the input, is one of the fields.
+	pub(super) fn contains(&self, key: &[u8]) -> bool {
+		self.names.binary_search_by(|name| name.as_slice().cmp(key)).is_ok()
+	}
+}
+
+#[test]
+fn test_known_fields() {
+	static FIELDS: &[&str] = &["Price", "Name", "Café", "SKU", "Name", "☃"];
+
+	let known = KnownFields::new(FIELDS, InputEncoding::Windows1252);
+	assert!(known.contains(b"Name"));
+	assert!(known.contains(b"SKU"));
+	assert!(known.contains(b"Caf\xe9"));
+	assert!(!known.contains("Café".as_bytes()));
+	assert!(!known.contains(b"name"));
+	assert!(!known.contains(b""));
+	assert_eq!(known.names.len(), 4);
+
+	let mut cache = None;
+	let first = KnownFields::cached(&mut cache, FIELDS, InputEncoding::Windows1252);
+	assert!(Arc::ptr_eq(&first, &KnownFields::cached(&mut cache, FIELDS, InputEncoding::Windows1252)));
+	assert!(!Arc::ptr_eq(&first, &KnownFields::cached(&mut cache, FIELDS, InputEncoding::Utf8)));
+	assert!(KnownFields::cached(&mut cache, FIELDS, InputEncoding::Utf8).contains("Café".as_bytes()));
+}
@@ -0,0 +1,69 @@
+/// Which line endings a file uses. See `Deserializer::line_ending`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq, derive_more::Display)]
+#[non_exhaustive]
+pub enum LineEnding {
+	/// `\n`, as on Unix.
+	#[display(fmt = "LF")]
+	Lf,
+
+	/// `\r\n`, as on Windows. ShopSite writes these.
+	#[display(fmt = "CRLF")]
+	CrLf,
+
+	/// `\r` alone, as on classic Mac OS.
+	#[display(fmt = "CR")]
+	Cr,
+
+	/// More than one of the above, as in a file that was edited by hand on a different system than it was written on.
+	#[display(fmt = "mixed")]
+	Mixed
+}
+
+impl LineEnding {
+	/// The line ending itself, for writing files with the same line endings as the one that was read. A file with mixed line endings can't be reproduced exactly, so it gets `\r\n`, like ShopSite writes.
+	pub fn as_str(self) -> &'static str {
+		match self {
+			LineEnding::Lf => "\n",
+			LineEnding::CrLf | LineEnding::Mixed => "\r\n",
+			LineEnding::Cr => "\r"
+		}
+	}
+}
+
+/// Which line endings the parser has seen so far, as a set of flags.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub(super) struct LineEndingsSeen(u8);
+
+impl LineEndingsSeen {
+	const LF: u8 = 1;
+	const CRLF: u8 = 2;
+	const CR: u8 = 4;
+
+	/// Takes note of the line ending, if any, that ends with `byte`, given the byte before it. A `\r` is only known to be alone once the byte after it turns out not to be `\n`, so it's noted then (or, at the end of the file, by `note_eof`).
+	pub(super) fn note(&mut self, last_byte: u8, byte: u8) {
+		match (last_byte, byte) {
+			(b'\r', b'\n') => self.0 |= Self::CRLF,
+			(_, b'\n') => self.0 |= Self::LF,
+			(b'\r', _) => self.0 |= Self::CR,
+			_ => {}
+		}
+	}
+
+	/// Takes note of the line ending, if any, that ends the file, given the last byte of the file.
+	pub(super) fn note_eof(&mut self, last_byte: u8) {
+		if last_byte == b'\r' {
+			self.0 |= Self::CR;
+		}
+	}
+
+	/// Which kind of line endings these are, or `None` if there haven't been any.
+	pub(super) fn line_ending(self) -> Option<LineEnding> {
+		match self.0 {
+			0 => None,
+			Self::LF => Some(LineEnding::Lf),
+			Self::CRLF => Some(LineEnding::CrLf),
+			Self::CR => Some(LineEnding::Cr),
+			_ => Some(LineEnding::Mixed)
+		}
+	}
+}
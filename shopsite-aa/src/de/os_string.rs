@@ -0,0 +1,81 @@
+use serde::de::{
+	self,
+	value::SeqDeserializer,
+	DeserializeSeed,
+	EnumAccess,
+	IntoDeserializer,
+	VariantAccess,
+	Visitor
+};
+use super::{Error, InputEncoding, OsStringValue, Result};
+
+/// Name of the enum that `OsString`'s `Deserialize` implementation asks for. Its variants are the platforms, each holding that platform's representation of the string.
+pub(super) const OS_STRING_ENUM: &str = "OsString";
+
+/// Variants of the enum that `OsString`'s `Deserialize` implementation asks for. The enum has to have exactly these variants to be recognized, so that an unrelated enum that happens to be named `OsString` isn't mistaken for it.
+pub(super) const OS_STRING_VARIANTS: &[&str] = &["Unix", "Windows"];
+
+/// Hands over a value as an `OsString`, as `OsString`'s `Deserialize` implementation expects: the variant for the current platform, holding that platform's code units. On Unix, those are bytes; on Windows, they're UTF-16 code units.
+pub(super) struct OsStringAccess<T> {
+	variant: &'static str,
+	units: Vec<T>
+}
+
+#[cfg(not(windows))]
+impl OsStringAccess<u8> {
+	/// Hands over a value as an `OsString`, on Unix. Unix strings are bytes, so they can be either the value's bytes as they are in the file, or its text decoded from the input's encoding into UTF-8.
+	pub(super) fn new(bytes: &[u8], encoding: InputEncoding, mode: OsStringValue) -> OsStringAccess<u8> {
+		OsStringAccess {
+			variant: "Unix",
+			units: match mode {
+				OsStringValue::Text => encoding.decode(bytes).into_bytes(),
+				OsStringValue::Bytes => bytes.to_vec()
+			}
+		}
+	}
+}
+
+#[cfg(windows)]
+impl OsStringAccess<u16> {
+	/// Hands over a value as an `OsString`, on Windows. Windows strings are UTF-16, so the value has to be decoded, whatever the mode.
+	pub(super) fn new(bytes: &[u8], encoding: InputEncoding, _mode: OsStringValue) -> OsStringAccess<u16> {
+		OsStringAccess {
+			variant: "Windows",
+			units: encoding.decode(bytes).encode_utf16().collect()
+		}
+	}
+}
+
+impl<'de, T: IntoDeserializer<'de, Error>> EnumAccess<'de> for OsStringAccess<T> {
+	type Error = Error;
+	type Variant = Self;
+
+	fn variant_seed<V>(self, seed: V) -> Result<(V::Value, Self)>
+	where V: DeserializeSeed<'de> {
+		let variant = seed.deserialize(self.variant.into_deserializer())?;
+		Ok((variant, self))
+	}
+}
+
+impl<'de, T: IntoDeserializer<'de, Error>> VariantAccess<'de> for OsStringAccess<T> {
+	type Error = Error;
+
+	fn unit_variant(self) -> Result<()> {
+		Err(de::Error::invalid_type(de::Unexpected::NewtypeVariant, &"unit variant"))
+	}
+
+	fn newtype_variant_seed<S>(self, seed: S) -> Result<S::Value>
+	where S: DeserializeSeed<'de> {
+		seed.deserialize(SeqDeserializer::new(self.units.into_iter()))
+	}
+
+	fn tuple_variant<V>(self, _len: usize, _visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		Err(de::Error::invalid_type(de::Unexpected::NewtypeVariant, &"tuple variant"))
+	}
+
+	fn struct_variant<V>(self, _fields: &'static [&'static str], _visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		Err(de::Error::invalid_type(de::Unexpected::NewtypeVariant, &"struct variant"))
+	}
+}
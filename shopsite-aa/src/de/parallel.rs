@@ -0,0 +1,56 @@
+use rayon::prelude::*;
+use serde::de::DeserializeOwned;
+use std::{
+	path::Path,
+	sync::Arc
+};
+use super::{from_file, Result};
+
+/// Parses many files at once, on all of the machine's cores, using [`rayon`](https://docs.rs/rayon)'s global thread pool. Each file is deserialized with `from_file`, and then `f` is called with the file's path and the result, on whichever thread parsed it. What `f` returns for each file is collected into a `Vec`, in the same order as `paths`.
+/// 
+/// This is for going over a whole store's worth of files, as in an audit. A file that fails to parse doesn't stop the others; `f` gets the error, and can report it, collect it, or whatever else:
+/// 
+/// ```
+/// use shopsite_aa::de as aa;
+/// use std::{fs, path::PathBuf};
+/// 
+/// let dir = std::env::temp_dir().join(format!("shopsite-aa-doc-parallel-{}", std::process::id()));
+/// fs::create_dir_all(&dir).unwrap();
+/// 
+/// let paths: Vec<PathBuf> = (1..=3).map(|n| dir.join(format!("{}.aa", n))).collect();
+/// fs::write(&paths[0], "Price: 5\n").unwrap();
+/// fs::write(&paths[1], "Price: five\n").unwrap();
+/// fs::write(&paths[2], "Price: 7\n").unwrap();
+/// 
+/// #[derive(serde::Deserialize)]
+/// #[serde(rename_all = "PascalCase")]
+/// struct Product { price: u32 }
+/// 
+/// let results = aa::parse_files_parallel(&paths, |path, result: aa::Result<Product>| {
+///     result.map(|product| product.price).map_err(|error| (path.to_owned(), error))
+/// });
+/// 
+/// let (prices, errors): (Vec<_>, Vec<_>) = results.into_iter().partition(Result::is_ok);
+/// assert_eq!(prices.into_iter().map(Result::unwrap).sum::<u32>(), 12);
+/// assert_eq!(errors.len(), 1);
+/// 
+/// fs::remove_dir_all(&dir).unwrap();
+/// ```
+/// 
+/// To parse files into `Value`s, whose keys aren't known ahead of time, make the result an `aa::Result<aa::Map>`.
+pub fn parse_files_parallel<T, P, U, F>(paths: impl IntoIterator<Item = P>, f: F) -> Vec<U>
+where
+	T: DeserializeOwned,
+	P: AsRef<Path>,
+	U: Send,
+	F: Fn(&Path, Result<T>) -> U + Sync
+{
+	let paths: Vec<Arc<Path>> = paths.into_iter().map(|path| path.as_ref().into()).collect();
+
+	paths.into_par_iter()
+	.map(|path| {
+		let result = from_file(path.clone());
+		f(&path, result)
+	})
+	.collect()
+}
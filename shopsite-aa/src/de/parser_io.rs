@@ -1,18 +1,17 @@
-use encoding::{
-	all::WINDOWS_1252,
-	types::{DecoderTrap, Encoding}
-};
-use std::{
-	io::{self, BufRead},
-	slice::{self, SliceIndex}
-};
+use crate::lexer::{find_any, LineLexer, Token};
+use std::io::{self, BufRead};
 use super::{
+	Bom,
 	Error,
 	Deserializer,
+	InputEncoding,
+	Limit,
+	Position,
 	Result
 };
 
 /// Outcome of `Deserializer::fill_buf` (aside from I/O errors).
+#[allow(clippy::enum_variant_names)]
 pub(super) enum FillBufResult {
 	/// One of the delimiters was found. Contains the delimiter that was found.
 	FoundDelim(u8),
@@ -24,7 +23,84 @@ pub(super) enum FillBufResult {
 	FoundEof
 }
 
+/// How much of the buffered input `Deserializer::peek_key_count` looks at, at most.
+const PEEK_KEY_COUNT_MAX_BYTES: usize = 64 * 1024;
+
+/// Where the column number goes after a byte, for the purpose of tracking column numbers. Line endings are handled separately, so they aren't considered here. `utf8` is whether the input is UTF-8, in which case a character may be more than one byte.
+fn advance_column(column: u32, byte: u8, tab_width: u32, utf8: bool) -> u32 {
+	match byte {
+		0x80..=0xbf if utf8 => {
+			// UTF-8 continuation bytes are part of the character that the byte before them started, which already took up a column.
+			column
+		},
+		b'\t' if tab_width != 0 => {
+			// Tabs advance to the next tab stop, as in a text editor.
+			column + tab_width - (column - 1) % tab_width
+		},
+		0..=31 | 127 => {
+			// Control codes and DEL have zero width. So do tabs, if the tab width is 0.
+			// Backspaces arguably have *negative* width, but computers (unlike telegraphs) don't generally interpret them that way, so nah.
+			// We are not keeping track of ANSI escape sequences. F#@% that.
+			column
+		},
+		_ => {
+			// Everything else increments the column number by 1.
+			column + 1
+		}
+	}
+}
+
+/// Gets a reader's buffered input, filling the buffer if it's empty. Retries when interrupted. An empty slice means end of file. `pos` is for errors.
+fn fill_reader_buf<'a, R: BufRead>(reader: &'a mut R, pos: &Position) -> Result<&'a [u8]> {
+	loop {
+		match reader.fill_buf() {
+			Ok(_) => break,
+			Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {
+				// Operation was interrupted. Keep trying.
+				continue
+			},
+			Err(error) => {
+				// I/O error!
+				return Err(Error::io(error, pos))
+			}
+		}
+	}
+
+	// The buffer has been filled, so this just returns it again. (Returning it from inside the loop would be simpler, but the borrow checker doesn't understand that yet.)
+	match reader.fill_buf() {
+		Ok(buf) => Ok(buf),
+		Err(error) => Err(Error::io(error, pos))
+	}
+}
+
 impl<R: BufRead> Deserializer<R> {
+	/// Gets the reader's buffered input, filling the buffer if it's empty. Retries when interrupted. An empty slice means end of file.
+	/// 
+	/// If there's input left to read again after a `rewind`, that's what this returns, instead of anything from the reader.
+	fn reader_buf(&mut self) -> Result<&[u8]> {
+		if self.replay_pos < self.replay.len() {
+			return Ok(&self.replay[self.replay_pos..])
+		}
+
+		fill_reader_buf(&mut self.reader, &self.pos)
+	}
+
+	/// Makes `reader_buf` return more bytes than it does now, if there are any more, by moving them from the reader's buffer to the end of `replay`. Returns `false` if the input has ended.
+	/// 
+	/// The moved bytes aren't consumed yet, as far as `bytes_read` and `mark` are concerned. They are when they're read from `replay`.
+	fn buffer_more(&mut self) -> Result<bool> {
+		if self.replay_pos != 0 {
+			self.replay.drain(..self.replay_pos);
+			self.replay_pos = 0;
+		}
+
+		let buf = fill_reader_buf(&mut self.reader, &self.pos)?;
+		let amount = buf.len();
+		self.replay.extend_from_slice(buf);
+		self.reader.consume(amount);
+		Ok(amount != 0)
+	}
+
 	/// Reads the next byte of input, keeping track of row and column numbers.
 	pub(super) fn read_byte(&mut self) -> Result<Option<u8>> {
 		// If we've already reached the end of the file, don't bother trying to read more.
@@ -32,21 +108,13 @@ impl<R: BufRead> Deserializer<R> {
 			return Ok(None);
 		}
 
-		// Either get the byte that was peeked, or read a new one.
-		let read_result = {
-			if let Some(peeked_byte) = self.peeked_byte {
-				// If a byte was peeked, take that byte instead.
-				self.peeked_byte = None;
-				Some(peeked_byte)
-			}
-			else {
-				// If no byte was peeked, then actually read a new byte.
-				self.read_byte_raw()?
-			}
-		};
+		let read_result = self.reader_buf()?.first().copied();
 
 		// If `read_result` is `None`, then we've reached the end of the file. If not…
 		if let Some(byte) = read_result {
+			self.consume_input(1);
+			self.line_endings_seen.note(self.last_byte, byte);
+
 			// Keep track of line and column numbers.
 			match (self.last_byte, byte) {
 				(b'\r', b'\n') => {
@@ -56,19 +124,12 @@ impl<R: BufRead> Deserializer<R> {
 					// New line. Increment the line number and reset the column number.
 					self.pos.line += 1;
 					self.pos.column = 1;
-				},
-				(_, b'\t') => {
-					// Tabs increment the column number by 8 instead of 1.
-					self.pos.column += 8;
-				},
-				(_, 0..=31) | (_, 127) => {
-					// Control codes and DEL have zero width.
-					// Backspaces arguably have *negative* width, but computers (unlike telegraphs) don't generally interpret them that way, so nah.
-					// We are not keeping track of ANSI escape sequences. F#@% that.
+					self.pos.byte_column = 1;
 				},
 				_ => {
-					// Everything else increments the column number by 1.
-					self.pos.column += 1;
+					self.pos.column = advance_column(self.pos.column, byte, self.config.tab_width, self.config.encoding == InputEncoding::Utf8);
+					self.pos.byte_column += 1;
+					self.check_limit(Limit::LineLength, self.config.max_line_length, self.pos.byte_column as usize - 1)?;
 				}
 			}
 
@@ -77,6 +138,7 @@ impl<R: BufRead> Deserializer<R> {
 		}
 		else {
 			// We've reached the end of the file. Take note of this.
+			self.line_endings_seen.note_eof(self.last_byte);
 			self.reached_eof = true;
 			self.last_byte = 0;
 		}
@@ -91,45 +153,78 @@ impl<R: BufRead> Deserializer<R> {
 		if self.reached_eof {
 			Ok(None)
 		}
-		else if let Some(peeked_byte) = self.peeked_byte {
-			// If this function has already been called without actually reading the peeked byte, then return the same peeked byte again.
-			Ok(Some(peeked_byte))
-		}
 		else {
-			// Otherwise, read a new byte, but store it as the peeked byte.
-			let byte_opt = self.read_byte_raw()?;
-			self.peeked_byte = byte_opt;
-			Ok(byte_opt)
+			// The reader's buffer already lets us look ahead without consuming anything, so this is easy.
+			Ok(self.reader_buf()?.first().copied())
 		}
 	}
 
-	/// Reads a byte from the reader. Retries when interrupted. Does not respect peeking or track line and column numbers. Called by `peek_byte` and `read_byte`.
-	fn read_byte_raw(&mut self) -> Result<Option<u8>> {
-		let mut byte = 0u8;
+	/// Reads a run of bytes up to (but not including) the first of the `stop` bytes, in one go rather than a byte at a time. If `keep`, the bytes are appended to `self.buf_b`; otherwise, they are skipped. Keeps track of row and column numbers, as `read_byte` does.
+	/// 
+	/// This reads no further than the end of the reader's current buffer, so it may stop before reaching a stop byte. Returns the number of bytes read, which is 0 if the next byte is a stop byte or the end of the file has been reached.
+	/// 
+	/// The run must not contain any line endings, so the `stop` bytes must include `\r` and `\n`. There must be at most 6 `stop` bytes.
+	pub(super) fn read_run(&mut self, stop: &[u8], keep: bool) -> Result<usize> {
+		if self.reached_eof {
+			return Ok(0)
+		}
 
-		loop {
-			return match self.reader.read(slice::from_mut(&mut byte)) {
-				Ok(0) => {
-					// If the reader read 0 bytes, then this is the end of the file. Return accordingly.
-					Ok(None)
-				},
-				Ok(_) => {
-					// Read a byte.
-					Ok(Some(byte))
-				},
-				Err(ref e) if e.kind() == io::ErrorKind::Interrupted => {
-					// Operation was interrupted. Keep trying.
-					continue
-				},
-				Err(error) => {
-					// I/O error!
-					Err(Error::Io {
-						error,
-						file: self.pos.file.clone()
-					})
+		self.reader_buf()?;
+
+		let line_budget = self.config.max_line_length.map(|max| max.saturating_sub(self.pos.byte_column as usize - 1));
+		let value_budget = if keep { self.config.max_value_size.map(|max| max.saturating_sub(self.buf_b.len())) } else { None };
+		let budget = line_budget.into_iter().chain(value_budget).min();
+
+		// Now that the buffer is known to be filled, get at it directly, so that the other fields of `self` can be modified while looking at it.
+		let chunk = {
+			if self.replay_pos < self.replay.len() {
+				&self.replay[self.replay_pos..]
+			}
+			else {
+				match self.reader.fill_buf() {
+					Ok(chunk) => chunk,
+					Err(error) => return Err(Error::io(error, &self.pos))
 				}
 			}
+		};
+
+		let run = match find_any(stop, chunk) {
+			Some(run_len) => &chunk[..run_len],
+			None => chunk
+		};
+
+		// Don't take more of the run than the limits allow, plus one byte to go over them, so that a huge line doesn't get copied into `buf_b` just to find out that it's too long. With slice input, the whole input is in the buffer, so the run could be the whole rest of the file.
+		let run = match budget {
+			Some(budget) if budget < run.len() => &run[..budget + 1],
+			_ => run
+		};
+
+		debug_assert!(!run.contains(&b'\r') && !run.contains(&b'\n'), "`stop` must include line endings");
+
+		if let (Some(first_byte), Some(last_byte)) = (run.first(), run.last()) {
+			// The run has no line endings in it, but it might come right after a `\r`.
+			self.line_endings_seen.note(self.last_byte, *first_byte);
+			self.last_byte = *last_byte;
+		}
+
+		let (tab_width, utf8) = (self.config.tab_width, self.config.encoding == InputEncoding::Utf8);
+		self.pos.column = run.iter().fold(self.pos.column, |column, byte| advance_column(column, *byte, tab_width, utf8));
+
+		if keep {
+			self.buf_b.extend_from_slice(run);
 		}
+
+		let run_len = run.len();
+		self.consume_input(run_len);
+
+		self.pos.byte_column += run_len as u32;
+		self.check_limit(Limit::LineLength, self.config.max_line_length, self.pos.byte_column as usize - 1)?;
+
+		if keep {
+			self.check_limit(Limit::ValueSize, self.config.max_value_size, self.buf_b.len())?;
+		}
+
+		Ok(run_len)
 	}
 
 	/// Clears `self.buf_b`, then fills it with input until reaching one of the given delimiter bytes, the end of the line, or the end of the file.
@@ -138,138 +233,258 @@ impl<R: BufRead> Deserializer<R> {
 	/// 
 	/// The buffer will not contain the delimiter or end-of-line marker. Blank lines and comment lines are skipped over.
 	/// 
-	/// If called at the beginning of a line, this will skip comment lines, blank lines, and lines with only whitespace. If called in the middle of reading a line, comments are not recognized and whitespace is not ignored.
+	/// If called at the beginning of a line, this will skip comment lines, blank lines, and lines with only whitespace. If it skips any blank or whitespace-only lines, it sets `self.saw_blank_line` (but never clears it). If called in the middle of reading a line, comments are not recognized and whitespace is not ignored.
 	/// 
-	/// The return value indicates the outcome of the operation, including which delimiter was found (if any).
+	/// If `escape` is `Some`, then that byte, when followed by a delimiter or by itself, causes the following byte to be added to the buffer literally instead of ending the read. The escape byte itself is not added to the buffer in that case. When followed by any other byte, the escape byte has no special meaning.
+	/// 
+	/// The return value indicates the outcome of the operation, including which delimiter was found (if any). The line and column where the delimiter, line ending, or end of file was found are stored in `self.fill_end`.
 	/// 
 	/// # Errors
 	/// 
 	/// This method may fail with a `std::io::Error`. Calling it again after such a failure may have bogus results.
-	pub(super) fn fill_buf(&mut self, delimiters: &[u8]) -> Result<FillBufResult> {
-		self.buf_b.clear();
+	pub(super) fn fill_buf(&mut self, delimiters: &[u8], escape: Option<u8>) -> Result<FillBufResult> {
+		let result = self.fill_buf_unchecked(delimiters, escape)?;
+
+		// `fill_buf_unchecked` checks the size of the buffer as it goes, to keep it from growing without bound, but the last bit it read hasn't been checked yet.
+		self.check_limit(Limit::ValueSize, self.config.max_value_size, self.buf_b.len())?;
+
+		Ok(result)
+	}
 
-		let mut in_comment = false;
-		let mut seen_non_whitespace = false;
+	/// Does the work of `fill_buf`, except for the final check that the buffer isn't too big. What each byte means is up to `LineLexer`, which the `scan` module uses too.
+	fn fill_buf_unchecked(&mut self, delimiters: &[u8], escape: Option<u8>) -> Result<FillBufResult> {
+		self.buf_b.clear();
 
-		// If this function starts from the beginning of a line, then `self.pos.column` will be 1, either because the previous call to this function found a line ending or because this is the beginning of the file.
-		let started_at_start_of_line = self.pos.column == 1;
+		// If this function starts from the beginning of a line, then `self.pos.byte_column` will be 1, either because the previous call to this function found a line ending or because this is the beginning of the file.
+		let mut lexer = LineLexer::new(delimiters, escape, self.pos.byte_column == 1, self.last_byte);
 
 		loop {
-			// Which column are we reading from?
-			let prev_column = self.pos.column;
+			self.check_limit(Limit::ValueSize, self.config.max_value_size, self.buf_b.len())?;
 
-			// OK, read the next byte.
-			if let Some(byte) = self.read_byte()? {
-				if byte == b'#' && (prev_column == 1 || (started_at_start_of_line && !seen_non_whitespace)) {
-					// This is the beginning of a comment line.
-					// Comment lines start with a `#` character, possibly after whitespace. `#` characters after non-whitespace characters do not count as comments. For example, on the line `bgcolor: #FFFFD6`, the key is `bgcolor` and the value is `#FFFFD6`.
-					in_comment = true;
+			// Before going byte by byte, see if there's a run of bytes that don't need any special attention.
+			if let Some((stop, keep)) = lexer.run() {
+				let buf_len_before = self.buf_b.len();
+				let run_len = self.read_run(stop, keep)?;
 
-					// Clear the buffer, in case the comment begins after some whitespace.
-					self.buf_b.clear();
-				}
-				else if in_comment && byte != b'\r' && byte != b'\n' {
-					// We're still inside a comment line. Skip this byte.
-				}
-				else if byte == b'\r' || byte == b'\n' {
-					// This is a line ending. Where is it?
-					if in_comment {
-						// It's the end of a comment line. We're out of the comment line now, but still haven't seen any significant text yet.
-						in_comment = false;
+				if run_len != 0 {
+					if keep {
+						lexer.note_run(&self.buf_b[buf_len_before..], true);
 					}
-					else if prev_column == 1 {
-						// It's the end of an empty line or part of a CR+LF sequence. Ignore it and keep going.
+					else {
+						lexer.note_run(&[self.last_byte], false);
 					}
-					else if started_at_start_of_line && !seen_non_whitespace {
-						// It's the end of a line containing only whitespace. Clear the buffer and skip to the next line, then.
-						// This can only be the case if we started at the beginning of a line. If this function is called in the *middle* of a line, then what we're looking at is an empty or all-whitespace *value*, which is not the same thing and is treated as significant.
+
+					continue
+				}
+			}
+
+			// Which line and column are we reading from?
+			let prev_line = self.pos.line;
+			let prev_column = self.pos.column;
+			let prev_byte_column = self.pos.byte_column;
+
+			// OK, read the next byte.
+			let byte = match self.read_byte()? {
+				Some(byte) => byte,
+				None => {
+					// If there are no more bytes to read, then we've reached the end of the file.
+					// If we never saw any non-whitespace, then the last line is effectively blank, so clear the buffer of any whitespace left in it.
+					if !lexer.seen_non_whitespace() {
 						self.buf_b.clear();
 					}
+
+					self.fill_end = (self.pos.line, self.pos.column, self.pos.byte_column);
+					return Ok(FillBufResult::FoundEof)
+				}
+			};
+
+			match lexer.next(byte) {
+				Token::Byte(byte) => self.buf_b.push(byte),
+				Token::Skip => {},
+				Token::CommentStart => {
+					#[cfg(feature = "trace")]
+					tracing::trace!(line = self.pos.line, "skipped comment");
+
+					self.buf_b.clear();
+				},
+				Token::BlankLine => {
+					self.buf_b.clear();
+					self.saw_blank_line = true;
+				},
+				Token::Escape(byte) => {
+					// This might be an escape sequence. Look at the next byte to find out.
+					let next = self.peek_byte()?;
+
+					if lexer.escapes(next) {
+						// It is. Consume the next byte and add it to the buffer, without considering whether it's a delimiter.
+						self.read_byte()?;
+						self.buf_b.extend(next);
+					}
 					else {
-						// By process of elimination, this must be the end of a line that isn't a comment, empty, or all whitespace. That means we're done filling the buffer, but didn't find a delimiter.
-						return Ok(FillBufResult::FoundEol)
+						// It isn't. The escape byte is just an ordinary byte here.
+						self.buf_b.push(byte);
 					}
-				}
-				else if delimiters.contains(&byte) {
+				},
+				Token::Delim(byte) => {
 					// Found a delimiter!
+					self.fill_end = (prev_line, prev_column, prev_byte_column);
 					return Ok(FillBufResult::FoundDelim(byte))
-				}
-				else {
-					// Not a delimiter or a line ending. Add it to the buffer, and take note if it's not whitespace. Then keep looking.
-					self.buf_b.push(byte);
-
-					if !byte.is_ascii_whitespace() {
-						seen_non_whitespace = true;
-					}
+				},
+				Token::Eol => {
+					// That's the end of the line, but no delimiter was found.
+					self.fill_end = (prev_line, prev_column, prev_byte_column);
+					return Ok(FillBufResult::FoundEol)
 				}
 			}
-			else {
-				// If there are no more bytes to read, then we've reached the end of the file.
-				// If we never saw any non-whitespace, then the last line is effectively blank, so clear the buffer of any whitespace left in it.
-				if !seen_non_whitespace {
-					self.buf_b.clear();
-				}
+		}
+	}
 
-				return Ok(FillBufResult::FoundEof)
+	/// Guesses how many elements the sequence that starts here has, by counting delimiters in what's in the reader's buffer. This reads more input only if the buffer is empty. `depth` is 1 for a sequence, 2 for a sequence in a sequence, and so on.
+	/// 
+	/// Returns `None` if the sequence doesn't end in the buffer, or is nested too deep to have more than one element. When the input is a slice, the whole input is in the buffer, so that only happens when the sequence is at the very end, without a line ending.
+	pub(super) fn peek_seq_len(&mut self, depth: u8) -> Result<Option<usize>> {
+		let (delimiter, enclosing_delimiter) = match depth {
+			1 => (self.seq_delimiter, None),
+			2 => (self.config.sub_seq_delimiter, Some(self.seq_delimiter)),
+			_ => return Ok(None)
+		};
+
+		let escape = self.config.seq_escape.escape_byte();
+		let buf = self.reader_buf()?;
+		let mut len = 1;
+		let mut index = 0;
+
+		while let Some(&byte) = buf.get(index) {
+			if byte == b'\r' || byte == b'\n' || Some(byte) == enclosing_delimiter {
+				// An empty value is an empty sequence, but an empty element of the enclosing sequence is a sequence with one empty element.
+				return Ok(Some(if index == 0 && enclosing_delimiter.is_none() { 0 } else { len }))
+			}
+			else if Some(byte) == escape && buf.get(index + 1).is_some_and(|next| *next == byte || *next == delimiter || Some(*next) == enclosing_delimiter) {
+				index += 1;
 			}
+			else if byte == delimiter {
+				len += 1;
+			}
+
+			index += 1;
 		}
+
+		Ok(None)
 	}
 
-	/// Clears `self.buf_s`, then decodes part of `self.buf_b` into it.
-	/// 
-	/// Windows-1252 cannot fail to decode, so this method does not return a `Result`. It always succeeds (or panics).
+	/// Guesses how many keys are left in the input, by counting the lines in what's in the reader's buffer, up to `PEEK_KEY_COUNT_MAX_BYTES` of it. Like `peek_seq_len`, this reads more input only if the buffer is empty, and it returns `None` instead of failing.
 	/// 
-	/// # Panics
-	/// 
-	/// If the given `range` is out of bounds, this method will likely panic.
-	pub(super) fn decode_buf(&mut self, range: impl SliceIndex<[u8], Output=[u8]>) {
-		self.buf_s.clear();
+	/// With slice or memory-mapped input, the whole rest of the file is in the buffer, so without a limit this would be a whole extra pass over it. With a limit, the guess is low for big files, but it's only a guess anyway.
+	pub(super) fn peek_key_count(&mut self) -> Option<usize> {
+		let pending = self.pending_key.is_some() as usize;
+		let buf = self.reader_buf().ok()?;
+		let buf = &buf[..buf.len().min(PEEK_KEY_COUNT_MAX_BYTES)];
+		Some(crate::scan::entries(buf).count() + pending)
+	}
+
+	/// Replaces every `&#124;`, `&#x7C;`, and `&#x7c;` in `self.buf_b` with a `|` character.
+	pub(super) fn unescape_pipe_entities(&mut self) {
+		const ENTITIES: [&[u8]; 3] = [b"&#124;", b"&#x7C;", b"&#x7c;"];
+
+		// Most elements won't have any entities in them, so check for that first and skip all the copying.
+		if !self.buf_b.contains(&b'&') {
+			return
+		}
+
+		let mut unescaped = Vec::with_capacity(self.buf_b.len());
+		let mut rest = &self.buf_b[..];
+
+		while !rest.is_empty() {
+			match ENTITIES.iter().find(|entity| rest.starts_with(entity)) {
+				Some(entity) => {
+					unescaped.push(b'|');
+					rest = &rest[entity.len()..];
+				},
+				None => {
+					unescaped.push(rest[0]);
+					rest = &rest[1..];
+				}
+			}
+		}
 
-		// The infallibility of Windows-1252 decoding is verified by a unit test, below.
-		WINDOWS_1252.decode_to(&self.buf_b[range], DecoderTrap::Replace, &mut self.buf_s).unwrap();
+		self.buf_b = unescaped;
 	}
 
-	/// Clears `self.buf_s`, then decodes all of `self.buf_b` into it.
+	/// Clears `self.buf_s`, then decodes all of `self.buf_b` into it, in the input's encoding, and normalizes its punctuation.
 	/// 
-	/// Windows-1252 cannot fail to decode, so this method does not return a `Result`. It always succeeds.
+	/// Decoding cannot fail, so this method does not return a `Result`. It always succeeds.
 	pub(super) fn decode_buf_all(&mut self) {
-		self.decode_buf(..)
+		self.decode_buf_all_unnormalized();
+		self.config.punctuation.normalize(&mut self.buf_s);
 	}
 
-	/// Decodes part of `self.buf_b` into a new `String`.
-	/// 
-	/// Windows-1252 cannot fail to decode, so this method does not return a `Result`. It always succeeds (or panics).
-	/// 
-	/// # Panics
-	/// 
-	/// If the given `range` is out of bounds, this method will likely panic.
-	pub(super) fn decode_buf_owned(&mut self, range: impl SliceIndex<[u8], Output=[u8]>) -> String {
-		WINDOWS_1252.decode(&self.buf_b[range], DecoderTrap::Replace).unwrap()
+	/// Like `decode_buf_all`, but leaves the punctuation alone, for a caller that has more decoding to do before normalizing it.
+	pub(super) fn decode_buf_all_unnormalized(&mut self) {
+		self.buf_s.clear();
+		self.config.encoding.decode_to(&self.buf_b, &mut self.buf_s);
+		self.trace_replaced_chars(&self.buf_s);
 	}
 
-	/// Decodes all of `self.buf_b` into a new `String`.
+	/// Decodes all of `self.buf_b` into a new `String`, in the input's encoding, and normalizes its punctuation.
 	/// 
-	/// Windows-1252 cannot fail to decode, so this method does not return a `Result`. It always succeeds (or panics).
+	/// Decoding cannot fail, so this method does not return a `Result`. It always succeeds.
 	pub(super) fn decode_buf_all_owned(&mut self) -> String {
-		self.decode_buf_owned(..)
+		let mut s = self.decode_buf_all_owned_unnormalized();
+		self.config.punctuation.normalize(&mut s);
+		s
 	}
-}
 
-#[test]
-fn test_decoding_windows_1252_cannot_fail() {
-	// We assume above that decoding Windows-1252 can never fail. This verifies that that's actually true by throwing every single Windows-1252 code point at the decoder.
+	/// Like `decode_buf_all_owned`, but leaves the punctuation alone, as `decode_buf_all_unnormalized` does.
+	pub(super) fn decode_buf_all_owned_unnormalized(&mut self) -> String {
+		let s = self.config.encoding.decode(&self.buf_b);
+		self.trace_replaced_chars(&s);
+		s
+	}
+
+	/// Reports a `tracing` event if decoding replaced any bytes with `U+FFFD`, because they weren't valid in the input's encoding. Does nothing without the `trace` feature.
+	/// 
+	/// A `U+FFFD` that was in the input to begin with is reported too, but those are rare enough not to bother telling apart.
+	#[cfg_attr(not(feature = "trace"), allow(unused_variables))]
+	fn trace_replaced_chars(&self, decoded: &str) {
+		#[cfg(feature = "trace")]
+		{
+			let replaced = decoded.matches('\u{fffd}').count();
+
+			if replaced != 0 {
+				tracing::debug!(replaced, line = self.pos.line, encoding = %self.config.encoding, "replaced characters");
+			}
+		}
+	}
+
+	/// Skips a UTF-8 byte order mark, if the input starts with one, or fails with `Error::UnexpectedBom`, according to `DeserializerBuilder::bom`. Call this before reading anything.
+	pub(super) fn skip_bom(&mut self) -> Result<()> {
+		const BOM: &[u8] = b"\xef\xbb\xbf";
+
+		if self.bytes_read != 0 {
+			return Ok(())
+		}
+
+		// The reader's buffer may have only part of a BOM in it, if the buffer is small or the reader hands over a byte at a time, so keep looking until the whole BOM is there, a byte doesn't match, or the input ends.
+		loop {
+			let buf = self.reader_buf()?;
+			let len = buf.len().min(BOM.len());
+
+			if buf[..len] != BOM[..len] {
+				return Ok(())
+			}
+			else if len == BOM.len() {
+				break
+			}
+			else if !self.buffer_more()? {
+				return Ok(())
+			}
+		}
+
+		match self.config.bom {
+			Bom::Skip => self.consume_input(BOM.len()),
+			Bom::Reject => return Err(Error::UnexpectedBom { pos: self.pos.clone() })
+		}
 
-	// Assemble the byte array.
-	let mut bytes = [0u8; 256];
-	for i in 0u8..=255u8 {
-		bytes[i as usize] = i;
+		Ok(())
 	}
-	
-	// Check that we assembled the byte array correctly.
-	assert_eq!(bytes[0], 0u8);
-	assert_eq!(bytes[127], 127u8);
-	assert_eq!(bytes[255], 255u8);
-
-	// Now, throw it at the decoder and make sure it doesn't fail. The decoder's output doesn't actually matter here, just that it succeeds.
-	WINDOWS_1252.decode(&bytes[..], DecoderTrap::Replace).expect("Decoding Windows-1252 should never fail!");
 }
@@ -1,26 +1,54 @@
+use serde::ser::{Serialize, SerializeStruct, Serializer};
 use std::{
 	fmt::{Display, Formatter, Result as FmtResult},
-	rc::Rc,
-	path::Path
+	path::Path,
+	sync::Arc
 };
-use super::rc_path_to_str;
+use super::arc_path_to_str;
 
 /// Position in an input file where an error occurred.
 // This structure is actually also used by the parser to keep track of where it's looking, not just for error reporting.
 #[derive(Clone, Debug, Eq, PartialEq)]
 pub struct Position {
 	/// Path to the file containing the error.
-	pub file: Option<Rc<Path>>,
+	pub file: Option<Arc<Path>>,
 
 	/// Line on which the error appears.
 	pub line: u32,
 
-	/// Column on which the error appears.
-	pub column: u32
+	/// Column on which the error appears, as a text editor would show it. Tabs advance to the next tab stop (see `DeserializerBuilder::tab_width`), and control characters take up no room. In UTF-8 input (see `DeserializerBuilder::encoding`), this counts characters, not bytes.
+	pub column: u32,
+
+	/// Column on which the error appears, counting bytes from the start of the line, starting at 1. Unlike `column`, this doesn't depend on tabs, so it's what to use for finding the spot in the file programmatically.
+	pub byte_column: u32,
+
+	/// Key whose value was being deserialized when the error occurred, if any. If the key is in a section, this is the section name, a `.`, and the key, like `Shipping.Zip`.
+	/// 
+	/// The parser doesn't keep track of this as it goes, so it's `None` except in errors.
+	pub key: Option<String>
 }
 
 impl Display for Position {
 	fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-		write!(f, "{}:{}:{}", rc_path_to_str(&self.file), self.line, self.column)
+		write!(f, "{}:{}:{}", arc_path_to_str(&self.file), self.line, self.column)?;
+
+		if let Some(ref key) = self.key {
+			write!(f, ": {}", key)?;
+		}
+
+		Ok(())
+	}
+}
+
+impl Serialize for Position {
+	/// Serializes the position as a struct with the same fields. The file name is text, decoded lossily if it isn't valid Unicode, or `None` if there's no file name.
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut s = serializer.serialize_struct("Position", 5)?;
+		s.serialize_field("file", &self.file.as_ref().map(|file| file.to_string_lossy()))?;
+		s.serialize_field("line", &self.line)?;
+		s.serialize_field("column", &self.column)?;
+		s.serialize_field("byte_column", &self.byte_column)?;
+		s.serialize_field("key", &self.key)?;
+		s.end()
 	}
 }
@@ -0,0 +1,177 @@
+use std::{
+	fmt::{self, Display, Formatter},
+	str::FromStr
+};
+use super::{
+	BoolSyntax,
+	DeserializerBuilder,
+	InputEncoding,
+	LineEnding,
+	NumberSyntax,
+	Punctuation,
+	SeqEscape,
+	TrailingEmptyElement
+};
+use crate::ser::SerializerBuilder;
+
+/// Named combinations of `DeserializerBuilder` options, for the usual kinds of `.aa` file, so that choosing one is enough instead of tuning each option. See `DeserializerBuilder::preset`.
+/// 
+/// Each preset sets the punctuation, boolean and number syntax, sequence escapes and delimiters, and trailing empty elements, and the ShopSite ones set the encoding too. Options that depend on what's being read rather than who wrote it, like record boundaries, sections, and limits, are left alone. Options set after the preset override it.
+/// 
+/// A preset also says how files of its kind are written, with `SerializerBuilder::preset`: in the same encoding, with the same escapes and delimiters, and with its line endings.
+/// 
+/// Some things about the format can't be configured, so there's nothing for a preset to set. When reading, every kind of line ending is accepted, even mixed together; lines with only whitespace on them are skipped; comments can be indented; and whitespace around keys and values is kept, except for the one space after the `:`. Every preset reads files that way.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Preset {
+	/// Files written by ShopSite 12 and earlier. This is the same as the defaults, except that booleans can be spelled the way ShopSite spells them, like `checked`.
+	ShopSite12,
+
+	/// Files written by ShopSite 14 and later. Like `ShopSite12`, but `&#124;` in a sequence element stands for a `|`, since newer versions write them that way in lists.
+	ShopSite14,
+
+	/// Files written or edited by people, in a text editor. Booleans and numbers are read leniently, so `$1,234.50` is a number; `\|` stands for a `|` in a sequence element; a `|` at the very end of the file doesn't add an empty element; and punctuation garbled by a trip through the wrong encoding is fixed. The encoding is left alone, since editors differ; see `detect_encoding`.
+	HandEdited
+}
+
+impl Preset {
+	/// Every preset, in the order they're documented in.
+	pub const ALL: &'static [Preset] = &[Preset::ShopSite12, Preset::ShopSite14, Preset::HandEdited];
+
+	/// The preset's name, as accepted by `FromStr` and written by `Display`, like `shopsite12`.
+	pub fn name(self) -> &'static str {
+		match self {
+			Preset::ShopSite12 => "shopsite12",
+			Preset::ShopSite14 => "shopsite14",
+			Preset::HandEdited => "hand-edited"
+		}
+	}
+
+	/// The options that this preset consists of.
+	pub fn options(self) -> PresetOptions {
+		let shopsite = PresetOptions {
+			encoding: Some(InputEncoding::Windows1252),
+			punctuation: Punctuation::Keep,
+			bool_syntax: BoolSyntax::Lenient,
+			number_syntax: NumberSyntax::Strict,
+			seq_escape: SeqEscape::None,
+			sub_seq_delimiter: b',',
+			seq_delimiter_overrides: &[],
+			trailing_empty_element: TrailingEmptyElement::Keep,
+			line_ending: LineEnding::CrLf,
+			trailing_line_ending: true
+		};
+
+		match self {
+			Preset::ShopSite12 => shopsite,
+			Preset::ShopSite14 => PresetOptions {
+				seq_escape: SeqEscape::NumericEntity,
+				..shopsite
+			},
+			Preset::HandEdited => PresetOptions {
+				encoding: None,
+				punctuation: Punctuation::Unicode,
+				bool_syntax: BoolSyntax::Lenient,
+				number_syntax: NumberSyntax::Lenient,
+				seq_escape: SeqEscape::Backslash,
+				sub_seq_delimiter: b',',
+				seq_delimiter_overrides: &[],
+				trailing_empty_element: TrailingEmptyElement::DropAtEof,
+				line_ending: LineEnding::Lf,
+				trailing_line_ending: true
+			}
+		}
+	}
+
+	/// Sets the options that this preset consists of. This is what `DeserializerBuilder::preset` does.
+	pub fn apply(self, builder: &mut DeserializerBuilder) {
+		let options = self.options();
+
+		if let Some(encoding) = options.encoding {
+			builder.encoding(encoding);
+		}
+
+		builder
+			.punctuation(options.punctuation)
+			.bool_syntax(options.bool_syntax)
+			.number_syntax(options.number_syntax)
+			.seq_escape(options.seq_escape)
+			.sub_seq_delimiter(options.sub_seq_delimiter)
+			.trailing_empty_element(options.trailing_empty_element);
+
+		for &(key_glob, delimiter) in options.seq_delimiter_overrides {
+			builder.seq_delimiter_override(key_glob, delimiter);
+		}
+	}
+
+	/// Sets the options for writing files the way this preset reads them. This is what `SerializerBuilder::preset` does.
+	pub fn apply_to_serializer(self, builder: &mut SerializerBuilder) {
+		let options = self.options();
+
+		if let Some(encoding) = options.encoding {
+			builder.encoding(encoding);
+		}
+
+		builder
+			.seq_escape(options.seq_escape)
+			.sub_seq_delimiter(options.sub_seq_delimiter)
+			.line_ending(options.line_ending)
+			.trailing_line_ending(options.trailing_line_ending);
+
+		for &(key_glob, delimiter) in options.seq_delimiter_overrides {
+			builder.seq_delimiter_override(key_glob, delimiter);
+		}
+	}
+}
+
+/// The options that a `Preset` sets. See `Preset::options`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub struct PresetOptions {
+	/// See `DeserializerBuilder::encoding`. `None` leaves the encoding alone.
+	pub encoding: Option<InputEncoding>,
+
+	/// See `DeserializerBuilder::punctuation`.
+	pub punctuation: Punctuation,
+
+	/// See `DeserializerBuilder::bool_syntax`.
+	pub bool_syntax: BoolSyntax,
+
+	/// See `DeserializerBuilder::number_syntax`.
+	pub number_syntax: NumberSyntax,
+
+	/// See `DeserializerBuilder::seq_escape`.
+	pub seq_escape: SeqEscape,
+
+	/// See `DeserializerBuilder::sub_seq_delimiter`.
+	pub sub_seq_delimiter: u8,
+
+	/// Key globs and the sequence delimiters for their values, added with `DeserializerBuilder::seq_delimiter_override` after any that were already set. None of the presets have any yet, since ShopSite uses `|` for every key we know of.
+	pub seq_delimiter_overrides: &'static [(&'static str, u8)],
+
+	/// See `DeserializerBuilder::trailing_empty_element`.
+	pub trailing_empty_element: TrailingEmptyElement,
+
+	/// The line ending that files are written with. See `SerializerBuilder::line_ending`. Files are read with any line ending, regardless.
+	pub line_ending: LineEnding,
+
+	/// Whether the last line of a file is written with a line ending. See `SerializerBuilder::trailing_line_ending`.
+	pub trailing_line_ending: bool
+}
+
+impl Display for Preset {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for Preset {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Preset::ALL.iter()
+			.copied()
+			.find(|preset| preset.name() == s)
+			.ok_or_else(|| format!("unrecognized preset “{}”; expected {}", s, Preset::ALL.iter().map(|preset| preset.name()).collect::<Vec<_>>().join(", ")))
+	}
+}
@@ -0,0 +1,76 @@
+use encoding::{all::WINDOWS_1252, DecoderTrap, Encoding};
+use std::str::FromStr;
+
+/// What to do with typographic punctuation, like curly quotes, dashes, and ellipses, in decoded text. ShopSite's product descriptions are full of these, since they're what word processors produce, and some downstream systems choke on them.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+#[non_exhaustive]
+pub enum Punctuation {
+	/// Leave text as it's decoded. This is the default.
+	#[default]
+	Keep,
+
+	/// Replace C1 control characters (`U+0080` through `U+009F`) with the characters that the same bytes stand for in Windows-1252, like `U+0093` with `“`. Text that went through a Latin-1 conversion on its way into a UTF-8 file has these in place of its punctuation.
+	Unicode,
+
+	/// Like `Unicode`, then replace curly quotes with straight ones, dashes with hyphens (two for an em dash), and ellipses with three periods.
+	Ascii
+}
+
+impl FromStr for Punctuation {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"keep" => Ok(Punctuation::Keep),
+			"unicode" => Ok(Punctuation::Unicode),
+			"ascii" => Ok(Punctuation::Ascii),
+			_ => Err(format!("unrecognized punctuation mode “{}”; expected keep, unicode, or ascii", s))
+		}
+	}
+}
+
+impl Punctuation {
+	/// Normalizes the punctuation in `s`, in place. The deserializer does this to the text it decodes; this is for text that it hands over undecoded, like a `RawValue`. Text that's all ASCII, which is most of it, is left alone without being copied.
+	pub fn normalize(self, s: &mut String) {
+		if self == Punctuation::Keep || s.is_ascii() {
+			return
+		}
+
+		let mut normalized = String::with_capacity(s.len());
+
+		for c in s.chars() {
+			let c = match c {
+				'\u{80}'..='\u{9f}' => WINDOWS_1252.decode(&[c as u8], DecoderTrap::Strict).ok().and_then(|decoded| decoded.chars().next()).unwrap_or(c),
+				_ => c
+			};
+
+			match (self, c) {
+				(Punctuation::Ascii, '‘' | '’' | '‚' | '‛') => normalized.push('\''),
+				(Punctuation::Ascii, '“' | '”' | '„' | '‟') => normalized.push('"'),
+				(Punctuation::Ascii, '‐' | '‑' | '‒' | '–') => normalized.push('-'),
+				(Punctuation::Ascii, '—' | '―') => normalized.push_str("--"),
+				(Punctuation::Ascii, '…') => normalized.push_str("..."),
+				_ => normalized.push(c)
+			}
+		}
+
+		*s = normalized;
+	}
+}
+
+#[test]
+fn test_normalize_punctuation() {
+	fn normalize(punctuation: Punctuation, s: &str) -> String {
+		let mut s = s.to_string();
+		punctuation.normalize(&mut s);
+		s
+	}
+
+	let text = "“Quoted” – it’s… great—\u{93}really\u{94}";
+	assert_eq!(normalize(Punctuation::Keep, text), text);
+	assert_eq!(normalize(Punctuation::Unicode, text), "“Quoted” – it’s… great—“really”");
+	assert_eq!(normalize(Punctuation::Ascii, text), "\"Quoted\" - it's... great--\"really\"");
+
+	// Other non-ASCII characters, and C1 controls that Windows-1252 doesn't define, are left alone.
+	assert_eq!(normalize(Punctuation::Ascii, "Café\u{81}"), "Café\u{81}");
+}
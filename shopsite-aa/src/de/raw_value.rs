@@ -0,0 +1,160 @@
+use serde::de::{self, Deserialize, Deserializer, Visitor};
+use std::{
+	convert::TryInto,
+	fmt::{self, Formatter},
+	path::Path,
+	sync::Arc
+};
+use super::{InputEncoding, Position};
+
+/// Name of the newtype struct that `RawValue` asks to deserialize. `AaValueDeserializer` recognizes it and responds with the raw value, encoded by `encode`.
+pub(super) const RAW_VALUE_TOKEN: &str = "$shopsite_aa::private::RawValue";
+
+/// A value exactly as it appears in the file, not yet decoded or parsed, along with where in the file it is.
+/// 
+/// Use this for fields whose interpretation has to wait until later, but whose location is still needed for error messages when that time comes:
+/// 
+/// ```
+/// use serde::Deserialize;
+/// use shopsite_aa::de as aa;
+/// 
+/// #[derive(Deserialize)]
+/// struct Product {
+///     price: aa::RawValue
+/// }
+/// 
+/// let product: Product = aa::from_bytes(b"price: twelve\n", None).unwrap();
+/// assert_eq!(product.price.decode(), "twelve");
+/// assert_eq!((product.price.start().line, product.price.start().column), (1, 8));
+/// ```
+/// 
+/// `RawValue` can only be deserialized from a `.aa` file. Other formats don't know how to produce one.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct RawValue {
+	bytes: Vec<u8>,
+	encoding: InputEncoding,
+	start: Position,
+	end: Position
+}
+
+impl RawValue {
+	/// The undecoded bytes of the value, in the input's encoding.
+	pub fn as_bytes(&self) -> &[u8] {
+		&self.bytes[..]
+	}
+
+	/// Takes the undecoded bytes of the value.
+	pub fn into_bytes(self) -> Vec<u8> {
+		self.bytes
+	}
+
+	/// The encoding of the input that the value came from, which is the encoding of `as_bytes`. This is Windows-1252 unless `DeserializerBuilder::encoding` said otherwise.
+	pub fn encoding(&self) -> InputEncoding {
+		self.encoding
+	}
+
+	/// Decodes the value into text.
+	pub fn decode(&self) -> String {
+		self.encoding.decode(&self.bytes[..])
+	}
+
+	/// Where the value starts.
+	pub fn start(&self) -> &Position {
+		&self.start
+	}
+
+	/// Where the value ends. This is the position just past the end of the value, where the following delimiter or line ending is.
+	pub fn end(&self) -> &Position {
+		&self.end
+	}
+}
+
+impl fmt::Display for RawValue {
+	/// Writes the decoded text of the value.
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		f.write_str(&self.decode())
+	}
+}
+
+/// Packs a raw value and its position into a byte buffer, for delivery through Serde to `RawValue`'s `Deserialize` implementation.
+/// 
+/// The layout is: start line, start column, start byte column, end line, end column, and end byte column, each as a little-endian `u32`; then the encoding, as a byte that is 0 for Windows-1252 or 1 for UTF-8; then the length of the file path as a little-endian `u32`, or `u32::MAX` if there is no file path; then the file path, as UTF-8; then the value's bytes.
+pub(super) fn encode(bytes: &[u8], encoding: InputEncoding, start: &Position, end: &Position) -> Vec<u8> {
+	let file = start.file.as_ref().map(|file| file.to_string_lossy());
+	let mut buf = Vec::with_capacity(29 + file.as_ref().map_or(0, |file| file.len()) + bytes.len());
+
+	for number in &[start.line, start.column, start.byte_column, end.line, end.column, end.byte_column] {
+		buf.extend_from_slice(&number.to_le_bytes());
+	}
+
+	buf.push(match encoding {
+		InputEncoding::Windows1252 => 0,
+		InputEncoding::Utf8 => 1
+	});
+
+	match file {
+		Some(file) => {
+			buf.extend_from_slice(&(file.len() as u32).to_le_bytes());
+			buf.extend_from_slice(file.as_bytes());
+		},
+		None => buf.extend_from_slice(&u32::MAX.to_le_bytes())
+	}
+
+	buf.extend_from_slice(bytes);
+	buf
+}
+
+/// Unpacks what `encode` packed. Returns `None` if it's not in the right format, which probably means it came from some other deserializer.
+fn decode(buf: &[u8]) -> Option<RawValue> {
+	fn take_u32(buf: &mut &[u8]) -> Option<u32> {
+		let number = u32::from_le_bytes(buf.get(..4)?.try_into().ok()?);
+		*buf = &buf[4..];
+		Some(number)
+	}
+
+	let mut rest = buf;
+	let (start_line, start_column, start_byte_column) = (take_u32(&mut rest)?, take_u32(&mut rest)?, take_u32(&mut rest)?);
+	let (end_line, end_column, end_byte_column) = (take_u32(&mut rest)?, take_u32(&mut rest)?, take_u32(&mut rest)?);
+
+	let encoding = match rest.split_first()? {
+		(0, tail) => { rest = tail; InputEncoding::Windows1252 },
+		(1, tail) => { rest = tail; InputEncoding::Utf8 },
+		_ => return None
+	};
+
+	let file: Option<Arc<Path>> = match take_u32(&mut rest)? {
+		u32::MAX => None,
+		len => {
+			let file = std::str::from_utf8(rest.get(..len as usize)?).ok()?;
+			rest = &rest[len as usize..];
+			Some(Arc::from(Path::new(file)))
+		}
+	};
+
+	Some(RawValue {
+		bytes: rest.to_vec(),
+		encoding,
+		start: Position { file: file.clone(), line: start_line, column: start_column, byte_column: start_byte_column, key: None },
+		end: Position { file, line: end_line, column: end_column, byte_column: end_byte_column, key: None }
+	})
+}
+
+struct RawValueVisitor;
+
+impl<'de> Visitor<'de> for RawValueVisitor {
+	type Value = RawValue;
+
+	fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+		f.write_str("a raw value from a `.aa` file")
+	}
+
+	fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<RawValue, E> {
+		decode(v).ok_or_else(|| E::invalid_value(de::Unexpected::Bytes(v), &self))
+	}
+}
+
+impl<'de> Deserialize<'de> for RawValue {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		deserializer.deserialize_newtype_struct(RAW_VALUE_TOKEN, RawValueVisitor)
+	}
+}
@@ -0,0 +1,86 @@
+use serde::de::DeserializeOwned;
+use std::{
+	io::{BufRead, Read},
+	marker::PhantomData,
+	path::Path,
+	sync::Arc
+};
+use super::{
+	Deserializer,
+	DeserializerBuilder,
+	RecordBoundary,
+	Result,
+	trace_result
+};
+
+/// Iterator over the records in a file that contains more than one. Returned by `Deserializer::records`.
+/// 
+/// After an error, the iterator ends.
+pub struct Records<'a, R, T> {
+	de: &'a mut Deserializer<R>,
+	done: bool,
+	_t: PhantomData<fn() -> T>
+}
+
+impl<R: BufRead> Deserializer<R> {
+	/// Deserializes the records in the file one at a time, as separated according to `DeserializerBuilder::record_boundary`.
+	/// 
+	/// If no record boundary was configured, the whole file is one record. Unlike deserializing with `T::deserialize`, though, an empty file has no records at all, rather than one empty record.
+	pub fn records<T: DeserializeOwned>(&mut self) -> Records<'_, R, T> {
+		Records {
+			de: self,
+			done: false,
+			_t: PhantomData
+		}
+	}
+}
+
+impl<R: BufRead> Deserializer<R> {
+	/// Checks whether there is another record left in the file. If so, the next call to `deserialize` on this `Deserializer` will deserialize it.
+	/// 
+	/// This is for deserializing records one at a time without `records`, such as when transcoding them to another format, which has no type to deserialize into.
+	pub fn has_more_records(&mut self) -> Result<bool> {
+		// Find the first key of the next record, to see if there is a next record. If there is, it'll be left pending for the record's deserializer to pick up.
+		Ok(self.pending_key.is_some() || trace_result(self.read_key())?)
+	}
+}
+
+impl<'a, R: BufRead, T: DeserializeOwned> Records<'a, R, T> {
+	fn next_record(&mut self) -> Result<Option<T>> {
+		if !self.de.has_more_records()? {
+			return Ok(None)
+		}
+
+		T::deserialize(&mut *self.de).map(Some)
+	}
+}
+
+impl<'a, R: BufRead, T: DeserializeOwned> Iterator for Records<'a, R, T> {
+	type Item = Result<T>;
+
+	fn next(&mut self) -> Option<Self::Item> {
+		if self.done {
+			return None
+		}
+
+		let result = self.next_record().transpose();
+
+		// Stop after the last record or the first error.
+		if !matches!(result, Some(Ok(_))) {
+			self.done = true;
+		}
+
+		result
+	}
+}
+
+impl<'a, R: BufRead, T: DeserializeOwned> std::iter::FusedIterator for Records<'a, R, T> {}
+
+/// Deserializes all of the records in a file that contains more than one, like ShopSite's dumps of its product and page databases. As with `from_reader`, the input is buffered internally.
+pub fn from_reader_multi<T: DeserializeOwned, R: Read>(reader: R, file: Option<Arc<Path>>, boundary: RecordBoundary) -> Result<Vec<T>> {
+	DeserializerBuilder::new()
+	.record_boundary(boundary)
+	.build_from_read(reader, file)
+	.records()
+	.collect()
+}
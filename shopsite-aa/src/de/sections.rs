@@ -0,0 +1,150 @@
+use serde::de::{
+	DeserializeSeed,
+	IntoDeserializer,
+	MapAccess,
+	Visitor
+};
+use std::io::BufRead;
+use super::{
+	AaTopMapAccess,
+	Deserializer,
+	Error,
+	Result,
+	SectionSyntax
+};
+
+/// A line that marks the beginning or end of a section.
+pub(super) enum SectionMarker<'a> {
+	/// The beginning of the section with the given name.
+	Begin(&'a str),
+
+	/// The end of a section.
+	End
+}
+
+impl<R: BufRead> Deserializer<R> {
+	/// Checks whether the pending key is actually a section marker, according to `DeserializerBuilder::section_syntax`.
+	pub(super) fn pending_section_marker(&self) -> Option<SectionMarker<'_>> {
+		// Section markers are on lines by themselves, so they look like keys with no value.
+		if self.pending_key != Some(true) {
+			return None
+		}
+
+		let line = self.buf_s.trim();
+		let (brackets, begin_end) = match self.config.section_syntax {
+			SectionSyntax::None => return None,
+			SectionSyntax::Brackets => (true, false),
+			SectionSyntax::BeginEnd => (false, true),
+			SectionSyntax::Any => (true, true)
+		};
+
+		if brackets && line.len() >= 2 && line.starts_with('[') && line.ends_with(']') {
+			Some(SectionMarker::Begin(&line[1..line.len() - 1]))
+		}
+		else if begin_end && line.starts_with("BEGIN_") {
+			Some(SectionMarker::Begin(&line["BEGIN_".len()..]))
+		}
+		else if begin_end && line.starts_with("END_") {
+			Some(SectionMarker::End)
+		}
+		else {
+			None
+		}
+	}
+}
+
+/// Accessor for the sections of a file, as a map of section names to maps of keys.
+pub(super) struct AaSectionsMapAccess<'a, R> {
+	de: &'a mut Deserializer<R>,
+
+	/// How many sections have been handed to the visitor so far. As with `AaTopMapAccess`, a record boundary only counts as one if the record has something in it.
+	sections_read: usize,
+
+	/// The name of the section most recently handed to the visitor, for error messages.
+	name: String
+}
+
+impl<'a, R: BufRead> AaSectionsMapAccess<'a, R> {
+	pub(super) fn new(de: &'a mut Deserializer<R>) -> AaSectionsMapAccess<'a, R> {
+		AaSectionsMapAccess {
+			de,
+			sections_read: 0,
+			name: String::new()
+		}
+	}
+}
+
+impl<'de, 'a, R: BufRead> MapAccess<'de> for AaSectionsMapAccess<'a, R> {
+	type Error = Error;
+
+	fn next_key_seed<K>(&mut self, seed: K) -> Result<Option<K::Value>>
+	where K: DeserializeSeed<'de> {
+		loop {
+			// Read the next key, unless it's already been read.
+			if self.de.pending_key.is_none() && !self.de.read_key()? {
+				return Ok(None)
+			}
+
+			// If the key begins a new record, then this record is over.
+			if self.sections_read != 0 && self.de.pending_key_starts_record() {
+				return Ok(None)
+			}
+
+			match self.de.pending_section_marker() {
+				Some(SectionMarker::Begin(name)) => {
+					// This is the beginning of a section. Submit its name as the key. Copy it out first, since consuming the marker lets `buf_s` be overwritten.
+					self.name = name.to_string();
+					self.de.pending_key = None;
+					self.sections_read += 1;
+					return seed.deserialize(self.name.clone().into_deserializer()).map(Some)
+				},
+				Some(SectionMarker::End) => {
+					// This is the end of a section that had no keys in it, or an `END_` marker without a matching `BEGIN_`. Either way, there's nothing to do but skip it.
+					self.de.pending_key = None;
+				},
+				None => {
+					// This is an ordinary key that isn't in any section. Such keys are in the section whose name is empty. Leave the key pending, for the section's map to pick up.
+					self.name.clear();
+					self.sections_read += 1;
+					return seed.deserialize("".into_deserializer()).map(Some)
+				}
+			}
+		}
+	}
+
+	fn next_value_seed<V>(&mut self, seed: V) -> Result<V::Value>
+	where V: DeserializeSeed<'de> {
+		let result = seed.deserialize(AaSectionDeserializer { de: self.de });
+
+		// Keys outside of any section are in the section with no name. There's no sense mentioning that in error messages.
+		if self.name.is_empty() {
+			result
+		}
+		else {
+			result.map_err(|error| error.in_key(&self.name))
+		}
+	}
+}
+
+/// Deserializer for the keys in one section.
+struct AaSectionDeserializer<'a, R> {
+	de: &'a mut Deserializer<R>
+}
+
+impl<'de, 'a, R: BufRead> serde::Deserializer<'de> for AaSectionDeserializer<'a, R> {
+	type Error = Error;
+
+	fn is_human_readable(&self) -> bool { true }
+
+	fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		let result = visitor.visit_map(AaTopMapAccess::in_section(self.de));
+		result.map_err(|error| self.de.locate_missing_field(error))
+	}
+
+	serde::forward_to_deserialize_any! {
+		bool i8 i16 i32 i64 i128 u8 u16 u32 u64 u128 f32 f64 char str string
+		bytes byte_buf option unit unit_struct newtype_struct seq tuple
+		tuple_struct map struct enum identifier ignored_any
+	}
+}
@@ -0,0 +1,350 @@
+//! Finding what changed between two versions of some `.aa` data, such as two backups of a store's settings.
+//! 
+//! `diff` compares two `Value`s, usually `Value::Map`s read from files, and lists the keys that were added, removed, or modified. Sequences are compared element by element, so a long list that had one element added shows up as one added element, not as a whole new list.
+//! 
+//! ```
+//! use shopsite_aa::de as aa;
+//! 
+//! let old: aa::Value = aa::from_bytes(b"Open\nStoreName: Widgets\nTax: 5\n", None).unwrap();
+//! let new: aa::Value = aa::from_bytes(b"StoreName: Widgets\nTax: 6\nCurrency: USD\n", None).unwrap();
+//! 
+//! assert_eq!(aa::diff(&old, &new), [
+//!     aa::Change::Removed { key: "Open".to_string(), value: aa::Value::Unit },
+//!     aa::Change::Modified { key: "Tax".to_string(), old: aa::Value::from("5"), new: aa::Value::from("6") },
+//!     aa::Change::Added { key: "Currency".to_string(), value: aa::Value::from("USD") }
+//! ]);
+//! ```
+//! 
+//! Values read from `.aa` files are text, even if they're sequences, since there's no telling whether a `|` is a separator, so `diff` reports a changed list as one `Change::Modified`. To compare lists element by element, either use `diff_split`, which splits text values at a delimiter, or turn them into `Value::Seq`s first, as `to_value` does for a struct with `Vec` fields:
+//! 
+//! ```
+//! use shopsite_aa::de as aa;
+//! 
+//! let old: aa::Value = aa::from_bytes(b"Categories: Tools|Garden\n", None).unwrap();
+//! let new: aa::Value = aa::from_bytes(b"Categories: Tools|Garden|Sale\n", None).unwrap();
+//! 
+//! assert_eq!(aa::diff_split(&old, &new, '|'), [
+//!     aa::Change::ElementAdded { key: "Categories".to_string(), index: 2, value: aa::Value::from("Sale") }
+//! ]);
+//! ```
+//! 
+//! `Change` is `Serialize`, so a list of changes can be written out as JSON or the like, for a report or a monitoring system.
+
+use crate::value::{Map, Value};
+use serde::ser::{Serialize, SerializeStruct, Serializer};
+use std::{
+	convert::Infallible,
+	fmt::{self, Display, Formatter}
+};
+
+/// One difference between two `Value`s. See `diff`.
+/// 
+/// Keys in sections (values that are maps on both sides) are the section name, a `.`, and the key, like `Shipping.Zip`. If the values being compared aren't maps at all, the key is empty.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Change {
+	/// A key that is only in the new value.
+	Added {
+		key: String,
+		value: Value
+	},
+
+	/// A key that is only in the old value.
+	Removed {
+		key: String,
+		value: Value
+	},
+
+	/// A key that is in both, with different values, which aren't both sequences.
+	Modified {
+		key: String,
+		old: Value,
+		new: Value
+	},
+
+	/// An element that is only in the new sequence. `index` is where it is in the new sequence.
+	ElementAdded {
+		key: String,
+		index: usize,
+		value: Value
+	},
+
+	/// An element that is only in the old sequence. `index` is where it was in the old sequence.
+	ElementRemoved {
+		key: String,
+		index: usize,
+		value: Value
+	},
+
+	/// An element that was replaced with a different one. `index` is where it was in the old sequence.
+	ElementModified {
+		key: String,
+		index: usize,
+		old: Value,
+		new: Value
+	}
+}
+
+impl Change {
+	/// The key that changed.
+	pub fn key(&self) -> &str {
+		match self {
+			Change::Added { key, .. } |
+			Change::Removed { key, .. } |
+			Change::Modified { key, .. } |
+			Change::ElementAdded { key, .. } |
+			Change::ElementRemoved { key, .. } |
+			Change::ElementModified { key, .. } => key
+		}
+	}
+
+	/// The name of this kind of change, in snake case, like `element_added`. This is the `kind` field when a `Change` is serialized.
+	pub fn kind(&self) -> &'static str {
+		match self {
+			Change::Added { .. } => "added",
+			Change::Removed { .. } => "removed",
+			Change::Modified { .. } => "modified",
+			Change::ElementAdded { .. } => "element_added",
+			Change::ElementRemoved { .. } => "element_removed",
+			Change::ElementModified { .. } => "element_modified"
+		}
+	}
+}
+
+impl Display for Change {
+	/// Writes the change as lines of a diff: `- ` before the old key and value, and `+ ` before the new one. Elements of sequences are written with their index, like `Categories[2]`.
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		fn write_line(f: &mut Formatter<'_>, sign: char, key: &str, index: Option<usize>, value: &Value) -> fmt::Result {
+			write!(f, "{} {}", sign, key)?;
+
+			if let Some(index) = index {
+				write!(f, "[{}]", index)?;
+			}
+
+			match value {
+				Value::Unit => Ok(()),
+				value => write!(f, ": {}", value)
+			}
+		}
+
+		match self {
+			Change::Added { key, value } => write_line(f, '+', key, None, value),
+			Change::Removed { key, value } => write_line(f, '-', key, None, value),
+			Change::ElementAdded { key, index, value } => write_line(f, '+', key, Some(*index), value),
+			Change::ElementRemoved { key, index, value } => write_line(f, '-', key, Some(*index), value),
+			Change::Modified { key, old, new } => {
+				write_line(f, '-', key, None, old)?;
+				f.write_str("\n")?;
+				write_line(f, '+', key, None, new)
+			},
+			Change::ElementModified { key, index, old, new } => {
+				write_line(f, '-', key, Some(*index), old)?;
+				f.write_str("\n")?;
+				write_line(f, '+', key, Some(*index), new)
+			}
+		}
+	}
+}
+
+impl Serialize for Change {
+	/// Serializes the change as a struct with a `kind` field, which is what `Change::kind` returns, and then the fields of the variant. In JSON, that looks like `{"kind": "modified", "key": "Tax", "old": "5", "new": "6"}`.
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let (index, values): (Option<usize>, &[(&'static str, &Value)]) = match self {
+			Change::Added { value, .. } | Change::Removed { value, .. } => (None, &[("value", value)]),
+			Change::Modified { old, new, .. } => (None, &[("old", old), ("new", new)]),
+			Change::ElementAdded { index, value, .. } | Change::ElementRemoved { index, value, .. } => (Some(*index), &[("value", value)]),
+			Change::ElementModified { index, old, new, .. } => (Some(*index), &[("old", old), ("new", new)])
+		};
+
+		let mut s = serializer.serialize_struct("Change", 2 + usize::from(index.is_some()) + values.len())?;
+		s.serialize_field("kind", self.kind())?;
+		s.serialize_field("key", self.key())?;
+
+		if let Some(index) = index {
+			s.serialize_field("index", &index)?;
+		}
+
+		for (name, value) in values {
+			s.serialize_field(name, value)?;
+		}
+
+		s.end()
+	}
+}
+
+/// Finds the differences between two values. Keys that were removed or modified come first, in the order of `old`, then keys that were added, in the order of `new`.
+/// 
+/// Sections (values that are maps on both sides) are compared key by key, rather than as a whole. Sequences (values that are `Value::Seq` on both sides) are compared element by element: elements that are in both, in the same order, are left alone, and the rest are `Change::ElementRemoved`, `Change::ElementAdded`, or, where an element was removed and another added in its place, `Change::ElementModified`.
+pub fn diff(old: &Value, new: &Value) -> Vec<Change> {
+	let mut changes = Vec::new();
+	diff_into(String::new(), old, new, None, &mut changes);
+	changes
+}
+
+/// Like `diff`, but text values are also compared element by element, as if they were sequences split at `delimiter` (usually `|`), if they're different and either of them has the delimiter in it. This is for comparing lists in data read from `.aa` files, where they're text.
+/// 
+/// The split is a plain one: an empty value has no elements, and escaped delimiters (see `SeqEscape`) are not recognized.
+pub fn diff_split(old: &Value, new: &Value, delimiter: char) -> Vec<Change> {
+	let mut changes = Vec::new();
+	diff_into(String::new(), old, new, Some(delimiter), &mut changes);
+	changes
+}
+
+/// Finds the differences between two maps, as `diff` does for `Value::Map`s, without having to wrap them in one.
+pub(crate) fn diff_maps(old: &Map, new: &Map) -> Vec<Change> {
+	let mut changes = Vec::new();
+	diff_maps_into("", old, new, None, &mut changes);
+	changes
+}
+
+fn diff_into(key: String, old: &Value, new: &Value, split: Option<char>, changes: &mut Vec<Change>) {
+	match (old, new, split) {
+		(Value::Map(old), Value::Map(new), _) => diff_maps_into(&key, old, new, split, changes),
+		(Value::Seq(old), Value::Seq(new), _) => diff_seqs(&key, old, new, changes),
+		(Value::String(old), Value::String(new), Some(delimiter)) if old != new && (old.contains(delimiter) || new.contains(delimiter)) => {
+			let elements = |text: &str| -> Vec<Value> {
+				if text.is_empty() { Vec::new() }
+				else { text.split(delimiter).map(Value::from).collect() }
+			};
+
+			diff_seqs(&key, &elements(old), &elements(new), changes)
+		},
+		_ if old != new => changes.push(Change::Modified { key, old: old.clone(), new: new.clone() }),
+		_ => ()
+	}
+}
+
+fn diff_maps_into(prefix: &str, old: &Map, new: &Map, split: Option<char>, changes: &mut Vec<Change>) {
+	let full_key = |key: &str| {
+		if prefix.is_empty() { key.to_string() }
+		else { format!("{}.{}", prefix, key) }
+	};
+
+	for (key, old_value) in old {
+		match new.get(key) {
+			None => changes.push(Change::Removed { key: full_key(key), value: old_value.clone() }),
+			Some(new_value) => diff_into(full_key(key), old_value, new_value, split, changes)
+		}
+	}
+
+	for (key, new_value) in new {
+		if !old.contains_key(key) {
+			changes.push(Change::Added { key: full_key(key), value: new_value.clone() });
+		}
+	}
+}
+
+/// Compares two sequences element by element, by way of their longest common subsequence.
+/// 
+/// Elements that are the same at the start and end of both are skipped first, since a sequence that changed usually changed in only a few places. What's left in between is compared with Myers' algorithm, from the `similar` library, which needs only linear space, so a long sequence that was rewritten entirely doesn't need a table of every pair of elements.
+fn diff_seqs(key: &str, old: &[Value], new: &[Value], changes: &mut Vec<Change>) {
+	let prefix = old.iter().zip(new).take_while(|(old, new)| old == new).count();
+	let suffix = old[prefix..].iter().rev().zip(new[prefix..].iter().rev()).take_while(|(old, new)| old == new).count();
+
+	let mut runs = SeqChanges { key, old, new, changes, removed: Vec::new(), added: Vec::new() };
+	match similar::algorithms::myers::diff(&mut runs, old, prefix..old.len() - suffix, new, prefix..new.len() - suffix) {
+		Ok(()) => (),
+		Err(never) => match never {}
+	}
+}
+
+/// Gathers the changes that `similar` finds between two sequences into runs of elements that differ, up to the next common one, and turns each run into `Change`s.
+struct SeqChanges<'a> {
+	key: &'a str,
+	old: &'a [Value],
+	new: &'a [Value],
+	changes: &'a mut Vec<Change>,
+	removed: Vec<usize>,
+	added: Vec<usize>
+}
+
+impl SeqChanges<'_> {
+	/// Pairs up the run's removals with its additions, as modifications, and starts a new run.
+	fn end_run(&mut self) {
+		let key = self.key;
+
+		for (&removed_index, &added_index) in self.removed.iter().zip(&self.added) {
+			self.changes.push(Change::ElementModified { key: key.to_string(), index: removed_index, old: self.old[removed_index].clone(), new: self.new[added_index].clone() });
+		}
+
+		for &index in self.removed.iter().skip(self.added.len()) {
+			self.changes.push(Change::ElementRemoved { key: key.to_string(), index, value: self.old[index].clone() });
+		}
+
+		for &index in self.added.iter().skip(self.removed.len()) {
+			self.changes.push(Change::ElementAdded { key: key.to_string(), index, value: self.new[index].clone() });
+		}
+
+		self.removed.clear();
+		self.added.clear();
+	}
+}
+
+impl similar::algorithms::DiffHook for SeqChanges<'_> {
+	type Error = Infallible;
+
+	fn equal(&mut self, _old_index: usize, _new_index: usize, _len: usize) -> Result<(), Infallible> {
+		self.end_run();
+		Ok(())
+	}
+
+	fn delete(&mut self, old_index: usize, old_len: usize, _new_index: usize) -> Result<(), Infallible> {
+		self.removed.extend(old_index..old_index + old_len);
+		Ok(())
+	}
+
+	fn insert(&mut self, _old_index: usize, new_index: usize, new_len: usize) -> Result<(), Infallible> {
+		self.added.extend(new_index..new_index + new_len);
+		Ok(())
+	}
+
+	fn finish(&mut self) -> Result<(), Infallible> {
+		self.end_run();
+		Ok(())
+	}
+}
+
+#[test]
+fn test_diff_seqs() {
+	fn seq(elements: &[&str]) -> Vec<Value> {
+		elements.iter().map(|&element| Value::from(element)).collect()
+	}
+
+	fn changes(old: &[&str], new: &[&str]) -> Vec<String> {
+		let mut changes = Vec::new();
+		diff_seqs("k", &seq(old), &seq(new), &mut changes);
+		changes.iter().map(|change| change.to_string().replace('\n', "; ")).collect()
+	}
+
+	assert!(changes(&["a", "b"], &["a", "b"]).is_empty());
+	assert_eq!(changes(&["a", "b", "c"], &["a", "c"]), ["- k[1]: b"]);
+	assert_eq!(changes(&["a", "c"], &["a", "b", "c", "d"]), ["+ k[1]: b", "+ k[3]: d"]);
+	assert_eq!(changes(&["a", "b", "c"], &["a", "x", "c"]), ["- k[1]: b; + k[1]: x"]);
+	assert_eq!(changes(&["a", "b", "c"], &["x", "y"]), ["- k[0]: a; + k[0]: x", "- k[1]: b; + k[1]: y", "- k[2]: c"]);
+	assert_eq!(changes(&[], &["a"]), ["+ k[0]: a"]);
+	assert_eq!(changes(&["a", "b", "a"], &["a"]), ["- k[1]: b", "- k[2]: a"]);
+
+	// Long sequences with a few changes in them are compared without a table of every pair of elements.
+	let old: Vec<String> = (0..100_000).map(|n| n.to_string()).collect();
+	let mut new = old.clone();
+	new[10] = "x".to_string();
+	new.remove(60_000);
+	new.insert(90_000, "y".to_string());
+	let old: Vec<&str> = old.iter().map(String::as_str).collect();
+	let new: Vec<&str> = new.iter().map(String::as_str).collect();
+	assert_eq!(changes(&old, &new), ["- k[10]: 10; + k[10]: x", "- k[60000]: 60000", "+ k[90000]: y"]);
+}
+
+#[test]
+fn test_diff_split() {
+	let old = Value::from("a|b|c");
+
+	assert_eq!(diff_split(&old, &Value::from("a|c"), '|'), [Change::ElementRemoved { key: String::new(), index: 1, value: Value::from("b") }]);
+	assert_eq!(diff_split(&Value::from("a"), &Value::from("a|b"), '|'), [Change::ElementAdded { key: String::new(), index: 1, value: Value::from("b") }]);
+	assert_eq!(diff_split(&Value::from(""), &Value::from("a|b"), '|').len(), 2);
+
+	// Text without the delimiter on either side is compared as a whole.
+	assert_eq!(diff_split(&Value::from("a"), &Value::from("b"), '|'), [Change::Modified { key: String::new(), old: Value::from("a"), new: Value::from("b") }]);
+	assert!(diff_split(&old, &old, '|').is_empty());
+	assert_eq!(diff(&old, &Value::from("a|c")).len(), 1);
+}
@@ -0,0 +1,472 @@
+//! A `.aa` file as a sequence of lines, which keeps every byte of the file, for programs that need to change a file without disturbing the parts they don't change.
+//! 
+//! Deserializing a file keeps only what it means. A `Document` keeps what it says, too: comments, blank lines, the order of the keys, repeated keys, line endings, and all. Writing a `Document` back out with `to_bytes` reproduces the file exactly.
+//! 
+//! ```
+//! use shopsite_aa::{de as aa, document::Document};
+//! 
+//! let doc = Document::parse(b"# Store settings\r\nStoreName: Widgets\r\nOpen\r\n".to_vec());
+//! 
+//! assert_eq!(doc.get("StoreName"), Some(aa::Value::from("Widgets")));
+//! assert_eq!(doc.get("Open"), Some(aa::Value::Unit));
+//! assert_eq!(doc.line_ending(), Some(aa::LineEnding::CrLf));
+//! assert_eq!(doc.to_bytes(), b"# Store settings\r\nStoreName: Widgets\r\nOpen\r\n");
+//! ```
+//! 
+//! To change a value, use `Document::set`, which rewrites only the line the key is on. `patch_file` does that to a file on disk:
+//! 
+//! ```
+//! use shopsite_aa::{de as aa, document::Document};
+//! 
+//! let mut doc = Document::parse(b"# Store settings\r\nStoreName: Widgets\r\nOpen\r\n".to_vec());
+//! doc.set("StoreName", "Gadgets").unwrap();
+//! doc.set("Currency", &aa::Value::from("USD")).unwrap();
+//! assert_eq!(doc.to_bytes(), b"# Store settings\r\nStoreName: Gadgets\r\nOpen\r\nCurrency: USD\r\n");
+//! ```
+//! 
+//! Keys and values are found the same way `scan::entries` finds them, and decoded in the document's encoding, which is Windows-1252 unless set otherwise with `Document::parse_with_encoding`. There are no sections or records here; every key is at the top level, and when a key appears more than once, the last one is the one that counts, as when deserializing into a `Map`.
+
+use crate::{
+	de::{InputEncoding, LineEnding, Map, Value},
+	diff::Change,
+	scan,
+	ser
+};
+use serde::Serialize;
+use std::{
+	collections::{BTreeSet, HashMap},
+	fs,
+	io::Write,
+	path::Path
+};
+use tempfile::NamedTempFile;
+
+/// A `.aa` file, line by line. See the module documentation.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Document {
+	/// Whether the file starts with a UTF-8 byte order mark. It isn't part of the first line.
+	bom: bool,
+
+	lines: Vec<Line>,
+
+	encoding: InputEncoding
+}
+
+/// One line of a `Document`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Line {
+	/// The bytes of the line, including its line ending, if it has one.
+	bytes: Vec<u8>,
+
+	/// How many of the bytes at the end are the line ending: 0, 1, or 2.
+	ending_len: usize,
+
+	kind: LineKind
+}
+
+/// What a line of a `Document` has on it.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum LineKind {
+	/// Nothing but whitespace, if anything.
+	Blank,
+
+	/// A comment, starting with `#`, possibly after whitespace.
+	Comment,
+
+	/// A key, which ends at byte `key_end`, and its value, which starts at byte `value_start`, if it has one.
+	Entry {
+		key_end: usize,
+		value_start: Option<usize>
+	}
+}
+
+impl Line {
+	/// Makes a line out of its bytes, including its line ending.
+	fn new(bytes: Vec<u8>) -> Line {
+		let ending_len = {
+			if bytes.ends_with(b"\r\n") { 2 }
+			else if bytes.ends_with(b"\n") || bytes.ends_with(b"\r") { 1 }
+			else { 0 }
+		};
+
+		let content = &bytes[..bytes.len() - ending_len];
+
+		// The line ending goes to `scan` too, since a value at the very end of the input is read a little differently: if it's all whitespace, it's empty.
+		let kind = match scan::entries(&bytes).next() {
+			None if content.iter().any(|byte| !byte.is_ascii_whitespace()) => LineKind::Comment,
+			None => LineKind::Blank,
+			Some(entry) => LineKind::Entry {
+				key_end: entry.key.len(),
+				value_start: entry.value.map(|value| content.len() - value.len())
+			}
+		};
+
+		Line {
+			bytes,
+			ending_len,
+			kind
+		}
+	}
+
+	/// The bytes of the line, including its line ending, exactly as they are in the file.
+	pub fn bytes(&self) -> &[u8] {
+		&self.bytes
+	}
+
+	/// The bytes of the line, not including its line ending.
+	pub fn content(&self) -> &[u8] {
+		&self.bytes[..self.bytes.len() - self.ending_len]
+	}
+
+	/// The line's line ending, or `None` if it doesn't have one, which only the last line of a file can lack.
+	pub fn line_ending(&self) -> Option<LineEnding> {
+		match &self.bytes[self.bytes.len() - self.ending_len..] {
+			b"\r\n" => Some(LineEnding::CrLf),
+			b"\n" => Some(LineEnding::Lf),
+			b"\r" => Some(LineEnding::Cr),
+			_ => None
+		}
+	}
+
+	/// Whether the line is a comment.
+	pub fn is_comment(&self) -> bool {
+		self.kind == LineKind::Comment
+	}
+
+	/// Whether the line is empty or only whitespace.
+	pub fn is_blank(&self) -> bool {
+		self.kind == LineKind::Blank
+	}
+
+	/// The key on this line, undecoded, and its value, undecoded, or `None` if the key has no value. Returns `None` if the line is a comment or blank.
+	pub fn entry(&self) -> Option<(&[u8], Option<&[u8]>)> {
+		match self.kind {
+			LineKind::Entry { key_end, value_start } => {
+				let content = self.content();
+				Some((&content[..key_end], value_start.map(|start| &content[start..])))
+			},
+			_ => None
+		}
+	}
+}
+
+
+/// Splits `bytes` into lines, each with its line ending. A CR+LF pair is one line ending, not two.
+fn split_lines(bytes: &[u8]) -> impl Iterator<Item = &[u8]> {
+	let mut rest = bytes;
+
+	std::iter::from_fn(move || {
+		if rest.is_empty() {
+			return None
+		}
+
+		let mut len = memchr::memchr2(b'\r', b'\n', rest).map_or(rest.len(), |end| end + 1);
+
+		if rest[..len].ends_with(b"\r") && rest.get(len) == Some(&b'\n') {
+			len += 1;
+		}
+
+		let (line, after) = rest.split_at(len);
+		rest = after;
+		Some(line)
+	})
+}
+
+impl Document {
+	/// Parses a `.aa` file in ShopSite's encoding, Windows-1252. This can't fail; everything in a file is a line of some kind.
+	pub fn parse(bytes: impl AsRef<[u8]>) -> Document {
+		Document::parse_with_encoding(bytes, InputEncoding::default())
+	}
+
+	/// Parses a `.aa` file in the given encoding.
+	pub fn parse_with_encoding(bytes: impl AsRef<[u8]>, encoding: InputEncoding) -> Document {
+		let bytes = bytes.as_ref();
+		let (bom, bytes) = match bytes.strip_prefix(b"\xef\xbb\xbf") {
+			Some(rest) => (true, rest),
+			None => (false, bytes)
+		};
+
+		Document {
+			bom,
+			lines: split_lines(bytes).map(|line| Line::new(line.to_vec())).collect(),
+			encoding
+		}
+	}
+
+	/// The file's bytes, exactly as they were parsed, plus any changes made since.
+	pub fn to_bytes(&self) -> Vec<u8> {
+		let mut bytes = Vec::with_capacity(self.lines.iter().map(|line| line.bytes.len()).sum::<usize>() + 3);
+
+		if self.bom {
+			bytes.extend_from_slice(b"\xef\xbb\xbf");
+		}
+
+		for line in &self.lines {
+			bytes.extend_from_slice(&line.bytes);
+		}
+
+		bytes
+	}
+
+	/// The encoding that keys and values are decoded in.
+	pub fn encoding(&self) -> InputEncoding {
+		self.encoding
+	}
+
+	/// The lines of the file, in order.
+	pub fn lines(&self) -> &[Line] {
+		&self.lines
+	}
+
+	/// Which line endings the file uses, or `None` if it has none, like `Deserializer::line_ending`.
+	pub fn line_ending(&self) -> Option<LineEnding> {
+		self.lines.iter().filter_map(Line::line_ending).try_fold(None, |seen, ending| match seen {
+			Some(seen) if seen != ending => Err(LineEnding::Mixed),
+			_ => Ok(Some(ending))
+		}).unwrap_or_else(Some)
+	}
+
+	/// Decodes some bytes in the document's encoding.
+	fn decode(&self, bytes: &[u8]) -> String {
+		self.encoding.decode(bytes)
+	}
+
+	/// Decodes the value on a line, as it would be deserialized into a `Value`.
+	fn decode_value(&self, value: Option<&[u8]>) -> Value {
+		match value {
+			Some(value) => Value::String(self.decode(value)),
+			None => Value::Unit
+		}
+	}
+
+	/// The keys and values in the file, decoded, in the order they appear, including repeats.
+	pub fn entries(&self) -> impl Iterator<Item = (String, Value)> + '_ {
+		self.lines.iter()
+		.filter_map(Line::entry)
+		.map(move |(key, value)| (self.decode(key), self.decode_value(value)))
+	}
+
+	/// Gets the value of a key, or `None` if the file doesn't have it. If the key appears more than once, this is the value of the last one.
+	pub fn get(&self, key: &str) -> Option<Value> {
+		let encoded = self.encoding.encode(key)?;
+
+		self.lines.iter().rev()
+		.filter_map(Line::entry)
+		.find(|(line_key, _)| *line_key == &*encoded)
+		.map(|(_, value)| self.decode_value(value))
+	}
+
+	/// The keys and values in the file, as a `Map`. As with `get`, the last of each key wins.
+	pub fn to_map(&self) -> Map {
+		self.entries().collect()
+	}
+
+	/// Sets the value of a key, the way the serializer would write it. `()`, `Value::Unit`, and `None` make it a key without a value.
+	/// 
+	/// If the file has the key, only the line it's on is rewritten, keeping whatever comes before the value on that line, like the `: `, and the line ending. If the key appears more than once, it's the last one that's rewritten, since that's the one that counts. If the file doesn't have the key, it's added at the end, with the same line ending as the rest of the file, or `\r\n` if there's no telling. Every other line is left exactly as it is, except that if the last line has no line ending, it gets one, so that the new line is on a line of its own.
+	/// 
+	/// # Errors
+	/// 
+	/// Fails with a `ser::Error` if the key or value can't be written in a `.aa` file, or in the document's encoding. The document isn't changed.
+	pub fn set<T: Serialize + ?Sized>(&mut self, key: &str, value: &T) -> ser::Result<()> {
+		let (key, value) = ser::encode_entry(key, value, self.encoding)?;
+
+		let existing = self.lines.iter().rposition(|line| line.entry().is_some_and(|(line_key, _)| line_key == &key[..]));
+
+		if let Some(index) = existing {
+			let line = &self.lines[index];
+			let (key_end, value_start) = match line.kind {
+				LineKind::Entry { key_end, value_start } => (key_end, value_start),
+				_ => unreachable!()
+			};
+
+			let mut bytes = match (value_start, &value) {
+				(Some(start), Some(_)) => line.content()[..start].to_vec(),
+				(None, Some(_)) => [&key[..], b": "].concat(),
+				(_, None) => line.content()[..key_end].to_vec()
+			};
+
+			bytes.extend_from_slice(value.as_deref().unwrap_or_default());
+			bytes.extend_from_slice(&line.bytes[line.bytes.len() - line.ending_len..]);
+
+			if bytes != line.bytes {
+				self.lines[index] = Line::new(bytes);
+			}
+		}
+		else {
+			let line_ending = match self.line_ending() {
+				Some(LineEnding::Mixed) | None => LineEnding::CrLf,
+				Some(line_ending) => line_ending
+			}.as_str().as_bytes();
+
+			let mut bytes = key;
+
+			if let Some(value) = value {
+				bytes.extend_from_slice(b": ");
+				bytes.extend_from_slice(&value);
+			}
+
+			// The new line ends the way the last line did: with a line ending, unless the file didn't end with one.
+			match self.lines.last_mut() {
+				Some(last) if last.ending_len == 0 => {
+					let mut last_bytes = std::mem::take(&mut last.bytes);
+					last_bytes.extend_from_slice(line_ending);
+					*last = Line::new(last_bytes);
+				},
+				_ => bytes.extend_from_slice(line_ending)
+			}
+
+			self.lines.push(Line::new(bytes));
+		}
+
+		Ok(())
+	}
+
+	/// The comment at the top of the file, if there is one, like the banner that says which program wrote it, without the `#` signs. This is the comment lines at the very beginning of the file, if a blank line comes after them; if a key comes right after them instead, they're that key's comment, and are in `key_comments`.
+	pub fn header(&self) -> Option<String> {
+		let comments = self.lines.iter().take_while(|line| line.is_comment()).count();
+
+		match self.lines.get(comments) {
+			Some(line) if comments != 0 && line.is_blank() => Some(self.comment_text(&self.lines[..comments])),
+			_ => None
+		}
+	}
+
+	/// The comments just above keys, without the `#` signs, by key. A key's comment is the comment lines right above it, with no blank line in between. If a key appears more than once, this is the comment of the last one, as with `get`.
+	pub fn key_comments(&self) -> HashMap<String, String> {
+		let mut comments = HashMap::new();
+		let mut comment_start = 0;
+
+		for (index, line) in self.lines.iter().enumerate() {
+			if let Some((key, _)) = line.entry() {
+				let key = self.decode(key);
+
+				if comment_start < index {
+					comments.insert(key, self.comment_text(&self.lines[comment_start..index]));
+				}
+				else {
+					comments.remove(&key);
+				}
+			}
+
+			if !line.is_comment() {
+				comment_start = index + 1;
+			}
+		}
+
+		comments
+	}
+
+	/// Decodes the text of some comment lines, without the `#` signs and the space after them, one line of text per line.
+	fn comment_text(&self, lines: &[Line]) -> String {
+		lines.iter().map(|line| {
+			let content = line.content().trim_ascii_start();
+			let content = content.strip_prefix(b"#").unwrap_or(content);
+			let content = content.strip_prefix(b" ").unwrap_or(content);
+			self.decode(content)
+		}).collect::<Vec<String>>().join("\n")
+	}
+
+	/// Replaces the contents of this document with a newer version of the same file, such as after it's changed on disk, and reports which keys changed. `Change::Removed` is a key that was removed, `Change::Added` is one that was added, and `Change::Modified` is one whose value changed.
+	/// 
+	/// Only the lines that changed are parsed. The lines at the beginning and end that are the same as before are kept as they are, and only the lines in between are compared and parsed, so this is quick when a big file has had a small change, as when someone edits a setting or two.
+	pub fn update(&mut self, bytes: impl AsRef<[u8]>) -> Vec<Change> {
+		let bytes = bytes.as_ref();
+		let (bom, bytes) = match bytes.strip_prefix(b"\xef\xbb\xbf") {
+			Some(rest) => (true, rest),
+			None => (false, bytes)
+		};
+
+		let new_lines: Vec<&[u8]> = split_lines(bytes).collect();
+
+		// Find how many lines at the beginning and end are the same as before. Each line is compared at most once, so comparing the bytes is as quick as anything.
+		let prefix = self.lines.iter().zip(&new_lines)
+		.take_while(|(old, new)| old.bytes == **new)
+		.count();
+
+		let suffix = self.lines[prefix..].iter().rev().zip(new_lines[prefix..].iter().rev())
+		.take_while(|(old, new)| old.bytes == **new)
+		.count();
+
+		// Only the lines in between need parsing.
+		let changed_lines: Vec<Line> = new_lines[prefix..new_lines.len() - suffix].iter().map(|line| Line::new(line.to_vec())).collect();
+
+		// Any key on a line that was removed or added may have a different value now. Keys elsewhere can't have.
+		let mut touched: BTreeSet<Vec<u8>> = BTreeSet::new();
+
+		for line in self.lines[prefix..self.lines.len() - suffix].iter().chain(&changed_lines) {
+			if let Some((key, _)) = line.entry() {
+				touched.insert(key.to_vec());
+			}
+		}
+
+		let mut old_values = self.values_of(&touched);
+		self.lines.splice(prefix..self.lines.len() - suffix, changed_lines);
+		self.bom = bom;
+		let mut new_values = self.values_of(&touched);
+
+		touched.iter().filter_map(|key| {
+			let change = match (old_values.remove(&key[..]), new_values.remove(&key[..])) {
+				(Some(value), None) => Change::Removed { key: self.decode(key), value },
+				(None, Some(value)) => Change::Added { key: self.decode(key), value },
+				(Some(old), Some(new)) if old != new => Change::Modified { key: self.decode(key), old, new },
+				_ => return None
+			};
+
+			Some(change)
+		}).collect()
+	}
+
+	/// The values of some keys, which are undecoded, found in one pass over the lines. As with `get`, the last of each key wins. Keys that aren't in the file are left out.
+	fn values_of<'a>(&self, keys: &'a BTreeSet<Vec<u8>>) -> HashMap<&'a [u8], Value> {
+		let mut values = HashMap::new();
+
+		for (key, value) in self.lines.iter().filter_map(Line::entry) {
+			if let Some(key) = keys.get(key) {
+				values.insert(&key[..], self.decode_value(value));
+			}
+		}
+
+		values
+	}
+}
+
+/// Changes the values of some keys in a `.aa` file on disk, in Windows-1252, and leaves the rest of the file exactly as it is, so that a program can change a setting or two without rewriting the whole file. Each key is changed as by `Document::set`, in order. The file is only written if something actually changed.
+/// 
+/// The new contents are written to a uniquely named temporary file next to the file first, with the same permissions, flushed to disk, then renamed over it, so that a crash or a full disk partway through leaves the old file alone. If `path` is a symbolic link, the file it points to is changed, and the link is left as it is.
+/// 
+/// For a file in some other encoding, parse it with `Document::parse_with_encoding`, call `Document::set`, and write it back out with `Document::to_bytes`.
+/// 
+/// # Errors
+/// 
+/// Fails with `ser::Error::Io` if the file can't be read or written, or another `ser::Error` if a key or value can't be written in a `.aa` file. If any key or value can't be written, the file isn't changed at all.
+pub fn patch_file(path: impl AsRef<Path>, changes: &[(&str, Value)]) -> ser::Result<()> {
+	// Resolve symbolic links first, so that the link isn't replaced by a regular file, and the temporary file is on the same file system as the real file.
+	let path = fs::canonicalize(path)?;
+	let bytes = fs::read(&path)?;
+	let mut doc = Document::parse(&bytes);
+
+	for (key, value) in changes {
+		doc.set(key, value)?;
+	}
+
+	let patched = doc.to_bytes();
+
+	if patched != bytes {
+		// The temporary file is deleted when it's dropped, if it hasn't been renamed into place yet.
+		let mut temp = NamedTempFile::new_in(path.parent().unwrap_or(Path::new(".")))?;
+		temp.write_all(&patched)?;
+		temp.as_file().set_permissions(fs::metadata(&path)?.permissions())?;
+		temp.as_file().sync_all()?;
+		temp.persist(&path).map_err(|error| error.error)?;
+	}
+
+	Ok(())
+}
+
+#[test]
+fn test_split_lines() {
+	let lines: Vec<&[u8]> = split_lines(b"a\r\nb\nc\rd\r\r\ne").collect();
+	assert_eq!(lines, [&b"a\r\n"[..], b"b\n", b"c\r", b"d\r", b"\r\n", b"e"]);
+	assert_eq!(split_lines(b"").count(), 0);
+}
@@ -0,0 +1,286 @@
+//! Functions for Serde's `deserialize_with` attribute, for the ways ShopSite spells things that Serde's own `Deserialize` implementations don't understand.
+//! 
+//! ```
+//! use serde::Deserialize;
+//! use shopsite_aa::de as aa;
+//! 
+//! #[derive(Deserialize)]
+//! struct Product {
+//!     #[serde(deserialize_with = "shopsite_aa::helpers::html_string")]
+//!     name: String,
+//! 
+//!     #[serde(deserialize_with = "shopsite_aa::helpers::currency")]
+//!     price: f64,
+//! 
+//!     #[serde(deserialize_with = "shopsite_aa::helpers::percent")]
+//!     discount: f64,
+//! 
+//!     #[serde(default, deserialize_with = "shopsite_aa::helpers::yes_no")]
+//!     taxable: bool,
+//! 
+//!     #[serde(deserialize_with = "shopsite_aa::helpers::comma_list")]
+//!     keywords: Vec<String>
+//! }
+//! 
+//! let product: Product = aa::from_bytes(b"\
+//! name: Caf&eacute; Mug &amp; Saucer
+//! price: $1,234.50
+//! discount: 7.5%
+//! taxable: Yes
+//! keywords: mugs, kitchen,, gifts
+//! ", None).unwrap();
+//! 
+//! assert_eq!(product.name, "Café Mug & Saucer");
+//! assert_eq!(product.price, 1234.5);
+//! assert_eq!(product.discount, 7.5);
+//! assert!(product.taxable);
+//! assert_eq!(product.keywords, ["mugs", "kitchen", "gifts"]);
+//! ```
+//! 
+//! Each of these works with any self-describing data format, not just `.aa`, and with any `Deserializer` options. Numbers in formats that have them, like `"price": 12.5` in JSON, are taken as they are written, so `currency` and `percent` accept them as well as text. For ShopSite's timestamps, see the `datetime` module; `date` and `timestamp` here are shortcuts to it. For exact amounts of money, see the `money` module.
+
+use serde::de::{self, Deserializer, Unexpected, Visitor};
+use std::{
+	borrow::Cow,
+	fmt::{self, Formatter},
+	marker::PhantomData,
+	str::FromStr
+};
+use super::de::{BoolSyntax, InputEncoding, NumberSyntax};
+
+/// Visitor that parses a string using some parsing function.
+struct ParseVisitor<T, F> {
+	parse: F,
+	expecting: &'static str,
+	_t: PhantomData<T>
+}
+
+impl<'de, T, F: FnOnce(&str) -> Option<T>> Visitor<'de> for ParseVisitor<T, F> {
+	type Value = T;
+
+	fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+		f.write_str(self.expecting)
+	}
+
+	fn visit_str<E: de::Error>(self, v: &str) -> Result<T, E> {
+		let expecting = self.expecting;
+
+		(self.parse)(v).ok_or_else(|| E::invalid_value(Unexpected::Str(v), &expecting))
+	}
+
+	fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<T, E> {
+		self.visit_str(&decode_bytes(v))
+	}
+
+	// Numbers, from formats that have them, are parsed the way they'd be written in a `.aa` file.
+
+	fn visit_i64<E: de::Error>(self, v: i64) -> Result<T, E> {
+		self.visit_str(&v.to_string())
+	}
+
+	fn visit_u64<E: de::Error>(self, v: u64) -> Result<T, E> {
+		self.visit_str(&v.to_string())
+	}
+
+	fn visit_f64<E: de::Error>(self, v: f64) -> Result<T, E> {
+		self.visit_str(&v.to_string())
+	}
+}
+
+/// Decodes the undecoded text that the `.aa` deserializer hands over with `UntypedValue::Bytes`, as `Value` does: as UTF-8 if it's valid UTF-8, and as Windows-1252 if not.
+fn decode_bytes(v: &[u8]) -> String {
+	let encoding = match std::str::from_utf8(v) {
+		Ok(_) => InputEncoding::Utf8,
+		Err(_) => InputEncoding::Windows1252
+	};
+
+	encoding.decode(v)
+}
+
+/// Deserializes a string, or a number, and parses it with `parse`.
+fn parse_str<'de, D: Deserializer<'de>, T>(deserializer: D, expecting: &'static str, parse: impl FnOnce(&str) -> Option<T>) -> Result<T, D::Error> {
+	deserializer.deserialize_any(ParseVisitor { parse, expecting, _t: PhantomData })
+}
+
+/// Deserializes a comma-separated list, like `mugs, kitchen, gifts`, as a `Vec`. Whitespace around each item is ignored, and so are empty items, so an empty value is an empty list. Each item is parsed with `FromStr`.
+/// 
+/// This is for lists that ShopSite writes with commas, like keywords and email addresses. Lists separated by `|` are sequences, which the `Deserializer` already handles.
+pub fn comma_list<'de, D: Deserializer<'de>, T: FromStr>(deserializer: D) -> Result<Vec<T>, D::Error> {
+	parse_str(deserializer, "a comma-separated list", |s| {
+		s.split(',')
+		.map(str::trim)
+		.filter(|item| !item.is_empty())
+		.map(|item| T::from_str(item).ok())
+		.collect()
+	})
+}
+
+/// Deserializes a boolean written any of the ways ShopSite writes them: `yes`/`no`, `on`/`off`, `checked`/`unchecked`, `1`/`0`, or `true`/`false`, ignoring letter case, as with `BoolSyntax::Lenient`. An empty value, or a key with no value at all, is `false`, like an unchecked checkbox.
+pub fn yes_no<'de, D: Deserializer<'de>>(deserializer: D) -> Result<bool, D::Error> {
+	struct YesNoVisitor;
+
+	impl<'de> Visitor<'de> for YesNoVisitor {
+		type Value = bool;
+
+		fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+			f.write_str("yes or no")
+		}
+
+		fn visit_bool<E: de::Error>(self, v: bool) -> Result<bool, E> {
+			Ok(v)
+		}
+
+		fn visit_str<E: de::Error>(self, v: &str) -> Result<bool, E> {
+			let v = v.trim();
+
+			if v.is_empty() {
+				Ok(false)
+			}
+			else {
+				BoolSyntax::Lenient.parse(v).map_err(|_| E::invalid_value(Unexpected::Str(v), &self))
+			}
+		}
+
+		fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<bool, E> {
+			self.visit_str(&decode_bytes(v))
+		}
+
+		fn visit_unit<E: de::Error>(self) -> Result<bool, E> {
+			Ok(false)
+		}
+
+		fn visit_none<E: de::Error>(self) -> Result<bool, E> {
+			Ok(false)
+		}
+
+		fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<bool, D::Error> {
+			deserializer.deserialize_any(self)
+		}
+	}
+
+	deserializer.deserialize_any(YesNoVisitor)
+}
+
+/// Deserializes an amount of money, like `$1,234.50`, as an `f64`. Surrounding whitespace, a leading `$` sign, and `,` thousands separators are ignored, as with `NumberSyntax::Lenient`.
+/// 
+/// `f64` can't represent most prices exactly. Where that matters, use `money::Money` instead.
+pub fn currency<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+	parse_str(deserializer, "an amount of money", |s| f64::from_str(&NumberSyntax::Lenient.clean(s)).ok())
+}
+
+/// Deserializes a percentage, like `7.5%`, as an `f64`. The `%` sign is optional, and surrounding whitespace is ignored. The number is as written, not divided by 100, so `7.5%` is `7.5`.
+pub fn percent<'de, D: Deserializer<'de>>(deserializer: D) -> Result<f64, D::Error> {
+	parse_str(deserializer, "a percentage", |s| {
+		let s = s.trim();
+		let s = s.strip_suffix('%').unwrap_or(s);
+		f64::from_str(s.trim_end()).ok()
+	})
+}
+
+/// Deserializes a string, decoding any HTML character references in it, like `&amp;`, `&eacute;`, or `&#8217;`. See `decode_html_entities`.
+/// 
+/// ShopSite stores some text, like product names, the way it's written in the store's HTML, entities and all.
+pub fn html_string<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+	parse_str(deserializer, "a string", |s| Some(decode_html_entities(s).into_owned()))
+}
+
+/// Deserializes a date, like `07/04/2021`, as a `chrono::NaiveDate`. This is the same as `datetime::date::deserialize`.
+#[cfg(feature = "chrono")]
+pub fn date<'de, D: Deserializer<'de>>(deserializer: D) -> Result<chrono::NaiveDate, D::Error> {
+	super::datetime::date::deserialize(deserializer)
+}
+
+/// Deserializes a timestamp, like `04/15/2020 13:45:00`, as a `chrono::NaiveDateTime`. This is the same as `datetime::naive::deserialize`.
+#[cfg(feature = "chrono")]
+pub fn timestamp<'de, D: Deserializer<'de>>(deserializer: D) -> Result<chrono::NaiveDateTime, D::Error> {
+	super::datetime::naive::deserialize(deserializer)
+}
+
+/// Named character references that `decode_html_entities` knows, sorted by name: the five from XML, and the ones for the punctuation, symbols, and accented letters that turn up in store text.
+const NAMED_ENTITIES: &[(&str, char)] = &[
+	("AElig", 'Æ'), ("Aacute", 'Á'), ("Agrave", 'À'), ("Auml", 'Ä'), ("Ccedil", 'Ç'), ("Eacute", 'É'), ("Egrave", 'È'), ("Iacute", 'Í'), ("Ntilde", 'Ñ'), ("Oacute", 'Ó'), ("Ouml", 'Ö'), ("Uacute", 'Ú'), ("Uuml", 'Ü'),
+	("aacute", 'á'), ("acirc", 'â'), ("aelig", 'æ'), ("agrave", 'à'), ("amp", '&'), ("apos", '\''), ("aring", 'å'), ("auml", 'ä'),
+	("bull", '•'), ("ccedil", 'ç'), ("cent", '¢'), ("copy", '©'), ("deg", '°'), ("divide", '÷'),
+	("eacute", 'é'), ("ecirc", 'ê'), ("egrave", 'è'), ("euml", 'ë'), ("euro", '€'),
+	("frac12", '½'), ("frac14", '¼'), ("frac34", '¾'), ("gt", '>'), ("hellip", '…'),
+	("iacute", 'í'), ("icirc", 'î'), ("iexcl", '¡'), ("iquest", '¿'), ("iuml", 'ï'),
+	("laquo", '«'), ("ldquo", '“'), ("lsquo", '‘'), ("lt", '<'), ("mdash", '—'), ("middot", '·'), ("nbsp", '\u{a0}'), ("ndash", '–'), ("ntilde", 'ñ'),
+	("oacute", 'ó'), ("ocirc", 'ô'), ("ouml", 'ö'), ("para", '¶'), ("plusmn", '±'), ("pound", '£'), ("quot", '"'),
+	("raquo", '»'), ("rdquo", '”'), ("reg", '®'), ("rsquo", '’'), ("sect", '§'), ("szlig", 'ß'), ("times", '×'), ("trade", '™'),
+	("uacute", 'ú'), ("ucirc", 'û'), ("uuml", 'ü'), ("yen", '¥')
+];
+
+/// Decodes the character reference at the start of `s`, which starts just after the `&`. Returns the character and how many bytes of `s` the reference takes up, including the `;`.
+fn decode_entity(s: &str) -> Option<(char, usize)> {
+	let end = s.get(..33)
+	.unwrap_or(s)
+	.find(';')?;
+
+	let name = &s[..end];
+
+	let c = match name.strip_prefix('#') {
+		Some(number) => {
+			let code = match number.strip_prefix(['x', 'X']) {
+				Some(hex) => u32::from_str_radix(hex, 16),
+				None => u32::from_str(number)
+			}.ok()?;
+
+			match code {
+				// Browsers take these to be Windows-1252, since that's what they almost always are, and so does this.
+				0x80..=0x9f => {
+					use encoding::{all::WINDOWS_1252, DecoderTrap, Encoding};
+					WINDOWS_1252.decode(&[code as u8], DecoderTrap::Strict).ok()?.chars().next()?
+				},
+				0 => return None,
+				_ => char::from_u32(code)?
+			}
+		},
+		None => {
+			let index = NAMED_ENTITIES.binary_search_by_key(&name, |(name, _)| name).ok()?;
+			NAMED_ENTITIES[index].1
+		}
+	};
+
+	Some((c, end + 1))
+}
+
+/// Decodes the HTML character references in a string: named ones like `&amp;` and `&eacute;`, and numeric ones like `&#233;` and `&#xE9;`. Numeric references from 128 to 159 are taken to be Windows-1252, as browsers do, so `&#146;` is `’`.
+/// 
+/// Anything that isn't a character reference this knows, such as a lone `&` or an unknown name like `&bogus;`, is left as it is. Only the commonly used named references are known, not all two thousand-odd of them.
+/// 
+/// Returns the string as it is, without copying it, if there's nothing to decode.
+pub fn decode_html_entities(s: &str) -> Cow<'_, str> {
+	if !s.contains('&') {
+		return Cow::Borrowed(s)
+	}
+
+	let mut decoded = String::with_capacity(s.len());
+	let mut rest = s;
+
+	while let Some(amp) = rest.find('&') {
+		decoded.push_str(&rest[..amp]);
+		rest = &rest[amp + 1..];
+
+		match decode_entity(rest) {
+			Some((c, len)) => {
+				decoded.push(c);
+				rest = &rest[len..];
+			},
+			None => decoded.push('&')
+		}
+	}
+
+	decoded.push_str(rest);
+	Cow::Owned(decoded)
+}
+
+#[test]
+fn test_decode_html_entities() {
+	assert!(NAMED_ENTITIES.windows(2).all(|pair| pair[0].0 < pair[1].0), "NAMED_ENTITIES must be sorted");
+
+	assert!(matches!(decode_html_entities("no entities"), Cow::Borrowed("no entities")));
+	assert_eq!(decode_html_entities("Caf&eacute; &amp; Bar"), "Café & Bar");
+	assert_eq!(decode_html_entities("&#233;&#xE9;&#xe9;&#146;"), "ééé’");
+	assert_eq!(decode_html_entities("AT&T &bogus; &#0; &#xFFFFFFFF; &amp"), "AT&T &bogus; &#0; &#xFFFFFFFF; &amp");
+	assert_eq!(decode_html_entities("&&lt;&"), "&<&");
+}
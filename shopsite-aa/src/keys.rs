@@ -0,0 +1,75 @@
+//! The spellings of well-known ShopSite keys and column names.
+//! 
+//! ShopSite isn't consistent about how it spells things. Some names have spaces (`Order Number`), some are run together (`QuantityOnHand`), and capitalization varies. These constants spell them the way ShopSite does, so they don't have to be looked up and typed by hand, and so that typos are compile errors instead of silently missing fields.
+//! 
+//! They can be used anywhere a key is looked up at run time, like indexing a `value::Map`, naming a field in a query, or finding a column in an export:
+//! 
+//! ```
+//! use shopsite_aa::{de as aa, keys::product};
+//! 
+//! let product: aa::Map = aa::from_bytes(b"Name: Blue Widget\nSKU: W-1\n", None).unwrap();
+//! assert_eq!(product[product::SKU], aa::Value::from("W-1"));
+//! ```
+//! 
+//! Serde's `#[serde(rename = "…")]` attribute only accepts a string literal, not a constant, so struct fields still need the name spelled out there. Each constant's documentation shows its spelling, for copying.
+
+/// Defines a module of key constants, along with an `ALL` list of every key in it.
+macro_rules! keys {
+	($(#[$module_doc:meta])* $module:ident { $($(#[$doc:meta])* $name:ident = $key:literal;)* }) => {
+		$(#[$module_doc])*
+		pub mod $module {
+			$(
+				$(#[$doc])*
+				#[doc = ""]
+				#[doc = concat!("Spelled `", $key, "`.")]
+				pub const $name: &str = $key;
+			)*
+
+			/// Every key in this module, in the order they're listed here.
+			pub const ALL: &[&str] = &[$($name),*];
+		}
+	}
+}
+
+keys! {
+	/// Keys of product records and columns of product exports.
+	product {
+		/// The product's name.
+		NAME = "Name";
+
+		/// The product's stock-keeping unit, which identifies it.
+		SKU = "SKU";
+
+		/// The product's price.
+		PRICE = "Price";
+
+		/// How many of the product are in stock.
+		QUANTITY_ON_HAND = "QuantityOnHand";
+
+		/// The category the product is in.
+		CATEGORY = "Category";
+	}
+}
+
+keys! {
+	/// Keys of order records and columns of order exports.
+	order {
+		/// The order number, which identifies the order.
+		ORDER_NUMBER = "Order Number";
+
+		/// When the order was placed.
+		DATE = "Date";
+
+		/// The email address of the customer who placed the order.
+		EMAIL = "Email";
+
+		/// The SKU of the product ordered, in an order line.
+		SKU = "SKU";
+
+		/// How many of the product were ordered, in an order line.
+		QUANTITY = "Quantity";
+
+		/// The total price of an order line.
+		TOTAL = "Total";
+	}
+}
@@ -0,0 +1,213 @@
+//! The rules for reading a key, value, or sequence element from a line of a `.aa` file, a byte at a time: where comments, blank lines, and lines with only whitespace are, where line endings and delimiters are, and what an escape byte does. The deserializer and the `scan` module both read lines with this, so that they always agree on what a file says.
+//! 
+//! This only decides what each byte means. Getting the bytes, keeping them, and keeping track of line and column numbers are up to the caller.
+
+use memchr::{memchr, memchr2, memchr3};
+
+/// Finds the first occurrence of any of the `needles` in the `haystack`. There must be at most 6 needles.
+/// 
+/// This uses `memchr`, which checks many bytes at a time, rather than comparing each byte in the haystack with each needle. That makes a big difference on large files, most of whose bytes are not interesting.
+pub(crate) fn find_any(needles: &[u8], haystack: &[u8]) -> Option<usize> {
+	match *needles {
+		[] => None,
+		[a] => memchr(a, haystack),
+		[a, b] => memchr2(a, b, haystack),
+		[a, b, c] => memchr3(a, b, c, haystack),
+		[a, b, c, ref rest @ ..] => {
+			// `memchr` only goes up to three needles, so look for the first three, then look for the rest in the part of the haystack before that.
+			match memchr3(a, b, c, haystack) {
+				Some(found) => Some(find_any(rest, &haystack[..found]).unwrap_or(found)),
+				None => find_any(rest, haystack)
+			}
+		}
+	}
+}
+
+#[test]
+fn test_find_any() {
+	assert_eq!(find_any(b"", b"abc"), None);
+	assert_eq!(find_any(b"c", b"abc"), Some(2));
+	assert_eq!(find_any(b"\r\n", b"ab\ncd\r"), Some(2));
+	assert_eq!(find_any(b"\r\n|", b"ab|c\n"), Some(2));
+	assert_eq!(find_any(b"\r\n|,\\", b"a,b\\|"), Some(1));
+	assert_eq!(find_any(b"\r\n|,\\", b"ab\\|"), Some(2));
+	assert_eq!(find_any(b"\r\n|,\\", b"abc"), None);
+}
+
+/// What a byte read by a `LineLexer` means. See `LineLexer::next`.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum Token {
+	/// The byte is part of what's being read. Add it to the buffer.
+	Byte(u8),
+
+	/// The byte is part of a comment, or is the LF of a CR+LF pair. Skip it.
+	Skip,
+
+	/// The byte begins a comment line. Clear the buffer, in case the comment begins after some whitespace, and skip the rest of the line.
+	CommentStart,
+
+	/// The byte ends a line that's blank or has only whitespace on it. Clear the buffer; these lines can separate records.
+	BlankLine,
+
+	/// The byte is the escape byte. Whether it escapes anything depends on the byte after it; see `LineLexer::escapes`.
+	Escape(u8),
+
+	/// The byte is one of the delimiters. The read is done.
+	Delim(u8),
+
+	/// The byte is a line ending, and the line had something on it. The read is done.
+	Eol
+}
+
+/// Reads one key, value, or sequence element, up to a delimiter or the end of the line. See the module documentation.
+pub(crate) struct LineLexer<'a> {
+	delimiters: &'a [u8],
+	escape: Option<u8>,
+
+	/// The bytes that end a run of ordinary bytes in the middle of a line: the line endings, delimiters, and escape byte.
+	stop: [u8; 6],
+	stop_len: usize,
+
+	/// Whether reading started at the beginning of a line. If so, comments, blank lines, and lines with only whitespace are skipped. If reading starts in the middle of a line, then what's being read is a value, and an empty or all-whitespace value is significant.
+	started_at_start_of_line: bool,
+
+	/// Whether the next byte is the first of a line.
+	at_start_of_line: bool,
+
+	/// The last byte read, for telling whether an LF is part of a CR+LF pair.
+	prev_byte: u8,
+
+	in_comment: bool,
+	seen_non_whitespace: bool
+}
+
+impl<'a> LineLexer<'a> {
+	/// Starts reading. `at_start_of_line` is whether the next byte is the first of a line, and `prev_byte` is the byte before it, if any (0 if not).
+	/// 
+	/// There must be at most 3 delimiters, and the delimiters and escape byte must not be line endings.
+	pub(crate) fn new(delimiters: &'a [u8], escape: Option<u8>, at_start_of_line: bool, prev_byte: u8) -> LineLexer<'a> {
+		let mut stop = [b'\r', b'\n', 0, 0, 0, 0];
+		let mut stop_len = 2;
+
+		for byte in delimiters.iter().chain(&escape) {
+			// This will panic if there are too many delimiters, but all callers are in this crate and none of them do that.
+			stop[stop_len] = *byte;
+			stop_len += 1;
+		}
+
+		LineLexer {
+			delimiters,
+			escape,
+			stop,
+			stop_len,
+			started_at_start_of_line: at_start_of_line,
+			at_start_of_line,
+			prev_byte,
+			in_comment: false,
+			seen_non_whitespace: false
+		}
+	}
+
+	/// If the bytes coming up can be handled as a run, rather than one at a time, returns the bytes that end the run, and whether the run is to be kept (added to the buffer) or skipped. The caller then finds the first of those bytes, and hands everything before it to `note_run`. Copying a run all at once is much faster than going byte by byte.
+	/// 
+	/// A run is possible inside a comment, where everything up to the line ending is skipped, and in the middle of a line, past where a comment could begin, where the only special bytes are line endings, delimiters, and the escape byte.
+	pub(crate) fn run(&self) -> Option<(&[u8], bool)> {
+		if self.in_comment {
+			Some((b"\r\n", false))
+		}
+		else if !self.at_start_of_line && (!self.started_at_start_of_line || self.seen_non_whitespace) {
+			Some((&self.stop[..self.stop_len], true))
+		}
+		else {
+			None
+		}
+	}
+
+	/// Takes note of a run of bytes, as described by `run`, having been kept or skipped. The run must not have any of `run`'s stop bytes in it.
+	pub(crate) fn note_run(&mut self, run: &[u8], kept: bool) {
+		if let Some(&last) = run.last() {
+			self.at_start_of_line = false;
+			self.prev_byte = last;
+
+			if kept && !self.seen_non_whitespace {
+				self.seen_non_whitespace = run.iter().any(|byte| !byte.is_ascii_whitespace());
+			}
+		}
+	}
+
+	/// Works out what the next byte means.
+	pub(crate) fn next(&mut self, byte: u8) -> Token {
+		let at_start_of_line = self.at_start_of_line;
+		let prev_byte = self.prev_byte;
+		self.at_start_of_line = byte == b'\r' || byte == b'\n';
+		self.prev_byte = byte;
+
+		if byte == b'#' && (at_start_of_line || (self.started_at_start_of_line && !self.seen_non_whitespace)) {
+			// This is the beginning of a comment line.
+			// Comment lines start with a `#` character, possibly after whitespace. `#` characters after non-whitespace characters do not count as comments. For example, on the line `bgcolor: #FFFFD6`, the key is `bgcolor` and the value is `#FFFFD6`.
+			self.in_comment = true;
+			Token::CommentStart
+		}
+		else if self.in_comment && byte != b'\r' && byte != b'\n' {
+			// We're still inside a comment line.
+			Token::Skip
+		}
+		else if byte == b'\r' || byte == b'\n' {
+			// This is a line ending. Where is it?
+			if self.in_comment {
+				// It's the end of a comment line. We're out of the comment line now, but still haven't seen any significant text yet.
+				self.in_comment = false;
+				Token::Skip
+			}
+			else if at_start_of_line {
+				// It's the end of an empty line or part of a CR+LF sequence. Ignore it and keep going, but take note if it's an empty line, since those can separate records.
+				if prev_byte == b'\r' && byte == b'\n' {
+					Token::Skip
+				}
+				else {
+					Token::BlankLine
+				}
+			}
+			else if self.started_at_start_of_line && !self.seen_non_whitespace {
+				// It's the end of a line containing only whitespace.
+				Token::BlankLine
+			}
+			else {
+				// By process of elimination, this must be the end of a line that isn't a comment, empty, or all whitespace.
+				Token::Eol
+			}
+		}
+		else if Some(byte) == self.escape {
+			self.seen_non_whitespace = true;
+			Token::Escape(byte)
+		}
+		else if self.delimiters.contains(&byte) {
+			Token::Delim(byte)
+		}
+		else {
+			if !byte.is_ascii_whitespace() {
+				self.seen_non_whitespace = true;
+			}
+
+			Token::Byte(byte)
+		}
+	}
+
+	/// After `Token::Escape`, says whether the escape byte escapes `next`, the byte after it (`None` at the end of the input). If so, the caller consumes `next` and adds it to the buffer, and the escape byte is dropped. If not, the escape byte is just an ordinary byte, and is added to the buffer; `next` is read as usual.
+	/// 
+	/// The escape byte escapes itself and the delimiters, and nothing else.
+	pub(crate) fn escapes(&mut self, next: Option<u8>) -> bool {
+		match next {
+			Some(next) if Some(next) == self.escape || self.delimiters.contains(&next) => {
+				self.prev_byte = next;
+				true
+			},
+			_ => false
+		}
+	}
+
+	/// Whether anything but whitespace has been read. At the end of the input, if not, the last line is effectively blank, and the buffer should be cleared of any whitespace left in it.
+	pub(crate) fn seen_non_whitespace(&self) -> bool {
+		self.seen_non_whitespace
+	}
+}
@@ -1,5 +1,74 @@
 //! [Serde](https://serde.rs/) data format implementation for ShopSite `.aa` files.
 //! 
-//! Currently, there is only a deserializer, in the `de` module.
+//! The deserializer is in the `de` module, and the serializer, for writing `.aa` files, is in the `ser` module. For data whose keys aren't known ahead of time, there's also the loosely-typed `Value`, in the `value` module, and for changing files without disturbing their comments and formatting, there's `Document`, in the `document` module, along with `patch_file`, for changing a setting or two in a file on disk. The `diff` module finds what changed between two versions of a file, and the `merge` module combines two sets of changes to the same file. The `schema` module checks that a file has the keys it should, with the right kinds of values. The `helpers` module has functions for Serde's `deserialize_with` attribute, for comma-separated lists, yes/no checkboxes, prices, percentages, and text with HTML entities in it. The `keys` module spells out the names of well-known ShopSite keys, the `path` module deserializes file paths without mangling them, and the `testing` module has the `assert_aa_eq!` macro, for tests that compare `.aa` files.
+//! 
+//! The `examples` directory of this crate's repository has complete programs that read files into structs, stream records, and edit files as `Map`s. They're run by the tests, so they're known to work.
+//! 
+//! The `scan` module is a much simpler parser, for files that are entirely in memory, that works without the standard library.
+//! 
+//! # Optional Features
+//! 
+//! * `std` (on by default): Everything but the `scan` module. Without it, this crate is `no_std`, and needs only `alloc`. All of the other features need it.
+//! * `decimal`: Adds the `money` module, for exact deserialization of prices.
+//! * `chrono`: Adds the `datetime` module, for deserializing ShopSite's timestamps.
+//! * `query`: Adds the `query` module, a small query language for filtering records.
+//! * `compression`: Makes `de::from_reader` and `de::from_file` decompress input compressed with gzip or Zstandard, like `.aa.gz` backups, using the [`flate2`](https://docs.rs/flate2) and [`ruzstd`](https://docs.rs/ruzstd) libraries, and adds `de::decompress`, which does the same for any reader.
+//! * `parallel`: Adds `de::parse_files_parallel`, which parses many files at once, using the [`rayon`](https://docs.rs/rayon) library.
+//! * `mmap`: Adds `de::from_mmap`, which reads a file through a memory map.
+//! * `html-entities`: Adds `de::DeserializerBuilder::html_entities`, which decodes HTML character references, like `&amp;` and `&#8220;`, in values, so that text fields come out readable. `RawValue` still has the value as it's written.
+//! * `indexmap`: Makes `value::Map` keep keys in the order they appear in the file, using the [`indexmap`](https://docs.rs/indexmap) library, instead of sorting them.
+//! * `wasm`: Adds the `wasm` module, which exports functions for converting between `.aa` and JSON to JavaScript, using [`wasm-bindgen`](https://rustwasm.github.io/wasm-bindgen/).
+//! * `trace`: Makes the deserializer report what it's doing to the [`tracing`](https://docs.rs/tracing) library: a span for each record, an event and a span for each key, and events for skipped comments, characters that couldn't be decoded, repeated keys, and errors.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
+mod lexer;
+
+pub mod scan;
+
+#[cfg(feature = "std")]
 pub mod de;
+
+#[cfg(feature = "std")]
+pub mod diff;
+
+#[cfg(feature = "std")]
+pub mod document;
+
+#[cfg(feature = "std")]
+pub mod helpers;
+
+#[cfg(feature = "std")]
+pub mod keys;
+
+#[cfg(feature = "std")]
+pub mod merge;
+
+#[cfg(feature = "std")]
+pub mod path;
+
+#[cfg(feature = "std")]
+pub mod schema;
+
+#[cfg(feature = "std")]
+pub mod ser;
+
+#[cfg(feature = "std")]
+pub mod testing;
+
+#[cfg(feature = "std")]
+pub mod value;
+
+#[cfg(feature = "chrono")]
+pub mod datetime;
+
+#[cfg(feature = "decimal")]
+pub mod money;
+
+#[cfg(feature = "query")]
+pub mod query;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
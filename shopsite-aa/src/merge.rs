@@ -0,0 +1,146 @@
+//! Three-way merging of `.aa` data, for reconciling two sets of changes to the same file, such as settings edited by hand and an update that ShopSite wrote.
+//! 
+//! `merge` takes a common ancestor, `base`, and two versions derived from it, `ours` and `theirs`, and combines the changes each made, key by key, the way a version control system's merge does. A key that only one side changed gets that side's value. A key that both sides changed in different ways is a `Conflict`.
+//! 
+//! ```
+//! use shopsite_aa::de as aa;
+//! 
+//! let base: aa::Value = aa::from_bytes(b"StoreName: Widgets\nTax: 5\nTheme: Classic\n", None).unwrap();
+//! let ours: aa::Value = aa::from_bytes(b"StoreName: Widgets & Co\nTax: 6\nTheme: Classic\n", None).unwrap();
+//! let theirs: aa::Value = aa::from_bytes(b"StoreName: Widgets\nTax: 7\nTheme: Modern\n", None).unwrap();
+//! 
+//! let merged = aa::merge(&base, &ours, &theirs);
+//! let expected: aa::Value = aa::from_bytes(b"StoreName: Widgets & Co\nTax: 6\nTheme: Modern\n", None).unwrap();
+//! assert_eq!(merged.value, expected);
+//! assert_eq!(merged.conflicts, [aa::Conflict {
+//!     key: "Tax".to_string(),
+//!     base: Some(aa::Value::from("5")),
+//!     ours: Some(aa::Value::from("6")),
+//!     theirs: Some(aa::Value::from("7"))
+//! }]);
+//! ```
+
+use crate::value::{Map, Value};
+use std::fmt::{self, Display, Formatter};
+
+/// The result of `merge`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Merged {
+	/// The merged value. Where there's a conflict, this has `ours`' side of it.
+	pub value: Value,
+
+	/// The keys that both sides changed in different ways, in the order of `ours`, then `theirs`.
+	pub conflicts: Vec<Conflict>
+}
+
+impl Merged {
+	/// Whether the merge went cleanly, without any conflicts.
+	pub fn is_clean(&self) -> bool {
+		self.conflicts.is_empty()
+	}
+}
+
+/// A key that both sides of a merge changed, in different ways. Each side is `None` if the key isn't there.
+/// 
+/// Keys in sections are the section name, a `.`, and the key, like `Shipping.Zip`, as in `diff::Change`. If the values being merged aren't maps at all, the key is empty.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Conflict {
+	pub key: String,
+	pub base: Option<Value>,
+	pub ours: Option<Value>,
+	pub theirs: Option<Value>
+}
+
+impl Display for Conflict {
+	/// Writes the conflict on one line, like `Tax: was "5", ours is "6", theirs is "7"`. A key that isn't there is written as `missing`, and a key without a value as `no value`.
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		struct Side<'a>(Option<&'a Value>);
+
+		impl Display for Side<'_> {
+			fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+				match self.0 {
+					None => f.write_str("missing"),
+					Some(Value::Unit) => f.write_str("no value"),
+					Some(value) => write!(f, "{:?}", value.to_string())
+				}
+			}
+		}
+
+		write!(f, "{}: was {}, ours is {}, theirs is {}", self.key, Side(self.base.as_ref()), Side(self.ours.as_ref()), Side(self.theirs.as_ref()))
+	}
+}
+
+/// Merges the changes that `ours` and `theirs` each made to `base`. See the module documentation.
+/// 
+/// For each key:
+/// 
+/// * If `ours` and `theirs` have the same value, or both removed it, that's the result.
+/// * If only one side changed it, added it, or removed it, that side's value is the result.
+/// * If both sides are sections (maps), they're merged key by key.
+/// * Otherwise, it's a `Conflict`, and the result has `ours`' value, or doesn't have the key if `ours` removed it.
+/// 
+/// Sequences are merged as a whole, not element by element.
+pub fn merge(base: &Value, ours: &Value, theirs: &Value) -> Merged {
+	let mut conflicts = Vec::new();
+	let value = merge_values(String::new(), Some(base), Some(ours), Some(theirs), &mut conflicts).unwrap_or_default();
+	Merged { value, conflicts }
+}
+
+/// Merges the values of one key, each of which is `None` if that side doesn't have the key. Returns the merged value, or `None` if the key should be left out.
+fn merge_values(key: String, base: Option<&Value>, ours: Option<&Value>, theirs: Option<&Value>, conflicts: &mut Vec<Conflict>) -> Option<Value> {
+	if ours == theirs || base == theirs {
+		return ours.cloned()
+	}
+	else if base == ours {
+		return theirs.cloned()
+	}
+
+	let empty = Map::new();
+
+	match (base, ours, theirs) {
+		(None | Some(Value::Map(_)), Some(Value::Map(ours)), Some(Value::Map(theirs))) => {
+			let base = match base {
+				Some(Value::Map(base)) => base,
+				_ => &empty
+			};
+
+			Some(Value::Map(merge_maps(&key, base, ours, theirs, conflicts)))
+		},
+		_ => {
+			conflicts.push(Conflict {
+				key,
+				base: base.cloned(),
+				ours: ours.cloned(),
+				theirs: theirs.cloned()
+			});
+
+			ours.cloned()
+		}
+	}
+}
+
+fn merge_maps(prefix: &str, base: &Map, ours: &Map, theirs: &Map, conflicts: &mut Vec<Conflict>) -> Map {
+	let full_key = |key: &str| {
+		if prefix.is_empty() { key.to_string() }
+		else { format!("{}.{}", prefix, key) }
+	};
+
+	let mut merged = Map::new();
+
+	// Keys that both sides removed aren't in either, so they're left out without further ado.
+	for (key, ours_value) in ours {
+		if let Some(value) = merge_values(full_key(key), base.get(key), Some(ours_value), theirs.get(key), conflicts) {
+			merged.insert(key.clone(), value);
+		}
+	}
+
+	for (key, theirs_value) in theirs {
+		if !ours.contains_key(key) {
+			if let Some(value) = merge_values(full_key(key), base.get(key), None, Some(theirs_value), conflicts) {
+				merged.insert(key.clone(), value);
+			}
+		}
+	}
+
+	merged
+}
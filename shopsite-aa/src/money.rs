@@ -0,0 +1,102 @@
+//! Exact decimal money amounts, using the [`rust_decimal`](https://docs.rs/rust_decimal) library.
+//! 
+//! Prices in ShopSite are decimal amounts of currency, which `f64` cannot represent exactly. `rust_decimal::Decimal` can, and the `Deserializer` in this crate will happily deserialize plain numbers like `1234.50` into a `Decimal`. Many price fields are formatted like `$1,234.50`, though, which `Decimal` doesn't understand. For those, use the `Money` type in this module.
+
+use rust_decimal::Decimal;
+use serde::{
+	de::{self, Deserialize, Deserializer, Visitor},
+	ser::{Serialize, Serializer}
+};
+use std::{
+	convert::TryFrom,
+	fmt::{self, Display, Formatter},
+	str::FromStr
+};
+use super::de::NumberSyntax;
+
+pub use rust_decimal;
+
+/// An exact amount of money.
+/// 
+/// This is a thin wrapper around `rust_decimal::Decimal`. The difference is in parsing: `Money` ignores surrounding whitespace, a leading `$` sign, and `,` thousands separators, the same way `NumberSyntax::Lenient` does. That works no matter which deserializer options are in use, and no matter which data format it's deserialized from.
+/// 
+/// `Money` serializes the same way `Decimal` does, without any currency formatting.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Money(pub Decimal);
+
+impl Money {
+	/// Gets the amount, as a `Decimal`.
+	pub fn amount(self) -> Decimal {
+		self.0
+	}
+}
+
+impl From<Decimal> for Money {
+	fn from(amount: Decimal) -> Self {
+		Money(amount)
+	}
+}
+
+impl From<Money> for Decimal {
+	fn from(money: Money) -> Self {
+		money.0
+	}
+}
+
+impl Display for Money {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		Display::fmt(&self.0, f)
+	}
+}
+
+impl FromStr for Money {
+	type Err = rust_decimal::Error;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		let s = NumberSyntax::Lenient.clean(s);
+
+		// Try plain notation first, then scientific notation, like `Decimal`'s own deserializer does.
+		Decimal::from_str(&s)
+		.or_else(|_| Decimal::from_scientific(&s))
+		.map(Money)
+	}
+}
+
+impl Serialize for Money {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		Serialize::serialize(&self.0, serializer)
+	}
+}
+
+impl<'de> Deserialize<'de> for Money {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		struct MoneyVisitor;
+
+		impl<'de> Visitor<'de> for MoneyVisitor {
+			type Value = Money;
+
+			fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+				write!(f, "an amount of money")
+			}
+
+			fn visit_str<E: de::Error>(self, v: &str) -> Result<Money, E> {
+				Money::from_str(v).map_err(|error| E::custom(format_args!("invalid amount of money “{}”: {}", v, error)))
+			}
+
+			fn visit_i64<E: de::Error>(self, v: i64) -> Result<Money, E> {
+				Ok(Money(v.into()))
+			}
+
+			fn visit_u64<E: de::Error>(self, v: u64) -> Result<Money, E> {
+				Ok(Money(v.into()))
+			}
+
+			fn visit_f64<E: de::Error>(self, v: f64) -> Result<Money, E> {
+				Decimal::try_from(v).map(Money).map_err(E::custom)
+			}
+		}
+
+		// This format is text, so ask for a string. Other formats might not, and that's fine too.
+		deserializer.deserialize_str(MoneyVisitor)
+	}
+}
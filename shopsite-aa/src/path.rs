@@ -0,0 +1,49 @@
+//! Deserialization of file paths, keeping their exact bytes.
+//! 
+//! `PathBuf`'s own `Deserialize` implementation wants text, and rejects a path that isn't valid UTF-8, even on Unix, where such paths are perfectly good. The functions in this module deserialize the path as an `OsString` instead, for use with Serde's `deserialize_with` attribute:
+//! 
+//! ```
+//! use serde::Deserialize;
+//! use shopsite_aa::de as aa;
+//! use std::path::PathBuf;
+//! 
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     #[serde(deserialize_with = "shopsite_aa::path::deserialize")]
+//!     data_dir: PathBuf,
+//! 
+//!     #[serde(default, deserialize_with = "shopsite_aa::path::option::deserialize")]
+//!     log_file: Option<PathBuf>
+//! }
+//! 
+//! let config = Config::deserialize(&mut aa::DeserializerBuilder::new()
+//!     .os_string_value(aa::OsStringValue::Bytes)
+//!     .build(&b"data_dir: /var/shopsite/data\n"[..], None)
+//! ).unwrap();
+//! 
+//! assert_eq!(config.data_dir, PathBuf::from("/var/shopsite/data"));
+//! assert_eq!(config.log_file, None);
+//! ```
+//! 
+//! By default, paths are decoded from the input encoding (Windows-1252 by default), the same as text. To keep their bytes exactly as they are in the file, so that a path survives a round trip through the `.aa` file unchanged, use `OsStringValue::Bytes` as above. That only works on Unix; on Windows, paths are always decoded.
+
+use serde::{Deserialize, Deserializer};
+use std::{
+	ffi::OsString,
+	path::PathBuf
+};
+
+/// Deserializes a `PathBuf`, through an `OsString`.
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+	OsString::deserialize(deserializer).map(PathBuf::from)
+}
+
+/// Like the parent module, but for `Option<PathBuf>`. Empty values are `None`.
+pub mod option {
+	use super::*;
+
+	/// Deserializes an `Option<PathBuf>`, through an `Option<OsString>`.
+	pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<PathBuf>, D::Error> {
+		Option::<OsString>::deserialize(deserializer).map(|path| path.map(PathBuf::from))
+	}
+}
@@ -0,0 +1,497 @@
+//! `aaq`, a tiny query language for picking out records, such as products or orders, by the values of their fields.
+//! 
+//! A query is one or more conditions, combined with `and`, `or`, and `not`, and grouped with parentheses:
+//! 
+//! ```text
+//! Price < 10 and (Name contains "widget" or SKU ~ "^W-") and not Taxable = no
+//! ```
+//! 
+//! The conditions are:
+//! 
+//! * `FIELD = VALUE`, `FIELD != VALUE`: The field is (or isn't) equal to the value. If both look like numbers, they are compared as numbers, so `Price = 10` matches a price of `10.00`. Otherwise, they are compared as text, exactly. `==` is the same as `=`.
+//! * `FIELD < VALUE`, `FIELD <= VALUE`, `FIELD > VALUE`, `FIELD >= VALUE`: The field is less than, etc, the value. Again, numbers are compared as numbers and anything else is compared as text.
+//! * `FIELD contains VALUE`: The field contains the value, ignoring letter case.
+//! * `FIELD ~ REGEX`, `FIELD !~ REGEX`: The field does (or doesn't) match the [regular expression](https://docs.rs/regex/*/regex/#syntax). The match can be anywhere in the field; use `^` and `$` to match the whole thing.
+//! * `FIELD`: The field is present and isn't blank.
+//! 
+//! Numbers are recognized the same way as by `NumberSyntax::Lenient`, so currency formatting like `$1,000` is fine.
+//! 
+//! Field names and values can be written bare, as long as they contain nothing but letters, digits, and the characters `_`, `-`, `.`, `$`, `,`, `:`, `/`, `@`, and `*`. Anything else, including spaces, needs to be in double or single quotes. Inside quotes, a backslash before the quote character or another backslash makes it literal, so `"say \"cheese\""` is `say "cheese"`. Other backslashes are left alone, so regular expressions like `"\d+"` can be written as usual. Keywords (`and`, `or`, `not`, `contains`) are not case sensitive; `&&`, `||`, and `!` also work.
+//! 
+//! A field that a record doesn't have is treated as if it were blank. This means that `Color != red` matches records with no `Color` at all.
+//! 
+//! Queries are evaluated against anything implementing the `Record` trait, such as a map from field names to values, or a `Map` of deserialized `.aa` data:
+//! 
+//! ```
+//! use shopsite_aa::query::Query;
+//! use std::collections::HashMap;
+//! 
+//! let query: Query = r#"Price < 10 and Name contains "widget""#.parse().unwrap();
+//! 
+//! let mut product = HashMap::new();
+//! product.insert("Name".to_string(), "Blue Widget".to_string());
+//! product.insert("Price".to_string(), "$4.99".to_string());
+//! 
+//! assert!(query.matches(&product));
+//! ```
+
+use regex::Regex;
+use std::{
+	borrow::Cow,
+	cmp::Ordering,
+	collections::{BTreeMap, HashMap},
+	fmt,
+	hash::BuildHasher,
+	str::FromStr
+};
+use super::{
+	de::NumberSyntax,
+	value::{Map, Value}
+};
+
+/// Something with named fields that a `Query` can be evaluated against.
+pub trait Record {
+	/// Gets the value of the named field, or `None` if there is no such field.
+	fn field(&self, name: &str) -> Option<Cow<'_, str>>;
+}
+
+impl<S: BuildHasher> Record for HashMap<String, String, S> {
+	fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+		self.get(name).map(|value| Cow::Borrowed(&value[..]))
+	}
+}
+
+impl Record for BTreeMap<String, String> {
+	fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+		self.get(name).map(|value| Cow::Borrowed(&value[..]))
+	}
+}
+
+/// Fields that are `Value::Unit` are treated as blank. Fields that are sequences are treated as their elements joined with `|`, which is how they'd look in a `.aa` file.
+impl Record for Map {
+	fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+		self.get(name).map(|value| match value {
+			Value::String(s) => Cow::Borrowed(&s[..]),
+			Value::Unit => Cow::Borrowed(""),
+			other => Cow::Owned(other.to_string())
+		})
+	}
+}
+
+impl<R: Record + ?Sized> Record for &R {
+	fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+		(**self).field(name)
+	}
+}
+
+/// An error in the text of a query.
+#[derive(Debug, derive_more::Display, derive_more::Error)]
+#[display(fmt = "query syntax error at character {}: {}", "offset + 1", message)]
+pub struct ParseError {
+	/// What's wrong.
+	#[error(ignore)]
+	pub message: Cow<'static, str>,
+
+	/// Where in the query text the problem is, as a byte offset from the beginning.
+	pub offset: usize
+}
+
+/// A parsed query, ready to be evaluated.
+#[derive(Clone, Debug)]
+pub struct Query {
+	expr: Expr,
+	text: String
+}
+
+impl Query {
+	/// Parses the text of a query.
+	pub fn parse(text: &str) -> Result<Query, ParseError> {
+		let mut parser = Parser {
+			tokens: tokenize(text)?,
+			pos: 0,
+			end: text.len(),
+			depth: 0
+		};
+
+		let expr = parser.parse_or()?;
+
+		// There shouldn't be anything left over.
+		if let Some((token, offset)) = parser.tokens.get(parser.pos) {
+			return Err(ParseError {
+				message: format!("unexpected {}", token).into(),
+				offset: *offset
+			})
+		}
+
+		Ok(Query {
+			expr,
+			text: text.to_string()
+		})
+	}
+
+	/// Checks whether the given record matches this query.
+	pub fn matches(&self, record: &impl Record) -> bool {
+		self.expr.eval(record)
+	}
+}
+
+impl FromStr for Query {
+	type Err = ParseError;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		Query::parse(s)
+	}
+}
+
+impl fmt::Display for Query {
+	/// Writes the original text of the query.
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(&self.text)
+	}
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum CompareOp {
+	Eq,
+	Ne,
+	Lt,
+	Le,
+	Gt,
+	Ge
+}
+
+impl CompareOp {
+	fn test(self, ordering: Ordering) -> bool {
+		match self {
+			CompareOp::Eq => ordering == Ordering::Equal,
+			CompareOp::Ne => ordering != Ordering::Equal,
+			CompareOp::Lt => ordering == Ordering::Less,
+			CompareOp::Le => ordering != Ordering::Greater,
+			CompareOp::Gt => ordering == Ordering::Greater,
+			CompareOp::Ge => ordering != Ordering::Less
+		}
+	}
+}
+
+#[derive(Clone, Debug)]
+enum Expr {
+	And(Box<Expr>, Box<Expr>),
+	Or(Box<Expr>, Box<Expr>),
+	Not(Box<Expr>),
+	Compare {
+		field: String,
+		op: CompareOp,
+		value: String,
+		/// `value` as a number, if it is one. Parsed ahead of time so it doesn't have to be parsed again for every record.
+		number: Option<f64>
+	},
+	Contains {
+		field: String,
+		/// The text to look for, already lowercased.
+		needle: String
+	},
+	Matches {
+		field: String,
+		regex: Regex,
+		negate: bool
+	},
+	Present(String)
+}
+
+fn parse_number(s: &str) -> Option<f64> {
+	NumberSyntax::Lenient.clean(s).parse().ok().filter(|n: &f64| !n.is_nan())
+}
+
+impl Expr {
+	fn eval(&self, record: &impl Record) -> bool {
+		let get = |field: &str| record.field(field).unwrap_or(Cow::Borrowed(""));
+
+		match self {
+			Expr::And(a, b) => a.eval(record) && b.eval(record),
+			Expr::Or(a, b) => a.eval(record) || b.eval(record),
+			Expr::Not(a) => !a.eval(record),
+			Expr::Compare { field, op, value, number } => {
+				let actual = get(field);
+
+				let ordering = match (parse_number(&actual), number) {
+					// Both are numbers. NaN was filtered out by `parse_number`, so these are always comparable.
+					(Some(actual), Some(number)) => actual.partial_cmp(number).unwrap_or(Ordering::Equal),
+					_ => actual[..].cmp(&value[..])
+				};
+
+				op.test(ordering)
+			},
+			Expr::Contains { field, needle } => get(field).to_lowercase().contains(&needle[..]),
+			Expr::Matches { field, regex, negate } => regex.is_match(&get(field)) != *negate,
+			Expr::Present(field) => !get(field).trim().is_empty()
+		}
+	}
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+	Word(String),
+	Quoted(String),
+	Op(&'static str),
+	LParen,
+	RParen
+}
+
+impl fmt::Display for Token {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		match self {
+			Token::Word(word) => write!(f, "`{}`", word),
+			Token::Quoted(text) => write!(f, "quoted text {:?}", text),
+			Token::Op(op) => write!(f, "`{}`", op),
+			Token::LParen => f.write_str("`(`"),
+			Token::RParen => f.write_str("`)`")
+		}
+	}
+}
+
+/// Operators, longest first so that `<=` isn't mistaken for `<` followed by `=`.
+const OPERATORS: &[&str] = &["&&", "||", "==", "!=", "<=", ">=", "!~", "=", "<", ">", "~", "!"];
+
+fn is_word_char(c: char) -> bool {
+	c.is_alphanumeric() || "_-.$,:/@*".contains(c)
+}
+
+/// Splits a query into tokens, each with its byte offset.
+fn tokenize(text: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+	let mut tokens = Vec::new();
+	let mut chars = text.char_indices().peekable();
+
+	while let Some(&(offset, c)) = chars.peek() {
+		if c.is_whitespace() {
+			chars.next();
+		}
+		else if c == '(' || c == ')' {
+			chars.next();
+			tokens.push((if c == '(' { Token::LParen } else { Token::RParen }, offset));
+		}
+		else if c == '"' || c == '\'' {
+			chars.next();
+			let mut quoted = String::new();
+			let mut terminated = false;
+
+			while let Some((_, ch)) = chars.next() {
+				if ch == c {
+					terminated = true;
+					break
+				}
+				else if ch == '\\' {
+					// A backslash before a quote or another backslash makes it literal. Before anything else, it's just a backslash, so that regular expressions like `"\d+"` don't need their backslashes doubled.
+					match chars.peek() {
+						Some(&(_, escaped)) if escaped == c || escaped == '\\' => {
+							quoted.push(escaped);
+							chars.next();
+						},
+						_ => quoted.push(ch)
+					}
+				}
+				else {
+					quoted.push(ch);
+				}
+			}
+
+			if !terminated {
+				return Err(ParseError {
+					message: "unterminated quoted text".into(),
+					offset
+				})
+			}
+
+			tokens.push((Token::Quoted(quoted), offset));
+		}
+		else if let Some(op) = OPERATORS.iter().find(|op| text[offset..].starts_with(*op)) {
+			for _ in 0..op.len() {
+				chars.next();
+			}
+
+			tokens.push((Token::Op(op), offset));
+		}
+		else if is_word_char(c) {
+			let mut word = String::new();
+
+			while let Some(&(_, c)) = chars.peek() {
+				if !is_word_char(c) {
+					break
+				}
+
+				word.push(c);
+				chars.next();
+			}
+
+			tokens.push((Token::Word(word), offset));
+		}
+		else {
+			return Err(ParseError {
+				message: format!("unexpected character `{}`", c).into(),
+				offset
+			})
+		}
+	}
+
+	Ok(tokens)
+}
+
+/// How deeply parentheses and `not` may nest in a query. The parser and evaluator are recursive, so without a limit, a query like `((((…` could overflow the stack.
+const MAX_DEPTH: usize = 100;
+
+struct Parser {
+	tokens: Vec<(Token, usize)>,
+	pos: usize,
+	/// Offset of the end of the query text, for errors about the query ending too soon.
+	end: usize,
+	/// How many parentheses and `not`s enclose the current position.
+	depth: usize
+}
+
+impl Parser {
+	fn peek(&self) -> Option<&Token> {
+		self.tokens.get(self.pos).map(|(token, _)| token)
+	}
+
+	fn offset(&self) -> usize {
+		self.tokens.get(self.pos).map_or(self.end, |(_, offset)| *offset)
+	}
+
+	fn error<T>(&self, message: impl Into<Cow<'static, str>>) -> Result<T, ParseError> {
+		Err(ParseError {
+			message: message.into(),
+			offset: self.offset()
+		})
+	}
+
+	/// Checks if the next token is the given keyword or operator, and consumes it if so.
+	fn eat(&mut self, keyword: &str, op: &str) -> bool {
+		let found = match self.peek() {
+			Some(Token::Word(word)) => word.eq_ignore_ascii_case(keyword),
+			Some(Token::Op(found)) => *found == op,
+			_ => false
+		};
+
+		if found {
+			self.pos += 1;
+		}
+
+		found
+	}
+
+	/// Runs `parse` one level deeper, failing if that's deeper than `MAX_DEPTH`.
+	fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, ParseError>) -> Result<T, ParseError> {
+		if self.depth >= MAX_DEPTH {
+			return self.error("query is nested too deeply")
+		}
+
+		self.depth += 1;
+		let result = parse(self);
+		self.depth -= 1;
+		result
+	}
+
+	fn parse_or(&mut self) -> Result<Expr, ParseError> {
+		let mut expr = self.parse_and()?;
+
+		while self.eat("or", "||") {
+			expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+		}
+
+		Ok(expr)
+	}
+
+	fn parse_and(&mut self) -> Result<Expr, ParseError> {
+		let mut expr = self.parse_not()?;
+
+		while self.eat("and", "&&") {
+			expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+		}
+
+		Ok(expr)
+	}
+
+	fn parse_not(&mut self) -> Result<Expr, ParseError> {
+		if self.eat("not", "!") {
+			Ok(Expr::Not(Box::new(self.nested(Self::parse_not)?)))
+		}
+		else {
+			self.parse_primary()
+		}
+	}
+
+	/// Parses a field name or value.
+	fn parse_text(&mut self, what: &str) -> Result<String, ParseError> {
+		match self.peek() {
+			Some(Token::Word(text)) | Some(Token::Quoted(text)) => {
+				let text = text.clone();
+				self.pos += 1;
+				Ok(text)
+			},
+			Some(token) => {
+				let message = format!("expected {}, found {}", what, token);
+				self.error(message)
+			},
+			None => self.error(format!("expected {}, but the query ended", what))
+		}
+	}
+
+	fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+		if let Some(Token::LParen) = self.peek() {
+			self.pos += 1;
+			let expr = self.nested(Self::parse_or)?;
+
+			return match self.peek() {
+				Some(Token::RParen) => {
+					self.pos += 1;
+					Ok(expr)
+				},
+				_ => self.error("expected `)`")
+			}
+		}
+
+		let field = self.parse_text("a field name")?;
+
+		let op = match self.peek() {
+			Some(Token::Op(op)) => match *op {
+				"=" | "==" => Some(CompareOp::Eq),
+				"!=" => Some(CompareOp::Ne),
+				"<" => Some(CompareOp::Lt),
+				"<=" => Some(CompareOp::Le),
+				">" => Some(CompareOp::Gt),
+				">=" => Some(CompareOp::Ge),
+				"~" | "!~" => {
+					let negate = *op == "!~";
+					self.pos += 1;
+
+					let offset = self.offset();
+					let pattern = self.parse_text("a regular expression")?;
+
+					return match Regex::new(&pattern) {
+						Ok(regex) => Ok(Expr::Matches { field, regex, negate }),
+						Err(error) => Err(ParseError {
+							message: format!("invalid regular expression: {}", error).into(),
+							offset
+						})
+					}
+				},
+				_ => None
+			},
+			Some(Token::Word(word)) if word.eq_ignore_ascii_case("contains") => {
+				self.pos += 1;
+				let needle = self.parse_text("text to look for")?.to_lowercase();
+				return Ok(Expr::Contains { field, needle })
+			},
+			_ => None
+		};
+
+		match op {
+			Some(op) => {
+				self.pos += 1;
+				let value = self.parse_text("a value to compare with")?;
+				let number = parse_number(&value);
+				Ok(Expr::Compare { field, op, value, number })
+			},
+			None => Ok(Expr::Present(field))
+		}
+	}
+}
@@ -0,0 +1,324 @@
+//! A minimal parser for `.aa` files that are entirely in memory, which doesn't need the standard library, only `alloc`.
+//! 
+//! This is for places where the `de` module can't go, like WebAssembly or embedded tooling, such as a browser-based `.aa` viewer. It splits a file into its keys and values, and values into sequence elements, with the same code the deserializer uses to read lines (see “Parsing Is Not Strict” in the `de` module), but doesn't do anything more: no Serde, no sections, no records. Keys and values are borrowed from the input.
+//! 
+//! ```
+//! use shopsite_aa::scan::{self, Entry};
+//! 
+//! let input = b"# A product\r\nName: Widget\r\nPrice:9.99\r\n\r\nTaxable\r\n";
+//! let entries: Vec<Entry> = scan::entries(input).collect();
+//! 
+//! assert_eq!(entries, [
+//!     Entry { key: b"Name", value: Some(b"Widget"), line: 2 },
+//!     Entry { key: b"Price", value: Some(b"9.99"), line: 3 },
+//!     Entry { key: b"Taxable", value: None, line: 5 }
+//! ]);
+//! 
+//! assert_eq!(scan::decode_windows_1252(b"Caf\xe9 \x93Cr\xe8me\x94"), "Café “Crème”");
+//! ```
+//! 
+//! Without the `std` feature, this is the only module in this crate.
+
+use alloc::{borrow::Cow, string::String, vec::Vec};
+use crate::lexer::{find_any, LineLexer, Token};
+
+/// One key and its value, as found by `entries`.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Entry<'a> {
+	/// The key, undecoded.
+	pub key: &'a [u8],
+
+	/// The value, undecoded, or `None` if the key has no value, not even an empty one (that is, the line has no `:` on it).
+	pub value: Option<&'a [u8]>,
+
+	/// The line that the key is on, counting from 1.
+	pub line: u32
+}
+
+/// Iterator over the entries in a `.aa` file. See `entries`.
+#[derive(Clone, Debug)]
+pub struct Entries<'a> {
+	input: &'a [u8],
+
+	/// How much of the input has been read.
+	pos: usize,
+
+	/// The line number of the byte at `pos`.
+	line: u32,
+
+	/// The byte before `pos`, or 0 at the start of the input.
+	prev_byte: u8
+}
+
+/// How `Entries::read` stopped.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum End {
+	Delim,
+	Eol,
+	Eof
+}
+
+/// Finds the entries in `input`, which is an entire `.aa` file, in the order they appear. Comment lines, blank lines, and lines with only whitespace are skipped.
+/// 
+/// Lines are read by the same code that the deserializer reads them with, so this finds the same keys and values as the deserializer does when deserializing into a map of strings. Any line ending (CR+LF, LF, or CR) ends a line. The delimiter and escape options only matter for sequences, which this doesn't split up; see `elements` for that.
+/// 
+/// A UTF-8 byte order mark, if any, is not skipped. Input that might have one should be checked for it first.
+pub fn entries(input: &[u8]) -> Entries<'_> {
+	Entries {
+		input,
+		pos: 0,
+		line: 1,
+		prev_byte: 0
+	}
+}
+
+impl<'a> Entries<'a> {
+	fn read_byte(&mut self) -> Option<u8> {
+		let byte = *self.input.get(self.pos)?;
+		self.pos += 1;
+
+		// A CR+LF pair is one line ending, not two.
+		if byte == b'\r' || (byte == b'\n' && self.prev_byte != b'\r') {
+			self.line = self.line.saturating_add(1);
+		}
+
+		self.prev_byte = byte;
+		Some(byte)
+	}
+
+	/// Reads a key or value, up to one of the `delimiters` or the end of the line. Returns what was read, the line it's on, and how reading stopped. Since there's no escape byte, what's read is always in one piece, so it can be borrowed from the input.
+	fn read(&mut self, delimiters: &[u8]) -> (&'a [u8], u32, End) {
+		let at_start_of_line = self.pos == 0 || self.prev_byte == b'\r' || self.prev_byte == b'\n';
+		let mut lexer = LineLexer::new(delimiters, None, at_start_of_line, self.prev_byte);
+
+		// Where what's been read so far starts and what line it's on, or `None` if nothing has been read yet, and where it ends.
+		let mut start: Option<(usize, u32)> = None;
+		let mut end = self.pos;
+
+		loop {
+			if let Some((stop, keep)) = lexer.run() {
+				let rest = &self.input[self.pos..];
+				let run = &rest[..find_any(stop, rest).unwrap_or(rest.len())];
+
+				if let Some(&last) = run.last() {
+					if keep {
+						start.get_or_insert((self.pos, self.line));
+						end = self.pos + run.len();
+					}
+
+					self.pos += run.len();
+					self.prev_byte = last;
+					lexer.note_run(run, keep);
+					continue;
+				}
+			}
+
+			let byte_pos = self.pos;
+			let byte_line = self.line;
+
+			let token = match self.read_byte() {
+				Some(byte) => lexer.next(byte),
+				None => {
+					if !lexer.seen_non_whitespace() {
+						start = None;
+					}
+
+					return self.read_result(start, end, End::Eof);
+				}
+			};
+
+			match token {
+				// There's no escape byte, so `Token::Escape` can't happen.
+				Token::Byte(_) | Token::Escape(_) => {
+					if start.is_none() {
+						start = Some((byte_pos, byte_line));
+					}
+
+					// Nothing is skipped in the middle of a key or value, so what's read is contiguous.
+					debug_assert_eq!(end, byte_pos);
+					end = self.pos;
+				},
+				Token::Skip => (),
+				Token::CommentStart | Token::BlankLine => start = None,
+				Token::Delim(_) => return self.read_result(start, end, End::Delim),
+				Token::Eol => return self.read_result(start, end, End::Eol)
+			}
+
+			if start.is_none() {
+				end = self.pos;
+			}
+		}
+	}
+
+	fn read_result(&self, start: Option<(usize, u32)>, end: usize, how: End) -> (&'a [u8], u32, End) {
+		match start {
+			Some((start, line)) => (&self.input[start..end], line, how),
+			None => (&self.input[end..end], self.line, how)
+		}
+	}
+}
+
+impl<'a> Iterator for Entries<'a> {
+	type Item = Entry<'a>;
+
+	fn next(&mut self) -> Option<Entry<'a>> {
+		// The key ends at the first `:`, if any. The value begins after it, and after the space that usually follows it, and goes to the end of the line.
+		let (key, line, end) = self.read(b":");
+
+		let value = match end {
+			End::Eof if key.is_empty() => return None,
+			End::Delim => {
+				if self.input.get(self.pos) == Some(&b' ') {
+					self.read_byte();
+				}
+
+				Some(self.read(&[]).0)
+			},
+			_ => None
+		};
+
+		Some(Entry { key, value, line })
+	}
+}
+
+/// Splits a value found by `entries` into the elements of a sequence, the way the deserializer does: at each `delimiter` (usually `|`), except where it's escaped with the `escape` byte (usually `\\`, or `None` if escaping is off). An empty value has no elements.
+/// 
+/// The escape byte escapes itself and the delimiter, and nothing else. An element is borrowed from the input unless it had escapes in it.
+pub fn elements(value: &[u8], delimiter: u8, escape: Option<u8>) -> Elements<'_> {
+	Elements {
+		rest: if value.is_empty() { None } else { Some(value) },
+		delimiter: [delimiter],
+		escape
+	}
+}
+
+/// Iterator over the elements of a sequence. See `elements`.
+#[derive(Clone, Debug)]
+pub struct Elements<'a> {
+	/// What hasn't been split up yet, or `None` once everything has been.
+	rest: Option<&'a [u8]>,
+
+	delimiter: [u8; 1],
+	escape: Option<u8>
+}
+
+impl<'a> Iterator for Elements<'a> {
+	type Item = Cow<'a, [u8]>;
+
+	fn next(&mut self) -> Option<Cow<'a, [u8]>> {
+		let rest = self.rest?;
+		let mut lexer = LineLexer::new(&self.delimiter, self.escape, false, 0);
+
+		// Everything up to the first escape is borrowed. After that, the element is copied.
+		let mut borrowed_len = 0;
+		let mut owned: Option<Vec<u8>> = None;
+		let mut pos = 0;
+
+		let end = loop {
+			let byte = match rest.get(pos) {
+				Some(&byte) => byte,
+				None => break None
+			};
+
+			pos += 1;
+
+			match lexer.next(byte) {
+				Token::Escape(escape) => {
+					let bytes = owned.get_or_insert_with(|| rest[..borrowed_len].to_vec());
+
+					if lexer.escapes(rest.get(pos).copied()) {
+						bytes.push(rest[pos]);
+						pos += 1;
+					}
+					else {
+						bytes.push(escape);
+					}
+				},
+				Token::Delim(_) => break Some(pos),
+				// A value found by `entries` has no line endings in it, but if there is one, the sequence ends there, as it would in the deserializer.
+				Token::Eol => break None,
+				Token::Byte(byte) => match &mut owned {
+					Some(bytes) => bytes.push(byte),
+					None => borrowed_len += 1
+				},
+				Token::Skip | Token::CommentStart | Token::BlankLine => ()
+			}
+		};
+
+		self.rest = end.map(|end| &rest[end..]);
+
+		Some(match owned {
+			Some(bytes) => Cow::Owned(bytes),
+			None => Cow::Borrowed(&rest[..borrowed_len])
+		})
+	}
+}
+
+/// The characters that the bytes `0x80` through `0x9F` stand for in Windows-1252. The rest of the bytes stand for the Unicode code points with the same numbers. The five bytes that Windows-1252 doesn't define are decoded as the C1 control characters with the same numbers, as web browsers do.
+const WINDOWS_1252_HIGH: [char; 32] = [
+	'€', '\u{81}', '‚', 'ƒ', '„', '…', '†', '‡', 'ˆ', '‰', 'Š', '‹', 'Œ', '\u{8d}', 'Ž', '\u{8f}',
+	'\u{90}', '‘', '’', '“', '”', '•', '–', '—', '˜', '™', 'š', '›', 'œ', '\u{9d}', 'ž', 'Ÿ'
+];
+
+/// Decodes text in Windows-1252, the encoding that ShopSite uses for everything, such as a key or value found by `entries`. Every byte stands for some character, so this can't fail.
+pub fn decode_windows_1252(bytes: &[u8]) -> String {
+	bytes.iter().map(|&byte| match byte {
+		0x80..=0x9f => WINDOWS_1252_HIGH[byte as usize - 0x80],
+		_ => char::from(byte)
+	}).collect()
+}
+
+#[test]
+fn test_entries() {
+	fn scan(input: &[u8]) -> alloc::vec::Vec<Entry<'_>> {
+		entries(input).collect()
+	}
+
+	fn entry<'a>(key: &'a [u8], value: Option<&'a [u8]>, line: u32) -> Entry<'a> {
+		Entry { key, value, line }
+	}
+
+	assert_eq!(scan(b""), []);
+	assert_eq!(scan(b"\n\r\n  \t\n# comment\n  # indented: comment\n"), []);
+	assert_eq!(scan(b"a: 1\rb:2\r\nc:  3\nd\ne:\nf: #not a comment"), [
+		entry(b"a", Some(b"1"), 1),
+		entry(b"b", Some(b"2"), 2),
+		entry(b"c", Some(b" 3"), 3),
+		entry(b"d", None, 4),
+		entry(b"e", Some(b""), 5),
+		entry(b"f", Some(b"#not a comment"), 6)
+	]);
+
+	// Only the first `:` ends the key.
+	assert_eq!(scan(b"url: http://example.com/"), [entry(b"url", Some(b"http://example.com/"), 1)]);
+
+	// Whitespace before a comment or key is skipped along with the comment, but it's part of the key.
+	assert_eq!(scan(b"  # comment\r\n  a: 1\r\n\r\n\r\nb"), [entry(b"  a", Some(b"1"), 2), entry(b"b", None, 5)]);
+}
+
+#[test]
+fn test_elements() {
+	fn split(value: &[u8], escape: Option<u8>) -> Vec<Cow<'_, [u8]>> {
+		elements(value, b'|', escape).collect()
+	}
+
+	assert_eq!(split(b"", None), Vec::<Cow<[u8]>>::new());
+	assert_eq!(split(b"a", None), [&b"a"[..]]);
+	assert_eq!(split(b"a||b|", None), [&b"a"[..], b"", b"b", b""]);
+	assert_eq!(split(b"a\\|b", None), [&b"a\\"[..], b"b"]);
+
+	let escaped = split(b"a\\|b|c\\\\d|e\\f", Some(b'\\'));
+	assert_eq!(escaped, [&b"a|b"[..], b"c\\d", b"e\\f"]);
+	assert!(matches!(escaped[0], Cow::Owned(_)));
+	assert!(matches!(split(b"a|b", Some(b'\\'))[0], Cow::Borrowed(_)));
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_decode_windows_1252() {
+	// This checks the decoding table against the `encoding` library, which the deserializer uses.
+	use encoding::{all::WINDOWS_1252, DecoderTrap, Encoding};
+
+	let bytes: alloc::vec::Vec<u8> = (0..=255).collect();
+	assert_eq!(decode_windows_1252(&bytes), WINDOWS_1252.decode(&bytes, DecoderTrap::Replace).unwrap());
+}
@@ -0,0 +1,549 @@
+//! Declaring what keys a `.aa` file should have, and checking that it has them, so that mistakes in a store's settings can be caught before ShopSite sees them, such as in a CI job.
+//! 
+//! A `Schema` lists keys, the `Type` of each one's value, and whether it's required. `validate` checks a `Value` against one, and `validate_document` checks a `Document`, which also gives the line each problem is on:
+//! 
+//! ```
+//! use shopsite_aa::{document::Document, schema::{self, Schema, Type}};
+//! 
+//! let mut schema = Schema::new();
+//! schema
+//!     .required("StoreName", Type::Text)
+//!     .optional("Tax", Type::Number)
+//!     .optional("Theme", Type::one_of(["Classic", "Modern"]))
+//!     .optional("PaymentMethods", Type::list(Type::Text));
+//! 
+//! let doc = Document::parse(b"Tax: 5%\nTheme: Classic\nPaymentMethods: Visa|PayPal\n");
+//! let violations: Vec<String> = schema::validate_document(&doc, &schema, None).iter().map(ToString::to_string).collect();
+//! assert_eq!(violations, [
+//!     "1:6: Tax: expected a number, found \"5%\"",
+//!     "StoreName: required key is missing"
+//! ]);
+//! ```
+//! 
+//! A `Schema` can also be deserialized, so it can be kept in a file of its own, in TOML, JSON, or any other format that Serde supports. Keys are listed in a table named `keys`. Each key has a `type`, which is one of `any`, `text`, `int`, `number`, `bool`, `enum`, or `list`, and can be `required`. An `enum` has a list of `values`, and a `list` has the type of its elements in `of`, which is `any` if left out. A key whose type is all there is to say about it can be written as just the type's name. If `deny_unknown_keys` is true, keys that aren't listed are violations, too.
+//! 
+//! ```toml
+//! deny_unknown_keys = true
+//! 
+//! [keys]
+//! Tax = "number"
+//! StoreName = { type = "text", required = true }
+//! Theme = { type = "enum", values = ["Classic", "Modern"] }
+//! PaymentMethods = { type = "list", of = "text" }
+//! ```
+//! 
+//! `Schema` is `Serialize`, too, in the same form, so one made in Rust, or by `Schema::infer`, can be saved.
+
+use crate::{
+	de::{BoolSyntax, NumberSyntax, Position},
+	document::Document,
+	value::{Map, Value}
+};
+use serde::{
+	de::{Deserialize, Deserializer, Error as _},
+	ser::{Serialize, SerializeMap, Serializer}
+};
+use std::{
+	collections::{BTreeMap, HashMap},
+	fmt::{self, Display, Formatter},
+	path::Path,
+	sync::Arc
+};
+
+/// What kind of value a key has.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Type {
+	/// Anything at all.
+	#[default]
+	Any,
+
+	/// Text, which is anything but a map. A key with no value counts as empty text.
+	Text,
+
+	/// A whole number, like `42` or `-1`.
+	Int,
+
+	/// A number, like `12.50`, which may be formatted as a price, like `$1,234.50`, as with `NumberSyntax::Lenient`.
+	Number,
+
+	/// Yes or no, spelled any way that `BoolSyntax::Lenient` accepts, or empty, or no value at all, which mean no, as with `helpers::yes_no`.
+	Bool,
+
+	/// One of these exact values. Letter case matters. Make one with `Type::one_of`.
+	Enum(Vec<String>),
+
+	/// A sequence, each element of which is of this type. Elements are separated by `|`, or by `,` in a list inside a list. An empty value is an empty list. Make one with `Type::list`.
+	List(Box<Type>)
+}
+
+impl Type {
+	/// Makes a `Type::Enum` with the given values.
+	pub fn one_of<V: Into<String>>(values: impl IntoIterator<Item = V>) -> Type {
+		Type::Enum(values.into_iter().map(Into::into).collect())
+	}
+
+	/// Makes a `Type::List` with the given element type.
+	pub fn list(of: Type) -> Type {
+		Type::List(Box::new(of))
+	}
+
+	/// The name of this type, as in a schema file.
+	pub fn name(&self) -> &'static str {
+		match self {
+			Type::Any => "any",
+			Type::Text => "text",
+			Type::Int => "int",
+			Type::Number => "number",
+			Type::Bool => "bool",
+			Type::Enum(_) => "enum",
+			Type::List(_) => "list"
+		}
+	}
+
+	/// Checks whether `value` is of this type. `depth` is how many lists the value is inside of, which decides what separates the elements of a list.
+	fn matches(&self, value: &Value, depth: usize) -> bool {
+		let text = match value {
+			_ if *self == Type::Any => return true,
+			Value::Map(_) => return false,
+			Value::Seq(elements) => return match self {
+				Type::List(of) => elements.iter().all(|element| of.matches(element, depth + 1)),
+				Type::Text => true,
+				_ => false
+			},
+			Value::Unit => return matches!(self, Type::Text | Type::Bool),
+			Value::String(text) => text
+		};
+
+		match self {
+			Type::Any | Type::Text => true,
+			Type::Int => text.trim().parse::<i64>().is_ok(),
+			Type::Number => NumberSyntax::Lenient.clean(text).parse::<f64>().is_ok(),
+			Type::Bool => text.trim().is_empty() || BoolSyntax::Lenient.parse(text.trim()).is_ok(),
+			Type::Enum(values) => values.iter().any(|allowed| allowed == text),
+			Type::List(of) => {
+				let delimiter = if depth == 0 { '|' } else { ',' };
+				text.is_empty() || text.split(delimiter).all(|element| of.matches(&Value::from(element), depth + 1))
+			}
+		}
+	}
+
+	/// Parses a type from a schema file, where it's either the name of the type, or a map with the name in `type`, along with `values` or `of`.
+	fn from_value(value: &Value) -> Result<Type, String> {
+		let (name, fields) = match value {
+			Value::String(name) => (name.as_str(), None),
+			Value::Map(fields) => match fields.get("type") {
+				Some(Value::String(name)) => (name.as_str(), Some(fields)),
+				_ => return Err("a type needs a `type` field, with the name of the type".to_string())
+			},
+			_ => return Err("a type must be the name of a type, or a map with a `type` field".to_string())
+		};
+
+		let field = |name: &str| fields.and_then(|fields| fields.get(name));
+
+		Ok(match name {
+			"any" => Type::Any,
+			"text" => Type::Text,
+			"int" => Type::Int,
+			"number" => Type::Number,
+			"bool" => Type::Bool,
+			"enum" => match field("values") {
+				Some(Value::Seq(values)) => Type::Enum(values.iter().map(|value| value.as_str().map(str::to_string).ok_or("the `values` of an enum must be text")).collect::<Result<_, _>>()?),
+				_ => return Err("an enum needs a list of `values`".to_string())
+			},
+			"list" => Type::list(field("of").map(Type::from_value).transpose()?.unwrap_or_default()),
+			other => return Err(format!("unknown type {:?}", other))
+		})
+	}
+}
+
+impl Display for Type {
+	/// Describes the type in words, like `a whole number` or `one of "Classic", "Modern"`.
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		match self {
+			Type::Any => f.write_str("anything"),
+			Type::Text => f.write_str("text"),
+			Type::Int => f.write_str("a whole number"),
+			Type::Number => f.write_str("a number"),
+			Type::Bool => f.write_str("yes or no"),
+			Type::Enum(values) => {
+				f.write_str("one of ")?;
+
+				for (index, value) in values.iter().enumerate() {
+					if index != 0 {
+						f.write_str(", ")?;
+					}
+
+					write!(f, "{:?}", value)?;
+				}
+
+				Ok(())
+			},
+			Type::List(of) => write!(f, "a list of {}", of)
+		}
+	}
+}
+
+impl Serialize for Type {
+	/// Serializes the type as a map, with its name in `type`, and its `values` or `of`, if it has them.
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut map = serializer.serialize_map(None)?;
+		serialize_type_fields(self, None, &mut map)?;
+		map.end()
+	}
+}
+
+/// Serializes the fields of a `Type` into a map, for `Type` and `KeySchema`, along with `required`, if given. `of` comes last, since TOML wants tables after everything else.
+fn serialize_type_fields<M: SerializeMap>(ty: &Type, required: Option<bool>, map: &mut M) -> Result<(), M::Error> {
+	map.serialize_entry("type", ty.name())?;
+
+	if let Some(required) = required {
+		map.serialize_entry("required", &required)?;
+	}
+
+	match ty {
+		Type::Enum(values) => map.serialize_entry("values", values),
+		Type::List(of) => map.serialize_entry("of", of),
+		_ => Ok(())
+	}
+}
+
+/// What a `Schema` says about one key.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct KeySchema {
+	/// The type of the key's value.
+	pub ty: Type,
+
+	/// Whether the key has to be there.
+	pub required: bool
+}
+
+impl Serialize for KeySchema {
+	/// Serializes the key's type, as for `Type`, with `required` after `type`.
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut map = serializer.serialize_map(None)?;
+		serialize_type_fields(&self.ty, Some(self.required), &mut map)?;
+		map.end()
+	}
+}
+
+/// What keys a `.aa` file should have. See the module documentation.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Schema {
+	keys: BTreeMap<String, KeySchema>,
+	deny_unknown_keys: bool
+}
+
+impl Schema {
+	/// Creates an empty schema, which any map matches.
+	pub fn new() -> Schema {
+		Default::default()
+	}
+
+	/// Adds a key that has to be there, with a value of the given type.
+	pub fn required(&mut self, key: impl Into<String>, ty: Type) -> &mut Self {
+		self.keys.insert(key.into(), KeySchema { ty, required: true });
+		self
+	}
+
+	/// Adds a key that doesn't have to be there, but if it is, has a value of the given type.
+	pub fn optional(&mut self, key: impl Into<String>, ty: Type) -> &mut Self {
+		self.keys.insert(key.into(), KeySchema { ty, required: false });
+		self
+	}
+
+	/// Sets whether keys that aren't in the schema are violations. The default is `false`, since every ShopSite release seems to add a few keys.
+	pub fn deny_unknown_keys(&mut self, deny_unknown_keys: bool) -> &mut Self {
+		self.deny_unknown_keys = deny_unknown_keys;
+		self
+	}
+
+	/// The keys in the schema, in alphabetical order.
+	pub fn keys(&self) -> impl Iterator<Item = (&str, &KeySchema)> {
+		self.keys.iter().map(|(key, schema)| (key.as_str(), schema))
+	}
+
+	/// What the schema says about a key, or `None` if it doesn't have the key.
+	pub fn get(&self, key: &str) -> Option<&KeySchema> {
+		self.keys.get(key)
+	}
+
+	/// Makes a schema that fits some existing files, as a starting point for one written by hand. Each value is usually a `Value::Map` read from a file; values that aren't maps are skipped.
+	/// 
+	/// The schema has every key that's in any of the files, and the keys that are in every file are required. Each key's type is the first of these that every value of it matches:
+	/// 
+	/// * `Type::Int`, `Type::Number`, or `Type::Bool`.
+	/// * `Type::Enum`, if the key has no more than `MAX_ENUM_VALUES` different values, and at least one of them appears more than once, so it looks like a choice from a list rather than free text.
+	/// * `Type::List`, if some value has a `|` in it, with its elements' type found the same way.
+	/// * `Type::Text`, or `Type::Any` for sections.
+	/// 
+	/// A key whose values are all empty is `Type::Text`. Every file that the schema was inferred from is valid according to it.
+	/// 
+	/// ```
+	/// use shopsite_aa::{de as aa, schema::{Schema, Type}};
+	/// 
+	/// let files: Vec<aa::Value> = [
+	///     &b"Name: Mug\nPrice: 12.50\nTaxable: yes\nColor: Blue\nSizes: S|M\n"[..],
+	///     b"Name: Towel\nPrice: 8\nTaxable: no\nColor: Red\n",
+	///     b"Name: Hat\nPrice: 20\nTaxable: yes\nColor: Blue\nSizes: M|L\n"
+	/// ].iter().map(|file| aa::from_bytes(file, None).unwrap()).collect();
+	/// 
+	/// let schema = Schema::infer(&files);
+	/// assert_eq!(schema.get("Price").unwrap().ty, Type::Number);
+	/// assert_eq!(schema.get("Taxable").unwrap().ty, Type::Bool);
+	/// assert_eq!(schema.get("Color").unwrap().ty, Type::one_of(["Blue", "Red"]));
+	/// assert_eq!(schema.get("Sizes").unwrap().ty, Type::list(Type::one_of(["L", "M", "S"])));
+	/// assert!(schema.get("Name").unwrap().required);
+	/// assert!(!schema.get("Sizes").unwrap().required);
+	/// ```
+	pub fn infer<'a>(values: impl IntoIterator<Item = &'a Value>) -> Schema {
+		let mut files = 0;
+		let mut observed: BTreeMap<&str, Vec<&Value>> = BTreeMap::new();
+
+		for value in values {
+			if let Value::Map(map) = value {
+				files += 1;
+
+				for (key, value) in map {
+					observed.entry(key).or_default().push(value);
+				}
+			}
+		}
+
+		let mut schema = Schema::new();
+
+		for (key, values) in observed {
+			let ty = infer_type(&values, true);
+			schema.keys.insert(key.to_string(), KeySchema { ty, required: values.len() == files });
+		}
+
+		schema
+	}
+
+	/// Parses a schema from a `Value`, in the form described in the module documentation.
+	fn from_value(value: &Value) -> Result<Schema, String> {
+		let Value::Map(fields) = value else {
+			return Err("a schema must be a map".to_string())
+		};
+
+		let mut schema = Schema::new();
+
+		match fields.get("deny_unknown_keys") {
+			None => {},
+			Some(value) => schema.deny_unknown_keys = parse_bool("deny_unknown_keys", value)?
+		}
+
+		match fields.get("keys") {
+			None => {},
+			Some(Value::Map(keys)) => for (key, value) in keys {
+				let required = match value {
+					Value::Map(fields) => fields.get("required").map(|value| parse_bool("required", value)).transpose()?.unwrap_or(false),
+					_ => false
+				};
+
+				let ty = Type::from_value(value).map_err(|error| format!("{}: {}", key, error))?;
+				schema.keys.insert(key.clone(), KeySchema { ty, required });
+			},
+			Some(_) => return Err("`keys` must be a map".to_string())
+		}
+
+		Ok(schema)
+	}
+}
+
+/// The most different values that `Schema::infer` will make a `Type::Enum` out of.
+pub const MAX_ENUM_VALUES: usize = 10;
+
+/// Finds the type of a key, or of the elements of a list, for `Schema::infer`. `values` is every value it was seen with. Lists are only considered if `allow_list`.
+fn infer_type(values: &[&Value], allow_list: bool) -> Type {
+	if values.iter().any(|value| matches!(value, Value::Map(_))) {
+		return Type::Any
+	}
+
+	if values.iter().all(|value| value.as_str().is_none_or(str::is_empty) && !matches!(value, Value::Seq(elements) if !elements.is_empty())) {
+		return Type::Text
+	}
+
+	for ty in [Type::Int, Type::Number, Type::Bool] {
+		if values.iter().all(|value| ty.matches(value, 0)) {
+			return ty
+		}
+	}
+
+	// Sequences can only be lists or text, so only plain text can be an enum.
+	let mut texts: Vec<&str> = Vec::new();
+	let mut repeated = false;
+
+	for value in values {
+		match value.as_str() {
+			Some(text) if texts.contains(&text) => repeated = true,
+			Some(text) => texts.push(text),
+			None => {
+				texts.clear();
+				break
+			}
+		}
+	}
+
+	if repeated && !texts.is_empty() && texts.len() <= MAX_ENUM_VALUES {
+		texts.sort_unstable();
+		return Type::one_of(texts)
+	}
+
+	let is_list = |value: &&Value| matches!(value, Value::Seq(_)) || value.as_str().is_some_and(|text| text.contains('|'));
+
+	// A key with no value isn't a list, not even an empty one.
+	if allow_list && values.iter().any(is_list) && !values.iter().any(|value| value.is_unit()) {
+		let elements: Vec<Value> = values.iter().flat_map(|value| match value {
+			Value::Seq(elements) => elements.clone(),
+			value => value.split().map(Value::from).collect()
+		}).collect();
+
+		return Type::list(infer_type(&elements.iter().collect::<Vec<_>>(), false))
+	}
+
+	Type::Text
+}
+
+/// Parses a `bool` from a schema file. Other formats' `bool`s become text in a `Value`.
+fn parse_bool(name: &str, value: &Value) -> Result<bool, String> {
+	match value.as_str() {
+		Some("true") => Ok(true),
+		Some("false") => Ok(false),
+		_ => Err(format!("`{}` must be true or false", name))
+	}
+}
+
+impl Serialize for Schema {
+	/// Serializes the schema in the form described in the module documentation.
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut map = serializer.serialize_map(Some(2))?;
+		map.serialize_entry("deny_unknown_keys", &self.deny_unknown_keys)?;
+		map.serialize_entry("keys", &self.keys)?;
+		map.end()
+	}
+}
+
+impl<'de> Deserialize<'de> for Schema {
+	/// Deserializes the schema from the form described in the module documentation.
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		let value = Value::deserialize(deserializer)?;
+		Schema::from_value(&value).map_err(D::Error::custom)
+	}
+}
+
+/// Something about a `.aa` file that doesn't match a `Schema`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Violation {
+	/// The key that's wrong.
+	pub key: String,
+
+	/// What's wrong with it.
+	pub kind: ViolationKind,
+
+	/// Where the key is in the file, if known. `validate` doesn't know; `validate_document` does, except for keys that are missing. For a value of the wrong type, this is where the value starts.
+	pub position: Option<Position>
+}
+
+/// What's wrong, in a `Violation`.
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum ViolationKind {
+	/// The key is required, but isn't there.
+	Missing,
+
+	/// The key isn't in the schema, which denies unknown keys.
+	Unknown,
+
+	/// The key's value isn't of the type the schema says.
+	WrongType {
+		expected: Type,
+		found: Value
+	}
+}
+
+impl Display for Violation {
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		// Not `Position`'s `Display`, which has the key in it, too.
+		if let Some(position) = &self.position {
+			if let Some(file) = &position.file {
+				write!(f, "{}:", file.display())?;
+			}
+
+			write!(f, "{}:{}: ", position.line, position.column)?;
+		}
+
+		match &self.kind {
+			ViolationKind::Missing => write!(f, "{}: required key is missing", self.key),
+			ViolationKind::Unknown => write!(f, "{}: unknown key", self.key),
+			ViolationKind::WrongType { expected, found } => write!(f, "{}: expected {}, found {:?}", self.key, expected, found.to_string())
+		}
+	}
+}
+
+/// Checks a value, usually a `Value::Map` read from a `.aa` file, against a schema. Violations are in the order of the keys in `value`, then missing keys, in alphabetical order. A value that isn't a map is treated as an empty one.
+pub fn validate(value: &Value, schema: &Schema) -> Vec<Violation> {
+	let empty = Map::new();
+	let map = match value {
+		Value::Map(map) => map,
+		_ => &empty
+	};
+
+	let mut violations = Vec::new();
+
+	for (key, value) in map {
+		let kind = match schema.keys.get(key) {
+			None if schema.deny_unknown_keys => ViolationKind::Unknown,
+			Some(key_schema) if !key_schema.ty.matches(value, 0) => ViolationKind::WrongType {
+				expected: key_schema.ty.clone(),
+				found: value.clone()
+			},
+			_ => continue
+		};
+
+		violations.push(Violation { key: key.clone(), kind, position: None });
+	}
+
+	for (key, key_schema) in &schema.keys {
+		if key_schema.required && !map.contains_key(key) {
+			violations.push(Violation { key: key.clone(), kind: ViolationKind::Missing, position: None });
+		}
+	}
+
+	violations
+}
+
+/// Checks a `Document` against a schema, as by `validate`, with the position of each violation in the file, except for missing keys. `file` is the path to the file, if any, for the positions. If a key appears more than once, the last one is the one that's checked, as with `Document::get`, and violations are in the order of those lines.
+pub fn validate_document(document: &Document, schema: &Schema, file: Option<Arc<Path>>) -> Vec<Violation> {
+	// Where the last of each key is: its line number, and the byte column where its value starts, if it has one.
+	let mut positions = HashMap::new();
+
+	for (index, line) in document.lines().iter().enumerate() {
+		if let Some((key, value)) = line.entry() {
+			let value_column = value.map(|value| line.content().len() - value.len() + 1);
+			positions.insert(document.encoding().decode(key), (index + 1, value_column));
+		}
+	}
+
+	let mut violations = validate(&Value::Map(document.to_map()), schema);
+
+	for violation in &mut violations {
+		if let Some(&(line, value_column)) = positions.get(&violation.key) {
+			let column = match violation.kind {
+				ViolationKind::WrongType { .. } => value_column.unwrap_or(1),
+				_ => 1
+			};
+
+			violation.position = Some(Position {
+				file: file.clone(),
+				line: line as u32,
+				column: column as u32,
+				byte_column: column as u32,
+				key: Some(violation.key.clone())
+			});
+		}
+	}
+
+	violations.sort_by_key(|violation| violation.position.as_ref().map_or(u32::MAX, |position| position.line));
+	violations
+}
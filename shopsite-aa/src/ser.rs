@@ -0,0 +1,393 @@
+//! Serializer implementation for ShopSite `.aa` files.
+//! 
+//! The top level of a `.aa` file is a map, so only maps and structs (and things that serialize as one, like `Option`s and newtypes of them) can be written as one. Each field or entry is written on a line of its own, as `key: value`, in the order they're serialized. Values are written like so:
+//! 
+//! * Text and numbers are written as they are. `bool`s are written as `true` or `false`, which is what the deserializer reads by default.
+//! * `None` isn't written at all. The key is left out, and deserializing it again, into an `Option`, gives `None` back.
+//! * `()`, and unit structs, are written as a key with no value.
+//! * Enum variants without any data are written as the name of the variant.
+//! * Bytes, as from `serde_bytes`, are written exactly as they are, without being encoded, the way `UntypedValue::Bytes` reads them.
+//! * Sequences and tuples are written as their elements, separated by `|`, the way the deserializer reads them. A sequence inside a sequence has its elements separated by `,` instead; an empty one is written as an empty element, and is read back as a sequence of one empty element. `None` and `()` elements are written as empty elements. See `SerializerBuilder::seq_escape` for elements that have a `|` in them, and `SerializerBuilder::empty_seq` for empty sequences.
+//! 
+//! Anything else, like a map inside a map, has no way to be written in a `.aa` file, and is an `Error::InvalidValue`. So is text with a line break in it, since it would be read back as more than one line.
+//! 
+//! ```
+//! use serde::Serialize;
+//! use shopsite_aa::ser;
+//! 
+//! #[derive(Serialize)]
+//! struct Product {
+//!     name: String,
+//!     price: f64,
+//!     sale_price: Option<f64>,
+//!     taxable: bool
+//! }
+//! 
+//! let product = Product {
+//!     name: "Café Mug".to_string(),
+//!     price: 12.5,
+//!     sale_price: None,
+//!     taxable: true
+//! };
+//! 
+//! assert_eq!(ser::to_bytes(&product).unwrap(), b"name: Caf\xe9 Mug\r\nprice: 12.5\r\ntaxable: true\r\n");
+//! ```
+//! 
+//! Whatever is written reads back the same, with the same options, except that a sequence of one empty element reads back as an empty sequence, and an empty sequence inside a sequence reads back as a sequence of one empty element. A `Value::Seq` reads back as the text it was written as, since `.aa` files don't say what's a sequence. Comments and blank lines aren't part of the data, so a file that has them isn't written back byte for byte, but a file that ShopSite wrote is.
+//! 
+//! By default, output is written the way ShopSite writes it: in Windows-1252, with `\r\n` line endings, and with the keys in the order they're serialized. To change that, use `SerializerBuilder`.
+
+use crate::de::InputEncoding;
+use serde::ser::{self, Impossible, Serialize};
+use std::{
+	io::Write,
+	mem
+};
+
+mod builder;
+pub use builder::*;
+
+mod error;
+pub use error::*;
+
+mod ser_key;
+use ser_key::*;
+
+mod ser_value;
+use ser_value::*;
+
+/// Writes `.aa` files. See the module documentation.
+pub struct Serializer<W> {
+	/// Where the output goes.
+	writer: W,
+
+	config: Config,
+
+	/// How many lines have been written so far. Each line's line ending is written just before the next line, so that whether the last line gets one can be decided at the end.
+	lines_written: usize,
+
+	/// The key most recently given to `SerializeMap::serialize_key`, waiting for its value.
+	pending_key: Option<String>,
+
+	/// Lines that haven't been written yet, each with its key, because they need sorting first. See `SerializerBuilder::key_order`.
+	unsorted_lines: Vec<(String, Vec<u8>)>
+}
+
+impl<W: Write> Serializer<W> {
+	/// Creates a new `Serializer` with the default options. To change the options, use `SerializerBuilder` instead.
+	pub fn new(writer: W) -> Serializer<W> {
+		Serializer::with_config(writer, Config::default())
+	}
+
+	fn with_config(writer: W, config: Config) -> Serializer<W> {
+		Serializer {
+			writer,
+			config,
+			lines_written: 0,
+			pending_key: None,
+			unsorted_lines: Vec::new()
+		}
+	}
+
+	/// Gets the writer back.
+	pub fn into_inner(self) -> W {
+		self.writer
+	}
+
+	/// Writes a key and its value, if it has one, on a line of its own.
+	fn write_entry(&mut self, key: &str, entry: Entry) -> Result<()> {
+		check_key(key)?;
+
+		let value = match entry {
+			Entry::Skip => return Ok(()),
+			Entry::KeyOnly => None,
+			Entry::Value(value) => Some(value)
+		};
+
+		let mut line = Vec::new();
+
+		if let Some(comment) = self.config.key_comments.as_ref().and_then(|comments| comments.get(key)) {
+			for comment_line in comment_lines(&self.config, key, comment)? {
+				line.extend_from_slice(&comment_line);
+				line.extend_from_slice(self.config.line_ending.as_str().as_bytes());
+			}
+		}
+
+		line.extend_from_slice(&encode(&self.config, key, key)?);
+
+		if let Some(value) = value {
+			line.extend_from_slice(b": ");
+			line.extend_from_slice(&value);
+		}
+
+		if let KeyOrder::AsSerialized = self.config.key_order {
+			self.write_line(&line)
+		}
+		else {
+			self.unsorted_lines.push((key.to_string(), line));
+			Ok(())
+		}
+	}
+
+	/// Writes a line, after the line ending of the line before it, if any. If it's the first line, the header comes first.
+	fn write_line(&mut self, line: &[u8]) -> Result<()> {
+		if self.lines_written == 0 {
+			self.write_header(true)?;
+		}
+
+		self.write_raw_line(line)
+	}
+
+	/// Writes the header, if there is one, followed by a blank line if `more_lines` are coming, so that it isn't taken for the comment of the first key. See `SerializerBuilder::header`.
+	fn write_header(&mut self, more_lines: bool) -> Result<()> {
+		if let Some(header) = &self.config.header {
+			for line in comment_lines(&self.config, "header", header)? {
+				self.write_raw_line(&line)?;
+			}
+
+			if more_lines {
+				self.write_raw_line(b"")?;
+			}
+		}
+
+		Ok(())
+	}
+
+	/// Writes a line, after the line ending of the line before it, if any.
+	fn write_raw_line(&mut self, line: &[u8]) -> Result<()> {
+		if self.lines_written != 0 {
+			self.writer.write_all(self.config.line_ending.as_str().as_bytes())?;
+		}
+
+		self.writer.write_all(line)?;
+		self.lines_written += 1;
+		Ok(())
+	}
+
+	/// Finishes the output, writing the last line's line ending if there's supposed to be one.
+	fn finish(&mut self) -> Result<()> {
+		if !self.unsorted_lines.is_empty() {
+			let mut lines = mem::take(&mut self.unsorted_lines);
+			self.config.key_order.sort(&mut lines);
+
+			for (_, line) in lines {
+				self.write_line(&line)?;
+			}
+		}
+
+		if self.lines_written == 0 {
+			// There were no keys, but there may still be a header.
+			self.write_header(false)?;
+		}
+
+		if self.lines_written != 0 && self.config.trailing_line_ending {
+			self.writer.write_all(self.config.line_ending.as_str().as_bytes())?;
+		}
+
+		self.writer.flush()?;
+		Ok(())
+	}
+}
+
+/// Checks that `key` can be written and read back as the same key.
+fn check_key(key: &str) -> Result<()> {
+	// A blank key would be a blank line, and one that starts with `#` would be a comment.
+	if key.trim().is_empty() || key.trim_start().starts_with('#') || key.contains([':', '\r', '\n']) {
+		return Err(Error::InvalidKey { key: key.to_string() })
+	}
+
+	Ok(())
+}
+
+/// Encodes a key, and serializes a value for it, with the default options, but in the given encoding, for `Document::set`. The value is `None` if the key is to be written without one, as for `()`. A value that would be left out, like `None`, is written without one, too.
+pub(crate) fn encode_entry<T: Serialize + ?Sized>(key: &str, value: &T, encoding: InputEncoding) -> Result<(Vec<u8>, Option<Vec<u8>>)> {
+	check_key(key)?;
+
+	let config = Config { encoding, ..Config::default() };
+
+	let value = match value.serialize(ValueSerializer::new(&config, key))? {
+		Entry::Skip | Entry::KeyOnly => None,
+		Entry::Value(value) => Some(value)
+	};
+
+	Ok((encode(&config, key, key)?, value))
+}
+
+/// Encodes `text` in the output encoding, dealing with characters that the encoding doesn't have according to `config.unencodable`. `key` is the key that the text is, or is the value of, for error messages.
+fn encode(config: &Config, key: &str, text: &str) -> Result<Vec<u8>> {
+	use encoding::{all::WINDOWS_1252, EncoderTrap, Encoding};
+
+	match config.encoding {
+		InputEncoding::Utf8 => Ok(text.as_bytes().to_vec()),
+		InputEncoding::Windows1252 => {
+			let trap = match config.unencodable {
+				Unencodable::Error => EncoderTrap::Strict,
+				Unencodable::Entity => EncoderTrap::NcrEscape,
+				Unencodable::Replace => EncoderTrap::Replace
+			};
+
+			WINDOWS_1252.encode(text, trap).map_err(|_| Error::Unencodable {
+				key: key.to_string(),
+				character: text.chars().find(|c| WINDOWS_1252.encode(c.encode_utf8(&mut [0; 4]), EncoderTrap::Strict).is_err()).unwrap_or(char::REPLACEMENT_CHARACTER),
+				encoding: config.encoding
+			})
+		}
+	}
+}
+
+/// Turns some comment text into comment lines, encoded, each starting with `# `, without line endings. `key` is the key that the comment is for, for error messages.
+fn comment_lines(config: &Config, key: &str, comment: &str) -> Result<Vec<Vec<u8>>> {
+	comment.lines().map(|line| {
+		let line = line.trim_end();
+
+		if line.is_empty() {
+			Ok(b"#".to_vec())
+		}
+		else {
+			let mut encoded = b"# ".to_vec();
+			encoded.extend_from_slice(&encode(config, key, line)?);
+			Ok(encoded)
+		}
+	}).collect()
+}
+
+/// Serializes a value as a `.aa` file, writing it to `writer`, with the default options.
+pub fn to_writer<W: Write, T: Serialize + ?Sized>(writer: W, value: &T) -> Result<()> {
+	value.serialize(&mut Serializer::new(writer))
+}
+
+/// Serializes a value as a `.aa` file, with the default options, and returns the bytes of the file.
+pub fn to_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>> {
+	let mut bytes = Vec::new();
+	to_writer(&mut bytes, value)?;
+	Ok(bytes)
+}
+
+macro_rules! not_a_map {
+	($($serialize_name:ident($($t:ty)?)),*) => {
+		$(
+			fn $serialize_name(self, $(_: $t)?) -> Result<()> {
+				Err(Error::NotAMap)
+			}
+		)*
+	}
+}
+
+impl<W: Write> ser::Serializer for &mut Serializer<W> {
+	type Ok = ();
+	type Error = Error;
+	type SerializeSeq = Impossible<(), Error>;
+	type SerializeTuple = Impossible<(), Error>;
+	type SerializeTupleStruct = Impossible<(), Error>;
+	type SerializeTupleVariant = Impossible<(), Error>;
+	type SerializeMap = Self;
+	type SerializeStruct = Self;
+	type SerializeStructVariant = Impossible<(), Error>;
+
+	not_a_map! {
+		serialize_bool(bool),
+		serialize_i8(i8),
+		serialize_i16(i16),
+		serialize_i32(i32),
+		serialize_i64(i64),
+		serialize_i128(i128),
+		serialize_u8(u8),
+		serialize_u16(u16),
+		serialize_u32(u32),
+		serialize_u64(u64),
+		serialize_u128(u128),
+		serialize_f32(f32),
+		serialize_f64(f64),
+		serialize_char(char),
+		serialize_str(&str),
+		serialize_bytes(&[u8]),
+		serialize_unit_struct(&'static str)
+	}
+
+	fn serialize_none(self) -> Result<()> {
+		// Nothing at all, which is an empty file.
+		Ok(())
+	}
+
+	fn serialize_unit(self) -> Result<()> {
+		Ok(())
+	}
+
+	fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<()> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit_variant(self, _: &'static str, _: u32, _: &'static str) -> Result<()> {
+		Err(Error::NotAMap)
+	}
+
+	fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<()> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _: &'static str, _: u32, _: &'static str, _: &T) -> Result<()> {
+		Err(Error::NotAMap)
+	}
+
+	fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq> {
+		Err(Error::NotAMap)
+	}
+
+	fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple> {
+		Err(Error::NotAMap)
+	}
+
+	fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeTupleStruct> {
+		Err(Error::NotAMap)
+	}
+
+	fn serialize_tuple_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<Self::SerializeTupleVariant> {
+		Err(Error::NotAMap)
+	}
+
+	fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap> {
+		Ok(self)
+	}
+
+	fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct> {
+		Ok(self)
+	}
+
+	fn serialize_struct_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<Self::SerializeStructVariant> {
+		Err(Error::NotAMap)
+	}
+}
+
+impl<W: Write> ser::SerializeMap for &mut Serializer<W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+		self.pending_key = Some(key.serialize(KeySerializer)?);
+		Ok(())
+	}
+
+	fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+		let key = self.pending_key.take().ok_or_else(|| Error::Other("value serialized without a key".into()))?;
+		let entry = value.serialize(ValueSerializer::new(&self.config, &key))?;
+		self.write_entry(&key, entry)
+	}
+
+	fn end(self) -> Result<()> {
+		self.finish()
+	}
+}
+
+impl<W: Write> ser::SerializeStruct for &mut Serializer<W> {
+	type Ok = ();
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+		let entry = value.serialize(ValueSerializer::new(&self.config, key))?;
+		self.write_entry(key, entry)
+	}
+
+	fn end(self) -> Result<()> {
+		self.finish()
+	}
+}
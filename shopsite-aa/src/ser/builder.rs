@@ -0,0 +1,283 @@
+use std::{
+	cmp::Ordering,
+	collections::HashMap,
+	fmt,
+	io::Write,
+	sync::Arc
+};
+use crate::{
+	de::{glob_matches, InputEncoding, LineEnding, Preset, SeqEscape},
+	document::Document
+};
+use super::Serializer;
+
+/// What to do with a character that the output encoding doesn't have, like `→` in Windows-1252.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum Unencodable {
+	/// Fail with `Error::Unencodable`. This is the default.
+	#[default]
+	Error,
+
+	/// Write an HTML character reference instead, like `&#8594;`. ShopSite puts most text into web pages as it is, so the character still shows up right there.
+	Entity,
+
+	/// Write a `?` instead, as ShopSite itself does.
+	Replace
+}
+
+/// What to write for a key whose value is an empty sequence.
+/// 
+/// Either way, the deserializer reads it back as an empty sequence, if the key's field has a `#[serde(default)]`.
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+#[non_exhaustive]
+pub enum EmptySeq {
+	/// Write the key with an empty value, like `Categories: `. This is the default.
+	#[default]
+	EmptyValue,
+
+	/// Leave the key out, as for `None`.
+	Omit
+}
+
+/// What order keys are written in.
+/// 
+/// ShopSite's back office is sensitive to the order of some settings, so when writing a file that ShopSite will read, write the keys in the order ShopSite writes them. For a struct, that's just a matter of declaring its fields in that order, since they're serialized in the order they're declared. For a map, it depends on the map: a `BTreeMap` is in alphabetical order, a `HashMap` is in no particular order, and an `IndexMap` is in the order the keys were inserted. Use this to override it.
+/// 
+/// Any order other than `AsSerialized` means holding the whole file in memory until the last key has been serialized, since that key might come first.
+#[derive(Clone, Default)]
+#[non_exhaustive]
+pub enum KeyOrder {
+	/// The order the keys are serialized in. This is the default.
+	#[default]
+	AsSerialized,
+
+	/// Alphabetical order, by Unicode code point, so uppercase before lowercase.
+	Sorted,
+
+	/// The keys in this list first, in the order they're listed, then any other keys, in the order they're serialized. Make one with `KeyOrder::explicit`.
+	Explicit(Arc<[String]>),
+
+	/// The order that this function says. Keys that it says are equal stay in the order they're serialized. Make one with `KeyOrder::by`.
+	Custom(Arc<CompareKeys>)
+}
+
+/// A function that compares two keys, for `KeyOrder::Custom`.
+pub type CompareKeys = dyn Fn(&str, &str) -> Ordering + Send + Sync;
+
+impl KeyOrder {
+	/// Makes a `KeyOrder::Explicit` with the given keys.
+	pub fn explicit<K: Into<String>>(keys: impl IntoIterator<Item = K>) -> KeyOrder {
+		KeyOrder::Explicit(keys.into_iter().map(Into::into).collect())
+	}
+
+	/// Makes a `KeyOrder::Custom` with the given comparison function.
+	pub fn by(compare: impl Fn(&str, &str) -> Ordering + Send + Sync + 'static) -> KeyOrder {
+		KeyOrder::Custom(Arc::new(compare))
+	}
+
+	/// Sorts lines of output, each with its key, into this order. The sort is stable.
+	pub(super) fn sort(&self, lines: &mut [(String, Vec<u8>)]) {
+		match self {
+			KeyOrder::AsSerialized => {},
+			KeyOrder::Sorted => lines.sort_by(|(a, _), (b, _)| a.cmp(b)),
+			KeyOrder::Explicit(keys) => lines.sort_by_key(|(key, _)| keys.iter().position(|listed| listed == key).unwrap_or(usize::MAX)),
+			KeyOrder::Custom(compare) => lines.sort_by(|(a, _), (b, _)| compare(a, b))
+		}
+	}
+}
+
+impl fmt::Debug for KeyOrder {
+	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+		match self {
+			KeyOrder::AsSerialized => f.write_str("AsSerialized"),
+			KeyOrder::Sorted => f.write_str("Sorted"),
+			KeyOrder::Explicit(keys) => f.debug_tuple("Explicit").field(keys).finish(),
+			KeyOrder::Custom(_) => f.write_str("Custom(..)")
+		}
+	}
+}
+
+/// Options that control how a `Serializer` writes its output.
+#[derive(Clone, Debug)]
+pub(super) struct Config {
+	pub(super) encoding: InputEncoding,
+	pub(super) unencodable: Unencodable,
+	pub(super) line_ending: LineEnding,
+	pub(super) trailing_line_ending: bool,
+	pub(super) key_order: KeyOrder,
+	pub(super) seq_escape: SeqEscape,
+	pub(super) sub_seq_delimiter: u8,
+	pub(super) seq_delimiter_overrides: Vec<(String, u8)>,
+	pub(super) empty_seq: EmptySeq,
+	pub(super) header: Option<String>,
+	pub(super) key_comments: Option<Arc<HashMap<String, String>>>
+}
+
+impl Default for Config {
+	fn default() -> Self {
+		Config {
+			encoding: Default::default(),
+			unencodable: Default::default(),
+			line_ending: LineEnding::CrLf,
+			trailing_line_ending: true,
+			key_order: Default::default(),
+			seq_escape: Default::default(),
+			sub_seq_delimiter: b',',
+			seq_delimiter_overrides: Vec::new(),
+			empty_seq: Default::default(),
+			header: None,
+			key_comments: None
+		}
+	}
+}
+
+impl Config {
+	/// Finds the sequence delimiter to use for the values of the given key.
+	pub(super) fn seq_delimiter_for(&self, key: &str) -> u8 {
+		self.seq_delimiter_overrides.iter()
+		.find(|(pattern, _)| glob_matches(pattern.as_bytes(), key.as_bytes()))
+		.map_or(b'|', |(_, delimiter)| *delimiter)
+	}
+}
+
+/// Builds a `Serializer` with non-default options.
+/// 
+/// The default options write files the way ShopSite does: in Windows-1252, with `\r\n` line endings, including after the last line.
+#[derive(Clone, Debug, Default)]
+pub struct SerializerBuilder {
+	config: Config
+}
+
+impl SerializerBuilder {
+	/// Creates a new builder with the default options.
+	pub fn new() -> SerializerBuilder {
+		Default::default()
+	}
+
+	/// Sets the options for writing a common kind of `.aa` file, so that it can be read back with the same `Preset`. Options set afterward override the preset's, so call this first. See `Preset::apply_to_serializer`.
+	pub fn preset(&mut self, preset: Preset) -> &mut Self {
+		preset.apply_to_serializer(self);
+		self
+	}
+
+	/// Sets the character encoding of the output. The default is `InputEncoding::Windows1252`, which is what ShopSite uses.
+	pub fn encoding(&mut self, encoding: InputEncoding) -> &mut Self {
+		self.config.encoding = encoding;
+		self
+	}
+
+	/// Sets what to do with characters that the output encoding doesn't have. The default is `Unencodable::Error`. UTF-8 has every character, so this only matters for Windows-1252.
+	pub fn unencodable(&mut self, unencodable: Unencodable) -> &mut Self {
+		self.config.unencodable = unencodable;
+		self
+	}
+
+	/// Sets the line ending to write after each line. The default is `LineEnding::CrLf`, which is what ShopSite writes. `LineEnding::Mixed` means `LineEnding::CrLf` here, so that the line ending that `Deserializer::line_ending` found in a file can be passed along as it is.
+	pub fn line_ending(&mut self, line_ending: LineEnding) -> &mut Self {
+		self.config.line_ending = line_ending;
+		self
+	}
+
+	/// Sets whether the last line ends with a line ending, as every line of a file written by ShopSite does. The default is `true`.
+	pub fn trailing_line_ending(&mut self, trailing_line_ending: bool) -> &mut Self {
+		self.config.trailing_line_ending = trailing_line_ending;
+		self
+	}
+
+	/// Sets what order keys are written in. The default is `KeyOrder::AsSerialized`.
+	/// 
+	/// ```
+	/// use shopsite_aa::ser;
+	/// use std::collections::HashMap;
+	/// 
+	/// let mut settings = HashMap::new();
+	/// settings.insert("Currency", "USD");
+	/// settings.insert("StoreName", "Widgets");
+	/// settings.insert("Locale", "en-US");
+	/// 
+	/// let mut serializer = ser::SerializerBuilder::new()
+	///     .key_order(ser::KeyOrder::explicit(["StoreName", "Locale"]))
+	///     .line_ending(shopsite_aa::de::LineEnding::Lf)
+	///     .build(Vec::new());
+	/// 
+	/// serde::Serialize::serialize(&settings, &mut serializer).unwrap();
+	/// assert_eq!(serializer.into_inner(), b"StoreName: Widgets\nLocale: en-US\nCurrency: USD\n");
+	/// ```
+	pub fn key_order(&mut self, key_order: KeyOrder) -> &mut Self {
+		self.config.key_order = key_order;
+		self
+	}
+
+	/// Sets how a `|` in a sequence element is written, so that it isn't taken for the end of the element. The default is `SeqEscape::None`, which means there's no way to write one, so an element with a `|` in it is an `Error::InvalidValue`.
+	/// 
+	/// A sequence written with one of these must be read with the same `DeserializerBuilder::seq_escape`. With `SeqEscape::Backslash`, backslashes in elements are doubled, so they come back as they were. With `SeqEscape::NumericEntity`, only `|` can be escaped, so an element of a nested sequence with the `sub_seq_delimiter` in it is still an error.
+	pub fn seq_escape(&mut self, seq_escape: SeqEscape) -> &mut Self {
+		self.config.seq_escape = seq_escape;
+		self
+	}
+
+	/// Sets the delimiter that separates the elements of a sequence inside a sequence. The default is `,`, as for `DeserializerBuilder::sub_seq_delimiter`.
+	/// 
+	/// Sequences can only be nested one deep. A sequence in a sequence in a sequence is an `Error::InvalidValue`.
+	pub fn sub_seq_delimiter(&mut self, delimiter: u8) -> &mut Self {
+		self.config.sub_seq_delimiter = delimiter;
+		self
+	}
+
+	/// Makes sequences in the values of keys matching `key_glob` use `delimiter` to separate their elements, instead of `|`. This works the same way as `DeserializerBuilder::seq_delimiter_override`. `SeqEscape::NumericEntity` can only escape `|`, so with it, an element with a `delimiter` in it is an error.
+	pub fn seq_delimiter_override(&mut self, key_glob: impl Into<String>, delimiter: u8) -> &mut Self {
+		self.config.seq_delimiter_overrides.push((key_glob.into(), delimiter));
+		self
+	}
+
+	/// Sets what to write for a key whose value is an empty sequence. The default is `EmptySeq::EmptyValue`.
+	/// 
+	/// A sequence whose only element is empty, like `vec![""]`, is written the same way as an empty sequence when this is `EmptySeq::EmptyValue`, and is read back as an empty sequence.
+	pub fn empty_seq(&mut self, empty_seq: EmptySeq) -> &mut Self {
+		self.config.empty_seq = empty_seq;
+		self
+	}
+
+	/// Sets a comment to write at the top of the file, like the banner that says which program wrote it. Each line of `header` is written as a comment line of its own, starting with `# `, and then there's a blank line, which sets it apart from the first key's comment, if any.
+	pub fn header(&mut self, header: impl Into<String>) -> &mut Self {
+		self.config.header = Some(header.into());
+		self
+	}
+
+	/// Sets comments to write just above some keys, to explain what they're for. Each line of each comment is written as a comment line of its own, starting with `# `. Keys that aren't in `comments` don't get a comment.
+	/// 
+	/// ```
+	/// use shopsite_aa::ser;
+	/// use std::collections::BTreeMap;
+	/// 
+	/// let mut settings = BTreeMap::new();
+	/// settings.insert("Currency", "USD");
+	/// settings.insert("StoreName", "Widgets");
+	/// 
+	/// let mut serializer = ser::SerializerBuilder::new()
+	///     .header("Written by store-setup")
+	///     .key_comments([("StoreName", "Shown at the top of every page.")])
+	///     .line_ending(shopsite_aa::de::LineEnding::Lf)
+	///     .build(Vec::new());
+	/// 
+	/// serde::Serialize::serialize(&settings, &mut serializer).unwrap();
+	/// assert_eq!(serializer.into_inner(), b"# Written by store-setup\n\nCurrency: USD\n# Shown at the top of every page.\nStoreName: Widgets\n");
+	/// ```
+	pub fn key_comments<K: Into<String>, V: Into<String>>(&mut self, comments: impl IntoIterator<Item = (K, V)>) -> &mut Self {
+		self.config.key_comments = Some(Arc::new(comments.into_iter().map(|(key, comment)| (key.into(), comment.into())).collect()));
+		self
+	}
+
+	/// Sets the header and key comments to the ones in `document`, so that a file written with this builder has the same comments as the one the document was parsed from. See `Document::header` and `Document::key_comments`.
+	pub fn comments_from(&mut self, document: &Document) -> &mut Self {
+		self.config.header = document.header();
+		self.config.key_comments = Some(Arc::new(document.key_comments()));
+		self
+	}
+
+	/// Creates a `Serializer` with the options set on this builder, writing to `writer`. Writes are small, one line at a time, so if `writer` isn't buffered, wrap it in a `BufWriter`.
+	pub fn build<W: Write>(&self, writer: W) -> Serializer<W> {
+		Serializer::with_config(writer, self.config.clone())
+	}
+}
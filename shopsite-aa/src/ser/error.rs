@@ -0,0 +1,54 @@
+use std::{
+	borrow::Cow,
+	io
+};
+use crate::de::InputEncoding;
+
+/// An error that occurred during serialization or writing.
+#[derive(Debug, derive_more::Display, derive_more::Error)]
+#[non_exhaustive]
+pub enum Error {
+	Other(#[error(ignore)] Cow<'static, str>),
+
+	#[display(fmt = "I/O error: {}", _0)]
+	Io(io::Error),
+
+	/// The value being serialized isn't a map or a struct. A `.aa` file is a list of keys and values, so nothing else can be written as one.
+	#[display(fmt = "only maps and structs can be written as .aa files")]
+	NotAMap,
+
+	/// A key is blank, starts with `#`, or has a `:` or a line break in it, so it would be read back as a blank line, a comment, or a different key.
+	#[display(fmt = "key {:?} can't be written in a .aa file", key)]
+	InvalidKey {
+		key: String
+	},
+
+	/// A value has a line break in it, or is of a kind that a `.aa` file has no way of writing, like a map, so it would be read back as something else.
+	#[display(fmt = "the value of {} can't be written in a .aa file: {}", key, reason)]
+	InvalidValue {
+		key: String,
+		reason: &'static str
+	},
+
+	/// A key or value has a character in it that the output encoding doesn't have, and `SerializerBuilder::unencodable` says that's an error. `key` is the key, or the key of the value, that the character is in.
+	#[display(fmt = "{}: “{}” can't be written in {}", key, character, encoding)]
+	Unencodable {
+		key: String,
+		character: char,
+		encoding: InputEncoding
+	}
+}
+
+impl serde::ser::Error for Error {
+	fn custom<T: std::fmt::Display>(msg: T) -> Self {
+		Error::Other(msg.to_string().into())
+	}
+}
+
+impl From<io::Error> for Error {
+	fn from(error: io::Error) -> Self {
+		Error::Io(error)
+	}
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
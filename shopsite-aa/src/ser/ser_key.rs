@@ -0,0 +1,110 @@
+use serde::ser::{self, Impossible, Serialize};
+use super::{Error, Result};
+
+/// Serializes a map key as text. Keys can be text, numbers, `bool`s, or enum variants without data; anything else is an error.
+pub(super) struct KeySerializer;
+
+macro_rules! key_to_string {
+	($($serialize_name:ident($t:ty)),*) => {
+		$(
+			fn $serialize_name(self, v: $t) -> Result<String> {
+				Ok(v.to_string())
+			}
+		)*
+	}
+}
+
+/// The error for a key that isn't text.
+fn not_text() -> Error {
+	Error::Other("keys must be text or numbers".into())
+}
+
+impl ser::Serializer for KeySerializer {
+	type Ok = String;
+	type Error = Error;
+	type SerializeSeq = Impossible<String, Error>;
+	type SerializeTuple = Impossible<String, Error>;
+	type SerializeTupleStruct = Impossible<String, Error>;
+	type SerializeTupleVariant = Impossible<String, Error>;
+	type SerializeMap = Impossible<String, Error>;
+	type SerializeStruct = Impossible<String, Error>;
+	type SerializeStructVariant = Impossible<String, Error>;
+
+	key_to_string! {
+		serialize_bool(bool),
+		serialize_i8(i8),
+		serialize_i16(i16),
+		serialize_i32(i32),
+		serialize_i64(i64),
+		serialize_i128(i128),
+		serialize_u8(u8),
+		serialize_u16(u16),
+		serialize_u32(u32),
+		serialize_u64(u64),
+		serialize_u128(u128),
+		serialize_f32(f32),
+		serialize_f64(f64),
+		serialize_char(char),
+		serialize_str(&str)
+	}
+
+	fn serialize_bytes(self, _: &[u8]) -> Result<String> {
+		Err(not_text())
+	}
+
+	fn serialize_none(self) -> Result<String> {
+		Err(not_text())
+	}
+
+	fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<String> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<String> {
+		Err(not_text())
+	}
+
+	fn serialize_unit_struct(self, _: &'static str) -> Result<String> {
+		Err(not_text())
+	}
+
+	fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<String> {
+		Ok(variant.to_string())
+	}
+
+	fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<String> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _: &'static str, _: u32, _: &'static str, _: &T) -> Result<String> {
+		Err(not_text())
+	}
+
+	fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq> {
+		Err(not_text())
+	}
+
+	fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple> {
+		Err(not_text())
+	}
+
+	fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeTupleStruct> {
+		Err(not_text())
+	}
+
+	fn serialize_tuple_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<Self::SerializeTupleVariant> {
+		Err(not_text())
+	}
+
+	fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap> {
+		Err(not_text())
+	}
+
+	fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct> {
+		Err(not_text())
+	}
+
+	fn serialize_struct_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<Self::SerializeStructVariant> {
+		Err(not_text())
+	}
+}
@@ -0,0 +1,269 @@
+use serde::ser::{self, Impossible, Serialize};
+use crate::de::SeqEscape;
+use super::{encode, Config, EmptySeq, Error, Result};
+
+/// What to write for a key, as determined by serializing its value.
+pub(super) enum Entry {
+	/// Nothing at all. The key is left out, as for `None`.
+	Skip,
+
+	/// The key alone, without a value, as for `()`.
+	KeyOnly,
+
+	/// The key and this value, already encoded.
+	Value(Vec<u8>)
+}
+
+/// Serializes the value of a key, or an element of a sequence in one.
+#[derive(Clone, Copy)]
+pub(super) struct ValueSerializer<'a> {
+	config: &'a Config,
+
+	/// The key whose value this is, for error messages.
+	key: &'a str,
+
+	/// The delimiter between the elements of a sequence in this key's value. See `SerializerBuilder::seq_delimiter_override`.
+	seq_delimiter: u8,
+
+	/// How many sequences the value is inside of: 0 for the value of a key, 1 for an element of a sequence, and 2 for an element of a sequence in a sequence.
+	depth: u8
+}
+
+impl<'a> ValueSerializer<'a> {
+	pub(super) fn new(config: &'a Config, key: &'a str) -> ValueSerializer<'a> {
+		ValueSerializer {
+			config,
+			key,
+			seq_delimiter: config.seq_delimiter_for(key),
+			depth: 0
+		}
+	}
+
+	/// The error for a value that can't be written, for the given reason.
+	fn invalid(&self, reason: &'static str) -> Error {
+		Error::InvalidValue { key: self.key.to_string(), reason }
+	}
+
+	/// Writes `text` as the value, encoded.
+	fn text(self, text: &str) -> Result<Entry> {
+		if text.contains(['\r', '\n']) {
+			return Err(self.invalid("it has a line break in it"))
+		}
+
+		self.escape(encode(self.config, self.key, text)?).map(Entry::Value)
+	}
+
+	/// The delimiter that separates the elements of a sequence at the given depth, which is 1 for the elements of a sequence, and 2 for the elements of a sequence in a sequence.
+	fn delimiter_at(&self, depth: u8) -> u8 {
+		if depth <= 1 { self.seq_delimiter } else { self.config.sub_seq_delimiter }
+	}
+
+	/// Escapes the delimiters in an element of a sequence, if this is one, according to `SerializerBuilder::seq_escape`, so that they aren't taken for the end of the element.
+	fn escape(&self, bytes: Vec<u8>) -> Result<Vec<u8>> {
+		let delimiters: &[u8] = match self.depth {
+			0 => return Ok(bytes),
+			1 => &[self.seq_delimiter],
+			_ => &[self.seq_delimiter, self.config.sub_seq_delimiter]
+		};
+
+		let needs_escape = |byte: &u8| delimiters.contains(byte) || (*byte == b'\\' && self.config.seq_escape == SeqEscape::Backslash);
+
+		if !bytes.iter().any(needs_escape) {
+			return Ok(bytes)
+		}
+
+		let mut escaped = Vec::with_capacity(bytes.len() + 8);
+
+		for byte in bytes {
+			if !needs_escape(&byte) {
+				escaped.push(byte);
+				continue
+			}
+
+			match self.config.seq_escape {
+				SeqEscape::Backslash => escaped.extend_from_slice(&[b'\\', byte]),
+				SeqEscape::NumericEntity if byte == b'|' => escaped.extend_from_slice(b"&#124;"),
+				_ => return Err(self.invalid("an element has a delimiter in it, which would split it in two"))
+			}
+		}
+
+		Ok(escaped)
+	}
+}
+
+/// Serializes a sequence, or a tuple, by joining its elements with delimiters.
+pub(super) struct SeqSerializer<'a> {
+	/// The serializer for the sequence itself.
+	value: ValueSerializer<'a>,
+
+	/// The elements so far, encoded and escaped.
+	elements: Vec<Vec<u8>>
+}
+
+impl<'a> SeqSerializer<'a> {
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, element: &T) -> Result<()> {
+		let entry = element.serialize(ValueSerializer {
+			depth: self.value.depth + 1,
+			..self.value
+		})?;
+
+		self.elements.push(match entry {
+			Entry::Value(bytes) => bytes,
+			// `None` and `()` are empty elements. They're read back as `None` and `()` too.
+			Entry::Skip | Entry::KeyOnly => Vec::new()
+		});
+
+		Ok(())
+	}
+
+	fn end(self) -> Result<Entry> {
+		if self.elements.is_empty() && self.value.depth == 0 {
+			return Ok(match self.value.config.empty_seq {
+				EmptySeq::EmptyValue => Entry::Value(Vec::new()),
+				EmptySeq::Omit => Entry::Skip
+			})
+		}
+
+		let delimiter = [self.value.delimiter_at(self.value.depth + 1)];
+		Ok(Entry::Value(self.elements.join(&delimiter[..])))
+	}
+}
+
+macro_rules! seq_serializer_impl {
+	($($trait_name:ident::$serialize_name:ident),*) => {
+		$(
+			impl<'a> ser::$trait_name for SeqSerializer<'a> {
+				type Ok = Entry;
+				type Error = Error;
+
+				fn $serialize_name<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+					self.serialize_element(value)
+				}
+
+				fn end(self) -> Result<Entry> {
+					SeqSerializer::end(self)
+				}
+			}
+		)*
+	}
+}
+
+seq_serializer_impl! {
+	SerializeSeq::serialize_element,
+	SerializeTuple::serialize_element,
+	SerializeTupleStruct::serialize_field
+}
+
+macro_rules! value_to_string {
+	($($serialize_name:ident($t:ty)),*) => {
+		$(
+			fn $serialize_name(self, v: $t) -> Result<Entry> {
+				self.text(&v.to_string())
+			}
+		)*
+	}
+}
+
+impl<'a> ser::Serializer for ValueSerializer<'a> {
+	type Ok = Entry;
+	type Error = Error;
+	type SerializeSeq = SeqSerializer<'a>;
+	type SerializeTuple = SeqSerializer<'a>;
+	type SerializeTupleStruct = SeqSerializer<'a>;
+	type SerializeTupleVariant = Impossible<Entry, Error>;
+	type SerializeMap = Impossible<Entry, Error>;
+	type SerializeStruct = Impossible<Entry, Error>;
+	type SerializeStructVariant = Impossible<Entry, Error>;
+
+	value_to_string! {
+		serialize_bool(bool),
+		serialize_i8(i8),
+		serialize_i16(i16),
+		serialize_i32(i32),
+		serialize_i64(i64),
+		serialize_i128(i128),
+		serialize_u8(u8),
+		serialize_u16(u16),
+		serialize_u32(u32),
+		serialize_u64(u64),
+		serialize_u128(u128),
+		serialize_f32(f32),
+		serialize_f64(f64),
+		serialize_char(char)
+	}
+
+	fn serialize_str(self, v: &str) -> Result<Entry> {
+		self.text(v)
+	}
+
+	fn serialize_bytes(self, v: &[u8]) -> Result<Entry> {
+		if v.contains(&b'\r') || v.contains(&b'\n') {
+			return Err(self.invalid("it has a line break in it"))
+		}
+
+		self.escape(v.to_vec()).map(Entry::Value)
+	}
+
+	fn serialize_none(self) -> Result<Entry> {
+		Ok(Entry::Skip)
+	}
+
+	fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Entry> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<Entry> {
+		Ok(Entry::KeyOnly)
+	}
+
+	fn serialize_unit_struct(self, _: &'static str) -> Result<Entry> {
+		Ok(Entry::KeyOnly)
+	}
+
+	fn serialize_unit_variant(self, _: &'static str, _: u32, variant: &'static str) -> Result<Entry> {
+		self.text(variant)
+	}
+
+	fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _: &'static str, value: &T) -> Result<Entry> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _: &'static str, _: u32, _: &'static str, _: &T) -> Result<Entry> {
+		Err(self.invalid("it is an enum variant with data"))
+	}
+
+	fn serialize_seq(self, _: Option<usize>) -> Result<Self::SerializeSeq> {
+		if self.depth >= 2 {
+			return Err(self.invalid("it has sequences nested more than two deep"))
+		}
+
+		Ok(SeqSerializer {
+			value: self,
+			elements: Vec::new()
+		})
+	}
+
+	fn serialize_tuple(self, _: usize) -> Result<Self::SerializeTuple> {
+		self.serialize_seq(None)
+	}
+
+	fn serialize_tuple_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeTupleStruct> {
+		self.serialize_seq(None)
+	}
+
+	fn serialize_tuple_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<Self::SerializeTupleVariant> {
+		Err(self.invalid("it is an enum variant with data"))
+	}
+
+	fn serialize_map(self, _: Option<usize>) -> Result<Self::SerializeMap> {
+		Err(self.invalid("it is a map"))
+	}
+
+	fn serialize_struct(self, _: &'static str, _: usize) -> Result<Self::SerializeStruct> {
+		Err(self.invalid("it is a struct"))
+	}
+
+	fn serialize_struct_variant(self, _: &'static str, _: u32, _: &'static str, _: usize) -> Result<Self::SerializeStructVariant> {
+		Err(self.invalid("it is an enum variant with data"))
+	}
+}
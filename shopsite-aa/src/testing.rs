@@ -0,0 +1,78 @@
+//! Helpers for testing code that reads or writes `.aa` files, such as crates with models of ShopSite's data.
+//! 
+//! The `assert_aa_eq!` macro compares two `.aa` files by what they mean, not by their bytes, so line endings, comments, blank lines, and the order of keys don't matter. When they differ, the panic message lists the keys that are missing, extra, or changed, in a form like a diff:
+//! 
+//! ```should_panic
+//! use shopsite_aa::assert_aa_eq;
+//! 
+//! assert_aa_eq!(b"Name: Widget\nPrice: 10\n", b"Price: 12\nName: Widget\nTaxable\n");
+//! // `.aa` data differs (- left, + right):
+//! // - Price: 10
+//! // + Price: 12
+//! // + Taxable
+//! ```
+//! 
+//! Sections are recognized, in either syntax (see `SectionSyntax::Any`), and compared key by key, so a difference in a section is reported with the section name, like `Shipping.Zip`.
+
+use crate::{
+	de::{DeserializerBuilder, SectionSyntax},
+	diff::Change,
+	value::Map
+};
+use serde::Deserialize;
+use std::fmt;
+
+/// Finds the differences between two `Map`s, as `diff::diff` does. Keys that are missing from `right` come first, in the order of `left`, along with those whose values changed; then keys that are only in `right`, in its order. `Change::Removed` is a key missing from `right`, and `Change::Added` is one that's only in `right`.
+/// 
+/// Sections (values that are maps on both sides) are compared key by key, rather than as a whole.
+pub fn diff(left: &Map, right: &Map) -> Vec<Change> {
+	crate::diff::diff_maps(left, right)
+}
+
+/// Parses two `.aa` files, with sections recognized, and panics, listing the differences, if they don't have the same keys and values. This is what `assert_aa_eq!` calls.
+/// 
+/// # Panics
+/// 
+/// Panics if either file fails to parse, or if they differ.
+#[track_caller]
+pub fn assert_aa_eq(left: &[u8], right: &[u8], message: Option<fmt::Arguments<'_>>) {
+	let parse = |side: &str, input: &[u8]| -> Map {
+		let mut de = DeserializerBuilder::new()
+			.section_syntax(SectionSyntax::Any)
+			.build(input, None);
+
+		match Map::deserialize(&mut de) {
+			Ok(map) => map,
+			Err(error) => panic!("couldn't parse the {} side of `assert_aa_eq!`: {}", side, error)
+		}
+	};
+
+	let differences = diff(&parse("left", left), &parse("right", right));
+
+	if !differences.is_empty() {
+		let mut report = String::from("`.aa` data differs (- left, + right)");
+
+		if let Some(message) = message {
+			report.push_str(&format!(": {}", message));
+		}
+
+		for difference in differences {
+			report.push_str(&format!("\n{}", difference));
+		}
+
+		panic!("{}", report);
+	}
+}
+
+/// Asserts that two `.aa` files have the same keys and values, ignoring line endings, comments, and key order. Each side can be anything that is `AsRef<[u8]>`, like a byte string or a `Vec<u8>`. Like `assert_eq!`, it takes an optional message, with formatting arguments.
+/// 
+/// See the `testing` module for an example.
+#[macro_export]
+macro_rules! assert_aa_eq {
+	($left:expr, $right:expr $(,)?) => {
+		$crate::testing::assert_aa_eq(::std::convert::AsRef::<[u8]>::as_ref(&$left), ::std::convert::AsRef::<[u8]>::as_ref(&$right), ::std::option::Option::None)
+	};
+	($left:expr, $right:expr, $($message:tt)+) => {
+		$crate::testing::assert_aa_eq(::std::convert::AsRef::<[u8]>::as_ref(&$left), ::std::convert::AsRef::<[u8]>::as_ref(&$right), ::std::option::Option::Some(::std::format_args!($($message)+)))
+	};
+}
@@ -0,0 +1,254 @@
+//! A loosely-typed representation of `.aa` data, for when you don't know (or don't care) exactly what keys a file has.
+//! 
+//! Every `.aa` file can be deserialized into a `Map`. This is also useful for capturing keys that a struct doesn't know about, since every ShopSite release seems to add a few:
+//! 
+//! ```
+//! use serde::Deserialize;
+//! use shopsite_aa::de as aa;
+//! use std::collections::HashMap;
+//! 
+//! #[derive(Deserialize)]
+//! struct Config {
+//!     title: String,
+//! 
+//!     #[serde(flatten)]
+//!     extra: HashMap<String, aa::Value>
+//! }
+//! 
+//! let config: Config = aa::from_bytes(b"title: My Store\nbgcolor: #FFFFFF\nshowcart\n", None).unwrap();
+//! assert_eq!(config.extra["bgcolor"], aa::Value::from("#FFFFFF"));
+//! assert_eq!(config.extra["showcart"], aa::Value::Unit);
+//! ```
+//! 
+//! `.aa` files don't say what type anything is, so values read from them are always either `Value::String` or, for keys with no value at all, `Value::Unit`. Sequences are not split up, since there's no telling whether a `|` in some value is a separator or just a `|`. Use `Value::split` if you know that a value is a sequence.
+//! 
+//! `to_value` and `from_value` convert between `Value` and other types, so a file can be read into a `Map`, patched, and then turned into a struct, or a struct can be turned into a `Map` and inspected or changed. The `ser` module writes a `Map`, or `Entries`, back out as a `.aa` file.
+
+use serde::{
+	de::{self, Deserialize, Deserializer, MapAccess, SeqAccess, Visitor},
+	ser::{Serialize, Serializer}
+};
+use std::fmt::{self, Formatter};
+use crate::de::InputEncoding;
+
+mod to_value;
+pub use to_value::*;
+
+mod from_value;
+pub use from_value::*;
+
+mod entries;
+pub use entries::*;
+
+/// A map from keys to values, such as the contents of an entire `.aa` file.
+/// 
+/// With the `indexmap` feature, this is an `IndexMap`, which keeps keys in the order they were inserted, so a file read into a `Map` and written back out has its keys in the same order. ShopSite sometimes cares about that order. Without it, this is a `BTreeMap`, which keeps keys sorted.
+#[cfg(feature = "indexmap")]
+pub type Map = indexmap::IndexMap<String, Value>;
+
+/// A map from keys to values, such as the contents of an entire `.aa` file.
+/// 
+/// With the `indexmap` feature, this is an `IndexMap`, which keeps keys in the order they were inserted, so a file read into a `Map` and written back out has its keys in the same order. ShopSite sometimes cares about that order. Without it, this is a `BTreeMap`, which keeps keys sorted.
+#[cfg(not(feature = "indexmap"))]
+pub type Map = std::collections::BTreeMap<String, Value>;
+
+/// Any value.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub enum Value {
+	/// The value of a key that has no value, not even an empty one (that is, the line has no `:` on it).
+	#[default]
+	Unit,
+
+	/// A value that is text. This includes numbers, booleans, and everything else that can appear in a `.aa` file, since `.aa` files don't distinguish between them.
+	String(String),
+
+	/// A sequence of values. These never come from a `.aa` file, but can come from other formats, or be constructed by hand.
+	Seq(Vec<Value>),
+
+	/// A map of values. The only map in a `.aa` file is the entire file.
+	Map(Map)
+}
+
+impl Value {
+	/// Gets the text of this value, if it is text.
+	pub fn as_str(&self) -> Option<&str> {
+		match self {
+			Value::String(s) => Some(&s[..]),
+			_ => None
+		}
+	}
+
+	/// Whether this value is `Unit`.
+	pub fn is_unit(&self) -> bool {
+		matches!(self, Value::Unit)
+	}
+
+	/// Splits a text value into sequence elements, the same way the deserializer would if it were deserializing a sequence. `Unit` and empty text are empty sequences. Other kinds of values have no elements.
+	pub fn split(&self) -> impl Iterator<Item = &str> {
+		self.as_str()
+		.filter(|s| !s.is_empty())
+		.into_iter()
+		.flat_map(|s| s.split('|'))
+	}
+}
+
+impl From<String> for Value {
+	fn from(s: String) -> Self {
+		Value::String(s)
+	}
+}
+
+impl From<&str> for Value {
+	fn from(s: &str) -> Self {
+		Value::String(s.to_string())
+	}
+}
+
+impl From<Vec<Value>> for Value {
+	fn from(seq: Vec<Value>) -> Self {
+		Value::Seq(seq)
+	}
+}
+
+impl From<Map> for Value {
+	fn from(map: Map) -> Self {
+		Value::Map(map)
+	}
+}
+
+impl fmt::Display for Value {
+	/// Writes the value as it would appear in a `.aa` file. Sequences are separated with `|`. Maps don't have a representation in a `.aa` value, so they're written like `{key: value, …}`.
+	fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+		match self {
+			Value::Unit => Ok(()),
+			Value::String(s) => f.write_str(s),
+			Value::Seq(seq) => {
+				for (index, element) in seq.iter().enumerate() {
+					if index != 0 {
+						f.write_str("|")?;
+					}
+
+					fmt::Display::fmt(element, f)?;
+				}
+
+				Ok(())
+			},
+			Value::Map(map) => {
+				f.write_str("{")?;
+
+				for (index, (key, value)) in map.iter().enumerate() {
+					if index != 0 {
+						f.write_str(", ")?;
+					}
+
+					write!(f, "{}: {}", key, value)?;
+				}
+
+				f.write_str("}")
+			}
+		}
+	}
+}
+
+impl Serialize for Value {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match self {
+			Value::Unit => serializer.serialize_unit(),
+			Value::String(s) => serializer.serialize_str(s),
+			Value::Seq(seq) => serializer.collect_seq(seq),
+			Value::Map(map) => serializer.collect_map(map)
+		}
+	}
+}
+
+/// The most elements to allocate room for ahead of time, however many a deserializer says are coming. Deserializers for other formats get their size hints from the input, so a hint can't be trusted not to be huge.
+const MAX_PREALLOCATE: usize = 4096;
+
+/// How many elements to allocate room for, given a size hint, as with Serde's own `size_hint::cautious`.
+fn cautious(hint: Option<usize>) -> usize {
+	hint.unwrap_or(0).min(MAX_PREALLOCATE)
+}
+
+struct ValueVisitor;
+
+impl<'de> Visitor<'de> for ValueVisitor {
+	type Value = Value;
+
+	fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+		f.write_str("any value")
+	}
+
+	fn visit_unit<E: de::Error>(self) -> Result<Value, E> {
+		Ok(Value::Unit)
+	}
+
+	fn visit_none<E: de::Error>(self) -> Result<Value, E> {
+		Ok(Value::Unit)
+	}
+
+	fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Value, D::Error> {
+		Deserialize::deserialize(deserializer)
+	}
+
+	fn visit_str<E: de::Error>(self, v: &str) -> Result<Value, E> {
+		Ok(Value::String(v.to_string()))
+	}
+
+	fn visit_string<E: de::Error>(self, v: String) -> Result<Value, E> {
+		Ok(Value::String(v))
+	}
+
+	fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<Value, E> {
+		// Bytes are what the deserializer hands over with `UntypedValue::Bytes`, undecoded, and this visitor can't ask it what encoding they're in. `.aa` files are Windows-1252 unless they're UTF-8, and Windows-1252 text almost never happens to be valid UTF-8 too, so decode them as UTF-8 if they're valid UTF-8, and as Windows-1252 if not.
+		let encoding = match std::str::from_utf8(v) {
+			Ok(_) => InputEncoding::Utf8,
+			Err(_) => InputEncoding::Windows1252
+		};
+
+		Ok(Value::String(encoding.decode(v)))
+	}
+
+	// Other formats have types that `.aa` doesn't. Those are converted to text, the way they'd look in a `.aa` file.
+
+	fn visit_bool<E: de::Error>(self, v: bool) -> Result<Value, E> {
+		Ok(Value::String(v.to_string()))
+	}
+
+	fn visit_i64<E: de::Error>(self, v: i64) -> Result<Value, E> {
+		Ok(Value::String(v.to_string()))
+	}
+
+	fn visit_u64<E: de::Error>(self, v: u64) -> Result<Value, E> {
+		Ok(Value::String(v.to_string()))
+	}
+
+	fn visit_f64<E: de::Error>(self, v: f64) -> Result<Value, E> {
+		Ok(Value::String(v.to_string()))
+	}
+
+	fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Value, A::Error> {
+		let mut elements = Vec::with_capacity(cautious(seq.size_hint()));
+
+		while let Some(element) = seq.next_element()? {
+			elements.push(element);
+		}
+
+		Ok(Value::Seq(elements))
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Value, A::Error> {
+		let mut entries = Map::new();
+
+		while let Some((key, value)) = map.next_entry()? {
+			entries.insert(key, value);
+		}
+
+		Ok(Value::Map(entries))
+	}
+}
+
+impl<'de> Deserialize<'de> for Value {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		deserializer.deserialize_any(ValueVisitor)
+	}
+}
@@ -0,0 +1,51 @@
+use serde::{
+	de::{Deserialize, Deserializer, MapAccess, Visitor},
+	ser::{Serialize, Serializer}
+};
+use std::fmt::{self, Formatter};
+use super::{cautious, Value};
+
+/// The top-level keys and values of a `.aa` file, in the order they appear, including any duplicates. Unlike a `Map`, this neither sorts the keys nor keeps only one value for each.
+/// 
+/// This is serialized as a map, and deserialized from one, so it can be read from a `.aa` file with `de::from_bytes` and friends, and written to other formats with the keys in the same order.
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Entries(pub Vec<(String, Value)>);
+
+impl Entries {
+	/// The keys and values, borrowed, in order.
+	pub fn iter(&self) -> impl Iterator<Item = (&str, &Value)> {
+		self.0.iter().map(|(key, value)| (key.as_str(), value))
+	}
+}
+
+impl Serialize for Entries {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		serializer.collect_map(self.iter())
+	}
+}
+
+impl<'de> Deserialize<'de> for Entries {
+	fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+		struct EntriesVisitor;
+
+		impl<'de> Visitor<'de> for EntriesVisitor {
+			type Value = Entries;
+
+			fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+				f.write_str("a map")
+			}
+
+			fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Entries, A::Error> {
+				let mut entries = Vec::with_capacity(cautious(map.size_hint()));
+
+				while let Some(entry) = map.next_entry()? {
+					entries.push(entry);
+				}
+
+				Ok(Entries(entries))
+			}
+		}
+
+		deserializer.deserialize_map(EntriesVisitor)
+	}
+}
@@ -0,0 +1,203 @@
+use serde::de::{
+	self,
+	value::{MapDeserializer, SeqDeserializer},
+	DeserializeOwned,
+	DeserializeSeed,
+	EnumAccess,
+	IntoDeserializer,
+	VariantAccess,
+	Visitor
+};
+use std::{
+	fmt::Display,
+	str::FromStr
+};
+use super::Value;
+use crate::de::{Error, Result};
+
+/// Converts a `Value` into some deserializable type.
+/// 
+/// This works the way deserializing straight from a `.aa` file does, with the default `DeserializerBuilder` options: text is parsed into numbers and booleans as needed, text is split at `|` when a sequence is wanted, and `Value::Unit` is `None` when an `Option` is wanted. So a file can be read into a `Map`, changed, and then turned into a struct, with the same results as if the changed file had been deserialized.
+/// 
+/// # Errors
+/// 
+/// Fails if the value doesn't fit the type, such as when a number is wanted but the text isn't one. There is no input file, so these errors have no position.
+pub fn from_value<T: DeserializeOwned>(value: Value) -> Result<T> {
+	T::deserialize(value)
+}
+
+impl<'de> IntoDeserializer<'de, Error> for Value {
+	type Deserializer = Value;
+
+	fn into_deserializer(self) -> Value {
+		self
+	}
+}
+
+/// Parses text into some type, for the `deserialize_*` methods for numbers and booleans.
+fn parse<T: FromStr>(s: &str, what: &str) -> Result<T>
+where T::Err: Display {
+	s.parse().map_err(|error| de::Error::custom(format_args!("invalid {} “{}”: {}", what, s, error)))
+}
+
+macro_rules! deserialize_with_from_str {
+	($method:ident, $visit:ident, $what:literal) => {
+		fn $method<V>(self, visitor: V) -> Result<V::Value>
+		where V: Visitor<'de> {
+			match self {
+				Value::String(s) => visitor.$visit(parse(&s, $what)?),
+				other => other.deserialize_any(visitor)
+			}
+		}
+	}
+}
+
+impl<'de> de::Deserializer<'de> for Value {
+	type Error = Error;
+
+	fn is_human_readable(&self) -> bool { true }
+
+	fn deserialize_any<V>(self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		match self {
+			Value::Unit => visitor.visit_unit(),
+			Value::String(s) => visitor.visit_string(s),
+			Value::Seq(seq) => visitor.visit_seq(SeqDeserializer::new(seq.into_iter())),
+			Value::Map(map) => visitor.visit_map(MapDeserializer::new(map.into_iter()))
+		}
+	}
+
+	deserialize_with_from_str!(deserialize_bool, visit_bool, "boolean");
+	deserialize_with_from_str!(deserialize_i8, visit_i8, "integer");
+	deserialize_with_from_str!(deserialize_i16, visit_i16, "integer");
+	deserialize_with_from_str!(deserialize_i32, visit_i32, "integer");
+	deserialize_with_from_str!(deserialize_i64, visit_i64, "integer");
+	deserialize_with_from_str!(deserialize_i128, visit_i128, "integer");
+	deserialize_with_from_str!(deserialize_u8, visit_u8, "integer");
+	deserialize_with_from_str!(deserialize_u16, visit_u16, "integer");
+	deserialize_with_from_str!(deserialize_u32, visit_u32, "integer");
+	deserialize_with_from_str!(deserialize_u64, visit_u64, "integer");
+	deserialize_with_from_str!(deserialize_u128, visit_u128, "integer");
+	deserialize_with_from_str!(deserialize_f32, visit_f32, "number");
+	deserialize_with_from_str!(deserialize_f64, visit_f64, "number");
+	deserialize_with_from_str!(deserialize_char, visit_char, "character");
+
+	fn deserialize_bytes<V>(self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		match self {
+			Value::String(s) => visitor.visit_byte_buf(s.into_bytes()),
+			other => other.deserialize_any(visitor)
+		}
+	}
+
+	fn deserialize_byte_buf<V>(self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		self.deserialize_bytes(visitor)
+	}
+
+	fn deserialize_option<V>(self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		match self {
+			Value::Unit => visitor.visit_none(),
+			other => visitor.visit_some(other)
+		}
+	}
+
+	fn deserialize_newtype_struct<V>(self, _name: &'static str, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		visitor.visit_newtype_struct(self)
+	}
+
+	fn deserialize_seq<V>(self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		match self {
+			// Split text the same way the deserializer would. No value at all is an empty sequence.
+			Value::String(_) | Value::Unit => {
+				let elements: Vec<Value> = self.split().map(Value::from).collect();
+				visitor.visit_seq(SeqDeserializer::new(elements.into_iter()))
+			},
+			other => other.deserialize_any(visitor)
+		}
+	}
+
+	fn deserialize_tuple<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		self.deserialize_seq(visitor)
+	}
+
+	fn deserialize_tuple_struct<V>(self, _name: &'static str, _len: usize, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		self.deserialize_seq(visitor)
+	}
+
+	fn deserialize_enum<V>(self, _name: &'static str, _variants: &'static [&'static str], visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		match self {
+			// A variant with no data, written as just its name.
+			Value::String(variant) => visitor.visit_enum(EnumDeserializer { variant, data: None }),
+
+			// A variant with data, written as a map from its name to its data, as `to_value` does.
+			Value::Map(map) if map.len() == 1 => {
+				let (variant, data) = map.into_iter().next().unwrap();
+				visitor.visit_enum(EnumDeserializer { variant, data: Some(data) })
+			},
+
+			_ => Err(de::Error::custom("expected an enum variant name, or a map with a single entry"))
+		}
+	}
+
+	fn deserialize_ignored_any<V>(self, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		visitor.visit_unit()
+	}
+
+	serde::forward_to_deserialize_any! {
+		str string unit unit_struct map struct identifier
+	}
+}
+
+/// Access to an enum variant in a `Value`.
+struct EnumDeserializer {
+	variant: String,
+	data: Option<Value>
+}
+
+impl<'de> EnumAccess<'de> for EnumDeserializer {
+	type Error = Error;
+	type Variant = VariantDeserializer;
+
+	fn variant_seed<V>(self, seed: V) -> Result<(V::Value, VariantDeserializer)>
+	where V: DeserializeSeed<'de> {
+		let variant = seed.deserialize(self.variant.into_deserializer())?;
+		Ok((variant, VariantDeserializer(self.data)))
+	}
+}
+
+/// Access to the data of an enum variant in a `Value`, if it has any.
+struct VariantDeserializer(Option<Value>);
+
+impl<'de> VariantAccess<'de> for VariantDeserializer {
+	type Error = Error;
+
+	fn unit_variant(self) -> Result<()> {
+		match self.0 {
+			None | Some(Value::Unit) => Ok(()),
+			Some(_) => Err(de::Error::custom("expected a variant with no data"))
+		}
+	}
+
+	fn newtype_variant_seed<T>(self, seed: T) -> Result<T::Value>
+	where T: DeserializeSeed<'de> {
+		seed.deserialize(self.0.unwrap_or_default())
+	}
+
+	fn tuple_variant<V>(self, _len: usize, visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		de::Deserializer::deserialize_seq(self.0.unwrap_or_default(), visitor)
+	}
+
+	fn struct_variant<V>(self, _fields: &'static [&'static str], visitor: V) -> Result<V::Value>
+	where V: Visitor<'de> {
+		de::Deserializer::deserialize_any(self.0.unwrap_or_default(), visitor)
+	}
+}
@@ -0,0 +1,258 @@
+use serde::ser::{self, Serialize};
+use super::{Map, Value};
+use crate::de::{Error, Result};
+
+/// Converts anything serializable into a `Value`.
+/// 
+/// Numbers, booleans, and other scalars become `Value::String`, written the way they'd appear in a `.aa` file, so the result looks just like what deserializing a `Value` from a `.aa` file would produce. `None` and `()` become `Value::Unit`. Enum variants without data become their name; other variants become a single-entry map from their name to their data, like `serde_json` does.
+/// 
+/// # Errors
+/// 
+/// Fails if `value`'s `Serialize` implementation fails, or if it has a map whose keys aren't text.
+pub fn to_value<T: Serialize + ?Sized>(value: &T) -> Result<Value> {
+	value.serialize(ValueSerializer)
+}
+
+/// Serializer whose output is a `Value`. See `to_value`.
+struct ValueSerializer;
+
+fn string(v: impl ToString) -> Result<Value> {
+	Ok(Value::String(v.to_string()))
+}
+
+/// Wraps an enum variant's data in a map from the variant's name to the data.
+fn variant(name: &str, data: Value) -> Value {
+	let mut map = Map::new();
+	map.insert(name.to_string(), data);
+	Value::Map(map)
+}
+
+impl ser::Serializer for ValueSerializer {
+	type Ok = Value;
+	type Error = Error;
+
+	type SerializeSeq = SerializeVec;
+	type SerializeTuple = SerializeVec;
+	type SerializeTupleStruct = SerializeVec;
+	type SerializeTupleVariant = SerializeVec;
+	type SerializeMap = SerializeMap;
+	type SerializeStruct = SerializeMap;
+	type SerializeStructVariant = SerializeMap;
+
+	fn serialize_bool(self, v: bool) -> Result<Value> { string(v) }
+	fn serialize_i8(self, v: i8) -> Result<Value> { string(v) }
+	fn serialize_i16(self, v: i16) -> Result<Value> { string(v) }
+	fn serialize_i32(self, v: i32) -> Result<Value> { string(v) }
+	fn serialize_i64(self, v: i64) -> Result<Value> { string(v) }
+	fn serialize_i128(self, v: i128) -> Result<Value> { string(v) }
+	fn serialize_u8(self, v: u8) -> Result<Value> { string(v) }
+	fn serialize_u16(self, v: u16) -> Result<Value> { string(v) }
+	fn serialize_u32(self, v: u32) -> Result<Value> { string(v) }
+	fn serialize_u64(self, v: u64) -> Result<Value> { string(v) }
+	fn serialize_u128(self, v: u128) -> Result<Value> { string(v) }
+	fn serialize_f32(self, v: f32) -> Result<Value> { string(v) }
+	fn serialize_f64(self, v: f64) -> Result<Value> { string(v) }
+	fn serialize_char(self, v: char) -> Result<Value> { string(v) }
+	fn serialize_str(self, v: &str) -> Result<Value> { string(v) }
+
+	fn serialize_bytes(self, v: &[u8]) -> Result<Value> {
+		// `Value` has no bytes variant. Deserializing bytes as `Value` decodes them the same way.
+		string(String::from_utf8_lossy(v))
+	}
+
+	fn serialize_none(self) -> Result<Value> {
+		Ok(Value::Unit)
+	}
+
+	fn serialize_some<T: Serialize + ?Sized>(self, value: &T) -> Result<Value> {
+		value.serialize(self)
+	}
+
+	fn serialize_unit(self) -> Result<Value> {
+		Ok(Value::Unit)
+	}
+
+	fn serialize_unit_struct(self, _name: &'static str) -> Result<Value> {
+		Ok(Value::Unit)
+	}
+
+	fn serialize_unit_variant(self, _name: &'static str, _index: u32, variant: &'static str) -> Result<Value> {
+		string(variant)
+	}
+
+	fn serialize_newtype_struct<T: Serialize + ?Sized>(self, _name: &'static str, value: &T) -> Result<Value> {
+		value.serialize(self)
+	}
+
+	fn serialize_newtype_variant<T: Serialize + ?Sized>(self, _name: &'static str, _index: u32, variant_name: &'static str, value: &T) -> Result<Value> {
+		Ok(variant(variant_name, value.serialize(self)?))
+	}
+
+	fn serialize_seq(self, len: Option<usize>) -> Result<SerializeVec> {
+		Ok(SerializeVec {
+			elements: Vec::with_capacity(len.unwrap_or(0)),
+			variant: None
+		})
+	}
+
+	fn serialize_tuple(self, len: usize) -> Result<SerializeVec> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_struct(self, _name: &'static str, len: usize) -> Result<SerializeVec> {
+		self.serialize_seq(Some(len))
+	}
+
+	fn serialize_tuple_variant(self, _name: &'static str, _index: u32, variant: &'static str, len: usize) -> Result<SerializeVec> {
+		Ok(SerializeVec {
+			elements: Vec::with_capacity(len),
+			variant: Some(variant)
+		})
+	}
+
+	fn serialize_map(self, _len: Option<usize>) -> Result<SerializeMap> {
+		Ok(SerializeMap {
+			map: Map::new(),
+			next_key: None,
+			variant: None
+		})
+	}
+
+	fn serialize_struct(self, _name: &'static str, len: usize) -> Result<SerializeMap> {
+		self.serialize_map(Some(len))
+	}
+
+	fn serialize_struct_variant(self, _name: &'static str, _index: u32, variant: &'static str, _len: usize) -> Result<SerializeMap> {
+		Ok(SerializeMap {
+			map: Map::new(),
+			next_key: None,
+			variant: Some(variant)
+		})
+	}
+}
+
+/// Collects the elements of a sequence, tuple, or tuple variant.
+struct SerializeVec {
+	elements: Vec<Value>,
+
+	/// The name of the enum variant being serialized, if any.
+	variant: Option<&'static str>
+}
+
+impl SerializeVec {
+	fn push<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+		self.elements.push(value.serialize(ValueSerializer)?);
+		Ok(())
+	}
+
+	fn finish(self) -> Result<Value> {
+		let seq = Value::Seq(self.elements);
+
+		Ok(match self.variant {
+			Some(name) => variant(name, seq),
+			None => seq
+		})
+	}
+}
+
+impl ser::SerializeSeq for SerializeVec {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> { self.push(value) }
+	fn end(self) -> Result<Value> { self.finish() }
+}
+
+impl ser::SerializeTuple for SerializeVec {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_element<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> { self.push(value) }
+	fn end(self) -> Result<Value> { self.finish() }
+}
+
+impl ser::SerializeTupleStruct for SerializeVec {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> { self.push(value) }
+	fn end(self) -> Result<Value> { self.finish() }
+}
+
+impl ser::SerializeTupleVariant for SerializeVec {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> { self.push(value) }
+	fn end(self) -> Result<Value> { self.finish() }
+}
+
+/// Collects the entries of a map, struct, or struct variant.
+struct SerializeMap {
+	map: Map,
+
+	/// The key of the entry being serialized, between `serialize_key` and `serialize_value`.
+	next_key: Option<String>,
+
+	/// The name of the enum variant being serialized, if any.
+	variant: Option<&'static str>
+}
+
+impl SerializeMap {
+	fn finish(self) -> Result<Value> {
+		let map = Value::Map(self.map);
+
+		Ok(match self.variant {
+			Some(name) => variant(name, map),
+			None => map
+		})
+	}
+}
+
+impl ser::SerializeMap for SerializeMap {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_key<T: Serialize + ?Sized>(&mut self, key: &T) -> Result<()> {
+		// Keys in a `.aa` file are always text. Numbers and such are fine, since they become text anyway, but anything more complicated isn't.
+		match key.serialize(ValueSerializer)? {
+			Value::String(key) => {
+				self.next_key = Some(key);
+				Ok(())
+			},
+			_ => Err(ser::Error::custom("map keys must be text"))
+		}
+	}
+
+	fn serialize_value<T: Serialize + ?Sized>(&mut self, value: &T) -> Result<()> {
+		let key = self.next_key.take().expect("`serialize_value` called without `serialize_key`");
+		self.map.insert(key, value.serialize(ValueSerializer)?);
+		Ok(())
+	}
+
+	fn end(self) -> Result<Value> { self.finish() }
+}
+
+impl ser::SerializeStruct for SerializeMap {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+		self.map.insert(key.to_string(), value.serialize(ValueSerializer)?);
+		Ok(())
+	}
+
+	fn end(self) -> Result<Value> { self.finish() }
+}
+
+impl ser::SerializeStructVariant for SerializeMap {
+	type Ok = Value;
+	type Error = Error;
+
+	fn serialize_field<T: Serialize + ?Sized>(&mut self, key: &'static str, value: &T) -> Result<()> {
+		self.map.insert(key.to_string(), value.serialize(ValueSerializer)?);
+		Ok(())
+	}
+
+	fn end(self) -> Result<Value> { self.finish() }
+}
@@ -0,0 +1,62 @@
+//! Conversion between `.aa` files and JSON, for JavaScript, through [`wasm-bindgen`](https://rustwasm.github.io/wasm-bindgen/). This lets a web-based back-office tool convert files in the browser, without a round trip to a server.
+//! 
+//! To use these from JavaScript, depend on this crate with the `wasm` feature from a `cdylib` crate, and build that with `wasm-pack` or `wasm-bindgen`. Functions exported from dependencies are exported from the final module, too.
+//! 
+//! Both functions work with the bytes of a `.aa` file in Windows-1252, which is what ShopSite reads and writes, and with JSON objects whose keys are in the same order as in the file.
+
+use crate::{
+	de::{self, LineEnding},
+	ser,
+	value::Entries
+};
+use serde::Serialize;
+use wasm_bindgen::prelude::*;
+
+/// Converts a `.aa` file to a JSON object. Keys with no value become `null`; everything else becomes a string, since `.aa` files don't say what type anything is.
+/// 
+/// Fails, with an error describing the problem and where it is, if the file can't be parsed.
+#[wasm_bindgen]
+pub fn parse_aa_to_json(bytes: &[u8]) -> Result<String, JsError> {
+	Ok(aa_to_json(bytes)?)
+}
+
+/// Converts a JSON object to a `.aa` file. Strings, numbers, and booleans become values as they are; `null` becomes a key with no value; and arrays of those become values separated by `|`. This is written by the `ser` module, with `\n` line endings, so arrays in arrays have their elements separated by `,`.
+/// 
+/// Fails if the JSON isn't an object, or if it has something that can't be written in a `.aa` file, such as a nested object, a value with a line break in it, or a character that Windows-1252 doesn't have.
+#[wasm_bindgen]
+pub fn json_to_aa(json: &str) -> Result<Vec<u8>, JsError> {
+	json_to_aa_bytes(json).map_err(|error| JsError::new(&error))
+}
+
+fn aa_to_json(bytes: &[u8]) -> de::Result<String> {
+	let entries: Entries = de::from_bytes(bytes, None)?;
+	Ok(serde_json::to_string(&entries).expect("serializing a `Value` to JSON should never fail"))
+}
+
+fn json_to_aa_bytes(json: &str) -> Result<Vec<u8>, String> {
+	let entries: Entries = serde_json::from_str(json).map_err(|error| format!("invalid JSON: {}", error))?;
+	let mut serializer = ser::SerializerBuilder::new().line_ending(LineEnding::Lf).build(Vec::new());
+	entries.serialize(&mut serializer).map_err(|error| error.to_string())?;
+	Ok(serializer.into_inner())
+}
+
+#[test]
+fn test_aa_to_json() {
+	assert_eq!(aa_to_json(b"# comment\nName: Caf\xe9\nTaxable\nPrice: 10\nName: again\n").unwrap(), r#"{"Name":"Café","Taxable":null,"Price":"10","Name":"again"}"#);
+}
+
+#[test]
+fn test_json_to_aa() {
+	assert_eq!(json_to_aa_bytes(r#"{"Name": "Café", "Taxable": null, "Price": 10.5, "Sale": false, "Tags": ["a", 2, null]}"#).unwrap(), b"Name: Caf\xe9\nTaxable\nPrice: 10.5\nSale: false\nTags: a|2|\n");
+
+	// Things that a `.aa` file can't hold are rejected, not mangled.
+	for json in &[r#"[1, 2]"#, r#"{"a": {"b": 1}}"#, r#"{"a": [[[1]]]}"#, r#"{"a": ["x|y"]}"#, r#"{"a": "x\ny"}"#, r#"{"a:b": 1}"#, r##"{"#a": 1}"##, r#"{"": 1}"#, r#"{"a": "€ ☃"}"#] {
+		assert!(json_to_aa_bytes(json).is_err(), "{} should have been rejected", json);
+	}
+
+	assert_eq!(json_to_aa_bytes(r#"{"a": [[1, 2], 3]}"#).unwrap(), b"a: 1,2|3\n");
+
+	// Converting to JSON and back gets the same file.
+	let aa = b"Name: Widget\nTaxable\nTags: a|b\n";
+	assert_eq!(json_to_aa_bytes(&aa_to_json(aa).unwrap()).unwrap(), aa);
+}
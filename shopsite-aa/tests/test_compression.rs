@@ -0,0 +1,133 @@
+#![cfg(feature = "compression")]
+
+use serde::Deserialize;
+use shopsite_aa::de as aa;
+use std::{fs, io::Write, path::Path, sync::Arc};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct TestCompression {
+	name: String,
+	list: Vec<u32>
+}
+
+const INPUT: &[u8] = b"# comment\r\nname: caf\xe9\r\nlist: 1|2|3\r\n";
+
+fn expected() -> TestCompression {
+	TestCompression { name: "café".to_string(), list: vec![1, 2, 3] }
+}
+
+fn gzip(bytes: &[u8]) -> Vec<u8> {
+	let mut encoder = flate2::write::GzEncoder::new(Vec::new(), Default::default());
+	encoder.write_all(bytes).unwrap();
+	encoder.finish().unwrap()
+}
+
+fn zstd(bytes: &[u8]) -> Vec<u8> {
+	ruzstd::encoding::compress_to_vec(bytes, ruzstd::encoding::CompressionLevel::Fastest)
+}
+
+/// A reader that hands over one byte per `read` call, like a slow network connection.
+struct Trickle<'a>(&'a [u8]);
+
+impl std::io::Read for Trickle<'_> {
+	fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+		match (self.0.split_first(), buf.first_mut()) {
+			(Some((byte, rest)), Some(slot)) => {
+				*slot = *byte;
+				self.0 = rest;
+				Ok(1)
+			},
+			_ => Ok(0)
+		}
+	}
+}
+
+#[test]
+fn test_from_reader_compressed() {
+	assert_eq!(aa::from_reader::<TestCompression, _>(&gzip(INPUT)[..], None).unwrap(), expected());
+	assert_eq!(aa::from_reader::<TestCompression, _>(&zstd(INPUT)[..], None).unwrap(), expected());
+	assert_eq!(aa::from_reader::<TestCompression, _>(INPUT, None).unwrap(), expected());
+
+	// Concatenated gzip files are one file.
+	let (first, second) = INPUT.split_at(15);
+	let mut concatenated = gzip(first);
+	concatenated.extend(gzip(second));
+	assert_eq!(aa::from_reader::<TestCompression, _>(&concatenated[..], None).unwrap(), expected());
+
+	// Corrupt compressed data is an I/O error.
+	let mut corrupt = gzip(INPUT);
+	corrupt.truncate(corrupt.len() / 2);
+	assert!(matches!(aa::from_reader::<TestCompression, _>(&corrupt[..], None), Err(aa::Error::Io { .. })));
+	assert!(matches!(aa::from_reader::<TestCompression, _>(&b"\x28\xb5\x2f\xfd\xff"[..], None), Err(aa::Error::Io { pos: None, .. })));
+}
+
+#[test]
+fn test_trickle_compressed() {
+	// This test verifies that compression is detected even if the reader hands over less than the whole magic number at a time.
+
+	assert_eq!(aa::from_reader::<TestCompression, _>(Trickle(&gzip(INPUT)), None).unwrap(), expected());
+	assert_eq!(aa::from_reader::<TestCompression, _>(Trickle(&zstd(INPUT)), None).unwrap(), expected());
+	assert_eq!(aa::from_reader::<TestCompression, _>(Trickle(INPUT), None).unwrap(), expected());
+
+	for (compressed, compression) in [(gzip(INPUT), aa::Compression::Gzip), (zstd(INPUT), aa::Compression::Zstd)] {
+		let input = aa::decompress(std::io::BufReader::with_capacity(1, &compressed[..])).unwrap();
+		assert_eq!(input.compression(), compression);
+	}
+
+	// Input shorter than a magic number is passed along as it is.
+	for input in [&b""[..], b"a", b"a:\n"] {
+		let mut decompressed = Vec::new();
+		let mut reader = aa::decompress(std::io::BufReader::with_capacity(1, Trickle(input))).unwrap();
+		assert_eq!(reader.compression(), aa::Compression::None);
+		std::io::Read::read_to_end(&mut reader, &mut decompressed).unwrap();
+		assert_eq!(decompressed, input);
+	}
+}
+
+#[test]
+fn test_zstd_frames() {
+	// This test verifies that every frame of Zstandard input is decompressed, skipping skippable frames, and that anything else after the last frame is an error.
+
+	let (first, second) = INPUT.split_at(15);
+	let mut concatenated = zstd(first);
+	concatenated.extend(zstd(second));
+	assert_eq!(aa::from_reader::<TestCompression, _>(&concatenated[..], None).unwrap(), expected());
+	assert_eq!(aa::from_reader::<TestCompression, _>(Trickle(&concatenated), None).unwrap(), expected());
+
+	// A skippable frame, like the one `pzstd` writes, can come first or in between.
+	let skippable = b"\x50\x2a\x4d\x18\x04\x00\x00\x00skip";
+	let mut with_skippable = skippable.to_vec();
+	with_skippable.extend(zstd(first));
+	with_skippable.extend(skippable);
+	with_skippable.extend(zstd(second));
+	assert_eq!(aa::Compression::detect(&with_skippable), aa::Compression::Zstd);
+	assert_eq!(aa::from_reader::<TestCompression, _>(&with_skippable[..], None).unwrap(), expected());
+
+	let mut trailing = zstd(INPUT);
+	trailing.extend(b"name: extra\n");
+	assert!(matches!(aa::from_reader::<TestCompression, _>(&trailing[..], None), Err(aa::Error::Io { .. })));
+
+	let mut truncated = zstd(INPUT);
+	truncated.extend(&skippable[..10]);
+	assert!(matches!(aa::from_reader::<TestCompression, _>(&truncated[..], None), Err(aa::Error::Io { .. })));
+}
+
+#[test]
+fn test_from_file_compressed() {
+	for (extension, compressed) in [("gz", gzip(INPUT)), ("zst", zstd(INPUT))] {
+		let path: Arc<Path> = std::env::temp_dir().join(format!("shopsite-aa-test-compression-{}.aa.{}", std::process::id(), extension)).into();
+		fs::write(&path, compressed).unwrap();
+
+		let result: aa::Result<TestCompression> = aa::from_file(path.clone());
+		fs::remove_file(&path).unwrap();
+		assert_eq!(result.unwrap(), expected());
+	}
+}
+
+#[test]
+fn test_detect_compression() {
+	assert_eq!(aa::Compression::detect(&gzip(INPUT)), aa::Compression::Gzip);
+	assert_eq!(aa::Compression::detect(&zstd(INPUT)), aa::Compression::Zstd);
+	assert_eq!(aa::Compression::detect(INPUT), aa::Compression::None);
+	assert_eq!(aa::Compression::detect(b""), aa::Compression::None);
+}
@@ -0,0 +1,61 @@
+#![cfg(feature = "chrono")]
+
+use chrono::{NaiveDate, TimeZone, Utc, DateTime};
+use serde::Deserialize;
+use shopsite_aa::de as aa;
+
+#[test]
+fn test_datetime() {
+	#[derive(Debug, Deserialize, Eq, PartialEq)]
+	struct TestDates {
+		#[serde(with = "shopsite_aa::datetime")]
+		full: DateTime<Utc>,
+		#[serde(with = "shopsite_aa::datetime")]
+		no_seconds: DateTime<Utc>,
+		#[serde(with = "shopsite_aa::datetime")]
+		date_only: DateTime<Utc>,
+		#[serde(with = "shopsite_aa::datetime::option")]
+		some: Option<DateTime<Utc>>,
+		#[serde(with = "shopsite_aa::datetime::option")]
+		none: Option<DateTime<Utc>>,
+		#[serde(with = "shopsite_aa::datetime::date")]
+		date: NaiveDate,
+		#[serde(with = "shopsite_aa::datetime::date::option")]
+		some_date: Option<NaiveDate>,
+		#[serde(with = "shopsite_aa::datetime::date::option")]
+		no_date: Option<NaiveDate>
+	}
+
+	let parsed: TestDates = aa::from_bytes(b"\
+full: 04/15/2020 13:45:06
+no_seconds: 4/5/2020 9:05
+date_only: 12/31/1999
+some: 2020-01-02 03:04:05
+none: 
+date: 07/04/2021
+some_date: 2021-07-05
+no_date:
+", None).unwrap();
+
+	assert_eq!(parsed, TestDates {
+		full: Utc.with_ymd_and_hms(2020, 4, 15, 13, 45, 6).unwrap(),
+		no_seconds: Utc.with_ymd_and_hms(2020, 4, 5, 9, 5, 0).unwrap(),
+		date_only: Utc.with_ymd_and_hms(1999, 12, 31, 0, 0, 0).unwrap(),
+		some: Some(Utc.with_ymd_and_hms(2020, 1, 2, 3, 4, 5).unwrap()),
+		none: None,
+		date: NaiveDate::from_ymd_opt(2021, 7, 4).unwrap(),
+		some_date: NaiveDate::from_ymd_opt(2021, 7, 5),
+		no_date: None
+	});
+
+	// Something that isn't a timestamp is an error, even in an optional field.
+	#[derive(Debug, Deserialize)]
+	struct TestBadDate {
+		#[serde(with = "shopsite_aa::datetime::option")]
+		#[allow(dead_code)]
+		when: Option<DateTime<Utc>>
+	}
+
+	let error = aa::from_bytes::<TestBadDate>(b"when: yesterday\n", None).unwrap_err();
+	assert!(error.to_string().contains("yesterday"), "unexpected error message: {}", error);
+}
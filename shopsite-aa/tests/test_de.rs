@@ -1,6 +1,11 @@
+#![cfg(feature = "std")]
+
 use serde::{Deserialize, Deserializer as _};
 use shopsite_aa::de as aa;
-use std::path::Path;
+use std::{
+	collections::HashMap,
+	path::Path
+};
 
 #[test]
 fn test_main() {
@@ -147,3 +152,1595 @@ fn test_whitespace_lines_are_ignored() {
 	let mut deser = aa::Deserializer::new(std::io::Cursor::new(b" \n"), None);
 	(&mut deser).deserialize_map(EmptyMapVisitor).unwrap();
 }
+
+#[test]
+fn test_bool_syntax() {
+	// This test verifies that ShopSite's own boolean spellings are understood in lenient mode, and only in lenient mode.
+
+	#[derive(Debug, Eq, PartialEq, Deserialize)]
+	struct TestBools {
+		bools: Vec<bool>
+	}
+
+	let input = b"bools: Yes|no|ON|off|checked|Unchecked|1|0|true|FALSE\n";
+
+	let mut de = aa::DeserializerBuilder::new()
+		.bool_syntax(aa::BoolSyntax::Lenient)
+		.build(&input[..], None);
+	let parsed = TestBools::deserialize(&mut de).unwrap();
+	assert_eq!(parsed.bools, vec![true, false, true, false, true, false, true, false, true, false]);
+
+	// Gibberish is still rejected in lenient mode.
+	let mut de = aa::DeserializerBuilder::new()
+		.bool_syntax(aa::BoolSyntax::Lenient)
+		.build(&b"bools: true|maybe\n"[..], None);
+	match TestBools::deserialize(&mut de) {
+		Err(aa::Error::InvalidBool { pos, .. }) => assert_eq!((pos.line, pos.column), (1, 13)),
+		other => panic!("expected an InvalidBool error, got {:?}", other)
+	}
+
+	// Strict mode is the default, and accepts only `true` and `false`.
+	assert!(aa::from_bytes::<TestBools>(b"bools: true|false\n", None).is_ok());
+	assert!(aa::from_bytes::<TestBools>(b"bools: yes\n", None).is_err());
+}
+
+#[test]
+fn test_number_syntax() {
+	// This test verifies that currency formatting is stripped from numbers in lenient mode, and only in lenient mode.
+
+	#[derive(Debug, PartialEq, Deserialize)]
+	struct TestNumbers {
+		price: f64,
+		sale_price: f32,
+		quantity: u32,
+		adjustment: i64
+	}
+
+	let input = b"price: $1,234.50\nsale_price:  $ 999.99 \nquantity: 1,000\nadjustment: -$5\n";
+
+	let mut de = aa::DeserializerBuilder::new()
+		.number_syntax(aa::NumberSyntax::Lenient)
+		.build(&input[..], None);
+	let parsed = TestNumbers::deserialize(&mut de).unwrap();
+	assert_eq!(parsed, TestNumbers {
+		price: 1234.5,
+		sale_price: 999.99,
+		quantity: 1000,
+		adjustment: -5
+	});
+
+	// Strict mode is the default, and rejects the formatting.
+	match aa::from_bytes::<TestNumbers>(&input[..], None) {
+		Err(aa::Error::InvalidFloat { pos, .. }) => assert_eq!((pos.line, pos.column), (1, 8)),
+		other => panic!("expected an InvalidFloat error, got {:?}", other)
+	}
+}
+
+#[test]
+fn test_seq_escape() {
+	// This test verifies that the escaping styles for `|` in sequence elements are decoded, and that escapes aren't recognized outside of sequences.
+
+	#[derive(Debug, PartialEq, Deserialize)]
+	struct TestEscapes {
+		options: Vec<String>,
+		path: String
+	}
+
+	let input = b"options: Red \\| Blue|Green\\\\|C:\\Temp\npath: C:\\Temp\\|x\n";
+
+	let mut de = aa::DeserializerBuilder::new()
+		.seq_escape(aa::SeqEscape::Backslash)
+		.build(&input[..], None);
+	let parsed = TestEscapes::deserialize(&mut de).unwrap();
+	assert_eq!(parsed, TestEscapes {
+		options: vec!["Red | Blue".to_string(), "Green\\".to_string(), "C:\\Temp".to_string()],
+		path: "C:\\Temp\\|x".to_string()
+	});
+
+	let input = b"options: Red &#124; Blue|Green&#x7c;Yellow|&amp;\npath: a&#124;b\n";
+
+	let mut de = aa::DeserializerBuilder::new()
+		.seq_escape(aa::SeqEscape::NumericEntity)
+		.build(&input[..], None);
+	let parsed = TestEscapes::deserialize(&mut de).unwrap();
+	assert_eq!(parsed, TestEscapes {
+		options: vec!["Red | Blue".to_string(), "Green|Yellow".to_string(), "&amp;".to_string()],
+		path: "a&#124;b".to_string()
+	});
+
+	// Without escaping, which is the default, every `|` is a separator.
+	let parsed = aa::from_bytes::<TestEscapes>(&b"options: a\\|b\npath: x\n"[..], None).unwrap();
+	assert_eq!(parsed.options, vec!["a\\".to_string(), "b".to_string()]);
+}
+
+#[test]
+fn test_nested_seq() {
+	// This test verifies that sequences inside sequences are split on commas, and that commas mean nothing special in sequences that aren't nested.
+
+	#[derive(Debug, PartialEq, Deserialize)]
+	struct TestNested {
+		options: Vec<Vec<String>>,
+		pairs: Vec<(String, u32)>,
+		flat: Vec<String>,
+		last: Vec<Vec<String>>
+	}
+
+	let input = b"options: Small,Medium,Large|Red|Blue,Green\npairs: a,1|b,2\nflat: a,b|c\nlast: x,y";
+
+	let parsed: TestNested = aa::from_bytes(&input[..], None).unwrap();
+	assert_eq!(parsed, TestNested {
+		options: vec![
+			vec!["Small".to_string(), "Medium".to_string(), "Large".to_string()],
+			vec!["Red".to_string()],
+			vec!["Blue".to_string(), "Green".to_string()]
+		],
+		pairs: vec![("a".to_string(), 1), ("b".to_string(), 2)],
+		flat: vec!["a,b".to_string(), "c".to_string()],
+		last: vec![vec!["x".to_string(), "y".to_string()]]
+	});
+
+	// The delimiter can be changed.
+	let mut de = aa::DeserializerBuilder::new()
+		.sub_seq_delimiter(b';')
+		.build(&b"options: a;b,c|d\npairs: \nflat: \nlast: \n"[..], None);
+	let parsed = TestNested::deserialize(&mut de).unwrap();
+	assert_eq!(parsed.options, vec![vec!["a".to_string(), "b,c".to_string()], vec!["d".to_string()]]);
+}
+
+#[test]
+fn test_seq_delimiter_override() {
+	// This test verifies that sequence delimiters can be overridden for some keys, leaving other keys alone.
+
+	#[derive(Debug, PartialEq, Deserialize)]
+	#[serde(rename_all = "PascalCase")]
+	struct TestOverrides {
+		keywords: Vec<String>,
+		cross_sell_skus: Vec<String>,
+		categories: Vec<String>
+	}
+
+	let input = b"Keywords: red,blue|green\nCrossSellSkus: A;B\nCategories: a,b|c\n";
+
+	let mut de = aa::DeserializerBuilder::new()
+		.seq_delimiter_override("Keywords", b',')
+		.seq_delimiter_override("Cross*Skus", b';')
+		.build(&input[..], None);
+	let parsed = TestOverrides::deserialize(&mut de).unwrap();
+	assert_eq!(parsed, TestOverrides {
+		keywords: vec!["red".to_string(), "blue|green".to_string()],
+		cross_sell_skus: vec!["A".to_string(), "B".to_string()],
+		categories: vec!["a,b".to_string(), "c".to_string()]
+	});
+}
+
+#[test]
+fn test_flatten_unknown_keys() {
+	// This test verifies that keys a struct doesn't know about can be captured with `#[serde(flatten)]`, including keys with no value.
+
+	#[derive(Debug, PartialEq, Deserialize)]
+	struct TestFlatten {
+		name: String,
+		count: u32,
+		list: Vec<String>,
+
+		#[serde(flatten)]
+		extra: HashMap<String, aa::Value>
+	}
+
+	let input = b"name: Widget\ncount: 3\nnew_in_12: a|b\nlist: x|y\nempty: \nno_value\n";
+
+	let parsed: TestFlatten = aa::from_bytes(&input[..], None).unwrap();
+	assert_eq!(parsed.name, "Widget");
+	assert_eq!(parsed.count, 3);
+	assert_eq!(parsed.list, vec!["x".to_string(), "y".to_string()]);
+	assert_eq!(parsed.extra, vec![
+		("new_in_12".to_string(), aa::Value::from("a|b")),
+		("empty".to_string(), aa::Value::from("")),
+		("no_value".to_string(), aa::Value::Unit)
+	].into_iter().collect());
+	assert_eq!(parsed.extra["new_in_12"].split().collect::<Vec<_>>(), ["a", "b"]);
+
+	// An entire file can also be read into a `Map`.
+	let map: aa::Map = aa::from_bytes(&input[..], None).unwrap();
+	assert_eq!(map.len(), 6);
+	assert_eq!(map["count"], aa::Value::from("3"));
+}
+
+#[test]
+fn test_entries() {
+	// This test verifies that entries can be read one at a time, in file order.
+
+	let input = b"# comment\nname: Widget\n\nno_value\nlist: a|b\n";
+	let mut de = aa::Deserializer::new(&input[..], None);
+
+	let entries: Vec<(String, aa::Value)> = de.entries().collect::<Result<_, _>>().unwrap();
+	assert_eq!(entries, vec![
+		("name".to_string(), aa::Value::from("Widget")),
+		("no_value".to_string(), aa::Value::Unit),
+		("list".to_string(), aa::Value::from("a|b"))
+	]);
+
+	// The iterator stops after the first error.
+	struct FailingReader;
+
+	impl std::io::Read for FailingReader {
+		fn read(&mut self, _: &mut [u8]) -> std::io::Result<usize> {
+			Err(std::io::Error::other("nope"))
+		}
+	}
+
+	let mut de = aa::Deserializer::new(std::io::BufReader::new(FailingReader), None);
+	let mut entries = de.entries();
+	assert!(matches!(entries.next(), Some(Err(aa::Error::Io { .. }))));
+	assert!(entries.next().is_none());
+}
+
+#[test]
+fn test_raw_value() {
+	// This test verifies that `RawValue`s capture the undecoded bytes of a value and where the value is.
+
+	#[derive(Debug, Deserialize)]
+	struct TestRaw {
+		name: aa::RawValue,
+		list: Vec<aa::RawValue>,
+		last: aa::RawValue
+	}
+
+	let file: std::sync::Arc<Path> = Path::new("raw.aa").into();
+	let parsed: TestRaw = aa::from_bytes(b"name: caf\xe9\r\nlist: ab|cd\nlast: x", Some(file.clone())).unwrap();
+
+	assert_eq!(parsed.name.as_bytes(), b"caf\xe9");
+	assert_eq!(parsed.name.decode(), "café");
+	assert_eq!(parsed.name.start().file.as_deref(), Some(&*file));
+	assert_eq!((parsed.name.start().line, parsed.name.start().column), (1, 7));
+	assert_eq!((parsed.name.end().line, parsed.name.end().column), (1, 11));
+
+	let list: Vec<(String, u32, u32)> = parsed.list.iter().map(|raw| (raw.decode(), raw.start().column, raw.end().column)).collect();
+	assert_eq!(list, [("ab".to_string(), 7, 9), ("cd".to_string(), 10, 12)]);
+
+	assert_eq!(parsed.last.to_string(), "x");
+	assert_eq!((parsed.last.end().line, parsed.last.end().column), (3, 8));
+}
+
+#[test]
+fn test_multiple_records() {
+	// This test verifies that files with more than one record can be split into records, on blank lines or on a sentinel key.
+
+	#[derive(Debug, PartialEq, Deserialize)]
+	struct TestRecord {
+		name: String,
+		#[serde(default)]
+		price: Option<u32>
+	}
+
+	let record = |name: &str, price| TestRecord { name: name.to_string(), price };
+
+	let input = b"\n\nname: A\r\nprice: 1\r\n\r\n# comment\r\nname: B\r\n   \r\n\r\nname: C\r\n# comment\r\nprice: 3\r\n\r\n";
+	let parsed: Vec<TestRecord> = aa::from_reader_multi(&input[..], None, aa::RecordBoundary::BlankLine).unwrap();
+	assert_eq!(parsed, vec![record("A", Some(1)), record("B", None), record("C", Some(3))]);
+
+	let input = b"name: A\nprice: 1\nname: B\n\nname: C\nprice: 3\n";
+	let parsed: Vec<TestRecord> = aa::from_reader_multi(&input[..], None, aa::RecordBoundary::Key("name".to_string())).unwrap();
+	assert_eq!(parsed, vec![record("A", Some(1)), record("B", None), record("C", Some(3))]);
+
+	// Without a boundary, the whole file is one record. Well, unless it's empty.
+	let parsed: Vec<TestRecord> = aa::from_reader_multi(&b"name: A\n\nprice: 1\n"[..], None, aa::RecordBoundary::None).unwrap();
+	assert_eq!(parsed, vec![record("A", Some(1))]);
+
+	let parsed: Vec<TestRecord> = aa::from_reader_multi(&b"\n# nothing here\n"[..], None, aa::RecordBoundary::BlankLine).unwrap();
+	assert_eq!(parsed, vec![]);
+}
+
+#[test]
+fn test_trailing_empty_element() {
+	// This test verifies that empty elements after a trailing `|` are kept or dropped as configured.
+
+	#[derive(Debug, PartialEq, Deserialize)]
+	struct TestTrailing {
+		middle: Vec<String>,
+		nested: Vec<Vec<String>>,
+		outer: Vec<Vec<String>>,
+		last: Vec<String>
+	}
+
+	let input = b"middle: a|b|\nnested: a,|b,c\nouter: a,b|\nlast: c|";
+
+	let parse = |policy| {
+		let mut de = aa::DeserializerBuilder::new()
+			.trailing_empty_element(policy)
+			.build(&input[..], None);
+		let parsed = TestTrailing::deserialize(&mut de).unwrap();
+		(parsed.middle.len(), parsed.nested.iter().map(Vec::len).collect::<Vec<_>>(), parsed.outer.iter().map(Vec::len).collect::<Vec<_>>(), parsed.last.len())
+	};
+
+	// An empty element after a trailing `|`, in a sequence of sequences, is an empty sequence.
+	assert_eq!(parse(aa::TrailingEmptyElement::Keep), (3, vec![2, 2], vec![2, 0], 2));
+	assert_eq!(parse(aa::TrailingEmptyElement::DropAtEof), (3, vec![2, 2], vec![2, 0], 1));
+	assert_eq!(parse(aa::TrailingEmptyElement::Drop), (2, vec![2, 2], vec![2], 1));
+}
+
+#[test]
+fn test_sections() {
+	// This test verifies that files divided into sections can be deserialized as a map of sections.
+
+	#[derive(Debug, Default, PartialEq, Deserialize)]
+	#[serde(default)]
+	struct TestSection {
+		name: String,
+		count: u32
+	}
+
+	#[derive(Debug, PartialEq, Deserialize)]
+	#[serde(rename_all = "PascalCase")]
+	struct TestSections {
+		#[serde(rename = "")]
+		top: TestSection,
+		first: TestSection,
+		second: TestSection
+	}
+
+	let expected = TestSections {
+		top: TestSection { name: "top".to_string(), count: 0 },
+		first: TestSection { name: "one".to_string(), count: 1 },
+		second: TestSection { name: "two".to_string(), count: 2 }
+	};
+
+	let input = b"name: top\n[First]\nname: one\ncount: 1\n[Ignored]\nname: nope\n\n[Second]\ncount: 2\nname: two\n";
+	let mut de = aa::DeserializerBuilder::new()
+		.section_syntax(aa::SectionSyntax::Brackets)
+		.build(&input[..], None);
+	assert_eq!(TestSections::deserialize(&mut de).unwrap(), expected);
+
+	let input = b"name: top\nBEGIN_First\nname: one\ncount: 1\nEND_First\nBEGIN_Empty\nEND_Empty\nBEGIN_Second\ncount: 2\nname: two\nEND_Second\n";
+	let mut de = aa::DeserializerBuilder::new()
+		.section_syntax(aa::SectionSyntax::BeginEnd)
+		.build(&input[..], None);
+	assert_eq!(TestSections::deserialize(&mut de).unwrap(), expected);
+
+	// Sections are also visible through `Value`.
+	let mut de = aa::DeserializerBuilder::new()
+		.section_syntax(aa::SectionSyntax::Any)
+		.build(&b"[A]\nx: 1\nBEGIN_B\ny: 2\nEND_B\n"[..], None);
+	let map = aa::Map::deserialize(&mut de).unwrap();
+	assert_eq!(map.keys().collect::<Vec<_>>(), ["A", "B"]);
+	assert_eq!(map["B"], aa::Value::Map(vec![("y".to_string(), aa::Value::from("2"))].into_iter().collect()));
+}
+
+#[test]
+fn test_small_read_buffer() {
+	// This test verifies that input split across many small reads parses the same as input read all at once, including line and column numbers. Input is scanned a buffer at a time, so every interesting byte needs to be able to land at the edge of a buffer.
+
+	#[derive(Debug, Deserialize, PartialEq)]
+	struct TestChunks {
+		name: String,
+		tags: Vec<String>,
+		count: u32,
+		last: aa::RawValue
+	}
+
+	let input = &b"# A comment, long enough to span several buffers\r\nname: \tsome name #not a comment\r\n   \r\n  # indented comment\ntags: a\\|b|c\nignored: value\ncount: 42\nlast: x\ty"[..];
+
+	let parse = |capacity: usize| -> TestChunks {
+		let mut de = aa::DeserializerBuilder::new()
+			.seq_escape(aa::SeqEscape::Backslash)
+			.build(std::io::BufReader::with_capacity(capacity, input), None);
+		TestChunks::deserialize(&mut de).unwrap()
+	};
+
+	let whole = parse(8192);
+	assert_eq!(whole.name, "\tsome name #not a comment");
+	assert_eq!(whole.tags, ["a|b", "c"]);
+	assert_eq!(whole.count, 42);
+	assert_eq!(whole.last.decode(), "x\ty");
+	assert_eq!((whole.last.start().line, whole.last.start().column), (8, 7));
+	assert_eq!((whole.last.end().line, whole.last.end().column), (8, 10));
+
+	for capacity in 1..=7 {
+		assert_eq!(parse(capacity), whole, "buffer capacity {}", capacity);
+	}
+
+	// Error positions are tracked correctly, too.
+	let input = &b"# comment\ncount: 1\r\ncount2: \t12x\n"[..];
+
+	#[derive(Debug, Deserialize)]
+	struct TestError {
+		#[allow(dead_code)]
+		count: u32,
+		#[allow(dead_code)]
+		count2: u32
+	}
+
+	for capacity in 1..=7 {
+		let mut de = aa::Deserializer::new(std::io::BufReader::with_capacity(capacity, input), None);
+
+		match TestError::deserialize(&mut de) {
+			Err(aa::Error::InvalidInt { pos, .. }) => assert_eq!((pos.line, pos.column), (3, 9), "buffer capacity {}", capacity),
+			other => panic!("unexpected result with buffer capacity {}: {:?}", capacity, other)
+		}
+	}
+}
+
+#[test]
+fn test_skip_unknown_keys() {
+	// This test verifies that keys that aren't fields of the struct being deserialized are skipped when asked, without getting in the way of record boundaries.
+
+	#[derive(Debug, PartialEq, Deserialize)]
+	struct TestKnown {
+		name: String,
+		#[serde(alias = "cost")]
+		price: u32
+	}
+
+	fn skipping() -> aa::DeserializerBuilder {
+		let mut builder = aa::DeserializerBuilder::new();
+		builder.skip_unknown_keys(true);
+		builder
+	}
+
+	// Values of unknown keys aren't even looked at, so it doesn't matter that this one isn't a number.
+	let input = b"id: not a number\nname: A\nunknown\ncost: 1\nextra: caf\xe9\n";
+	let parsed = TestKnown::deserialize(&mut skipping().build(&input[..], None)).unwrap();
+	assert_eq!(parsed, TestKnown { name: "A".to_string(), price: 1 });
+
+	// Skipped keys don't hide the blank lines before them, and a boundary key is never skipped, even if it isn't a field.
+	let input = b"name: A\nprice: 1\n\nid: 2\nname: B\nprice: 2\n";
+	let parsed: Vec<TestKnown> = skipping()
+		.record_boundary(aa::RecordBoundary::BlankLine)
+		.build(&input[..], None)
+		.records()
+		.collect::<aa::Result<_>>()
+		.unwrap();
+	assert_eq!(parsed.len(), 2);
+	assert_eq!(parsed[1].name, "B");
+
+	let input = b"id: 1\nname: A\nprice: 1\nid: 2\nname: B\nprice: 2\n";
+	let parsed: Vec<TestKnown> = skipping()
+		.record_boundary(aa::RecordBoundary::Key("id".to_string()))
+		.build(&input[..], None)
+		.records()
+		.collect::<aa::Result<_>>()
+		.unwrap();
+	assert_eq!(parsed.len(), 2);
+	assert_eq!(parsed[1].name, "B");
+
+	// `deny_unknown_fields` only works if unknown keys aren't skipped.
+	#[derive(Debug, Deserialize)]
+	#[serde(deny_unknown_fields)]
+	struct TestStrict {
+		#[allow(dead_code)]
+		name: String
+	}
+
+	let input = b"name: A\nbogus: 1\n";
+	let mut de = skipping().build(&input[..], None);
+	assert!(TestStrict::deserialize(&mut de).is_ok());
+}
+
+#[test]
+fn test_deny_unknown_fields() {
+	// This test verifies that `#[serde(deny_unknown_fields)]` works with the default options, which don't skip unknown keys.
+
+	#[derive(Debug, Deserialize)]
+	#[serde(deny_unknown_fields)]
+	struct TestStrict {
+		#[allow(dead_code)]
+		name: String
+	}
+
+	let input = b"name: a\nbogus: b\n";
+	let error = aa::from_bytes::<TestStrict>(&input[..], None).unwrap_err();
+	assert!(error.to_string().contains("bogus"), "{}", error);
+
+	let mut de = aa::DeserializerBuilder::new().build(&input[..], None);
+	assert!(TestStrict::deserialize(&mut de).is_err());
+
+	let mut de = aa::DeserializerBuilder::new()
+		.record_boundary(aa::RecordBoundary::Key("name".to_string()))
+		.build(&input[..], None);
+	assert!(de.records::<TestStrict>().next().unwrap().is_err());
+}
+
+#[test]
+fn test_unbuffered_reader() {
+	// This test verifies that readers that aren't `BufRead` can be deserialized from, too.
+
+	/// A reader that hands over one byte per `read` call, like a slow network connection.
+	struct Trickle<'a>(&'a [u8]);
+
+	impl std::io::Read for Trickle<'_> {
+		fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+			match (self.0.split_first(), buf.first_mut()) {
+				(Some((byte, rest)), Some(slot)) => {
+					*slot = *byte;
+					self.0 = rest;
+					Ok(1)
+				},
+				_ => Ok(0)
+			}
+		}
+	}
+
+	let input = b"name: A\r\nlist: 1|2\r\n";
+	let expected: HashMap<String, String> = vec![("name".to_string(), "A".to_string()), ("list".to_string(), "1|2".to_string())].into_iter().collect();
+
+	let parsed: HashMap<String, String> = aa::from_reader(Trickle(input), None).unwrap();
+	assert_eq!(parsed, expected);
+
+	let mut de = aa::Deserializer::from_read(Trickle(input), None);
+	assert_eq!(HashMap::<String, String>::deserialize(&mut de).unwrap(), expected);
+
+	let records: Vec<HashMap<String, String>> = aa::from_reader_multi(Trickle(b"a: 1\n\na: 2\n"), None, aa::RecordBoundary::BlankLine).unwrap();
+	assert_eq!(records.len(), 2);
+}
+
+#[test]
+fn test_send_sync() {
+	// This test verifies that deserializers and errors can be sent to other threads, as with a thread pool.
+
+	fn assert_send_sync<T: Send + Sync>() {}
+	assert_send_sync::<aa::Deserializer<&[u8]>>();
+	assert_send_sync::<aa::Error>();
+	assert_send_sync::<aa::Position>();
+	assert_send_sync::<aa::RawValue>();
+
+	let file: std::sync::Arc<Path> = Path::new("thread.aa").into();
+	let error = std::thread::spawn(move || aa::from_bytes::<HashMap<String, u32>>(b"count: x\n", Some(file)).unwrap_err())
+		.join()
+		.unwrap();
+	assert!(error.to_string().starts_with("thread.aa:1:"), "{}", error);
+}
+
+#[test]
+fn test_limits() {
+	// This test verifies that each of the limits is enforced, with the position where it was exceeded, and that input within the limits is unaffected.
+
+	#[derive(Debug, Deserialize)]
+	struct TestLimits {
+		#[allow(dead_code)]
+		name: String,
+		#[allow(dead_code)]
+		#[serde(default)]
+		list: Vec<String>
+	}
+
+	fn parse(builder: &aa::DeserializerBuilder, input: &[u8]) -> aa::Result<TestLimits> {
+		TestLimits::deserialize(&mut builder.build(input, None))
+	}
+
+	fn assert_limit(result: aa::Result<TestLimits>, expected_limit: aa::Limit, expected_line: u32) {
+		match result {
+			Err(aa::Error::LimitExceeded { limit, pos, .. }) => {
+				assert_eq!(limit, expected_limit);
+				assert_eq!(pos.line, expected_line);
+			},
+			other => panic!("expected {:?} to be exceeded, but got {:?}", expected_limit, other)
+		}
+	}
+
+	let input = b"name: abcdef\nlist: a|b|c\n";
+	let long_line = [&b"name: x\nignored: "[..], &[b'y'; 100_000][..], b"\n"].concat();
+
+	let mut builder = aa::DeserializerBuilder::new();
+	builder.max_line_length(12);
+	assert!(parse(&builder, input).is_ok());
+	assert_limit(parse(&builder, b"name: abcdefg\n"), aa::Limit::LineLength, 1);
+	// Even keys that are skipped count.
+	assert_limit(parse(&builder, &long_line), aa::Limit::LineLength, 2);
+
+	let mut builder = aa::DeserializerBuilder::new();
+	builder.max_value_size(6);
+	assert!(parse(&builder, input).is_ok());
+	assert_limit(parse(&builder, b"list: a|b\nname: abcdefg\n"), aa::Limit::ValueSize, 2);
+	assert_limit(parse(&builder, b"name: a\nlist: abcdefg|b\n"), aa::Limit::ValueSize, 2);
+
+	let mut builder = aa::DeserializerBuilder::new();
+	builder.max_seq_elements(3);
+	assert!(parse(&builder, input).is_ok());
+	assert_limit(parse(&builder, b"name: a\nlist: a|b|c|d\n"), aa::Limit::SeqElements, 2);
+
+	let mut builder = aa::DeserializerBuilder::new();
+	builder.max_keys(2);
+	assert!(parse(&builder, input).is_ok());
+	assert_limit(parse(&builder, b"name: a\nlist: a\nextra: 1\n"), aa::Limit::Keys, 3);
+
+	let error = parse(&builder, b"name: a\nlist: a\nextra: 1\n").unwrap_err();
+	assert_eq!(error.to_string(), "<unknown>:3:8: number of keys exceeds the limit of 2");
+}
+
+#[test]
+fn test_progress() {
+	// This test verifies that `bytes_read` and `position` keep up with the parser, so that they can be used to report progress.
+
+	let input = b"name: A\r\nlist: 1|2\r\n\r\nname: B\r\n";
+	let mut de = aa::DeserializerBuilder::new()
+		.record_boundary(aa::RecordBoundary::BlankLine)
+		.build(&input[..], None);
+	assert_eq!(de.bytes_read(), 0);
+	assert_eq!((de.position().line, de.position().column), (1, 1));
+
+	let mut progress = Vec::new();
+
+	while de.has_more_records().unwrap() {
+		HashMap::<String, String>::deserialize(&mut de).unwrap();
+		progress.push(de.bytes_read());
+	}
+
+	assert_eq!(progress.len(), 2);
+	assert!(progress[0] > 0 && progress[0] < progress[1], "{:?}", progress);
+	assert_eq!(progress[1], input.len() as u64);
+	assert_eq!(de.position().line, 5);
+}
+
+#[test]
+fn test_end() {
+	// This test verifies that `end` detects input left over after deserializing, and that `into_inner` gives back the reader.
+
+	let input = b"a: 1\n# comment\n\n";
+	let mut de = aa::Deserializer::new(&input[..], None);
+	assert_eq!(HashMap::<String, u32>::deserialize(&mut de).unwrap()["a"], 1);
+	de.end().unwrap();
+	assert!(de.into_inner().is_empty());
+
+	let input = b"a: 1\n\n\na: 2\n";
+	let mut de = aa::DeserializerBuilder::new()
+		.record_boundary(aa::RecordBoundary::BlankLine)
+		.build(&input[..], None);
+	HashMap::<String, u32>::deserialize(&mut de).unwrap();
+
+	match de.end() {
+		Err(aa::Error::UnexpectedText { pos }) => assert_eq!((pos.line, pos.column), (4, 1)),
+		other => panic!("expected unexpected text, but got {:?}", other)
+	}
+
+	// The rest of the record is still there to read.
+	assert_eq!(de.into_inner(), b"2\n");
+}
+
+#[test]
+fn test_reset() {
+	// This test verifies that a `Deserializer` can be reused for another input, keeping its options but nothing else.
+
+	let mut de = aa::DeserializerBuilder::new()
+		.record_boundary(aa::RecordBoundary::BlankLine)
+		.buffer_capacity(16)
+		.build(&b"a: 1\nb: 2\n\na: leftover\n"[..], Some(Path::new("first.aa").into()));
+	assert_eq!(HashMap::<String, u32>::deserialize(&mut de).unwrap().len(), 2);
+
+	// The first input is abandoned partway, with a key already read from the next record.
+	let old_reader = de.reset(&b"\nc: 3\n\nd: 4\n"[..], Some(Path::new("second.aa").into()));
+	assert_eq!(old_reader, b"leftover\n");
+	assert_eq!(de.bytes_read(), 0);
+	assert_eq!((de.position().line, de.position().column), (1, 1));
+
+	// The record boundary option is still in effect.
+	let record: HashMap<String, u32> = HashMap::deserialize(&mut de).unwrap();
+	assert_eq!(record.keys().collect::<Vec<_>>(), ["c"]);
+	assert_eq!(de.position().file.as_deref(), Some(Path::new("second.aa")));
+	assert_eq!(de.position().line, 4);
+
+	// A small capacity still works for long lines.
+	let mut de = aa::Deserializer::with_capacity(&b"name: a much longer value than sixteen bytes\n"[..], None, 16);
+	assert_eq!(HashMap::<String, String>::deserialize(&mut de).unwrap()["name"], "a much longer value than sixteen bytes");
+}
+
+#[test]
+fn test_mark_rewind() {
+	// This test verifies that parsing can go back to a mark and try again, even when the input comes through a small buffer and the first attempt read ahead into the next record.
+
+	#[derive(Debug, Deserialize, Eq, PartialEq)]
+	struct Typed {
+		count: u32
+	}
+
+	let input = b"count: 1\n\ncount: many\n# comment\n\ncount: 3\n";
+	let mut de = aa::DeserializerBuilder::new()
+		.record_boundary(aa::RecordBoundary::BlankLine)
+		.build(std::io::BufReader::with_capacity(4, &input[..]), None);
+
+	assert!(!de.rewind());
+
+	de.mark();
+	assert_eq!(Typed::deserialize(&mut de).unwrap(), Typed { count: 1 });
+	let after_first = (de.bytes_read(), de.position().line);
+	assert!(Typed::deserialize(&mut de).is_err());
+
+	// Go back and read both records as text instead. The mark stays, so this can be done twice.
+	for _ in 0..2 {
+		assert!(de.rewind());
+		assert_eq!(de.bytes_read(), 0);
+		assert_eq!(de.position().line, 1);
+		assert_eq!(HashMap::<String, String>::deserialize(&mut de).unwrap()["count"], "1");
+		assert_eq!((de.bytes_read(), de.position().line), after_first);
+		assert_eq!(HashMap::<String, String>::deserialize(&mut de).unwrap()["count"], "many");
+	}
+
+	de.unmark();
+	assert!(!de.is_marked());
+	assert_eq!(Typed::deserialize(&mut de).unwrap(), Typed { count: 3 });
+	de.end().unwrap();
+	assert_eq!(de.bytes_read(), input.len() as u64);
+}
+
+#[test]
+fn test_line_ending() {
+	// This test verifies that the deserializer notices which line endings the input uses, including a lone `\r` at the very end and line endings on comment and blank lines.
+
+	fn line_ending(input: &[u8]) -> Option<aa::LineEnding> {
+		let mut de = aa::Deserializer::new(std::io::BufReader::with_capacity(3, input), None);
+		aa::Map::deserialize(&mut de).unwrap();
+		de.line_ending()
+	}
+
+	assert_eq!(line_ending(b""), None);
+	assert_eq!(line_ending(b"a: 1"), None);
+	assert_eq!(line_ending(b"a: 1\nb: 2\n"), Some(aa::LineEnding::Lf));
+	assert_eq!(line_ending(b"a: 1\r\n\r\n# c\r\nb: 2\r\n"), Some(aa::LineEnding::CrLf));
+	assert_eq!(line_ending(b"a: 1\rb: 2\r"), Some(aa::LineEnding::Cr));
+	assert_eq!(line_ending(b"a: 1\r\nb: 2\r"), Some(aa::LineEnding::Mixed));
+	assert_eq!(line_ending(b"# c\nb: 2\r\n"), Some(aa::LineEnding::Mixed));
+
+	assert_eq!(aa::LineEnding::CrLf.as_str(), "\r\n");
+	assert_eq!(aa::LineEnding::Mixed.to_string(), "mixed");
+}
+
+#[test]
+fn test_extract() {
+	// This test verifies that `extract` finds just the keys asked for, and stops reading once it has them all.
+
+	let input = b"# Store\r\nStoreName: Caf\xe9\r\nProductCount: 5\r\nOpen\r\nEmail: a@example.com\r\nEmail: b@example.com\r\nStoreName: again\r\n";
+	let mut de = aa::Deserializer::new(&input[..], None);
+
+	let found = de.extract(&["StoreName", "Open", "Email", "Missing"]).unwrap();
+	assert_eq!(found.len(), 3);
+	assert_eq!(found["StoreName"], "Café");
+	assert_eq!(found["Open"], "");
+	assert_eq!(found["Email"], "a@example.com");
+
+	// Only the one key still wanted was looked for after that, so the parser is at the end now.
+	assert_eq!(de.bytes_read(), input.len() as u64);
+
+	let mut de = aa::Deserializer::new(&input[..], None);
+	assert_eq!(de.extract(&["StoreName", "ProductCount"]).unwrap()["ProductCount"], "5");
+	assert_eq!(de.position().line, 4);
+
+	// The rest of the input can still be read.
+	assert_eq!(de.extract(&["StoreName"]).unwrap()["StoreName"], "again");
+
+	assert!(aa::extract(&input[..], &[]).unwrap().is_empty());
+}
+
+#[test]
+fn test_tab_width() {
+	// This test verifies that tabs advance the column number to the next tab stop, according to the tab width, and that byte columns don't care.
+
+	#[derive(Deserialize)]
+	struct TestTabs {
+		name: aa::RawValue
+	}
+
+	let input = b"name: \tab\tc\n";
+
+	fn columns(tab_width: Option<u32>, input: &[u8]) -> ((u32, u32), (u32, u32)) {
+		let mut builder = aa::DeserializerBuilder::new();
+
+		if let Some(tab_width) = tab_width {
+			builder.tab_width(tab_width);
+		}
+
+		let parsed = TestTabs::deserialize(&mut builder.build(input, None)).unwrap();
+		let (start, end) = (parsed.name.start(), parsed.name.end());
+		((start.column, start.byte_column), (end.column, end.byte_column))
+	}
+
+	// The default is 8, which puts the second tab's stop at column 17.
+	assert_eq!(columns(None, input), ((7, 7), (18, 12)));
+	assert_eq!(columns(Some(4), input), ((7, 7), (14, 12)));
+	assert_eq!(columns(Some(0), input), ((7, 7), (10, 12)));
+}
+
+#[test]
+fn test_utf8_column() {
+	// This test verifies that, in UTF-8 input, the column number counts characters rather than bytes, and the byte column counts bytes.
+
+	#[derive(Deserialize)]
+	struct TestColumns {
+		name: aa::RawValue
+	}
+
+	fn columns(encoding: aa::InputEncoding, input: &[u8]) -> (u32, u32) {
+		let parsed = TestColumns::deserialize(&mut aa::DeserializerBuilder::new().encoding(encoding).build(input, None)).unwrap();
+		let end = parsed.name.end();
+		(end.column, end.byte_column)
+	}
+
+	// “Crème brûlée” is 12 characters, and 15 bytes in UTF-8. The value starts at column 7.
+	assert_eq!(columns(aa::InputEncoding::Utf8, "name: Crème brûlée\n".as_bytes()), (19, 22));
+	assert_eq!(columns(aa::InputEncoding::Utf8, "name: Crème\tbrûlée\n".as_bytes()), (23, 22));
+
+	// In Windows-1252, every byte is a character.
+	assert_eq!(columns(aa::InputEncoding::Windows1252, b"name: Cr\xe8me br\xfbl\xe9e\n"), (19, 19));
+}
+
+#[test]
+fn test_assert_aa_eq() {
+	// This test verifies that `assert_aa_eq!` ignores formatting and key order, and reports what differs.
+
+	shopsite_aa::assert_aa_eq!(b"# comment\r\nName: Widget\r\n\r\nPrice: 10\r\nTaxable\r\n", b"Taxable\nPrice: 10\nName: Widget\n");
+
+	let left = b"Color: red\nName: Widget\nPrice: 10\n";
+	let right = b"Price: 12\nName: Widget\nTaxable\n";
+
+	assert_eq!(shopsite_aa::testing::diff(&aa::from_bytes(left, None).unwrap(), &aa::from_bytes(right, None).unwrap()), [
+		aa::Change::Removed { key: "Color".to_string(), value: aa::Value::from("red") },
+		aa::Change::Modified { key: "Price".to_string(), old: aa::Value::from("10"), new: aa::Value::from("12") },
+		aa::Change::Added { key: "Taxable".to_string(), value: aa::Value::Unit }
+	]);
+
+	let message = std::panic::catch_unwind(|| shopsite_aa::assert_aa_eq!(left, right, "product {}", 7)).unwrap_err();
+	assert_eq!(message.downcast_ref::<String>().map(String::as_str), Some("\
+`.aa` data differs (- left, + right): product 7
+- Color: red
+- Price: 10
++ Price: 12
++ Taxable"));
+
+	// Sections are compared key by key, in either syntax.
+	shopsite_aa::assert_aa_eq!(b"[Shipping]\nZip: 12345\nState: NY\n", b"BEGIN_Shipping\nState: NY\nZip: 12345\nEND_Shipping\n");
+
+	let message = std::panic::catch_unwind(|| shopsite_aa::assert_aa_eq!(b"Name: Widget\n[Shipping]\nZip: 12345\n", b"Name: Widget\n[Shipping]\nZip: 54321\n[Billing]\n")).unwrap_err();
+	assert_eq!(message.downcast_ref::<String>().map(String::as_str), Some("\
+`.aa` data differs (- left, + right)
+- Shipping.Zip: 12345
++ Shipping.Zip: 54321
++ Billing: {}"));
+}
+
+#[test]
+fn test_preset() {
+	// This test verifies that presets set the options they're made of, and that options set afterward override them.
+
+	#[derive(Debug, Deserialize, PartialEq, serde::Serialize)]
+	#[serde(rename_all = "PascalCase")]
+	struct TestProduct {
+		taxable: bool,
+		price: f64,
+		tags: Vec<String>
+	}
+
+	fn parse(input: &[u8], preset: aa::Preset) -> Result<TestProduct, aa::Error> {
+		TestProduct::deserialize(&mut aa::DeserializerBuilder::new().preset(preset).build(input, None))
+	}
+
+	let exported = b"Taxable: checked\nPrice: 9.99\nTags: a&#124;b|c\n";
+	assert_eq!(parse(exported, aa::Preset::ShopSite12).unwrap().tags, ["a&#124;b", "c"]);
+	assert_eq!(parse(exported, aa::Preset::ShopSite14).unwrap(), TestProduct { taxable: true, price: 9.99, tags: vec!["a|b".to_string(), "c".to_string()] });
+
+	let hand_edited = b"Taxable: checked\nPrice: $1,234.50\nTags: a\\|b|c|";
+	assert_eq!(parse(hand_edited, aa::Preset::HandEdited).unwrap(), TestProduct { taxable: true, price: 1234.5, tags: vec!["a|b".to_string(), "c".to_string()] });
+	assert!(parse(hand_edited, aa::Preset::ShopSite14).is_err());
+
+	let mut de = aa::DeserializerBuilder::new()
+		.preset(aa::Preset::HandEdited)
+		.number_syntax(aa::NumberSyntax::Strict)
+		.build(&hand_edited[..], None);
+	assert!(TestProduct::deserialize(&mut de).is_err());
+
+	// The table of what each preset sets is public.
+	assert_eq!(aa::Preset::ShopSite14.options().seq_escape, aa::SeqEscape::NumericEntity);
+	assert_eq!(aa::Preset::HandEdited.options().encoding, None);
+	assert_eq!(aa::Preset::ShopSite12.options().sub_seq_delimiter, b',');
+	assert_eq!(aa::Preset::HandEdited.options().line_ending, aa::LineEnding::Lf);
+
+	// Presets apply to writing, too, so a file can be written the way it'll be read back.
+	let product = TestProduct { taxable: true, price: 1.5, tags: vec!["a|b".to_string(), "c".to_string()] };
+
+	let mut ser = shopsite_aa::ser::SerializerBuilder::new().preset(aa::Preset::ShopSite14).build(Vec::new());
+	serde::Serialize::serialize(&product, &mut ser).unwrap();
+	assert_eq!(ser.into_inner(), b"Taxable: true\r\nPrice: 1.5\r\nTags: a&#124;b|c\r\n");
+
+	let mut ser = shopsite_aa::ser::SerializerBuilder::new().preset(aa::Preset::HandEdited).build(Vec::new());
+	serde::Serialize::serialize(&product, &mut ser).unwrap();
+	let written = ser.into_inner();
+	assert_eq!(written, b"Taxable: true\nPrice: 1.5\nTags: a\\|b|c\n");
+	assert_eq!(parse(&written, aa::Preset::HandEdited).unwrap(), product);
+
+	// Presets go by their names.
+	for preset in aa::Preset::ALL {
+		assert_eq!(preset.to_string().parse::<aa::Preset>(), Ok(*preset));
+	}
+
+	assert_eq!("shopsite14".parse::<aa::Preset>(), Ok(aa::Preset::ShopSite14));
+	assert!("shopsite13".parse::<aa::Preset>().is_err());
+}
+
+#[test]
+fn test_error_key() {
+	// This test verifies that errors in a value say which key the value belongs to.
+
+	#[derive(Debug, Deserialize)]
+	#[allow(dead_code)]
+	struct TestProduct {
+		#[serde(rename = "Name")]
+		name: String,
+		#[serde(rename = "ProductPrice")]
+		price: u32,
+		#[serde(rename = "Tags", default)]
+		tags: Vec<u32>
+	}
+
+	let error = aa::from_bytes::<TestProduct>(b"Name: Widget\nProductPrice: 9x\n", None).unwrap_err();
+	assert_eq!(error.position().and_then(|pos| pos.key.as_deref()), Some("ProductPrice"));
+	assert_eq!(error.to_string(), "<unknown>:2:15: ProductPrice: invalid digit found in string");
+
+	// So do errors in sequence elements.
+	let error = aa::from_bytes::<TestProduct>(b"Name: Widget\nProductPrice: 1\nTags: 1|x\n", None).unwrap_err();
+	assert_eq!(error.to_string(), "<unknown>:3:9: Tags: invalid digit found in string");
+
+	// Errors from serde itself, which don't have a position, get the key at the beginning.
+	let error = aa::from_bytes::<TestProduct>(b"Name: Widget\nProductPrice\n", None).unwrap_err();
+	assert_eq!(error.to_string(), "ProductPrice: invalid type: unit value, expected u32");
+
+	// Keys in sections are named along with the section.
+	let mut de = aa::DeserializerBuilder::new()
+		.section_syntax(aa::SectionSyntax::Brackets)
+		.build(&b"[Widget]\nProductPrice: 9x\n"[..], None);
+	let error = HashMap::<String, HashMap<String, u32>>::deserialize(&mut de).unwrap_err();
+	assert_eq!(error.position().and_then(|pos| pos.key.as_deref()), Some("Widget.ProductPrice"));
+
+	// The position of a successfully parsed value doesn't have a key, though.
+	let mut de = aa::Deserializer::new(&b"a: 1\n"[..], None);
+	HashMap::<String, u32>::deserialize(&mut de).unwrap();
+	assert_eq!(de.position().key, None);
+}
+
+#[test]
+fn test_error_serialize() {
+	// This test verifies that errors and positions serialize as structs, with all of their fields, for machine-readable error reports.
+
+	#[derive(Debug, Deserialize)]
+	struct Product {
+		#[allow(dead_code)]
+		price: u32
+	}
+
+	let error = aa::from_bytes::<Product>(b"price: lots\n", Some(Path::new("product.aa").into())).unwrap_err();
+	assert_eq!(serde_json::to_value(&error).unwrap(), serde_json::json!({
+		"code": "AA0003",
+		"message": "product.aa:1:8: price: invalid digit found in string",
+		"file": "product.aa",
+		"line": 1,
+		"column": 8,
+		"byte_column": 8,
+		"key": "price"
+	}));
+
+	assert_eq!(serde_json::to_value(error.position().unwrap()).unwrap()["key"], "price");
+
+	let error = aa::from_bytes::<Product>(b"", None).unwrap_err();
+	assert_eq!(serde_json::to_value(&error).unwrap(), serde_json::json!({
+		"code": "AA0007",
+		"message": "<unknown>:1:1: missing field `price`",
+		"file": null,
+		"line": 1,
+		"column": 1,
+		"byte_column": 1,
+		"key": null
+	}));
+
+	let error = aa::from_file::<Product>(Path::new("/nonexistent/product.aa").into()).unwrap_err();
+	let report = serde_json::to_value(&error).unwrap();
+	assert_eq!(report["file"], "/nonexistent/product.aa");
+	assert_eq!(report["line"], serde_json::Value::Null);
+}
+
+#[test]
+fn test_error_kind() {
+	// This test verifies that each error has a kind with a stable code, and that codes can be parsed back into kinds.
+
+	let kind = |input: &[u8]| aa::from_bytes::<HashMap<String, u8>>(input, None).unwrap_err().kind();
+
+	assert_eq!(kind(b"a: x\n"), aa::ErrorKind::InvalidInt);
+	assert_eq!(aa::ErrorKind::InvalidInt.code(), "AA0003");
+	assert_eq!(aa::ErrorKind::InvalidInt.to_string(), "AA0003");
+	assert_eq!(kind(b"a\n"), aa::ErrorKind::Other);
+	assert_eq!(aa::from_bytes::<HashMap<String, bool>>(b"a: x\n", None).unwrap_err().code(), "AA0002");
+
+	for kind in aa::ErrorKind::ALL {
+		assert_eq!(kind.code().parse::<aa::ErrorKind>(), Ok(*kind));
+	}
+
+	assert_eq!("aa0008".parse::<aa::ErrorKind>(), Ok(aa::ErrorKind::UnexpectedBom));
+	assert!("AA9999".parse::<aa::ErrorKind>().is_err());
+}
+
+#[test]
+fn test_io_error_position() {
+	// This test verifies that I/O errors in the middle of the input say how far parsing got, such as where a download was cut off.
+
+	/// A reader that hands over some input, then fails, like a dropped connection.
+	struct CutOff<'a>(&'a [u8]);
+
+	impl std::io::Read for CutOff<'_> {
+		fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+			if self.0.is_empty() {
+				Err(std::io::Error::new(std::io::ErrorKind::ConnectionReset, "connection reset"))
+			}
+			else {
+				std::io::Read::read(&mut self.0, buf)
+			}
+		}
+	}
+
+	let file: std::sync::Arc<Path> = Path::new("download.aa").into();
+	let error = aa::from_reader::<HashMap<String, String>, _>(CutOff(b"name: Widget\nlist: a|"), Some(file.clone())).unwrap_err();
+
+	match &error {
+		aa::Error::Io { file: error_file, pos: Some(pos), .. } => {
+			assert_eq!(error_file.as_ref(), Some(&file));
+			assert_eq!((pos.line, pos.column), (2, 9));
+		},
+		other => panic!("expected an I/O error with a position, but got {:?}", other)
+	}
+
+	assert_eq!(error.to_string(), "download.aa:2:9: I/O error: connection reset");
+}
+
+#[test]
+fn test_missing_field() {
+	// This test verifies that a missing field is reported with the line of the last key read, instead of no position at all.
+
+	#[derive(Debug, Deserialize)]
+	#[allow(dead_code)]
+	struct TestProduct {
+		#[serde(rename = "Name")]
+		name: String,
+		#[serde(rename = "ProductPrice")]
+		price: u32
+	}
+
+	let file: std::sync::Arc<Path> = Path::new("products.aa").into();
+	let error = aa::from_bytes::<TestProduct>(b"# Products
+Name: Widget
+
+", Some(file)).unwrap_err();
+
+	match &error {
+		aa::Error::MissingField { field, pos: Some(pos) } => {
+			assert_eq!(*field, "ProductPrice");
+			assert_eq!(pos.line, 2);
+		},
+		other => panic!("expected a missing field with a position, but got {:?}", other)
+	}
+
+	assert_eq!(error.to_string(), "products.aa:2:1: missing field `ProductPrice`");
+
+	// With records, it's the last line of the record that's missing the field, not the first line of the next one.
+	let mut de = aa::DeserializerBuilder::new()
+		.record_boundary(aa::RecordBoundary::BlankLine)
+		.build(&b"Name: Widget
+ProductPrice: 1
+
+Name: Gadget
+
+Name: Gizmo
+ProductPrice: 2
+"[..], None);
+	TestProduct::deserialize(&mut de).unwrap();
+	let error = TestProduct::deserialize(&mut de).unwrap_err();
+	assert_eq!(error.position().map(|pos| pos.line), Some(4));
+
+	// In a section, the section is named as the key.
+	let mut de = aa::DeserializerBuilder::new()
+		.section_syntax(aa::SectionSyntax::Brackets)
+		.build(&b"[Widget]
+Name: Widget
+"[..], None);
+	let error = HashMap::<String, TestProduct>::deserialize(&mut de).unwrap_err();
+	assert_eq!(error.to_string(), "<unknown>:2:1: Widget: missing field `ProductPrice`");
+}
+
+#[test]
+fn test_defaults() {
+	// This test verifies that keys missing from the input are filled in from the defaults, and only those keys.
+
+	#[derive(Debug, Deserialize, Eq, PartialEq)]
+	struct TestProduct {
+		#[serde(rename = "Name")]
+		name: String,
+		#[serde(rename = "Price")]
+		price: u32,
+		#[serde(rename = "Taxable")]
+		taxable: bool
+	}
+
+	let defaults: aa::Map = aa::from_bytes(b"Price: 10\nTaxable: true\n", None).unwrap();
+	let input = b"Name: Widget\n\nName: Gadget\nPrice: 20\n\nPrice: 30\n";
+
+	let mut de = aa::DeserializerBuilder::new()
+		.record_boundary(aa::RecordBoundary::BlankLine)
+		.defaults(defaults)
+		.build(&input[..], None);
+
+	assert_eq!(TestProduct::deserialize(&mut de).unwrap(), TestProduct { name: "Widget".to_string(), price: 10, taxable: true });
+	assert_eq!(TestProduct::deserialize(&mut de).unwrap(), TestProduct { name: "Gadget".to_string(), price: 20, taxable: true });
+
+	// Keys that aren't in the defaults are still required.
+	match TestProduct::deserialize(&mut de) {
+		Err(aa::Error::MissingField { field: "Name", .. }) => {},
+		other => panic!("expected Name to be missing, but got {:?}", other)
+	}
+
+	// Defaults can come from TOML, too. Errors in them say which key they're for.
+	let defaults: aa::Map = toml::from_str("Price = 5\nTaxable = \"maybe\"\n").unwrap();
+
+	let error = TestProduct::deserialize(&mut aa::DeserializerBuilder::new()
+		.defaults(defaults)
+		.build(&b"Name: Widget\n"[..], None)
+	).unwrap_err();
+
+	assert_eq!(error.to_string(), "Taxable: invalid boolean “maybe”: provided string was not `true` or `false`");
+}
+
+#[test]
+fn test_os_string() {
+	// This test verifies that values can be deserialized into `OsString` and `PathBuf`, and that on Unix, their bytes can be kept exactly as they are in the file.
+
+	use std::{ffi::OsString, path::PathBuf};
+
+	#[derive(Debug, Deserialize)]
+	struct TestConfig {
+		name: OsString,
+		#[serde(deserialize_with = "shopsite_aa::path::deserialize")]
+		dir: PathBuf,
+		#[serde(default, deserialize_with = "shopsite_aa::path::option::deserialize")]
+		log: Option<PathBuf>
+	}
+
+	let input = b"name: caf\xe9\ndir: /srv/caf\xe9\nlog:\n";
+
+	let config: TestConfig = aa::from_bytes(input, None).unwrap();
+	assert_eq!(config.name, "café");
+	assert_eq!(config.dir, Path::new("/srv/café"));
+	assert_eq!(config.log, None);
+
+	let config = TestConfig::deserialize(&mut aa::DeserializerBuilder::new()
+		.os_string_value(aa::OsStringValue::Bytes)
+		.build(&input[..], None)
+	).unwrap();
+
+	#[cfg(unix)]
+	{
+		use std::os::unix::ffi::OsStrExt;
+		assert_eq!(config.name.as_bytes(), b"caf\xe9");
+		assert_eq!(config.dir.as_os_str().as_bytes(), b"/srv/caf\xe9");
+	}
+
+	#[cfg(windows)]
+	assert_eq!(config.dir, Path::new("/srv/café"));
+}
+
+#[test]
+fn test_utf8() {
+	// This test verifies that UTF-8 input can be read, byte order mark and all, and that its encoding can be detected.
+
+	#[derive(Debug, Deserialize)]
+	struct TestProduct {
+		#[serde(rename = "Café")]
+		name: String,
+		price: aa::RawValue
+	}
+
+	let input = "\u{feff}Café: Crème\nignored: x\nprice: 5 €\n".as_bytes();
+	assert_eq!(aa::detect_encoding(input).encoding, aa::InputEncoding::Utf8);
+
+	let product = TestProduct::deserialize(&mut aa::DeserializerBuilder::new()
+		.encoding(aa::InputEncoding::Utf8)
+		.build(input, None)
+	).unwrap();
+
+	assert_eq!(product.name, "Crème");
+	assert_eq!(product.price.decode(), "5 €");
+	assert_eq!(product.price.encoding(), aa::InputEncoding::Utf8);
+
+	// Read as Windows-1252, the same input is garbled, which is why detection matters. The byte order mark is still skipped.
+	let map: HashMap<String, String> = aa::from_bytes(input, None).unwrap();
+	assert_eq!(map["ignored"], "x");
+	assert!(!map.contains_key("Café"));
+	assert!(map.contains_key("CafÃ©"));
+}
+
+#[test]
+fn test_bom() {
+	// This test verifies that a byte order mark at the beginning of the input is skipped, whatever the encoding, unless it's rejected, and that one anywhere else is just part of the text.
+
+	let input = b"\xef\xbb\xbfName: Widget\n";
+
+	let map: HashMap<String, String> = aa::from_bytes(input, None).unwrap();
+	assert_eq!(map["Name"], "Widget");
+
+	let error = HashMap::<String, String>::deserialize(&mut aa::DeserializerBuilder::new()
+		.bom(aa::Bom::Reject)
+		.build(&input[..], Some(Path::new("bom.aa").into()))
+	).unwrap_err();
+
+	match &error {
+		aa::Error::UnexpectedBom { pos } => assert_eq!((pos.line, pos.column), (1, 1)),
+		other => panic!("expected UnexpectedBom, but got {:?}", other)
+	}
+	assert_eq!(error.to_string(), "bom.aa:1:1: unexpected byte order mark");
+
+	let map: HashMap<String, String> = HashMap::deserialize(&mut aa::DeserializerBuilder::new()
+		.bom(aa::Bom::Reject)
+		.build(&b"Name: \xef\xbb\xbfWidget\n"[..], None)
+	).unwrap();
+	assert_eq!(map["Name"], "\u{ef}\u{bb}\u{bf}Widget");
+}
+
+#[test]
+fn test_bom_small_buffer() {
+	// This test verifies that a byte order mark is found even if the reader's buffer only has part of it at a time.
+
+	fn build(bom: aa::Bom, input: &[u8]) -> aa::Deserializer<std::io::BufReader<&[u8]>> {
+		aa::DeserializerBuilder::new()
+			.bom(bom)
+			.build(std::io::BufReader::with_capacity(1, input), None)
+	}
+
+	let input = b"\xef\xbb\xbfname: a\n";
+
+	let mut de = build(aa::Bom::Skip, input);
+	let map = HashMap::<String, String>::deserialize(&mut de).unwrap();
+	assert_eq!(map.keys().collect::<Vec<_>>(), ["name"]);
+	assert_eq!(de.bytes_read(), input.len() as u64);
+
+	let error = HashMap::<String, String>::deserialize(&mut build(aa::Bom::Reject, input)).unwrap_err();
+	assert!(matches!(error, aa::Error::UnexpectedBom { .. }), "{:?}", error);
+
+	// A BOM that's skipped is read again after a rewind, and skipped again.
+	let mut de = build(aa::Bom::Skip, input);
+	de.mark();
+	assert_eq!(HashMap::<String, String>::deserialize(&mut de).unwrap()["name"], "a");
+	assert!(de.rewind());
+	assert_eq!(HashMap::<String, String>::deserialize(&mut de).unwrap()["name"], "a");
+
+	// Input that only starts like a BOM is read as it is.
+	for input in [&b"\xef\xbb: a\n"[..], b"\xef\xbbx: a\n", b"\xef"] {
+		let mut de = build(aa::Bom::Reject, input);
+		let map = aa::Map::deserialize(&mut de).unwrap();
+		assert_eq!(map.len(), 1, "{:?}", input);
+		assert_eq!(de.bytes_read(), input.len() as u64);
+	}
+}
+
+#[test]
+fn test_key_only_line() {
+	// This test verifies each of the ways a key with no value can be taken.
+
+	#[derive(Debug, Deserialize, PartialEq)]
+	struct Flags {
+		#[serde(default)]
+		showcart: bool,
+		#[serde(default)]
+		hidden: bool,
+		name: String
+	}
+
+	fn build(key_only_line: aa::KeyOnlyLine) -> aa::DeserializerBuilder {
+		let mut builder = aa::DeserializerBuilder::new();
+		builder.key_only_line(key_only_line);
+		builder
+	}
+
+	let input = &b"showcart\nname: Widget\n"[..];
+
+	let flags = Flags::deserialize(&mut build(aa::KeyOnlyLine::True).build(input, None)).unwrap();
+	assert_eq!(flags, Flags { showcart: true, hidden: false, name: "Widget".to_string() });
+
+	let flags = Flags::deserialize(&mut build(aa::KeyOnlyLine::Skip).build(input, None)).unwrap();
+	assert_eq!(flags, Flags { showcart: false, hidden: false, name: "Widget".to_string() });
+
+	let map: aa::Map = aa::Map::deserialize(&mut build(aa::KeyOnlyLine::EmptyString).build(input, None)).unwrap();
+	assert_eq!(map["showcart"], aa::Value::from(""));
+
+	let map: aa::Map = aa::Map::deserialize(&mut build(aa::KeyOnlyLine::UnitValue).build(input, None)).unwrap();
+	assert_eq!(map["showcart"], aa::Value::Unit);
+
+	let error = aa::Map::deserialize(&mut build(aa::KeyOnlyLine::Error).build(&b"name: Widget\n\nshowcart\n"[..], Some(Path::new("flags.aa").into()))).unwrap_err();
+	assert_eq!(error.to_string(), "flags.aa:3:1: showcart: key has no value");
+
+	// Keys with empty values, and section markers, are not keys with no value.
+	let sections: HashMap<String, HashMap<String, String>> = HashMap::deserialize(&mut build(aa::KeyOnlyLine::Error)
+		.section_syntax(aa::SectionSyntax::Brackets)
+		.build(&b"[Store]\nname:\n"[..], None)
+	).unwrap();
+	assert_eq!(sections["Store"]["name"], "");
+
+	// A skipped key can still start a new record.
+	let records: Vec<aa::Map> = build(aa::KeyOnlyLine::Skip)
+		.record_boundary(aa::RecordBoundary::BlankLine)
+		.build(&b"name: a\n\nflag\nname: b\n"[..], None)
+		.records()
+		.collect::<aa::Result<_>>()
+		.unwrap();
+	assert_eq!(records.len(), 2);
+	assert_eq!(records[1].keys().collect::<Vec<_>>(), ["name"]);
+}
+
+#[test]
+fn test_entry_seq() {
+	// This test verifies that the top level can be deserialized as a sequence of key-value pairs, keeping repeated keys and their order.
+
+	let input = b"zebra: 1\napple: 2\nmango\napple: 3\n";
+
+	let entries: Vec<(String, aa::Value)> = aa::from_bytes(input, None).unwrap();
+	assert_eq!(entries, vec![
+		("zebra".to_string(), aa::Value::String("1".to_string())),
+		("apple".to_string(), aa::Value::String("2".to_string())),
+		("mango".to_string(), aa::Value::Unit),
+		("apple".to_string(), aa::Value::String("3".to_string()))
+	]);
+
+	// Values can be typed, too.
+	let entries: Vec<(String, Option<u32>)> = aa::from_bytes(input, None).unwrap();
+	assert_eq!(entries[2], ("mango".to_string(), None));
+	assert_eq!(entries[3], ("apple".to_string(), Some(3)));
+
+	// A visitor that only wants keys still gets the right ones.
+	let keys: Vec<(String,)> = aa::from_bytes(input, None).unwrap();
+	assert_eq!(keys.iter().map(|(key,)| &key[..]).collect::<Vec<_>>(), ["zebra", "apple", "mango", "apple"]);
+
+	// Each record can be a sequence.
+	let records: Vec<Vec<(String, String)>> = aa::from_reader_multi(&b"a: 1\na: 2\n\na: 3\n"[..], None, aa::RecordBoundary::BlankLine).unwrap();
+	assert_eq!(records, vec![
+		vec![("a".to_string(), "1".to_string()), ("a".to_string(), "2".to_string())],
+		vec![("a".to_string(), "3".to_string())]
+	]);
+
+	// With sections, the entries are the sections.
+	let mut de = aa::DeserializerBuilder::new()
+		.section_syntax(aa::SectionSyntax::Brackets)
+		.build(&b"[B]\nx: 1\n[A]\nx: 2\n"[..], None);
+	let sections = Vec::<(String, HashMap<String, String>)>::deserialize(&mut de).unwrap();
+	assert_eq!(sections.iter().map(|(name, section)| (&name[..], &section["x"][..])).collect::<Vec<_>>(), [("B", "1"), ("A", "2")]);
+}
+
+#[test]
+fn test_option_in_seq() {
+	// This test verifies that empty elements of a sequence are `None` when deserializing into `Option`s, wherever in the sequence they are.
+
+	#[derive(Debug, Deserialize)]
+	struct TestOptions {
+		middle: Vec<Option<String>>,
+		ends: Vec<Option<String>>,
+		trailing: Vec<Option<u32>>,
+		nested: Vec<Vec<Option<String>>>
+	}
+
+	let options: TestOptions = aa::from_bytes(b"middle: a||b\nends: |a|\ntrailing: 1|\nnested: a,,b|,c\n", None).unwrap();
+	assert_eq!(options.middle, [Some("a".to_string()), None, Some("b".to_string())]);
+	assert_eq!(options.ends, [None, Some("a".to_string()), None]);
+	assert_eq!(options.trailing, [Some(1), None]);
+	assert_eq!(options.nested, [
+		vec![Some("a".to_string()), None, Some("b".to_string())],
+		vec![None, Some("c".to_string())]
+	]);
+
+	// An empty element at the end is still dropped, if asked.
+	let options = TestOptions::deserialize(&mut aa::DeserializerBuilder::new()
+		.trailing_empty_element(aa::TrailingEmptyElement::Drop)
+		.build(&b"middle: a\nends: |a|\ntrailing: 1|\nnested: a\n"[..], None)
+	).unwrap();
+	assert_eq!(options.ends, [None, Some("a".to_string())]);
+	assert_eq!(options.trailing, [Some(1)]);
+}
+
+#[test]
+fn test_value_conversion() {
+	// This test verifies that a file can be read into a `Map`, patched, and then turned into a struct, and that a struct turns into the same `Map` it came from.
+
+	#[derive(Debug, Deserialize, serde::Serialize, Eq, PartialEq)]
+	enum Shipping {
+		Free,
+		Flat(u32)
+	}
+
+	#[derive(Debug, Deserialize, serde::Serialize, Eq, PartialEq)]
+	struct Config {
+		title: String,
+		count: u16,
+		enabled: bool,
+		tags: Vec<String>,
+		discount: Option<u8>,
+		shipping: Shipping
+	}
+
+	let mut map: aa::Map = aa::from_bytes(b"title: My Store\ncount: 12\nenabled: true\ntags: a|b\ndiscount\nshipping: Free\n", None).unwrap();
+	map.insert("count".to_string(), aa::Value::from("13"));
+
+	let config: Config = aa::from_value(aa::Value::Map(map)).unwrap();
+	assert_eq!(config, Config {
+		title: "My Store".to_string(),
+		count: 13,
+		enabled: true,
+		tags: vec!["a".to_string(), "b".to_string()],
+		discount: None,
+		shipping: Shipping::Free
+	});
+
+	let config = Config { shipping: Shipping::Flat(5), ..config };
+	let value = aa::to_value(&config).unwrap();
+
+	match &value {
+		aa::Value::Map(map) => {
+			assert_eq!(map["count"], aa::Value::from("13"));
+			assert_eq!(map["discount"], aa::Value::Unit);
+			assert_eq!(map["tags"], aa::Value::Seq(vec![aa::Value::from("a"), aa::Value::from("b")]));
+		},
+		other => panic!("expected a map, but got {:?}", other)
+	}
+
+	assert_eq!(aa::from_value::<Config>(value).unwrap(), config);
+
+	let error = aa::from_value::<Config>(aa::Value::Map(aa::from_bytes(b"title: x\ncount: lots\n", None).unwrap())).unwrap_err();
+	assert!(error.to_string().contains("invalid integer “lots”"), "{}", error);
+}
+
+#[test]
+fn test_value_from_bytes() {
+	// Values handed over as bytes are decoded the way the file is: as Windows-1252, unless they're UTF-8.
+	let parse = |input: &[u8], encoding: aa::InputEncoding| -> aa::Map {
+		aa::Map::deserialize(&mut aa::DeserializerBuilder::new()
+			.untyped_value(aa::UntypedValue::Bytes)
+			.encoding(encoding)
+			.build(input, None)
+		).unwrap()
+	};
+
+	let map = parse(b"name: Caf\xe9 \x93Deluxe\x94\n", aa::InputEncoding::Windows1252);
+	assert_eq!(map["name"], aa::Value::from("Café “Deluxe”"));
+
+	let map = parse("name: Café “Deluxe”\n".as_bytes(), aa::InputEncoding::Utf8);
+	assert_eq!(map["name"], aa::Value::from("Café “Deluxe”"));
+}
+
+#[test]
+fn test_arbitrary_input() {
+	// This test verifies that no input, however malformed, makes the deserializer panic. It's a small, repeatable stand-in for the fuzz target in `fuzz/`: inputs are pieced together from bits of `.aa` syntax and random bytes, with a fixed seed, and parsed with various options.
+
+	/// A small pseudo-random number generator (xorshift64*), so that the inputs are the same every time.
+	struct Rng(u64);
+
+	impl Rng {
+		fn below(&mut self, n: usize) -> usize {
+			self.0 ^= self.0 >> 12;
+			self.0 ^= self.0 << 25;
+			self.0 ^= self.0 >> 27;
+			(self.0.wrapping_mul(0x2545_F491_4F6C_DD1D) % n as u64) as usize
+		}
+	}
+
+	const PIECES: &[&[u8]] = &[
+		b":", b": ", b"|", b"||", b"\n", b"\r\n", b"\r", b" ", b"\t", b"#",
+		b"[", b"]", b"[A]", b"BEGIN_A", b"END_A", b"BEGIN_", b"END_",
+		b"\\", b"\\|", b"&#124;", b"&#", b";", b"^",
+		b"a", b"key", b"1", b"-1", b"true", b"\x93", b"\xff", b"\0"
+	];
+
+	let mut rng = Rng(0x1234_5678_9ABC_DEF1);
+
+	for _ in 0..5000 {
+		let mut input = Vec::new();
+
+		for _ in 0..rng.below(30) {
+			if rng.below(5) == 0 {
+				input.push(rng.below(256) as u8);
+			}
+			else {
+				input.extend_from_slice(PIECES[rng.below(PIECES.len())]);
+			}
+		}
+
+		let mut builder = aa::DeserializerBuilder::new();
+		builder
+		.seq_escape([aa::SeqEscape::None, aa::SeqEscape::Backslash, aa::SeqEscape::NumericEntity][rng.below(3)])
+		.section_syntax([aa::SectionSyntax::None, aa::SectionSyntax::Brackets, aa::SectionSyntax::BeginEnd, aa::SectionSyntax::Any][rng.below(4)])
+		.record_boundary([aa::RecordBoundary::None, aa::RecordBoundary::BlankLine, aa::RecordBoundary::Key("key".to_string())][rng.below(3)].clone())
+		.trailing_empty_element([aa::TrailingEmptyElement::Keep, aa::TrailingEmptyElement::DropAtEof, aa::TrailingEmptyElement::Drop][rng.below(3)])
+		.sub_seq_delimiter(b'^')
+		.max_line_length(40)
+		.max_value_size(20)
+		.max_seq_elements(5)
+		.max_keys(10);
+
+		let buffer_size = 1 + rng.below(16);
+		let reader = || std::io::BufReader::with_capacity(buffer_size, &input[..]);
+
+		let _ = aa::from_bytes::<aa::Value>(&input, None);
+		let _ = aa::Map::deserialize(&mut builder.build(reader(), None));
+		let _ = HashMap::<String, Vec<(String, u8)>>::deserialize(&mut builder.build(reader(), None));
+
+		let mut de = builder.build(reader(), None);
+		for record in de.records::<aa::Map>().take(50) {
+			let _ = record;
+		}
+		let _ = de.end();
+
+		let mut de = builder.build(reader(), None);
+		for entry in de.entries().take(50) {
+			let _ = entry;
+		}
+		let _ = de.end();
+	}
+}
+
+#[test]
+fn test_size_hint() {
+	use serde::de::{MapAccess, SeqAccess, Visitor};
+
+	// Records the size hints that a map or sequence gives, before and after reading its first entry or element.
+	#[derive(Debug, Eq, PartialEq)]
+	struct Hints(Vec<Option<usize>>);
+
+	struct HintsVisitor;
+
+	impl<'de> Visitor<'de> for HintsVisitor {
+		type Value = Hints;
+
+		fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+			f.write_str("a map or a sequence")
+		}
+
+		fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Hints, A::Error> {
+			let mut hints = vec![map.size_hint()];
+
+			while let Some((_, value)) = map.next_entry::<String, Hints>()? {
+				hints.push(map.size_hint());
+				hints.extend(value.0);
+			}
+
+			Ok(Hints(hints))
+		}
+
+		fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Hints, A::Error> {
+			let mut hints = vec![seq.size_hint()];
+
+			if seq.next_element::<String>()?.is_some() {
+				hints.push(seq.size_hint());
+			}
+
+			while seq.next_element::<String>()?.is_some() {}
+			Ok(Hints(hints))
+		}
+	}
+
+	impl<'de> Deserialize<'de> for Hints {
+		fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+			deserializer.deserialize_seq(HintsVisitor)
+		}
+	}
+
+	let hints = aa::Deserializer::new(&b"a: 1|2|3\nb: \n# comment\nc: x\\|y|z\n"[..], None).deserialize_map(HintsVisitor).unwrap();
+	assert_eq!(hints, Hints(vec![
+		Some(3),
+		Some(2), Some(3), Some(2),
+		Some(1), Some(0),
+		Some(0), Some(3), Some(2)
+	]));
+
+	// Escaped delimiters aren't counted.
+	let hints = aa::DeserializerBuilder::new()
+		.seq_escape(aa::SeqEscape::Backslash)
+		.build(&b"c: x\\|y|z\n"[..], None)
+		.deserialize_map(HintsVisitor)
+		.unwrap();
+	assert_eq!(hints, Hints(vec![Some(1), Some(0), Some(2), Some(1)]));
+
+	// Only `deserialize_map` counts the keys. Other visitors of the top level, like those of structs, don't use the hint.
+	let hints = aa::Deserializer::new(&b"a: 1\nb: 2\n"[..], None).deserialize_any(HintsVisitor).unwrap();
+	assert_eq!(hints.0[0], None);
+
+	// Only the start of a big input is looked at, so the count is low, but it doesn't take a pass over the whole input.
+	let big: Vec<u8> = (0..100_000).flat_map(|n| format!("key{}: value\n", n).into_bytes()).collect();
+	let hints = aa::Deserializer::new(&big[..], None).deserialize_map(HintsVisitor).unwrap();
+	assert!(matches!(hints.0[0], Some(count) if count > 1000 && count < 10_000), "{:?}", hints.0[0]);
+}
@@ -0,0 +1,35 @@
+#![cfg(feature = "decimal")]
+
+use serde::Deserialize;
+use shopsite_aa::{
+	de as aa,
+	money::{rust_decimal::Decimal, Money}
+};
+use std::str::FromStr;
+
+#[test]
+fn test_decimal() {
+	#[derive(Debug, Deserialize, Eq, PartialEq)]
+	struct TestPrices {
+		plain: Decimal,
+		formatted: Money,
+		negative: Money,
+		list: Vec<Money>
+	}
+
+	let parsed: TestPrices = aa::from_bytes(b"plain: 0.10\nformatted: $1,234.50\nnegative: -$0.01\nlist: $1|2.5|$3,000\n", None).unwrap();
+
+	assert_eq!(parsed, TestPrices {
+		plain: Decimal::from_str("0.10").unwrap(),
+		formatted: Money(Decimal::from_str("1234.50").unwrap()),
+		negative: Money(Decimal::from_str("-0.01").unwrap()),
+		list: vec![Money(1.into()), Money(Decimal::from_str("2.5").unwrap()), Money(3000.into())]
+	});
+
+	// Exactness is the whole point.
+	assert_eq!(parsed.formatted.to_string(), "1234.50");
+	assert_eq!(parsed.plain.to_string(), "0.10");
+
+	// Gibberish is still rejected.
+	assert!(aa::from_bytes::<TestPrices>(b"plain: 1\nformatted: $lots\nnegative: 0\nlist: 1\n", None).is_err());
+}
@@ -0,0 +1,54 @@
+#![cfg(feature = "std")]
+
+use serde::Serialize;
+use shopsite_aa::de as aa;
+
+#[test]
+fn test_diff() {
+	// This test verifies that sections are compared key by key, sequences element by element, and that changes serialize with their kind.
+
+	#[derive(Serialize)]
+	struct Shipping {
+		methods: Vec<String>,
+		zip: String
+	}
+
+	#[derive(Serialize)]
+	struct Settings {
+		name: String,
+		shipping: Shipping
+	}
+
+	let old = aa::to_value(&Settings {
+		name: "Widgets".to_string(),
+		shipping: Shipping { methods: vec!["Ground".to_string(), "Air".to_string(), "Freight".to_string()], zip: "12345".to_string() }
+	}).unwrap();
+
+	let new = aa::to_value(&Settings {
+		name: "Widgets".to_string(),
+		shipping: Shipping { methods: vec!["Ground".to_string(), "Freight".to_string(), "Pickup".to_string()], zip: "54321".to_string() }
+	}).unwrap();
+
+	let changes = aa::diff(&old, &new);
+	assert_eq!(changes, [
+		aa::Change::ElementRemoved { key: "shipping.methods".to_string(), index: 1, value: aa::Value::from("Air") },
+		aa::Change::ElementAdded { key: "shipping.methods".to_string(), index: 2, value: aa::Value::from("Pickup") },
+		aa::Change::Modified { key: "shipping.zip".to_string(), old: aa::Value::from("12345"), new: aa::Value::from("54321") }
+	]);
+
+	assert!(aa::diff(&old, &old).is_empty());
+
+	assert_eq!(
+		serde_json::to_value(&changes).unwrap(),
+		serde_json::json!([
+			{ "kind": "element_removed", "key": "shipping.methods", "index": 1, "value": "Air" },
+			{ "kind": "element_added", "key": "shipping.methods", "index": 2, "value": "Pickup" },
+			{ "kind": "modified", "key": "shipping.zip", "old": "12345", "new": "54321" }
+		])
+	);
+
+	assert_eq!(changes[2].to_string(), "- shipping.zip: 12345\n+ shipping.zip: 54321");
+
+	// Values that aren't maps are compared as a whole, with an empty key.
+	assert_eq!(aa::diff(&aa::Value::from("a"), &aa::Value::Unit), [aa::Change::Modified { key: String::new(), old: aa::Value::from("a"), new: aa::Value::Unit }]);
+}
@@ -0,0 +1,135 @@
+#![cfg(feature = "std")]
+
+use shopsite_aa::{de as aa, document::Document};
+
+#[test]
+fn test_document_round_trip() {
+	// This test verifies that a `Document` reproduces its input exactly, and finds the same entries as the deserializer.
+
+	let input = include_bytes!("test.aa");
+	let doc = Document::parse(&input[..]);
+	assert_eq!(doc.to_bytes(), &input[..]);
+
+	let deserialized: Vec<(String, aa::Value)> = aa::from_bytes(input, None).unwrap();
+	assert_eq!(doc.entries().collect::<Vec<_>>(), deserialized);
+
+	let odd = b"\xef\xbb\xbf  # indented comment\r\n\r\n   \nkey:value\rflag\nlast: no line ending";
+	let doc = Document::parse(&odd[..]);
+	assert_eq!(doc.to_bytes(), &odd[..]);
+	assert_eq!(doc.lines().len(), 6);
+	assert!(doc.lines()[0].is_comment());
+	assert!(doc.lines()[1].is_blank() && doc.lines()[2].is_blank());
+	assert_eq!(doc.lines()[3].entry(), Some((&b"key"[..], Some(&b"value"[..]))));
+	assert_eq!(doc.lines()[4].entry(), Some((&b"flag"[..], None)));
+	assert_eq!(doc.lines()[5].line_ending(), None);
+	assert_eq!(doc.line_ending(), Some(aa::LineEnding::Mixed));
+	assert_eq!(doc.get("last"), Some(aa::Value::from("no line ending")));
+	assert_eq!(doc.get("missing"), None);
+}
+
+#[test]
+fn test_document_update() {
+	// This test verifies that updating a `Document` with a newer version of its file reports the keys that changed, and leaves it the same as if the new version had been parsed from scratch.
+
+	let old = b"# Settings\r\nStoreName: Widgets\r\nEmail: a@example.com\r\nOpen\r\nTax: 5\r\nTax: 6\r\nFooter: bye\r\n";
+	let new = b"# Settings, edited\r\nStoreName: Widgets\r\nEmail: b@example.com\r\nTax: 5\r\nTax: 6\r\nPhone: 555-1234\r\nFooter: bye\r\n";
+
+	let mut doc = Document::parse(&old[..]);
+	let changes = doc.update(&new[..]);
+
+	assert_eq!(changes, [
+		aa::Change::Modified { key: "Email".to_string(), old: aa::Value::from("a@example.com"), new: aa::Value::from("b@example.com") },
+		aa::Change::Removed { key: "Open".to_string(), value: aa::Value::Unit },
+		aa::Change::Added { key: "Phone".to_string(), value: aa::Value::from("555-1234") }
+	]);
+
+	assert_eq!(doc, Document::parse(&new[..]));
+	assert_eq!(doc.to_bytes(), &new[..]);
+
+	// Removing the last of a repeated key reveals the one before it.
+	let changes = doc.update(b"Tax: 5\r\n");
+	assert!(changes.contains(&aa::Change::Modified { key: "Tax".to_string(), old: aa::Value::from("6"), new: aa::Value::from("5") }));
+
+	// No change, no differences.
+	assert!(doc.update(b"Tax: 5\r\n").is_empty());
+}
+
+#[test]
+fn test_document_set() {
+	// This test verifies that setting a value rewrites only the line it's on, and adds missing keys at the end.
+
+	let input = b"# Settings\r\nStoreName:Widgets\r\nOpen\r\nTax: 5\r\nTax: 6\r\nFooter: bye\r\n";
+	let mut doc = Document::parse(&input[..]);
+
+	doc.set("StoreName", "Gadgets").unwrap();
+	doc.set("Open", "yes").unwrap();
+	doc.set("Tax", &7).unwrap();
+	doc.set("Footer", &()).unwrap();
+	doc.set("Categories", &aa::Value::Seq(vec!["a".into(), "b".into()])).unwrap();
+	assert_eq!(doc.to_bytes(), b"# Settings\r\nStoreName:Gadgets\r\nOpen: yes\r\nTax: 5\r\nTax: 7\r\nFooter\r\nCategories: a|b\r\n");
+	assert_eq!(doc, Document::parse(doc.to_bytes()));
+
+	// The same value again changes nothing.
+	let before = doc.clone();
+	doc.set("StoreName", "Gadgets").unwrap();
+	assert_eq!(doc, before);
+
+	// Without a line ending on the last line, the new last line doesn't get one either.
+	let mut doc = Document::parse(&b"a: 1\nb: 2"[..]);
+	doc.set("c", "3").unwrap();
+	assert_eq!(doc.to_bytes(), b"a: 1\nb: 2\nc: 3");
+
+	// Values are encoded in the document's encoding, and have to fit in it.
+	doc.set("d", "café").unwrap();
+	assert_eq!(doc.get("d"), Some(aa::Value::from("café")));
+	assert!(doc.to_bytes().ends_with(b"\nd: caf\xe9"));
+
+	let before = doc.clone();
+	assert!(doc.set("e", "→").is_err());
+	assert!(doc.set("f", "two\nlines").is_err());
+	assert!(doc.set("#g", "1").is_err());
+	assert_eq!(doc, before);
+}
+
+#[test]
+fn test_patch_file() {
+	// This test verifies that patching a file changes only the targeted lines, and doesn't touch the file if nothing changes.
+
+	let path = std::env::temp_dir().join(format!("shopsite-aa-test-patch-{}.aa", std::process::id()));
+	std::fs::write(&path, b"# Settings\r\nStoreName: Widgets\r\n\r\nOpen\r\n").unwrap();
+
+	aa::patch_file(&path, &[("Open", aa::Value::from("no")), ("Currency", aa::Value::from("USD"))]).unwrap();
+	assert_eq!(std::fs::read(&path).unwrap(), b"# Settings\r\nStoreName: Widgets\r\n\r\nOpen: no\r\nCurrency: USD\r\n");
+
+	// The new contents were written to a temporary file and renamed into place, keeping the file's permissions and following symbolic links. A file that happens to have the name of a temporary file is left alone.
+	let tmp = path.with_extension("aa.tmp");
+	std::fs::write(&tmp, b"unrelated").unwrap();
+	aa::patch_file(&path, &[("Open", aa::Value::from("yes"))]).unwrap();
+	assert_eq!(std::fs::read(&tmp).unwrap(), b"unrelated");
+	std::fs::remove_file(&tmp).unwrap();
+
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::PermissionsExt;
+
+		std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o640)).unwrap();
+		aa::patch_file(&path, &[("Open", aa::Value::from("no"))]).unwrap();
+		assert_eq!(std::fs::metadata(&path).unwrap().permissions().mode() & 0o777, 0o640);
+
+		let link = path.with_extension("link.aa");
+		std::os::unix::fs::symlink(&path, &link).unwrap();
+		aa::patch_file(&link, &[("Open", aa::Value::from("yes"))]).unwrap();
+		assert!(std::fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+		assert_eq!(std::fs::read(&path).unwrap(), b"# Settings\r\nStoreName: Widgets\r\n\r\nOpen: yes\r\nCurrency: USD\r\n");
+		std::fs::remove_file(&link).unwrap();
+	}
+
+	aa::patch_file(&path, &[("Open", aa::Value::from("no"))]).unwrap();
+
+	// If any change is bad, none are made.
+	assert!(aa::patch_file(&path, &[("Open", aa::Value::from("yes")), ("Bad", aa::Value::from("a\nb"))]).is_err());
+	assert_eq!(std::fs::read(&path).unwrap(), b"# Settings\r\nStoreName: Widgets\r\n\r\nOpen: no\r\nCurrency: USD\r\n");
+
+	std::fs::remove_file(&path).unwrap();
+	assert!(matches!(aa::patch_file(&path, &[]), Err(shopsite_aa::ser::Error::Io(_))));
+}
@@ -0,0 +1,74 @@
+#![cfg(feature = "std")]
+
+use serde::Deserialize;
+use shopsite_aa::{de as aa, helpers};
+
+/// Deserializes one field with a helper, from `.aa` input, from `.aa` input with `UntypedValue::Bytes`, and from JSON, and checks that all three agree.
+macro_rules! parse_field {
+	($helper:literal, $t:ty, $aa:expr, $json:expr) => {{
+		#[derive(Debug, Deserialize, PartialEq)]
+		struct Field {
+			#[serde(deserialize_with = $helper)]
+			field: $t
+		}
+
+		let from_aa: Field = aa::from_bytes($aa, None).unwrap();
+
+		let mut de = aa::DeserializerBuilder::new()
+			.untyped_value(aa::UntypedValue::Bytes)
+			.build(&$aa[..], None);
+		assert_eq!(Field::deserialize(&mut de).unwrap(), from_aa);
+
+		let from_json: Field = serde_json::from_str($json).unwrap();
+		assert_eq!(from_json, from_aa);
+
+		from_aa.field
+	}};
+}
+
+#[test]
+fn test_comma_list() {
+	assert_eq!(parse_field!("helpers::comma_list", Vec<String>, b"field: mugs, kitchen,, gifts\n", r#"{"field": "mugs, kitchen,, gifts"}"#), ["mugs", "kitchen", "gifts"]);
+	assert_eq!(parse_field!("helpers::comma_list", Vec<u32>, b"field: 1,2\n", r#"{"field": "1,2"}"#), [1, 2]);
+	assert_eq!(parse_field!("helpers::comma_list", Vec<u32>, b"field: 7\n", r#"{"field": 7}"#), [7]);
+	assert!(parse_field!("helpers::comma_list", Vec<String>, b"field:\n", r#"{"field": ""}"#).is_empty());
+}
+
+#[test]
+fn test_yes_no() {
+	assert!(parse_field!("helpers::yes_no", bool, b"field: Yes\n", r#"{"field": true}"#));
+	assert!(!parse_field!("helpers::yes_no", bool, b"field: unchecked\n", r#"{"field": "no"}"#));
+	assert!(!parse_field!("helpers::yes_no", bool, b"field\n", r#"{"field": null}"#));
+}
+
+#[test]
+fn test_currency() {
+	assert_eq!(parse_field!("helpers::currency", f64, b"field: $1,234.50\n", r#"{"field": "$1,234.50"}"#), 1234.5);
+	assert_eq!(parse_field!("helpers::currency", f64, b"field: 12.5\n", r#"{"field": 12.5}"#), 12.5);
+	assert_eq!(parse_field!("helpers::currency", f64, b"field: 3\n", r#"{"field": 3}"#), 3.0);
+
+	#[derive(Debug, Deserialize)]
+	struct Currency {
+		#[serde(deserialize_with = "helpers::currency")]
+		_field: f64
+	}
+
+	assert!(aa::from_bytes::<Currency>(b"_field: cheap\n", None).is_err());
+	assert!(serde_json::from_str::<Currency>(r#"{"_field": "cheap"}"#).is_err());
+	assert!(serde_json::from_str::<Currency>(r#"{"_field": true}"#).is_err());
+}
+
+#[test]
+fn test_percent() {
+	assert_eq!(parse_field!("helpers::percent", f64, b"field: 7.5%\n", r#"{"field": "7.5 %"}"#), 7.5);
+	assert_eq!(parse_field!("helpers::percent", f64, b"field: 7.5\n", r#"{"field": 7.5}"#), 7.5);
+	assert_eq!(parse_field!("helpers::percent", f64, b"field: -2\n", r#"{"field": -2}"#), -2.0);
+}
+
+#[test]
+fn test_html_string() {
+	assert_eq!(parse_field!("helpers::html_string", String, b"field: Caf&eacute; &amp; Bar\n", r#"{"field": "Caf&eacute; &amp; Bar"}"#), "Café & Bar");
+
+	// Bytes that aren't UTF-8 are Windows-1252.
+	assert_eq!(parse_field!("helpers::html_string", String, b"field: Caf\xe9 &#8220;Bar&#8221;\n", r#"{"field": "Café “Bar”"}"#), "Café “Bar”");
+}
@@ -0,0 +1,66 @@
+#![cfg(feature = "html-entities")]
+
+use serde::Deserialize;
+use shopsite_aa::de as aa;
+
+#[test]
+fn test_html_entities() {
+	#[derive(Debug, Deserialize)]
+	struct Product {
+		name: String,
+		options: Vec<String>,
+		symbol: char,
+		raw: aa::RawValue
+	}
+
+	const INPUT: &[u8] = b"name: Fish &amp; Chips &#8220;Deluxe&#8221;\noptions: Salt &amp; Vinegar|Caf&eacute;\nsymbol: &copy;\nraw: Fish &amp; Chips\nR&amp;D: yes\n";
+
+	// Entities are left alone by default.
+	let map: aa::Map = aa::from_bytes(INPUT, None).unwrap();
+	assert_eq!(map.get("name"), Some(&aa::Value::from("Fish &amp; Chips &#8220;Deluxe&#8221;")));
+
+	let mut de = aa::DeserializerBuilder::new()
+		.html_entities(aa::HtmlEntities::Decode)
+		.build(INPUT, None);
+
+	let product = Product::deserialize(&mut de).unwrap();
+	assert_eq!(product.name, "Fish & Chips “Deluxe”");
+	assert_eq!(product.options, ["Salt & Vinegar", "Café"]);
+	assert_eq!(product.symbol, '©');
+
+	// The raw value is still available as it's written in the file.
+	assert_eq!(product.raw.decode(), "Fish &amp; Chips");
+
+	// Keys aren't decoded.
+	let map = aa::Map::deserialize(&mut aa::DeserializerBuilder::new()
+		.html_entities(aa::HtmlEntities::Decode)
+		.build(INPUT, None)
+	).unwrap();
+	assert_eq!(map.get("R&amp;D"), Some(&aa::Value::from("yes")));
+	assert_eq!(map.get("raw"), Some(&aa::Value::from("Fish & Chips")));
+}
+
+#[test]
+fn test_html_entities_and_punctuation() {
+	// Punctuation written as a character reference is normalized along with the rest, since references are decoded first.
+	const INPUT: &[u8] = b"name: &#8220;Deluxe&#8221; \x93Fish\x94 &amp; Chips&#8230;\n";
+
+	let map = aa::Map::deserialize(&mut aa::DeserializerBuilder::new()
+		.html_entities(aa::HtmlEntities::Decode)
+		.punctuation(aa::Punctuation::Ascii)
+		.build(INPUT, None)
+	).unwrap();
+	assert_eq!(map.get("name"), Some(&aa::Value::from("\"Deluxe\" \"Fish\" & Chips...")));
+
+	#[derive(Debug, Deserialize)]
+	struct Product {
+		name: String
+	}
+
+	let product = Product::deserialize(&mut aa::DeserializerBuilder::new()
+		.html_entities(aa::HtmlEntities::Decode)
+		.punctuation(aa::Punctuation::Ascii)
+		.build(INPUT, None)
+	).unwrap();
+	assert_eq!(product.name, "\"Deluxe\" \"Fish\" & Chips...");
+}
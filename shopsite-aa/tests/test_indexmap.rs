@@ -0,0 +1,16 @@
+#![cfg(feature = "indexmap")]
+
+use shopsite_aa::de as aa;
+
+#[test]
+fn test_map_order() {
+	// This test verifies that `Map` keeps keys in file order, and that they stay that way through `to_value` and `from_value`.
+
+	let map: aa::Map = aa::from_bytes(b"zebra: 1\napple: 2\nmango\n", None).unwrap();
+	assert_eq!(map.keys().collect::<Vec<_>>(), ["zebra", "apple", "mango"]);
+
+	let value = aa::to_value(&map).unwrap();
+	let map: aa::Map = aa::from_value(value).unwrap();
+	assert_eq!(map.keys().collect::<Vec<_>>(), ["zebra", "apple", "mango"]);
+	assert_eq!(map["mango"], aa::Value::Unit);
+}
@@ -0,0 +1,72 @@
+#![cfg(feature = "std")]
+
+use shopsite_aa::de as aa;
+
+fn map(entries: &[(&str, aa::Value)]) -> aa::Value {
+	aa::Value::Map(entries.iter().cloned().map(|(key, value)| (key.to_string(), value)).collect())
+}
+
+#[test]
+fn test_merge() {
+	// This test verifies that changes from each side are combined, that sections are merged key by key, and that conflicting changes are reported and resolved in favor of `ours`.
+
+	let base = map(&[
+		("Kept", "1".into()),
+		("RemovedByUs", "2".into()),
+		("RemovedByThem", "3".into()),
+		("RemovedByBoth", "4".into()),
+		("ChangedByBoth", "5".into()),
+		("ChangedAlike", "6".into()),
+		("RemovedVsChanged", "7".into()),
+		("Shipping", map(&[("Zip", "12345".into()), ("Method", "Ground".into())]))
+	]);
+
+	let ours = map(&[
+		("Kept", "1".into()),
+		("RemovedByThem", "3".into()),
+		("ChangedByBoth", "ours".into()),
+		("ChangedAlike", "same".into()),
+		("AddedByUs", aa::Value::Unit),
+		("Shipping", map(&[("Zip", "54321".into()), ("Method", "Ground".into())]))
+	]);
+
+	let theirs = map(&[
+		("Kept", "1".into()),
+		("RemovedByUs", "2".into()),
+		("ChangedByBoth", "theirs".into()),
+		("ChangedAlike", "same".into()),
+		("RemovedVsChanged", "changed".into()),
+		("AddedByThem", "new".into()),
+		("Shipping", map(&[("Zip", "12345".into()), ("Method", "Air".into())]))
+	]);
+
+	let merged = aa::merge(&base, &ours, &theirs);
+
+	assert_eq!(merged.value, map(&[
+		("Kept", "1".into()),
+		("ChangedByBoth", "ours".into()),
+		("ChangedAlike", "same".into()),
+		("AddedByUs", aa::Value::Unit),
+		("AddedByThem", "new".into()),
+		("Shipping", map(&[("Zip", "54321".into()), ("Method", "Air".into())]))
+	]));
+
+	let mut conflicts = merged.conflicts.clone();
+	conflicts.sort_by(|a, b| a.key.cmp(&b.key));
+	assert_eq!(conflicts, [
+		aa::Conflict { key: "ChangedByBoth".to_string(), base: Some("5".into()), ours: Some("ours".into()), theirs: Some("theirs".into()) },
+		aa::Conflict { key: "RemovedVsChanged".to_string(), base: Some("7".into()), ours: None, theirs: Some("changed".into()) }
+	]);
+	assert!(!merged.is_clean());
+	assert_eq!(conflicts[1].to_string(), "RemovedVsChanged: was \"7\", ours is missing, theirs is \"changed\"");
+
+	// Merging with no changes on one side gives the other side.
+	let merged = aa::merge(&base, &base, &theirs);
+	assert!(merged.is_clean());
+	assert_eq!(merged.value, theirs);
+
+	// Sections added on both sides are merged too.
+	let merged = aa::merge(&map(&[]), &map(&[("S", map(&[("a", "1".into())]))]), &map(&[("S", map(&[("b", "2".into())]))]));
+	assert!(merged.is_clean());
+	assert_eq!(merged.value, map(&[("S", map(&[("a", "1".into()), ("b", "2".into())]))]));
+}
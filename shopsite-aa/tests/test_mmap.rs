@@ -0,0 +1,28 @@
+#![cfg(feature = "mmap")]
+
+use serde::Deserialize;
+use shopsite_aa::de as aa;
+use std::{fs, path::Path, sync::Arc};
+
+#[derive(Debug, Deserialize, PartialEq)]
+struct TestMmap {
+	name: String,
+	list: Vec<u32>
+}
+
+#[test]
+fn test_from_mmap() {
+	let path: Arc<Path> = std::env::temp_dir().join(format!("shopsite-aa-test-mmap-{}.aa", std::process::id())).into();
+	fs::write(&path, b"# comment\r\nname: caf\xe9\r\nlist: 1|2|3\r\n").unwrap();
+
+	let result: aa::Result<TestMmap> = unsafe { aa::from_mmap(path.clone()) };
+	fs::remove_file(&path).unwrap();
+	assert_eq!(result.unwrap(), TestMmap { name: "café".to_string(), list: vec![1, 2, 3] });
+
+	// Errors mention the file.
+	let missing: Arc<Path> = Path::new("/nonexistent/shopsite-aa-test-mmap.aa").into();
+	match unsafe { aa::from_mmap::<TestMmap>(missing.clone()) } {
+		Err(aa::Error::Io { file, .. }) => assert_eq!(file, Some(missing)),
+		other => panic!("unexpected result: {:?}", other)
+	}
+}
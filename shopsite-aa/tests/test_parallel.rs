@@ -0,0 +1,48 @@
+#![cfg(feature = "parallel")]
+
+use serde::Deserialize;
+use shopsite_aa::de as aa;
+use std::{fs, path::PathBuf};
+
+#[test]
+fn test_parse_files_parallel() {
+	#[derive(Debug, Deserialize, PartialEq)]
+	struct TestFile {
+		n: u32
+	}
+
+	let dir = std::env::temp_dir().join(format!("shopsite-aa-test-parallel-{}", std::process::id()));
+	fs::create_dir_all(&dir).unwrap();
+
+	let mut paths: Vec<PathBuf> = (0..20u32).map(|n| {
+		let path = dir.join(format!("{}.aa", n));
+		fs::write(&path, format!("n: {}\n", n)).unwrap();
+		path
+	}).collect();
+
+	paths.push(dir.join("missing.aa"));
+
+	// Results come back in the same order as the paths, errors and all.
+	let results: Vec<(PathBuf, aa::Result<TestFile>)> = aa::parse_files_parallel(&paths, |path, result| (path.to_owned(), result));
+
+	// Untyped.
+	let maps = aa::parse_files_parallel(&paths[..2], |_, result: aa::Result<aa::Map>| result.unwrap());
+
+	fs::remove_dir_all(&dir).unwrap();
+
+	assert_eq!(maps[1].get("n"), Some(&aa::Value::from("1")));
+
+	assert_eq!(results.len(), 21);
+
+	for (n, (path, result)) in results.iter().enumerate() {
+		assert_eq!(path, &paths[n]);
+
+		match result {
+			Ok(file) => assert_eq!(file.n, n as u32),
+			Err(aa::Error::Io { file, .. }) => assert_eq!(file.as_deref(), Some(&*paths[20])),
+			Err(error) => panic!("unexpected error: {}", error)
+		}
+	}
+
+	assert!(results[20].1.is_err());
+}
@@ -0,0 +1,73 @@
+#![cfg(feature = "query")]
+
+use shopsite_aa::query::Query;
+use std::collections::BTreeMap;
+
+fn record(fields: &[(&str, &str)]) -> BTreeMap<String, String> {
+	fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+}
+
+#[test]
+fn test_query_matches() {
+	let widget = record(&[("SKU", "W-100"), ("Name", "Blue Widget"), ("Price", "$1,004.99"), ("Taxable", "yes")]);
+	let gadget = record(&[("SKU", "G-7"), ("Name", "Gadget \"Pro\""), ("Price", "9.5"), ("Color", "red")]);
+
+	let cases: &[(&str, bool, bool)] = &[
+		("Price > 10", true, false),
+		("Price = 9.50", false, true),
+		("Price >= 1004.99 and Price <= $1,004.99", true, false),
+		("Name contains WIDGET", true, false),
+		(r#"Name = 'Gadget "Pro"'"#, false, true),
+		(r#"Name = "Gadget \"Pro\"""#, false, true),
+		("SKU ~ '^W-\\d+$'", true, false),
+		("SKU !~ '^W-'", false, true),
+		("Color", false, true),
+		("Color != red", true, false),
+		("not Color && (Taxable = yes || Price < 1)", true, false),
+		("NOT Taxable OR Name contains blue", true, true),
+		("!Taxable", false, true),
+		("SKU < H", false, true)
+	];
+
+	for (text, widget_matches, gadget_matches) in cases {
+		let query: Query = text.parse().unwrap_or_else(|error| panic!("{}: {}", text, error));
+		assert_eq!(query.matches(&widget), *widget_matches, "{} (widget)", text);
+		assert_eq!(query.matches(&gadget), *gadget_matches, "{} (gadget)", text);
+	}
+}
+
+#[test]
+fn test_query_errors() {
+	let cases: &[(&str, usize)] = &[
+		("", 0),
+		("Price >", 7),
+		("(Price > 1", 10),
+		("Price > 1 Name", 10),
+		("Name = 'oops", 7),
+		("SKU ~ '('", 6),
+		("Name # 1", 5)
+	];
+
+	for (text, offset) in cases {
+		match Query::parse(text) {
+			Ok(_) => panic!("{}: expected an error", text),
+			Err(error) => assert_eq!(error.offset, *offset, "{}: {}", text, error)
+		}
+	}
+}
+
+#[test]
+fn test_query_nested_too_deeply() {
+	let parens = "(".repeat(100_000);
+	let nots = "not ".repeat(100_000) + "Color";
+
+	for text in &[parens, nots] {
+		match Query::parse(text) {
+			Ok(_) => panic!("expected an error"),
+			Err(error) => assert_eq!(error.message, "query is nested too deeply")
+		}
+	}
+
+	let ok = format!("{}Color{}", "(".repeat(50), ")".repeat(50));
+	assert!(Query::parse(&ok).is_ok());
+}
@@ -0,0 +1,132 @@
+#![cfg(feature = "std")]
+
+use proptest::{collection, prelude::*};
+use serde::{Deserialize, Serialize};
+use shopsite_aa::{de as aa, ser, value::Entries};
+
+/// Keys that can be written and read back: not blank, not a comment, not a section header, and without a `:` or line break.
+fn key() -> impl Strategy<Value = String> {
+	"[A-Za-z0-9_][A-Za-z0-9_ .()\\-]{0,15}"
+}
+
+/// Text made of characters that Windows-1252 has, without line breaks. Leading and trailing spaces are included on purpose, since they're kept.
+fn windows_1252_text() -> impl Strategy<Value = String> {
+	"[\t -~\u{a0}-\u{ff}€‚ƒ„…†‡ˆ‰Š‹ŒŽ‘’“”•–—˜™š›œžŸ]{0,20}"
+}
+
+/// Any text without line breaks.
+fn utf8_text() -> impl Strategy<Value = String> {
+	"[^\r\n]{0,20}"
+}
+
+fn map(text: impl Strategy<Value = String>) -> impl Strategy<Value = aa::Map> {
+	let value = prop_oneof![
+		Just(aa::Value::Unit),
+		text.prop_map(aa::Value::String)
+	];
+
+	collection::btree_map(key(), value, 0..8).prop_map(|map| map.into_iter().collect())
+}
+
+/// A sequence of text and `Unit`s, maybe with sequences in it, none of which have a delimiter in them.
+fn seq() -> impl Strategy<Value = aa::Value> {
+	let text = "[^\r\n|,]{0,8}".prop_map(aa::Value::String);
+	let element = prop_oneof![Just(aa::Value::Unit), text.clone()];
+	let inner = collection::vec(prop_oneof![Just(aa::Value::Unit), text], 1..4).prop_map(aa::Value::Seq);
+
+	collection::vec(prop_oneof![element, inner], 0..6).prop_map(aa::Value::Seq)
+}
+
+/// What a value reads back as, after it's written. `.aa` files don't say what's a sequence, so a sequence reads back as the text it was written as.
+fn as_read(value: &aa::Value) -> aa::Value {
+	fn join(elements: &[aa::Value], delimiter: &str) -> String {
+		elements.iter().map(|element| match element {
+			aa::Value::Seq(inner) => join(inner, ","),
+			other => other.as_str().unwrap_or_default().to_string()
+		}).collect::<Vec<_>>().join(delimiter)
+	}
+
+	match value {
+		aa::Value::Seq(elements) => aa::Value::String(join(elements, "|")),
+		other => other.clone()
+	}
+}
+
+proptest! {
+	/// Text and `Unit`s read back exactly as they were written, with the default options.
+	#[test]
+	fn test_round_trip_windows_1252(map in map(windows_1252_text())) {
+		let bytes = ser::to_bytes(&map).unwrap();
+		let read: aa::Map = aa::from_bytes(&bytes, None).unwrap();
+		prop_assert_eq!(read, map);
+	}
+
+	/// The same goes for any text at all, in UTF-8.
+	#[test]
+	fn test_round_trip_utf8(map in map(utf8_text())) {
+		let mut serializer = ser::SerializerBuilder::new().encoding(aa::InputEncoding::Utf8).build(Vec::new());
+		map.serialize(&mut serializer).unwrap();
+		let bytes = serializer.into_inner();
+
+		let mut de = aa::DeserializerBuilder::new().encoding(aa::InputEncoding::Utf8).build(&bytes[..], None);
+		prop_assert_eq!(aa::Map::deserialize(&mut de).unwrap(), map);
+	}
+
+	/// Sequences read back as text, joined with `|`, and with `,` in nested sequences.
+	#[test]
+	fn test_round_trip_seq(seq in seq()) {
+		let mut map = aa::Map::new();
+		map.insert("seq".to_string(), seq.clone());
+
+		let mut serializer = ser::SerializerBuilder::new().encoding(aa::InputEncoding::Utf8).build(Vec::new());
+		map.serialize(&mut serializer).unwrap();
+		let bytes = serializer.into_inner();
+
+		let mut de = aa::DeserializerBuilder::new().encoding(aa::InputEncoding::Utf8).build(&bytes[..], None);
+		let read = aa::Map::deserialize(&mut de).unwrap();
+		prop_assert_eq!(&read["seq"], &as_read(&seq));
+	}
+
+	/// Sequences of text, read back as sequences, come back the same, even with `|` and `\` in them, as long as both sides escape them the same way. The one exception is a sequence of one empty element, which is written the same as an empty sequence.
+	#[test]
+	fn test_round_trip_typed_seq(items in collection::vec("[^\r\n]{0,8}", 0..6)) {
+		#[derive(Debug, Deserialize, PartialEq, Serialize)]
+		struct Items {
+			items: Vec<String>
+		}
+
+		let mut serializer = ser::SerializerBuilder::new()
+			.encoding(aa::InputEncoding::Utf8)
+			.seq_escape(aa::SeqEscape::Backslash)
+			.build(Vec::new());
+		Items { items: items.clone() }.serialize(&mut serializer).unwrap();
+		let bytes = serializer.into_inner();
+
+		let mut de = aa::DeserializerBuilder::new()
+			.encoding(aa::InputEncoding::Utf8)
+			.seq_escape(aa::SeqEscape::Backslash)
+			.build(&bytes[..], None);
+		let read = Items::deserialize(&mut de).unwrap();
+
+		let expected = if items == [""] { Vec::new() } else { items };
+		prop_assert_eq!(read.items, expected);
+	}
+}
+
+/// Files written the way ShopSite writes them are written back byte for byte, and files that aren't are written back the way ShopSite would write them. Comments and blank lines are dropped, since `Entries` doesn't have them; see `SerializerBuilder::comments_from` for keeping them.
+#[test]
+fn test_golden_files() {
+	fn rewrite(input: &[u8]) -> Vec<u8> {
+		let entries: Entries = aa::from_bytes(input, None).unwrap();
+		ser::to_bytes(&entries).unwrap()
+	}
+
+	for input in [&include_bytes!("golden/store.aa")[..], &include_bytes!("golden/products.aa")[..]] {
+		assert_eq!(rewrite(input), input);
+	}
+
+	assert_eq!(rewrite(include_bytes!("golden/hand_edited.aa")), &include_bytes!("golden/hand_edited.expected.aa")[..]);
+
+	// Rewriting changes how the file looks, but not what it says.
+	shopsite_aa::assert_aa_eq!(rewrite(include_bytes!("golden/hand_edited.aa")), include_bytes!("golden/hand_edited.aa"));
+}
@@ -0,0 +1,39 @@
+#![cfg(feature = "std")]
+
+use shopsite_aa::{de as aa, scan};
+
+#[test]
+fn test_scan_matches_deserializer() {
+	// This test verifies that the `scan` module finds the same entries in a file as the deserializer does.
+
+	let input = include_bytes!("test.aa");
+
+	let scanned: Vec<(String, aa::Value)> = scan::entries(input).map(|entry| (
+		scan::decode_windows_1252(entry.key),
+		entry.value.map_or(aa::Value::Unit, |value| aa::Value::String(scan::decode_windows_1252(value)))
+	)).collect();
+
+	let deserialized: Vec<(String, aa::Value)> = aa::from_bytes(input, None).unwrap();
+	assert_eq!(scanned, deserialized);
+}
+
+#[test]
+fn test_elements_match_deserializer() {
+	// This test verifies that `scan::elements` splits a sequence the same way the deserializer does, with and without escapes.
+
+	use serde::Deserialize;
+
+	let input = b"options: Red \\| Blue|Green\\\\|C:\\Temp||\n";
+
+	for (seq_escape, escape) in [(aa::SeqEscape::None, None), (aa::SeqEscape::Backslash, Some(b'\\'))] {
+		let value = scan::entries(input).next().unwrap().value.unwrap();
+		let scanned: Vec<String> = scan::elements(value, b'|', escape).map(|element| scan::decode_windows_1252(&element)).collect();
+
+		let mut de = aa::DeserializerBuilder::new()
+			.seq_escape(seq_escape)
+			.build(&input[..], None);
+		let mut deserialized = std::collections::HashMap::<String, Vec<String>>::deserialize(&mut de).unwrap();
+
+		assert_eq!(scanned, deserialized.remove("options").unwrap());
+	}
+}
@@ -0,0 +1,163 @@
+#![cfg(feature = "std")]
+
+use shopsite_aa::{
+	de as aa,
+	document::Document,
+	schema::{self, KeySchema, Schema, Type, Violation, ViolationKind}
+};
+
+fn kinds(violations: &[Violation]) -> Vec<(&str, &ViolationKind)> {
+	violations.iter().map(|violation| (violation.key.as_str(), &violation.kind)).collect()
+}
+
+#[test]
+fn test_schema_validate() {
+	// This test verifies that each type accepts what it should and rejects what it shouldn't, and that missing and unknown keys are reported.
+
+	let mut schema = Schema::new();
+	schema
+		.required("Name", Type::Text)
+		.required("Quantity", Type::Int)
+		.optional("Price", Type::Number)
+		.optional("Taxable", Type::Bool)
+		.optional("Theme", Type::one_of(["Classic", "Modern"]))
+		.optional("Sizes", Type::list(Type::one_of(["S", "M", "L"])))
+		.optional("Grid", Type::list(Type::list(Type::Int)))
+		.optional("Extra", Type::Any);
+
+	let good: aa::Value = aa::from_bytes(b"Name: Mug\nQuantity: 3\nPrice: $1,234.50\nTaxable\nTheme: Modern\nSizes: S|L\nGrid: 1,2|3\nExtra: anything\nUnknown: fine\n", None).unwrap();
+	assert_eq!(schema::validate(&good, &schema), []);
+
+	// Empty lists are fine, and so are lists from `to_value`.
+	let good: aa::Value = aa::from_bytes(b"Name: Mug\nQuantity: 3\nTaxable: no\nSizes: \n", None).unwrap();
+	assert_eq!(schema::validate(&good, &schema), []);
+
+	let mut map = aa::Map::new();
+	map.insert("Name".to_string(), "Mug".into());
+	map.insert("Quantity".to_string(), "3".into());
+	map.insert("Sizes".to_string(), aa::Value::Seq(vec!["M".into(), "XL".into()]));
+	assert_eq!(kinds(&schema::validate(&aa::Value::Map(map), &schema)), [
+		("Sizes", &ViolationKind::WrongType { expected: Type::list(Type::one_of(["S", "M", "L"])), found: aa::Value::Seq(vec!["M".into(), "XL".into()]) })
+	]);
+
+	let bad: aa::Value = aa::from_bytes(b"Quantity: 3.5\nPrice: cheap\nTaxable: maybe\nTheme: classic\nSizes: S|XL\nGrid: 1,x\n", None).unwrap();
+	let violations = schema::validate(&bad, &schema);
+	let mut keys: Vec<&str> = violations.iter().map(|violation| violation.key.as_str()).collect();
+	keys.sort();
+	assert_eq!(keys, ["Grid", "Name", "Price", "Quantity", "Sizes", "Taxable", "Theme"]);
+	assert_eq!(violations.last().unwrap().kind, ViolationKind::Missing);
+	assert!(violations.iter().all(|violation| violation.position.is_none()));
+
+	// Unknown keys, if denied.
+	schema.deny_unknown_keys(true);
+	let unknown: aa::Value = aa::from_bytes(b"Name: Mug\nQuantity: 3\nColour: Blue\n", None).unwrap();
+	assert_eq!(kinds(&schema::validate(&unknown, &schema)), [("Colour", &ViolationKind::Unknown)]);
+}
+
+#[test]
+fn test_schema_validate_document() {
+	// This test verifies that violations found in a `Document` have positions.
+
+	let mut schema = Schema::new();
+	schema.required("Name", Type::Text).optional("Quantity", Type::Int);
+
+	let doc = Document::parse(b"# Product\nQuantity: 1\nQuantity:  lots\n");
+	let violations = schema::validate_document(&doc, &schema, Some(std::path::Path::new("product.aa").into()));
+	let messages: Vec<String> = violations.iter().map(ToString::to_string).collect();
+
+	assert_eq!(messages, [
+		"product.aa:3:11: Quantity: expected a whole number, found \" lots\"",
+		"Name: required key is missing"
+	]);
+}
+
+#[test]
+fn test_schema_files() {
+	// This test verifies that schemas can be read from TOML and JSON, and written back out.
+
+	let toml_schema: Schema = toml::from_str(r#"
+		deny_unknown_keys = true
+
+		[keys]
+		Tax = "number"
+		StoreName = { type = "text", required = true }
+		Theme = { type = "enum", values = ["Classic", "Modern"] }
+		PaymentMethods = { type = "list", of = "text" }
+		Anything = { type = "list" }
+	"#).unwrap();
+
+	let mut expected = Schema::new();
+	expected
+		.deny_unknown_keys(true)
+		.optional("Tax", Type::Number)
+		.required("StoreName", Type::Text)
+		.optional("Theme", Type::one_of(["Classic", "Modern"]))
+		.optional("PaymentMethods", Type::list(Type::Text))
+		.optional("Anything", Type::list(Type::Any));
+	assert_eq!(toml_schema, expected);
+	assert_eq!(toml_schema.get("StoreName"), Some(&KeySchema { ty: Type::Text, required: true }));
+
+	let json_schema: Schema = serde_json::from_str(r#"{
+		"deny_unknown_keys": true,
+		"keys": {
+			"Tax": "number",
+			"StoreName": { "type": "text", "required": true },
+			"Theme": { "type": "enum", "values": ["Classic", "Modern"] },
+			"PaymentMethods": { "type": "list", "of": "text" },
+			"Anything": { "type": "list" }
+		}
+	}"#).unwrap();
+	assert_eq!(json_schema, expected);
+
+	// Written out and read back, it's the same.
+	let written = toml::to_string(&expected).unwrap();
+	assert_eq!(toml::from_str::<Schema>(&written).unwrap(), expected);
+
+	let written = serde_json::to_string(&expected).unwrap();
+	assert_eq!(serde_json::from_str::<Schema>(&written).unwrap(), expected);
+
+	// Mistakes in schema files are errors.
+	assert!(toml::from_str::<Schema>("[keys]\nTax = \"float\"\n").unwrap_err().to_string().contains("Tax: unknown type \"float\""));
+	assert!(toml::from_str::<Schema>("[keys]\nTheme = { type = \"enum\" }\n").is_err());
+	assert!(toml::from_str::<Schema>("deny_unknown_keys = \"yes\"\n").is_err());
+}
+
+#[test]
+fn test_schema_infer() {
+	// This test verifies that an inferred schema has the right keys and types, and that the files it was inferred from are valid according to it.
+
+	let files: Vec<aa::Value> = [
+		&b"Name: Mug\nQuantity: 3\nPrice: $12.50\nTaxable: checked\nShipping: Ground\nSizes: S|M\nNotes: \nGift\n"[..],
+		b"Name: Towel\nQuantity: 0\nPrice: 8\nTaxable: \nShipping: Air\nSizes: \nNotes: \n",
+		b"Name: Hat\nQuantity: 12\nPrice: 20\nTaxable: checked\nShipping: Ground\nSizes: M|L\nNotes: \nGift\n",
+		b"Name: Mug\nQuantity: 1\nPrice: 5\nShipping: Ground\nSizes: a|b|c|d|e|f|g|h\nNotes: \n"
+	].iter().map(|file| aa::from_bytes(file, None).unwrap()).collect();
+
+	let schema = Schema::infer(&files);
+	let types: Vec<(&str, &Type, bool)> = schema.keys().map(|(key, key_schema)| (key, &key_schema.ty, key_schema.required)).collect();
+
+	assert_eq!(types, [
+		("Gift", &Type::Text, false),
+		("Name", &Type::one_of(["Hat", "Mug", "Towel"]), true),
+		("Notes", &Type::Text, true),
+		("Price", &Type::Number, true),
+		("Quantity", &Type::Int, true),
+		("Shipping", &Type::one_of(["Air", "Ground"]), true),
+		("Sizes", &Type::list(Type::Text), true),
+		("Taxable", &Type::Bool, false)
+	]);
+
+	for file in &files {
+		assert_eq!(schema::validate(file, &schema), []);
+	}
+
+	// Sections are anything, and values that aren't maps are skipped.
+	let mut section = aa::Map::new();
+	section.insert("Zip".to_string(), "12345".into());
+	let mut map = aa::Map::new();
+	map.insert("Shipping".to_string(), aa::Value::Map(section));
+
+	let schema = Schema::infer([&aa::Value::Map(map), &aa::Value::from("not a map")]);
+	assert_eq!(schema.get("Shipping"), Some(&KeySchema { ty: Type::Any, required: true }));
+	assert_eq!(Schema::infer([]), Schema::new());
+}
@@ -0,0 +1,341 @@
+#![cfg(feature = "std")]
+
+use serde::{Deserialize, Serialize};
+use shopsite_aa::{de as aa, ser};
+use std::collections::BTreeMap;
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+enum Color {
+	Red,
+	Blue
+}
+
+#[derive(Debug, Deserialize, PartialEq, Serialize)]
+struct Product {
+	name: String,
+	price: f64,
+	quantity: u32,
+	sale_price: Option<f64>,
+	taxable: bool,
+	color: Color,
+	initial: char,
+	gift_wrap: ()
+}
+
+fn product() -> Product {
+	Product {
+		name: "Café Mug".to_string(),
+		price: 12.5,
+		quantity: 3,
+		sale_price: None,
+		taxable: true,
+		color: Color::Blue,
+		initial: 'C',
+		gift_wrap: ()
+	}
+}
+
+#[test]
+fn test_ser_main() {
+	let bytes = ser::to_bytes(&product()).unwrap();
+	assert_eq!(bytes, &b"name: Caf\xe9 Mug\r\nprice: 12.5\r\nquantity: 3\r\ntaxable: true\r\ncolor: Blue\r\ninitial: C\r\ngift_wrap\r\n"[..]);
+
+	// It reads back the same.
+	let read: Product = aa::from_bytes(&bytes, None).unwrap();
+	assert_eq!(read, product());
+
+	// Maps too, in the order they're given.
+	let mut map = BTreeMap::new();
+	map.insert("b", "2");
+	map.insert("a", "1");
+	assert_eq!(ser::to_bytes(&map).unwrap(), b"a: 1\r\nb: 2\r\n");
+
+	// Nothing at all is an empty file.
+	assert_eq!(ser::to_bytes(&BTreeMap::<String, String>::new()).unwrap(), b"");
+	assert_eq!(ser::to_bytes(&None::<Product>).unwrap(), b"");
+}
+
+#[test]
+fn test_ser_options() {
+	fn to_bytes<T: Serialize>(builder: &ser::SerializerBuilder, value: &T) -> ser::Result<Vec<u8>> {
+		let mut serializer = builder.build(Vec::new());
+		value.serialize(&mut serializer)?;
+		Ok(serializer.into_inner())
+	}
+
+	let mut map = BTreeMap::new();
+	map.insert("arrow", "a → b");
+	map.insert("name", "Café");
+
+	// Characters that aren't in Windows-1252 are an error by default.
+	match ser::to_bytes(&map) {
+		Err(ser::Error::Unencodable { key, character: '→', encoding: aa::InputEncoding::Windows1252 }) => assert_eq!(key, "arrow"),
+		other => panic!("unexpected result: {:?}", other)
+	}
+
+	assert_eq!(
+		to_bytes(ser::SerializerBuilder::new().unencodable(ser::Unencodable::Entity), &map).unwrap(),
+		b"arrow: a &#8594; b\r\nname: Caf\xe9\r\n"
+	);
+
+	assert_eq!(
+		to_bytes(ser::SerializerBuilder::new().unencodable(ser::Unencodable::Replace), &map).unwrap(),
+		b"arrow: a ? b\r\nname: Caf\xe9\r\n"
+	);
+
+	assert_eq!(
+		to_bytes(ser::SerializerBuilder::new().encoding(aa::InputEncoding::Utf8), &map).unwrap(),
+		"arrow: a → b\r\nname: Café\r\n".as_bytes()
+	);
+
+	// Line endings.
+	let mut map = BTreeMap::new();
+	map.insert("a", "1");
+	map.insert("b", "2");
+
+	assert_eq!(to_bytes(ser::SerializerBuilder::new().line_ending(aa::LineEnding::Lf), &map).unwrap(), b"a: 1\nb: 2\n");
+	assert_eq!(to_bytes(ser::SerializerBuilder::new().line_ending(aa::LineEnding::Cr), &map).unwrap(), b"a: 1\rb: 2\r");
+	assert_eq!(to_bytes(ser::SerializerBuilder::new().line_ending(aa::LineEnding::Mixed), &map).unwrap(), b"a: 1\r\nb: 2\r\n");
+	assert_eq!(to_bytes(ser::SerializerBuilder::new().trailing_line_ending(false), &map).unwrap(), b"a: 1\r\nb: 2");
+}
+
+#[test]
+fn test_ser_errors() {
+	fn error<T: Serialize>(value: &T) -> String {
+		ser::to_bytes(value).unwrap_err().to_string()
+	}
+
+	assert_eq!(error(&"just a string"), "only maps and structs can be written as .aa files");
+	assert_eq!(error(&[("a", "1")]), "only maps and structs can be written as .aa files");
+
+	let mut map = BTreeMap::new();
+	map.insert("#comment", "1");
+	assert_eq!(error(&map), "key \"#comment\" can't be written in a .aa file");
+
+	let mut map = BTreeMap::new();
+	map.insert("a:b", "1");
+	assert_eq!(error(&map), "key \"a:b\" can't be written in a .aa file");
+
+	let mut map = BTreeMap::new();
+	map.insert("a", "line 1\nline 2");
+	assert_eq!(error(&map), "the value of a can't be written in a .aa file: it has a line break in it");
+
+	let mut map = BTreeMap::new();
+	map.insert("a", BTreeMap::from([("b", "c")]));
+	assert_eq!(error(&map), "the value of a can't be written in a .aa file: it is a map");
+
+	let mut map = BTreeMap::new();
+	map.insert(vec![1], "a");
+	assert_eq!(error(&map), "keys must be text or numbers");
+}
+
+#[test]
+fn test_ser_entries() {
+	// This test verifies that entries read from a file, duplicates and all, are written back the same way, and that anything that would be read back differently is refused.
+
+	use shopsite_aa::value::Entries;
+
+	fn to_string(entries: &Entries) -> ser::Result<String> {
+		let mut serializer = ser::SerializerBuilder::new().line_ending(aa::LineEnding::Lf).build(Vec::new());
+		entries.serialize(&mut serializer)?;
+		Ok(String::from_utf8(serializer.into_inner()).unwrap())
+	}
+
+	let input = "Name: Widget\nTaxable\nTags: a|b\nName: again\nEmpty: \n";
+	let entries: Entries = aa::from_bytes(input.as_bytes(), None).unwrap();
+	assert_eq!(to_string(&entries).unwrap(), input);
+
+	let tags = aa::Value::Seq(vec![aa::Value::from("a"), aa::Value::Unit, aa::Value::from("c")]);
+	assert_eq!(to_string(&Entries(vec![("Tags".to_string(), tags)])).unwrap(), "Tags: a||c\n");
+
+	for key in &["", " ", "#Name", "  # Name", "a:b", "a\nb"] {
+		let entries = Entries(vec![(key.to_string(), aa::Value::Unit)]);
+		assert!(matches!(to_string(&entries), Err(ser::Error::InvalidKey { .. })), "{:?} should have been refused", key);
+	}
+
+	for value in [
+		aa::Value::from("a\r\nb"),
+		aa::Value::Seq(vec![aa::Value::from("x|y")]),
+		aa::Value::Seq(vec![aa::Value::Seq(vec![aa::Value::Seq(vec![])])]),
+		aa::Value::Map(Default::default())
+	] {
+		let entries = Entries(vec![("Name".to_string(), value)]);
+		assert!(matches!(to_string(&entries), Err(ser::Error::InvalidValue { .. })), "{:?} should have been refused", entries);
+	}
+}
+
+#[test]
+fn test_ser_key_order() {
+	#[derive(Serialize)]
+	struct Settings {
+		b: u8,
+		a: u8,
+		d: Option<u8>,
+		c: u8
+	}
+
+	let settings = Settings { b: 2, a: 1, d: None, c: 3 };
+
+	fn to_bytes(key_order: ser::KeyOrder, settings: &Settings) -> Vec<u8> {
+		let mut serializer = ser::SerializerBuilder::new()
+			.key_order(key_order)
+			.line_ending(aa::LineEnding::Lf)
+			.build(Vec::new());
+
+		settings.serialize(&mut serializer).unwrap();
+		serializer.into_inner()
+	}
+
+	assert_eq!(to_bytes(ser::KeyOrder::AsSerialized, &settings), b"b: 2\na: 1\nc: 3\n");
+	assert_eq!(to_bytes(ser::KeyOrder::Sorted, &settings), b"a: 1\nb: 2\nc: 3\n");
+
+	// Listed keys first, then the rest as serialized. Keys that aren't there are ignored.
+	assert_eq!(to_bytes(ser::KeyOrder::explicit(["c", "d", "a"]), &settings), b"c: 3\na: 1\nb: 2\n");
+
+	// Reverse order.
+	assert_eq!(to_bytes(ser::KeyOrder::by(|a, b| b.cmp(a)), &settings), b"c: 3\nb: 2\na: 1\n");
+
+	// Equal keys stay as serialized.
+	assert_eq!(to_bytes(ser::KeyOrder::by(|_, _| std::cmp::Ordering::Equal), &settings), b"b: 2\na: 1\nc: 3\n");
+
+	// The order changes how the file looks, but not what it says.
+	shopsite_aa::assert_aa_eq!(to_bytes(ser::KeyOrder::Sorted, &settings), to_bytes(ser::KeyOrder::by(|a, b| b.cmp(a)), &settings));
+}
+
+#[test]
+fn test_ser_comments() {
+	let mut settings = BTreeMap::new();
+	settings.insert("Currency", "USD");
+	settings.insert("StoreName", "Widgets");
+
+	fn to_bytes<T: Serialize>(builder: &ser::SerializerBuilder, settings: &T) -> Vec<u8> {
+		let mut serializer = builder.build(Vec::new());
+		settings.serialize(&mut serializer).unwrap();
+		serializer.into_inner()
+	}
+
+	let mut builder = ser::SerializerBuilder::new();
+	builder
+		.line_ending(aa::LineEnding::Lf)
+		.header("Store settings\n\nDo not edit.")
+		.key_comments([("StoreName", "Shown on every page.\nKeep it short."), ("Missing", "Not written.")]);
+
+	let expected = b"# Store settings\n#\n# Do not edit.\n\nCurrency: USD\n# Shown on every page.\n# Keep it short.\nStoreName: Widgets\n";
+	assert_eq!(to_bytes(&builder, &settings), expected);
+
+	// Comments go with their keys when the keys are sorted.
+	builder.key_order(ser::KeyOrder::by(|a, b| b.cmp(a)));
+	assert_eq!(to_bytes(&builder, &settings), b"# Store settings\n#\n# Do not edit.\n\n# Shown on every page.\n# Keep it short.\nStoreName: Widgets\nCurrency: USD\n");
+
+	// Comments don't change what the file says.
+	shopsite_aa::assert_aa_eq!(to_bytes(&builder, &settings), ser::to_bytes(&settings).unwrap());
+
+	// A file with only a header.
+	assert_eq!(to_bytes(&builder, &BTreeMap::<String, String>::new()), b"# Store settings\n#\n# Do not edit.\n");
+
+	// Comments can come from a `Document`, and are read back the same way.
+	let document = aa::Document::parse(&expected[..]);
+	assert_eq!(document.header().as_deref(), Some("Store settings\n\nDo not edit."));
+	assert_eq!(document.key_comments().get("StoreName").map(String::as_str), Some("Shown on every page.\nKeep it short."));
+	assert_eq!(document.key_comments().get("Currency"), None);
+
+	let builder = ser::SerializerBuilder::new()
+		.line_ending(aa::LineEnding::Lf)
+		.comments_from(&document)
+		.clone();
+	assert_eq!(to_bytes(&builder, &document.to_map()), expected);
+
+	// Comments have to be encodable, too.
+	let mut serializer = ser::SerializerBuilder::new().header("→").build(Vec::new());
+	assert!(matches!(settings.serialize(&mut serializer), Err(ser::Error::Unencodable { character: '→', .. })));
+}
+
+#[test]
+fn test_ser_seqs() {
+	#[derive(Debug, Default, Deserialize, PartialEq, Serialize)]
+	struct Lists {
+		#[serde(default)]
+		options: Vec<String>,
+		#[serde(default)]
+		grid: Vec<Vec<u32>>,
+		#[serde(default)]
+		pair: (u8, Option<u8>, u8),
+		#[serde(default)]
+		product_fields: Vec<String>
+	}
+
+	fn to_bytes(builder: &mut ser::SerializerBuilder, lists: &Lists) -> Result<Vec<u8>, ser::Error> {
+		let mut serializer = builder
+			.line_ending(aa::LineEnding::Lf)
+			.seq_delimiter_override("product_*", b';')
+			.build(Vec::new());
+
+		lists.serialize(&mut serializer)?;
+		Ok(serializer.into_inner())
+	}
+
+	fn round_trip(seq_escape: aa::SeqEscape, lists: &Lists) -> Lists {
+		let bytes = to_bytes(ser::SerializerBuilder::new().seq_escape(seq_escape), lists).unwrap();
+
+		let mut de = aa::DeserializerBuilder::new()
+			.seq_escape(seq_escape)
+			.seq_delimiter_override("product_*", b';')
+			.build(&bytes[..], None);
+		Lists::deserialize(&mut de).unwrap()
+	}
+
+	let lists = Lists {
+		options: vec!["Red".to_string(), "Blue".to_string()],
+		grid: vec![vec![1, 2], vec![3]],
+		pair: (1, None, 3),
+		product_fields: vec!["a|b".to_string(), "c".to_string()]
+	};
+
+	assert_eq!(to_bytes(&mut ser::SerializerBuilder::new(), &lists).unwrap(), b"options: Red|Blue\ngrid: 1,2|3\npair: 1||3\nproduct_fields: a|b;c\n");
+	assert_eq!(round_trip(aa::SeqEscape::None, &lists), lists);
+
+	// Empty sequences.
+	let empty = Lists::default();
+	assert_eq!(to_bytes(&mut ser::SerializerBuilder::new(), &empty).unwrap(), b"options: \ngrid: \npair: 0||0\nproduct_fields: \n");
+	assert_eq!(to_bytes(ser::SerializerBuilder::new().empty_seq(ser::EmptySeq::Omit), &empty).unwrap(), b"pair: 0||0\n");
+	assert_eq!(round_trip(aa::SeqEscape::None, &empty), empty);
+
+	// Elements with delimiters in them.
+	let tricky = Lists {
+		options: vec!["Red | Blue".to_string(), "C:\\Temp\\".to_string(), "".to_string()],
+		product_fields: vec!["a;b".to_string()],
+		..Lists::default()
+	};
+
+	assert_eq!(
+		to_bytes(&mut ser::SerializerBuilder::new(), &tricky).unwrap_err().to_string(),
+		"the value of options can't be written in a .aa file: an element has a delimiter in it, which would split it in two"
+	);
+
+	assert_eq!(
+		to_bytes(ser::SerializerBuilder::new().seq_escape(aa::SeqEscape::Backslash), &tricky).unwrap(),
+		b"options: Red \\| Blue|C:\\\\Temp\\\\|\ngrid: \npair: 0||0\nproduct_fields: a\\;b\n"
+	);
+	assert_eq!(round_trip(aa::SeqEscape::Backslash, &tricky), tricky);
+
+	// Numeric entities only work for `|`.
+	assert_eq!(
+		to_bytes(ser::SerializerBuilder::new().seq_escape(aa::SeqEscape::NumericEntity), &tricky).unwrap_err().to_string(),
+		"the value of product_fields can't be written in a .aa file: an element has a delimiter in it, which would split it in two"
+	);
+
+	let tricky = Lists { product_fields: Vec::new(), ..tricky };
+	assert_eq!(
+		to_bytes(ser::SerializerBuilder::new().seq_escape(aa::SeqEscape::NumericEntity), &tricky).unwrap(),
+		b"options: Red &#124; Blue|C:\\Temp\\|\ngrid: \npair: 0||0\nproduct_fields: \n"
+	);
+	assert_eq!(round_trip(aa::SeqEscape::NumericEntity, &tricky), tricky);
+
+	// Nesting is limited to two levels.
+	let mut map = BTreeMap::new();
+	map.insert("a", vec![vec![vec![1]]]);
+	assert_eq!(
+		ser::to_bytes(&map).unwrap_err().to_string(),
+		"the value of a can't be written in a .aa file: it has sequences nested more than two deep"
+	);
+}
@@ -0,0 +1,128 @@
+#![cfg(feature = "trace")]
+
+use serde::Deserialize;
+use shopsite_aa::de as aa;
+use std::{
+	fmt::Debug,
+	sync::{
+		atomic::{AtomicU64, Ordering},
+		Arc,
+		Mutex
+	}
+};
+use tracing::{
+	field::{Field, Visit},
+	span::{Attributes, Id, Record},
+	Event,
+	Metadata,
+	Subscriber
+};
+
+/// A `Subscriber` that writes down the name of each span and the message and `key` field of each event.
+#[derive(Default)]
+struct Recorder {
+	log: Arc<Mutex<Vec<String>>>,
+	next_id: AtomicU64
+}
+
+#[derive(Default)]
+struct Fields {
+	message: String,
+	key: Option<String>
+}
+
+impl Visit for Fields {
+	fn record_debug(&mut self, field: &Field, value: &dyn Debug) {
+		match field.name() {
+			"message" => self.message = format!("{:?}", value),
+			"key" => self.key = Some(format!("{:?}", value)),
+			_ => {}
+		}
+	}
+}
+
+impl Subscriber for Recorder {
+	fn enabled(&self, _: &Metadata) -> bool { true }
+
+	fn new_span(&self, span: &Attributes) -> Id {
+		self.log.lock().unwrap().push(format!("span {}", span.metadata().name()));
+		Id::from_u64(self.next_id.fetch_add(1, Ordering::Relaxed) + 1)
+	}
+
+	fn record(&self, _: &Id, _: &Record) {}
+	fn record_follows_from(&self, _: &Id, _: &Id) {}
+
+	fn event(&self, event: &Event) {
+		let mut fields = Fields::default();
+		event.record(&mut fields);
+
+		self.log.lock().unwrap().push(match fields.key {
+			Some(key) => format!("{} {}", fields.message, key),
+			None => fields.message
+		});
+	}
+
+	fn enter(&self, _: &Id) {}
+	fn exit(&self, _: &Id) {}
+}
+
+#[test]
+fn test_trace() {
+	#[derive(Deserialize)]
+	struct TestRecord {
+		#[allow(dead_code)]
+		count: u32
+	}
+
+	let recorder = Recorder::default();
+	let log = recorder.log.clone();
+
+	tracing::subscriber::with_default(recorder, || {
+		let input = b"count: 1\nextra: x\n\ncount: nope\n";
+		let results: Vec<aa::Result<TestRecord>> = aa::DeserializerBuilder::new()
+			.record_boundary(aa::RecordBoundary::BlankLine)
+			.skip_unknown_keys(true)
+			.build(&input[..], None)
+			.records()
+			.collect();
+		assert!(results[0].is_ok());
+		assert!(results[1].is_err());
+	});
+
+	assert_eq!(*log.lock().unwrap(), [
+		"span record",
+		"key count",
+		"span key",
+		"skipped key extra",
+		"span record",
+		"key count",
+		"span key",
+		"deserialization failed"
+	]);
+}
+
+#[test]
+fn test_trace_oddities() {
+	let recorder = Recorder::default();
+	let log = recorder.log.clone();
+
+	tracing::subscriber::with_default(recorder, || {
+		let input = b"# A comment\nname: Caf\xe9\nname: again\n";
+		let map = aa::Map::deserialize(&mut aa::DeserializerBuilder::new()
+			.encoding(aa::InputEncoding::Utf8)
+			.build(&input[..], None)
+		).unwrap();
+		assert_eq!(map["name"], aa::Value::from("again"));
+	});
+
+	assert_eq!(*log.lock().unwrap(), [
+		"span record",
+		"skipped comment",
+		"key name",
+		"span key",
+		"replaced characters",
+		"key name",
+		"duplicate key name",
+		"span key"
+	]);
+}
@@ -0,0 +1,136 @@
+use std::{
+	borrow::Cow,
+	str::FromStr
+};
+
+/// How keys are renamed on their way to the JSON output.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum KeyCase {
+	/// Keys are written exactly as they are in the input.
+	Keep,
+
+	/// Keys are split into words and written in `snake_case`, like `product_field1`.
+	Snake,
+
+	/// Keys are split into words and written in `camelCase`, like `productField1`.
+	Camel,
+
+	/// Keys are converted to lower case, but otherwise unchanged, like `productfield1`.
+	Lower
+}
+
+impl FromStr for KeyCase {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"keep" => Ok(KeyCase::Keep),
+			"snake" => Ok(KeyCase::Snake),
+			"camel" => Ok(KeyCase::Camel),
+			"lower" => Ok(KeyCase::Lower),
+			_ => Err(format!("unrecognized key case “{}”; expected keep, snake, camel, or lower", s))
+		}
+	}
+}
+
+impl KeyCase {
+	/// Renames a key according to this key case.
+	pub fn apply(self, key: &str) -> Cow<'_, str> {
+		match self {
+			KeyCase::Keep => Cow::Borrowed(key),
+			KeyCase::Lower => Cow::Owned(key.to_lowercase()),
+			KeyCase::Snake => Cow::Owned(
+				words(key)
+				.map(str::to_lowercase)
+				.collect::<Vec<_>>()
+				.join("_")
+			),
+			KeyCase::Camel => Cow::Owned(
+				words(key)
+				.enumerate()
+				.map(|(index, word)| {
+					let word = word.to_lowercase();
+
+					if index == 0 {
+						word
+					}
+					else {
+						let mut chars = word.chars();
+						chars.next().map_or_else(String::new, |first| first.to_uppercase().chain(chars).collect())
+					}
+				})
+				.collect()
+			)
+		}
+	}
+}
+
+/// Splits a key into words. Words are separated by anything that isn't a letter or digit, and by changes in letter case: `ProductField1` is `Product` and `Field1`, and `SKUNumber` is `SKU` and `Number`. Digits stick to the word before them.
+fn words(key: &str) -> impl Iterator<Item = &str> {
+	let mut words = Vec::new();
+	let mut word_start: Option<usize> = None;
+	let mut chars = key.char_indices().peekable();
+	let mut prev: Option<char> = None;
+
+	while let Some((index, c)) = chars.next() {
+		if !c.is_alphanumeric() {
+			// A separator. End the current word, if any.
+			if let Some(start) = word_start.take() {
+				words.push(&key[start..index]);
+			}
+		}
+		else if let Some(start) = word_start {
+			// Inside a word. Does a new one start here?
+			let next = chars.peek().map(|(_, next)| *next);
+
+			let boundary = c.is_uppercase() && match prev {
+				// `fieldName`, `Field1Name`: lower case or digit, then upper case.
+				Some(prev) if prev.is_lowercase() || prev.is_numeric() => true,
+
+				// `SKUNumber`: the last of a run of upper case letters, if a lower case letter follows it.
+				Some(prev) if prev.is_uppercase() => next.is_some_and(char::is_lowercase),
+
+				_ => false
+			};
+
+			if boundary {
+				words.push(&key[start..index]);
+				word_start = Some(index);
+			}
+		}
+		else {
+			// The beginning of a word.
+			word_start = Some(index);
+		}
+
+		prev = Some(c);
+	}
+
+	if let Some(start) = word_start {
+		words.push(&key[start..]);
+	}
+
+	words.into_iter()
+}
+
+#[test]
+fn test_key_case() {
+	let cases: &[(&str, &str, &str, &str)] = &[
+		// Input, snake, camel, lower
+		("ProductField1", "product_field1", "productField1", "productfield1"),
+		("bgcolor", "bgcolor", "bgcolor", "bgcolor"),
+		("SKUNumber", "sku_number", "skuNumber", "skunumber"),
+		("Product Name", "product_name", "productName", "product name"),
+		("ship-to_Zip", "ship_to_zip", "shipToZip", "ship-to_zip"),
+		("Field1Name", "field1_name", "field1Name", "field1name"),
+		("  __  ", "", "", "  __  "),
+		("Café Noir", "café_noir", "caféNoir", "café noir")
+	];
+
+	for &(input, snake, camel, lower) in cases {
+		assert_eq!(KeyCase::Keep.apply(input), input);
+		assert_eq!(KeyCase::Snake.apply(input), snake, "snake case of {:?}", input);
+		assert_eq!(KeyCase::Camel.apply(input), camel, "camel case of {:?}", input);
+		assert_eq!(KeyCase::Lower.apply(input), lower, "lower case of {:?}", input);
+	}
+}
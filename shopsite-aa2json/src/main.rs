@@ -1,17 +1,263 @@
+use serde::{
+	de::{self, DeserializeSeed, Deserializer, MapAccess, Visitor},
+	ser::{self, Serialize, SerializeMap, SerializeSeq, Serializer}
+};
 use shopsite_aa::de as aa;
 use std::{
+	cell::RefCell,
+	fmt::{self, Formatter},
 	fs::{File, OpenOptions},
 	io::{self, BufRead, BufReader, Write},
 	num::NonZeroU8,
 	path::PathBuf,
 	process::exit,
-	rc::Rc
+	sync::Arc,
+	str::FromStr
 };
 use structopt::StructOpt;
 
+mod key_case;
+use key_case::KeyCase;
+
+/// How values are written to the JSON output.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum BytesMode {
+	/// Values are decoded from Windows-1252 into JSON strings. This is the most readable, but the exact original bytes are not necessarily recoverable from it.
+	LossyString,
+
+	/// Values are written as base64-encoded strings of their raw bytes.
+	Base64,
+
+	/// Values are written as arrays of byte values, like `[72, 105]`.
+	Array
+}
+
+impl FromStr for BytesMode {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"lossy-string" => Ok(BytesMode::LossyString),
+			"base64" => Ok(BytesMode::Base64),
+			"array" => Ok(BytesMode::Array),
+			_ => Err(format!("unrecognized bytes mode “{}”; expected lossy-string, base64, or array", s))
+		}
+	}
+}
+
+/// Writes the map of JSON pointers to positions in the input, for the `--positions` option. Entries are written as they're found, one per line, so that the whole map doesn't have to be kept in memory.
+struct Positions {
+	writer: Box<dyn Write>,
+	empty: bool
+}
+
+impl Positions {
+	fn new(mut writer: Box<dyn Write>) -> io::Result<Positions> {
+		writer.write_all(b"{")?;
+		Ok(Positions { writer, empty: true })
+	}
+
+	fn write(&mut self, pointer: &str, pos: &aa::Position) -> io::Result<()> {
+		self.writer.write_all(if self.empty { b"\n\t" } else { b",\n\t" })?;
+		serde_json::to_writer(&mut self.writer, pointer)?;
+		self.writer.write_all(b": ")?;
+		serde_json::to_writer(&mut self.writer, &pos.to_string())?;
+		self.empty = false;
+		Ok(())
+	}
+
+	fn finish(mut self) -> io::Result<()> {
+		self.writer.write_all(if self.empty { b"}\n" } else { b"\n}\n" })?;
+		self.writer.flush()
+	}
+}
+
+/// Escapes a key for use in a JSON pointer, as described in RFC 6901.
+fn escape_pointer(key: &str) -> String {
+	key.replace('~', "~0").replace('/', "~1")
+}
+
+/// Writes a value read as a `RawValue`, in the given `BytesMode` and with the given punctuation, the same way it would have been written had it been transcoded. `None` is a key with no value, which is written as unit (`null`).
+struct RawValueOutput<'a>(Option<&'a aa::RawValue>, BytesMode, aa::Punctuation);
+
+impl Serialize for RawValueOutput<'_> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		match (self.0, self.1) {
+			(None, _) => serializer.serialize_unit(),
+			(Some(value), BytesMode::LossyString) => {
+				let mut text = value.decode();
+				self.2.normalize(&mut text);
+				serializer.serialize_str(&text)
+			},
+			(Some(value), BytesMode::Base64) => serializer.serialize_str(&base64::encode(value.as_bytes())),
+			(Some(value), BytesMode::Array) => value.as_bytes().serialize(serializer)
+		}
+	}
+}
+
+/// The argument of the `--encoding` option.
+#[derive(Clone, Copy)]
+enum EncodingOpt {
+	Known(aa::InputEncoding),
+
+	/// Guess the encoding from the beginning of the input.
+	Auto
+}
+
+impl FromStr for EncodingOpt {
+	type Err = String;
+
+	fn from_str(s: &str) -> Result<Self, Self::Err> {
+		match s {
+			"auto" => Ok(EncodingOpt::Auto),
+			_ => s.parse().map(EncodingOpt::Known).map_err(|_| format!("unrecognized encoding “{}”; expected windows-1252, utf-8, or auto", s))
+		}
+	}
+}
+
+/// Guesses with less confidence than this are warned about.
+const ENCODING_CONFIDENCE_WARNING: f32 = 0.5;
+
+/// Transcodes the top-level map of a `.aa` file to a `Serializer`, renaming the keys and, in `base64` mode, encoding the values in base64. `serde_transcode` can't do either of these things, since it doesn't know which strings are keys, and `serde_json` always writes bytes as arrays.
+/// 
+/// If `positions` is given, each value's position is written there too, under the JSON pointer to the value, which is `pointer` followed by the key.
+struct MapTranscoder<'a, S> {
+	ser: S,
+	bytes: BytesMode,
+	key_case: KeyCase,
+	punctuation: aa::Punctuation,
+	positions: Option<&'a RefCell<Positions>>,
+	pointer: &'a str
+}
+
+impl<'de, S: Serializer> Visitor<'de> for MapTranscoder<'_, S> {
+	type Value = S::Ok;
+
+	fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+		f.write_str("a map")
+	}
+
+	fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<S::Ok, A::Error> {
+		let mut ser_map = self.ser.serialize_map(None).map_err(de::Error::custom)?;
+
+		while let Some(key) = map.next_key::<String>()? {
+			let key = self.key_case.apply(&key).into_owned();
+
+			if let Some(positions) = self.positions {
+				// Read the value raw, since that's the only way to find out where it is. Keys with no value have no position, so they're left out of the positions map.
+				let value: Option<aa::RawValue> = map.next_value()?;
+
+				if let Some(ref value) = value {
+					positions.borrow_mut().write(&format!("{}/{}", self.pointer, escape_pointer(&key)), value.start()).map_err(de::Error::custom)?;
+				}
+
+				ser_map.serialize_entry(&key, &RawValueOutput(value.as_ref(), self.bytes, self.punctuation)).map_err(de::Error::custom)?;
+			}
+			else if self.bytes == BytesMode::Base64 {
+				map.next_value_seed(Base64Value(&mut ser_map, key))?;
+			}
+			else {
+				map.next_value_seed(TranscodeValue(&mut ser_map, key))?;
+			}
+		}
+
+		ser_map.end().map_err(de::Error::custom)
+	}
+}
+
+/// Reads one value and writes it, along with its key, to a `SerializeMap`, as `serde_transcode` would.
+struct TranscodeValue<'a, M>(&'a mut M, String);
+
+impl<'de, M: SerializeMap> DeserializeSeed<'de> for TranscodeValue<'_, M> {
+	type Value = ();
+
+	fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+		self.0.serialize_entry(&self.1, &serde_transcode::Transcoder::new(deserializer)).map_err(de::Error::custom)
+	}
+}
+
+/// Reads one value and writes it, along with its key, to a `SerializeMap`. Bytes are written as base64; a missing value is written as unit (`null`).
+struct Base64Value<'a, M>(&'a mut M, String);
+
+impl<'de, M: SerializeMap> DeserializeSeed<'de> for Base64Value<'_, M> {
+	type Value = ();
+
+	fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<(), D::Error> {
+		deserializer.deserialize_any(self)
+	}
+}
+
+impl<'de, M: SerializeMap> Visitor<'de> for Base64Value<'_, M> {
+	type Value = ();
+
+	fn expecting(&self, f: &mut Formatter) -> fmt::Result {
+		f.write_str("bytes")
+	}
+
+	fn visit_bytes<E: de::Error>(self, v: &[u8]) -> Result<(), E> {
+		self.0.serialize_entry(&self.1, &base64::encode(v)).map_err(E::custom)
+	}
+
+	fn visit_str<E: de::Error>(self, v: &str) -> Result<(), E> {
+		self.visit_bytes(v.as_bytes())
+	}
+
+	fn visit_unit<E: de::Error>(self) -> Result<(), E> {
+		self.0.serialize_entry(&self.1, &()).map_err(E::custom)
+	}
+}
+
+/// Transcodes one record (or the whole file, if it's not divided into records) to a `Serializer`, as a map. `Serialize` only gets a shared reference, hence the `RefCell`.
+/// 
+/// `pointer` is the JSON pointer to the record, like `/3`, or empty if the file isn't divided into records. It's only used for the positions map.
+struct TranscodeRecord<'a, R> {
+	de: RefCell<&'a mut aa::Deserializer<R>>,
+	bytes: BytesMode,
+	key_case: KeyCase,
+	punctuation: aa::Punctuation,
+	positions: Option<&'a RefCell<Positions>>,
+	pointer: String
+}
+
+impl<R: BufRead> Serialize for TranscodeRecord<'_, R> {
+	fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+		let mut de = self.de.borrow_mut();
+
+		if self.bytes != BytesMode::Base64 && self.key_case == KeyCase::Keep && self.positions.is_none() {
+			// Nothing to change, so let `serde_transcode` do all the work. In `array` mode, the deserializer hands over bytes, which `serde_json` already writes as arrays of numbers.
+			serde_transcode::transcode(&mut **de, serializer)
+		}
+		else {
+			(&mut **de).deserialize_map(MapTranscoder {
+				ser: serializer,
+				bytes: self.bytes,
+				key_case: self.key_case,
+				punctuation: self.punctuation,
+				positions: self.positions,
+				pointer: &self.pointer
+			})
+			.map_err(ser::Error::custom)
+		}
+	}
+}
+
+/// Parses the argument of the `--records` option.
+fn parse_record_boundary(s: &str) -> Result<aa::RecordBoundary, String> {
+	if s == "blank-line" {
+		Ok(aa::RecordBoundary::BlankLine)
+	}
+	else if let Some(key) = s.strip_prefix("key=") {
+		Ok(aa::RecordBoundary::Key(key.to_string()))
+	}
+	else {
+		Err(format!("unrecognized record boundary “{}”; expected blank-line or key=NAME", s))
+	}
+}
+
 #[derive(StructOpt)]
 #[structopt(
-	about = "Converts a ShopSite `.aa` file to JSON."
+	about = "Converts a ShopSite `.aa` file to JSON.",
+	after_help = "Keys are written in exactly the order they appear in the input. A key that appears more than once is written more than once, in each place it appears, even though most JSON readers will only keep one of them.\n\nOptions can also be given in a configuration file, in the `[shopsite-aa2json]` table of `shopsite-utils/config.toml` in the user or system configuration directory. Options given on the command line take precedence."
 )]
 struct Opts {
 	/// Pretty-print the output JSON.
@@ -26,6 +272,38 @@ struct Opts {
 	#[structopt(short = "t", long, requires = "pretty")]
 	indent_tabs: bool,
 
+	/// How to write values: `lossy-string` decodes them as text; `base64` writes their raw bytes as base64 strings; `array` writes their raw bytes as arrays of numbers. Use `base64` or `array` if the exact original bytes need to be recovered from the JSON.
+	#[structopt(long, default_value = "lossy-string", possible_values = &["lossy-string", "base64", "array"])]
+	bytes: BytesMode,
+
+	/// How to rename keys: `keep` leaves them as they are; `snake` writes them like `product_field1`; `camel` writes them like `productField1`; `lower` converts them to lower case.
+	#[structopt(long, default_value = "keep", possible_values = &["keep", "snake", "camel", "lower"])]
+	key_case: KeyCase,
+
+	/// What kind of file the input is, which decides how it's read: `shopsite12` or `shopsite14` for files written by those versions of ShopSite or later ones, or `hand-edited` for files written or edited in a text editor. --encoding and --punctuation override it.
+	#[structopt(long, possible_values = &["shopsite12", "shopsite14", "hand-edited"])]
+	preset: Option<aa::Preset>,
+
+	/// Character encoding of the input: `windows-1252`, which is what ShopSite uses; `utf-8`; or `auto`, to guess from the beginning of the input. A guess that isn't very confident is warned about. [default: windows-1252, or whatever --preset says]
+	#[structopt(long, possible_values = &["windows-1252", "utf-8", "auto"])]
+	encoding: Option<EncodingOpt>,
+
+	/// What to do with typographic punctuation, like curly quotes, dashes, and ellipses: `keep` leaves it as it is; `unicode` also fixes punctuation that was garbled into control characters, as happens to Windows-1252 text converted to UTF-8 as if it were Latin-1; `ascii` does that, then replaces quotes, dashes, and ellipses with their plain ASCII equivalents, for systems that can't handle anything else. This only affects `lossy-string` values. [default: keep, or whatever --preset says]
+	#[structopt(long, possible_values = &["keep", "unicode", "ascii"])]
+	punctuation: Option<aa::Punctuation>,
+
+	/// Fail if a line of the input is longer than this many bytes, instead of reading it all into memory. This guards batch jobs against input that isn't a `.aa` file at all, like a binary file given by mistake, which may have no line endings in it. ShopSite's product descriptions can be long, so don't set this too low. By default, there is no limit.
+	#[structopt(long, value_name = "BYTES")]
+	max_line_length: Option<usize>,
+
+	/// Treat the input as a series of records, like ShopSite's product database dumps, and write them as a JSON array of objects. BOUNDARY is `blank-line` if records are separated by blank lines, or `key=NAME` if each record begins with the key NAME.
+	#[structopt(long, value_name = "BOUNDARY", parse(try_from_str = parse_record_boundary))]
+	records: Option<aa::RecordBoundary>,
+
+	/// Also write a JSON object mapping JSON pointers, like `/3/Price`, to the position in the input of the value they point to, like `products.aa:120:8`, to this file. Validators of the JSON output can use it to report problems in terms of the `.aa` file. Keys with no value aren't included. If a key appears more than once, so does its pointer.
+	#[structopt(long, value_name = "FILE")]
+	positions: Option<PathBuf>,
+
 	/// JSON file to write to, instead of standard output.
 	#[structopt(short, long)]
 	output: Option<PathBuf>,
@@ -36,12 +314,12 @@ struct Opts {
 }
 
 fn main() {
-	let opts: Opts = Opts::from_args();
+	let opts: Opts = shopsite_cli::from_args();
 
 	let stdin = io::stdin();
 	let stdout = io::stdout();
 
-	let input: Box<dyn BufRead> = {
+	let mut input: Box<dyn BufRead> = {
 		if let Some(ref input_file) = opts.input {
 			let open_result = File::open(input_file);
 
@@ -79,15 +357,90 @@ fn main() {
 		}
 	};
 
-	let de = aa::Deserializer::new(input, opts.input.map(Rc::from));
+	let positions: Option<RefCell<Positions>> = opts.positions.as_ref().map(|positions_file| {
+		let result = File::create(positions_file).and_then(|fh| Positions::new(Box::new(io::BufWriter::new(fh))));
+
+		match result {
+			Ok(positions) => RefCell::new(positions),
+			Err(error) => {
+				eprintln!("Error opening positions file {}: {}", positions_file.to_string_lossy(), error);
+				exit(1)
+			}
+		}
+	});
+
+	let preset = opts.preset.map(aa::Preset::options);
+
+	let encoding = match opts.encoding {
+		None => preset.and_then(|preset| preset.encoding).unwrap_or_default(),
+		Some(EncodingOpt::Known(encoding)) => encoding,
+		Some(EncodingOpt::Auto) => {
+			// Guess from whatever is in the input buffer, without consuming it.
+			let detected = match input.fill_buf() {
+				Ok(sample) => aa::detect_encoding(sample),
+				Err(error) => {
+					eprintln!("Error reading input: {}", error);
+					exit(1)
+				}
+			};
+
+			if detected.confidence < ENCODING_CONFIDENCE_WARNING {
+				eprintln!("Warning: guessing that the input is {}, but only {:.0}% sure. Use --encoding to say which it is.", detected.encoding, detected.confidence * 100.0);
+			}
+
+			detected.encoding
+		}
+	};
+
+	let punctuation = opts.punctuation.or_else(|| preset.map(|preset| preset.punctuation)).unwrap_or_default();
+
+	let mut builder = aa::DeserializerBuilder::new();
+
+	if let Some(preset) = opts.preset {
+		builder.preset(preset);
+	}
+
+	if let Some(max) = opts.max_line_length {
+		builder.max_line_length(max);
+	}
+
+	let de = builder
+		.encoding(encoding)
+		.punctuation(punctuation)
+		.untyped_value(match opts.bytes {
+			BytesMode::LossyString => aa::UntypedValue::Text,
+			BytesMode::Base64 | BytesMode::Array => aa::UntypedValue::Bytes
+		})
+		.record_boundary(opts.records.clone().unwrap_or_default())
+		.build(input, opts.input.as_deref().map(Arc::from));
 
 	// `serde_json::ser::Formatter` can't be used as a trait object, so we get to do this instead…
-	fn do_transcode(mut de: aa::Deserializer<impl BufRead>, mut writer: impl Write, formatter: impl serde_json::ser::Formatter) -> Result<(), std::io::Error> {
+	fn do_transcode(mut de: aa::Deserializer<impl BufRead>, mut writer: impl Write, formatter: impl serde_json::ser::Formatter, opts: &Opts, punctuation: aa::Punctuation, positions: Option<RefCell<Positions>>) -> Result<(), std::io::Error> {
+		let Opts { bytes, key_case, .. } = *opts;
 		let mut ser = serde_json::Serializer::with_formatter(&mut writer, formatter);
 
-		serde_transcode::transcode(&mut de, &mut ser)?;
+		if opts.records.is_some() {
+			let mut seq = ser.serialize_seq(None)?;
+			let mut index = 0usize;
+
+			while de.has_more_records().map_err(io::Error::other)? {
+				seq.serialize_element(&TranscodeRecord { de: RefCell::new(&mut de), bytes, key_case, punctuation, positions: positions.as_ref(), pointer: format!("/{}", index) })?;
+				index += 1;
+			}
+
+			SerializeSeq::end(seq)?;
+		}
+		else {
+			TranscodeRecord { de: RefCell::new(&mut de), bytes, key_case, punctuation, positions: positions.as_ref(), pointer: String::new() }.serialize(&mut ser)?;
+		}
+
 		writeln!(&mut writer)?;
-		writer.flush()
+		writer.flush()?;
+
+		match positions {
+			Some(positions) => positions.into_inner().finish(),
+			None => Ok(())
+		}
 	}
 
 	let result = {
@@ -99,10 +452,7 @@ fn main() {
 					b"\t"
 				}
 				else if let Some(indent_spaces) = opts.indent_spaces {
-					indent_string_buf.reserve_exact(indent_spaces.get() as usize);
-					for _ in 0..indent_spaces.get() {
-						indent_string_buf.push(b' ');
-					}
+					indent_string_buf.resize(indent_spaces.get() as usize, b' ');
 					&indent_string_buf[..]
 				}
 				else {
@@ -110,10 +460,10 @@ fn main() {
 				}
 			};
 
-			do_transcode(de, output, serde_json::ser::PrettyFormatter::with_indent(indent_string))
+			do_transcode(de, output, serde_json::ser::PrettyFormatter::with_indent(indent_string), &opts, punctuation, positions)
 		}
 		else {
-			do_transcode(de, output, serde_json::ser::CompactFormatter)
+			do_transcode(de, output, serde_json::ser::CompactFormatter, &opts, punctuation, positions)
 		}
 	};
 
@@ -17,6 +17,8 @@ fn run_test(cmd: &mut Command, expected_output: &str) {
 	assert_eq!(&results.stderr[..], &[], "standard error output should have been empty");
 }
 
+// The `expected-*.json` files are regenerated by `cargo xtask bless`, which needs to be told about any new ones, and how to produce them.
+
 #[test]
 fn run_compact() {
 	run_test(
@@ -28,7 +30,7 @@ fn run_compact() {
 #[test]
 fn run_pretty_spaces() {
 	run_test(
-		get_cmd().args(&["-p", "-s", "3"]).arg(test_aa_location()),
+		get_cmd().args(["-p", "-s", "3"]).arg(test_aa_location()),
 		include_str!("expected-pretty-spaces.json")
 	)
 }
@@ -40,3 +42,197 @@ fn run_pretty_tabs() {
 		include_str!("expected-pretty-tabs.json")
 	)
 }
+
+#[test]
+fn run_bytes_modes() {
+	let input: &[u8] = b"name: caf\xe9\nflag\n";
+
+	run_test(
+		get_cmd().args(["--bytes", "base64"]).write_stdin(input),
+		"{\"name\":\"Y2Fm6Q==\",\"flag\":null}\n"
+	);
+
+	run_test(
+		get_cmd().args(["--bytes", "array"]).write_stdin(input),
+		"{\"name\":[99,97,102,233],\"flag\":null}\n"
+	);
+
+	run_test(
+		get_cmd().write_stdin(input),
+		"{\"name\":\"café\",\"flag\":null}\n"
+	);
+}
+
+#[test]
+fn run_encodings() {
+	let utf8_input = "name: café\n".as_bytes();
+
+	run_test(
+		get_cmd().args(["--encoding", "utf-8"]).write_stdin(utf8_input),
+		"{\"name\":\"café\"}\n"
+	);
+
+	run_test(
+		get_cmd().write_stdin(utf8_input),
+		"{\"name\":\"cafÃ©\"}\n"
+	);
+
+	run_test(
+		get_cmd().args(["--encoding", "auto"]).write_stdin(utf8_input),
+		"{\"name\":\"café\"}\n"
+	);
+
+	run_test(
+		get_cmd().args(["--encoding", "auto"]).write_stdin(&b"name: caf\xe9\n"[..]),
+		"{\"name\":\"café\"}\n"
+	);
+
+	// A shaky guess gets a warning.
+	let results = get_cmd().args(["--encoding", "auto"]).write_stdin(&b"name: \x81\x90\n"[..]).assert().success();
+	assert!(String::from_utf8_lossy(&results.get_output().stderr).contains("Warning: guessing that the input is windows-1252, but only 0% sure"));
+}
+
+#[test]
+fn run_punctuation() {
+	let input: &[u8] = b"desc: \x93Big\x94 \x96 it\x92s\x85 great\n";
+
+	run_test(
+		get_cmd().write_stdin(input),
+		"{\"desc\":\"“Big” – it’s… great\"}\n"
+	);
+
+	run_test(
+		get_cmd().args(["--punctuation", "ascii"]).write_stdin(input),
+		"{\"desc\":\"\\\"Big\\\" - it's... great\"}\n"
+	);
+
+	// Punctuation garbled into control characters is fixed, with or without the rest of the transcoder.
+	let garbled = "desc: \u{93}Big\u{94}\n".as_bytes();
+
+	run_test(
+		get_cmd().args(["--encoding", "utf-8", "--punctuation", "unicode"]).write_stdin(garbled),
+		"{\"desc\":\"“Big”\"}\n"
+	);
+
+	run_test(
+		get_cmd().args(["--encoding", "utf-8", "--punctuation", "unicode", "--key-case", "camel"]).write_stdin(garbled),
+		"{\"desc\":\"“Big”\"}\n"
+	);
+}
+
+#[test]
+fn run_preset() {
+	let garbled = "desc: \u{93}Big\u{94}\n".as_bytes();
+
+	run_test(
+		get_cmd().args(["--preset", "hand-edited", "--encoding", "utf-8"]).write_stdin(garbled),
+		"{\"desc\":\"“Big”\"}\n"
+	);
+
+	// Options given along with a preset override it.
+	run_test(
+		get_cmd().args(["--preset", "hand-edited", "--encoding", "utf-8", "--punctuation", "keep"]).write_stdin(garbled),
+		"{\"desc\":\"\u{93}Big\u{94}\"}\n"
+	);
+
+	run_test(
+		get_cmd().args(["--preset", "shopsite14"]).write_stdin(&b"desc: caf\xe9\n"[..]),
+		"{\"desc\":\"café\"}\n"
+	);
+}
+
+#[test]
+fn run_max_line_length() {
+	run_test(
+		get_cmd().args(["--max-line-length", "12"]).write_stdin(&b"name: Widget\n"[..]),
+		"{\"name\":\"Widget\"}\n"
+	);
+
+	// Something that isn't a `.aa` file, with no line endings in it, fails with a clear message instead of being read into memory.
+	let results = get_cmd().args(["--max-line-length", "12"]).write_stdin(vec![0u8; 100_000]).assert().failure();
+	assert!(String::from_utf8_lossy(&results.get_output().stderr).contains("line length exceeds the limit of 12"));
+}
+
+#[test]
+fn run_key_order() {
+	// Keys come out in the order they went in, duplicates and all.
+	let input: &[u8] = b"zebra: 1\napple: 2\nmango\napple: 3\n";
+
+	run_test(
+		get_cmd().write_stdin(input),
+		"{\"zebra\":\"1\",\"apple\":\"2\",\"mango\":null,\"apple\":\"3\"}\n"
+	);
+
+	run_test(
+		get_cmd().args(["--bytes", "base64"]).write_stdin(input),
+		"{\"zebra\":\"MQ==\",\"apple\":\"Mg==\",\"mango\":null,\"apple\":\"Mw==\"}\n"
+	);
+}
+
+#[test]
+fn run_records() {
+	let input: &[u8] = b"# products\nname: B\nsku: 2\nsku: 2a\n\nname: A\n\n\nname: C\nsku: 3\n";
+
+	run_test(
+		get_cmd().args(["--records", "blank-line"]).write_stdin(input),
+		"[{\"name\":\"B\",\"sku\":\"2\",\"sku\":\"2a\"},{\"name\":\"A\"},{\"name\":\"C\",\"sku\":\"3\"}]\n"
+	);
+
+	run_test(
+		get_cmd().args(["--records", "key=name", "--bytes", "base64"]).write_stdin(&b"name: B\nsku: 2\nname: A\n"[..]),
+		"[{\"name\":\"Qg==\",\"sku\":\"Mg==\"},{\"name\":\"QQ==\"}]\n"
+	);
+
+	run_test(
+		get_cmd().args(["--records", "blank-line"]).write_stdin(&b"\n# nothing\n"[..]),
+		"[]\n"
+	);
+
+	let results = get_cmd().args(["--records", "bogus"]).write_stdin(input).assert().failure();
+	assert!(String::from_utf8_lossy(&results.get_output().stderr).contains("expected blank-line or key=NAME"));
+}
+
+#[test]
+fn run_key_case() {
+	let input: &[u8] = b"ProductField1: caf\xe9\nSKUNumber: 2\nbgcolor\n";
+
+	run_test(
+		get_cmd().args(["--key-case", "snake"]).write_stdin(input),
+		"{\"product_field1\":\"café\",\"sku_number\":\"2\",\"bgcolor\":null}\n"
+	);
+
+	run_test(
+		get_cmd().args(["--key-case", "camel", "--bytes", "base64"]).write_stdin(input),
+		"{\"productField1\":\"Y2Fm6Q==\",\"skuNumber\":\"Mg==\",\"bgcolor\":null}\n"
+	);
+
+	run_test(
+		get_cmd().args(["--key-case", "lower", "--bytes", "array", "--records", "blank-line"]).write_stdin(input),
+		"[{\"productfield1\":[99,97,102,233],\"skunumber\":[50],\"bgcolor\":null}]\n"
+	);
+}
+
+#[test]
+fn run_positions() {
+	let positions_file: PathBuf = [env!("CARGO_TARGET_TMPDIR"), "run_positions.json"].iter().collect();
+	let input: &[u8] = b"Name: B\nA/B~: 2\nflag\n\nName: A\n";
+
+	// The JSON output is the same as without `--positions`.
+	run_test(
+		get_cmd().args(["--records", "blank-line", "--bytes", "base64", "--positions"]).arg(&positions_file).write_stdin(input),
+		"[{\"Name\":\"Qg==\",\"A/B~\":\"Mg==\",\"flag\":null},{\"Name\":\"QQ==\"}]\n"
+	);
+
+	assert_eq!(
+		std::fs::read_to_string(&positions_file).unwrap(),
+		"{\n\t\"/0/Name\": \"<unknown>:1:7\",\n\t\"/0/A~1B~0\": \"<unknown>:2:7\",\n\t\"/1/Name\": \"<unknown>:5:7\"\n}\n"
+	);
+
+	// Pointers use the renamed keys, and there's no record index if there are no records.
+	run_test(
+		get_cmd().args(["--key-case", "lower", "--positions"]).arg(&positions_file).write_stdin(&b"Name: B\n"[..]),
+		"{\"name\":\"B\"}\n"
+	);
+
+	assert_eq!(std::fs::read_to_string(&positions_file).unwrap(), "{\n\t\"/name\": \"<unknown>:1:7\"\n}\n");
+}
@@ -0,0 +1,348 @@
+//! Command-line plumbing shared by the tools in this project.
+//! 
+//! # Configuration files
+//! 
+//! Options that would otherwise have to be repeated on every invocation can be given in a configuration file instead. Configuration files are in [TOML](https://toml.io/) format, with a table for each tool, named after the tool, and a table within that for each subcommand. The keys in each table are the long names of command-line options, without the leading `--`:
+//! 
+//! ```toml
+//! [shopsite-aa2json]
+//! key-case = "snake"
+//! 
+//! [shopsite-tools.report.sales]
+//! format = "markdown"
+//! excel = true
+//! 
+//! [shopsite-tools.serve]
+//! products = "/var/shopsite/products.txt"
+//! ```
+//! 
+//! A string or number is given as the option's value. An array gives the option once for each element. `true` gives a flag that takes no value, like `--excel`; `false` does nothing, since there's no way to un-give a flag.
+//! 
+//! Configuration is read from these places, from least to most important:
+//! 
+//! 1. The system configuration file: `shopsite-utils/config.toml` in each of the directories in `$XDG_CONFIG_DIRS` (by default, `/etc/xdg`), or in `%PROGRAMDATA%` on Windows.
+//! 2. The user configuration file: `shopsite-utils/config.toml` in `$XDG_CONFIG_HOME` (by default, `~/.config`), or in `%APPDATA%` on Windows.
+//! 3. Environment variables, for options that can be given that way. These are named after the tool and the option, like `SHOPSITE_TOOLS_TOKEN` for `shopsite-tools serve --token`. An option's `--help` says if it can be given this way.
+//! 4. The command line.
+//! 
+//! Something given in a more important place replaces anything given in a less important one. Missing configuration files are fine, but unreadable or malformed ones are an error.
+
+use std::{
+	env,
+	ffi::OsString,
+	fs,
+	io,
+	path::PathBuf,
+	process::exit,
+	sync::OnceLock
+};
+use structopt::{clap::App, StructOpt};
+use toml::{value::Table, Value};
+
+/// Name of the directory that configuration files are in, within each of the standard configuration directories.
+const CONFIG_DIR_NAME: &str = "shopsite-utils";
+
+/// Name of configuration files.
+const CONFIG_FILE_NAME: &str = "config.toml";
+
+/// The contents of all configuration files, merged together.
+#[derive(Debug, Default)]
+pub struct Config {
+	table: Table
+}
+
+static CONFIG: OnceLock<Config> = OnceLock::new();
+
+/// Gets the configuration, reading it from the configuration files the first time. This can be called from any thread; the files are only read once.
+pub fn config() -> Result<&'static Config, String> {
+	if let Some(config) = CONFIG.get() {
+		return Ok(config)
+	}
+
+	// If two threads get here at once, both read the files, but only one of the results is kept. They're the same anyway.
+	let config = Config::read(&config_files())?;
+	Ok(CONFIG.get_or_init(|| config))
+}
+
+/// Parses the command line into some `StructOpt` type, as `StructOpt::from_args` does, but with options from the configuration files added. The tool's name, which is the name of its table in the configuration files, is the `clap` app name, which is the package name unless `StructOpt` is told otherwise.
+/// 
+/// If the configuration files can't be read, or the command line is wrong, prints an error and exits.
+pub fn from_args<T: StructOpt>() -> T {
+	let app = T::clap();
+
+	let args = config().and_then(|config| config.apply(&app, env::args_os()));
+
+	match args {
+		Ok(args) => T::from_iter(args),
+		Err(error) => {
+			eprintln!("{}", error);
+			exit(1)
+		}
+	}
+}
+
+/// Lists the configuration files that might exist, from least to most important.
+fn config_files() -> Vec<PathBuf> {
+	let mut dirs: Vec<PathBuf> = Vec::new();
+
+	#[cfg(windows)] {
+		dirs.extend(env_dir("PROGRAMDATA"));
+		dirs.extend(env_dir("APPDATA"));
+	}
+
+	#[cfg(not(windows))] {
+		// `XDG_CONFIG_DIRS` lists the most important directory first.
+		match env::var_os("XDG_CONFIG_DIRS").filter(|dirs| !dirs.is_empty()) {
+			Some(system_dirs) => {
+				let mut system_dirs: Vec<PathBuf> = env::split_paths(&system_dirs).filter(|dir| dir.is_absolute()).collect();
+				system_dirs.reverse();
+				dirs.extend(system_dirs);
+			},
+			None => dirs.push(PathBuf::from("/etc/xdg"))
+		}
+
+		match env_dir("XDG_CONFIG_HOME") {
+			Some(user_dir) => dirs.push(user_dir),
+			None => dirs.extend(env_dir("HOME").map(|home| home.join(".config")))
+		}
+	}
+
+	dirs.into_iter().map(|dir| dir.join(CONFIG_DIR_NAME).join(CONFIG_FILE_NAME)).collect()
+}
+
+/// Gets a directory from an environment variable. The XDG Base Directory Specification says to ignore relative paths, so those are ignored here too.
+fn env_dir(var: &str) -> Option<PathBuf> {
+	env::var_os(var).map(PathBuf::from).filter(|dir| dir.is_absolute())
+}
+
+/// Copies the entries of `from` into `into`, replacing those that are already there. Tables in both are merged instead of replaced, so that a user configuration file can change one option without discarding the rest of the system configuration file's table.
+fn merge(into: &mut Table, from: Table) {
+	for (key, value) in from {
+		match (into.get_mut(&key), value) {
+			(Some(Value::Table(into)), Value::Table(from)) => merge(into, from),
+			(_, value) => {
+				into.insert(key, value);
+			}
+		}
+	}
+}
+
+/// The ways that an option can be given, found in the `clap` app's definition of it.
+#[derive(Debug, Default)]
+struct OptionSpelling {
+	/// The option's long name and its aliases, without the leading `--`.
+	longs: Vec<String>,
+
+	/// The option's short name, if it has one.
+	short: Option<char>,
+
+	/// The environment variable that the option can be given in, if it can be.
+	env: Option<OsString>,
+
+	/// The short names of all of the app's options that take a value. In `-xovalue`, if `-o` takes a value, then `value` is its value, not more short options.
+	value_shorts: Vec<char>
+}
+
+impl OptionSpelling {
+	/// Finds out how the option whose long name is `long` can be given to `app`. If `app` doesn't have such an option, it can only be given as `--long`, which `clap` will then reject.
+	/// 
+	/// `clap` 2 has no public way to look at an app's arguments, but the fields it keeps them in are public, just undocumented.
+	fn find(app: Option<&App>, long: &str) -> OptionSpelling {
+		let mut spelling = OptionSpelling {
+			longs: vec![long.to_string()],
+			..OptionSpelling::default()
+		};
+
+		let app = match app {
+			Some(app) => app,
+			None => return spelling
+		};
+
+		let aliases = |aliases: &Option<Vec<(&str, bool)>>| aliases.iter().flatten().map(|(alias, _)| alias.to_string()).collect::<Vec<String>>();
+
+		if let Some(flag) = app.p.flags.iter().find(|flag| flag.s.long == Some(long)) {
+			spelling.longs.extend(aliases(&flag.s.aliases));
+			spelling.short = flag.s.short;
+		}
+		else if let Some(opt) = app.p.opts.iter().find(|opt| opt.s.long == Some(long)) {
+			spelling.longs.extend(aliases(&opt.s.aliases));
+			spelling.short = opt.s.short;
+			spelling.env = opt.v.env.as_ref().map(|(name, _)| name.to_os_string());
+		}
+
+		spelling.value_shorts = app.p.opts.iter().filter_map(|opt| opt.s.short).collect();
+		spelling
+	}
+
+	/// Whether the option is among `args`, in any of its spellings: `--format`, `--format=…`, `-f`, `-f…`, or in a cluster of short options like `-xf`. Anything after `--` is an argument, not an option, so it's not looked at.
+	fn is_given(&self, args: &[OsString]) -> bool {
+		args.iter()
+		.map(|arg| arg.to_string_lossy())
+		.take_while(|arg| arg != "--")
+		.any(|arg| {
+			if let Some(arg) = arg.strip_prefix("--") {
+				self.longs.iter().any(|long| arg.strip_prefix(long.as_str()).is_some_and(|rest| rest.is_empty() || rest.starts_with('=')))
+			}
+			else if let Some(cluster) = arg.strip_prefix('-') {
+				for short in cluster.chars() {
+					if Some(short) == self.short {
+						return true
+					}
+					else if self.value_shorts.contains(&short) {
+						// The rest of the cluster is this option's value.
+						break
+					}
+				}
+
+				false
+			}
+			else {
+				false
+			}
+		})
+	}
+
+	/// Whether the option's environment variable, if it has one, is set.
+	fn is_in_env(&self) -> bool {
+		self.env.as_ref().is_some_and(|name| env::var_os(name).is_some())
+	}
+}
+
+impl Config {
+	/// Reads and merges the given configuration files, from least to most important. Files that don't exist are skipped.
+	pub fn read(files: &[PathBuf]) -> Result<Config, String> {
+		let mut table = Table::new();
+
+		for file in files {
+			let text = match fs::read_to_string(file) {
+				Ok(text) => text,
+				Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+				Err(error) => return Err(format!("Error reading configuration file {}: {}", file.display(), error))
+			};
+
+			let file_table: Table = toml::from_str(&text).map_err(|error| format!("Error in configuration file {}: {}", file.display(), error))?;
+			merge(&mut table, file_table);
+		}
+
+		Ok(Config { table })
+	}
+
+	/// Gets a table from the configuration, like `["shopsite-tools", "serve"]` for the `shopsite-tools serve` command's options.
+	pub fn section(&self, path: &[&str]) -> Option<&Table> {
+		path.iter().try_fold(&self.table, |table, name| match table.get(*name) {
+			Some(Value::Table(table)) => Some(table),
+			_ => None
+		})
+	}
+
+	/// Adds options from the configuration to a command line, for the `clap` app `app`, whose name is the tool's name. `args` is the whole command line, starting with the program name.
+	/// 
+	/// The subcommand is found by following subcommand names at the start of `args` for as long as there's a table for them, and the options in the last table found are inserted right after the subcommand name. Options that are already on the command line, by any of their names, or whose environment variables are set, are left out.
+	pub fn apply(&self, app: &App, args: impl IntoIterator<Item = OsString>) -> Result<Vec<OsString>, String> {
+		let tool = app.get_name();
+		let mut app = Some(app);
+		let mut args: Vec<OsString> = args.into_iter().collect();
+
+		let mut table = match self.section(&[tool]) {
+			Some(table) => table,
+			None => return Ok(args)
+		};
+
+		let mut section_path = vec![tool.to_string()];
+		let mut insert_at = 1;
+
+		while let Some(Value::Table(subtable)) = args.get(insert_at).and_then(|arg| arg.to_str()).and_then(|arg| table.get(arg)) {
+			let name = args[insert_at].to_string_lossy().into_owned();
+			app = app.and_then(|app| app.p.subcommands.iter().find(|subcommand| subcommand.get_name() == name));
+			section_path.push(name);
+			table = subtable;
+			insert_at += 1;
+		}
+
+		let mut options: Vec<OsString> = Vec::new();
+
+		for (key, value) in table {
+			if value.is_table() {
+				continue
+			}
+
+			let spelling = OptionSpelling::find(app, key);
+
+			if spelling.is_given(&args[insert_at..]) || spelling.is_in_env() {
+				continue
+			}
+
+			let option = format!("--{}", key);
+
+			let scalar = |value: &Value| match value {
+				Value::String(s) => Ok(format!("{}={}", option, s)),
+				Value::Integer(_) | Value::Float(_) | Value::Datetime(_) => Ok(format!("{}={}", option, value)),
+				_ => Err(format!("Error in configuration: [{}] {}: expected a string, number, or date", section_path.join("."), key))
+			};
+
+			match value {
+				Value::Boolean(true) => options.push(option.clone().into()),
+				Value::Boolean(false) => {},
+				Value::Array(values) => {
+					for value in values {
+						options.push(scalar(value)?.into());
+					}
+				},
+				value => options.push(scalar(value)?.into())
+			}
+		}
+
+		args.splice(insert_at..insert_at, options);
+		Ok(args)
+	}
+}
+
+#[test]
+fn test_apply() {
+	use structopt::clap::{Arg, SubCommand};
+
+	let config = Config {
+		table: toml::from_str(r#"
+			[tool.sub]
+			output = "config.csv"
+			excel = true
+			token = "config token"
+			plain = "config plain"
+		"#).unwrap()
+	};
+
+	let app = App::new("tool").subcommand(
+		SubCommand::with_name("sub")
+		.arg(Arg::with_name("output").short("o").long("output").alias("out").takes_value(true))
+		.arg(Arg::with_name("excel").short("x").long("excel"))
+		.arg(Arg::with_name("format").short("f").long("format").takes_value(true))
+		.arg(Arg::with_name("token").long("token").takes_value(true).env("SHOPSITE_CLI_TEST_TOKEN"))
+		.arg(Arg::with_name("plain").long("plain").takes_value(true))
+	);
+
+	let apply = |args: &[&str]| -> Vec<String> {
+		let args = ["tool", "sub"].iter().chain(args).map(OsString::from);
+		config.apply(&app, args).unwrap().into_iter().map(|arg| arg.into_string().unwrap()).collect()
+	};
+
+	// Options from the configuration go right after the subcommand.
+	assert_eq!(apply(&["in.txt"]), ["tool", "sub", "--excel", "--output=config.csv", "--plain=config plain", "--token=config token", "in.txt"]);
+
+	// Options on the command line are left alone, no matter how they're spelled.
+	for given in [&["-o", "x.csv"][..], &["-ox.csv"], &["--out=x.csv"], &["--output", "x.csv"], &["-xo", "x.csv"]] {
+		assert!(!apply(given).contains(&"--output=config.csv".to_string()), "{:?} should override the configuration", given);
+	}
+
+	// In a cluster of short options, what comes after one that takes a value is its value, not more options.
+	assert!(apply(&["-fo"]).contains(&"--output=config.csv".to_string()));
+	assert!(!apply(&["-x"]).contains(&"--excel".to_string()));
+
+	// Environment variables only matter for options that can be given in them.
+	std::env::set_var("SHOPSITE_CLI_TEST_TOKEN", "env token");
+	std::env::set_var("TOOL_PLAIN", "env plain");
+	let args = apply(&[]);
+	std::env::remove_var("SHOPSITE_CLI_TEST_TOKEN");
+	std::env::remove_var("TOOL_PLAIN");
+	assert!(!args.contains(&"--token=config token".to_string()));
+	assert!(args.contains(&"--plain=config plain".to_string()));
+}
@@ -0,0 +1,183 @@
+//! Writing CSV reports, optionally in a form that Microsoft Excel opens correctly.
+//! 
+//! Excel is picky about CSV. Left to its own devices, it decodes CSV files in the system's legacy code page, not UTF-8, so names like “Café” come out garbled, unless the file starts with a byte order mark. It also evaluates cells that look like formulas, which is dangerous when the cells come from customer-supplied data like order notes. `--excel` mode takes care of both.
+
+use std::{
+	fmt::Display,
+	io::Write,
+	path::Path,
+	str::FromStr
+};
+use structopt::StructOpt;
+use super::{
+	open_output,
+	Result
+};
+
+/// Character encodings that CSV output can be written in.
+#[derive(Clone, Copy, Eq, PartialEq)]
+pub enum OutputEncoding {
+	Utf8,
+
+	/// The encoding used by ShopSite, and by Excel on most Western European and American systems. Characters that it can't represent are written as `?`.
+	Windows1252
+}
+
+impl FromStr for OutputEncoding {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s {
+			"utf-8" => Ok(OutputEncoding::Utf8),
+			"windows-1252" => Ok(OutputEncoding::Windows1252),
+			_ => Err(format!("unrecognized encoding “{}”; expected utf-8 or windows-1252", s))
+		}
+	}
+}
+
+/// Options for CSV output, shared by every command that writes CSV.
+#[derive(StructOpt)]
+pub struct CsvOpts {
+	/// Write CSV meant to be opened in Microsoft Excel. Cells that Excel would take for formulas, like `=1+1`, are prefixed with `'` so that they're shown as text instead of being evaluated. With UTF-8 encoding, the file starts with a byte order mark, so that Excel knows it's UTF-8. With Windows-1252 encoding, the file starts with a line like `sep=,` instead, so that Excel splits cells in the right places no matter what its list separator is set to. (Excel ignores the byte order mark if there's a `sep=` line, so it can't have both.)
+	#[structopt(long)]
+	excel: bool,
+
+	/// Character encoding to write CSV in: `utf-8` or `windows-1252`.
+	#[structopt(long, default_value = "utf-8", possible_values = &["utf-8", "windows-1252"])]
+	encoding: OutputEncoding,
+
+	/// Character to write between the whole and fractional parts of numbers, like `,` for `9,99`. If this is `,`, then cells are separated with `;` instead of `,`, as is usual where the decimal comma is used.
+	#[structopt(long, default_value = ".")]
+	decimal_separator: char,
+
+	/// Character to write between groups of thousands in numbers, like `.` for `1.234,50`. By default, there is none.
+	#[structopt(long)]
+	thousands_separator: Option<char>
+}
+
+/// Writes CSV, according to some `CsvOpts`.
+pub struct CsvWriter {
+	writer: csv::Writer<Box<dyn Write>>,
+	excel: bool,
+	encoding: OutputEncoding
+}
+
+impl CsvOpts {
+	/// The character that separates cells.
+	fn delimiter(&self) -> u8 {
+		if self.decimal_separator == ',' {
+			b';'
+		}
+		else {
+			b','
+		}
+	}
+
+	/// Formats a number for a cell, with the separators chosen in these options. `number` must display as plain decimal notation, like `-1234.5`; integers, `Money`, and `format!`ted floats all do.
+	/// 
+	/// Only cells that are known to be numbers should be formatted this way. Anything that merely looks like a number, like an order number or a SKU, should be left alone.
+	pub fn number(&self, number: impl Display) -> String {
+		let number = number.to_string();
+
+		let (sign, unsigned) = match number.strip_prefix('-') {
+			Some(unsigned) => ("-", unsigned),
+			None => ("", &number[..])
+		};
+
+		let (whole, fraction) = match unsigned.split_once('.') {
+			Some((whole, fraction)) => (whole, Some(fraction)),
+			None => (unsigned, None)
+		};
+
+		// If it's not plain decimal notation after all, leave it be.
+		if whole.is_empty() || !whole.bytes().all(|b| b.is_ascii_digit()) || !fraction.unwrap_or_default().bytes().all(|b| b.is_ascii_digit()) {
+			return number
+		}
+
+		let mut result = String::from(sign);
+
+		for (index, digit) in whole.chars().enumerate() {
+			if index != 0 && (whole.len() - index) % 3 == 0 {
+				result.extend(self.thousands_separator);
+			}
+
+			result.push(digit);
+		}
+
+		if let Some(fraction) = fraction {
+			result.push(self.decimal_separator);
+			result.push_str(fraction);
+		}
+
+		result
+	}
+
+	/// Opens a file to write CSV to, as with `open_output`, and writes whatever has to come before the first record.
+	pub fn create(&self, path: Option<&Path>) -> Result<CsvWriter> {
+		if Some(self.decimal_separator) == self.thousands_separator {
+			return Err("the decimal separator must be different from the thousands separator".into())
+		}
+
+		let mut output = open_output(path)?;
+
+		if self.excel {
+			match self.encoding {
+				OutputEncoding::Utf8 => output.write_all("\u{feff}".as_bytes())?,
+				OutputEncoding::Windows1252 => write!(output, "sep={}\r\n", self.delimiter() as char)?
+			}
+		}
+
+		let mut builder = csv::WriterBuilder::new();
+		builder.delimiter(self.delimiter());
+
+		if self.excel {
+			// Excel is a Windows program, and Windows line endings are what it writes itself.
+			builder.terminator(csv::Terminator::CRLF);
+		}
+
+		Ok(CsvWriter {
+			writer: builder.from_writer(output),
+			excel: self.excel,
+			encoding: self.encoding
+		})
+	}
+}
+
+/// Whether Excel would treat a cell as a formula, or something like one. That's anything beginning with `=`, `+`, `-`, or `@`, which Excel (or Lotus 1-2-3, before it) treats as the start of a formula, or with a tab or carriage return, which can hide one. Numbers like `-5` and `+1.5` are left alone, since Excel reads those as numbers anyway.
+pub fn looks_like_formula(cell: &str) -> bool {
+	cell.starts_with(['=', '+', '-', '@', '\t', '\r']) && cell.parse::<f64>().is_err()
+}
+
+impl CsvWriter {
+	/// Writes one record, escaping and encoding each cell as needed.
+	pub fn write_record<I, T>(&mut self, record: I) -> Result<()>
+	where I: IntoIterator<Item = T>, T: AsRef<str> {
+		let (excel, encoding) = (self.excel, self.encoding);
+
+		self.writer.write_record(record.into_iter().map(|cell| {
+			let cell = cell.as_ref();
+
+			let cell = {
+				if excel && looks_like_formula(cell) {
+					format!("'{}", cell)
+				}
+				else {
+					cell.to_string()
+				}
+			};
+
+			match encoding {
+				OutputEncoding::Utf8 => cell.into_bytes(),
+				// The CSV syntax itself is all ASCII, which is the same in Windows-1252, so the cells can be encoded separately.
+				OutputEncoding::Windows1252 => encoding::Encoding::encode(encoding::all::WINDOWS_1252, &cell, encoding::EncoderTrap::Replace).unwrap_or_default()
+			}
+		}))?;
+
+		Ok(())
+	}
+
+	pub fn flush(&mut self) -> Result<()> {
+		self.writer.flush()?;
+		Ok(())
+	}
+}
@@ -0,0 +1,137 @@
+//! GraphQL schema for the `serve` command, available with the `graphql` feature.
+//! 
+//! The schema looks like this:
+//! 
+//! ```graphql
+//! type Query {
+//!   products(search: String): [Record!]!
+//!   product(sku: String!): Record
+//!   orders(from: String, to: String): [Record!]!
+//!   priceHistory(sku: String!): [PriceChange!]!
+//! }
+//! 
+//! type Record {
+//!   field(name: String!): String
+//!   fields: [Field!]!
+//! }
+//! 
+//! type Field {
+//!   name: String!
+//!   value: String!
+//! }
+//! 
+//! type PriceChange {
+//!   date: String!
+//!   price: String!
+//! }
+//! ```
+//! 
+//! Products and orders are `Record`s rather than types with a field for each column, because the columns in an export depend on how the export was configured in ShopSite. Dates are `YYYY-MM-DD` strings.
+
+use async_graphql::{
+	Context,
+	EmptyMutation,
+	EmptySubscription,
+	Object,
+	SimpleObject
+};
+use chrono::NaiveDate;
+use std::sync::Arc;
+use super::store::{Row, Store};
+
+pub type ApiSchema = async_graphql::Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Creates the schema, serving data from `store`.
+pub fn schema(store: Arc<Store>) -> ApiSchema {
+	async_graphql::Schema::build(Query, EmptyMutation, EmptySubscription)
+		.data(store)
+		.finish()
+}
+
+/// Executes a GraphQL request, given as JSON, and returns the JSON response.
+pub fn execute(schema: &ApiSchema, request_json: &str) -> serde_json::Value {
+	let response = match serde_json::from_str::<async_graphql::Request>(request_json) {
+		Ok(request) => pollster::block_on(schema.execute(request)),
+		Err(error) => async_graphql::Response::from_errors(vec![async_graphql::ServerError::new(format!("malformed GraphQL request: {}", error), None)])
+	};
+
+	serde_json::to_value(response).unwrap_or_default()
+}
+
+/// One product or order.
+pub struct Record {
+	fields: Vec<Field>
+}
+
+impl From<Row<'_>> for Record {
+	fn from(row: Row) -> Self {
+		Record {
+			fields: row.iter().map(|(name, value)| Field { name: name.to_string(), value: value.to_string() }).collect()
+		}
+	}
+}
+
+#[Object]
+impl Record {
+	/// Value of the named field (that is, column of the export), if there is such a field.
+	async fn field(&self, name: String) -> Option<&str> {
+		self.fields.iter().find(|field| field.name == name).map(|field| &field.value[..])
+	}
+
+	/// All fields, in the order they appear in the export.
+	async fn fields(&self) -> &[Field] {
+		&self.fields
+	}
+}
+
+/// A field of a record, and its value.
+#[derive(Clone, SimpleObject)]
+pub struct Field {
+	name: String,
+	value: String
+}
+
+/// A product's price, as of some date.
+#[derive(SimpleObject)]
+pub struct PriceChange {
+	date: String,
+	price: String
+}
+
+pub struct Query;
+
+fn parse_date(date: Option<String>) -> async_graphql::Result<Option<NaiveDate>> {
+	date.map(|date| date.parse::<NaiveDate>().map_err(|_| format!("invalid date “{}”; expected YYYY-MM-DD", date).into())).transpose()
+}
+
+#[Object]
+impl Query {
+	/// All products, or only those with a field containing `search`, ignoring letter case.
+	async fn products(&self, ctx: &Context<'_>, search: Option<String>) -> Vec<Record> {
+		let store = ctx.data_unchecked::<Arc<Store>>();
+
+		match search {
+			Some(search) => store.search_products(&search).map(Record::from).collect(),
+			None => store.products().map(Record::from).collect()
+		}
+	}
+
+	/// The product with the given SKU.
+	async fn product(&self, ctx: &Context<'_>, sku: String) -> Option<Record> {
+		ctx.data_unchecked::<Arc<Store>>().product(&sku).map(Record::from)
+	}
+
+	/// Orders placed between `from` and `to`, inclusive. Either may be omitted.
+	async fn orders(&self, ctx: &Context<'_>, from: Option<String>, to: Option<String>) -> async_graphql::Result<Vec<Record>> {
+		let (from, to) = (parse_date(from)?, parse_date(to)?);
+		Ok(ctx.data_unchecked::<Arc<Store>>().orders_between(from, to).map(Record::from).collect())
+	}
+
+	/// Recorded price changes of the product with the given SKU, oldest first.
+	async fn price_history(&self, ctx: &Context<'_>, sku: String) -> Vec<PriceChange> {
+		ctx.data_unchecked::<Arc<Store>>()
+		.price_history(&sku)
+		.map(|change| PriceChange { date: change.date.to_string(), price: change.price.to_string() })
+		.collect()
+	}
+}
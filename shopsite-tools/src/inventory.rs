@@ -0,0 +1,174 @@
+//! The `inventory-diff` command: compares the stock levels in two product exports.
+
+use std::{
+	collections::HashMap,
+	path::PathBuf
+};
+use structopt::StructOpt;
+use super::{
+	csv_output::CsvOpts,
+	table::Table,
+	Result
+};
+
+#[derive(StructOpt)]
+pub struct InventoryDiffOpts {
+	/// Product export taken at the start of the period.
+	#[structopt(name = "OLD")]
+	old: PathBuf,
+
+	/// Product export taken at the end of the period.
+	#[structopt(name = "NEW")]
+	new: PathBuf,
+
+	/// Length of the period between the two exports, in days. Used to calculate how fast each product is selling.
+	#[structopt(short, long)]
+	days: f64,
+
+	/// Name of the column containing each product's SKU.
+	#[structopt(long, default_value = "SKU")]
+	sku_field: String,
+
+	/// Name of the column containing each product's name.
+	#[structopt(long, default_value = "Name")]
+	name_field: String,
+
+	/// Name of the column containing the quantity on hand.
+	#[structopt(long, default_value = "QuantityOnHand")]
+	quantity_field: String,
+
+	/// Days it takes for a reorder to arrive. Products that will run out sooner than this are suggested for reordering.
+	#[structopt(long, default_value = "14")]
+	lead_time_days: f64,
+
+	/// Days of sales that a reorder should cover, after it arrives.
+	#[structopt(long, default_value = "30")]
+	cover_days: f64,
+
+	/// Products selling fewer than this many units per day are never suggested for reordering.
+	#[structopt(long, default_value = "0")]
+	min_velocity: f64,
+
+	/// CSV file to write the sold/restocked report to, instead of standard output.
+	#[structopt(short, long)]
+	output: Option<PathBuf>,
+
+	/// CSV file to write reorder suggestions to. If omitted, no reorder suggestions are made.
+	#[structopt(short, long)]
+	reorder_output: Option<PathBuf>,
+
+	#[structopt(flatten)]
+	csv: CsvOpts
+}
+
+/// Stock level of one product, as found in one export.
+struct Stock {
+	name: String,
+
+	/// Quantity on hand. `None` if inventory isn't tracked for this product.
+	quantity: Option<i64>
+}
+
+/// Reads the stock levels of every product in an export, keyed by SKU.
+fn read_stock(table: &Table, opts: &InventoryDiffOpts) -> Result<HashMap<String, Stock>> {
+	let sku_col = table.column(&opts.sku_field)?;
+	let name_col = table.column(&opts.name_field)?;
+	let quantity_col = table.column(&opts.quantity_field)?;
+
+	let mut stock = HashMap::with_capacity(table.rows.len());
+
+	for (row_index, row) in table.rows.iter().enumerate() {
+		let sku = row[sku_col].trim();
+
+		if sku.is_empty() {
+			// Products without a SKU can't be matched up between exports, so there's nothing to be done with them.
+			continue
+		}
+
+		let quantity = row[quantity_col].trim();
+		let quantity = {
+			if quantity.is_empty() {
+				None
+			}
+			else {
+				// Row numbers are 1-based, and the header is row 1.
+				Some(quantity.parse::<i64>().map_err(|error| format!("{}: row {}: invalid quantity “{}”: {}", table.path.display(), row_index + 2, quantity, error))?)
+			}
+		};
+
+		stock.insert(sku.to_string(), Stock {
+			name: row[name_col].clone(),
+			quantity
+		});
+	}
+
+	Ok(stock)
+}
+
+pub fn run(opts: InventoryDiffOpts) -> Result<()> {
+	if opts.days.is_nan() || opts.days <= 0.0 {
+		return Err("the number of days between exports must be positive".into())
+	}
+
+	let old = read_stock(&Table::read(&opts.old)?, &opts)?;
+	let new = read_stock(&Table::read(&opts.new)?, &opts)?;
+
+	// Report in a stable order, so that reports from different runs can be compared.
+	let mut skus: Vec<&String> = old.keys().chain(new.keys().filter(|sku| !old.contains_key(*sku))).collect();
+	skus.sort();
+
+	let mut report = opts.csv.create(opts.output.as_deref())?;
+	report.write_record(["SKU", "Name", "Old Quantity", "New Quantity", "Sold", "Restocked"])?;
+
+	let mut reorders = Vec::new();
+
+	for sku in skus {
+		let (old_stock, new_stock) = (old.get(sku), new.get(sku));
+		let old_qty = old_stock.and_then(|s| s.quantity);
+		let new_qty = new_stock.and_then(|s| s.quantity);
+		let name = new_stock.or(old_stock).map(|s| &s.name[..]).unwrap_or_default();
+
+		let (sold, restocked) = match (old_qty, new_qty) {
+			(Some(old_qty), Some(new_qty)) if new_qty < old_qty => (old_qty - new_qty, 0),
+			(Some(old_qty), Some(new_qty)) => (0, new_qty - old_qty),
+			// A product that appeared during the period was stocked from nothing.
+			(None, Some(new_qty)) if old_stock.is_none() => (0, new_qty.max(0)),
+			// A product that disappeared during the period is still worth mentioning, but it's unknown whether the stock was sold or just written off.
+			(Some(_), None) if new_stock.is_none() => (0, 0),
+			// Inventory isn't tracked for this product, at least not in both exports. There's nothing to compare.
+			_ => continue
+		};
+
+		let format_qty = |q: Option<i64>| q.map(|q| opts.csv.number(q)).unwrap_or_default();
+		report.write_record([sku, name, &format_qty(old_qty), &format_qty(new_qty), &opts.csv.number(sold), &opts.csv.number(restocked)])?;
+
+		// Now, decide whether this product needs reordering. Products that were removed from the store don't, obviously.
+		if let Some(on_hand) = new_qty {
+			let velocity = sold as f64 / opts.days;
+
+			if velocity > 0.0 && velocity >= opts.min_velocity {
+				let days_of_cover = on_hand.max(0) as f64 / velocity;
+
+				if days_of_cover < opts.lead_time_days {
+					let suggested = (velocity * (opts.lead_time_days + opts.cover_days)).ceil() as i64 - on_hand.max(0);
+					reorders.push([sku.clone(), name.to_string(), opts.csv.number(on_hand), opts.csv.number(format!("{:.2}", velocity)), opts.csv.number(format!("{:.1}", days_of_cover)), opts.csv.number(suggested)]);
+				}
+			}
+		}
+	}
+
+	report.flush()?;
+
+	if let Some(ref reorder_output) = opts.reorder_output {
+		let mut reorder_report = opts.csv.create(Some(reorder_output))?;
+		reorder_report.write_record(["SKU", "Name", "On Hand", "Sold Per Day", "Days Of Cover", "Suggested Quantity"])?;
+
+		for reorder in reorders {
+			reorder_report.write_record(reorder)?;
+		}
+
+		reorder_report.flush()?;
+	}
+
+	Ok(())
+}
@@ -0,0 +1,149 @@
+//! The `join` command: adds product details to each line of an order export.
+
+use std::{
+	collections::HashMap,
+	fs,
+	path::PathBuf
+};
+use structopt::StructOpt;
+use super::{
+	csv_output::CsvOpts,
+	sku_map::read_sku_map,
+	table::TableReader,
+	Result
+};
+
+#[derive(StructOpt)]
+pub struct JoinOpts {
+	/// Product export to take product details from.
+	#[structopt(name = "PRODUCTS")]
+	products: PathBuf,
+
+	/// Order export to add product details to.
+	#[structopt(name = "ORDERS")]
+	orders: PathBuf,
+
+	/// Columns of the product export to add to each order line, like `Name,Price,Category`. Each is added as a column named `Product` followed by a space and the column's name. By default, these are the columns that every product export has: `Name` and `Price`.
+	#[structopt(long, default_value = "Name,Price", require_delimiter = true)]
+	add: Vec<String>,
+
+	/// Name of the column containing each product's SKU, in the product export.
+	#[structopt(long, default_value = "SKU")]
+	sku_field: String,
+
+	/// Name of the column containing the SKU of the product ordered, in the order export.
+	#[structopt(long, default_value = "SKU")]
+	order_sku_field: String,
+
+	/// SKU mapping file, as written by the `sku-map` command. Order lines for old SKUs listed in it get the details of the product with the corresponding new SKU. The file's name must end in `.csv`.
+	#[structopt(long)]
+	sku_map: Option<PathBuf>,
+
+	/// CSV file to write the enriched order lines to, instead of standard output.
+	#[structopt(short, long)]
+	output: Option<PathBuf>,
+
+	#[structopt(flatten)]
+	csv: CsvOpts
+}
+
+/// Columns of interest in the product export.
+struct ProductColumns {
+	sku: usize,
+	add: Vec<usize>
+}
+
+impl ProductColumns {
+	fn find(products: &TableReader, opts: &JoinOpts) -> Result<ProductColumns> {
+		Ok(ProductColumns {
+			sku: products.column(&opts.sku_field)?,
+			add: opts.add.iter().map(|field| products.column(field)).collect::<Result<_>>()?
+		})
+	}
+
+	/// Picks out the cells to add to order lines from a product row.
+	fn details(&self, mut product: Vec<String>) -> Vec<String> {
+		self.add.iter().map(|col| std::mem::take(&mut product[*col])).collect()
+	}
+}
+
+pub fn run(opts: JoinOpts) -> Result<()> {
+	let mut products = TableReader::open(&opts.products)?;
+	let mut orders = TableReader::open(&opts.orders)?;
+
+	let product_cols = ProductColumns::find(&products, &opts)?;
+	let order_sku_col = orders.column(&opts.order_sku_field)?;
+	let sku_map = opts.sku_map.as_deref().map(read_sku_map).transpose()?.unwrap_or_default();
+
+	// Looks up the SKU of the product ordered on an order line, translated through the SKU map.
+	let order_sku = |order: &[String]| {
+		let sku = order[order_sku_col].trim();
+		sku_map.get(sku).map_or(sku, String::as_str).to_string()
+	};
+
+	let mut output = opts.csv.create(opts.output.as_deref())?;
+	output.write_record(orders.headers.iter().cloned().chain(opts.add.iter().map(|field| format!("Product {}", field))))?;
+
+	// Order lines for products that aren't in the product export get empty cells.
+	let no_details = vec![String::new(); opts.add.len()];
+
+	// Only one of the two exports has to be held in memory, and it might as well be the smaller one. Either way, the output is in the same order as the order export.
+	let size = |path: &PathBuf| fs::metadata(path).map(|m| m.len()).unwrap_or(u64::MAX);
+
+	if size(&opts.products) <= size(&opts.orders) {
+		// Load the product details, then stream the orders past them.
+		let mut details = HashMap::new();
+
+		for product in products.rows() {
+			let product = product?;
+			let sku = product[product_cols.sku].trim().to_string();
+
+			// If a SKU appears more than once, the first one wins, since that's the one ShopSite finds when looking up a SKU.
+			if !sku.is_empty() && !details.contains_key(&sku) {
+				details.insert(sku, product_cols.details(product));
+			}
+		}
+
+		for order in orders.rows() {
+			let order = order?;
+			let details = details.get(&order_sku(&order)).unwrap_or(&no_details);
+			output.write_record(order.iter().chain(details))?;
+		}
+	}
+	else {
+		// Load the orders, indexed by SKU, then stream the products past them, filling in details as matching products come by.
+		let mut lines: Vec<(Vec<String>, Option<Vec<String>>)> = Vec::new();
+		let mut lines_by_sku: HashMap<String, Vec<usize>> = HashMap::new();
+
+		for order in orders.rows() {
+			let order = order?;
+			lines_by_sku.entry(order_sku(&order)).or_default().push(lines.len());
+			lines.push((order, None));
+		}
+
+		for product in products.rows() {
+			let product = product?;
+			let sku = product[product_cols.sku].trim();
+
+			if sku.is_empty() {
+				continue
+			}
+
+			// As above, the first product with a given SKU wins. Removing the SKU from the index makes sure of that.
+			if let Some(indices) = lines_by_sku.remove(sku) {
+				let details = product_cols.details(product);
+
+				for index in indices {
+					lines[index].1 = Some(details.clone());
+				}
+			}
+		}
+
+		for (order, details) in &lines {
+			output.write_record(order.iter().chain(details.as_ref().unwrap_or(&no_details)))?;
+		}
+	}
+
+	output.flush()?;
+	Ok(())
+}
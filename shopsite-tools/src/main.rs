@@ -0,0 +1,85 @@
+use std::{
+	fs::OpenOptions,
+	io::{self, Write},
+	path::Path,
+	process::exit
+};
+use structopt::StructOpt;
+
+mod csv_output;
+mod email;
+#[cfg(feature = "graphql")]
+mod graphql;
+mod inventory;
+mod join;
+mod price_history;
+mod report;
+mod serve;
+mod sku_map;
+mod store;
+mod table;
+
+pub type Result<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+#[derive(StructOpt)]
+#[structopt(
+	about = "Tools for working with data exported from ShopSite.",
+	after_help = "Options can also be given in a configuration file, in the `[shopsite-tools.COMMAND]` table of `shopsite-utils/config.toml` in the user or system configuration directory. Options given on the command line or in environment variables take precedence.",
+	rename_all = "kebab-case"
+)]
+enum Command {
+	/// Renders an email for each order in an order export, from templates, and writes them to files or sends them.
+	Email(email::EmailOpts),
+
+	/// Compares the stock levels in two product exports, reporting what sold and what was restocked in between, and suggesting what to reorder.
+	InventoryDiff(inventory::InventoryDiffOpts),
+
+	/// Adds product details, like each product's current name and price, to every line of an order export.
+	Join(join::JoinOpts),
+
+	/// Records and shows the history of product prices.
+	PriceHistory(price_history::PriceHistoryCommand),
+
+	/// Summarizes exported data, for checking against ShopSite's own reports.
+	Report(report::ReportCommand),
+
+	/// Serves product, order, and price history data over a small, read-only HTTP API, for use by internal dashboards and such.
+	Serve(serve::ServeOpts),
+
+	/// Compares two product exports and suggests which old SKUs were renamed to which new ones, by matching up products with similar names. For use when a catalog has been renumbered.
+	SkuMap(sku_map::SkuMapOpts)
+}
+
+/// Opens a file for writing, truncating it if it already exists. If `path` is `None`, opens standard output instead.
+pub fn open_output(path: Option<&Path>) -> Result<Box<dyn Write>> {
+	if let Some(path) = path {
+		let fh = OpenOptions::new()
+			.create(true)
+			.write(true)
+			.truncate(true)
+			.open(path)
+			.map_err(|error| format!("Error opening output file {}: {}", path.display(), error))?;
+
+		Ok(Box::new(io::BufWriter::new(fh)))
+	}
+	else {
+		Ok(Box::new(io::stdout()))
+	}
+}
+
+fn main() {
+	let result = match shopsite_cli::from_args::<Command>() {
+		Command::Email(opts) => email::run(opts),
+		Command::InventoryDiff(opts) => inventory::run(opts),
+		Command::Join(opts) => join::run(opts),
+		Command::PriceHistory(command) => price_history::run(command),
+		Command::Report(command) => report::run(command),
+		Command::Serve(opts) => serve::run(opts),
+		Command::SkuMap(opts) => sku_map::run(opts)
+	};
+
+	if let Err(error) = result {
+		eprintln!("{}", error);
+		exit(1);
+	}
+}
@@ -0,0 +1,210 @@
+//! The `price-history` commands: keeps a record of every product's price over time.
+//! 
+//! The history is stored in a CSV file with columns `Date`, `SKU`, and `Price`. A row is added only when a product's price differs from the last price recorded for it, so the file stays small even when exports are recorded daily.
+
+use chrono::{Local, NaiveDate};
+use shopsite_aa::money::Money;
+use std::{
+	collections::HashMap,
+	fs::{File, OpenOptions},
+	io::{self, Write},
+	path::{Path, PathBuf}
+};
+use structopt::StructOpt;
+use super::{
+	csv_output::CsvOpts,
+	open_output,
+	table::Table,
+	Result
+};
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum PriceHistoryCommand {
+	/// Records the prices in a product export, adding an entry for every product whose price has changed since it was last recorded.
+	Record(RecordOpts),
+
+	/// Shows the recorded price history of one or all products.
+	Show(ShowOpts)
+}
+
+#[derive(StructOpt)]
+pub struct HistoryFileOpts {
+	/// CSV file in which price history is kept.
+	#[structopt(long = "history", default_value = "price-history.csv")]
+	path: PathBuf
+}
+
+#[derive(StructOpt)]
+pub struct RecordOpts {
+	#[structopt(flatten)]
+	history: HistoryFileOpts,
+
+	/// Date that the export was taken, as `YYYY-MM-DD`. Defaults to today.
+	#[structopt(long)]
+	date: Option<NaiveDate>,
+
+	/// Name of the column containing each product's SKU.
+	#[structopt(long, default_value = "SKU")]
+	sku_field: String,
+
+	/// Name of the column containing each product's price.
+	#[structopt(long, default_value = "Price")]
+	price_field: String,
+
+	/// Product export to record prices from.
+	#[structopt(name = "EXPORT")]
+	export: PathBuf
+}
+
+#[derive(StructOpt)]
+pub struct ShowOpts {
+	#[structopt(flatten)]
+	history: HistoryFileOpts,
+
+	/// Write CSV instead of a human-readable table.
+	#[structopt(long)]
+	csv: bool,
+
+	#[structopt(flatten)]
+	csv_output: CsvOpts,
+
+	/// File to write to, instead of standard output.
+	#[structopt(short, long)]
+	output: Option<PathBuf>,
+
+	/// SKU of the product to show the price history of. If omitted, shows the history of all products.
+	#[structopt(name = "SKU")]
+	sku: Option<String>
+}
+
+/// One row of the price history: a product's price as of some date.
+pub struct PriceChange {
+	pub date: NaiveDate,
+	pub sku: String,
+	pub price: Money
+}
+
+const HEADERS: [&str; 3] = ["Date", "SKU", "Price"];
+
+/// Reads the entire price history, in the order it was recorded. A missing history file is treated as an empty history.
+pub fn read_history(path: &Path) -> Result<Vec<PriceChange>> {
+	let fh = match File::open(path) {
+		Ok(fh) => fh,
+		Err(ref error) if error.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+		Err(error) => return Err(format!("{}: {}", path.display(), error).into())
+	};
+
+	let mut history = Vec::new();
+
+	for (row_index, record) in csv::Reader::from_reader(fh).into_records().enumerate() {
+		let context = |error: &dyn std::fmt::Display| format!("{}: row {}: {}", path.display(), row_index + 2, error);
+		let record = record.map_err(|error| context(&error))?;
+
+		history.push(PriceChange {
+			date: record.get(0).unwrap_or_default().parse().map_err(|error| context(&error))?,
+			sku: record.get(1).unwrap_or_default().to_string(),
+			price: record.get(2).unwrap_or_default().parse().map_err(|error| context(&error))?
+		});
+	}
+
+	Ok(history)
+}
+
+fn record(opts: RecordOpts) -> Result<()> {
+	let path = &opts.history.path;
+	let history = read_history(path)?;
+
+	// Find the latest recorded price of each product.
+	let mut latest: HashMap<&str, Money> = HashMap::new();
+	for change in &history {
+		latest.insert(&change.sku, change.price);
+	}
+
+	let export = Table::read(&opts.export)?;
+	let sku_col = export.column(&opts.sku_field)?;
+	let price_col = export.column(&opts.price_field)?;
+	let date = opts.date.unwrap_or_else(|| Local::now().date_naive());
+
+	let mut changes = Vec::new();
+
+	for (row_index, row) in export.rows.iter().enumerate() {
+		let (sku, price) = (row[sku_col].trim(), row[price_col].trim());
+
+		if sku.is_empty() || price.is_empty() {
+			continue
+		}
+
+		let price: Money = price.parse().map_err(|error| format!("{}: row {}: invalid price “{}”: {}", export.path.display(), row_index + 2, price, error))?;
+
+		if latest.get(sku) != Some(&price) {
+			changes.push(PriceChange { date, sku: sku.to_string(), price });
+		}
+	}
+
+	if changes.is_empty() {
+		return Ok(())
+	}
+
+	// Append the changes to the history file, writing the headers first if the file is new.
+	let fh = OpenOptions::new()
+		.create(true)
+		.append(true)
+		.open(path)
+		.map_err(|error| format!("{}: {}", path.display(), error))?;
+	let is_new = fh.metadata()?.len() == 0;
+
+	let mut writer = csv::Writer::from_writer(fh);
+
+	if is_new {
+		writer.write_record(HEADERS)?;
+	}
+
+	for change in changes {
+		writer.write_record([change.date.to_string(), change.sku, change.price.to_string()])?;
+	}
+
+	writer.flush()?;
+	Ok(())
+}
+
+fn show(opts: ShowOpts) -> Result<()> {
+	let history = read_history(&opts.history.path)?;
+	let history = history.iter().filter(|change| opts.sku.as_ref().is_none_or(|sku| *sku == change.sku));
+	if opts.csv {
+		let mut writer = opts.csv_output.create(opts.output.as_deref())?;
+		writer.write_record(HEADERS)?;
+
+		for change in history {
+			writer.write_record([change.date.to_string(), change.sku.clone(), opts.csv_output.number(change.price)])?;
+		}
+
+		writer.flush()?;
+	}
+	else {
+		let mut output = open_output(opts.output.as_deref())?;
+		let mut any = false;
+
+		for change in history {
+			writeln!(output, "{}  {:<20}  {:>12}", change.date, change.sku, change.price)?;
+			any = true;
+		}
+
+		if !any {
+			if let Some(ref sku) = opts.sku {
+				return Err(format!("no price history for SKU “{}”", sku).into())
+			}
+		}
+
+		output.flush()?;
+	}
+
+	Ok(())
+}
+
+pub fn run(command: PriceHistoryCommand) -> Result<()> {
+	match command {
+		PriceHistoryCommand::Record(opts) => record(opts),
+		PriceHistoryCommand::Show(opts) => show(opts)
+	}
+}
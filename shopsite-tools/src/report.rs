@@ -0,0 +1,295 @@
+//! The `report` commands: summaries of exported data, for checking against ShopSite's own reports.
+
+use chrono::NaiveDate;
+use serde_json::{json, Value};
+use shopsite_aa::money::Money;
+use std::{
+	collections::{BTreeMap, HashMap},
+	io::Write,
+	path::PathBuf,
+	str::FromStr
+};
+use structopt::StructOpt;
+use super::{
+	csv_output::CsvOpts,
+	open_output,
+	table::{Table, TableReader},
+	Result
+};
+
+#[derive(StructOpt)]
+#[structopt(rename_all = "kebab-case")]
+pub enum ReportCommand {
+	/// Totals up the orders in an order export by day, product, or category.
+	Sales(SalesOpts)
+}
+
+/// What to total the orders by.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum GroupBy {
+	Day,
+	Product,
+	Category
+}
+
+impl FromStr for GroupBy {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s {
+			"day" => Ok(GroupBy::Day),
+			"product" => Ok(GroupBy::Product),
+			"category" => Ok(GroupBy::Category),
+			_ => Err(format!("unrecognized grouping “{}”; expected day, product, or category", s))
+		}
+	}
+}
+
+impl GroupBy {
+	/// Heading of the first column of the report, which identifies each group.
+	fn heading(self) -> &'static str {
+		match self {
+			GroupBy::Day => "Day",
+			GroupBy::Product => "SKU",
+			GroupBy::Category => "Category"
+		}
+	}
+}
+
+/// How to write the report.
+#[derive(Clone, Copy, Eq, PartialEq)]
+enum Format {
+	Csv,
+	Json,
+	Markdown
+}
+
+impl FromStr for Format {
+	type Err = String;
+
+	fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+		match s {
+			"csv" => Ok(Format::Csv),
+			"json" => Ok(Format::Json),
+			"markdown" => Ok(Format::Markdown),
+			_ => Err(format!("unrecognized format “{}”; expected csv, json, or markdown", s))
+		}
+	}
+}
+
+#[derive(StructOpt)]
+pub struct SalesOpts {
+	/// Order export to total up.
+	#[structopt(name = "ORDERS")]
+	orders: PathBuf,
+
+	/// Only count orders placed on or after this date, given as `YYYY-MM-DD`.
+	#[structopt(long)]
+	from: Option<NaiveDate>,
+
+	/// Only count orders placed on or before this date, given as `YYYY-MM-DD`.
+	#[structopt(long)]
+	to: Option<NaiveDate>,
+
+	/// What to total the orders by: `day`, `product`, or `category`. Totaling by category needs a product export to look up each product's category in; see `--products`.
+	#[structopt(long, default_value = "day", possible_values = &["day", "product", "category"])]
+	by: GroupBy,
+
+	/// Product export to look up each product's category in.
+	#[structopt(long)]
+	products: Option<PathBuf>,
+
+	/// How to write the report: `csv`, `json`, or `markdown`.
+	#[structopt(long, default_value = "csv", possible_values = &["csv", "json", "markdown"])]
+	format: Format,
+
+	/// Name of the column containing the date each order was placed, in the order export.
+	#[structopt(long, default_value = "Date")]
+	order_date_field: String,
+
+	/// Name of the column containing the SKU of the product ordered, in the order export.
+	#[structopt(long, default_value = "SKU")]
+	order_sku_field: String,
+
+	/// Name of the column containing the quantity ordered, in the order export.
+	#[structopt(long, default_value = "Quantity")]
+	quantity_field: String,
+
+	/// Name of the column containing the total price of each order line, in the order export.
+	#[structopt(long, default_value = "Total")]
+	total_field: String,
+
+	/// Name of the column containing each product's SKU, in the product export.
+	#[structopt(long, default_value = "SKU")]
+	sku_field: String,
+
+	/// Name of the column containing each product's category, in the product export.
+	#[structopt(long, default_value = "Category")]
+	category_field: String,
+
+	/// File to write the report to, instead of standard output.
+	#[structopt(short, long)]
+	output: Option<PathBuf>,
+
+	#[structopt(flatten)]
+	csv: CsvOpts
+}
+
+/// Group name given to orders for products that have no category, or aren't in the product export at all.
+const NO_CATEGORY: &str = "(none)";
+
+/// Running totals for one group of order lines.
+#[derive(Default)]
+struct Totals {
+	lines: u64,
+	quantity: i64,
+	total: Money
+}
+
+impl Totals {
+	fn add(&mut self, quantity: i64, total: Money) {
+		self.lines += 1;
+		self.quantity += quantity;
+		self.total = Money(self.total.0 + total.0);
+	}
+
+	fn cells(&self) -> [String; 3] {
+		[self.lines.to_string(), self.quantity.to_string(), self.total.to_string()]
+	}
+
+	fn to_json(&self) -> Value {
+		json!({
+			"lines": self.lines,
+			"quantity": self.quantity,
+			"total": self.total
+		})
+	}
+}
+
+/// Reads the category of every product in a product export, keyed by SKU. As in the `join` command, if a SKU appears more than once, the first one wins.
+fn read_categories(opts: &SalesOpts) -> Result<HashMap<String, String>> {
+	let products = match opts.products {
+		Some(ref products) => Table::read(products)?,
+		None => return Err("totaling sales by category needs a product export; use `--products` to give one".into())
+	};
+
+	let sku_col = products.column(&opts.sku_field)?;
+	let category_col = products.column(&opts.category_field)?;
+
+	let mut categories = HashMap::with_capacity(products.rows.len());
+
+	for row in products.rows {
+		let sku = row[sku_col].trim();
+
+		if !sku.is_empty() && !categories.contains_key(sku) {
+			categories.insert(sku.to_string(), row[category_col].trim().to_string());
+		}
+	}
+
+	Ok(categories)
+}
+
+fn sales(opts: SalesOpts) -> Result<()> {
+	let categories = match opts.by {
+		GroupBy::Category => read_categories(&opts)?,
+		_ => HashMap::new()
+	};
+
+	let mut orders = TableReader::open(&opts.orders)?;
+	let date_col = orders.column(&opts.order_date_field)?;
+	let sku_col = orders.column(&opts.order_sku_field)?;
+	let quantity_col = orders.column(&opts.quantity_field)?;
+	let total_col = orders.column(&opts.total_field)?;
+	let path = orders.path.clone();
+
+	// Groups are kept sorted by name, which for days means chronological order.
+	let mut groups: BTreeMap<String, Totals> = BTreeMap::new();
+	let mut grand_total = Totals::default();
+
+	for (row_index, row) in orders.rows().enumerate() {
+		let row = row?;
+		let context = |what: &str, value: &str| format!("{}: row {}: invalid {} “{}”", path.display(), row_index + 2, what, value);
+
+		let date = shopsite_aa::datetime::parse(&row[date_col]).ok_or_else(|| context("date", &row[date_col]))?.date();
+
+		if opts.from.is_some_and(|from| date < from) || opts.to.is_some_and(|to| date > to) {
+			continue
+		}
+
+		let quantity = row[quantity_col].trim();
+		let quantity: i64 = if quantity.is_empty() { 0 } else { quantity.parse().map_err(|_| context("quantity", quantity))? };
+
+		let total = row[total_col].trim();
+		let total: Money = if total.is_empty() { Money::default() } else { total.parse().map_err(|_| context("total", total))? };
+
+		let sku = row[sku_col].trim();
+
+		let group = match opts.by {
+			GroupBy::Day => date.to_string(),
+			GroupBy::Product => sku.to_string(),
+			GroupBy::Category => match categories.get(sku) {
+				Some(category) if !category.is_empty() => category.clone(),
+				_ => NO_CATEGORY.to_string()
+			}
+		};
+
+		groups.entry(group).or_default().add(quantity, total);
+		grand_total.add(quantity, total);
+	}
+
+	let heading = opts.by.heading();
+
+	match opts.format {
+		Format::Csv => {
+			let mut writer = opts.csv.create(opts.output.as_deref())?;
+			writer.write_record([heading, "Lines", "Quantity", "Total"])?;
+
+			for (group, totals) in &groups {
+				writer.write_record(std::iter::once(group.clone()).chain(totals.cells().map(|cell| opts.csv.number(cell))))?;
+			}
+
+			writer.write_record(std::iter::once("Total".to_string()).chain(grand_total.cells().map(|cell| opts.csv.number(cell))))?;
+			writer.flush()?;
+		},
+		Format::Json => {
+			let mut output = open_output(opts.output.as_deref())?;
+			let groups: Vec<Value> = groups.iter().map(|(group, totals)| {
+				// The group's name goes first, followed by its totals.
+				let mut value = serde_json::Map::new();
+				value.insert(heading.to_lowercase(), json!(group));
+				value.extend(totals.to_json().as_object().into_iter().flatten().map(|(k, v)| (k.clone(), v.clone())));
+				Value::Object(value)
+			}).collect();
+
+			serde_json::to_writer_pretty(&mut output, &json!({
+				"groups": groups,
+				"total": grand_total.to_json()
+			}))?;
+			writeln!(output)?;
+			output.flush()?;
+		},
+		Format::Markdown => {
+			let mut output = open_output(opts.output.as_deref())?;
+			writeln!(output, "| {} | Lines | Quantity | Total |", heading)?;
+			writeln!(output, "| --- | ---: | ---: | ---: |")?;
+
+			for (group, totals) in &groups {
+				let [lines, quantity, total] = totals.cells();
+				// `|` would end the cell early, so it has to be escaped.
+				writeln!(output, "| {} | {} | {} | {} |", group.replace('|', "\\|"), lines, quantity, total)?;
+			}
+
+			let [lines, quantity, total] = grand_total.cells();
+			writeln!(output, "| **Total** | **{}** | **{}** | **{}** |", lines, quantity, total)?;
+			output.flush()?;
+		}
+	}
+
+	Ok(())
+}
+
+pub fn run(command: ReportCommand) -> Result<()> {
+	match command {
+		ReportCommand::Sales(opts) => sales(opts)
+	}
+}
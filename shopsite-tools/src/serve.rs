@@ -0,0 +1,255 @@
+//! The `serve` command: a small, read-only HTTP API over exported store data.
+//! 
+//! All requests must carry an `Authorization: Bearer TOKEN` header with the token given on the command line. Responses are JSON. The endpoints are:
+//! 
+//! * `GET /products`: All products. With `?q=TEXT`, only products with a field containing `TEXT`, ignoring letter case.
+//! * `GET /products/SKU`: One product.
+//! * `GET /products/SKU/price-history`: Recorded price changes of one product.
+//! * `GET /orders?from=YYYY-MM-DD&to=YYYY-MM-DD`: Orders placed between the given dates, inclusive. Either bound may be omitted. `?date=YYYY-MM-DD` is short for setting both to the same date.
+//! * `POST /graphql`: A GraphQL query, covering all of the above. Only available with the `graphql` feature. See the `graphql` module for the schema.
+//! 
+//! `GET /products` and `GET /orders` also accept `?where=QUERY`, which returns only the records matching an `aaq` query, like `Price < 10 and Name contains widget`. See the `shopsite_aa::query` module for the query syntax.
+
+use chrono::NaiveDate;
+use serde_json::{json, Map, Value};
+use shopsite_aa::query::Query;
+use std::{
+	collections::HashMap,
+	sync::Arc
+};
+use structopt::StructOpt;
+use super::{
+	store::{Row, Store, StoreOpts},
+	Result
+};
+
+#[derive(StructOpt)]
+pub struct ServeOpts {
+	#[structopt(flatten)]
+	store: StoreOpts,
+
+	/// Address and port to listen on.
+	#[structopt(long, default_value = "127.0.0.1:8080")]
+	listen: String,
+
+	/// Token that clients must present in an `Authorization: Bearer` header.
+	#[structopt(long, env = "SHOPSITE_TOOLS_TOKEN", hide_env_values = true)]
+	token: String
+}
+
+/// Converts a table row to a JSON object, with the column names as keys.
+fn row_to_json(row: Row) -> Value {
+	Value::Object(row.iter().map(|(k, v)| (k.to_string(), Value::String(v.to_string()))).collect::<Map<String, Value>>())
+}
+
+/// Decodes `%XX` escapes and `+` signs in a URL query component.
+fn percent_decode(s: &str) -> String {
+	let mut bytes = Vec::with_capacity(s.len());
+	let mut iter = s.bytes();
+
+	while let Some(byte) = iter.next() {
+		match byte {
+			b'+' => bytes.push(b' '),
+			b'%' => {
+				let hex: Vec<u8> = iter.clone().take(2).collect();
+
+				match std::str::from_utf8(&hex).ok().and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+					Some(decoded) if hex.len() == 2 => {
+						bytes.push(decoded);
+						iter.nth(1);
+					},
+					_ => bytes.push(b'%')
+				}
+			},
+			_ => bytes.push(byte)
+		}
+	}
+
+	String::from_utf8_lossy(&bytes).into_owned()
+}
+
+/// Splits a request URL into its decoded path segments and query parameters.
+fn parse_url(url: &str) -> (Vec<String>, HashMap<String, String>) {
+	let (path, query) = match url.find('?') {
+		Some(index) => (&url[..index], &url[index + 1..]),
+		None => (url, "")
+	};
+
+	let segments = path.split('/').filter(|s| !s.is_empty()).map(percent_decode).collect();
+
+	let params = query.split('&').filter(|s| !s.is_empty()).map(|pair| {
+		match pair.find('=') {
+			Some(index) => (percent_decode(&pair[..index]), percent_decode(&pair[index + 1..])),
+			None => (percent_decode(pair), String::new())
+		}
+	}).collect();
+
+	(segments, params)
+}
+
+/// Compares two strings in time that depends only on their lengths, so that the token can't be guessed by timing responses.
+fn constant_time_eq(a: &str, b: &str) -> bool {
+	a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0u8, |acc, (a, b)| acc | (a ^ b)) == 0
+}
+
+fn error(status: u16, message: &str) -> (u16, Value) {
+	(status, json!({ "error": message }))
+}
+
+fn parse_date_param(params: &HashMap<String, String>, name: &str) -> std::result::Result<Option<NaiveDate>, (u16, Value)> {
+	params.get(name)
+	.map(|date| date.parse::<NaiveDate>().map_err(|_| error(400, &format!("invalid date “{}”; expected YYYY-MM-DD", date))))
+	.transpose()
+}
+
+fn parse_where_param(params: &HashMap<String, String>) -> std::result::Result<Option<Query>, (u16, Value)> {
+	params.get("where")
+	.map(|query| Query::parse(query).map_err(|e| error(400, &e.to_string())))
+	.transpose()
+}
+
+/// The API: the data being served, and everything needed to serve it.
+pub struct Api {
+	store: Arc<Store>,
+	token: String,
+
+	#[cfg(feature = "graphql")]
+	schema: super::graphql::ApiSchema
+}
+
+impl Api {
+	pub fn new(store: Store, token: String) -> Api {
+		let store = Arc::new(store);
+
+		Api {
+			#[cfg(feature = "graphql")]
+			schema: super::graphql::schema(store.clone()),
+			store,
+			token
+		}
+	}
+
+	/// Works out the response to a request. Returns the HTTP status code and the JSON body.
+	pub fn handle(&self, method: &str, url: &str, authorization: Option<&str>, body: &str) -> (u16, Value) {
+		let authorized = authorization
+			.and_then(|auth| auth.strip_prefix("Bearer "))
+			.is_some_and(|presented| constant_time_eq(presented.trim(), &self.token));
+
+		if !authorized {
+			return error(401, "missing or incorrect bearer token")
+		}
+
+		let (segments, params) = parse_url(url);
+		let segments: Vec<&str> = segments.iter().map(String::as_str).collect();
+
+		#[cfg(feature = "graphql")]
+		{
+			if segments == ["graphql"] {
+				// GraphQL queries are sent with POST, but are still read-only, since the schema has no mutations.
+				return match method {
+					"POST" => (200, super::graphql::execute(&self.schema, body)),
+					_ => error(405, "GraphQL queries must be sent with POST")
+				}
+			}
+		}
+		#[cfg(not(feature = "graphql"))]
+		let _ = body;
+
+		if method != "GET" {
+			return error(405, "this API is read-only")
+		}
+
+		let store = &*self.store;
+
+		match &segments[..] {
+			["products"] | ["products", _] if !store.has_products() => error(404, "no product data is being served"),
+			["products"] => {
+				let filter = match parse_where_param(&params) {
+					Ok(filter) => filter,
+					Err(e) => return e
+				};
+
+				let products: Vec<Value> = match params.get("q") {
+					Some(query) => store.search_products(query).filter(|row| filter.as_ref().is_none_or(|filter| filter.matches(row))).map(row_to_json).collect(),
+					None => store.products().filter(|row| filter.as_ref().is_none_or(|filter| filter.matches(row))).map(row_to_json).collect()
+				};
+
+				(200, json!({ "products": products }))
+			},
+			["products", sku] => match store.product(sku) {
+				Some(row) => (200, row_to_json(row)),
+				None => error(404, "no such product")
+			},
+			["products", sku, "price-history"] => {
+				if !store.has_price_history() {
+					return error(404, "no price history is being served")
+				}
+
+				let history: Vec<Value> = store.price_history(sku)
+					.map(|change| json!({ "date": change.date.to_string(), "price": change.price.to_string() }))
+					.collect();
+
+				(200, json!({ "sku": sku, "price_history": history }))
+			},
+			["orders"] => {
+				if !store.has_orders() {
+					return error(404, "no order data is being served")
+				}
+
+				let (from, to) = match (parse_date_param(&params, "date"), parse_date_param(&params, "from"), parse_date_param(&params, "to")) {
+					(Ok(Some(date)), _, _) => (Some(date), Some(date)),
+					(Ok(None), Ok(from), Ok(to)) => (from, to),
+					(Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return e
+				};
+
+				let filter = match parse_where_param(&params) {
+					Ok(filter) => filter,
+					Err(e) => return e
+				};
+
+				let orders: Vec<Value> = store.orders_between(from, to).filter(|row| filter.as_ref().is_none_or(|filter| filter.matches(row))).map(row_to_json).collect();
+				(200, json!({ "orders": orders }))
+			},
+			_ => error(404, "no such endpoint")
+		}
+	}
+}
+
+pub fn run(opts: ServeOpts) -> Result<()> {
+	if opts.token.is_empty() {
+		return Err("the access token must not be empty".into())
+	}
+
+	let server = tiny_http::Server::http(&opts.listen[..])
+		.map_err(|error| format!("Error listening on {}: {}", opts.listen, error))?;
+
+	let api = Api::new(Store::load(&opts.store)?, opts.token);
+
+	eprintln!("Listening on http://{}", server.server_addr());
+
+	let content_type = tiny_http::Header::from_bytes(&b"Content-Type"[..], &b"application/json"[..]).unwrap();
+
+	for mut request in server.incoming_requests() {
+		let mut body = String::new();
+		if let Err(error) = request.as_reader().read_to_string(&mut body) {
+			eprintln!("Error reading request: {}", error);
+			continue
+		}
+
+		let authorization = request.headers().iter()
+			.find(|header| header.field.equiv("Authorization"))
+			.map(|header| header.value.as_str().to_string());
+
+		let (status, body) = api.handle(request.method().as_str(), request.url(), authorization.as_deref(), &body);
+
+		let response = tiny_http::Response::from_string(body.to_string())
+			.with_status_code(status)
+			.with_header(content_type.clone());
+
+		if let Err(error) = request.respond(response) {
+			eprintln!("Error sending response: {}", error);
+		}
+	}
+
+	Ok(())
+}
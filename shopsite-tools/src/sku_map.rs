@@ -0,0 +1,221 @@
+//! The `sku-map` command: guesses which products were given new SKUs between two product exports.
+//! 
+//! When a merchant renumbers their catalog, old order exports still refer to the old SKUs. This command finds the products whose SKUs disappeared, matches each one with the most similar product whose SKU appeared, and writes the pairs to a CSV file with columns `Old SKU` and `New SKU`. After checking it over by hand, that file can be given to other commands, like `join --sku-map`, to translate old SKUs into new ones.
+
+use encoding::{all::WINDOWS_1252, DecoderTrap, Encoding};
+use std::{
+	collections::{HashMap, HashSet},
+	fs,
+	path::{Path, PathBuf}
+};
+use structopt::StructOpt;
+use super::{
+	csv_output::{looks_like_formula, CsvOpts},
+	table::Table,
+	Result
+};
+
+#[derive(StructOpt)]
+pub struct SkuMapOpts {
+	/// Product export from before the SKUs were changed.
+	#[structopt(name = "OLD")]
+	old: PathBuf,
+
+	/// Product export from after the SKUs were changed.
+	#[structopt(name = "NEW")]
+	new: PathBuf,
+
+	/// Name of the column containing each product's SKU.
+	#[structopt(long, default_value = "SKU")]
+	sku_field: String,
+
+	/// Columns to compare when looking for similar products. Their contents are joined together and compared as a whole.
+	#[structopt(long, default_value = "Name", require_delimiter = true)]
+	match_fields: Vec<String>,
+
+	/// How similar two products must be, from 0 (not at all) to 1 (identical), to be suggested as a match.
+	#[structopt(long, default_value = "0.5")]
+	min_score: f64,
+
+	/// CSV file to write the suggested mapping to, instead of standard output.
+	#[structopt(short, long)]
+	output: Option<PathBuf>,
+
+	#[structopt(flatten)]
+	csv: CsvOpts
+}
+
+/// Column headings of a SKU mapping file.
+const OLD_SKU: &str = "Old SKU";
+const NEW_SKU: &str = "New SKU";
+
+/// A product whose SKU is in one export but not the other.
+struct Candidate {
+	sku: String,
+
+	/// The contents of the `match_fields`, joined with spaces.
+	text: String,
+
+	/// Character pairs in `text`, for comparing with other candidates. See `similarity`.
+	bigrams: HashSet<(char, char)>
+}
+
+/// Finds the products in `table` whose SKUs aren't in `other_skus`.
+fn candidates(table: &Table, opts: &SkuMapOpts, other_skus: &HashSet<String>) -> Result<Vec<Candidate>> {
+	let sku_col = table.column(&opts.sku_field)?;
+	let match_cols = opts.match_fields.iter().map(|field| table.column(field)).collect::<Result<Vec<_>>>()?;
+
+	Ok(table.rows.iter()
+		.filter(|row| {
+			let sku = row[sku_col].trim();
+			!sku.is_empty() && !other_skus.contains(sku)
+		})
+		.map(|row| {
+			let text = match_cols.iter().map(|col| row[*col].trim()).collect::<Vec<_>>().join(" ");
+
+			Candidate {
+				sku: row[sku_col].trim().to_string(),
+				bigrams: bigrams(&text),
+				text
+			}
+		})
+		.collect())
+}
+
+/// Splits text into pairs of adjacent characters, ignoring letter case and punctuation, for comparison with `similarity`. Each word is compared separately, so `Blue Widget` has `bl`, `lu`, `ue`, `wi`, and so on, but not `ew`.
+fn bigrams(text: &str) -> HashSet<(char, char)> {
+	text.split(|c: char| !c.is_alphanumeric())
+	.flat_map(|word| {
+		let chars: Vec<char> = word.chars().flat_map(char::to_lowercase).collect();
+		chars.windows(2).map(|pair| (pair[0], pair[1])).collect::<Vec<_>>()
+	})
+	.collect()
+}
+
+/// Scores how similar two products are, from 0 to 1, using the Sørensen–Dice coefficient of their `bigrams`. This tolerates small changes, like fixed typos and reordered words, that an exact comparison wouldn't.
+fn similarity(a: &HashSet<(char, char)>, b: &HashSet<(char, char)>) -> f64 {
+	if a.is_empty() && b.is_empty() {
+		return 0.0
+	}
+
+	2.0 * a.intersection(b).count() as f64 / (a.len() + b.len()) as f64
+}
+
+fn skus(table: &Table, sku_field: &str) -> Result<HashSet<String>> {
+	let sku_col = table.column(sku_field)?;
+	Ok(table.rows.iter().map(|row| row[sku_col].trim().to_string()).collect())
+}
+
+pub fn run(opts: SkuMapOpts) -> Result<()> {
+	let old = Table::read(&opts.old)?;
+	let new = Table::read(&opts.new)?;
+
+	let disappeared = candidates(&old, &opts, &skus(&new, &opts.sku_field)?)?;
+	let appeared = candidates(&new, &opts, &skus(&old, &opts.sku_field)?)?;
+
+	// Score every possible pair, then take the best pairs first, so that each product is matched with at most one other, and the best match wins when two old products resemble the same new one.
+	let mut pairs: Vec<(f64, usize, usize)> = Vec::new();
+
+	for (old_index, old_product) in disappeared.iter().enumerate() {
+		for (new_index, new_product) in appeared.iter().enumerate() {
+			let score = similarity(&old_product.bigrams, &new_product.bigrams);
+
+			if score >= opts.min_score {
+				pairs.push((score, old_index, new_index));
+			}
+		}
+	}
+
+	// Ties go to whichever comes first in the exports, so the output doesn't depend on anything else.
+	pairs.sort_by(|a, b| b.0.total_cmp(&a.0).then(a.1.cmp(&b.1)).then(a.2.cmp(&b.2)));
+
+	let mut old_matched = vec![false; disappeared.len()];
+	let mut new_matched = vec![false; appeared.len()];
+	let mut matches = Vec::new();
+
+	for (score, old_index, new_index) in pairs {
+		if !old_matched[old_index] && !new_matched[new_index] {
+			old_matched[old_index] = true;
+			new_matched[new_index] = true;
+			matches.push((score, old_index, new_index));
+		}
+	}
+
+	// Write the matches in old export order, which is easier to check by hand.
+	matches.sort_by_key(|(_, old_index, _)| *old_index);
+
+	let mut output = opts.csv.create(opts.output.as_deref())?;
+	output.write_record([OLD_SKU, NEW_SKU, "Score", "Old", "New"])?;
+
+	for (score, old_index, new_index) in matches {
+		let (old_product, new_product) = (&disappeared[old_index], &appeared[new_index]);
+		output.write_record([&old_product.sku, &new_product.sku, &opts.csv.number(format!("{:.2}", score)), &old_product.text, &new_product.text])?;
+	}
+
+	output.flush()?;
+	Ok(())
+}
+
+/// Reads a SKU mapping file, as written by the `sku-map` command, into a map from old SKUs to new ones. Only the `Old SKU` and `New SKU` columns are used, so rows can be removed, added, or corrected by hand before using it.
+/// 
+/// The file can be written with any of the CSV options (see `CsvOpts`), or saved by Excel: in UTF-8, with or without a byte order mark, or in Windows-1252; with `;` between cells instead of `,`, with or without a `sep=` line saying so; and with `'` before cells that Excel would take for formulas.
+pub fn read_sku_map(path: &Path) -> Result<HashMap<String, String>> {
+	let bytes = fs::read(path).map_err(|error| format!("{}: {}", path.display(), error))?;
+
+	// Windows-1252 text is almost never valid UTF-8 too, so anything that isn't valid UTF-8 is taken to be Windows-1252.
+	let text = match String::from_utf8(bytes) {
+		Ok(text) => text,
+		Err(error) => WINDOWS_1252.decode(error.as_bytes(), DecoderTrap::Replace).map_err(|error| format!("{}: {}", path.display(), error))?
+	};
+
+	let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+
+	// A `sep=` line says what the delimiter is. Without one, the header row tells: it has both SKU columns in it, so it has a `,` in it unless the delimiter is `;`.
+	let (delimiter, text) = match text.strip_prefix("sep=").and_then(|rest| rest.split_once('\n')) {
+		Some((sep, rest)) => match sep.trim_end_matches('\r').as_bytes() {
+			&[delimiter] => (delimiter, rest),
+			_ => return Err(format!("{}: the `sep=` line must name one character", path.display()).into())
+		},
+		None => {
+			let header = text.lines().next().unwrap_or_default();
+			(if header.contains(';') && !header.contains(',') { b';' } else { b',' }, text)
+		}
+	};
+
+	let mut reader = csv::ReaderBuilder::new()
+		.delimiter(delimiter)
+		.flexible(true)
+		.from_reader(text.as_bytes());
+
+	let headers: Vec<String> = reader.headers()
+		.map_err(|error| format!("{}: {}", path.display(), error))?
+		.iter()
+		.map(|header| header.trim().to_string())
+		.collect();
+
+	let rows = reader.records()
+		.map(|record| {
+			let record = record.map_err(|error| format!("{}: {}", path.display(), error))?;
+			let mut row: Vec<String> = record.iter().take(headers.len()).map(|cell| unescape_formula(cell.trim()).to_string()).collect();
+			row.resize(headers.len(), String::new());
+			Ok(row)
+		})
+		.collect::<Result<Vec<_>>>()?;
+
+	let table = Table { path: path.to_path_buf(), headers, rows };
+	let old_col = table.column(OLD_SKU)?;
+	let new_col = table.column(NEW_SKU)?;
+
+	Ok(table.rows.iter()
+		.map(|row| (row[old_col].clone(), row[new_col].clone()))
+		.filter(|(old, new)| !old.is_empty() && !new.is_empty())
+		.collect())
+}
+
+/// Removes the `'` that `--excel` mode puts before a cell that Excel would take for a formula.
+fn unescape_formula(cell: &str) -> &str {
+	match cell.strip_prefix('\'') {
+		Some(rest) if looks_like_formula(rest) => rest,
+		_ => cell
+	}
+}
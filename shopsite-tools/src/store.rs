@@ -0,0 +1,132 @@
+//! Store data from ShopSite exports, loaded into memory for querying.
+
+use chrono::NaiveDate;
+use shopsite_aa::query::Record;
+use std::{
+	borrow::Cow,
+	path::PathBuf
+};
+use structopt::StructOpt;
+use super::{
+	price_history::{self, PriceChange},
+	table::Table,
+	Result
+};
+
+#[derive(StructOpt)]
+pub struct StoreOpts {
+	/// Product export to serve.
+	#[structopt(long)]
+	products: Option<PathBuf>,
+
+	/// Order export to serve.
+	#[structopt(long)]
+	orders: Option<PathBuf>,
+
+	/// Price history file to serve, as written by the `price-history record` command.
+	#[structopt(long)]
+	price_history: Option<PathBuf>,
+
+	/// Name of the column containing each product's SKU.
+	#[structopt(long, default_value = "SKU")]
+	sku_field: String,
+
+	/// Name of the column containing the date each order was placed.
+	#[structopt(long, default_value = "Date")]
+	order_date_field: String
+}
+
+/// All of the data that was loaded.
+pub struct Store {
+	products: Option<Table>,
+	orders: Option<Table>,
+	price_history: Option<Vec<PriceChange>>,
+	sku_col: usize,
+	order_date_col: usize
+}
+
+/// A row of a table, with the table's column names.
+pub struct Row<'a> {
+	pub headers: &'a [String],
+	pub cells: &'a [String]
+}
+
+impl<'a> Row<'a> {
+	/// Iterates over the column names and values in this row, in column order.
+	pub fn iter(&self) -> impl Iterator<Item = (&'a str, &'a str)> {
+		self.headers.iter().map(String::as_str).zip(self.cells.iter().map(String::as_str))
+	}
+}
+
+impl Record for Row<'_> {
+	fn field(&self, name: &str) -> Option<Cow<'_, str>> {
+		self.iter().find(|(k, _)| *k == name).map(|(_, v)| Cow::Borrowed(v))
+	}
+}
+
+fn rows(table: &Table) -> impl Iterator<Item = Row<'_>> {
+	table.rows.iter().map(move |cells| Row { headers: &table.headers, cells })
+}
+
+impl Store {
+	/// Loads the data named in `opts`.
+	pub fn load(opts: &StoreOpts) -> Result<Store> {
+		let products = opts.products.as_deref().map(Table::read).transpose()?;
+		let orders = opts.orders.as_deref().map(Table::read).transpose()?;
+		let price_history = opts.price_history.as_deref().map(price_history::read_history).transpose()?;
+
+		let sku_col = products.as_ref().map(|t| t.column(&opts.sku_field)).transpose()?.unwrap_or_default();
+		let order_date_col = orders.as_ref().map(|t| t.column(&opts.order_date_field)).transpose()?.unwrap_or_default();
+
+		Ok(Store { products, orders, price_history, sku_col, order_date_col })
+	}
+
+	/// Whether any products were loaded.
+	pub fn has_products(&self) -> bool {
+		self.products.is_some()
+	}
+
+	/// Whether any orders were loaded.
+	pub fn has_orders(&self) -> bool {
+		self.orders.is_some()
+	}
+
+	/// Whether price history was loaded.
+	pub fn has_price_history(&self) -> bool {
+		self.price_history.is_some()
+	}
+
+	/// All products, in export order.
+	pub fn products(&self) -> impl Iterator<Item = Row<'_>> {
+		self.products.iter().flat_map(rows)
+	}
+
+	/// Products with any field containing `query`, ignoring letter case.
+	pub fn search_products<'a>(&'a self, query: &str) -> impl Iterator<Item = Row<'a>> {
+		let query = query.to_lowercase();
+		self.products().filter(move |row| row.cells.iter().any(|cell| cell.to_lowercase().contains(&query)))
+	}
+
+	/// The product with the given SKU.
+	pub fn product(&self, sku: &str) -> Option<Row<'_>> {
+		self.products().find(|row| row.cells[self.sku_col] == sku)
+	}
+
+	/// Orders placed between `from` and `to`, inclusive. Orders whose date can't be parsed are skipped.
+	pub fn orders_between(&self, from: Option<NaiveDate>, to: Option<NaiveDate>) -> impl Iterator<Item = Row<'_>> {
+		self.orders.iter().flat_map(rows).filter(move |row| {
+			match shopsite_aa::datetime::parse(&row.cells[self.order_date_col]) {
+				Some(placed) => {
+					let placed = placed.date();
+					from.is_none_or(|from| placed >= from) && to.is_none_or(|to| placed <= to)
+				},
+				None => false
+			}
+		})
+	}
+
+	/// Recorded price changes of the product with the given SKU, oldest first.
+	pub fn price_history<'a>(&'a self, sku: &'a str) -> impl Iterator<Item = &'a PriceChange> {
+		self.price_history.iter().flatten().filter(move |change| change.sku == sku)
+	}
+}
@@ -0,0 +1,105 @@
+//! Tabular data, such as ShopSite's product and order exports.
+
+use std::{
+	ffi::OsStr,
+	fs::File,
+	path::{Path, PathBuf}
+};
+use super::Result;
+
+/// The contents of a delimited text file, with a header row naming each column.
+pub struct Table {
+	/// Where the table was read from. Used in error messages.
+	pub path: PathBuf,
+
+	/// Names of the columns, from the first row of the file.
+	pub headers: Vec<String>,
+
+	/// All rows after the first. Every row has exactly as many cells as there are `headers`.
+	pub rows: Vec<Vec<String>>
+}
+
+/// A delimited text file that is read one row at a time, rather than all at once like `Table`. For files too big to comfortably hold in memory.
+pub struct TableReader {
+	/// Where the table is being read from. Used in error messages.
+	pub path: PathBuf,
+
+	/// Names of the columns, from the first row of the file.
+	pub headers: Vec<String>,
+
+	reader: csv::Reader<File>
+}
+
+impl TableReader {
+	/// Opens a table and reads its header row.
+	/// 
+	/// Files whose names end in `.csv` are read as comma-separated values. Anything else is assumed to be tab-delimited, which is what ShopSite's “Download” feature produces.
+	pub fn open(path: &Path) -> Result<TableReader> {
+		let delimiter = match path.extension().and_then(OsStr::to_str) {
+			Some(ext) if ext.eq_ignore_ascii_case("csv") => b',',
+			_ => b'\t'
+		};
+
+		let mut reader = csv::ReaderBuilder::new()
+			.delimiter(delimiter)
+			// ShopSite doesn't quote fields in its tab-delimited exports, and product descriptions are full of `"` characters.
+			.quoting(delimiter != b'\t')
+			.flexible(true)
+			.from_path(path)
+			.map_err(|error| format!("{}: {}", path.display(), error))?;
+
+		let mut headers: Vec<String> = reader.headers()
+			.map_err(|error| format!("{}: {}", path.display(), error))?
+			.iter()
+			.map(|h| h.trim().to_string())
+			.collect();
+
+		// Files saved by Excel, including those written with `--excel`, begin with a byte order mark. It isn't part of the first column's name.
+		if let Some(first) = headers.first_mut() {
+			if let Some(rest) = first.strip_prefix('\u{feff}') {
+				*first = rest.trim_start().to_string();
+			}
+		}
+
+		Ok(TableReader { path: path.to_path_buf(), headers, reader })
+	}
+
+	/// Finds the index of the column with the given name. Fails if there is no such column.
+	pub fn column(&self, name: &str) -> Result<usize> {
+		find_column(&self.path, &self.headers, name)
+	}
+
+	/// Reads the rest of the rows, one at a time. As with `Table::rows`, every row has exactly as many cells as there are `headers`.
+	pub fn rows(&mut self) -> impl Iterator<Item = Result<Vec<String>>> + '_ {
+		let (path, width) = (&self.path, self.headers.len());
+
+		self.reader.records().map(move |record| {
+			let record = record.map_err(|error| format!("{}: {}", path.display(), error))?;
+
+			// Pad or truncate short and long rows, so that everyone downstream can index cells by column number without worrying about it.
+			let mut row: Vec<String> = record.iter().take(width).map(str::to_string).collect();
+			row.resize(width, String::new());
+			Ok(row)
+		})
+	}
+}
+
+impl Table {
+	/// Reads a table from a file. See `TableReader::open` for which formats are understood.
+	pub fn read(path: &Path) -> Result<Table> {
+		let mut reader = TableReader::open(path)?;
+		let rows = reader.rows().collect::<Result<Vec<_>>>()?;
+		Ok(Table { path: reader.path, headers: reader.headers, rows })
+	}
+
+	/// Finds the index of the column with the given name. Fails if there is no such column.
+	pub fn column(&self, name: &str) -> Result<usize> {
+		find_column(&self.path, &self.headers, name)
+	}
+}
+
+fn find_column(path: &Path, headers: &[String], name: &str) -> Result<usize> {
+	headers.iter()
+		.position(|h| h == name)
+		.ok_or_else(|| format!("{}: there is no column named “{}”", path.display(), name).into())
+}
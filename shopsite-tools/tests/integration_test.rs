@@ -0,0 +1,648 @@
+use assert_cmd::Command;
+use std::{
+	fs,
+	path::PathBuf
+};
+
+fn fixture(name: &str) -> PathBuf {
+	[env!("CARGO_MANIFEST_DIR"), "tests", name].iter().collect()
+}
+
+fn get_cmd() -> Command {
+	Command::cargo_bin("shopsite-tools").unwrap()
+}
+
+fn run_test(cmd: &mut Command) -> String {
+	let results = cmd.unwrap();
+
+	assert!(results.status.success());
+	assert_eq!(&results.stderr[..], b"", "standard error output should have been empty");
+	String::from_utf8(results.stdout).unwrap()
+}
+
+#[test]
+fn config() {
+	let dir = std::env::temp_dir().join(format!("shopsite-tools-test-config-{}", std::process::id()));
+	let (system_dir, user_dir) = (dir.join("system"), dir.join("user"));
+	fs::create_dir_all(system_dir.join("shopsite-utils")).unwrap();
+	fs::create_dir_all(user_dir.join("shopsite-utils")).unwrap();
+
+	fs::write(system_dir.join("shopsite-utils").join("config.toml"), "\
+[shopsite-tools.report.sales]
+by = \"product\"
+format = \"markdown\"
+").unwrap();
+
+	fs::write(user_dir.join("shopsite-utils").join("config.toml"), "\
+[shopsite-tools.report.sales]
+format = \"csv\"
+from = 2020-04-15
+").unwrap();
+
+	let report = |args: &[&str]| {
+		run_test(
+			get_cmd()
+			.env("XDG_CONFIG_DIRS", &system_dir)
+			.env("XDG_CONFIG_HOME", &user_dir)
+			.args(["report", "sales"])
+			.args(args)
+			.arg(fixture("orders.txt"))
+		)
+	};
+
+	// The user configuration file overrides the system one, but only for the options it has.
+	let from_config = report(&[]);
+
+	// The command line overrides both.
+	let from_command_line = report(&["--by", "day", "--from=2020-04-16"]);
+
+	let _ = fs::remove_dir_all(&dir);
+
+	assert_eq!(from_config, "\
+SKU,Lines,Quantity,Total
+W-1,1,1,9.99
+W-2,1,1,9.99
+Total,2,2,19.98
+");
+
+	assert_eq!(from_command_line, "\
+Day,Lines,Quantity,Total
+2020-04-16,1,1,9.99
+Total,1,1,9.99
+");
+}
+
+#[test]
+fn email() {
+	let dir = std::env::temp_dir().join(format!("shopsite-tools-test-email-{}", std::process::id()));
+	let out_dir = dir.join("out");
+	fs::create_dir_all(&dir).unwrap();
+
+	let orders_path = dir.join("orders.txt");
+	let text_path = dir.join("text.hbs");
+	let html_path = dir.join("html.hbs");
+	let bad_path = dir.join("bad.hbs");
+
+	fs::write(&orders_path, "Order Number\tEmail\tSKU\tQuantity\tNote\n1001\tpat@example.com\tW-1\t2\t<rush>\n1002\tsam@example.com\tW-3\t1\t\n1001\tpat@example.com\tW-2\t5\t\n").unwrap();
+	fs::write(&text_path, "Order {{[Order Number]}}:\n{{#each lines}}{{Quantity}} x {{SKU}}\n{{/each}}").unwrap();
+	fs::write(&html_path, "<p>Order {{[Order Number]}} & {{Note}}</p>").unwrap();
+	fs::write(&bad_path, "{{Nonexistent}}").unwrap();
+
+	let email = |template_args: &[&str]| {
+		get_cmd()
+		.args(["email", "--from", "Example Store <orders@example.com>", "--output-dir"])
+		.arg(&out_dir)
+		.args(template_args)
+		.arg(&orders_path)
+		.assert()
+	};
+
+	email(&["--text", text_path.to_str().unwrap(), "--html", html_path.to_str().unwrap()]).success();
+
+	// Lines of the same order are gathered into one email, even if they aren't next to each other.
+	let mut files: Vec<String> = fs::read_dir(&out_dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+	files.sort();
+	assert_eq!(files, ["1001.eml", "1002.eml"]);
+
+	let message = fs::read_to_string(out_dir.join("1001.eml")).unwrap();
+	assert!(message.contains("To: pat@example.com"), "{}", message);
+	assert!(message.contains("Subject: Your order 1001"), "{}", message);
+	assert!(message.contains("Order 1001:\r\n2 x W-1\r\n5 x W-2"), "{}", message);
+	// Values are escaped in the HTML body, but not the template itself.
+	assert!(message.contains("<p>Order 1001 & &lt;rush&gt;</p>"), "{}", message);
+
+	// Misspelled column names are caught.
+	let results = email(&["--text", bad_path.to_str().unwrap()]).failure();
+	assert!(String::from_utf8_lossy(&results.get_output().stderr).contains("Nonexistent"));
+
+	// Order numbers that come out the same once they're made safe for file names don't overwrite each other's emails. The one that was already safe keeps its name.
+	let _ = fs::remove_dir_all(&out_dir);
+	fs::write(&orders_path, "Order Number\tEmail\tSKU\tQuantity\tNote\nA/1\tpat@example.com\tW-1\t1\t\nA_1\tsam@example.com\tW-2\t1\t\na:1\tlee@example.com\tW-3\t1\t\n").unwrap();
+	email(&["--text", text_path.to_str().unwrap()]).success();
+
+	let mut files: Vec<String> = fs::read_dir(&out_dir).unwrap().map(|entry| entry.unwrap().file_name().into_string().unwrap()).collect();
+	files.sort();
+	assert_eq!(files, ["A_1-2.eml", "A_1.eml", "a_1-3.eml"]);
+	assert!(fs::read_to_string(out_dir.join("A_1.eml")).unwrap().contains("Order A_1:"));
+	assert!(fs::read_to_string(out_dir.join("A_1-2.eml")).unwrap().contains("Order A/1:"));
+
+	let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn excel() {
+	let dir = std::env::temp_dir().join(format!("shopsite-tools-test-excel-{}", std::process::id()));
+	fs::create_dir_all(&dir).unwrap();
+
+	let products_path = dir.join("products.txt");
+	let orders_path = dir.join("orders.txt");
+	fs::write(&products_path, "Name\tSKU\nCafé Mug\tM-1\n").unwrap();
+	fs::write(&orders_path, "SKU\tNote\tTotal\nM-1\t=HYPERLINK(\"http://example.com\")\t-5.00\nM-1\t@SUM(1)\t+3\n").unwrap();
+
+	let join = |args: &[&str]| {
+		get_cmd()
+		.args(["join", "--add", "Name"])
+		.args(args)
+		.arg(&products_path)
+		.arg(&orders_path)
+		.unwrap()
+		.stdout
+	};
+
+	// Without `--excel`, cells are written as they are.
+	assert_eq!(String::from_utf8(join(&[])).unwrap(), "\
+SKU,Note,Total,Product Name
+M-1,\"=HYPERLINK(\"\"http://example.com\"\")\",-5.00,Café Mug
+M-1,@SUM(1),+3,Café Mug
+");
+
+	// With it, formulas are defused, but numbers are left alone.
+	assert_eq!(String::from_utf8(join(&["--excel"])).unwrap(), "\
+\u{feff}SKU,Note,Total,Product Name\r
+M-1,\"'=HYPERLINK(\"\"http://example.com\"\")\",-5.00,Café Mug\r
+M-1,'@SUM(1),+3,Café Mug\r
+");
+
+	assert_eq!(join(&["--excel", "--encoding", "windows-1252"]), &b"\
+sep=,\r
+SKU,Note,Total,Product Name\r
+M-1,\"'=HYPERLINK(\"\"http://example.com\"\")\",-5.00,Caf\xe9 Mug\r
+M-1,'@SUM(1),+3,Caf\xe9 Mug\r
+"[..]);
+
+	// Files written in Excel mode can be read back in.
+	let excel_path = dir.join("products.csv");
+	fs::write(&excel_path, "\u{feff}Name,SKU\r\nCafé Mug,M-1\r\n").unwrap();
+	let joined = run_test(get_cmd().args(["join", "--add", "Name"]).arg(&excel_path).arg(&orders_path));
+	assert!(joined.ends_with(",Café Mug\n"), "{}", joined);
+
+	let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn inventory_diff() {
+	let reorder_path = std::env::temp_dir().join(format!("shopsite-tools-test-reorder-{}.csv", std::process::id()));
+
+	let report = run_test(
+		get_cmd()
+		.args(["inventory-diff", "--days", "30", "--reorder-output"])
+		.arg(&reorder_path)
+		.arg(fixture("products-old.txt"))
+		.arg(fixture("products-new.txt"))
+	);
+
+	assert_eq!(report, "\
+SKU,Name,Old Quantity,New Quantity,Sold,Restocked
+G-9,Discontinued Gadget,3,,0,0
+W-1,Blue Widget,100,40,60,0
+W-2,Red Widget,20,50,0,30
+W-3,Green Widget,,12,0,12
+");
+
+	let reorders = fs::read_to_string(&reorder_path).unwrap();
+	let _ = fs::remove_file(&reorder_path);
+
+	// Blue widgets sell at 2 per day, so the 40 on hand last 20 days, which is more than the default lead time of 14 days. No reorder yet.
+	assert_eq!(reorders, "SKU,Name,On Hand,Sold Per Day,Days Of Cover,Suggested Quantity\n");
+}
+
+#[test]
+fn inventory_diff_reorder() {
+	let reorder_path = std::env::temp_dir().join(format!("shopsite-tools-test-reorder-lead-{}.csv", std::process::id()));
+
+	run_test(
+		get_cmd()
+		.args(["inventory-diff", "--days", "30", "--lead-time-days", "21", "--cover-days", "10", "--reorder-output"])
+		.arg(&reorder_path)
+		.arg(fixture("products-old.txt"))
+		.arg(fixture("products-new.txt"))
+	);
+
+	let reorders = fs::read_to_string(&reorder_path).unwrap();
+	let _ = fs::remove_file(&reorder_path);
+
+	// With a 21-day lead time, the 20 days of cover isn't enough. Cover 31 days at 2 per day, less the 40 on hand.
+	assert_eq!(reorders, "\
+SKU,Name,On Hand,Sold Per Day,Days Of Cover,Suggested Quantity
+W-1,Blue Widget,40,2.00,20.0,22
+");
+}
+
+#[test]
+fn join() {
+	let products_len = fs::metadata(fixture("products-new.txt")).unwrap().len();
+	let orders_path = std::env::temp_dir().join(format!("shopsite-tools-test-join-orders-{}.txt", std::process::id()));
+
+	let join = |orders: &str| {
+		fs::write(&orders_path, orders).unwrap();
+
+		run_test(
+			get_cmd()
+			.args(["join", "--add", "Name,Price"])
+			.arg(fixture("products-new.txt"))
+			.arg(&orders_path)
+		)
+	};
+
+	// The order export is bigger than the product export here, so the products are the ones held in memory. One of the orders is for a product that doesn't exist.
+	let orders = fs::read_to_string(fixture("orders.txt")).unwrap() + "1004\t04/17/2020 09:00:00\tX-404\t1\t5.00\n";
+	assert!(orders.len() as u64 > products_len);
+	assert_eq!(join(&orders), "\
+Order Number,Date,SKU,Quantity,Total,Product Name,Product Price
+1001,04/14/2020 10:00:00,W-1,2,19.98,Blue Widget,9.99
+1002,04/15/2020 13:45:06,W-2,1,9.99,Red Widget,9.99
+1003,04/16/2020 08:30:00,W-1,1,9.99,Blue Widget,9.99
+1004,04/17/2020 09:00:00,X-404,1,5.00,,
+");
+
+	// With fewer orders, the orders are held in memory instead. The output is still in order export order.
+	let orders = "Order Number\tSKU\n1\tW-3\n2\tX-404\n3\tW-1\n";
+	assert!((orders.len() as u64) < products_len);
+	let joined = join(orders);
+	let _ = fs::remove_file(&orders_path);
+	assert_eq!(joined, "\
+Order Number,SKU,Product Name,Product Price
+1,W-3,Green Widget,10.99
+2,X-404,,
+3,W-1,Blue Widget,9.99
+");
+
+	// By default, the name and price are added, which every product export has.
+	let joined = run_test(
+		get_cmd()
+		.arg("join")
+		.arg(fixture("products-new.txt"))
+		.arg(fixture("orders.txt"))
+	);
+	assert!(joined.starts_with("Order Number,Date,SKU,Quantity,Total,Product Name,Product Price\n"), "{}", joined);
+
+	// Asking for a column that isn't there is an error.
+	let results = get_cmd()
+		.args(["join", "--add", "Name,Category"])
+		.arg(fixture("products-new.txt"))
+		.arg(fixture("orders.txt"))
+		.assert()
+		.failure();
+	assert!(String::from_utf8_lossy(&results.get_output().stderr).contains("there is no column named “Category”"));
+}
+
+#[test]
+fn price_history() {
+	let history_path = std::env::temp_dir().join(format!("shopsite-tools-test-price-history-{}.csv", std::process::id()));
+	let _ = fs::remove_file(&history_path);
+
+	let record = |date: &str, export: &str| {
+		run_test(
+			get_cmd()
+			.args(["price-history", "record", "--date", date, "--history"])
+			.arg(&history_path)
+			.arg(fixture(export))
+		)
+	};
+
+	record("2020-04-01", "products-old.txt");
+	record("2020-05-01", "products-new.txt");
+	// Recording the same prices again changes nothing.
+	record("2020-05-02", "products-new.txt");
+
+	let show = |args: &[&str]| {
+		run_test(
+			get_cmd()
+			.args(["price-history", "show", "--history"])
+			.arg(&history_path)
+			.args(args)
+		)
+	};
+
+	let all_csv = show(&["--csv"]);
+	let w3 = show(&["W-3"]);
+	let w1_csv = show(&["--csv", "W-1"]);
+
+	let _ = fs::remove_file(&history_path);
+
+	assert_eq!(all_csv, "\
+Date,SKU,Price
+2020-04-01,W-1,9.99
+2020-04-01,W-2,9.99
+2020-04-01,GW,2.00
+2020-04-01,G-9,5.00
+2020-05-01,W-3,10.99
+");
+	assert_eq!(w3, "2020-05-01  W-3                          10.99\n");
+	assert_eq!(w1_csv, "Date,SKU,Price\n2020-04-01,W-1,9.99\n");
+}
+
+/// A running `serve` command, which is killed when dropped.
+struct Server {
+	process: std::process::Child,
+	addr: String
+}
+
+impl Server {
+	/// Starts a server for the product and order fixtures.
+	fn start() -> Server {
+		Server::start_with(&["--products".as_ref(), fixture("products-new.txt").as_os_str(), "--orders".as_ref(), fixture("orders.txt").as_os_str()])
+	}
+
+	/// Starts a server with the given options saying what to serve.
+	fn start_with(args: &[&std::ffi::OsStr]) -> Server {
+		use std::{
+			io::{BufRead, BufReader},
+			process::Stdio
+		};
+
+		let mut process = std::process::Command::new(assert_cmd::cargo::cargo_bin("shopsite-tools"))
+			.args(["serve", "--listen", "127.0.0.1:0", "--token", "s3cret"])
+			.args(args)
+			.stderr(Stdio::piped())
+			.spawn()
+			.unwrap();
+
+		// The server says where it's listening on its first line of output.
+		let mut first_line = String::new();
+		BufReader::new(process.stderr.take().unwrap()).read_line(&mut first_line).unwrap();
+		let addr = first_line.trim().strip_prefix("Listening on http://").expect("server should say where it's listening").to_string();
+
+		Server { process, addr }
+	}
+
+	fn request(&self, method: &str, path: &str, token: Option<&str>, body: &str) -> (u16, serde_json::Value) {
+		use std::{
+			io::{Read, Write},
+			net::TcpStream
+		};
+
+		let mut stream = TcpStream::connect(&self.addr).unwrap();
+		write!(stream, "{} {} HTTP/1.0\r\nContent-Length: {}\r\n", method, path, body.len()).unwrap();
+		if let Some(token) = token {
+			write!(stream, "Authorization: Bearer {}\r\n", token).unwrap();
+		}
+		write!(stream, "\r\n{}", body).unwrap();
+
+		let mut response = String::new();
+		stream.read_to_string(&mut response).unwrap();
+
+		let status = response[9..12].parse().unwrap();
+		let body = &response[response.find("\r\n\r\n").unwrap() + 4..];
+		(status, serde_json::from_str(body).unwrap())
+	}
+
+	fn get(&self, path: &str, token: Option<&str>) -> (u16, serde_json::Value) {
+		self.request("GET", path, token, "")
+	}
+}
+
+impl Drop for Server {
+	fn drop(&mut self) {
+		let _ = self.process.kill();
+		let _ = self.process.wait();
+	}
+}
+
+#[test]
+fn report_sales() {
+	let report = |args: &[&str]| {
+		run_test(
+			get_cmd()
+			.args(["report", "sales"])
+			.args(args)
+			.arg(fixture("orders.txt"))
+		)
+	};
+
+	assert_eq!(report(&[]), "\
+Day,Lines,Quantity,Total
+2020-04-14,1,2,19.98
+2020-04-15,1,1,9.99
+2020-04-16,1,1,9.99
+Total,3,4,39.96
+");
+
+	assert_eq!(report(&["--by", "product", "--from", "2020-04-15", "--format", "markdown"]), "\
+| SKU | Lines | Quantity | Total |
+| --- | ---: | ---: | ---: |
+| W-1 | 1 | 1 | 9.99 |
+| W-2 | 1 | 1 | 9.99 |
+| **Total** | **2** | **2** | **19.98** |
+");
+
+	let orders_path = std::env::temp_dir().join(format!("shopsite-tools-test-report-orders-{}.txt", std::process::id()));
+	fs::write(&orders_path, "Date\tSKU\tQuantity\tTotal\n2020-04-14\tW-1\t1500\t14985.00\n2020-04-14\tW-2\t1\t-0.50\n").unwrap();
+	let european = run_test(
+		get_cmd()
+		.args(["report", "sales", "--by", "product", "--decimal-separator", ",", "--thousands-separator", "."])
+		.arg(&orders_path)
+	);
+	let _ = fs::remove_file(&orders_path);
+	assert_eq!(european, "\
+SKU;Lines;Quantity;Total
+W-1;1;1.500;14.985,00
+W-2;1;1;-0,50
+Total;2;1.501;14.984,50
+");
+
+	let products_path = std::env::temp_dir().join(format!("shopsite-tools-test-report-products-{}.txt", std::process::id()));
+	fs::write(&products_path, "SKU\tCategory\nW-1\tBlue Things\n").unwrap();
+
+	let by_category = report(&["--by", "category", "--to", "2020-04-15", "--format", "json", "--products", products_path.to_str().unwrap()]);
+	let _ = fs::remove_file(&products_path);
+	let by_category: serde_json::Value = serde_json::from_str(&by_category).unwrap();
+	assert_eq!(by_category, serde_json::json!({
+		"groups": [
+			{ "category": "(none)", "lines": 1, "quantity": 1, "total": "9.99" },
+			{ "category": "Blue Things", "lines": 1, "quantity": 2, "total": "19.98" }
+		],
+		"total": { "lines": 2, "quantity": 3, "total": "29.97" }
+	}));
+
+	// Totaling by category without a product export is an error.
+	get_cmd()
+		.args(["report", "sales", "--by", "category"])
+		.arg(fixture("orders.txt"))
+		.assert()
+		.failure();
+}
+
+#[test]
+fn serve() {
+	let server = Server::start();
+
+	assert_eq!(server.get("/products", None).0, 401);
+	assert_eq!(server.get("/products", Some("wrong")).0, 401);
+
+	let (status, body) = server.get("/products?q=red", Some("s3cret"));
+	assert_eq!(status, 200);
+	assert_eq!(body["products"].as_array().unwrap().len(), 1);
+	assert_eq!(body["products"][0]["SKU"], "W-2");
+
+	let (status, body) = server.get("/products/W-3", Some("s3cret"));
+	assert_eq!(status, 200);
+	assert_eq!(body["Name"], "Green Widget");
+	assert_eq!(body["QuantityOnHand"], "12");
+
+	assert_eq!(server.get("/products/nope", Some("s3cret")).0, 404);
+
+	let (status, body) = server.get("/orders?from=2020-04-15", Some("s3cret"));
+	assert_eq!(status, 200);
+	let order_numbers: Vec<&str> = body["orders"].as_array().unwrap().iter().map(|o| o["Order Number"].as_str().unwrap()).collect();
+	assert_eq!(order_numbers, ["1002", "1003"]);
+
+	let (status, body) = server.get("/orders?date=2020-04-14", Some("s3cret"));
+	assert_eq!(status, 200);
+	assert_eq!(body["orders"][0]["Order Number"], "1001");
+
+	let (status, body) = server.get("/products?where=Price%20%3E%2010%20or%20SKU%20%3D%20W-1", Some("s3cret"));
+	assert_eq!(status, 200);
+	let skus: Vec<&str> = body["products"].as_array().unwrap().iter().map(|p| p["SKU"].as_str().unwrap()).collect();
+	assert_eq!(skus, ["W-1", "W-3"]);
+
+	assert_eq!(server.get("/products?where=Price%20%3E", Some("s3cret")).0, 400);
+	assert_eq!(server.get("/orders?date=yesterday", Some("s3cret")).0, 400);
+	assert_eq!(server.request("DELETE", "/products/W-1", Some("s3cret"), "").0, 405);
+}
+
+#[test]
+fn serve_price_history() {
+	let history_path = std::env::temp_dir().join(format!("shopsite-tools-test-serve-price-history-{}.csv", std::process::id()));
+	let _ = fs::remove_file(&history_path);
+
+	run_test(
+		get_cmd()
+		.args(["price-history", "record", "--date", "2020-05-01", "--history"])
+		.arg(&history_path)
+		.arg(fixture("products-new.txt"))
+	);
+
+	// Price history can be served without the products it's about.
+	let server = Server::start_with(&["--price-history".as_ref(), history_path.as_os_str()]);
+	let (status, body) = server.get("/products/W-3/price-history", Some("s3cret"));
+	let products_status = server.get("/products/W-3", Some("s3cret")).0;
+	drop(server);
+	let _ = fs::remove_file(&history_path);
+
+	assert_eq!(status, 200);
+	assert_eq!(body, serde_json::json!({
+		"sku": "W-3",
+		"price_history": [{ "date": "2020-05-01", "price": "10.99" }]
+	}));
+	assert_eq!(products_status, 404);
+}
+
+#[cfg(feature = "graphql")]
+#[test]
+fn serve_graphql() {
+	let server = Server::start();
+
+	let query = serde_json::json!({
+		"query": "{ product(sku: \"W-1\") { name: field(name: \"Name\") } orders(from: \"2020-04-16\") { field(name: \"Order Number\") } }"
+	}).to_string();
+
+	assert_eq!(server.request("POST", "/graphql", None, &query).0, 401);
+
+	let (status, body) = server.request("POST", "/graphql", Some("s3cret"), &query);
+	assert_eq!(status, 200);
+	assert_eq!(body, serde_json::json!({
+		"data": {
+			"product": { "name": "Blue Widget" },
+			"orders": [{ "field": "1003" }]
+		}
+	}));
+}
+
+#[test]
+fn sku_map() {
+	let dir = std::env::temp_dir().join(format!("shopsite-tools-test-sku-map-{}", std::process::id()));
+	fs::create_dir_all(&dir).unwrap();
+
+	let old_path = dir.join("old.txt");
+	let new_path = dir.join("new.txt");
+	let map_path = dir.join("map.csv");
+	let orders_path = dir.join("orders.txt");
+
+	fs::write(&old_path, "Name\tSKU\tPrice\nBlue Widget\tW-1\t9.99\nRed Widget\tW-2\t9.99\nGift Wrap\tGW\t2.00\nDiscontinued Gadget\tG-9\t5.00\n").unwrap();
+	fs::write(&new_path, "Name\tSKU\tPrice\nWidget, Red\tWID-002\t9.99\nBlue Widget\tWID-001\t10.99\nGift Wrap\tGW\t2.00\nBrand New Thing\tN-1\t1.00\n").unwrap();
+
+	run_test(
+		get_cmd()
+		.arg("sku-map")
+		.arg(&old_path)
+		.arg(&new_path)
+		.arg("-o")
+		.arg(&map_path)
+	);
+
+	// The discontinued gadget and brand new thing aren't anything alike, so they aren't matched.
+	assert_eq!(fs::read_to_string(&map_path).unwrap(), "\
+Old SKU,New SKU,Score,Old,New
+W-1,WID-001,1.00,Blue Widget,Blue Widget
+W-2,WID-002,1.00,Red Widget,\"Widget, Red\"
+");
+
+	// The mapping lets old orders be joined with the renumbered products.
+	fs::write(&orders_path, "Order Number\tSKU\n1\tW-2\n2\tGW\n3\tG-9\n").unwrap();
+
+	let joined = run_test(
+		get_cmd()
+		.args(["join", "--add", "Name,Price", "--sku-map"])
+		.arg(&map_path)
+		.arg(&new_path)
+		.arg(&orders_path)
+	);
+	let _ = fs::remove_dir_all(&dir);
+
+	assert_eq!(joined, "\
+Order Number,SKU,Product Name,Product Price
+1,W-2,\"Widget, Red\",9.99
+2,GW,Gift Wrap,2.00
+3,G-9,,
+");
+}
+
+#[test]
+fn sku_map_round_trip() {
+	// A SKU mapping written with any of the CSV options can be read back by `join --sku-map`.
+	let dir = std::env::temp_dir().join(format!("shopsite-tools-test-sku-map-round-trip-{}", std::process::id()));
+	fs::create_dir_all(&dir).unwrap();
+
+	let old_path = dir.join("old.txt");
+	let new_path = dir.join("new.txt");
+	let map_path = dir.join("map.csv");
+	let orders_path = dir.join("orders.txt");
+
+	// The old SKU looks like a formula, and the new product's name isn't ASCII, so `--excel` and `--encoding windows-1252` both change how they're written.
+	fs::write(&old_path, "Name\tSKU\nCaf\u{e9} Mug\t-M1\n").unwrap();
+	fs::write(&new_path, "Name\tSKU\nCaf\u{e9} Mug\tM-001\n").unwrap();
+	fs::write(&orders_path, "Order Number\tSKU\n1\t-M1\n").unwrap();
+
+	let option_sets: &[&[&str]] = &[
+		&["--excel"],
+		&["--encoding", "windows-1252"],
+		&["--excel", "--encoding", "windows-1252"],
+		&["--decimal-separator", ","],
+		&["--excel", "--encoding", "windows-1252", "--decimal-separator", ","]
+	];
+
+	for options in option_sets {
+		run_test(
+			get_cmd()
+			.arg("sku-map")
+			.args(*options)
+			.arg(&old_path)
+			.arg(&new_path)
+			.arg("-o")
+			.arg(&map_path)
+		);
+
+		let joined = run_test(
+			get_cmd()
+			.args(["join", "--add", "Name", "--sku-map"])
+			.arg(&map_path)
+			.arg(&new_path)
+			.arg(&orders_path)
+		);
+
+		assert_eq!(joined, "Order Number,SKU,Product Name\n1,-M1,Caf\u{e9} Mug\n", "with {:?}", options);
+	}
+
+	let _ = fs::remove_dir_all(&dir);
+}
@@ -0,0 +1,61 @@
+//! Postal addresses, as they appear in orders and customer records.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display, Formatter};
+
+/// A postal address, along with the name and phone number of whoever is there.
+/// 
+/// In ShopSite's records, the fields of an address have the same prefix, like `Billing Name` and `Billing City`.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct Address {
+	/// The name of the person. Spelled `… Name`.
+	pub name: String,
+
+	/// The name of the company, if any. Spelled `… Company`.
+	pub company: String,
+
+	/// The first line of the street address. Spelled `… Address 1`.
+	pub address1: String,
+
+	/// The second line of the street address, if any, like an apartment number. Spelled `… Address 2`.
+	pub address2: String,
+
+	/// Spelled `… City`.
+	pub city: String,
+
+	/// The state, province, or region. Spelled `… State`.
+	pub state: String,
+
+	/// The postal code. Spelled `… Zip`.
+	pub zip: String,
+
+	/// Spelled `… Country`.
+	pub country: String,
+
+	/// Spelled `… Phone`.
+	pub phone: String
+}
+
+impl Address {
+	/// Whether every field is empty, as when a record doesn't have this address at all.
+	pub fn is_empty(&self) -> bool {
+		self == &Address::default()
+	}
+}
+
+impl Display for Address {
+	/// Writes the address the way it'd go on an envelope: one line each for the name, company, street address, city, state, and postal code, and country, leaving out empty lines. The phone number is left out.
+	fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+		let region = join([&self.state[..], &self.zip[..]], " ");
+		let city_line = join([&self.city[..], &region[..]], ", ");
+		let lines = [&self.name[..], &self.company[..], &self.address1[..], &self.address2[..], &city_line[..], &self.country[..]];
+
+		f.write_str(&join(lines, "\n"))
+	}
+}
+
+/// Joins the parts that aren't empty with `separator`.
+fn join<'a>(parts: impl IntoIterator<Item = &'a str>, separator: &str) -> String {
+	parts.into_iter().filter(|part| !part.is_empty()).collect::<Vec<_>>().join(separator)
+}
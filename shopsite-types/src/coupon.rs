@@ -0,0 +1,165 @@
+//! Records of the coupon database.
+//! 
+//! ShopSite dumps its coupons as one `.aa` file, with one record after another, each starting with the coupon's `Code`:
+//! 
+//! ```
+//! use chrono::NaiveDate;
+//! use shopsite_types::{coupon::Discount, Coupons};
+//! 
+//! let coupons = Coupons::from_reader(&b"\
+//! Code: SPRING10
+//! Name: Spring sale
+//! DiscountType: Percent
+//! Percent: 10%
+//! Expires: 05/31/2020
+//! MaxUses: 100
+//! TimesUsed: 100
+//! Code: SHIPFREE
+//! DiscountType: Free Shipping
+//! MinimumOrder: $50.00
+//! "[..], None).unwrap();
+//! 
+//! let today = NaiveDate::from_ymd_opt(2020, 5, 1).unwrap();
+//! let spring = coupons.get("SPRING10").unwrap();
+//! assert_eq!(spring.discount, Discount::Percent(10.0));
+//! assert!(!spring.is_expired(today));
+//! assert_eq!(spring.uses_left(), Some(0));
+//! 
+//! assert_eq!(coupons.usable(today).map(|coupon| &coupon.code[..]).collect::<Vec<_>>(), ["SHIPFREE"]);
+//! ```
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use shopsite_aa::{de as aa, money::Money};
+
+/// One record of the coupon database.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(from = "RawCoupon")]
+#[non_exhaustive]
+pub struct Coupon {
+	/// The code that customers enter to use the coupon. Spelled `Code`.
+	pub code: String,
+
+	/// The coupon's name, for the back office. Spelled `Name`.
+	pub name: String,
+
+	/// What the coupon takes off. Spelled `DiscountType`, along with `Amount` or `Percent`.
+	pub discount: Discount,
+
+	/// The coupon only works on orders whose subtotal is at least this much. Spelled `MinimumOrder`.
+	pub minimum_order: Option<Money>,
+
+	/// The last day the coupon works, if it ever stops working. Spelled `Expires`.
+	#[serde(with = "shopsite_aa::datetime::date::option")]
+	pub expires: Option<NaiveDate>,
+
+	/// How many times the coupon can be used, all told. `None`, written as an empty value, means there's no limit. Spelled `MaxUses`.
+	pub max_uses: Option<u32>,
+
+	/// How many times the coupon has been used so far. Spelled `TimesUsed`.
+	pub times_used: u32,
+
+	/// Whether each customer can only use the coupon once. Spelled `OncePerCustomer`.
+	pub once_per_customer: bool
+}
+
+impl Coupon {
+	/// Whether the coupon has expired, as of `today`. It still works on the day it expires.
+	pub fn is_expired(&self, today: NaiveDate) -> bool {
+		self.expires.is_some_and(|expires| today > expires)
+	}
+
+	/// How many more times the coupon can be used, or `None` if there's no limit.
+	pub fn uses_left(&self) -> Option<u32> {
+		self.max_uses.map(|max_uses| max_uses.saturating_sub(self.times_used))
+	}
+
+	/// Whether the coupon can still be used, as of `today`: it hasn't expired, and it hasn't been used up.
+	pub fn is_usable(&self, today: NaiveDate) -> bool {
+		!self.is_expired(today) && self.uses_left() != Some(0)
+	}
+}
+
+/// What a coupon takes off of an order.
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(tag = "kind", content = "value", rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum Discount {
+	/// This much off the subtotal. Spelled `Amount` in `DiscountType`, with the amount in `Amount`.
+	Amount(Money),
+
+	/// This percentage off the subtotal, so `10%` is `10.0`. Spelled `Percent` in `DiscountType`, with the percentage in `Percent`.
+	Percent(f64),
+
+	/// Shipping is free. Spelled `Free Shipping` in `DiscountType`.
+	FreeShipping
+}
+
+records! {
+	/// All of the records in the coupon database, in the order they're in the file.
+	Coupons(Coupon), first_key = "Code", what = "the coupon database"
+}
+
+impl Coupons {
+	/// Finds the coupon with the given code. Codes are matched without regard to letter case, as ShopSite does at checkout.
+	pub fn get(&self, code: &str) -> Option<&Coupon> {
+		self.0.iter().find(|coupon| coupon.code.eq_ignore_ascii_case(code))
+	}
+
+	/// The coupons that can still be used, as of `today`. See `Coupon::is_usable`.
+	pub fn usable(&self, today: NaiveDate) -> impl Iterator<Item = &Coupon> {
+		self.0.iter().filter(move |coupon| coupon.is_usable(today))
+	}
+}
+
+/// The spellings of `DiscountType`.
+#[derive(Clone, Copy, Default, Deserialize)]
+enum DiscountType {
+	#[default]
+	#[serde(alias = "amount", alias = "$")]
+	Amount,
+
+	#[serde(alias = "percent", alias = "%")]
+	Percent,
+
+	#[serde(rename = "Free Shipping", alias = "FreeShipping", alias = "free shipping")]
+	FreeShipping
+}
+
+/// How a coupon record is laid out, with the kind of discount apart from its amount.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+struct RawCoupon {
+	code: String,
+	name: String,
+	discount_type: DiscountType,
+	amount: Money,
+	#[serde(deserialize_with = "shopsite_aa::helpers::percent")]
+	percent: f64,
+	minimum_order: Option<Money>,
+	#[serde(with = "shopsite_aa::datetime::date::option")]
+	expires: Option<NaiveDate>,
+	max_uses: Option<u32>,
+	times_used: u32,
+	#[serde(deserialize_with = "shopsite_aa::helpers::yes_no")]
+	once_per_customer: bool
+}
+
+impl From<RawCoupon> for Coupon {
+	fn from(raw: RawCoupon) -> Self {
+		Coupon {
+			code: raw.code,
+			name: raw.name,
+			discount: match raw.discount_type {
+				DiscountType::Amount => Discount::Amount(raw.amount),
+				DiscountType::Percent => Discount::Percent(raw.percent),
+				DiscountType::FreeShipping => Discount::FreeShipping
+			},
+			minimum_order: raw.minimum_order,
+			expires: raw.expires,
+			max_uses: raw.max_uses,
+			times_used: raw.times_used,
+			once_per_customer: raw.once_per_customer
+		}
+	}
+}
@@ -0,0 +1,154 @@
+//! Records of the customer registration database.
+//! 
+//! ShopSite dumps its registered customers as one `.aa` file, with one record after another, each starting with the customer's `Email`. The billing and shipping addresses are in keys starting with `Billing ` and `Shipping `, as in orders:
+//! 
+//! ```
+//! use shopsite_types::Customers;
+//! 
+//! let customers = Customers::from_reader(&b"\
+//! Email: pat@example.com
+//! First Name: Pat
+//! Last Name: Smith
+//! Registered: 04/15/2020
+//! Newsletter: checked
+//! Billing City: Springfield
+//! Email: sam@example.com
+//! First Name: Sam
+//! "[..], None).unwrap();
+//! 
+//! let pat = customers.get("Pat@Example.com").unwrap();
+//! assert_eq!(pat.full_name(), "Pat Smith");
+//! assert_eq!(pat.billing.city, "Springfield");
+//! 
+//! assert_eq!(customers.newsletter().map(|customer| &customer.email[..]).collect::<Vec<_>>(), ["pat@example.com"]);
+//! ```
+//! 
+//! These records are full of personal information. To share them, or keep them around, consider `redact::Redact`.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use shopsite_aa::de as aa;
+use super::{
+	redact::{self, Redact},
+	Address
+};
+
+/// One record of the customer registration database.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "RawCustomer")]
+#[non_exhaustive]
+pub struct Customer {
+	/// The customer's email address, which identifies them. Spelled `Email`.
+	pub email: String,
+
+	/// Spelled `First Name`.
+	pub first_name: String,
+
+	/// Spelled `Last Name`.
+	pub last_name: String,
+
+	/// When the customer registered. Spelled `Registered`.
+	#[serde(with = "shopsite_aa::datetime::date::option")]
+	pub registered: Option<NaiveDate>,
+
+	/// The customer's billing address. Its keys start with `Billing `, like `Billing Name`.
+	pub billing: Address,
+
+	/// The customer's shipping address, if it's different from the billing address. Its keys start with `Shipping `, like `Shipping Name`.
+	pub shipping: Address,
+
+	/// Whether the customer asked for the store's newsletter. Spelled `Newsletter`.
+	pub newsletter: bool,
+
+	/// Whether the customer agreed to hear from the store's partners. Spelled `Partner Offers`.
+	pub partner_offers: bool
+}
+
+impl Customer {
+	/// The customer's first and last names, with a space between them if there are both.
+	pub fn full_name(&self) -> String {
+		[&self.first_name[..], &self.last_name[..]]
+			.iter()
+			.filter(|name| !name.is_empty())
+			.copied()
+			.collect::<Vec<_>>()
+			.join(" ")
+	}
+}
+
+impl Redact for Customer {
+	/// Replaces the customer's name and email address, and redacts their addresses. When they registered and what they opted into are kept.
+	fn redacted(&self) -> Self {
+		Customer {
+			email: redact::email(&self.email),
+			first_name: redact::text(&self.first_name),
+			last_name: redact::text(&self.last_name),
+			registered: self.registered,
+			billing: self.billing.redacted(),
+			shipping: self.shipping.redacted(),
+			newsletter: self.newsletter,
+			partner_offers: self.partner_offers
+		}
+	}
+}
+
+records! {
+	/// All of the records in the customer registration database, in the order they're in the file.
+	Customers(Customer), first_key = "Email", what = "the customer registration database"
+}
+
+impl Customers {
+	/// Finds the customer with the given email address, without regard to letter case.
+	pub fn get(&self, email: &str) -> Option<&Customer> {
+		self.0.iter().find(|customer| customer.email.eq_ignore_ascii_case(email))
+	}
+
+	/// The customers who asked for the store's newsletter.
+	pub fn newsletter(&self) -> impl Iterator<Item = &Customer> {
+		self.0.iter().filter(|customer| customer.newsletter)
+	}
+}
+
+impl Redact for Customers {
+	fn redacted(&self) -> Self {
+		Customers(self.0.redacted())
+	}
+}
+
+raw_with_addresses! {
+	/// How a customer record is laid out, with the fields of the addresses alongside the rest.
+	#[derive(Default, Deserialize)]
+	#[serde(default)]
+	struct RawCustomer {
+		#[serde(rename = "Email")]
+		email: String,
+		#[serde(rename = "First Name")]
+		first_name: String,
+		#[serde(rename = "Last Name")]
+		last_name: String,
+		#[serde(rename = "Registered", with = "shopsite_aa::datetime::date::option")]
+		registered: Option<NaiveDate>,
+		#[serde(rename = "Newsletter", deserialize_with = "shopsite_aa::helpers::yes_no")]
+		newsletter: bool,
+		#[serde(rename = "Partner Offers", deserialize_with = "shopsite_aa::helpers::yes_no")]
+		partner_offers: bool
+	}
+}
+
+impl From<RawCustomer> for Customer {
+	fn from(mut raw: RawCustomer) -> Self {
+		let billing = raw.billing_address();
+		let shipping = raw.shipping_address();
+
+		Customer {
+			email: raw.email,
+			first_name: raw.first_name,
+			last_name: raw.last_name,
+			registered: raw.registered,
+			billing,
+			shipping,
+			newsletter: raw.newsletter,
+			partner_offers: raw.partner_offers
+		}
+	}
+}
@@ -0,0 +1,91 @@
+//! Records of the gift certificate database.
+//! 
+//! ShopSite dumps its gift certificates as one `.aa` file, with one record after another, each starting with the certificate's `Code`:
+//! 
+//! ```
+//! use chrono::NaiveDate;
+//! use shopsite_types::GiftCertificates;
+//! 
+//! let certificates = GiftCertificates::from_reader(&b"\
+//! Code: GC-1001
+//! Amount: $50.00
+//! Balance: 12.50
+//! Issued: 12/20/2019
+//! Expires: 12/31/2020
+//! Code: GC-1002
+//! Amount: 25.00
+//! Balance: 0.00
+//! "[..], None).unwrap();
+//! 
+//! let today = NaiveDate::from_ymd_opt(2020, 6, 1).unwrap();
+//! assert_eq!(certificates.outstanding(today).to_string(), "12.50");
+//! assert!(certificates.get("GC-1002").unwrap().is_used_up());
+//! ```
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use shopsite_aa::{de as aa, money::Money};
+
+/// One record of the gift certificate database.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, rename_all(deserialize = "PascalCase"))]
+#[non_exhaustive]
+pub struct GiftCertificate {
+	/// The code that customers enter to use the certificate. Spelled `Code`.
+	pub code: String,
+
+	/// What the certificate was worth when it was issued. Spelled `Amount`.
+	pub amount: Money,
+
+	/// What's left of it. Spelled `Balance`.
+	pub balance: Money,
+
+	/// When the certificate was issued. Spelled `Issued`.
+	#[serde(with = "shopsite_aa::datetime::date::option")]
+	pub issued: Option<NaiveDate>,
+
+	/// The last day the certificate can be used, if it ever expires. Spelled `Expires`.
+	#[serde(with = "shopsite_aa::datetime::date::option")]
+	pub expires: Option<NaiveDate>,
+
+	/// The number of the order the certificate was bought in, if it was bought rather than given away. Spelled `Order Number`, as in orders.
+	#[serde(rename(deserialize = "Order Number"))]
+	pub order_number: String
+}
+
+impl GiftCertificate {
+	/// Whether the certificate has expired, as of `today`. It can still be used on the day it expires.
+	pub fn is_expired(&self, today: NaiveDate) -> bool {
+		self.expires.is_some_and(|expires| today > expires)
+	}
+
+	/// Whether the whole amount of the certificate has been spent.
+	pub fn is_used_up(&self) -> bool {
+		self.balance.0.is_sign_negative() || self.balance.0.is_zero()
+	}
+
+	/// How much was spent with the certificate so far.
+	pub fn spent(&self) -> Money {
+		Money(self.amount.0 - self.balance.0)
+	}
+}
+
+records! {
+	/// All of the records in the gift certificate database, in the order they're in the file.
+	GiftCertificates(GiftCertificate), first_key = "Code", what = "the gift certificate database"
+}
+
+impl GiftCertificates {
+	/// Finds the certificate with the given code. Codes are matched without regard to letter case, as ShopSite does at checkout.
+	pub fn get(&self, code: &str) -> Option<&GiftCertificate> {
+		self.0.iter().find(|certificate| certificate.code.eq_ignore_ascii_case(code))
+	}
+
+	/// The total balance of the certificates that haven't expired as of `today`: what the store still owes its customers.
+	pub fn outstanding(&self, today: NaiveDate) -> Money {
+		Money(self.0.iter()
+			.filter(|certificate| !certificate.is_expired(today) && !certificate.is_used_up())
+			.map(|certificate| certificate.balance.0)
+			.sum())
+	}
+}
@@ -0,0 +1,270 @@
+//! Strongly-typed models of ShopSite's data, read from its `.aa` files with the `shopsite-aa` library.
+//! 
+//! `shopsite-aa` can deserialize a `.aa` file into any struct, but every program that reads one has to declare that struct itself, with ShopSite's spelling of each key and the right `deserialize_with` helper for each field. This crate declares them once:
+//! 
+//! * `StoreConfig`, in the `store` module, is the store's main configuration file: its name, contact address, and locale, and how it takes payment, charges tax, and ships.
+//! * `Product`, in the `product` module, is a record of the product database, and `Products` is the whole database.
+//! * `Page`, in the `page` module, is a record of the page database, and `Pages` is the whole database.
+//! * `Order`, in the `order` module, is a record of an order export, with its line items, billing, and shipping, and `Orders` is the whole export. Addresses in orders are `Address`es, in the `address` module.
+//! * `Coupon` and `GiftCertificate`, in the `coupon` and `gift_certificate` modules, are records of the coupon and gift certificate databases, and `Coupons` and `GiftCertificates` are the whole databases.
+//! * `Customer`, in the `customer` module, is a record of the customer registration database, and `Customers` is the whole database.
+//! * `ShippingConfig` and `TaxConfig`, in the `shipping` and `tax` modules, are the shipping and tax rate configuration files: shipping carriers, zones, and rates, and tax rates by state.
+//! 
+//! Records with personal information in them, like customers and orders, can be copied without it, with the `Redact` trait in the `redact` module.
+//! 
+//! Each type can be deserialized with any `shopsite-aa` `Deserializer`, or from a `Value` with `de::from_value`, and also has `from_reader` and `from_file` methods that set the options ShopSite's own files need (see `deserializer_builder`). For databases of many records, those methods are on the collection type, like `Products`.
+//! 
+//! Fields are read leniently, the way ShopSite writes them: booleans can be `checked` or `Yes`, prices can have a `$` sign and `,` thousands separators, and missing keys take their defaults (except for the few that are required, like an order's `Date`, since a made-up value would be wrong), so a file from an older or newer version of ShopSite still reads. Fields that aren't modeled here are skipped. The types are `Serialize` too, with field names in snake case, for writing them out as JSON or the like.
+
+use serde::{Deserialize, Deserializer, de::DeserializeOwned};
+use shopsite_aa::de as aa;
+use std::{
+	fs::File,
+	io::Read,
+	path::Path,
+	sync::Arc
+};
+
+/// Defines a collection of all of the records in a database dump, like `Products`, with methods to read one, and the usual conversions to and from a `Vec` of records.
+macro_rules! records {
+	($(#[$doc:meta])* $name:ident($record:ty), first_key = $first_key:literal, what = $what:literal) => {
+		$(#[$doc])*
+		#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+		#[serde(transparent)]
+		pub struct $name(pub Vec<$record>);
+
+		impl $name {
+			#[doc = concat!("A `DeserializerBuilder` for reading ", $what, " one record at a time. It has the options from `deserializer_builder`, except that a record starts at each `", $first_key, "` key instead of the file being divided into sections, and keys that aren't fields of the record are skipped without being decoded (see `DeserializerBuilder::skip_unknown_keys`).")]
+			pub fn deserializer_builder() -> aa::DeserializerBuilder {
+				crate::records_builder($first_key)
+			}
+
+			#[doc = concat!("Reads ", $what, ", with the options from `deserializer_builder`. `file` is for error messages.")]
+			pub fn from_reader(reader: impl std::io::Read, file: Option<std::sync::Arc<std::path::Path>>) -> aa::Result<$name> {
+				crate::records_from_reader($name::deserializer_builder(), reader, file).map($name)
+			}
+
+			#[doc = concat!("Reads ", $what, ", with the options from `deserializer_builder`.")]
+			pub fn from_file(file: impl AsRef<std::path::Path>) -> aa::Result<$name> {
+				crate::records_from_file($name::deserializer_builder(), file.as_ref()).map($name)
+			}
+		}
+
+		impl std::ops::Deref for $name {
+			type Target = [$record];
+
+			fn deref(&self) -> &[$record] {
+				&self.0
+			}
+		}
+
+		impl From<Vec<$record>> for $name {
+			fn from(records: Vec<$record>) -> Self {
+				$name(records)
+			}
+		}
+
+		impl std::iter::FromIterator<$record> for $name {
+			fn from_iter<I: IntoIterator<Item = $record>>(iter: I) -> Self {
+				$name(iter.into_iter().collect())
+			}
+		}
+
+		impl IntoIterator for $name {
+			type Item = $record;
+			type IntoIter = std::vec::IntoIter<$record>;
+
+			fn into_iter(self) -> Self::IntoIter {
+				self.0.into_iter()
+			}
+		}
+
+		impl<'a> IntoIterator for &'a $name {
+			type Item = &'a $record;
+			type IntoIter = std::slice::Iter<'a, $record>;
+
+			fn into_iter(self) -> Self::IntoIter {
+				self.0.iter()
+			}
+		}
+	}
+}
+
+/// Defines a struct for deserializing a record with a billing and a shipping address, like an order. It has the fields given, and a field for each key of the addresses, like `Billing Name`, along with `billing_address` and `shipping_address` methods that gather them into `Address`es. The address fields default to empty, so the struct doesn't need `#[serde(default)]` unless its other fields should default, too.
+macro_rules! raw_with_addresses {
+	($(#[$meta:meta])* struct $name:ident { $($(#[$field_meta:meta])* $field:ident: $ty:ty),* $(,)? }) => {
+		$(#[$meta])*
+		struct $name {
+			$($(#[$field_meta])* $field: $ty,)*
+
+			#[serde(rename = "Billing Name", default)]
+			billing_name: String,
+			#[serde(rename = "Billing Company", default)]
+			billing_company: String,
+			#[serde(rename = "Billing Address 1", default)]
+			billing_address1: String,
+			#[serde(rename = "Billing Address 2", default)]
+			billing_address2: String,
+			#[serde(rename = "Billing City", default)]
+			billing_city: String,
+			#[serde(rename = "Billing State", default)]
+			billing_state: String,
+			#[serde(rename = "Billing Zip", default)]
+			billing_zip: String,
+			#[serde(rename = "Billing Country", default)]
+			billing_country: String,
+			#[serde(rename = "Billing Phone", default)]
+			billing_phone: String,
+
+			#[serde(rename = "Shipping Name", default)]
+			shipping_name: String,
+			#[serde(rename = "Shipping Company", default)]
+			shipping_company: String,
+			#[serde(rename = "Shipping Address 1", default)]
+			shipping_address1: String,
+			#[serde(rename = "Shipping Address 2", default)]
+			shipping_address2: String,
+			#[serde(rename = "Shipping City", default)]
+			shipping_city: String,
+			#[serde(rename = "Shipping State", default)]
+			shipping_state: String,
+			#[serde(rename = "Shipping Zip", default)]
+			shipping_zip: String,
+			#[serde(rename = "Shipping Country", default)]
+			shipping_country: String,
+			#[serde(rename = "Shipping Phone", default)]
+			shipping_phone: String
+		}
+
+		impl $name {
+			/// Takes the fields of the billing address.
+			fn billing_address(&mut self) -> $crate::Address {
+				$crate::Address {
+					name: std::mem::take(&mut self.billing_name),
+					company: std::mem::take(&mut self.billing_company),
+					address1: std::mem::take(&mut self.billing_address1),
+					address2: std::mem::take(&mut self.billing_address2),
+					city: std::mem::take(&mut self.billing_city),
+					state: std::mem::take(&mut self.billing_state),
+					zip: std::mem::take(&mut self.billing_zip),
+					country: std::mem::take(&mut self.billing_country),
+					phone: std::mem::take(&mut self.billing_phone)
+				}
+			}
+
+			/// Takes the fields of the shipping address.
+			fn shipping_address(&mut self) -> $crate::Address {
+				$crate::Address {
+					name: std::mem::take(&mut self.shipping_name),
+					company: std::mem::take(&mut self.shipping_company),
+					address1: std::mem::take(&mut self.shipping_address1),
+					address2: std::mem::take(&mut self.shipping_address2),
+					city: std::mem::take(&mut self.shipping_city),
+					state: std::mem::take(&mut self.shipping_state),
+					zip: std::mem::take(&mut self.shipping_zip),
+					country: std::mem::take(&mut self.shipping_country),
+					phone: std::mem::take(&mut self.shipping_phone)
+				}
+			}
+		}
+	}
+}
+
+pub mod address;
+pub mod coupon;
+pub mod customer;
+pub mod gift_certificate;
+pub mod order;
+pub mod page;
+pub mod product;
+pub mod redact;
+pub mod shipping;
+pub mod store;
+pub mod tax;
+
+pub use address::Address;
+pub use coupon::{Coupon, Coupons};
+pub use customer::{Customer, Customers};
+pub use gift_certificate::{GiftCertificate, GiftCertificates};
+pub use order::{Order, Orders};
+pub use page::{Page, Pages};
+pub use product::{Product, Products};
+pub use shipping::ShippingConfig;
+pub use store::StoreConfig;
+pub use tax::TaxConfig;
+
+/// A `DeserializerBuilder` with the options for reading ShopSite's own files: `Preset::ShopSite12`, with sections recognized in either syntax.
+/// 
+/// The `from_reader` and `from_file` methods of the types in this crate use these options. To read a file with different ones, such as `Preset::HandEdited` for a file that's been edited by hand, change them here and deserialize with the resulting `Deserializer`.
+pub fn deserializer_builder() -> aa::DeserializerBuilder {
+	let mut builder = aa::DeserializerBuilder::new();
+	builder
+		.preset(aa::Preset::ShopSite12)
+		.section_syntax(aa::SectionSyntax::Any);
+	builder
+}
+
+/// A `DeserializerBuilder` with the options from `deserializer_builder`, except that instead of sections, the file is divided into records, each starting with `first_key`. Keys that aren't fields of the record type are skipped without being decoded, which is safe because none of the record types here deny unknown fields.
+fn records_builder(first_key: &str) -> aa::DeserializerBuilder {
+	let mut builder = aa::DeserializerBuilder::new();
+	builder
+		.preset(aa::Preset::ShopSite12)
+		.record_boundary(aa::RecordBoundary::Key(first_key.to_string()))
+		.skip_unknown_keys(true);
+	builder
+}
+
+/// Deserializes one value from a reader, with the options from `deserializer_builder`.
+fn from_reader<T: DeserializeOwned>(reader: impl Read, file: Option<Arc<Path>>) -> aa::Result<T> {
+	T::deserialize(&mut deserializer_builder().build_from_read(reader, file))
+}
+
+/// Deserializes one value from a file, with the options from `deserializer_builder`.
+fn from_file<T: DeserializeOwned>(file: &Path) -> aa::Result<T> {
+	open(file, from_reader)
+}
+
+/// Deserializes a file without sections, like the shipping rate configuration, from a reader, with the options from `deserializer_builder`. With those options, everything in such a file is in the unnamed section.
+fn unsectioned_from_reader<T: DeserializeOwned + Default>(reader: impl Read, file: Option<Arc<Path>>) -> aa::Result<T> {
+	from_reader(reader, file).map(|unsectioned: Unsectioned<T>| unsectioned.general)
+}
+
+/// Deserializes a file without sections from a file, as with `unsectioned_from_reader`.
+fn unsectioned_from_file<T: DeserializeOwned + Default>(file: &Path) -> aa::Result<T> {
+	open(file, unsectioned_from_reader)
+}
+
+/// The unnamed section of a file, which is all of it if it has no sections.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct Unsectioned<T: Default> {
+	#[serde(rename = "")]
+	general: T
+}
+
+/// Deserializes all of the records from a reader, with the given options.
+fn records_from_reader<T: DeserializeOwned>(builder: aa::DeserializerBuilder, reader: impl Read, file: Option<Arc<Path>>) -> aa::Result<Vec<T>> {
+	builder.build_from_read(reader, file).records().collect()
+}
+
+/// Deserializes all of the records from a file, with the given options.
+fn records_from_file<T: DeserializeOwned>(builder: aa::DeserializerBuilder, file: &Path) -> aa::Result<Vec<T>> {
+	open(file, |fh, file| records_from_reader(builder, fh, file))
+}
+
+/// Opens a file and reads it with `read`, or fails with `Error::Io` naming the file.
+fn open<T>(file: &Path, read: impl FnOnce(File, Option<Arc<Path>>) -> aa::Result<T>) -> aa::Result<T> {
+	let file: Arc<Path> = file.into();
+
+	match File::open(&file) {
+		Ok(fh) => read(fh, Some(file)),
+		Err(error) => Err(aa::Error::Io { error, file: Some(file), pos: None })
+	}
+}
+
+/// Deserializes a sequence of text, leaving out empty elements, like the one after the `|` that ShopSite writes at the end of some lists.
+fn non_empty_elements<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+	let mut elements = Vec::<String>::deserialize(deserializer)?;
+	elements.retain(|element| !element.is_empty());
+	Ok(elements)
+}
@@ -0,0 +1,238 @@
+//! Records of ShopSite's order exports.
+//! 
+//! Orders are exported as one `.aa` file, with one record after another, each starting with its `Order Number`. The line items are in `Items`, a sequence with one element per item, each a sequence of the SKU, name, quantity, and unit price. The billing and shipping addresses are in keys starting with `Billing ` and `Shipping `:
+//! 
+//! ```
+//! use shopsite_types::Orders;
+//! 
+//! let orders = Orders::from_reader(&b"\
+//! Order Number: 1001
+//! Date: 04/15/2020 13:45:00
+//! Email: pat@example.com
+//! Items: MUG-001,Coffee Mug,2,12.50|TWL-002,Tea Towel,1,5.95
+//! Subtotal: 30.95
+//! Tax: 1.86
+//! Shipping Charge: 4.00
+//! Grand Total: $36.81
+//! Payment Method: Visa
+//! Billing Name: Pat Smith
+//! Billing City: Springfield
+//! Shipping Method: Ground
+//! "[..], None).unwrap();
+//! 
+//! let order = orders.get("1001").unwrap();
+//! assert_eq!(order.placed.to_string(), "2020-04-15 13:45:00");
+//! assert_eq!(order.items[0].total().to_string(), "25.00");
+//! assert_eq!(order.items_total(), order.subtotal);
+//! assert_eq!(order.billing.address.name, "Pat Smith");
+//! assert_eq!(order.shipping.method, "Ground");
+//! ```
+//! 
+//! Timestamps are `NaiveDateTime`s, since ShopSite doesn't say what time zone they're in. See the `datetime` module of `shopsite-aa`.
+
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Deserializer, Serialize};
+use shopsite_aa::{
+	de as aa,
+	money::{rust_decimal::Decimal, Money}
+};
+use super::{
+	address::Address,
+	redact::{self, Redact}
+};
+
+/// One order.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "RawOrder")]
+#[non_exhaustive]
+pub struct Order {
+	/// The order number, which identifies the order. Spelled `Order Number`.
+	pub order_number: String,
+
+	/// When the order was placed. Spelled `Date`. Unlike the other fields, this one is required.
+	#[serde(with = "shopsite_aa::datetime::naive")]
+	pub placed: NaiveDateTime,
+
+	/// The email address of the customer who placed the order. Spelled `Email`.
+	pub email: String,
+
+	/// What was ordered. Spelled `Items`.
+	pub items: Vec<OrderItem>,
+
+	/// The total price of the items, before discounts, tax, and shipping. Spelled `Subtotal`.
+	pub subtotal: Money,
+
+	/// How much was taken off the subtotal, by coupons and the like. Spelled `Discount`.
+	pub discount: Money,
+
+	/// The tax charged. Spelled `Tax`.
+	pub tax: Money,
+
+	/// What the customer paid, all told. Spelled `Grand Total`.
+	pub total: Money,
+
+	/// Who paid for the order, and how.
+	pub billing: Billing,
+
+	/// Where the order is going, and how.
+	pub shipping: Shipping
+}
+
+impl Order {
+	/// The total price of the items, figured from their quantities and prices. This is normally the same as `subtotal`.
+	pub fn items_total(&self) -> Money {
+		Money(self.items.iter().map(|item| item.total().0).sum())
+	}
+
+	/// The address the order ships to: the shipping address, or the billing address if there isn't one.
+	pub fn ship_to(&self) -> &Address {
+		if self.shipping.address.is_empty() { &self.billing.address } else { &self.shipping.address }
+	}
+}
+
+/// One line of an order: a product, and how many of it were ordered.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "(String, String, u32, Money)")]
+#[non_exhaustive]
+pub struct OrderItem {
+	/// The product's SKU.
+	pub sku: String,
+
+	/// The product's name, as of when the order was placed.
+	pub name: String,
+
+	/// How many were ordered.
+	pub quantity: u32,
+
+	/// The price of each one.
+	pub price: Money
+}
+
+impl OrderItem {
+	/// The total price of this line: the price times the quantity.
+	pub fn total(&self) -> Money {
+		Money(self.price.0 * Decimal::from(self.quantity))
+	}
+}
+
+impl From<(String, String, u32, Money)> for OrderItem {
+	fn from((sku, name, quantity, price): (String, String, u32, Money)) -> Self {
+		OrderItem { sku, name, quantity, price }
+	}
+}
+
+/// Who paid for an order, and how.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct Billing {
+	/// The billing address. Its keys start with `Billing `, like `Billing Name`.
+	pub address: Address,
+
+	/// How the order was paid for, like `Visa`. Spelled `Payment Method`.
+	pub payment_method: String
+}
+
+/// Where an order is going, and how.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Serialize)]
+#[non_exhaustive]
+pub struct Shipping {
+	/// The shipping address. Its keys start with `Shipping `, like `Shipping Name`. If they're all empty, the order ships to the billing address.
+	pub address: Address,
+
+	/// How the order ships, like `Ground`. Spelled `Shipping Method`.
+	pub method: String,
+
+	/// What the customer paid for shipping. Spelled `Shipping Charge`.
+	pub charge: Money
+}
+
+impl Redact for Order {
+	/// Replaces the customer's email address, and redacts the billing and shipping addresses. What was ordered, and what it cost, are kept.
+	fn redacted(&self) -> Self {
+		let mut order = self.clone();
+		order.email = redact::email(&self.email);
+		order.billing.address = self.billing.address.redacted();
+		order.shipping.address = self.shipping.address.redacted();
+		order
+	}
+}
+
+records! {
+	/// All of the records in an order export, in the order they're in the file.
+	Orders(Order), first_key = "Order Number", what = "an order export"
+}
+
+impl Orders {
+	/// Finds the order with the given order number.
+	pub fn get(&self, order_number: &str) -> Option<&Order> {
+		self.0.iter().find(|order| order.order_number == order_number)
+	}
+}
+
+impl Redact for Orders {
+	fn redacted(&self) -> Self {
+		Orders(self.0.redacted())
+	}
+}
+
+/// Deserializes `Order::items`, leaving out empty items, like the one after the `|` that ShopSite writes at the end of some lists.
+fn items<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<OrderItem>, D::Error> {
+	let items = Vec::<Option<OrderItem>>::deserialize(deserializer)?;
+	Ok(items.into_iter().flatten().collect())
+}
+
+raw_with_addresses! {
+	/// How an order record is laid out, with the fields of the addresses alongside the rest. Every field but `placed` has a default; an order without a `Date` is an error, rather than being given a made-up one.
+	#[derive(Deserialize)]
+	struct RawOrder {
+		#[serde(rename = "Order Number", default)]
+		order_number: String,
+		#[serde(rename = "Date", deserialize_with = "shopsite_aa::datetime::naive::deserialize")]
+		placed: NaiveDateTime,
+		#[serde(rename = "Email", default)]
+		email: String,
+		#[serde(rename = "Items", deserialize_with = "items", default)]
+		items: Vec<OrderItem>,
+		#[serde(rename = "Subtotal", default)]
+		subtotal: Money,
+		#[serde(rename = "Discount", default)]
+		discount: Money,
+		#[serde(rename = "Tax", default)]
+		tax: Money,
+		#[serde(rename = "Grand Total", default)]
+		total: Money,
+		#[serde(rename = "Payment Method", default)]
+		payment_method: String,
+		#[serde(rename = "Shipping Method", default)]
+		shipping_method: String,
+		#[serde(rename = "Shipping Charge", default)]
+		shipping_charge: Money
+	}
+}
+
+impl From<RawOrder> for Order {
+	fn from(mut raw: RawOrder) -> Self {
+		let billing_address = raw.billing_address();
+		let shipping_address = raw.shipping_address();
+
+		Order {
+			order_number: raw.order_number,
+			placed: raw.placed,
+			email: raw.email,
+			items: raw.items,
+			subtotal: raw.subtotal,
+			discount: raw.discount,
+			tax: raw.tax,
+			total: raw.total,
+			billing: Billing {
+				address: billing_address,
+				payment_method: raw.payment_method
+			},
+			shipping: Shipping {
+				address: shipping_address,
+				method: raw.shipping_method,
+				charge: raw.shipping_charge
+			}
+		}
+	}
+}
@@ -0,0 +1,98 @@
+//! Records of the page database.
+//! 
+//! Like the product database, ShopSite dumps its page database as one `.aa` file, with one record after another, each starting with the page's `Name`:
+//! 
+//! ```
+//! use shopsite_types::Pages;
+//! 
+//! let pages = Pages::from_reader(&b"\
+//! Name: Mugs
+//! FileName: mugs.html
+//! LinkText: Mugs &amp; Cups
+//! Layout: two-column
+//! Products: MUG-001|MUG-002|
+//! MetaKeywords: mugs, cups, coffee
+//! Name: About Us
+//! FileName: about.html
+//! Text1: <p>We make widgets.</p>
+//! "[..], None).unwrap();
+//! 
+//! let mugs = pages.get("Mugs").unwrap();
+//! assert_eq!(mugs.link_text, "Mugs & Cups");
+//! assert_eq!(mugs.products, ["MUG-001", "MUG-002"]);
+//! assert_eq!(mugs.meta_keywords, ["mugs", "cups", "coffee"]);
+//! 
+//! assert_eq!(pages.with_product("MUG-002").map(|page| &page.name[..]).collect::<Vec<_>>(), ["Mugs"]);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use shopsite_aa::de as aa;
+
+/// One record of the page database.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(default, rename_all(deserialize = "PascalCase"))]
+#[non_exhaustive]
+pub struct Page {
+	/// The page's name, which identifies it in the back office. Spelled `Name`.
+	pub name: String,
+
+	/// The name of the HTML file the page is published as, like `mugs.html`. Spelled `FileName`.
+	pub file_name: String,
+
+	/// The text of links to the page from other pages, with HTML character references like `&amp;` decoded. Spelled `LinkText`.
+	#[serde(deserialize_with = "shopsite_aa::helpers::html_string")]
+	pub link_text: String,
+
+	/// The name of the template the page is laid out with. Spelled `Layout`.
+	pub layout: String,
+
+	/// Text at the top of the page, in HTML. Spelled `Header`.
+	pub header: String,
+
+	/// Text above the page's products, in HTML. Spelled `Text1`.
+	pub text1: String,
+
+	/// Text below the page's products, in HTML. Spelled `Text2`.
+	pub text2: String,
+
+	/// Text at the bottom of the page, in HTML. Spelled `Footer`.
+	pub footer: String,
+
+	/// SKUs of the products on the page, in the order they're shown. Spelled `Products`.
+	#[serde(deserialize_with = "crate::non_empty_elements")]
+	pub products: Vec<String>,
+
+	/// The page's title, for the `<title>` element, if it's different from `name`. Spelled `MetaTitle`.
+	pub meta_title: String,
+
+	/// The page's description, for the `description` meta tag. Spelled `MetaDescription`.
+	pub meta_description: String,
+
+	/// The page's keywords, for the `keywords` meta tag. Spelled `MetaKeywords`, and written as a comma-separated list.
+	#[serde(deserialize_with = "shopsite_aa::helpers::comma_list")]
+	pub meta_keywords: Vec<String>
+}
+
+impl Page {
+	/// The page's title: `meta_title`, if it has one, or else its name.
+	pub fn title(&self) -> &str {
+		if self.meta_title.is_empty() { &self.name } else { &self.meta_title }
+	}
+}
+
+records! {
+	/// All of the records in the page database, in the order they're in the file.
+	Pages(Page), first_key = "Name", what = "the page database"
+}
+
+impl Pages {
+	/// Finds the page with the given name. If there's more than one, this is the first.
+	pub fn get(&self, name: &str) -> Option<&Page> {
+		self.0.iter().find(|page| page.name == name)
+	}
+
+	/// The pages that the product with the given SKU is on.
+	pub fn with_product<'a>(&'a self, sku: &'a str) -> impl Iterator<Item = &'a Page> {
+		self.0.iter().filter(move |page| page.products.iter().any(|product| product == sku))
+	}
+}
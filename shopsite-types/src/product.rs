@@ -0,0 +1,130 @@
+//! Records of the product database.
+//! 
+//! ShopSite dumps its product database as one `.aa` file, with one record after another. Each record starts with the product's `Name`, and blank lines between them are optional:
+//! 
+//! ```
+//! use shopsite_types::Products;
+//! 
+//! let products = Products::from_reader(&b"\
+//! Name: Coffee Mug
+//! SKU: MUG-001
+//! Price: 12.50
+//! Options: Color,Red,Blue|Size,12 oz.,16 oz.
+//! Name: Tea Towel
+//! SKU: TWL-002
+//! Price: $7.95
+//! SalePrice: 5.95
+//! CrossSell: MUG-001|
+//! "[..], None).unwrap();
+//! 
+//! assert_eq!(products.len(), 2);
+//! assert_eq!(products[0].options[1].name, "Size");
+//! assert_eq!(products[0].options[1].choices, ["12 oz.", "16 oz."]);
+//! 
+//! let towel = products.get("TWL-002").unwrap();
+//! assert_eq!(towel.current_price().to_string(), "5.95");
+//! assert_eq!(towel.cross_sell, ["MUG-001"]);
+//! ```
+//! 
+//! A big store's database can be large. To look at one product at a time, without reading them all into memory, use `Products::deserializer_builder` and `Deserializer::records`.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use shopsite_aa::{de as aa, money::Money};
+
+/// One record of the product database.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all(deserialize = "PascalCase"))]
+#[non_exhaustive]
+pub struct Product {
+	/// The product's stock-keeping unit, which identifies it. Spelled `SKU`.
+	#[serde(rename(deserialize = "SKU"))]
+	pub sku: String,
+
+	/// The product's name, with HTML character references like `&amp;` decoded. Spelled `Name`.
+	#[serde(deserialize_with = "shopsite_aa::helpers::html_string")]
+	pub name: String,
+
+	/// The product's regular price. Spelled `Price`.
+	pub price: Money,
+
+	/// The product's price while it's on sale, if it has one. Spelled `SalePrice`.
+	pub sale_price: Option<Money>,
+
+	/// How much the product weighs, in the store's unit of weight, for figuring shipping. Spelled `Weight`.
+	pub weight: Option<f64>,
+
+	/// The choices the customer makes when ordering, like color and size. Spelled `Options`, and written like `Color,Red,Blue|Size,Small,Large`: a sequence of options, each a sequence of its name followed by its choices.
+	#[serde(deserialize_with = "options")]
+	pub options: Vec<ProductOption>,
+
+	/// File names of the product's pictures, the first of which is the main one. Spelled `Images`.
+	#[serde(deserialize_with = "crate::non_empty_elements")]
+	pub images: Vec<String>,
+
+	/// SKUs of other products to suggest along with this one. Spelled `CrossSell`.
+	#[serde(deserialize_with = "crate::non_empty_elements")]
+	pub cross_sell: Vec<String>,
+
+	/// The product's description, in HTML. Spelled `ProductDescription`.
+	pub product_description: String,
+
+	/// Whether the product has a page of its own, with more information than fits on the pages it's listed on. Spelled `DisplayMoreInformationPage`.
+	#[serde(rename(deserialize = "DisplayMoreInformationPage"), deserialize_with = "shopsite_aa::helpers::yes_no")]
+	pub more_info_page: bool,
+
+	/// The file name of the more-information page. Spelled `MoreInfoFileName`.
+	pub more_info_file_name: String,
+
+	/// The text of the more-information page, in HTML, if it's different from `product_description`. Spelled `MoreInfoText`.
+	pub more_info_text: String
+}
+
+impl Product {
+	/// What the product sells for now: the sale price, if it has one, or else the regular price.
+	pub fn current_price(&self) -> Money {
+		self.sale_price.unwrap_or(self.price)
+	}
+}
+
+/// One of a product's ordering options, like its color.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "Vec<String>")]
+#[non_exhaustive]
+pub struct ProductOption {
+	/// The option's name, like `Color`.
+	pub name: String,
+
+	/// What the customer can choose from, like `Red` and `Blue`.
+	pub choices: Vec<String>
+}
+
+impl From<Vec<String>> for ProductOption {
+	/// Takes the first element as the name, and the rest as the choices.
+	fn from(mut elements: Vec<String>) -> Self {
+		let name = if elements.is_empty() { String::new() } else { elements.remove(0) };
+
+		ProductOption {
+			name,
+			choices: elements
+		}
+	}
+}
+
+/// Deserializes `Product::options`, leaving out options without a name, like the one after the `|` that ShopSite writes at the end of some lists.
+fn options<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<ProductOption>, D::Error> {
+	let mut options = Vec::<ProductOption>::deserialize(deserializer)?;
+	options.retain(|option| !option.name.is_empty());
+	Ok(options)
+}
+
+records! {
+	/// All of the records in the product database, in the order they're in the file.
+	Products(Product), first_key = "Name", what = "the product database"
+}
+
+impl Products {
+	/// Finds the product with the given SKU. If there's more than one, this is the first.
+	pub fn get(&self, sku: &str) -> Option<&Product> {
+		self.0.iter().find(|product| product.sku == sku)
+	}
+}
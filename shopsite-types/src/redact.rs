@@ -0,0 +1,68 @@
+//! Removing personal information from records, so that they can be shared, such as with a consultant or in a bug report, or kept for longer than the personal information should be.
+//! 
+//! `Redact::redacted` makes a copy of a record with names, street addresses, phone numbers, and email addresses replaced with `REDACTED`. What's left is still good for reports: states, countries, and the domains of email addresses are kept, as is everything that isn't personal, like what was ordered and when. Empty fields are left empty, so it's still visible which ones were filled in.
+//! 
+//! ```
+//! use shopsite_types::{redact::Redact, Customers};
+//! 
+//! let customers = Customers::from_reader(&b"\
+//! Email: pat@example.com
+//! First Name: Pat
+//! Billing City: Springfield
+//! Billing State: IL
+//! "[..], None).unwrap();
+//! 
+//! let redacted = customers.redacted();
+//! assert_eq!(redacted[0].email, "[redacted]@example.com");
+//! assert_eq!(redacted[0].first_name, "[redacted]");
+//! assert_eq!(redacted[0].last_name, "");
+//! assert_eq!(redacted[0].billing.city, "[redacted]");
+//! assert_eq!(redacted[0].billing.state, "IL");
+//! ```
+
+use super::Address;
+
+/// What personal information is replaced with.
+pub const REDACTED: &str = "[redacted]";
+
+/// Records that have personal information in them.
+pub trait Redact {
+	/// Makes a copy of this record with the personal information replaced with `REDACTED`. See the module documentation.
+	fn redacted(&self) -> Self;
+}
+
+/// Replaces some text with `REDACTED`, unless it's empty.
+pub fn text(text: &str) -> String {
+	if text.is_empty() { String::new() } else { REDACTED.to_string() }
+}
+
+/// Replaces the part of an email address before the `@` with `REDACTED`, keeping the domain, like `[redacted]@example.com`. Something without an `@` in it is replaced entirely, as with `text`.
+pub fn email(email: &str) -> String {
+	match email.rfind('@') {
+		Some(at) => format!("{}{}", REDACTED, &email[at..]),
+		None => text(email)
+	}
+}
+
+impl Redact for Address {
+	/// Keeps the state and country, and replaces the rest.
+	fn redacted(&self) -> Self {
+		Address {
+			name: text(&self.name),
+			company: text(&self.company),
+			address1: text(&self.address1),
+			address2: text(&self.address2),
+			city: text(&self.city),
+			state: self.state.clone(),
+			zip: text(&self.zip),
+			country: self.country.clone(),
+			phone: text(&self.phone)
+		}
+	}
+}
+
+impl<T: Redact> Redact for Vec<T> {
+	fn redacted(&self) -> Self {
+		self.iter().map(Redact::redacted).collect()
+	}
+}
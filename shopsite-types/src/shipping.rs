@@ -0,0 +1,195 @@
+//! The shipping rate configuration.
+//! 
+//! ShopSite keeps its shipping rates apart from the store configuration, in a file of their own. It lists the carriers and the services each one offers, the zones that countries are grouped into, and a table of rates by service and zone. Each of these is a sequence of sequences, like `UPS,Ground,2nd Day Air|USPS,Priority Mail`:
+//! 
+//! ```
+//! use shopsite_types::{shipping::RateBasis, ShippingConfig};
+//! 
+//! let config = ShippingConfig::from_reader(&b"\
+//! Carriers: UPS,Ground,2nd Day Air|USPS,Priority Mail|
+//! Zones: Domestic,US|North America,CA,MX|Rest of World,*|
+//! RateBasis: Weight
+//! Rates: Ground,Domestic,0,5.00|Ground,Domestic,5,8.50|Ground,North America,0,$15.00|
+//! "[..], None).unwrap();
+//! 
+//! assert_eq!(config.carriers[0].services, ["Ground", "2nd Day Air"]);
+//! assert_eq!(config.rate_basis, RateBasis::Weight);
+//! 
+//! let zone = config.zone_for("mx").unwrap();
+//! assert_eq!(zone.name, "North America");
+//! assert_eq!(config.zone_for("FR").unwrap().name, "Rest of World");
+//! 
+//! assert_eq!(config.rate("Ground", "Domestic", 7.0).unwrap().to_string(), "8.50");
+//! assert_eq!(config.rate("Ground", "North America", 2.0).unwrap().to_string(), "15.00");
+//! assert_eq!(config.rate("2nd Day Air", "Domestic", 2.0), None);
+//! ```
+//! 
+//! How much an order's handling fee is, and how big it has to be to ship free, are in the `Shipping` section of the store configuration. See `store::ShippingSettings`.
+
+use serde::{Deserialize, Deserializer, Serialize};
+use shopsite_aa::{de as aa, money::Money};
+use std::{
+	io::Read,
+	path::Path,
+	sync::Arc
+};
+
+/// The shipping rate configuration. See the module documentation.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all(deserialize = "PascalCase"))]
+#[non_exhaustive]
+pub struct ShippingConfig {
+	/// The carriers the store ships with. Spelled `Carriers`, and written like `UPS,Ground,2nd Day Air|USPS,Priority Mail`: a sequence of carriers, each a sequence of its name followed by its services.
+	#[serde(deserialize_with = "carriers")]
+	pub carriers: Vec<Carrier>,
+
+	/// The zones that destinations are grouped into, for figuring rates. Spelled `Zones`, and written like `Domestic,US|North America,CA,MX`: a sequence of zones, each a sequence of its name followed by its countries.
+	#[serde(deserialize_with = "zones")]
+	pub zones: Vec<Zone>,
+
+	/// What the thresholds in `rates` are measured in. Spelled `RateBasis`.
+	pub rate_basis: RateBasis,
+
+	/// The rate table. Spelled `Rates`, and written like `Ground,Domestic,0,5.00|Ground,Domestic,5,8.50`: a sequence of rates, each a sequence of the service, the zone, the threshold, and the charge.
+	#[serde(deserialize_with = "rates")]
+	pub rates: Vec<ShippingRate>
+}
+
+impl ShippingConfig {
+	/// Reads a shipping rate configuration file, with the options from `deserializer_builder`. `file` is for error messages.
+	pub fn from_reader(reader: impl Read, file: Option<Arc<Path>>) -> aa::Result<ShippingConfig> {
+		crate::unsectioned_from_reader(reader, file)
+	}
+
+	/// Reads a shipping rate configuration file, with the options from `deserializer_builder`.
+	pub fn from_file(file: impl AsRef<Path>) -> aa::Result<ShippingConfig> {
+		crate::unsectioned_from_file(file.as_ref())
+	}
+
+	/// Finds the zone that a country, given by its ISO 3166 code like `US`, is in. This is the first zone that lists the country, without regard to letter case, or else the first zone that lists `*`, if any.
+	pub fn zone_for(&self, country: &str) -> Option<&Zone> {
+		self.zones.iter().find(|zone| zone.countries.iter().any(|c| c.eq_ignore_ascii_case(country)))
+			.or_else(|| self.zones.iter().find(|zone| zone.countries.iter().any(|c| c == "*")))
+	}
+
+	/// Finds the charge for shipping with the given service to the given zone, for an order of the given `amount`, measured as `rate_basis` says.
+	/// 
+	/// This is the charge of the rate, of those for that service and zone, with the highest threshold that's no more than `amount`. Returns `None` if there isn't one, meaning the service doesn't ship there, or not orders that small.
+	pub fn rate(&self, service: &str, zone: &str, amount: f64) -> Option<Money> {
+		self.rates.iter()
+			.filter(|rate| rate.service == service && rate.zone == zone && rate.from <= amount)
+			.max_by(|a, b| a.from.partial_cmp(&b.from).unwrap_or(std::cmp::Ordering::Equal))
+			.map(|rate| rate.charge)
+	}
+}
+
+/// A shipping carrier, and the services it offers.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "Vec<String>")]
+#[non_exhaustive]
+pub struct Carrier {
+	/// The carrier's name, like `UPS`.
+	pub name: String,
+
+	/// The carrier's services that the store offers, like `Ground`. These are the names that `ShippingRate::service` refers to.
+	pub services: Vec<String>
+}
+
+impl From<Vec<String>> for Carrier {
+	/// Takes the first element as the name, and the rest as the services.
+	fn from(mut elements: Vec<String>) -> Self {
+		let name = if elements.is_empty() { String::new() } else { elements.remove(0) };
+
+		Carrier {
+			name,
+			services: elements
+		}
+	}
+}
+
+/// A group of countries that are charged the same rates.
+#[derive(Clone, Debug, Default, Deserialize, Eq, PartialEq, Serialize)]
+#[serde(from = "Vec<String>")]
+#[non_exhaustive]
+pub struct Zone {
+	/// The zone's name, like `Domestic`. This is the name that `ShippingRate::zone` refers to.
+	pub name: String,
+
+	/// The ISO 3166 codes of the countries in the zone, like `US`. `*` stands for every country that isn't in another zone.
+	pub countries: Vec<String>
+}
+
+impl From<Vec<String>> for Zone {
+	/// Takes the first element as the name, and the rest as the countries.
+	fn from(mut elements: Vec<String>) -> Self {
+		let name = if elements.is_empty() { String::new() } else { elements.remove(0) };
+
+		Zone {
+			name,
+			countries: elements
+		}
+	}
+}
+
+/// What the thresholds of shipping rates are measured in.
+#[derive(Clone, Copy, Debug, Default, Deserialize, Eq, Hash, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+#[non_exhaustive]
+pub enum RateBasis {
+	/// The total weight of the order, in the store's unit of weight. Spelled `Weight`.
+	#[default]
+	#[serde(alias = "Weight")]
+	Weight,
+
+	/// The order's subtotal, in the store's currency. Spelled `Subtotal`.
+	#[serde(alias = "Subtotal", alias = "Price")]
+	Subtotal,
+
+	/// How many items are in the order. Spelled `Quantity`.
+	#[serde(alias = "Quantity")]
+	Quantity
+}
+
+/// One line of the rate table: what a service charges to ship to a zone, for orders of at least some size.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(from = "(String, String, f64, Money)")]
+#[non_exhaustive]
+pub struct ShippingRate {
+	/// The name of the service, like `Ground`. See `Carrier::services`.
+	pub service: String,
+
+	/// The name of the zone. See `Zone::name`.
+	pub zone: String,
+
+	/// The smallest order that this rate applies to, measured as `ShippingConfig::rate_basis` says. It applies up to the next higher threshold for the same service and zone.
+	pub from: f64,
+
+	/// What shipping costs.
+	pub charge: Money
+}
+
+impl From<(String, String, f64, Money)> for ShippingRate {
+	fn from((service, zone, from, charge): (String, String, f64, Money)) -> Self {
+		ShippingRate { service, zone, from, charge }
+	}
+}
+
+/// Deserializes `ShippingConfig::carriers`, leaving out carriers without a name, like the one after the `|` that ShopSite writes at the end of some lists.
+fn carriers<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Carrier>, D::Error> {
+	let mut carriers = Vec::<Carrier>::deserialize(deserializer)?;
+	carriers.retain(|carrier| !carrier.name.is_empty());
+	Ok(carriers)
+}
+
+/// Deserializes `ShippingConfig::zones`, leaving out zones without a name, as with `carriers`.
+fn zones<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<Zone>, D::Error> {
+	let mut zones = Vec::<Zone>::deserialize(deserializer)?;
+	zones.retain(|zone| !zone.name.is_empty());
+	Ok(zones)
+}
+
+/// Deserializes `ShippingConfig::rates`, leaving out empty rates, as with `carriers`.
+fn rates<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<ShippingRate>, D::Error> {
+	let rates = Vec::<Option<ShippingRate>>::deserialize(deserializer)?;
+	Ok(rates.into_iter().flatten().collect())
+}
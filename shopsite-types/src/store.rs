@@ -0,0 +1,188 @@
+//! The store's main configuration file.
+//! 
+//! General settings, like the store's name, come first, followed by a section each for payment, tax, and shipping settings:
+//! 
+//! ```
+//! use shopsite_types::StoreConfig;
+//! 
+//! let config = StoreConfig::from_reader(&b"\
+//! StoreName: Caf\xe9 Widgets
+//! Email: orders@example.com
+//! Locale: en-US
+//! [Payment]
+//! Methods: Visa|MasterCard|PayPal
+//! [Tax]
+//! Rate: 7.5%
+//! TaxShipping: checked
+//! [Shipping]
+//! Methods: Ground|2nd Day Air
+//! FreeShippingOver: $50.00
+//! "[..], None).unwrap();
+//! 
+//! assert_eq!(config.store_name, "Café Widgets");
+//! assert_eq!(config.payment.methods, ["Visa", "MasterCard", "PayPal"]);
+//! assert_eq!(config.tax.rate, 7.5);
+//! assert!(config.tax.tax_shipping);
+//! assert_eq!(config.shipping.free_shipping_over.unwrap().to_string(), "50.00");
+//! ```
+//! 
+//! Sections can also be written `BEGIN_Payment` … `END_Payment`. A file without any sections is all general settings, and the sections take their defaults.
+
+use serde::{Deserialize, Serialize};
+use shopsite_aa::{de as aa, money::Money};
+use std::{
+	io::Read,
+	path::Path,
+	sync::Arc
+};
+
+/// The store's main configuration file. See the module documentation.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(from = "RawStoreConfig")]
+#[non_exhaustive]
+pub struct StoreConfig {
+	/// The store's name, as shown to customers. Spelled `StoreName`.
+	pub store_name: String,
+
+	/// ShopSite's identifier for the store. Spelled `StoreID`.
+	pub store_id: String,
+
+	/// The address that order notices and customer email come from. Spelled `Email`.
+	pub email: String,
+
+	/// The store's locale, like `en-US`. Spelled `Locale`.
+	pub locale: String,
+
+	/// The ISO 4217 code of the currency that prices are in, like `USD`. Spelled `Currency`.
+	pub currency: String,
+
+	/// The symbol written before prices, like `$`. Spelled `CurrencySymbol`.
+	pub currency_symbol: String,
+
+	/// The `Payment` section.
+	pub payment: PaymentSettings,
+
+	/// The `Tax` section.
+	pub tax: TaxSettings,
+
+	/// The `Shipping` section.
+	pub shipping: ShippingSettings
+}
+
+impl StoreConfig {
+	/// Reads a store configuration file, with the options from `deserializer_builder`. `file` is for error messages.
+	pub fn from_reader(reader: impl Read, file: Option<Arc<Path>>) -> aa::Result<StoreConfig> {
+		crate::from_reader(reader, file)
+	}
+
+	/// Reads a store configuration file, with the options from `deserializer_builder`.
+	pub fn from_file(file: impl AsRef<Path>) -> aa::Result<StoreConfig> {
+		crate::from_file(file.as_ref())
+	}
+}
+
+/// How the store takes payment: the `Payment` section of the store configuration.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all(deserialize = "PascalCase"))]
+#[non_exhaustive]
+pub struct PaymentSettings {
+	/// The payment methods offered at checkout, like `Visa` and `PayPal`. Spelled `Methods`.
+	#[serde(deserialize_with = "crate::non_empty_elements")]
+	pub methods: Vec<String>,
+
+	/// The payment processor that charges cards, if any. Spelled `Processor`.
+	pub processor: Option<String>,
+
+	/// Whether payments go to the processor's test system, so no one is actually charged. Spelled `TestMode`.
+	#[serde(deserialize_with = "shopsite_aa::helpers::yes_no")]
+	pub test_mode: bool
+}
+
+/// How the store charges tax: the `Tax` section of the store configuration.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all(deserialize = "PascalCase"))]
+#[non_exhaustive]
+pub struct TaxSettings {
+	/// The tax rate, as a percentage, so `7.5%` is `7.5`. Spelled `Rate`.
+	#[serde(deserialize_with = "shopsite_aa::helpers::percent")]
+	pub rate: f64,
+
+	/// Whether shipping charges are taxed too. Spelled `TaxShipping`.
+	#[serde(deserialize_with = "shopsite_aa::helpers::yes_no")]
+	pub tax_shipping: bool,
+
+	/// Whether prices already include tax, as is usual outside the United States. Spelled `PricesIncludeTax`.
+	#[serde(deserialize_with = "shopsite_aa::helpers::yes_no")]
+	pub prices_include_tax: bool,
+
+	/// Whether tax is shown in the shopping cart, before checkout. Spelled `ShowOnCart`.
+	#[serde(deserialize_with = "shopsite_aa::helpers::yes_no")]
+	pub show_on_cart: bool
+}
+
+/// How the store ships: the `Shipping` section of the store configuration.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all(deserialize = "PascalCase"))]
+#[non_exhaustive]
+pub struct ShippingSettings {
+	/// The shipping methods offered at checkout, like `Ground`. Spelled `Methods`.
+	#[serde(deserialize_with = "crate::non_empty_elements")]
+	pub methods: Vec<String>,
+
+	/// The postal code that orders ship from, for carriers that figure rates by distance. Spelled `OriginZip`.
+	pub origin_zip: Option<String>,
+
+	/// A fee added to the shipping charge of every order. Spelled `HandlingFee`.
+	pub handling_fee: Option<Money>,
+
+	/// Orders whose subtotal is at least this much ship free. Spelled `FreeShippingOver`.
+	pub free_shipping_over: Option<Money>
+}
+
+/// How a store configuration file is laid out: the general settings in the unnamed section at the top, and the rest in sections of their own.
+#[derive(Default, Deserialize)]
+#[serde(default)]
+struct RawStoreConfig {
+	#[serde(rename = "")]
+	general: General,
+
+	#[serde(rename = "Payment")]
+	payment: PaymentSettings,
+
+	#[serde(rename = "Tax")]
+	tax: TaxSettings,
+
+	#[serde(rename = "Shipping")]
+	shipping: ShippingSettings
+}
+
+/// The unnamed section of a store configuration file.
+#[derive(Default, Deserialize)]
+#[serde(default, rename_all = "PascalCase")]
+struct General {
+	store_name: String,
+	#[serde(rename = "StoreID")]
+	store_id: String,
+	email: String,
+	locale: String,
+	currency: String,
+	currency_symbol: String
+}
+
+impl From<RawStoreConfig> for StoreConfig {
+	fn from(raw: RawStoreConfig) -> Self {
+		let RawStoreConfig { general, payment, tax, shipping } = raw;
+
+		StoreConfig {
+			store_name: general.store_name,
+			store_id: general.store_id,
+			email: general.email,
+			locale: general.locale,
+			currency: general.currency,
+			currency_symbol: general.currency_symbol,
+			payment,
+			tax,
+			shipping
+		}
+	}
+}
@@ -0,0 +1,103 @@
+//! The tax rate configuration.
+//! 
+//! Besides the flat rate in the `Tax` section of the store configuration (see `store::TaxSettings`), ShopSite can charge tax by destination, with a table of rates by country and state, kept in a file of its own. The table is a sequence of sequences, like `US,CA,7.25%|US,NY,4%`:
+//! 
+//! ```
+//! use shopsite_types::TaxConfig;
+//! 
+//! let config = TaxConfig::from_reader(&b"\
+//! DefaultRate: 0%
+//! Rates: US,CA,7.25%|US,NY,4%|GB,*,20%|
+//! ExemptSKUs: GFT-003|
+//! "[..], None).unwrap();
+//! 
+//! assert_eq!(config.rate_for("US", "ca"), 7.25);
+//! assert_eq!(config.rate_for("GB", "Kent"), 20.0);
+//! assert_eq!(config.rate_for("US", "OR"), 0.0);
+//! assert!(config.is_exempt("GFT-003"));
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize};
+use shopsite_aa::de as aa;
+use std::{
+	io::Read,
+	path::Path,
+	sync::Arc
+};
+
+/// The tax rate configuration. See the module documentation.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(default, rename_all(deserialize = "PascalCase"))]
+#[non_exhaustive]
+pub struct TaxConfig {
+	/// The tax rate for destinations that aren't in `rates`, as a percentage, so `7.5%` is `7.5`. Spelled `DefaultRate`.
+	#[serde(deserialize_with = "shopsite_aa::helpers::percent")]
+	pub default_rate: f64,
+
+	/// The rate table. Spelled `Rates`, and written like `US,CA,7.25%|US,NY,4%`: a sequence of rates, each a sequence of the country, the state, and the rate.
+	#[serde(deserialize_with = "rates")]
+	pub rates: Vec<TaxRate>,
+
+	/// SKUs of the products that aren't taxed, like gift certificates. Spelled `ExemptSKUs`.
+	#[serde(rename(deserialize = "ExemptSKUs"), deserialize_with = "crate::non_empty_elements")]
+	pub exempt_skus: Vec<String>
+}
+
+impl TaxConfig {
+	/// Reads a tax rate configuration file, with the options from `deserializer_builder`. `file` is for error messages.
+	pub fn from_reader(reader: impl Read, file: Option<Arc<Path>>) -> aa::Result<TaxConfig> {
+		crate::unsectioned_from_reader(reader, file)
+	}
+
+	/// Reads a tax rate configuration file, with the options from `deserializer_builder`.
+	pub fn from_file(file: impl AsRef<Path>) -> aa::Result<TaxConfig> {
+		crate::unsectioned_from_file(file.as_ref())
+	}
+
+	/// The tax rate for orders shipping to the given country and state, as a percentage.
+	/// 
+	/// This is the rate of the first line in `rates` for that country and state, or else the first line for that country with a state of `*`, or else `default_rate`. Countries and states are matched without regard to letter case.
+	pub fn rate_for(&self, country: &str, state: &str) -> f64 {
+		let in_country = || self.rates.iter().filter(|rate| rate.country.eq_ignore_ascii_case(country));
+
+		in_country().find(|rate| rate.state.eq_ignore_ascii_case(state))
+			.or_else(|| in_country().find(|rate| rate.state == "*"))
+			.map_or(self.default_rate, |rate| rate.rate)
+	}
+
+	/// Whether the product with the given SKU is exempt from tax.
+	pub fn is_exempt(&self, sku: &str) -> bool {
+		self.exempt_skus.iter().any(|exempt| exempt == sku)
+	}
+}
+
+/// One line of the tax rate table: the rate for orders shipping to a state.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize)]
+#[serde(from = "(String, String, Percent)")]
+#[non_exhaustive]
+pub struct TaxRate {
+	/// The ISO 3166 code of the country, like `US`.
+	pub country: String,
+
+	/// The state, province, or the like, as written in addresses, like `CA`. `*` stands for the rest of the country.
+	pub state: String,
+
+	/// The tax rate, as a percentage, so `7.5%` is `7.5`.
+	pub rate: f64
+}
+
+impl From<(String, String, Percent)> for TaxRate {
+	fn from((country, state, Percent(rate)): (String, String, Percent)) -> Self {
+		TaxRate { country, state, rate }
+	}
+}
+
+/// A percentage in a sequence, where `deserialize_with` can't be used on it directly.
+#[derive(Deserialize)]
+struct Percent(#[serde(deserialize_with = "shopsite_aa::helpers::percent")] f64);
+
+/// Deserializes `TaxConfig::rates`, leaving out empty rates, like the one after the `|` that ShopSite writes at the end of some lists.
+fn rates<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<TaxRate>, D::Error> {
+	let rates = Vec::<Option<TaxRate>>::deserialize(deserializer)?;
+	Ok(rates.into_iter().flatten().collect())
+}
@@ -0,0 +1,127 @@
+use chrono::NaiveDate;
+use shopsite_types::{coupon::*, Coupons, GiftCertificates};
+
+fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+	NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+#[test]
+fn test_coupons() {
+	// This test verifies that the coupon database is read into `Coupons`, with each kind of discount.
+	let coupons = Coupons::from_reader(&b"\
+Code: TAKE5
+Name: $5 off
+DiscountType: Amount
+Amount: $5.00
+MaxUses:
+TimesUsed: 12
+OncePerCustomer: checked
+
+Code: SPRING10
+DiscountType: %
+Percent: 10
+Expires: 05/31/2020
+MaxUses: 100
+TimesUsed: 40
+
+Code: SHIPFREE
+DiscountType: FreeShipping
+MinimumOrder: 50
+Expires: 2020-01-31
+
+Code: OLD
+Amount: 1
+MaxUses: 5
+TimesUsed: 7
+"[..], None).unwrap();
+	assert_eq!(coupons.len(), 4);
+
+	let take5 = coupons.get("take5").unwrap();
+	assert_eq!(take5.code, "TAKE5");
+	assert_eq!(take5.name, "$5 off");
+	assert_eq!(take5.discount, Discount::Amount("5.00".parse().unwrap()));
+	assert_eq!(take5.max_uses, None);
+	assert_eq!(take5.uses_left(), None);
+	assert!(take5.once_per_customer);
+
+	let spring = coupons.get("SPRING10").unwrap();
+	assert_eq!(spring.discount, Discount::Percent(10.0));
+	assert_eq!(spring.expires, Some(date(2020, 5, 31)));
+	assert_eq!(spring.uses_left(), Some(60));
+	assert!(!spring.once_per_customer);
+
+	// A coupon works through the day it expires.
+	assert!(!spring.is_expired(date(2020, 5, 31)));
+	assert!(spring.is_expired(date(2020, 6, 1)));
+
+	let ship_free = coupons.get("SHIPFREE").unwrap();
+	assert_eq!(ship_free.discount, Discount::FreeShipping);
+	assert_eq!(ship_free.minimum_order.unwrap().to_string(), "50");
+
+	// Without a `DiscountType`, the discount is an amount. A coupon used more times than it's allowed has no uses left.
+	let old = coupons.get("OLD").unwrap();
+	assert_eq!(old.discount, Discount::Amount("1".parse().unwrap()));
+	assert_eq!(old.uses_left(), Some(0));
+
+	let usable = |today| coupons.usable(today).map(|coupon| &coupon.code[..]).collect::<Vec<_>>();
+	assert_eq!(usable(date(2020, 1, 1)), ["TAKE5", "SPRING10", "SHIPFREE"]);
+	assert_eq!(usable(date(2020, 3, 1)), ["TAKE5", "SPRING10"]);
+	assert_eq!(usable(date(2021, 1, 1)), ["TAKE5"]);
+
+	// An unknown kind of discount is an error.
+	let error = Coupons::from_reader(&b"Code: X\nDiscountType: Bogus\n"[..], None).unwrap_err();
+	assert!(error.to_string().contains("Bogus"), "{}", error);
+}
+
+#[test]
+fn test_coupon_serialize() {
+	// This test verifies that discounts are serialized with their kind and value.
+	let coupons = Coupons::from_reader(&b"Code: A\nDiscountType: Percent\nPercent: 7.5%\nExpires: 5/1/2020\nCode: B\nDiscountType: Free Shipping\n"[..], None).unwrap();
+	let json = serde_json::to_value(&coupons).unwrap();
+	assert_eq!(json[0]["discount"], serde_json::json!({ "kind": "percent", "value": 7.5 }));
+	assert_eq!(json[0]["expires"], "05/01/2020");
+	assert_eq!(json[1]["discount"], serde_json::json!({ "kind": "free_shipping" }));
+	assert_eq!(json[1]["expires"], serde_json::Value::Null);
+}
+
+#[test]
+fn test_gift_certificates() {
+	// This test verifies that the gift certificate database is read into `GiftCertificates`.
+	let certificates = GiftCertificates::from_reader(&b"\
+Code: GC-1001
+Amount: $50.00
+Balance: 12.50
+Issued: 12/20/2019
+Expires: 12/31/2020
+Order Number: 1001
+
+Code: GC-1002
+Amount: 25.00
+Balance: 0.00
+
+Code: GC-1003
+Amount: 10.00
+Balance: 10.00
+Expires: 01/31/2020
+"[..], None).unwrap();
+	assert_eq!(certificates.len(), 3);
+
+	let first = certificates.get("gc-1001").unwrap();
+	assert_eq!(first.amount.to_string(), "50.00");
+	assert_eq!(first.balance.to_string(), "12.50");
+	assert_eq!(first.spent().to_string(), "37.50");
+	assert_eq!(first.issued, Some(date(2019, 12, 20)));
+	assert_eq!(first.order_number, "1001");
+	assert!(!first.is_used_up());
+
+	let second = certificates.get("GC-1002").unwrap();
+	assert_eq!(second.issued, None);
+	assert_eq!(second.expires, None);
+	assert!(second.is_used_up());
+	assert!(!second.is_expired(date(2100, 1, 1)));
+
+	// Expired and used-up certificates aren't owed anymore.
+	assert_eq!(certificates.outstanding(date(2020, 1, 1)).to_string(), "22.50");
+	assert_eq!(certificates.outstanding(date(2020, 2, 1)).to_string(), "12.50");
+	assert_eq!(certificates.outstanding(date(2021, 1, 1)).to_string(), "0");
+}
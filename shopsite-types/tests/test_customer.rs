@@ -0,0 +1,124 @@
+use chrono::NaiveDate;
+use shopsite_types::{
+	redact::{self, Redact},
+	Customers,
+	Orders
+};
+
+const CUSTOMERS: &[u8] = b"\
+Email: pat@example.com
+First Name: Pat
+Last Name: Smith
+Registered: 04/15/2020
+Newsletter: checked
+Partner Offers: no
+Billing Name: Pat Smith
+Billing Address 1: 742 Evergreen Terrace
+Billing City: Springfield
+Billing State: IL
+Billing Zip: 62704
+Billing Country: US
+Billing Phone: 555-0100
+Shipping Name: Chris Smith
+Shipping City: Shelbyville
+Shipping State: IL
+
+Email: sam@example.net
+Last Name: Jones
+Partner Offers: yes
+";
+
+#[test]
+fn test_customers() {
+	// This test verifies that the customer registration database is read into `Customers`, with each customer's addresses and opt-ins.
+
+	let customers = Customers::from_reader(CUSTOMERS, None).unwrap();
+	assert_eq!(customers.len(), 2);
+
+	let pat = customers.get("PAT@example.com").unwrap();
+	assert_eq!(pat.full_name(), "Pat Smith");
+	assert_eq!(pat.registered, NaiveDate::from_ymd_opt(2020, 4, 15));
+	assert!(pat.newsletter);
+	assert!(!pat.partner_offers);
+	assert_eq!(pat.billing.address1, "742 Evergreen Terrace");
+	assert_eq!(pat.billing.zip, "62704");
+	assert_eq!(pat.shipping.name, "Chris Smith");
+	assert_eq!(pat.shipping.city, "Shelbyville");
+
+	let sam = customers.get("sam@example.net").unwrap();
+	assert_eq!(sam.full_name(), "Jones");
+	assert_eq!(sam.registered, None);
+	assert!(!sam.newsletter);
+	assert!(sam.partner_offers);
+	assert!(sam.billing.is_empty());
+
+	assert!(customers.get("nobody@example.com").is_none());
+	assert_eq!(customers.newsletter().map(|customer| &customer.email[..]).collect::<Vec<_>>(), ["pat@example.com"]);
+}
+
+#[test]
+fn test_redact_customers() {
+	// This test verifies that redacting customers replaces their names, email addresses, and street addresses, but keeps everything else, and leaves empty fields empty.
+
+	let customers = Customers::from_reader(CUSTOMERS, None).unwrap().redacted();
+
+	let pat = &customers[0];
+	assert_eq!(pat.email, "[redacted]@example.com");
+	assert_eq!(pat.first_name, redact::REDACTED);
+	assert_eq!(pat.last_name, redact::REDACTED);
+	assert_eq!(pat.registered, NaiveDate::from_ymd_opt(2020, 4, 15));
+	assert!(pat.newsletter);
+	assert_eq!(pat.billing.name, redact::REDACTED);
+	assert_eq!(pat.billing.address1, redact::REDACTED);
+	assert_eq!(pat.billing.address2, "");
+	assert_eq!(pat.billing.city, redact::REDACTED);
+	assert_eq!(pat.billing.state, "IL");
+	assert_eq!(pat.billing.zip, redact::REDACTED);
+	assert_eq!(pat.billing.country, "US");
+	assert_eq!(pat.billing.phone, redact::REDACTED);
+	assert_eq!(pat.shipping.name, redact::REDACTED);
+	assert_eq!(pat.shipping.state, "IL");
+
+	let sam = &customers[1];
+	assert_eq!(sam.email, "[redacted]@example.net");
+	assert_eq!(sam.first_name, "");
+	assert!(sam.billing.is_empty());
+}
+
+#[test]
+fn test_redact_orders() {
+	// This test verifies that redacting orders replaces the customer's email address and addresses, but keeps what was ordered.
+
+	let orders = Orders::from_reader(&b"\
+Order Number: 1001
+Date: 04/15/2020 13:45:00
+Email: pat@example.com
+Items: MUG-001,Coffee Mug,2,12.50
+Grand Total: 25.00
+Payment Method: Visa
+Billing Name: Pat Smith
+Billing State: IL
+Shipping Method: Ground
+"[..], None).unwrap().redacted();
+
+	let order = &orders[0];
+	assert_eq!(order.order_number, "1001");
+	assert_eq!(order.email, "[redacted]@example.com");
+	assert_eq!(order.items[0].name, "Coffee Mug");
+	assert_eq!(order.total.to_string(), "25.00");
+	assert_eq!(order.billing.payment_method, "Visa");
+	assert_eq!(order.billing.address.name, redact::REDACTED);
+	assert_eq!(order.billing.address.state, "IL");
+	assert!(order.shipping.address.is_empty());
+	assert_eq!(order.shipping.method, "Ground");
+}
+
+#[test]
+fn test_redact_email() {
+	// This test verifies that `redact::email` keeps the domain of an email address, and replaces anything that isn't one entirely.
+
+	assert_eq!(redact::email("pat@example.com"), "[redacted]@example.com");
+	assert_eq!(redact::email("\"pat@home\"@example.com"), "[redacted]@example.com");
+	assert_eq!(redact::email("pat"), redact::REDACTED);
+	assert_eq!(redact::email(""), "");
+}
@@ -0,0 +1,111 @@
+use shopsite_types::{Address, Orders};
+
+const ORDERS: &[u8] = b"\
+Order Number: 1001
+Date: 04/15/2020 13:45:00
+Email: pat@example.com
+Items: MUG-001,Coffee Mug,2,12.50|TWL-002,Tea Towel,1,5.95|
+Subtotal: 30.95
+Discount: 0.00
+Tax: 1.86
+Grand Total: $36.81
+Payment Method: Visa
+Billing Name: Pat Smith
+Billing Company: Widgets Inc.
+Billing Address 1: 742 Evergreen Terrace
+Billing City: Springfield
+Billing State: IL
+Billing Zip: 62704
+Billing Country: US
+Billing Phone: 555-0100
+Shipping Name: Chris Smith
+Shipping Address 1: 1 Main St.
+Shipping Address 2: Apt. 2
+Shipping City: Shelbyville
+Shipping State: IL
+Shipping Zip: 62565
+Shipping Method: Ground
+Shipping Charge: 4.00
+
+Order Number: 1002
+Date: 2020-04-16
+Items: GFT-003,Gift Card,1,25
+Subtotal: 25
+Grand Total: 25
+Billing Name: Sam Jones
+";
+
+#[test]
+fn test_orders() {
+	// This test verifies that an order export is read into `Orders`, with each order's items, billing, and shipping.
+	let orders = Orders::from_reader(ORDERS, None).unwrap();
+	assert_eq!(orders.len(), 2);
+
+	let order = orders.get("1001").unwrap();
+	assert_eq!(order.placed.to_string(), "2020-04-15 13:45:00");
+	assert_eq!(order.email, "pat@example.com");
+
+	// The empty element after the trailing `|` isn't an item.
+	assert_eq!(order.items.len(), 2);
+	assert_eq!(order.items[0].sku, "MUG-001");
+	assert_eq!(order.items[0].name, "Coffee Mug");
+	assert_eq!(order.items[0].quantity, 2);
+	assert_eq!(order.items[0].price.to_string(), "12.50");
+	assert_eq!(order.items[0].total().to_string(), "25.00");
+	assert_eq!(order.items[1].sku, "TWL-002");
+	assert_eq!(order.items_total(), order.subtotal);
+
+	assert_eq!(order.discount.to_string(), "0.00");
+	assert_eq!(order.tax.to_string(), "1.86");
+	assert_eq!(order.total.to_string(), "36.81");
+
+	assert_eq!(order.billing.payment_method, "Visa");
+	assert_eq!(order.billing.address.to_string(), "Pat Smith\nWidgets Inc.\n742 Evergreen Terrace\nSpringfield, IL 62704\nUS");
+	assert_eq!(order.billing.address.phone, "555-0100");
+
+	assert_eq!(order.shipping.method, "Ground");
+	assert_eq!(order.shipping.charge.to_string(), "4.00");
+	assert_eq!(order.shipping.address.to_string(), "Chris Smith\n1 Main St.\nApt. 2\nShelbyville, IL 62565");
+	assert_eq!(order.ship_to(), &order.shipping.address);
+
+	// Missing keys take their defaults, and without a shipping address, the order ships to the billing address.
+	let order = orders.get("1002").unwrap();
+	assert_eq!(order.placed.to_string(), "2020-04-16 00:00:00");
+	assert_eq!(order.email, "");
+	assert_eq!(order.items[0].total().to_string(), "25");
+	assert!(order.shipping.address.is_empty());
+	assert_eq!(order.ship_to().name, "Sam Jones");
+	assert_eq!(order.tax.to_string(), "0");
+
+	assert!(orders.get("1003").is_none());
+}
+
+#[test]
+fn test_order_serialize() {
+	// This test verifies that orders are serialized with snake-case field names, and with timestamps the way ShopSite writes them.
+	let orders = Orders::from_reader(ORDERS, None).unwrap();
+	let json = serde_json::to_value(&orders[1]).unwrap();
+	assert_eq!(json["order_number"], "1002");
+	assert_eq!(json["placed"], "04/16/2020 00:00:00");
+	assert_eq!(json["items"][0], serde_json::json!({ "sku": "GFT-003", "name": "Gift Card", "quantity": 1, "price": "25" }));
+	assert_eq!(json["billing"]["address"]["name"], "Sam Jones");
+}
+
+#[test]
+fn test_order_errors() {
+	// This test verifies that malformed items and timestamps, and missing timestamps, are errors.
+	let error = Orders::from_reader(&b"Order Number: 1\nItems: A,Widget,lots,1.00\n"[..], None).unwrap_err();
+	assert_eq!(error.position().map(|pos| (pos.line, pos.column)), Some((2, 17)));
+
+	let error = Orders::from_reader(&b"Order Number: 1\nItems: A,Widget\n"[..], None).unwrap_err();
+	assert!(error.to_string().contains("Items"), "{}", error);
+
+	let error = Orders::from_reader(&b"Order Number: 1\nDate: yesterday\n"[..], None).unwrap_err();
+	assert!(error.to_string().contains("yesterday"), "{}", error);
+
+	// An order without a date is an error, rather than being dated 1970.
+	let error = Orders::from_reader(&b"Order Number: 1\nEmail: a@b.c\nOrder Number: 2\nDate: 2020-04-16\n"[..], None).unwrap_err();
+	assert!(matches!(error, shopsite_aa::de::Error::MissingField { field: "Date", .. }), "{:?}", error);
+
+	assert_eq!(Address::default().to_string(), "");
+}
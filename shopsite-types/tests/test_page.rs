@@ -0,0 +1,68 @@
+use shopsite_types::{page::*, Pages};
+
+const PAGES: &[u8] = b"\
+Name: Mugs
+FileName: mugs.html
+LinkText: Mugs &amp; Cups
+Layout: two-column
+Header: <h1>Mugs</h1>
+Text1: <p>Our mugs.</p>
+Text2: <p>Dishwasher safe.</p>
+Footer: <p>&copy; Widgets</p>
+Products: MUG-001|MUG-002|
+MetaTitle: Coffee Mugs
+MetaDescription: Mugs for coffee and tea.
+MetaKeywords: mugs, cups,, coffee
+
+Name: About Us
+FileName: about.html
+Text1: <p>We make widgets.</p>
+Products:
+MetaKeywords:
+";
+
+#[test]
+fn test_pages() {
+	// This test verifies that a page database dump is read into `Pages`, one `Page` per record.
+	let pages = Pages::from_reader(PAGES, None).unwrap();
+	assert_eq!(pages.len(), 2);
+
+	let mugs = &pages[0];
+	assert_eq!(mugs.name, "Mugs");
+	assert_eq!(mugs.file_name, "mugs.html");
+	assert_eq!(mugs.link_text, "Mugs & Cups");
+	assert_eq!(mugs.layout, "two-column");
+	assert_eq!(mugs.header, "<h1>Mugs</h1>");
+	assert_eq!(mugs.text1, "<p>Our mugs.</p>");
+	assert_eq!(mugs.text2, "<p>Dishwasher safe.</p>");
+	// Text fields are HTML, so their character references are left alone.
+	assert_eq!(mugs.footer, "<p>&copy; Widgets</p>");
+	assert_eq!(mugs.products, ["MUG-001", "MUG-002"]);
+	assert_eq!(mugs.meta_title, "Coffee Mugs");
+	assert_eq!(mugs.meta_description, "Mugs for coffee and tea.");
+	assert_eq!(mugs.meta_keywords, ["mugs", "cups", "coffee"]);
+	assert_eq!(mugs.title(), "Coffee Mugs");
+
+	let about = pages.get("About Us").unwrap();
+	assert_eq!(about.title(), "About Us");
+	assert!(about.products.is_empty());
+	assert!(about.meta_keywords.is_empty());
+	assert_eq!(about.layout, "");
+
+	assert!(pages.get("Nope").is_none());
+	assert_eq!(pages.with_product("MUG-001").map(|page| &page.name[..]).collect::<Vec<_>>(), ["Mugs"]);
+	assert_eq!(pages.with_product("NOPE").count(), 0);
+}
+
+#[test]
+fn test_pages_one_at_a_time() {
+	// This test verifies that pages can be read one record at a time, with `Pages::deserializer_builder`.
+	let mut de = Pages::deserializer_builder().build(PAGES, None);
+	let names = de.records::<Page>().map(|page| page.unwrap().name).collect::<Vec<_>>();
+	assert_eq!(names, ["Mugs", "About Us"]);
+
+	// The collection converts to and from a `Vec` of records.
+	let pages: Pages = vec![Page::default()].into_iter().collect();
+	assert_eq!(pages.0, [Page::default()]);
+	assert_eq!(pages.into_iter().count(), 1);
+}
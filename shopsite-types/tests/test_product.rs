@@ -0,0 +1,89 @@
+use shopsite_types::{product::*, Products};
+
+const PRODUCTS: &[u8] = b"\
+Name: Coffee Mug &amp; Saucer
+SKU: MUG-001
+Price: 12.50
+SalePrice:
+Weight: 0.75
+Options: Color,Red,Blue|Size,12 oz.,16 oz.|
+Images: mug.jpg|mug-side.jpg
+CrossSell: SAU-001|TWL-002
+ProductDescription: A 12 oz. mug with a <b>handle</b>.
+DisplayMoreInformationPage: checked
+MoreInfoFileName: mug.html
+MoreInfoText: Dishwasher safe.
+Categories: Kitchen|Gifts
+
+Name: Tea Towel
+SKU: TWL-002
+Price: $1,007.95
+SalePrice: 5.95
+DisplayMoreInformationPage: unchecked
+";
+
+#[test]
+fn test_products() {
+	// This test verifies that a product database dump is read into `Products`, one `Product` per record.
+	let products = Products::from_reader(PRODUCTS, None).unwrap();
+	assert_eq!(products.len(), 2);
+
+	let mug = &products[0];
+	assert_eq!(mug.sku, "MUG-001");
+	assert_eq!(mug.name, "Coffee Mug & Saucer");
+	assert_eq!(mug.price.to_string(), "12.50");
+	assert_eq!(mug.sale_price, None);
+	assert_eq!(mug.current_price(), mug.price);
+	assert_eq!(mug.weight, Some(0.75));
+	assert_eq!(mug.options.len(), 2);
+	assert_eq!(mug.options[0].name, "Color");
+	assert_eq!(mug.options[0].choices, ["Red", "Blue"]);
+	assert_eq!(mug.options[1].name, "Size");
+	assert_eq!(mug.options[1].choices, ["12 oz.", "16 oz."]);
+	assert_eq!(mug.images, ["mug.jpg", "mug-side.jpg"]);
+	assert_eq!(mug.cross_sell, ["SAU-001", "TWL-002"]);
+	assert_eq!(mug.product_description, "A 12 oz. mug with a <b>handle</b>.");
+	assert!(mug.more_info_page);
+	assert_eq!(mug.more_info_file_name, "mug.html");
+	assert_eq!(mug.more_info_text, "Dishwasher safe.");
+
+	let towel = products.get("TWL-002").unwrap();
+	assert_eq!(towel.name, "Tea Towel");
+	assert_eq!(towel.price.to_string(), "1007.95");
+	assert_eq!(towel.current_price().to_string(), "5.95");
+	assert_eq!(towel.weight, None);
+	assert!(towel.options.is_empty());
+	assert!(towel.images.is_empty());
+	assert!(!towel.more_info_page);
+
+	assert!(products.get("NOPE").is_none());
+	assert_eq!(products.iter().map(|product| &product.sku[..]).collect::<Vec<_>>(), ["MUG-001", "TWL-002"]);
+
+	// Records don't need blank lines between them.
+	let without_blank_lines: Vec<u8> = PRODUCTS.split(|&byte| byte == b'\n').filter(|line| !line.is_empty()).flat_map(|line| line.iter().copied().chain(Some(b'\n'))).collect();
+	assert_eq!(Products::from_reader(&without_blank_lines[..], None).unwrap(), products);
+
+	// An empty file has no products.
+	assert!(Products::from_reader(&b""[..], None).unwrap().is_empty());
+}
+
+#[test]
+fn test_products_one_at_a_time() {
+	// This test verifies that products can be read one record at a time, with `Products::deserializer_builder`.
+	let mut de = Products::deserializer_builder().build(PRODUCTS, None);
+	let skus = de.records::<Product>().map(|product| product.unwrap().sku).collect::<Vec<_>>();
+	assert_eq!(skus, ["MUG-001", "TWL-002"]);
+
+	// A single product can also be read by itself, from any deserializer.
+	let product: Product = shopsite_aa::de::from_bytes(b"Name: Widget\nPrice: 1\n", None).unwrap();
+	assert_eq!(product.name, "Widget");
+	assert_eq!(ProductOption::from(vec!["Color".to_string(), "Red".to_string()]).choices, ["Red"]);
+}
+
+#[test]
+fn test_product_errors() {
+	// This test verifies that a bad value in a record is an error that says which key it's in.
+	let error = Products::from_reader(&b"Name: Widget\nPrice: 1\n\nName: Gadget\nPrice: free\n"[..], None).unwrap_err();
+	assert!(error.to_string().starts_with("Price: "), "{}", error);
+	assert!(error.to_string().contains("free"), "{}", error);
+}
@@ -0,0 +1,56 @@
+// These tests verify that every kind of record is serialized with snake-case field names, including the ones that ShopSite spells unusually, and in sections and nested records too, rather than with ShopSite's own names for them.
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use shopsite_types::{GiftCertificates, Pages, Products, ShippingConfig, StoreConfig, TaxConfig};
+
+/// A record serialized to JSON, the fields it should have, as JSON pointers, with their values, and a field with ShopSite's own name that it shouldn't have.
+type Case = (Value, Vec<(&'static str, Value)>, &'static str);
+
+fn to_json(record: &impl Serialize) -> Value {
+	serde_json::to_value(record).unwrap()
+}
+
+#[test]
+fn test_serialize_snake_case() {
+	let cases: Vec<Case> = vec![
+		(
+			to_json(&GiftCertificates::from_reader(&b"Code: GC-1001\nAmount: 50.00\nBalance: 12.50\nIssued: 12/20/2019\nOrder Number: 1001\n"[..], None).unwrap()[0]),
+			vec![("/code", json!("GC-1001")), ("/balance", json!("12.50")), ("/issued", json!("12/20/2019")), ("/expires", Value::Null), ("/order_number", json!("1001"))],
+			"/Order Number"
+		),
+		(
+			to_json(&Pages::from_reader(&b"Name: Mugs\nFileName: mugs.html\nLinkText: Mugs &amp; Cups\nMetaKeywords: mugs, cups,, coffee\n"[..], None).unwrap()[0]),
+			vec![("/file_name", json!("mugs.html")), ("/link_text", json!("Mugs & Cups")), ("/meta_keywords", json!(["mugs", "cups", "coffee"]))],
+			"/FileName"
+		),
+		(
+			to_json(&Products::from_reader(&b"Name: Coffee Mug\nSKU: MUG-001\nPrice: 12.50\nSalePrice:\nCrossSell: SAU-001|TWL-002\nDisplayMoreInformationPage: checked\nMoreInfoFileName: mug.html\n"[..], None).unwrap()[0]),
+			vec![("/sku", json!("MUG-001")), ("/sale_price", Value::Null), ("/cross_sell", json!(["SAU-001", "TWL-002"])), ("/more_info_page", json!(true)), ("/more_info_file_name", json!("mug.html"))],
+			"/DisplayMoreInformationPage"
+		),
+		(
+			to_json(&ShippingConfig::from_reader(&b"Carriers: UPS,Ground|\nZones: Domestic,US|\nRateBasis: Weight\nRates: Ground,Domestic,0,5.00|\n"[..], None).unwrap()),
+			vec![("/carriers/0/name", json!("UPS")), ("/zones/0/countries", json!(["US"])), ("/rate_basis", json!("weight")), ("/rates/0/service", json!("Ground"))],
+			"/RateBasis"
+		),
+		(
+			to_json(&StoreConfig::from_reader(&b"StoreName: Widgets\nStoreID: 1\nBEGIN_Payment\nTestMode: Yes\nEND_Payment\nBEGIN_Tax\nPricesIncludeTax: checked\nEND_Tax\nBEGIN_Shipping\nOriginZip: 02134\nEND_Shipping\n"[..], None).unwrap()),
+			vec![("/store_name", json!("Widgets")), ("/store_id", json!("1")), ("/payment/test_mode", json!(true)), ("/tax/prices_include_tax", json!(true)), ("/shipping/origin_zip", json!("02134"))],
+			"/payment/TestMode"
+		),
+		(
+			to_json(&TaxConfig::from_reader(&b"DefaultRate: 1.5%\nRates: US,CA,7.25%|\nExemptSKUs: GFT-003|\n"[..], None).unwrap()),
+			vec![("/default_rate", json!(1.5)), ("/rates/0/state", json!("CA")), ("/exempt_skus", json!(["GFT-003"]))],
+			"/ExemptSKUs"
+		)
+	];
+
+	for (json, fields, pascal_key) in &cases {
+		for (pointer, expected) in fields {
+			assert_eq!(json.pointer(pointer), Some(expected), "{}: {}", pointer, json);
+		}
+
+		assert!(json.pointer(pascal_key).is_none(), "{}: {}", pascal_key, json);
+	}
+}
@@ -0,0 +1,82 @@
+use shopsite_types::{shipping::*, ShippingConfig};
+
+#[test]
+fn test_shipping_config() {
+	// This test verifies that a shipping rate configuration file, with its sequences of sequences, is read into a `ShippingConfig`.
+	let input = b"\
+Carriers: UPS,Ground,2nd Day Air,Next Day Air|USPS,Priority Mail|FedEx|
+Zones: Domestic,US,PR|North America,CA,MX|Rest of World,*|
+RateBasis: Subtotal
+Rates: Ground,Domestic,0,5.00|Ground,Domestic,50,$8.50|Ground,Domestic,100,0|Priority Mail,Rest of World,0,25|
+UnknownKey: ignored
+";
+
+	let config = ShippingConfig::from_reader(&input[..], None).unwrap();
+
+	// The empty elements after the trailing `|`s are left out, and a carrier can have no services.
+	assert_eq!(config.carriers.len(), 3);
+	assert_eq!(config.carriers[0].name, "UPS");
+	assert_eq!(config.carriers[0].services, ["Ground", "2nd Day Air", "Next Day Air"]);
+	assert_eq!(config.carriers[1].services, ["Priority Mail"]);
+	assert_eq!(config.carriers[2].name, "FedEx");
+	assert!(config.carriers[2].services.is_empty());
+
+	assert_eq!(config.zones.len(), 3);
+	assert_eq!(config.zones[0].name, "Domestic");
+	assert_eq!(config.zones[0].countries, ["US", "PR"]);
+	assert_eq!(config.zones[2].countries, ["*"]);
+
+	assert_eq!(config.rate_basis, RateBasis::Subtotal);
+
+	assert_eq!(config.rates.len(), 4);
+	assert_eq!(config.rates[1].service, "Ground");
+	assert_eq!(config.rates[1].zone, "Domestic");
+	assert_eq!(config.rates[1].from, 50.0);
+	assert_eq!(config.rates[1].charge.to_string(), "8.50");
+}
+
+#[test]
+fn test_shipping_rate_lookup() {
+	// This test verifies that `zone_for` and `rate` find the zone and rate that apply.
+	let config = ShippingConfig::from_reader(&b"\
+Zones: Domestic,US|Rest of World,*|North America,CA,MX,US|
+Rates: Ground,Domestic,50,8.50|Ground,Domestic,0,5.00|Ground,Domestic,100,0|
+"[..], None).unwrap();
+
+	// The first zone listing a country wins, and `*` is only a fallback, wherever it is.
+	assert_eq!(config.zone_for("us").unwrap().name, "Domestic");
+	assert_eq!(config.zone_for("CA").unwrap().name, "North America");
+	assert_eq!(config.zone_for("FR").unwrap().name, "Rest of World");
+
+	// Rates don't have to be in order of their thresholds.
+	assert_eq!(config.rate("Ground", "Domestic", 0.0).unwrap().to_string(), "5.00");
+	assert_eq!(config.rate("Ground", "Domestic", 49.99).unwrap().to_string(), "5.00");
+	assert_eq!(config.rate("Ground", "Domestic", 50.0).unwrap().to_string(), "8.50");
+	assert_eq!(config.rate("Ground", "Domestic", 250.0).unwrap().to_string(), "0");
+	assert_eq!(config.rate("Ground", "Domestic", -1.0), None);
+	assert_eq!(config.rate("Ground", "Rest of World", 10.0), None);
+
+	let config = ShippingConfig::from_reader(&b"Zones: Domestic,US\n"[..], None).unwrap();
+	assert!(config.zone_for("FR").is_none());
+}
+
+#[test]
+fn test_shipping_config_defaults() {
+	// This test verifies that missing keys take their defaults.
+	let config = ShippingConfig::from_reader(&b""[..], None).unwrap();
+	assert_eq!(config, ShippingConfig::default());
+	assert_eq!(config.rate_basis, RateBasis::Weight);
+}
+
+#[test]
+fn test_shipping_config_errors() {
+	// This test verifies that malformed rates are errors.
+	let error = ShippingConfig::from_reader(&b"Rates: Ground,Domestic,heavy,5.00\n"[..], None).unwrap_err();
+	assert_eq!(error.position().map(|pos| (pos.line, pos.column)), Some((1, 24)));
+
+	let error = ShippingConfig::from_reader(&b"Rates: Ground,Domestic\n"[..], None).unwrap_err();
+	assert!(error.to_string().contains("Rates"), "{}", error);
+
+	let error = ShippingConfig::from_reader(&b"RateBasis: Distance\n"[..], None).unwrap_err();
+	assert!(error.to_string().contains("Distance"), "{}", error);
+}
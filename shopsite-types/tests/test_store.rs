@@ -0,0 +1,76 @@
+use shopsite_types::{store::*, StoreConfig};
+
+#[test]
+fn test_store_config() {
+	// This test verifies that a store configuration file, as ShopSite writes it, is read into a `StoreConfig`.
+	let input = b"\
+StoreName: Caf\xe9 Widgets\xae
+StoreID: *12345678
+Email: orders@example.com
+Locale: en-US
+Currency: USD
+CurrencySymbol: $
+UnknownKey: ignored
+BEGIN_Payment
+Methods: Visa|MasterCard|American Express|PayPal|
+Processor: Authorize.Net
+TestMode: Yes
+END_Payment
+BEGIN_Tax
+Rate: 6.25
+PricesIncludeTax: unchecked
+ShowOnCart
+END_Tax
+BEGIN_Shipping
+Methods: Ground|2nd Day Air|
+OriginZip: 02134
+HandlingFee: $1.50
+FreeShippingOver:
+END_Shipping
+";
+
+	let config = StoreConfig::from_reader(&input[..], None).unwrap();
+	assert_eq!(config.store_name, "Café Widgets®");
+	assert_eq!(config.store_id, "*12345678");
+	assert_eq!(config.email, "orders@example.com");
+	assert_eq!(config.locale, "en-US");
+	assert_eq!(config.currency, "USD");
+	assert_eq!(config.currency_symbol, "$");
+
+	// The empty element after the trailing `|` is left out.
+	assert_eq!(config.payment.methods, ["Visa", "MasterCard", "American Express", "PayPal"]);
+	assert_eq!(config.payment.processor.as_deref(), Some("Authorize.Net"));
+	assert!(config.payment.test_mode);
+
+	assert_eq!(config.tax.rate, 6.25);
+	assert!(!config.tax.tax_shipping);
+	assert!(!config.tax.prices_include_tax);
+	// A key without a value is an unchecked checkbox.
+	assert!(!config.tax.show_on_cart);
+
+	assert_eq!(config.shipping.methods, ["Ground", "2nd Day Air"]);
+	assert_eq!(config.shipping.origin_zip.as_deref(), Some("02134"));
+	assert_eq!(config.shipping.handling_fee.unwrap().to_string(), "1.50");
+	assert_eq!(config.shipping.free_shipping_over, None);
+}
+
+#[test]
+fn test_store_config_defaults() {
+	// This test verifies that missing keys and sections take their defaults.
+	let config = StoreConfig::from_reader(&b"StoreName: Widgets\n"[..], None).unwrap();
+	assert_eq!(config.store_name, "Widgets");
+	assert_eq!(config.email, "");
+	assert_eq!(config.payment, PaymentSettings::default());
+	assert_eq!(config.tax, TaxSettings::default());
+	assert_eq!(config.shipping, ShippingSettings::default());
+
+	let config = StoreConfig::from_reader(&b""[..], None).unwrap();
+	assert_eq!(config, StoreConfig::default());
+}
+
+#[test]
+fn test_store_config_file() {
+	// This test verifies that errors reading a file say which file it was.
+	let error = StoreConfig::from_file("does-not-exist.aa").unwrap_err();
+	assert!(error.to_string().contains("does-not-exist.aa"), "{}", error);
+}
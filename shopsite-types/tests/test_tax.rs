@@ -0,0 +1,52 @@
+use shopsite_types::TaxConfig;
+
+#[test]
+fn test_tax_config() {
+	// This test verifies that a tax rate configuration file, with its sequence of sequences, is read into a `TaxConfig`.
+	let input = b"\
+DefaultRate: 1.5%
+Rates: US,CA,7.25%|US,NY,4|US,*,2.5%|GB,,20%|
+ExemptSKUs: GFT-003|GFT-004|
+";
+
+	let config = TaxConfig::from_reader(&input[..], None).unwrap();
+	assert_eq!(config.default_rate, 1.5);
+
+	// The empty element after the trailing `|` is left out, and an empty state is kept.
+	assert_eq!(config.rates.len(), 4);
+	assert_eq!(config.rates[0].country, "US");
+	assert_eq!(config.rates[0].state, "CA");
+	assert_eq!(config.rates[0].rate, 7.25);
+	assert_eq!(config.rates[1].rate, 4.0);
+	assert_eq!(config.rates[3].state, "");
+	assert_eq!(config.rates[3].rate, 20.0);
+
+	assert_eq!(config.exempt_skus, ["GFT-003", "GFT-004"]);
+	assert!(config.is_exempt("GFT-004"));
+	assert!(!config.is_exempt("MUG-001"));
+}
+
+#[test]
+fn test_tax_rate_lookup() {
+	// This test verifies that `rate_for` finds the rate for a state, then for the rest of its country, then the default.
+	let config = TaxConfig::from_reader(&b"\
+DefaultRate: 1.5%
+Rates: US,*,2.5%|US,CA,7.25%|GB,,20%|
+"[..], None).unwrap();
+
+	assert_eq!(config.rate_for("US", "CA"), 7.25);
+	assert_eq!(config.rate_for("us", "ca"), 7.25);
+	assert_eq!(config.rate_for("US", "OR"), 2.5);
+	assert_eq!(config.rate_for("GB", ""), 20.0);
+	assert_eq!(config.rate_for("GB", "Kent"), 1.5);
+	assert_eq!(config.rate_for("FR", ""), 1.5);
+
+	assert_eq!(TaxConfig::from_reader(&b""[..], None).unwrap(), TaxConfig::default());
+}
+
+#[test]
+fn test_tax_config_errors() {
+	// This test verifies that a malformed rate is an error.
+	let error = TaxConfig::from_reader(&b"Rates: US,CA,lots\n"[..], None).unwrap_err();
+	assert!(error.to_string().contains("lots"), "{}", error);
+}
@@ -0,0 +1,108 @@
+//! Development tasks for this workspace. Run `cargo xtask --help` for a list.
+
+use similar::TextDiff;
+use std::{
+	env,
+	ffi::OsString,
+	fs,
+	io,
+	path::{Path, PathBuf},
+	process::{self, Command}
+};
+use structopt::StructOpt;
+
+#[derive(StructOpt)]
+#[structopt(name = "cargo xtask")]
+enum Task {
+	/// Regenerates the expected output that `shopsite-aa2json`'s tests compare against, from what it outputs now, and shows what changed.
+	/// 
+	/// Use this after changing the output on purpose, then review the diff before committing.
+	Bless {
+		/// Only show what would change, without changing anything. Exits with status 1 if anything would change.
+		#[structopt(long)]
+		check: bool
+	}
+}
+
+/// The expected-output files in `shopsite-aa2json/tests`, and the arguments that make `shopsite-aa2json` produce them from `shopsite-aa/tests/test.aa`. These must match the tests that use them, in `shopsite-aa2json/tests/integration_test.rs`.
+const AA2JSON_FIXTURES: &[(&str, &[&str])] = &[
+	("expected-compact.json", &[]),
+	("expected-pretty-spaces.json", &["-p", "-s", "3"]),
+	("expected-pretty-tabs.json", &["-tp"])
+];
+
+fn workspace_root() -> PathBuf {
+	Path::new(env!("CARGO_MANIFEST_DIR")).parent().unwrap().to_path_buf()
+}
+
+/// Runs the current `shopsite-aa2json` through Cargo, which builds it first if needed, and returns what it writes to standard output.
+fn run_aa2json(root: &Path, args: &[&str]) -> io::Result<String> {
+	let cargo = env::var_os("CARGO").unwrap_or_else(|| OsString::from("cargo"));
+
+	let output = Command::new(cargo)
+		.current_dir(root)
+		.args(["run", "--quiet", "--package", "shopsite-aa2json", "--"])
+		.args(args)
+		.arg(root.join("shopsite-aa").join("tests").join("test.aa"))
+		.output()?;
+
+	if !output.status.success() {
+		return Err(io::Error::other(format!("shopsite-aa2json {} failed ({}):\n{}", args.join(" "), output.status, String::from_utf8_lossy(&output.stderr))))
+	}
+
+	String::from_utf8(output.stdout).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error))
+}
+
+/// Does the `bless` task. Returns whether any of the files changed (or would have, if `check`).
+fn bless(check: bool) -> io::Result<bool> {
+	let root = workspace_root();
+	let mut changed = false;
+
+	for (file_name, args) in AA2JSON_FIXTURES {
+		let path = root.join("shopsite-aa2json").join("tests").join(file_name);
+		let display_path = path.strip_prefix(&root).unwrap_or(&path).display().to_string();
+
+		let old = match fs::read_to_string(&path) {
+			Ok(old) => old,
+			Err(error) if error.kind() == io::ErrorKind::NotFound => String::new(),
+			Err(error) => return Err(error)
+		};
+
+		let new = run_aa2json(&root, args)?;
+
+		if old == new {
+			eprintln!("{}: unchanged", display_path);
+			continue
+		}
+
+		changed = true;
+
+		// Print the changes as a unified diff, which is what reviewers are used to reading.
+		print!("{}", TextDiff::from_lines(&old, &new).unified_diff().context_radius(3).header(&format!("a/{}", display_path), &format!("b/{}", display_path)));
+
+		if check {
+			eprintln!("{}: would change", display_path);
+		}
+		else {
+			fs::write(&path, &new)?;
+			eprintln!("{}: updated", display_path);
+		}
+	}
+
+	Ok(changed)
+}
+
+fn main() {
+	let result = match Task::from_args() {
+		Task::Bless { check } => bless(check).map(|changed| !(check && changed))
+	};
+
+	match result {
+		Ok(true) => (),
+		Ok(false) => process::exit(1),
+		Err(error) => {
+			eprintln!("error: {}", error);
+			process::exit(2);
+		}
+	}
+}